@@ -9,6 +9,10 @@ use clap::{Args, Parser, Subcommand};
 pub struct Cli {
     #[command(subcommand)]
     pub command: Command,
+    #[arg(long, global = true)]
+    /// Read prototypes from a `data-raw-dump.json`, written by running the game with `--dump-data`,
+    /// instead of using the built-in vanilla ones
+    pub data_raw: Option<PathBuf>,
 }
 
 #[derive(Subcommand)]
@@ -16,17 +20,36 @@ pub struct Cli {
 pub enum Command {
     /// Decode a blueprint string into JSON and its corresponding rust structure
     Decode(DecodeCommand),
+    /// Encode blueprint JSON into a blueprint string that can be imported into the game
+    Encode(EncodeCommand),
+    /// Check a blueprint for errors. Exits with 1 if any are found, or 2 if it can't be decoded
+    Validate(ValidateCommand),
+    /// Draw a blueprint as an image. Books are drawn as their active blueprint
+    Render(RenderCommand),
+    /// Report on what a blueprint does. Books are analyzed as their active blueprint
+    Analyze(AnalyzeCommand),
+    /// Report how long a blueprint string is, and which parts of the blueprint make it long
+    Size(SizeCommand),
+    /// Turn an image into a blueprint string of coloured refined concrete or lamps (needs the
+    /// `image` feature)
+    Image(ImageCommand),
 }
 
 #[derive(Args)]
 /// Parameters needed for decoding a blueprint string
 pub struct DecodeCommand {
     #[arg(short, long)]
-    /// The path to the file containing the blueprint string
-    pub infile: PathBuf,
+    /// The path to the file containing the blueprint string. Read from stdin if omitted or `-`
+    pub infile: Option<PathBuf>,
     #[arg(short, long)]
-    /// The path that the decoded blueprint should be written to
-    pub outfile: PathBuf,
+    /// The path that the decoded blueprint should be written to. Written to stdout if omitted or `-`
+    pub outfile: Option<PathBuf>,
+    #[arg(long, conflicts_with = "infile")]
+    /// Read the blueprint string from the system clipboard (needs the `clipboard` feature)
+    pub from_clipboard: bool,
+    #[arg(long, conflicts_with = "outfile")]
+    /// Copy the decoded blueprint to the system clipboard (needs the `clipboard` feature)
+    pub to_clipboard: bool,
     #[arg(long)]
     /// The output file format
     pub outform: BpFormat,
@@ -35,11 +58,212 @@ pub struct DecodeCommand {
     pub verbose: bool,
 }
 
+#[derive(Args)]
+/// Parameters needed for encoding a blueprint string
+pub struct EncodeCommand {
+    #[arg(short, long)]
+    /// The path to the file containing the blueprint JSON, as written by `decode --outform json`.
+    /// Read from stdin if omitted or `-`. The Rust debug output of `decode --outform rust` can't
+    /// be encoded, as it doesn't keep the names the game uses
+    pub infile: Option<PathBuf>,
+    #[arg(short, long)]
+    /// The path that the blueprint string should be written to. Written to stdout if omitted or `-`
+    pub outfile: Option<PathBuf>,
+    #[arg(long, conflicts_with = "infile")]
+    /// Read the blueprint JSON from the system clipboard (needs the `clipboard` feature)
+    pub from_clipboard: bool,
+    #[arg(long, conflicts_with = "outfile")]
+    /// Copy the blueprint string to the system clipboard, ready to import in game (needs the
+    /// `clipboard` feature)
+    pub to_clipboard: bool,
+    #[arg(long)]
+    /// Encode the JSON as-is, without checking that it describes a blueprint
+    pub raw: bool,
+    #[arg(long, default_value_t = 9, value_parser = clap::value_parser!(u32).range(0..=9))]
+    /// The zlib compression level, from 0 for none to 9 for the best
+    pub level: u32,
+    #[arg(long, conflicts_with = "raw")]
+    /// Write the blueprint in a canonical form, with its entities in order of position and
+    /// defaults left out, so that the same blueprint always gives the same string
+    pub canonical: bool,
+    #[arg(long, conflicts_with = "raw")]
+    /// Remove labels, descriptions, station names, alert messages, and custom colours, so the
+    /// blueprint can be shared without saying who made it
+    pub sanitize: bool,
+    #[arg(long, conflicts_with = "raw")]
+    /// Leave out the fields the game assumes anyway, like north facing entities' directions, to
+    /// make the blueprint string shorter
+    pub minimize: bool,
+    #[arg(long, requires = "sanitize")]
+    /// Replace the labels, station names, and alert messages removed by `--sanitize` with
+    /// placeholders, so that trains still know their stations apart
+    pub placeholders: bool,
+    #[arg(short, long)]
+    /// Print fields that were not understood and so left out of the blueprint string
+    pub verbose: bool,
+}
+
+#[derive(Args)]
+/// Parameters needed for validating a blueprint
+pub struct ValidateCommand {
+    #[arg(short, long)]
+    /// The path to the file containing the blueprint string or JSON. Read from stdin if omitted or `-`
+    pub infile: Option<PathBuf>,
+    #[arg(long, conflicts_with = "infile")]
+    /// Read the blueprint from the system clipboard (needs the `clipboard` feature)
+    pub from_clipboard: bool,
+    #[arg(long)]
+    /// Treat fields this tool doesn't know about as errors
+    pub strict: bool,
+    #[arg(long)]
+    /// Exit with 1 on warnings as well as errors
+    pub deny_warnings: bool,
+}
+
+#[derive(Args)]
+/// Parameters needed for rendering a blueprint
+pub struct RenderCommand {
+    #[arg(short, long)]
+    /// The path to the file containing the blueprint string or JSON. Read from stdin if omitted or `-`
+    pub infile: Option<PathBuf>,
+    #[arg(short, long)]
+    /// The path that the image should be written to. Written to stdout if omitted or `-`
+    pub outfile: Option<PathBuf>,
+    #[arg(long, conflicts_with = "infile")]
+    /// Read the blueprint from the system clipboard (needs the `clipboard` feature)
+    pub from_clipboard: bool,
+    #[arg(long, default_value = "svg")]
+    /// The image format, `svg`, `png`, `ascii`, or `dot` for the circuit networks. `png` needs the
+    /// `image` feature
+    pub format: RenderFormat,
+    #[arg(long, default_value = "dark")]
+    /// The colours to draw with, `dark` or `light`
+    pub theme: RenderTheme,
+    #[arg(long, default_value_t = 16)]
+    /// Pixels per tile, for raster formats
+    pub scale: u32,
+    #[arg(long)]
+    /// Write each entity's number over it, for raster formats
+    pub entity_numbers: bool,
+    #[arg(long)]
+    /// Draw with box drawing characters and arrows, for the ascii format
+    pub unicode: bool,
+    #[arg(long)]
+    /// Colour the text with ANSI escape codes, for the ascii format
+    pub color: bool,
+}
+
+#[derive(Args)]
+/// Parameters needed for reporting the size of a blueprint string
+pub struct SizeCommand {
+    #[arg(short, long)]
+    /// The path to the file containing the blueprint string or JSON. Read from stdin if omitted or `-`
+    pub infile: Option<PathBuf>,
+    #[arg(long, conflicts_with = "infile")]
+    /// Read the blueprint from the system clipboard (needs the `clipboard` feature)
+    pub from_clipboard: bool,
+    #[arg(long, default_value_t = 9, value_parser = clap::value_parser!(u32).range(0..=9))]
+    /// The zlib compression level to measure the string at, from 0 for none to 9 for the best
+    pub level: u32,
+}
+
+#[derive(Args)]
+/// Parameters needed for turning an image into a blueprint
+pub struct ImageCommand {
+    #[arg(short, long)]
+    /// The path to the image
+    pub infile: PathBuf,
+    #[arg(short, long)]
+    /// The path that the blueprint string should be written to. Written to stdout if omitted or `-`
+    pub outfile: Option<PathBuf>,
+    #[arg(long, conflicts_with = "outfile")]
+    /// Copy the blueprint string to the system clipboard (needs the `clipboard` feature)
+    pub to_clipboard: bool,
+    #[arg(long)]
+    /// Draw with lamps in the colours of the color signals, powered by substations, instead of
+    /// refined concrete
+    pub lamps: bool,
+    #[arg(long, default_value_t = 1)]
+    /// Pixels of the image per tile
+    pub scale: u32,
+    #[arg(long)]
+    /// Match each pixel to the closest colour alone, without dithering
+    pub no_dither: bool,
+}
+
+#[derive(Args)]
+/// Parameters needed for analyzing a blueprint
+pub struct AnalyzeCommand {
+    #[command(subcommand)]
+    pub analysis: Analysis,
+}
+
+#[derive(Subcommand)]
+/// Analyses that can be run on a blueprint
+pub enum Analysis {
+    /// Sum the electricity the blueprint uses and makes, and check it makes enough
+    Power(PowerCommand),
+    /// Check offshore pumps, boilers, and steam engines are piped up in the right numbers
+    Steam(InputArgs),
+    /// List the fluid networks, and check they don't mix fluids or leave machine inputs unconnected
+    Fluids(InputArgs),
+    /// Check pipes between pumps are short enough to carry the fluid the machines along them use
+    Pipes(InputArgs),
+    /// Work out the heat, heat exchangers, turbines, and fuel of nuclear reactors
+    Nuclear(InputArgs),
+    /// List the rail networks and the blocks their signals split them into, with their train stops.
+    /// Of the rails of 2.0 only straight ones are understood
+    Rails(InputArgs),
+    /// Check a belt balancer is throughput unlimited and splits every input evenly
+    Balancer(InputArgs),
+    /// Work out how fast the crafting machines make and use each item
+    Throughput(InputArgs),
+    /// Count the beacons reaching each crafting machine and lab, and find beacons that reach none
+    Beacons(InputArgs),
+    /// Check each recipe has enough machines to keep up with the recipes using its products
+    Ratios(InputArgs),
+    /// Count the items it takes to build the blueprint
+    Cost(CostCommand),
+    /// Work out how fast the labs research, and the science packs they need
+    Science(ScienceCommand),
+    /// Score how much the blueprint slows the game down, to compare designs
+    Ups(InputArgs),
+    /// List the mods the blueprint needs, and the names no known mod adds
+    Mods(InputArgs),
+    /// Work out the oldest game version that can paste the blueprint, from what it uses
+    Version(InputArgs),
+}
+
+#[derive(Args)]
+/// Where to read the blueprint from, for commands that need nothing else
+pub struct InputArgs {
+    #[arg(short, long)]
+    /// The path to the file containing the blueprint string or JSON. Read from stdin if omitted or `-`
+    pub infile: Option<PathBuf>,
+    #[arg(long, conflicts_with = "infile")]
+    /// Read the blueprint from the system clipboard (needs the `clipboard` feature)
+    pub from_clipboard: bool,
+}
+
+#[derive(Args)]
+/// Parameters needed for the power analysis
+pub struct PowerCommand {
+    #[arg(short, long)]
+    /// The path to the file containing the blueprint string or JSON. Read from stdin if omitted or `-`
+    pub infile: Option<PathBuf>,
+    #[arg(long, conflicts_with = "infile")]
+    /// Read the blueprint from the system clipboard (needs the `clipboard` feature)
+    pub from_clipboard: bool,
+    #[arg(long, default_value_t = 100.0)]
+    /// The percentage of time machines spend working, for the average consumption
+    pub utilization: f64,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 /// A format that a blueprint output can be stored in
 pub enum BpFormat {
     /// Javascript object notation
-    JSON,
+    Json,
     /// Rust's debug print
     Rust,
 }
@@ -48,7 +272,7 @@ impl FromStr for BpFormat {
     type Err = io::Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
-            "json" => Ok(BpFormat::JSON),
+            "json" => Ok(BpFormat::Json),
             "rust" => Ok(BpFormat::Rust),
             _ => Err(io::Error::new(
                 io::ErrorKind::InvalidInput,
@@ -57,3 +281,91 @@ impl FromStr for BpFormat {
         }
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A format that a blueprint can be drawn in
+pub enum RenderFormat {
+    /// Scalable vector graphics
+    Svg,
+    /// Portable network graphics
+    Png,
+    /// A character per tile, for terminals
+    Ascii,
+    /// A Graphviz graph of the circuit networks
+    Dot,
+}
+
+impl FromStr for RenderFormat {
+    type Err = io::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "svg" => Ok(RenderFormat::Svg),
+            "png" => Ok(RenderFormat::Png),
+            "ascii" => Ok(RenderFormat::Ascii),
+            "dot" => Ok(RenderFormat::Dot),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Unexpected image format '{s}'"),
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A set of colours to draw a blueprint with
+pub enum RenderTheme {
+    /// Light entities on a dark background
+    Dark,
+    /// Dark outlines on a white background
+    Light,
+}
+
+impl FromStr for RenderTheme {
+    type Err = io::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dark" => Ok(RenderTheme::Dark),
+            "light" => Ok(RenderTheme::Light),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Unexpected theme '{s}'"),
+            )),
+        }
+    }
+}
+
+#[derive(Args)]
+/// Parameters needed for the build cost
+pub struct CostCommand {
+    #[arg(short, long)]
+    /// The path to the file containing the blueprint string or JSON. Read from stdin if omitted or `-`
+    pub infile: Option<PathBuf>,
+    #[arg(long, conflicts_with = "infile")]
+    /// Read the blueprint from the system clipboard (needs the `clipboard` feature)
+    pub from_clipboard: bool,
+    #[arg(long)]
+    /// Work the items back to the raw resources they are crafted from
+    pub raw: bool,
+}
+
+#[derive(Args)]
+/// Parameters needed for the science analysis
+pub struct ScienceCommand {
+    #[arg(short, long)]
+    /// The path to the file containing the blueprint string or JSON. Read from stdin if omitted or `-`
+    pub infile: Option<PathBuf>,
+    #[arg(long, conflicts_with = "infile")]
+    /// Read the blueprint from the system clipboard (needs the `clipboard` feature)
+    pub from_clipboard: bool,
+    #[arg(short, long, default_value_t = 30.0)]
+    /// The seconds one research unit takes in a lab with a researching speed of 1
+    pub unit_time: f64,
+    #[arg(
+        short,
+        long,
+        value_delimiter = ',',
+        default_value = "automation-science-pack,logistic-science-pack"
+    )]
+    /// The science packs each research unit takes, separated by commas
+    pub packs: Vec<String>,
+}
@@ -0,0 +1,42 @@
+use std::io;
+
+#[cfg(feature = "clipboard")]
+fn clipboard_error(e: arboard::Error) -> io::Error {
+    io::Error::other(format!("Clipboard unavailable: {e}"))
+}
+
+#[cfg(feature = "clipboard")]
+/// Read text from the system clipboard
+pub fn read() -> io::Result<String> {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.get_text())
+        .map_err(clipboard_error)
+}
+
+#[cfg(feature = "clipboard")]
+/// Replace the contents of the system clipboard with text
+pub fn write(text: &str) -> io::Result<()> {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(text))
+        .map_err(clipboard_error)
+}
+
+#[cfg(not(feature = "clipboard"))]
+fn unsupported() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "factorio-bp was built without the clipboard feature",
+    )
+}
+
+#[cfg(not(feature = "clipboard"))]
+/// Read text from the system clipboard
+pub fn read() -> io::Result<String> {
+    Err(unsupported())
+}
+
+#[cfg(not(feature = "clipboard"))]
+/// Replace the contents of the system clipboard with text
+pub fn write(_text: &str) -> io::Result<()> {
+    Err(unsupported())
+}
@@ -1,71 +1,861 @@
-use std::{fs, io::Read};
+use std::{
+    collections::BTreeSet,
+    fs,
+    io::{self, Read, Write},
+    path::PathBuf,
+    process::ExitCode,
+};
 
-use base64::{engine::general_purpose, Engine};
 use clap::Parser;
-use factorio_bp_rs::blueprint::{Blueprint, BlueprintBook};
-use flate2::read::ZlibDecoder;
-use serde_json::{Map, Value};
+use factorio_bp_rs::{
+    analysis::{
+        balancer::BalancerReport,
+        beacons::BeaconReport,
+        fluids::FluidReport,
+        nuclear::NuclearReport,
+        pipes::PipeReport,
+        power::PowerReport,
+        ratios::RatioReport,
+        science::{Research, ScienceReport},
+        size::SizeReport,
+        solar::{SolarReport, IDEAL_RATIO},
+        steam::{SteamReport, BOILERS_PER_PUMP, ENGINES_PER_BOILER},
+        throughput::ThroughputReport,
+        ups::UpsReport,
+        version::VersionReport,
+    },
+    blueprint::{
+        decode_json, decode_string, Blueprint, BlueprintEnvelope, DecodeOptions, Decoded,
+        EncodeOptions, SanitizeOptions, Severity,
+    },
+    plan::PlanOptions,
+    prototypes::PrototypeDb,
+    rail::RailGraph,
+    render::{to_ascii, to_dot, to_svg, AsciiOptions, Scene, Theme},
+};
 
 mod cli;
+mod clipboard;
 
-use self::cli::{BpFormat, Cli, Command, DecodeCommand};
+use self::cli::{
+    Analysis, AnalyzeCommand, BpFormat, Cli, Command, CostCommand, DecodeCommand, EncodeCommand,
+    ImageCommand, InputArgs, PowerCommand, RenderCommand, RenderFormat, RenderTheme,
+    ScienceCommand, SizeCommand, ValidateCommand,
+};
 
-fn decode_bp(args: &DecodeCommand) -> Result<(), std::io::Error> {
-    let mut input = fs::read_to_string(&args.infile)?;
+/// The file a file argument names, or `None` if it means stdin or stdout.
+fn named_file(path: &Option<PathBuf>) -> Option<&PathBuf> {
+    path.as_ref().filter(|path| path.as_os_str() != "-")
+}
 
-    // Remove all line breaks before parsing
-    input.retain(|c| !(c == '\n' || c == '\r'));
+/// Read the whole input file, the clipboard, or stdin.
+fn read_input(path: &Option<PathBuf>, from_clipboard: bool) -> io::Result<String> {
+    if from_clipboard {
+        return clipboard::read();
+    }
 
-    // Remove the "version byte"
-    let mut input_iter = input.chars();
-    let _version = input_iter.next().unwrap();
+    match named_file(path) {
+        Some(path) => fs::read_to_string(path),
+        None => {
+            let mut input = String::new();
+            io::stdin().read_to_string(&mut input)?;
+            Ok(input)
+        }
+    }
+}
 
-    let parseable: String = input_iter.collect();
+/// Write the output file, the clipboard, or stdout followed by a newline.
+fn write_output(path: &Option<PathBuf>, to_clipboard: bool, output: &str) -> io::Result<()> {
+    if to_clipboard {
+        return clipboard::write(output);
+    }
 
-    let input_bytes = general_purpose::STANDARD
-        .decode(parseable)
-        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    match named_file(path) {
+        Some(path) => fs::write(path, output),
+        None => writeln!(io::stdout().lock(), "{output}"),
+    }
+}
 
-    let mut z = ZlibDecoder::new(&input_bytes[..]);
-    let mut json = String::new();
-    z.read_to_string(&mut json)?;
+/// Decode a blueprint string, or the JSON written by `decode`.
+fn decode_input(input: &str, options: &DecodeOptions) -> io::Result<Decoded> {
+    if input.trim_start().starts_with('{') {
+        options.decode_from_json(input)
+    } else {
+        options.decode(input)
+    }
+    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Write binary data to the output file or stdout.
+fn write_binary_output(path: &Option<PathBuf>, output: &[u8]) -> io::Result<()> {
+    match named_file(path) {
+        Some(path) => fs::write(path, output),
+        None => io::stdout().lock().write_all(output),
+    }
+}
+
+fn decode_bp(args: &DecodeCommand) -> Result<(), std::io::Error> {
+    let input = read_input(&args.infile, args.from_clipboard)?;
 
     let data_to_write = match args.outform {
-        BpFormat::JSON => json,
-        BpFormat::Rust => {
-            // Determine if its a bp book or single bp
-            let v: Map<String, Value> = serde_json::from_str(&json)?;
-
-            if let Some(inner) = v.get("blueprint") {
-                let js = &inner.to_string();
-                // println!("{}", js);
-                let bp: Blueprint = serde_json::from_str(&inner.to_string())?;
-                format!("{:?}", bp)
-            } else if let Some(inner) = v.get("blueprint_book") {
-                let bp_book: BlueprintBook = serde_json::from_str(&inner.to_string())?;
-                format!("{:?}", bp_book)
+        BpFormat::Json => decode_json(&input),
+        BpFormat::Rust => decode_string(&input).map(|envelope| format!("{:?}", envelope)),
+    }
+    .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+
+    write_output(&args.outfile, args.to_clipboard, &data_to_write)?;
+
+    Ok(())
+}
+
+fn encode_bp(args: &EncodeCommand) -> Result<(), std::io::Error> {
+    let input = read_input(&args.infile, args.from_clipboard)?;
+    // JSON starts with punctuation, and debug output with the name of what it prints
+    if input
+        .trim_start()
+        .starts_with(|c: char| c.is_ascii_uppercase())
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "this looks like the output of `decode --outform rust`, which can't be encoded; \
+             decode with `--outform json` instead",
+        ));
+    }
+
+    let options = EncodeOptions {
+        compression_level: args.level,
+        canonical: args.canonical,
+    };
+    let bp_string = if args.raw {
+        options.encode_json(&input)
+    } else {
+        DecodeOptions::default()
+            .decode_from_json(&input)
+            .and_then(|decoded| {
+                if args.verbose {
+                    for warning in &decoded.warnings {
+                        eprintln!("warning: {warning}");
+                    }
+                }
+                let mut envelope = decoded.envelope;
+                if args.sanitize {
+                    envelope.sanitize_with(&SanitizeOptions {
+                        placeholders: args.placeholders,
+                    });
+                }
+                if args.minimize {
+                    envelope.minimize();
+                }
+                options.encode(&envelope)
+            })
+    }
+    .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+
+    write_output(&args.outfile, args.to_clipboard, &bp_string)?;
+
+    Ok(())
+}
+
+fn size_bp(args: &SizeCommand) -> Result<(), std::io::Error> {
+    let input = read_input(&args.infile, args.from_clipboard)?;
+    let decoded = decode_input(&input, &DecodeOptions::default())?;
+    let options = EncodeOptions {
+        compression_level: args.level,
+        ..Default::default()
+    };
+    let report = SizeReport::new(&decoded.envelope, &options)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    println!(
+        "string: {} characters at compression level {}",
+        report.string_length, args.level
+    );
+    println!("json: {} bytes", report.json_length);
+    for (name, size) in report.largest() {
+        println!(
+            "  {name}: {} characters ({:.1}%), {} bytes of json",
+            size.string_length,
+            size.string_length as f64 / report.string_length as f64 * 100.0,
+            size.json_length
+        );
+    }
+    Ok(())
+}
+
+fn validate_bp(args: &ValidateCommand) -> ExitCode {
+    let options = if args.strict {
+        DecodeOptions::strict()
+    } else {
+        DecodeOptions::default()
+    };
+
+    let decoded = read_input(&args.infile, args.from_clipboard)
+        .and_then(|input| decode_input(&input, &options));
+    let decoded = match decoded {
+        Ok(decoded) => decoded,
+        Err(e) => {
+            eprintln!("error: {e}");
+            return ExitCode::from(2);
+        }
+    };
+
+    for warning in &decoded.warnings {
+        println!("warning: {warning}");
+    }
+
+    let issues = decoded.envelope.validate();
+    for issue in &issues {
+        match issue.severity() {
+            Severity::Warning => println!("warning: {issue}"),
+            Severity::Error => println!("error: {issue}"),
+        }
+    }
+
+    let has_errors = issues
+        .iter()
+        .any(|issue| issue.severity() == Severity::Error);
+    let has_warnings = !decoded.warnings.is_empty()
+        || issues
+            .iter()
+            .any(|issue| issue.severity() == Severity::Warning);
+
+    if has_errors || (args.deny_warnings && has_warnings) {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// The blueprint to draw: the blueprint itself, or the active blueprint of a book.
+fn active_blueprint(envelope: &BlueprintEnvelope) -> Option<&Blueprint> {
+    match envelope {
+        BlueprintEnvelope::Blueprint(blueprint) => Some(blueprint),
+        BlueprintEnvelope::BlueprintBook(book) => book
+            .blueprints
+            .iter()
+            .find(|entry| entry.index == book.active_index)
+            .and_then(|entry| active_blueprint(&entry.content)),
+        _ => None,
+    }
+}
+
+fn render_bp(args: &RenderCommand) -> Result<(), std::io::Error> {
+    let input = read_input(&args.infile, args.from_clipboard)?;
+    let decoded = decode_input(&input, &DecodeOptions::default())?;
+
+    let blueprint = active_blueprint(&decoded.envelope).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "only blueprints and blueprint books can be rendered",
+        )
+    })?;
+    let scene = Scene::new(blueprint, PrototypeDb::global());
+    let theme = match args.theme {
+        RenderTheme::Dark => Theme::dark(),
+        RenderTheme::Light => Theme::light(),
+    };
+
+    match args.format {
+        RenderFormat::Svg => write_output(&args.outfile, false, &to_svg(&scene, &theme)),
+        RenderFormat::Png => write_binary_output(&args.outfile, &render_png(&scene, theme, args)?),
+        RenderFormat::Ascii => {
+            let options = AsciiOptions {
+                unicode: args.unicode,
+                color: args.color.then_some(theme),
+            };
+            write_binary_output(&args.outfile, to_ascii(&scene, &options).as_bytes())
+        }
+        RenderFormat::Dot => write_output(&args.outfile, false, &to_dot(blueprint)),
+    }
+}
+
+#[cfg(feature = "image")]
+fn render_png(scene: &Scene, theme: Theme, args: &RenderCommand) -> io::Result<Vec<u8>> {
+    use factorio_bp_rs::render::{to_png, PngOptions};
+
+    let options = PngOptions {
+        scale: args.scale,
+        theme,
+        entity_numbers: args.entity_numbers,
+    };
+    to_png(scene, &options).map_err(io::Error::other)
+}
+
+#[cfg(not(feature = "image"))]
+fn render_png(_scene: &Scene, _theme: Theme, _args: &RenderCommand) -> io::Result<Vec<u8>> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "factorio-bp was built without the image feature",
+    ))
+}
+
+#[cfg(feature = "image")]
+fn image_bp(args: &ImageCommand) -> io::Result<()> {
+    use factorio_bp_rs::generators::pixel_art::{PixelArt, Swatch};
+
+    let image = image::open(&args.infile).map_err(io::Error::other)?;
+    let art = PixelArt {
+        palette: if args.lamps {
+            Swatch::signal_lamps()
+        } else {
+            Swatch::concrete()
+        },
+        scale: args.scale,
+        dither: !args.no_dither,
+    };
+    let blueprint = art.generate(&image).build().map_err(io::Error::other)?;
+    let bp_string = blueprint.to_bp_string().map_err(io::Error::other)?;
+    write_output(&args.outfile, args.to_clipboard, &bp_string)
+}
+
+#[cfg(not(feature = "image"))]
+fn image_bp(_args: &ImageCommand) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "factorio-bp was built without the image feature",
+    ))
+}
+
+/// A power in watts with an SI prefix, e.g. "1.5 MW".
+fn format_power(watts: f64) -> String {
+    format_si(watts, "W")
+}
+
+/// A value with an SI prefix and `unit`, to three significant figures or so.
+fn format_si(value: f64, unit: &str) -> String {
+    let prefixes = [(1e12, "T"), (1e9, "G"), (1e6, "M"), (1e3, "k")];
+    let (scale, prefix) = prefixes
+        .into_iter()
+        .find(|(scale, _)| value.abs() >= *scale)
+        .unwrap_or((1.0, ""));
+    let scaled = value / scale;
+    // Trim trailing zeros, so 1.50 reads as 1.5
+    let number = format!("{scaled:.2}");
+    let number = number.trim_end_matches('0').trim_end_matches('.');
+    format!("{number} {prefix}{unit}")
+}
+
+/// Read and decode the blueprint to analyze, the active blueprint of a book.
+fn read_blueprint(infile: &Option<PathBuf>, from_clipboard: bool) -> io::Result<Blueprint> {
+    let input = read_input(infile, from_clipboard)?;
+    let decoded = decode_input(&input, &DecodeOptions::default())?;
+    active_blueprint(&decoded.envelope).cloned().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "only blueprints and blueprint books can be analyzed",
+        )
+    })
+}
+
+fn analyze_power(args: &PowerCommand) -> Result<(), std::io::Error> {
+    let blueprint = &read_blueprint(&args.infile, args.from_clipboard)?;
+
+    let report = PowerReport::new(blueprint, PrototypeDb::global());
+    let utilization = args.utilization / 100.0;
+    let average = report.average_consumption(utilization);
+
+    println!(
+        "consumption: {} max, {} idle, {} average at {}% utilization",
+        format_power(report.max_consumption),
+        format_power(report.idle_consumption),
+        format_power(average),
+        args.utilization
+    );
+    println!(
+        "production: {} from generators, {} from solar at peak ({} average)",
+        format_power(report.generator_output),
+        format_power(report.solar_peak),
+        format_power(report.solar_average())
+    );
+    if report.accumulator_capacity > 0.0 {
+        println!(
+            "accumulators: {} stored, {} output",
+            format_si(report.accumulator_capacity, "J"),
+            format_power(report.accumulator_output)
+        );
+    }
+    if report.reactor_output > 0.0 {
+        println!("reactors: {} of heat", format_power(report.reactor_output));
+    }
+    if !report.unknown.is_empty() {
+        let unknown: Vec<String> = report.unknown.iter().map(usize::to_string).collect();
+        println!("not counted, unknown entities: {}", unknown.join(", "));
+    }
+
+    let solar = SolarReport::new(blueprint, PrototypeDb::global(), average);
+    if let Some(ratio) = solar.ratio() {
+        let deviation = solar.ratio_deviation().unwrap_or_default() * 100.0;
+        println!(
+            "solar: {} panels, {} accumulators, {ratio:.2} accumulators per panel ({deviation:+.0}% from the ideal {IDEAL_RATIO:.2})",
+            solar.panels, solar.accumulators
+        );
+        if solar.is_covered() {
+            println!(
+                "night: covered, accumulators bottom out at {}",
+                format_si(solar.min_charge, "J")
+            );
+        } else {
+            println!(
+                "night: not covered, {} short over {:.0} s",
+                format_si(solar.shortfall, "J"),
+                solar.unpowered_seconds
+            );
+        }
+    }
+
+    let sustained = report.sustained_production();
+    if report.covers(average) {
+        println!(
+            "production covers consumption, {} to spare",
+            format_power(sustained - average)
+        );
+    } else {
+        println!(
+            "production does not cover consumption, {} short",
+            format_power(average - sustained)
+        );
+    }
+
+    Ok(())
+}
+
+fn analyze_steam(args: &InputArgs) -> Result<(), std::io::Error> {
+    let blueprint = read_blueprint(&args.infile, args.from_clipboard)?;
+    let report = SteamReport::new(&blueprint, PrototypeDb::global());
+
+    for (i, setup) in report.setups.iter().enumerate() {
+        let (pumps, boilers, engines) = (
+            setup.offshore_pumps.len(),
+            setup.boilers.len(),
+            setup.engines.len(),
+        );
+        let limit = match setup.limit() {
+            Some(component) => format!("limited by {component}"),
+            None => "balanced".to_owned(),
+        };
+        println!(
+            "setup {}: {pumps} offshore pumps, {boilers} boilers, {engines} steam engines: {}, {limit}",
+            i + 1,
+            format_power(setup.output()),
+        );
+        if pumps > 0 && boilers > 0 {
+            println!(
+                "  {:.2} boilers per offshore pump (at most {BOILERS_PER_PUMP})",
+                boilers as f64 / pumps as f64
+            );
+        }
+        if boilers > 0 {
+            println!(
+                "  {:.2} steam engines per boiler (ideal {ENGINES_PER_BOILER})",
+                engines as f64 / boilers as f64
+            );
+        }
+    }
+    for issue in &report.issues {
+        println!("warning: {issue}");
+    }
+    println!("total: {}", format_power(report.output()));
+
+    Ok(())
+}
+
+fn analyze_fluids(args: &InputArgs) -> Result<(), std::io::Error> {
+    let blueprint = read_blueprint(&args.infile, args.from_clipboard)?;
+    let report = FluidReport::new(&blueprint, PrototypeDb::global());
+
+    // Lone fluid boxes aren't worth listing
+    let networks = report
+        .networks
+        .iter()
+        .filter(|network| network.network.entity_numbers().len() > 1);
+    for (i, network) in networks.enumerate() {
+        let fluids = match network.fluids.len() {
+            0 => "no fixed fluid".to_owned(),
+            _ => network
+                .fluids
+                .iter()
+                .map(String::as_str)
+                .collect::<Vec<_>>()
+                .join(", "),
+        };
+        println!(
+            "network {}: {} entities, {fluids}",
+            i + 1,
+            network.network.entity_numbers().len()
+        );
+    }
+    for issue in &report.issues {
+        println!("warning: {issue}");
+    }
+
+    Ok(())
+}
+
+fn analyze_pipes(args: &InputArgs) -> Result<(), std::io::Error> {
+    let blueprint = read_blueprint(&args.infile, args.from_clipboard)?;
+    let report = PipeReport::new(&blueprint, PrototypeDb::global());
+
+    for (i, segment) in report.segments.iter().enumerate() {
+        println!(
+            "segment {}: {} long, {:.0}/s of {}, using {:.0}/s",
+            i + 1,
+            segment.length,
+            segment.capacity,
+            segment.fluid.as_deref().unwrap_or("fluid"),
+            segment.needed
+        );
+    }
+    for segment in report.issues() {
+        println!("warning: {segment}");
+    }
+
+    Ok(())
+}
+
+fn analyze_nuclear(args: &InputArgs) -> Result<(), std::io::Error> {
+    let blueprint = read_blueprint(&args.infile, args.from_clipboard)?;
+    let report = NuclearReport::new(&blueprint, PrototypeDb::global());
+
+    for reactor in &report.reactors {
+        println!(
+            "reactor {}: {} neighbours, {}",
+            reactor.entity_number,
+            reactor.neighbours.len(),
+            format_power(reactor.output())
+        );
+    }
+    println!("heat: {}", format_power(report.heat_output()));
+    println!(
+        "heat exchangers: {} in reach, {} needed",
+        report.heat_exchangers.len(),
+        report.heat_exchangers_needed()
+    );
+    println!(
+        "steam turbines: {}, {} needed",
+        report.turbines,
+        report.turbines_needed()
+    );
+    println!("output: {}", format_power(report.output()));
+    println!(
+        "fuel: {:.2} uranium fuel cells per minute",
+        report.fuel_cells_per_minute()
+    );
+    for exchanger in &report.out_of_reach {
+        println!("warning: heat exchanger {exchanger} is out of reach of every reactor");
+    }
+
+    Ok(())
+}
+
+fn analyze_rails(args: &InputArgs) -> Result<(), std::io::Error> {
+    let blueprint = read_blueprint(&args.infile, args.from_clipboard)?;
+    let graph = RailGraph::new(&blueprint);
+
+    let networks = graph.networks();
+    let blocks = graph.blocks();
+    println!(
+        "{} rails in {} networks and {} blocks, {} signals, {} train stops",
+        graph.rails().count(),
+        networks.len(),
+        blocks.len(),
+        graph.signals().len(),
+        graph.stops().len()
+    );
+    for (i, block) in blocks.iter().enumerate() {
+        let stations: Vec<&str> = graph
+            .stops()
+            .iter()
+            .filter(|stop| block.stops.contains(&stop.entity_number))
+            .map(|stop| stop.station.as_deref().unwrap_or("unnamed"))
+            .collect();
+        print!(
+            "block {}: {} rails, {} signals",
+            i + 1,
+            block.rails.len(),
+            block.signals.len()
+        );
+        if stations.is_empty() {
+            println!();
+        } else {
+            println!(", stops {}", stations.join(", "));
+        }
+    }
+    if !graph.unsupported().is_empty() {
+        println!(
+            "warning: {} rails left out, only straight rails are understood from 2.0 \
+             and none from mods",
+            graph.unsupported().len()
+        );
+    }
+
+    Ok(())
+}
+
+fn analyze_balancer(args: &InputArgs) -> Result<(), std::io::Error> {
+    // More than this many issues are summarized rather than listed
+    const MAX_ISSUES: usize = 20;
+
+    let blueprint = read_blueprint(&args.infile, args.from_clipboard)?;
+    let report = BalancerReport::new(&blueprint, PrototypeDb::global()).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "no balancer found: the belts need splitters and to all be joined up",
+        )
+    })?;
+
+    let yes_no = |ok: bool| if ok { "yes" } else { "no" };
+    println!(
+        "{}x{} balancer, inputs {:?}, outputs {:?}",
+        report.inputs.len(),
+        report.outputs.len(),
+        report.inputs,
+        report.outputs
+    );
+    println!(
+        "throughput unlimited: {}{}",
+        yes_no(report.is_throughput_unlimited()),
+        if report.exhaustive {
+            ""
+        } else {
+            " (only single inputs and outputs tried)"
+        }
+    );
+    println!("balanced: {}", yes_no(report.is_balanced()));
+    println!("lanes balanced: {}", yes_no(report.is_lane_balanced()));
+    for issue in report.issues.iter().take(MAX_ISSUES) {
+        println!("warning: {issue}");
+    }
+    if report.issues.len() > MAX_ISSUES {
+        println!("and {} more", report.issues.len() - MAX_ISSUES);
+    }
+
+    Ok(())
+}
+
+fn analyze_throughput(args: &InputArgs) -> Result<(), std::io::Error> {
+    let blueprint = read_blueprint(&args.infile, args.from_clipboard)?;
+    let report = ThroughputReport::new(&blueprint, PrototypeDb::global());
+
+    println!("{} crafting machines", report.machines.len());
+    let (produced, consumed) = (report.produced(), report.consumed());
+    let names: BTreeSet<&String> = produced.keys().chain(consumed.keys()).collect();
+    for name in names {
+        let rates: Vec<String> = [(produced.get(name), "made"), (consumed.get(name), "used")]
+            .into_iter()
+            .filter_map(|(rate, what)| Some(format!("{:.2}/s {what}", rate?)))
+            .collect();
+        println!("{name}: {}", rates.join(", "));
+    }
+    if !report.bottlenecks.is_empty() {
+        println!("bottlenecks, worst first:");
+    }
+    for (i, bottleneck) in report.bottlenecks.iter().enumerate() {
+        println!(
+            "  {}. {bottleneck} ({:.0}% of what's needed)",
+            i + 1,
+            bottleneck.fraction() * 100.0
+        );
+    }
+    for machine in &report.unknown {
+        println!("warning: crafting machine {machine} has no recipe set, or an unknown one");
+    }
+    Ok(())
+}
+
+fn analyze_beacons(args: &InputArgs) -> Result<(), std::io::Error> {
+    let blueprint = read_blueprint(&args.infile, args.from_clipboard)?;
+    let report = BeaconReport::new(&blueprint, PrototypeDb::global());
+
+    println!(
+        "{} beacons, {} crafting machines and labs",
+        report.beacons.len(),
+        report.machines.len()
+    );
+    for (beacons, machines) in report.counts().iter().rev() {
+        println!("  {machines} machines reached by {beacons} beacons");
+    }
+    for (machine, beacons) in &report.machines {
+        println!("machine {machine}: {} beacons {beacons:?}", beacons.len());
+    }
+    for beacon in report.unused() {
+        println!("warning: beacon {beacon} reaches no crafting machine or lab");
+    }
+    Ok(())
+}
+
+fn analyze_ratios(args: &InputArgs) -> Result<(), std::io::Error> {
+    let blueprint = read_blueprint(&args.infile, args.from_clipboard)?;
+    let report = RatioReport::new(&blueprint, PrototypeDb::global());
+
+    for recipe in &report.recipes {
+        println!(
+            "{}: {} machines, {:.2} needed{}",
+            recipe.recipe,
+            recipe.machines,
+            recipe.ideal,
+            if recipe.end_product {
+                " (end product)"
             } else {
-                return Err(std::io::Error::new(
-                    std::io::ErrorKind::InvalidData,
-                    format!("given data is not a blueprint or blueprint book"),
-                ));
+                ""
             }
+        );
+    }
+    for recipe in report.suggestions() {
+        let (verb, count) = match recipe.change() {
+            change if change > 0 => ("add", change),
+            change => ("remove", -change),
+        };
+        println!("suggestion: {verb} {count} {} machines", recipe.recipe);
+    }
+    Ok(())
+}
+
+fn analyze_cost(args: &CostCommand) -> Result<(), std::io::Error> {
+    let blueprint = read_blueprint(&args.infile, args.from_clipboard)?;
+    let db = PrototypeDb::global();
+
+    if args.raw {
+        for (item, count) in blueprint.raw_build_cost(db, &PlanOptions::default()) {
+            println!("{item}: {count:.2}");
+        }
+    } else {
+        for (item, count) in blueprint.build_cost(db) {
+            println!("{item}: {count}");
         }
+    }
+    Ok(())
+}
+
+fn analyze_science(args: &ScienceCommand) -> Result<(), std::io::Error> {
+    let blueprint = read_blueprint(&args.infile, args.from_clipboard)?;
+    let research = Research {
+        unit_time: args.unit_time,
+        packs: args.packs.clone(),
     };
+    let report = ScienceReport::new(&blueprint, PrototypeDb::global(), &research);
+
+    println!(
+        "{} labs: {:.2} units/min",
+        report.labs.len(),
+        report.units_per_second() * 60.0
+    );
+    for pack in &report.packs {
+        println!(
+            "{}: {:.2}/min needed, {:.2}/min made",
+            pack.name,
+            pack.needed * 60.0,
+            pack.made * 60.0
+        );
+    }
+    if let Some(saturation) = report.saturation() {
+        println!("labs kept busy {:.0}% of the time", saturation * 100.0);
+    }
+    for lab in &report.unusable {
+        println!("warning: lab {lab} can't take every pack");
+    }
+    Ok(())
+}
 
-    fs::write(&args.outfile, data_to_write)?;
+fn analyze_ups(args: &InputArgs) -> Result<(), std::io::Error> {
+    let blueprint = read_blueprint(&args.infile, args.from_clipboard)?;
+    let report = UpsReport::new(&blueprint, PrototypeDb::global());
 
+    println!("score: {:.1}", report.score());
+    for (cost, score) in report.breakdown() {
+        if score > 0.0 {
+            println!("  {cost}: {score:.1}");
+        }
+    }
     Ok(())
 }
 
-fn main() -> Result<(), std::io::Error> {
+fn analyze_mods(args: &InputArgs) -> Result<(), std::io::Error> {
+    let blueprint = read_blueprint(&args.infile, args.from_clipboard)?;
+    let mods = blueprint.required_mods(PrototypeDb::global());
+
+    if mods.is_vanilla() {
+        println!("vanilla");
+    }
+    for mod_name in mods.mods() {
+        let names: Vec<_> = mods.by_mod[mod_name].iter().map(String::as_str).collect();
+        println!("{mod_name}: {}", names.join(", "));
+    }
+    if !mods.unknown.is_empty() {
+        let names: Vec<_> = mods.unknown.iter().map(String::as_str).collect();
+        println!("unknown: {}", names.join(", "));
+    }
+    Ok(())
+}
+
+fn analyze_version(args: &InputArgs) -> Result<(), std::io::Error> {
+    let blueprint = read_blueprint(&args.infile, args.from_clipboard)?;
+    let report = VersionReport::new(&blueprint);
+
+    println!("needs {} or later", report.required());
+    for (feature, version) in &report.features {
+        println!("  {feature}: {version}");
+    }
+    if !report.is_consistent() {
+        println!("warning: saved by {}, older than it needs", report.stored);
+    }
+    Ok(())
+}
+
+fn analyze_bp(args: &AnalyzeCommand) -> Result<(), std::io::Error> {
+    match &args.analysis {
+        Analysis::Power(args) => analyze_power(args),
+        Analysis::Steam(args) => analyze_steam(args),
+        Analysis::Fluids(args) => analyze_fluids(args),
+        Analysis::Pipes(args) => analyze_pipes(args),
+        Analysis::Nuclear(args) => analyze_nuclear(args),
+        Analysis::Rails(args) => analyze_rails(args),
+        Analysis::Balancer(args) => analyze_balancer(args),
+        Analysis::Throughput(args) => analyze_throughput(args),
+        Analysis::Beacons(args) => analyze_beacons(args),
+        Analysis::Ratios(args) => analyze_ratios(args),
+        Analysis::Cost(args) => analyze_cost(args),
+        Analysis::Science(args) => analyze_science(args),
+        Analysis::Ups(args) => analyze_ups(args),
+        Analysis::Mods(args) => analyze_mods(args),
+        Analysis::Version(args) => analyze_version(args),
+    }
+}
+
+/// Read the prototypes of a `data.raw` dump, and use them in place of the vanilla ones.
+fn load_data_raw(path: &PathBuf) -> Result<(), io::Error> {
+    let file = io::BufReader::new(fs::File::open(path)?);
+    let db = PrototypeDb::from_data_raw_reader(file)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    // Nothing has used the global prototypes yet
+    assert!(PrototypeDb::set_global(db));
+    Ok(())
+}
+
+fn main() -> ExitCode {
     let cli = Cli::parse();
 
-    match &cli.command {
-        Command::Decode(args) => {
-            decode_bp(args)?;
+    if let Some(path) = &cli.data_raw {
+        if let Err(e) = load_data_raw(path) {
+            eprintln!("Error: {e}");
+            return ExitCode::FAILURE;
         }
     }
 
-    Ok(())
+    let result = match &cli.command {
+        Command::Decode(args) => decode_bp(args),
+        Command::Encode(args) => encode_bp(args),
+        Command::Validate(args) => return validate_bp(args),
+        Command::Render(args) => render_bp(args),
+        Command::Analyze(args) => analyze_bp(args),
+        Command::Size(args) => size_bp(args),
+        Command::Image(args) => image_bp(args),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            ExitCode::FAILURE
+        }
+    }
 }
@@ -0,0 +1,44 @@
+/// Running several analyses over a blueprint in one walk, sharing what they work out.
+pub mod pass;
+
+/// How much electricity a blueprint uses and makes.
+pub mod power;
+
+/// Whether solar panels and accumulators keep a load powered through the night.
+pub mod solar;
+
+/// Whether offshore pumps, boilers, and steam engines are piped up in the right numbers.
+pub mod steam;
+
+/// Whether pipes keep fluids apart and reach every machine that takes them.
+pub mod fluids;
+
+/// Whether pipes between pumps are short enough to carry the fluid the machines along them use.
+pub mod pipes;
+
+/// What nuclear reactors make, and the heat exchangers, turbines, and fuel they need.
+pub mod nuclear;
+
+/// Whether belts and splitters make a balancer that is throughput unlimited and balanced.
+pub mod balancer;
+
+/// How fast crafting machines make and use items, with their modules and beacons.
+pub mod throughput;
+
+/// Which crafting machines and labs each beacon reaches, for checking beacon layouts.
+pub mod beacons;
+
+/// Whether the recipes of a blueprint have their machines in the right ratios.
+pub mod ratios;
+
+/// How fast labs research, and the science packs it takes to keep them going.
+pub mod science;
+
+/// A rough score of how much a blueprint slows the game down, for comparing designs.
+pub mod ups;
+
+/// The oldest game version that can paste a blueprint.
+pub mod version;
+
+/// How long a blueprint string is, and which parts of the blueprint take up the most of it.
+pub mod size;
@@ -0,0 +1,503 @@
+use core::fmt;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use crate::{
+    belt::{BeltGraph, Items, Lane, LaneGraph, NodeKind},
+    blueprint::Blueprint,
+    prototypes::PrototypeDb,
+};
+
+/// Flows closer than this, in items per second, count as equal.
+const TOLERANCE: f64 = 1e-6;
+/// The most combinations of inputs and outputs to try every one of.
+const MAX_COMBINATIONS: usize = 4096;
+
+#[derive(Debug, Clone, PartialEq)]
+/// A way a balancer falls short.
+pub enum BalancerIssue {
+    /// With only some inputs fed and some outputs taken from, less gets through than the belts
+    /// could carry.
+    Throughput {
+        /// The inputs fed at full speed, in order.
+        inputs: Vec<usize>,
+        /// The outputs taken from, in order. The others are blocked.
+        outputs: Vec<usize>,
+        /// The items per second that could get through.
+        expected: f64,
+        /// The most items per second that can, with splitters sending items wherever there is
+        /// room.
+        actual: f64,
+    },
+    /// With every input fed at full speed, an output gets more or less of an input's items than
+    /// the others.
+    Unbalanced {
+        /// The input node.
+        input: usize,
+        /// The output node.
+        output: usize,
+        /// The items per second of the input every output would get if balanced.
+        expected: f64,
+        /// The items per second of the input the output gets.
+        actual: f64,
+    },
+    /// With every lane of every input fed, a lane of an output gets more or less of an input's
+    /// items than the others.
+    LaneUnbalanced {
+        /// The input node.
+        input: usize,
+        /// The output node.
+        output: usize,
+        /// The lane of the output.
+        lane: Lane,
+        /// The items per second of the input every output lane would get if balanced.
+        expected: f64,
+        /// The items per second of the input the output lane gets.
+        actual: f64,
+    },
+}
+
+/// `nodes` written as a list.
+fn list(nodes: &[usize]) -> String {
+    nodes
+        .iter()
+        .map(usize::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl fmt::Display for BalancerIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BalancerIssue::Throughput {
+                inputs,
+                outputs,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "inputs {} to outputs {} move {actual:.2}/s, not {expected:.2}/s",
+                list(inputs),
+                list(outputs)
+            ),
+            BalancerIssue::Unbalanced {
+                input,
+                output,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "output {output} gets {actual:.2}/s from input {input}, not {expected:.2}/s"
+            ),
+            BalancerIssue::LaneUnbalanced {
+                input,
+                output,
+                lane,
+                expected,
+                actual,
+            } => {
+                let lane = match lane {
+                    Lane::Left => "left",
+                    Lane::Right => "right",
+                };
+                write!(
+                    f,
+                    "the {lane} lane of output {output} gets {actual:.2}/s from input {input}, \
+                     not {expected:.2}/s"
+                )
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// Whether the belts of a blueprint make a working balancer.
+pub struct BalancerReport {
+    /// The belts items are put onto, in order.
+    pub inputs: Vec<usize>,
+    /// The belts items are taken from, in order.
+    pub outputs: Vec<usize>,
+    /// Whether every combination of inputs and outputs was tried. Balancers with more than
+    /// 4096 combinations only have each input and output tried alone, and all of them together.
+    pub exhaustive: bool,
+    /// The ways it falls short, throughput first.
+    pub issues: Vec<BalancerIssue>,
+}
+
+/// Every nonempty subset of `nodes`, or if there are too many, each single node and all of them.
+fn subsets(nodes: &[usize], exhaustive: bool) -> Vec<Vec<usize>> {
+    if !exhaustive {
+        let mut subsets: Vec<Vec<usize>> = nodes.iter().map(|&node| vec![node]).collect();
+        if nodes.len() > 1 {
+            subsets.push(nodes.to_vec());
+        }
+        return subsets;
+    }
+    (1..1usize << nodes.len())
+        .map(|mask| {
+            nodes
+                .iter()
+                .enumerate()
+                .filter(|&(i, _)| mask & (1 << i) != 0)
+                .map(|(_, &node)| node)
+                .collect()
+        })
+        .collect()
+}
+
+impl BalancerReport {
+    /// Check the belts of `blueprint` as a balancer, with belt speeds from `db`.
+    ///
+    /// The inputs are the belts nothing feeds and the outputs the belts that lead nowhere. Returns
+    /// `None` if the blueprint isn't a balancer: if it has no splitters, or its belts aren't all
+    /// joined up.
+    ///
+    /// Throughput is checked as the most items that can flow from the inputs fed to the outputs
+    /// taken from, and balance by following the items of each input through the belts.
+    pub fn new(blueprint: &Blueprint, db: &PrototypeDb) -> Option<Self> {
+        let graph = BeltGraph::new(blueprint, db);
+        let belts = |nodes: Vec<usize>| -> Vec<usize> {
+            nodes
+                .into_iter()
+                .filter(|&node| {
+                    !matches!(
+                        graph.kind(node),
+                        Some(NodeKind::Container | NodeKind::LoaderInput | NodeKind::LoaderOutput)
+                    )
+                })
+                .collect()
+        };
+        let inputs = belts(graph.sources());
+        let outputs = belts(graph.sinks());
+        let has_splitter = graph
+            .nodes()
+            .any(|node| graph.kind(node) == Some(NodeKind::Splitter));
+        if inputs.is_empty() || outputs.is_empty() || !has_splitter || !joined_up(&graph) {
+            return None;
+        }
+
+        // The number of nonempty subsets of `nodes`, or `None` if too many to count
+        let combinations = |nodes: &[usize]| 1usize.checked_shl(nodes.len() as u32).map(|n| n - 1);
+        let exhaustive = combinations(&inputs)
+            .zip(combinations(&outputs))
+            .and_then(|(a, b)| a.checked_mul(b))
+            .is_some_and(|count| count <= MAX_COMBINATIONS);
+        let mut report = Self {
+            exhaustive,
+            ..Self::default()
+        };
+        let network = Capacities::new(&graph);
+
+        // Feed each input at full speed with items named after it, to follow them through
+        let fed = |inputs: &[usize]| -> BTreeMap<usize, Items> {
+            inputs
+                .iter()
+                .map(|&input| {
+                    let items = Items::from([(input.to_string(), graph.speed(input))]);
+                    (input, items)
+                })
+                .collect()
+        };
+        for fed_inputs in subsets(&inputs, report.exhaustive) {
+            for taken in subsets(&outputs, report.exhaustive) {
+                let supply: f64 = fed_inputs.iter().map(|&input| graph.speed(input)).sum();
+                let demand: f64 = taken.iter().map(|&output| graph.speed(output)).sum();
+                let expected = supply.min(demand);
+                let actual = network.max_flow(&graph, &fed_inputs, &taken);
+                if actual < expected - TOLERANCE {
+                    report.issues.push(BalancerIssue::Throughput {
+                        inputs: fed_inputs.clone(),
+                        outputs: taken,
+                        expected,
+                        actual,
+                    });
+                }
+            }
+        }
+
+        let flow = graph.flow(&fed(&inputs));
+        for &input in &inputs {
+            let name = input.to_string();
+            let got: Vec<f64> = outputs
+                .iter()
+                .map(|&output| {
+                    flow.items(output)
+                        .and_then(|items| items.get(&name))
+                        .copied()
+                        .unwrap_or_default()
+                })
+                .collect();
+            let expected = got.iter().sum::<f64>() / outputs.len() as f64;
+            for (&output, actual) in outputs.iter().zip(got) {
+                if (actual - expected).abs() > TOLERANCE {
+                    report.issues.push(BalancerIssue::Unbalanced {
+                        input,
+                        output,
+                        expected,
+                        actual,
+                    });
+                }
+            }
+        }
+
+        if report.is_balanced() {
+            report.check_lanes(&graph, &inputs, &outputs);
+        }
+
+        report.inputs = inputs;
+        report.outputs = outputs;
+        Some(report)
+    }
+
+    /// Whether every combination of inputs and outputs tried moves as much as the belts can.
+    pub fn is_throughput_unlimited(&self) -> bool {
+        !self
+            .issues
+            .iter()
+            .any(|issue| matches!(issue, BalancerIssue::Throughput { .. }))
+    }
+
+    /// Whether every output gets an equal share of every input, with all inputs fed.
+    pub fn is_balanced(&self) -> bool {
+        !self
+            .issues
+            .iter()
+            .any(|issue| matches!(issue, BalancerIssue::Unbalanced { .. }))
+    }
+
+    /// Whether every lane of every output gets an equal share of every input, with both lanes of
+    /// all inputs fed. Only checked for balancers that balance belts.
+    pub fn is_lane_balanced(&self) -> bool {
+        self.is_balanced()
+            && !self
+                .issues
+                .iter()
+                .any(|issue| matches!(issue, BalancerIssue::LaneUnbalanced { .. }))
+    }
+
+    /// Check every output lane gets an equal share of every input, following the lanes of `graph`.
+    fn check_lanes(&mut self, graph: &BeltGraph, inputs: &[usize], outputs: &[usize]) {
+        let lanes = LaneGraph::new(graph);
+        let fed: BTreeMap<(usize, Lane), Items> = inputs
+            .iter()
+            .flat_map(|&input| {
+                Lane::BOTH.map(|lane| {
+                    let items = Items::from([(input.to_string(), lanes.speed((input, lane)))]);
+                    ((input, lane), items)
+                })
+            })
+            .collect();
+        let flow = lanes.flow(&fed);
+        for &input in inputs {
+            let name = input.to_string();
+            let got: Vec<(usize, Lane, f64)> = outputs
+                .iter()
+                .flat_map(|&output| Lane::BOTH.map(|lane| (output, lane)))
+                .map(|(output, lane)| {
+                    let actual = flow
+                        .items((output, lane))
+                        .and_then(|items| items.get(&name))
+                        .copied()
+                        .unwrap_or_default();
+                    (output, lane, actual)
+                })
+                .collect();
+            let expected = got.iter().map(|&(_, _, actual)| actual).sum::<f64>() / got.len() as f64;
+            for (output, lane, actual) in got {
+                if (actual - expected).abs() > TOLERANCE {
+                    self.issues.push(BalancerIssue::LaneUnbalanced {
+                        input,
+                        output,
+                        lane,
+                        expected,
+                        actual,
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// The belts of a [BeltGraph] as a flow network, to find the most items that can get from some
+/// belts to others.
+///
+/// Each node is split into a vertex items enter and one they leave, with an edge between them
+/// carrying what the node can move. The two vertices past those are the source and sink.
+#[derive(Clone)]
+struct Capacities {
+    /// The vertex items enter each node at, the one they leave by being the next.
+    vertices: BTreeMap<usize, usize>,
+    /// The vertex each edge leads to and how much more it can carry. Edges come in pairs, the
+    /// second of each leading back the way the first came.
+    edges: Vec<(usize, f64)>,
+    /// The edges leaving each vertex.
+    leaving: Vec<Vec<usize>>,
+}
+
+impl Capacities {
+    /// The flow network of `graph`, without a source or sink.
+    fn new(graph: &BeltGraph) -> Self {
+        let vertices: BTreeMap<usize, usize> = graph
+            .nodes()
+            .enumerate()
+            .map(|(i, node)| (node, i * 2))
+            .collect();
+        let mut network = Self {
+            leaving: vec![Vec::new(); vertices.len() * 2 + 2],
+            vertices,
+            edges: Vec::new(),
+        };
+
+        for node in graph.nodes() {
+            let speed = graph.speed(node);
+            let splitter = graph.splitter(node);
+            let through = if splitter.is_some() {
+                speed * 2.0
+            } else {
+                speed
+            };
+            let vertex = network.vertices[&node];
+            network.edge(vertex, vertex + 1, through);
+
+            for &next in graph.next(node) {
+                // Splitters move no more than their speed through each half
+                let halves = |sides: [Option<usize>; 2], other| {
+                    sides
+                        .iter()
+                        .filter(|&&side| side == Some(other))
+                        .count()
+                        .max(1) as f64
+                };
+                let mut capacity = f64::INFINITY;
+                if let Some(splitter) = splitter {
+                    capacity = capacity.min(speed * halves(splitter.outputs, next));
+                }
+                if let Some(splitter) = graph.splitter(next) {
+                    capacity = capacity.min(graph.speed(next) * halves(splitter.inputs, node));
+                }
+                network.edge(vertex + 1, network.vertices[&next], capacity);
+            }
+        }
+        network
+    }
+
+    /// Add an edge carrying up to `capacity` from one vertex to another.
+    fn edge(&mut self, from: usize, to: usize, capacity: f64) {
+        self.leaving[from].push(self.edges.len());
+        self.edges.push((to, capacity));
+        self.leaving[to].push(self.edges.len());
+        self.edges.push((from, 0.0));
+    }
+
+    /// The most items per second that can get from the `inputs` of `graph`, each fed as fast
+    /// as it moves items, to the `outputs`.
+    fn max_flow(&self, graph: &BeltGraph, inputs: &[usize], outputs: &[usize]) -> f64 {
+        let mut network = self.clone();
+        let (source, sink) = (network.leaving.len() - 2, network.leaving.len() - 1);
+        for &input in inputs {
+            network.edge(source, network.vertices[&input], graph.speed(input));
+        }
+        for &output in outputs {
+            network.edge(network.vertices[&output] + 1, sink, f64::INFINITY);
+        }
+
+        // Push items along the shortest path with room left until there is none
+        let mut total = 0.0;
+        loop {
+            let mut reached_by: Vec<Option<usize>> = vec![None; network.leaving.len()];
+            let mut queue = VecDeque::from([source]);
+            while let Some(vertex) = queue.pop_front() {
+                for &edge in &network.leaving[vertex] {
+                    let (to, room) = network.edges[edge];
+                    if room > TOLERANCE && to != source && reached_by[to].is_none() {
+                        reached_by[to] = Some(edge);
+                        queue.push_back(to);
+                    }
+                }
+            }
+            if reached_by[sink].is_none() {
+                return total;
+            }
+
+            let mut path = Vec::new();
+            let mut vertex = sink;
+            while let Some(edge) = reached_by[vertex] {
+                path.push(edge);
+                vertex = network.edges[edge ^ 1].0;
+            }
+            let pushed = path
+                .iter()
+                .map(|&edge| network.edges[edge].1)
+                .fold(f64::INFINITY, f64::min);
+            for edge in path {
+                network.edges[edge].1 -= pushed;
+                network.edges[edge ^ 1].1 += pushed;
+            }
+            total += pushed;
+        }
+    }
+}
+
+/// Whether every node of `graph` is joined to every other, ignoring which way items move.
+fn joined_up(graph: &BeltGraph) -> bool {
+    let Some(start) = graph.nodes().next() else {
+        return false;
+    };
+    let mut seen = BTreeSet::from([start]);
+    let mut queue = vec![start];
+    while let Some(node) = queue.pop() {
+        for &neighbor in graph.next(node).iter().chain(graph.previous(node)) {
+            if seen.insert(neighbor) {
+                queue.push(neighbor);
+            }
+        }
+    }
+    seen.len() == graph.nodes().count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bp;
+
+    #[test]
+    fn one_splitter_balances_two_belts() {
+        let db = PrototypeDb::vanilla();
+        let blueprint = bp! {
+            "transport-belt" @ (0, 2);
+            "transport-belt" @ (1, 2);
+            "splitter" @ (0, 1);
+            "transport-belt" @ (0, 0);
+            "transport-belt" @ (1, 0);
+        }
+        .build_with(db)
+        .unwrap();
+        let report = BalancerReport::new(&blueprint, db).unwrap();
+        assert_eq!((report.inputs.len(), report.outputs.len()), (2, 2));
+        assert!(report.exhaustive);
+        assert_eq!(report.issues, []);
+    }
+
+    #[test]
+    fn slow_belts_limit_throughput() {
+        let db = PrototypeDb::vanilla();
+        let blueprint = bp! {
+            "express-transport-belt" @ (0, 3);
+            "express-splitter" @ (0, 2);
+            "transport-belt" @ (0, 1);
+            "express-transport-belt" @ (1, 1);
+            "express-transport-belt" @ (0, 0);
+            "express-transport-belt" @ (1, 0);
+        }
+        .build_with(db)
+        .unwrap();
+        let report = BalancerReport::new(&blueprint, db).unwrap();
+        let (input, left) = (report.inputs[0], report.outputs[0]);
+        assert!(report.issues.contains(&BalancerIssue::Throughput {
+            inputs: vec![input],
+            outputs: vec![left],
+            expected: 45.0,
+            actual: 15.0,
+        }));
+        assert!(!report.is_throughput_unlimited());
+    }
+}
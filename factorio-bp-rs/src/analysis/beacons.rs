@@ -0,0 +1,80 @@
+use std::collections::BTreeMap;
+
+use crate::{blueprint::Blueprint, effects::Effects, prototypes::PrototypeDb};
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// Which crafting machines and labs each beacon of a blueprint reaches.
+pub struct BeaconReport {
+    /// Every beacon, with the entity_numbers of the machines it reaches, in order.
+    pub beacons: BTreeMap<usize, Vec<usize>>,
+    /// Every crafting machine and lab, with the entity_numbers of the beacons reaching it, in order.
+    pub machines: BTreeMap<usize, Vec<usize>>,
+}
+
+impl BeaconReport {
+    /// Find the crafting machines and labs inside the effect area of each beacon in `blueprint`, with
+    /// sizes and effect areas from `db`.
+    pub fn new(blueprint: &Blueprint, db: &PrototypeDb) -> Self {
+        let mut beacons: BTreeMap<usize, Vec<usize>> = blueprint
+            .entities
+            .iter()
+            .flatten()
+            .filter(|entity| entity.prototype(db).is_some_and(|p| p.beacon.is_some()))
+            .map(|entity| (entity.entity_number.get(), Vec::new()))
+            .collect();
+        let mut machines = BTreeMap::new();
+        for (machine, effects) in Effects::of_machines(blueprint, db) {
+            for &beacon in &effects.beacons {
+                beacons.entry(beacon).or_default().push(machine);
+            }
+            machines.insert(machine, effects.beacons);
+        }
+        Self { beacons, machines }
+    }
+
+    /// The entity_numbers of the beacons that reach no machine, in order.
+    pub fn unused(&self) -> Vec<usize> {
+        self.beacons
+            .iter()
+            .filter(|(_, machines)| machines.is_empty())
+            .map(|(&beacon, _)| beacon)
+            .collect()
+    }
+
+    /// The number of machines reached by each number of beacons.
+    pub fn counts(&self) -> BTreeMap<usize, usize> {
+        let mut counts = BTreeMap::new();
+        for beacons in self.machines.values() {
+            *counts.entry(beacons.len()).or_default() += 1;
+        }
+        counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bp;
+
+    #[test]
+    fn beacons_and_machines_list_each_other() {
+        let db = PrototypeDb::vanilla();
+        let blueprint = bp! {
+            "assembling-machine-2" @ (0, 0);
+            "assembling-machine-2" @ (6, 0);
+            "beacon" @ (3, 0);
+            "beacon" @ (3, 3);
+            "beacon" @ (30, 30);
+            "assembling-machine-1" @ (20, 0);
+        }
+        .build_with(db)
+        .unwrap();
+        let report = BeaconReport::new(&blueprint, db);
+
+        assert_eq!(report.beacons[&3], [1, 2]);
+        assert_eq!(report.machines[&1], [3, 4]);
+        assert!(report.machines[&6].is_empty());
+        assert_eq!(report.unused(), [5]);
+        assert_eq!(report.counts(), BTreeMap::from([(0, 1), (2, 2)]));
+    }
+}
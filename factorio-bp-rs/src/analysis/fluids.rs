@@ -0,0 +1,148 @@
+use core::fmt;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use crate::{
+    blueprint::Blueprint,
+    fluid::{FluidBoxId, FluidGraph, FluidNetwork},
+    prototypes::{ProductionType, PrototypeDb},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A fluid network, with the fluids it holds.
+pub struct NetworkFluids {
+    /// The fluid boxes of the network.
+    pub network: FluidNetwork,
+    /// The fluids its fluid boxes are fixed to hold, by filters or recipes. Empty if none are,
+    /// as for pipes joined to nothing else.
+    pub fluids: BTreeSet<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A fluid network that doesn't work as built.
+pub enum FluidIssue {
+    /// A network joining fluid boxes fixed to different fluids, which the game won't build.
+    Mixed {
+        /// The fluids, in order.
+        fluids: BTreeSet<String>,
+        /// The entities on either side of where the fluids meet, in order.
+        junctions: BTreeSet<usize>,
+    },
+    /// A crafting machine whose recipe takes a fluid through an input connected to nothing.
+    UnconnectedInput {
+        /// The entity_number of the machine.
+        entity: usize,
+        /// The fluid the input takes.
+        fluid: String,
+    },
+}
+
+impl fmt::Display for FluidIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FluidIssue::Mixed { fluids, junctions } => {
+                let fluids: Vec<_> = fluids.iter().map(String::as_str).collect();
+                let junctions: Vec<_> = junctions.iter().map(usize::to_string).collect();
+                write!(
+                    f,
+                    "{} mix where entities {} meet",
+                    fluids.join(", "),
+                    junctions.join(", ")
+                )
+            }
+            FluidIssue::UnconnectedInput { entity, fluid } => {
+                write!(f, "the {fluid} input of entity {entity} isn't connected")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+/// The fluid networks of a blueprint and what is wrong with them.
+pub struct FluidReport {
+    /// Every network, ordered by its lowest fluid box.
+    pub networks: Vec<NetworkFluids>,
+    /// Mixed fluids, then unconnected inputs in entity order.
+    pub issues: Vec<FluidIssue>,
+}
+
+/// The entities where the fluids of `network` meet: spreading each fluid out from the boxes
+/// fixed to it, the boxes where one fluid runs into another.
+fn junctions(graph: &FluidGraph, network: &FluidNetwork) -> BTreeSet<usize> {
+    let mut reached: BTreeMap<FluidBoxId, &str> = BTreeMap::new();
+    let mut queue = VecDeque::new();
+    for &fluid_box in &network.fluid_boxes {
+        if let Some(fluid) = graph.fluid(fluid_box) {
+            reached.insert(fluid_box, fluid);
+            queue.push_back(fluid_box);
+        }
+    }
+
+    let mut junctions = BTreeSet::new();
+    while let Some(next) = queue.pop_front() {
+        let fluid = reached[&next];
+        for &neighbor in graph.neighbors(next) {
+            match reached.get(&neighbor) {
+                None => {
+                    reached.insert(neighbor, fluid);
+                    queue.push_back(neighbor);
+                }
+                Some(&other) if other != fluid => {
+                    junctions.insert(next.entity_number);
+                    junctions.insert(neighbor.entity_number);
+                }
+                Some(_) => {}
+            }
+        }
+    }
+    junctions
+}
+
+impl FluidReport {
+    /// Find the fluid networks of `blueprint`, with fluid boxes and recipes from `db`.
+    pub fn new(blueprint: &Blueprint, db: &PrototypeDb) -> Self {
+        let graph = FluidGraph::new(blueprint, db);
+        let mut report = Self::default();
+
+        for network in graph.networks() {
+            let fluids: BTreeSet<String> = graph
+                .fluids(&network)
+                .into_iter()
+                .map(str::to_owned)
+                .collect();
+            if fluids.len() > 1 {
+                report.issues.push(FluidIssue::Mixed {
+                    fluids: fluids.clone(),
+                    junctions: junctions(&graph, &network),
+                });
+            }
+            report.networks.push(NetworkFluids { network, fluids });
+        }
+
+        for entity in blueprint.entities.iter().flatten() {
+            let Some(prototype) = entity.prototype(db) else {
+                continue;
+            };
+            let number = entity.entity_number.get();
+            for (index, fluid_box) in prototype.fluid_boxes.iter().enumerate() {
+                let id = FluidBoxId {
+                    entity_number: number,
+                    index,
+                };
+                let Some(fluid) = graph.fluid(id) else {
+                    continue;
+                };
+                let connected = graph
+                    .neighbors(id)
+                    .iter()
+                    .any(|neighbor| neighbor.entity_number != number);
+                if fluid_box.production_type == ProductionType::Input && !connected {
+                    report.issues.push(FluidIssue::UnconnectedInput {
+                        entity: number,
+                        fluid: fluid.to_owned(),
+                    });
+                }
+            }
+        }
+        report
+    }
+}
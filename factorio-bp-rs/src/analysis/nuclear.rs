@@ -0,0 +1,194 @@
+use std::collections::{btree_map::Entry, BTreeMap, VecDeque};
+
+use crate::{
+    blueprint::Blueprint,
+    heat::HeatGraph,
+    prototypes::{PowerSource, PrototypeDb},
+};
+
+/// The extra heat a reactor makes for each working reactor next to it, as a fraction of its own.
+pub const NEIGHBOUR_BONUS: f64 = 1.0;
+/// The heat a heat exchanger can turn into steam, in watts.
+pub const HEAT_EXCHANGER_POWER: f64 = 10e6;
+/// The electricity a steam turbine makes from 500° steam, in watts.
+pub const TURBINE_POWER: f64 = 5.82e6;
+/// The energy in a uranium fuel cell, in joules.
+pub const FUEL_CELL_ENERGY: f64 = 8e9;
+/// How many heat pipes heat can get through before it is too cool for a heat exchanger. Each
+/// heat pipe loses a degree, and reactors at their 1000° limit have 500° to lose.
+pub const HEAT_PIPE_REACH: usize = 500;
+
+#[derive(Debug, Clone, PartialEq)]
+/// A nuclear reactor and the reactors it touches.
+pub struct Reactor {
+    /// The entity_number of the reactor.
+    pub entity_number: usize,
+    /// The heat it makes when fuelled, before neighbour bonuses, in watts.
+    pub base_output: f64,
+    /// The entity_numbers of the reactors next to it, which give it a neighbour bonus.
+    pub neighbours: Vec<usize>,
+}
+
+impl Reactor {
+    /// The heat it makes with every neighbour fuelled, in watts.
+    pub fn output(&self) -> f64 {
+        self.base_output * (1.0 + NEIGHBOUR_BONUS * self.neighbours.len() as f64)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// The nuclear reactors of a blueprint, and what they need to run at full power.
+pub struct NuclearReport {
+    /// Every reactor, in entity order.
+    pub reactors: Vec<Reactor>,
+    /// The entity_numbers of the heat exchangers that heat pipes join to a reactor within
+    /// [HEAT_PIPE_REACH].
+    pub heat_exchangers: Vec<usize>,
+    /// The entity_numbers of heat exchangers not joined to any reactor, or too far from one.
+    pub out_of_reach: Vec<usize>,
+    /// The number of steam turbines.
+    pub turbines: usize,
+}
+
+impl NuclearReport {
+    /// Find the reactors of `blueprint`, and how they are joined to heat exchangers, with heat
+    /// connections from `db`.
+    ///
+    /// Reactors are assumed to be kept fuelled, so every neighbour gives its bonus.
+    pub fn new(blueprint: &Blueprint, db: &PrototypeDb) -> Self {
+        let mut report = Self::default();
+        let mut heat_pipes = Vec::new();
+        let mut exchangers = Vec::new();
+        for entity in blueprint.entities.iter().flatten() {
+            let Some(prototype) = entity.prototype(db) else {
+                continue;
+            };
+            let number = entity.entity_number.get();
+            if let Some(PowerSource::Reactor(output)) = prototype.power_source {
+                report.reactors.push(Reactor {
+                    entity_number: number,
+                    base_output: output,
+                    neighbours: Vec::new(),
+                });
+            }
+            match entity.name.as_str() {
+                "heat-pipe" => heat_pipes.push(number),
+                "heat-exchanger" => exchangers.push(number),
+                "steam-turbine" => report.turbines += 1,
+                _ => {}
+            }
+        }
+        let graph = HeatGraph::new(blueprint, db);
+
+        let is_reactor = |number: usize| {
+            report
+                .reactors
+                .binary_search_by_key(&number, |reactor| reactor.entity_number)
+                .is_ok()
+        };
+        let neighbours: Vec<Vec<usize>> = report
+            .reactors
+            .iter()
+            .map(|reactor| {
+                graph
+                    .neighbors(reactor.entity_number)
+                    .iter()
+                    .copied()
+                    .filter(|&other| is_reactor(other))
+                    .collect()
+            })
+            .collect();
+        for (reactor, neighbours) in report.reactors.iter_mut().zip(neighbours) {
+            reactor.neighbours = neighbours;
+        }
+
+        // Heat pipes passed through on the shortest way from any reactor
+        let mut distance: BTreeMap<usize, usize> = BTreeMap::new();
+        let mut queue = VecDeque::new();
+        for reactor in &report.reactors {
+            distance.insert(reactor.entity_number, 0);
+            queue.push_back(reactor.entity_number);
+        }
+        while let Some(next) = queue.pop_front() {
+            let through = distance[&next] + usize::from(heat_pipes.binary_search(&next).is_ok());
+            for &neighbour in graph.neighbors(next) {
+                if let Entry::Vacant(entry) = distance.entry(neighbour) {
+                    entry.insert(through);
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+        for exchanger in exchangers {
+            match distance.get(&exchanger) {
+                Some(&pipes) if pipes <= HEAT_PIPE_REACH => report.heat_exchangers.push(exchanger),
+                _ => report.out_of_reach.push(exchanger),
+            }
+        }
+        report
+    }
+
+    /// The heat every reactor together makes, in watts.
+    pub fn heat_output(&self) -> f64 {
+        self.reactors.iter().map(Reactor::output).sum()
+    }
+
+    /// The heat exchangers needed to use all the heat, rounded up.
+    pub fn heat_exchangers_needed(&self) -> usize {
+        (self.heat_output() / HEAT_EXCHANGER_POWER).ceil() as usize
+    }
+
+    /// The steam turbines needed to use all the steam the heat makes, rounded up.
+    pub fn turbines_needed(&self) -> usize {
+        (self.heat_output() / TURBINE_POWER).ceil() as usize
+    }
+
+    /// The electricity the setup can make, in watts, limited by the reactors, the heat
+    /// exchangers in reach, or the turbines.
+    pub fn output(&self) -> f64 {
+        self.heat_output()
+            .min(self.heat_exchangers.len() as f64 * HEAT_EXCHANGER_POWER)
+            .min(self.turbines as f64 * TURBINE_POWER)
+    }
+
+    /// The fuel cells every reactor together burns, per minute. The neighbour bonus gives
+    /// more heat, not faster burning.
+    pub fn fuel_cells_per_minute(&self) -> f64 {
+        self.reactors
+            .iter()
+            .map(|reactor| reactor.base_output / FUEL_CELL_ENERGY * 60.0)
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bp;
+
+    #[test]
+    fn neighbouring_reactors_double_their_heat() {
+        let db = PrototypeDb::vanilla();
+        let blueprint = bp! {
+            "nuclear-reactor" @ (0, 0);
+            "nuclear-reactor" @ (5, 0);
+            "heat-pipe" @ (2, 5);
+            "heat-exchanger" @ (1, 6) facing South;
+            "heat-exchanger" @ (20, 20);
+            "steam-turbine" @ (30, 0);
+        }
+        .build_with(db)
+        .unwrap();
+        let report = NuclearReport::new(&blueprint, db);
+
+        assert_eq!(report.reactors.len(), 2);
+        assert_eq!(report.reactors[0].neighbours, [2]);
+        assert_eq!(report.reactors[1].neighbours, [1]);
+        assert_eq!(report.heat_output(), 160e6);
+        assert_eq!(report.heat_exchangers, [4]);
+        assert_eq!(report.out_of_reach, [5]);
+        assert_eq!(report.heat_exchangers_needed(), 16);
+        assert_eq!(report.turbines_needed(), 28);
+        assert_eq!(report.output(), TURBINE_POWER);
+        assert_eq!(report.fuel_cells_per_minute(), 0.6);
+    }
+}
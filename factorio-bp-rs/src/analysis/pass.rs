@@ -0,0 +1,204 @@
+use std::{
+    cell::OnceCell,
+    collections::{BTreeMap, HashMap},
+};
+
+use crate::{
+    blueprint::{Blueprint, Entity, Schedule, Tile},
+    circuit::{CircuitGraph, CircuitWire},
+    effects::Effects,
+    prototypes::PrototypeDb,
+};
+
+/// An analysis fed the parts of a blueprint one at a time by [run_passes], so that several of
+/// them can share one walk over the blueprint and the [PassContext] built for it.
+///
+/// Every method does nothing unless overridden, so a pass only implements what it looks at.
+pub trait AnalysisPass {
+    /// Look at an entity. Entities come in the order the blueprint lists them.
+    fn entity(&mut self, _entity: &Entity, _context: &PassContext) {}
+
+    /// Look at a tile.
+    fn tile(&mut self, _tile: &Tile, _context: &PassContext) {}
+
+    /// Look at a red or green wire, from 2.0 `wires` or older per-entity `connections`.
+    fn wire(&mut self, _wire: &CircuitWire, _context: &PassContext) {}
+
+    /// Look at a train schedule.
+    fn schedule(&mut self, _schedule: &Schedule, _context: &PassContext) {}
+
+    /// Finish up once every part of the blueprint has been seen.
+    fn finish(&mut self, _context: &PassContext) {}
+}
+
+/// What the passes run over a blueprint share: the blueprint, the prototypes, and what is worked
+/// out from them once rather than by every pass.
+pub struct PassContext<'a> {
+    /// The blueprint being walked.
+    pub blueprint: &'a Blueprint,
+    /// The prototypes of the blueprint's entities.
+    pub db: &'a PrototypeDb,
+    /// The blueprint's red and green wires.
+    pub circuits: CircuitGraph,
+    entities: BTreeMap<usize, &'a Entity>,
+    occupied: HashMap<(i64, i64), Vec<usize>>,
+    effects: OnceCell<BTreeMap<usize, Effects>>,
+}
+
+impl<'a> PassContext<'a> {
+    /// Index the entities and wires of `blueprint`, with entity sizes from `db`.
+    pub fn new(blueprint: &'a Blueprint, db: &'a PrototypeDb) -> Self {
+        let mut entities = BTreeMap::new();
+        let mut occupied: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+        for entity in blueprint.entities.iter().flatten() {
+            let number = entity.entity_number.get();
+            entities.insert(number, entity);
+            match entity.footprint(db) {
+                Some(bounds) => {
+                    for x in bounds.min_x.round() as i64..bounds.max_x.round() as i64 {
+                        for y in bounds.min_y.round() as i64..bounds.max_y.round() as i64 {
+                            occupied.entry((x, y)).or_default().push(number);
+                        }
+                    }
+                }
+                // Entities `db` doesn't know are taken to fill the tile they are centered on
+                None => {
+                    let tile = (
+                        entity.position.x.floor() as i64,
+                        entity.position.y.floor() as i64,
+                    );
+                    occupied.entry(tile).or_default().push(number);
+                }
+            }
+        }
+
+        Self {
+            blueprint,
+            db,
+            circuits: CircuitGraph::new(blueprint),
+            entities,
+            occupied,
+            effects: OnceCell::new(),
+        }
+    }
+
+    /// The entity with this entity_number.
+    pub fn entity(&self, entity_number: usize) -> Option<&'a Entity> {
+        self.entities.get(&entity_number).copied()
+    }
+
+    /// The entities covering the tile with its top-left corner at `(x, y)`.
+    pub fn entities_at(&self, x: i64, y: i64) -> impl Iterator<Item = &'a Entity> + '_ {
+        self.occupied
+            .get(&(x, y))
+            .into_iter()
+            .flatten()
+            .filter_map(|&number| self.entity(number))
+    }
+
+    /// The effects of every crafting machine and lab, as [Effects::of_machines] finds them.
+    /// Worked out the first time a pass asks.
+    pub fn machine_effects(&self) -> &BTreeMap<usize, Effects> {
+        self.effects
+            .get_or_init(|| Effects::of_machines(self.blueprint, self.db))
+    }
+}
+
+/// Walk `blueprint` once, feeding its entities, tiles, wires, and schedules to every pass in
+/// turn, then finish each pass.
+pub fn run_passes(blueprint: &Blueprint, db: &PrototypeDb, passes: &mut [&mut dyn AnalysisPass]) {
+    let context = PassContext::new(blueprint, db);
+    for entity in blueprint.entities.iter().flatten() {
+        for pass in passes.iter_mut() {
+            pass.entity(entity, &context);
+        }
+    }
+    for tile in blueprint.tiles.iter().flatten() {
+        for pass in passes.iter_mut() {
+            pass.tile(tile, &context);
+        }
+    }
+    for wire in context.circuits.wires() {
+        for pass in passes.iter_mut() {
+            pass.wire(wire, &context);
+        }
+    }
+    for schedule in blueprint.schedules.iter().flatten() {
+        for pass in passes.iter_mut() {
+            pass.schedule(schedule, &context);
+        }
+    }
+    for pass in passes.iter_mut() {
+        pass.finish(&context);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::num::NonZeroUsize;
+
+    use super::*;
+    use crate::blueprint::{BlueprintBuilder, EntityBuilder, Wire, WireConnectorId};
+
+    #[derive(Default)]
+    /// A pass writing down what it is fed, in order.
+    struct Log(Vec<String>);
+
+    impl AnalysisPass for Log {
+        fn entity(&mut self, entity: &Entity, context: &PassContext) {
+            let (x, y) = (entity.position.x as i64, entity.position.y as i64);
+            let covering = context.entities_at(x, y).count();
+            self.0.push(format!("{} covering {covering}", entity.name));
+        }
+
+        fn tile(&mut self, tile: &Tile, _context: &PassContext) {
+            self.0.push(tile.name.clone());
+        }
+
+        fn wire(&mut self, wire: &CircuitWire, _context: &PassContext) {
+            self.0.push(format!("{:?} wire", wire.color));
+        }
+
+        fn finish(&mut self, _context: &PassContext) {
+            self.0.push("finish".to_owned());
+        }
+    }
+
+    #[test]
+    fn every_pass_sees_each_part_in_one_walk() {
+        let db = PrototypeDb::vanilla();
+        let number = |n| NonZeroUsize::new(n).unwrap();
+        let blueprint = BlueprintBuilder::new()
+            .entity(EntityBuilder::new("iron-chest").at_tile(0, 0))
+            .entity(EntityBuilder::new("assembling-machine-1").at_tile(2, 0))
+            .tile("stone-path", 0, 4)
+            .wire(Wire {
+                source_entity: number(1),
+                source_connector: WireConnectorId::CircuitRed,
+                target_entity: number(2),
+                target_connector: WireConnectorId::CircuitRed,
+            })
+            .build_with(db)
+            .unwrap();
+
+        let (mut first, mut second) = (Log::default(), Log::default());
+        run_passes(&blueprint, db, &mut [&mut first, &mut second]);
+        let expected = [
+            "iron-chest covering 1",
+            "assembling-machine-1 covering 1",
+            "stone-path",
+            "Red wire",
+            "finish",
+        ];
+        assert_eq!(first.0, expected);
+        assert_eq!(second.0, expected);
+
+        let context = PassContext::new(&blueprint, db);
+        assert_eq!(
+            context.entities_at(4, 2).next().unwrap().name,
+            "assembling-machine-1"
+        );
+        assert_eq!(context.entities_at(1, 0).count(), 0);
+        assert!(context.machine_effects().contains_key(&2));
+    }
+}
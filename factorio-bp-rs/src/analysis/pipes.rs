@@ -0,0 +1,438 @@
+use core::fmt;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use crate::{
+    analysis::throughput::ThroughputReport,
+    blueprint::{Blueprint, Entity, InfinityPipeMode},
+    fluid::{FluidBoxId, FluidGraph},
+    prototypes::{ProductionType, PrototypeDb},
+};
+
+/// Fluid a pump or offshore pump moves, per second.
+const PUMP_RATE: f64 = 1200.0;
+
+/// The fluid per second a pipeline of so many entities carries, as measured in game. Rates for
+/// lengths in between are interpolated, and longer pipelines fall off in proportion to length.
+const PIPE_FLOW: &[(usize, f64)] = &[
+    (1, 6000.0),
+    (2, 3000.0),
+    (3, 3000.0),
+    (7, 2000.0),
+    (12, 1500.0),
+    (17, 1200.0),
+    (20, 1090.0),
+    (30, 1000.0),
+    (150, 1000.0),
+    (200, 960.0),
+    (261, 900.0),
+    (300, 800.0),
+    (400, 600.0),
+    (500, 480.0),
+    (600, 400.0),
+    (800, 300.0),
+    (1000, 240.0),
+];
+
+/// The fluid per second a pipeline `length` entities long carries.
+pub fn pipe_flow(length: usize) -> f64 {
+    let length = length.max(1);
+    let mut previous = PIPE_FLOW[0];
+    for &(to, flow) in PIPE_FLOW {
+        if length <= to {
+            let (from, from_flow) = previous;
+            if to == from {
+                return flow;
+            }
+            let t = (length - from) as f64 / (to - from) as f64;
+            return from_flow + (flow - from_flow) * t;
+        }
+        previous = (to, flow);
+    }
+    let (to, flow) = previous;
+    flow * to as f64 / length as f64
+}
+
+/// Whether `entity` is an infinity pipe set to make fluid.
+fn makes_fluid(entity: &Entity) -> bool {
+    entity
+        .infinity_pipe_settings()
+        .is_some_and(|settings| settings.mode.unwrap_or_default() != InfinityPipeMode::Remove)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Fluid boxes joined by pipes between pumps, which fluid has to flow through on its own.
+pub struct PipeSegment {
+    /// The fluid boxes of the segment, in order.
+    pub fluid_boxes: Vec<FluidBoxId>,
+    /// The fluid the segment carries, or `None` if nothing in it is fixed to one.
+    pub fluid: Option<String>,
+    /// The length of the pipeline in entities, from where fluid enters the segment to the
+    /// machine or pump farthest from it that takes fluid out.
+    pub length: usize,
+    /// The entity_number of that machine or pump.
+    pub farthest: usize,
+    /// The fluid per second a pipeline of that length carries.
+    pub capacity: f64,
+    /// The fluid per second the machines and pumps taking from the segment use.
+    pub needed: f64,
+}
+
+impl PipeSegment {
+    /// Whether the segment carries less than the machines along it use.
+    pub fn is_short(&self) -> bool {
+        self.needed > self.capacity
+    }
+}
+
+impl fmt::Display for PipeSegment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            length,
+            farthest,
+            capacity,
+            needed,
+            ..
+        } = self;
+        let fluid = self.fluid.as_deref().unwrap_or("fluid");
+        write!(
+            f,
+            "a pipeline of {length} entities to entity {farthest} carries {capacity:.0}/s of \
+             {fluid}, but {needed:.0}/s is used along it"
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// Whether the pipes of a blueprint are short enough to carry the fluid its crafting machines
+/// use, assuming they all work at full speed.
+pub struct PipeReport {
+    /// Every segment a machine or pump takes fluid from, ordered by their lowest fluid box.
+    pub segments: Vec<PipeSegment>,
+}
+
+impl PipeReport {
+    /// Split the fluid networks of `blueprint` at its pumps, and work out what each part carries
+    /// and needs to, with pipe connections, recipes, and crafting speeds from `db`.
+    ///
+    /// Fluid enters a segment at offshore pumps, pumps, infinity pipes, and machines making
+    /// fluids. If there are none, it is taken to be brought in from outside the blueprint through
+    /// whichever end of a pipeline makes for the shortest one.
+    pub fn new(blueprint: &Blueprint, db: &PrototypeDb) -> Self {
+        let graph = FluidGraph::new(blueprint, db);
+        let grid = blueprint.occupancy_grid(db);
+        let throughput = ThroughputReport::new(blueprint, db);
+
+        // What the crafting machines take in and give out through each fluid box
+        let mut rates: BTreeMap<FluidBoxId, f64> = BTreeMap::new();
+        let mut outputs = BTreeSet::new();
+        let mut open = BTreeSet::new();
+        // Each pump's fluid box, with the fluid box on its output side
+        let mut pumps = BTreeMap::new();
+        // Offshore pumps, and infinity pipes making fluid
+        let mut offshore_pumps = BTreeSet::new();
+        let machines: BTreeMap<usize, _> = throughput
+            .machines
+            .iter()
+            .map(|machine| (machine.entity_number, machine))
+            .collect();
+        for entity in blueprint.entities.iter().flatten() {
+            let Some(prototype) = entity.prototype(db) else {
+                continue;
+            };
+            let number = entity.entity_number.get();
+            for (index, fluid_box) in prototype.fluid_boxes.iter().enumerate() {
+                let id = FluidBoxId {
+                    entity_number: number,
+                    index,
+                };
+                let neighbors = graph.neighbors(id);
+                match (entity.name.as_str(), fluid_box.production_type) {
+                    ("pump", _) => {
+                        // Pumps push out through their first connection
+                        let Some(connection) = fluid_box.connections.first() else {
+                            continue;
+                        };
+                        let connection = connection.of_entity(entity);
+                        let (dx, dy) = connection.direction.tile_offset().unwrap_or_default();
+                        let output = grid.get(
+                            (entity.position.x + connection.x).floor() as i64 + dx as i64,
+                            (entity.position.y + connection.y).floor() as i64 + dy as i64,
+                        );
+                        let output = neighbors.iter().copied().find(|neighbor| {
+                            Some(neighbor.entity_number) == output.map(|output| output.get())
+                        });
+                        pumps.insert(id, output);
+                    }
+                    ("offshore-pump", _) => {
+                        offshore_pumps.insert(id);
+                    }
+                    ("infinity-pipe", _) if makes_fluid(entity) => {
+                        offshore_pumps.insert(id);
+                    }
+                    (_, ProductionType::None) => {
+                        // The ends of pipelines, where fluid could be brought in
+                        if neighbors.len() <= 1 && fluid_box.filter.is_none() {
+                            open.insert(id);
+                        }
+                    }
+                    (_, production_type) => {
+                        let (Some(machine), Some(fluid)) = (machines.get(&number), graph.fluid(id))
+                        else {
+                            continue;
+                        };
+                        let (items, is_output) = match production_type {
+                            ProductionType::Output => (&machine.products, true),
+                            _ => (&machine.ingredients, false),
+                        };
+                        if let Some(&rate) = items.get(fluid) {
+                            rates.insert(id, rate);
+                            if is_output {
+                                outputs.insert(id);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        // Split the networks at pumps
+        let mut segment_of = BTreeMap::new();
+        let mut segments: Vec<Vec<FluidBoxId>> = Vec::new();
+        for start in graph.fluid_boxes() {
+            if pumps.contains_key(&start) || segment_of.contains_key(&start) {
+                continue;
+            }
+            let mut seen = BTreeSet::from([start]);
+            let mut queue = vec![start];
+            while let Some(next) = queue.pop() {
+                for &neighbor in graph.neighbors(next) {
+                    if !pumps.contains_key(&neighbor) && seen.insert(neighbor) {
+                        queue.push(neighbor);
+                    }
+                }
+            }
+            for &fluid_box in &seen {
+                segment_of.insert(fluid_box, segments.len());
+            }
+            segments.push(seen.into_iter().collect());
+        }
+
+        // The pumps each segment feeds into, and the segment on their output side
+        let mut feeds: BTreeMap<usize, Vec<Option<usize>>> = BTreeMap::new();
+        for (&pump, &output) in &pumps {
+            let fed = output.and_then(|output| segment_of.get(&output).copied());
+            for input in graph.neighbors(pump) {
+                if Some(*input) != output {
+                    if let Some(&segment) = segment_of.get(input) {
+                        feeds.entry(segment).or_default().push(fed);
+                    }
+                }
+            }
+        }
+        // Where fluid enters each segment from a pump
+        let pump_outputs: BTreeSet<FluidBoxId> = pumps.values().flatten().copied().collect();
+
+        let mut needed = BTreeMap::new();
+        for segment in 0..segments.len() {
+            segment_need(
+                segment,
+                &segments,
+                &rates,
+                &outputs,
+                &feeds,
+                &mut needed,
+                &mut BTreeSet::new(),
+            );
+        }
+        // Segments of plain pipe carry whatever the segments they feed take
+        let fixed_fluid = |segment: usize| {
+            segments[segment]
+                .iter()
+                .find_map(|&fluid_box| graph.fluid(fluid_box))
+        };
+        let fluid = |segment: usize| {
+            fixed_fluid(segment).or_else(|| {
+                feeds
+                    .get(&segment)
+                    .into_iter()
+                    .flatten()
+                    .flatten()
+                    .find_map(|&fed| fixed_fluid(fed))
+            })
+        };
+
+        let mut report = Self::default();
+        for (segment, fluid_boxes) in segments.iter().enumerate() {
+            let needed = needed.get(&segment).copied().unwrap_or_default();
+            if needed <= 0.0 {
+                continue;
+            }
+            let is_source = |fluid_box: &FluidBoxId| {
+                outputs.contains(fluid_box)
+                    || offshore_pumps.contains(fluid_box)
+                    || pump_outputs.contains(fluid_box)
+            };
+            let sources: Vec<_> = fluid_boxes.iter().copied().filter(is_source).collect();
+            // Fed from outside, it could be through any open end, so take the best
+            let entries = match sources.is_empty() {
+                true => fluid_boxes
+                    .iter()
+                    .filter(|fluid_box| open.contains(fluid_box))
+                    .map(|&fluid_box| vec![fluid_box])
+                    .collect(),
+                false => vec![sources],
+            };
+            // Machines and pumps taking fluid out of the segment
+            let sinks: BTreeSet<FluidBoxId> = fluid_boxes
+                .iter()
+                .copied()
+                .filter(|fluid_box| rates.contains_key(fluid_box) && !outputs.contains(fluid_box))
+                .chain(
+                    pumps
+                        .keys()
+                        .flat_map(|&pump| graph.neighbors(pump).iter().copied())
+                        .filter(|fluid_box| {
+                            segment_of.get(fluid_box) == Some(&segment)
+                                && !pump_outputs.contains(fluid_box)
+                        }),
+                )
+                .collect();
+
+            let farthest = |entry: &[FluidBoxId]| {
+                let distances = distances(&graph, entry, |fluid_box| {
+                    segment_of.get(&fluid_box) == Some(&segment)
+                });
+                sinks
+                    .iter()
+                    .filter_map(|sink| {
+                        distances
+                            .get(sink)
+                            .map(|&distance| (distance, sink.entity_number))
+                    })
+                    .max_by_key(|&(distance, entity)| (distance, core::cmp::Reverse(entity)))
+            };
+            let Some((length, farthest)) = entries
+                .iter()
+                .filter_map(|entry| farthest(entry))
+                .min_by_key(|&(distance, entity)| (distance, entity))
+            else {
+                continue;
+            };
+            report.segments.push(PipeSegment {
+                fluid_boxes: fluid_boxes.clone(),
+                fluid: fluid(segment).map(str::to_owned),
+                length,
+                farthest,
+                capacity: pipe_flow(length),
+                needed,
+            });
+        }
+        report
+    }
+
+    /// The segments that carry less than the machines along them use.
+    pub fn issues(&self) -> impl Iterator<Item = &PipeSegment> {
+        self.segments.iter().filter(|segment| segment.is_short())
+    }
+}
+
+/// The fluid per second taken out of `segment`, by its machines and by the pumps it feeds,
+/// which take what the segments they feed need, up to what a pump moves. Pumps feeding back
+/// into a segment already counted are left out.
+fn segment_need(
+    segment: usize,
+    segments: &[Vec<FluidBoxId>],
+    rates: &BTreeMap<FluidBoxId, f64>,
+    outputs: &BTreeSet<FluidBoxId>,
+    feeds: &BTreeMap<usize, Vec<Option<usize>>>,
+    needed: &mut BTreeMap<usize, f64>,
+    visiting: &mut BTreeSet<usize>,
+) -> f64 {
+    if let Some(&need) = needed.get(&segment) {
+        return need;
+    }
+    if !visiting.insert(segment) {
+        return 0.0;
+    }
+    let mut need: f64 = segments[segment]
+        .iter()
+        .filter(|fluid_box| !outputs.contains(fluid_box))
+        .filter_map(|fluid_box| rates.get(fluid_box))
+        .sum();
+    for &output in feeds.get(&segment).into_iter().flatten() {
+        let downstream = output.map_or(0.0, |output| {
+            segment_need(output, segments, rates, outputs, feeds, needed, visiting)
+        });
+        need += downstream.min(PUMP_RATE);
+    }
+    visiting.remove(&segment);
+    needed.insert(segment, need);
+    need
+}
+
+/// The number of entities fluid passes through from the nearest of `sources` to each fluid box
+/// it can reach through fluid boxes that are `inside`, counting the last but not the first.
+fn distances(
+    graph: &FluidGraph,
+    sources: &[FluidBoxId],
+    inside: impl Fn(FluidBoxId) -> bool,
+) -> BTreeMap<FluidBoxId, usize> {
+    let mut distances: BTreeMap<FluidBoxId, usize> = BTreeMap::new();
+    let mut queue = VecDeque::new();
+    for &source in sources {
+        distances.insert(source, 0);
+        queue.push_back(source);
+    }
+    while let Some(next) = queue.pop_front() {
+        let distance = distances[&next];
+        for &neighbor in graph.neighbors(next) {
+            if inside(neighbor) && !distances.contains_key(&neighbor) {
+                // Fluid boxes of the same entity don't add to the length
+                let step = usize::from(neighbor.entity_number != next.entity_number);
+                distances.insert(neighbor, distance + step);
+                queue.push_back(neighbor);
+            }
+        }
+    }
+    distances
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blueprint::{BlueprintBuilder, EntityBuilder};
+
+    #[test]
+    fn pipes_carry_less_the_longer_they_are() {
+        assert_eq!(pipe_flow(0), 6000.0);
+        assert_eq!(pipe_flow(3), 3000.0);
+        assert_eq!(pipe_flow(5), 2500.0);
+        assert_eq!(pipe_flow(1000), 240.0);
+        assert_eq!(pipe_flow(2000), 120.0);
+    }
+
+    #[test]
+    fn pipelines_are_measured_to_the_farthest_machine() {
+        let db = PrototypeDb::vanilla();
+        // A chemical plant making plastic, fed through 9 pipes running north from its input
+        let mut builder = BlueprintBuilder::new().entity(
+            EntityBuilder::new("chemical-plant")
+                .at_tile(0, 0)
+                .recipe("plastic-bar"),
+        );
+        for y in 1..=9 {
+            builder.add_entity(EntityBuilder::new("pipe").at_tile(0, -y));
+        }
+        let blueprint = builder.build_with(db).unwrap();
+        let report = PipeReport::new(&blueprint, db);
+
+        let [segment] = report.segments.as_slice() else {
+            panic!("expected one segment, got {:?}", report.segments);
+        };
+        assert_eq!(segment.fluid.as_deref(), Some("petroleum-gas"));
+        assert_eq!((segment.length, segment.farthest), (9, 1));
+        assert_eq!(segment.capacity, 1800.0);
+        assert_eq!(segment.needed, 20.0);
+        assert_eq!(report.issues().count(), 0);
+    }
+}
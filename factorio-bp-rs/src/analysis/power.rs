@@ -0,0 +1,128 @@
+use crate::{
+    blueprint::{Blueprint, Entity},
+    effects::Effects,
+    prototypes::{PowerSource, PrototypeDb},
+};
+
+use super::pass::{run_passes, AnalysisPass, PassContext};
+
+/// How much of their peak output solar panels make on average over a day on Nauvis.
+pub const SOLAR_AVERAGE: f64 = 0.7;
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// The electricity used and made by the entities of a blueprint. Powers are in watts and
+/// energies in joules.
+pub struct PowerReport {
+    /// The power used with every machine working, including module effects and drain.
+    pub max_consumption: f64,
+    /// The power used with every machine idle, which is just the drain.
+    pub idle_consumption: f64,
+    /// The most power steam engines, turbines, and other generators can make.
+    pub generator_output: f64,
+    /// The power solar panels make in full daylight.
+    pub solar_peak: f64,
+    /// The energy accumulators hold when full.
+    pub accumulator_capacity: f64,
+    /// The most power accumulators can give back at once.
+    pub accumulator_output: f64,
+    /// The heat reactors make, before neighbour bonuses, for heat exchangers to turn into steam.
+    pub reactor_output: f64,
+    /// The entity_numbers of entities `db` doesn't know, which aren't counted.
+    pub unknown: Vec<usize>,
+}
+
+impl PowerReport {
+    /// Sum the power of every entity in `blueprint`, with power figures and module effects from
+    /// `db`. Beacons count towards the crafting machines and labs they reach.
+    pub fn new(blueprint: &Blueprint, db: &PrototypeDb) -> Self {
+        let mut report = Self::default();
+        run_passes(blueprint, db, &mut [&mut report]);
+        report
+    }
+
+    /// The power used with machines working `utilization` of the time, from 0 to 1, and idle
+    /// the rest.
+    pub fn average_consumption(&self, utilization: f64) -> f64 {
+        self.idle_consumption + (self.max_consumption - self.idle_consumption) * utilization
+    }
+
+    /// The power solar panels make on average over a day.
+    pub fn solar_average(&self) -> f64 {
+        self.solar_peak * SOLAR_AVERAGE
+    }
+
+    /// The power that can be kept up indefinitely: generators, and solar panels on average.
+    ///
+    /// Generators are assumed to be fed with all the steam or fuel they can use. Accumulators
+    /// only move energy around, so they aren't counted.
+    pub fn sustained_production(&self) -> f64 {
+        self.generator_output + self.solar_average()
+    }
+
+    /// Whether the sustained production is enough for `consumption` watts.
+    pub fn covers(&self, consumption: f64) -> bool {
+        self.sustained_production() >= consumption
+    }
+}
+
+impl AnalysisPass for PowerReport {
+    fn entity(&mut self, entity: &Entity, context: &PassContext) {
+        let Some(prototype) = entity.prototype(context.db) else {
+            self.unknown.push(entity.entity_number.get());
+            return;
+        };
+
+        let consumption = match context.machine_effects().get(&entity.entity_number.get()) {
+            Some(effects) => effects.consumption(),
+            None => Effects::new(entity, context.db).consumption(),
+        };
+        self.max_consumption += prototype.energy_usage * consumption + prototype.drain;
+        self.idle_consumption += prototype.drain;
+
+        match prototype.power_source {
+            Some(PowerSource::Generator(output)) => self.generator_output += output,
+            Some(PowerSource::Solar(output)) => self.solar_peak += output,
+            Some(PowerSource::Accumulator { capacity, output }) => {
+                self.accumulator_capacity += capacity;
+                self.accumulator_output += output;
+            }
+            Some(PowerSource::Reactor(output)) => self.reactor_output += output,
+            None => {}
+        }
+    }
+
+    fn finish(&mut self, _context: &PassContext) {
+        self.unknown.sort_unstable();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{blueprint::EntityBuilder, bp};
+
+    #[test]
+    fn power_is_summed_over_every_entity() {
+        let db = PrototypeDb::vanilla();
+        let blueprint = bp! {
+            "assembling-machine-1" @ (0, 0);
+            "assembling-machine-1" @ (3, 0);
+            "solar-panel" @ (0, 3);
+            "accumulator" @ (3, 3);
+        }
+        .entity(EntityBuilder::new("modded-machine").at(10.5, 0.5))
+        .build_with(db)
+        .unwrap();
+        let report = PowerReport::new(&blueprint, db);
+
+        assert_eq!(report.max_consumption, 155e3);
+        assert_eq!(report.idle_consumption, 5e3);
+        assert_eq!(report.average_consumption(0.5), 80e3);
+        assert_eq!(report.solar_peak, 60e3);
+        assert_eq!(report.sustained_production(), 42e3);
+        assert_eq!(report.accumulator_capacity, 5e6);
+        assert_eq!(report.accumulator_output, 300e3);
+        assert_eq!(report.unknown, [5]);
+        assert!(!report.covers(report.max_consumption));
+    }
+}
@@ -0,0 +1,189 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{belt::Items, blueprint::Blueprint, prototypes::PrototypeDb};
+
+use super::throughput::ThroughputReport;
+
+/// Machine counts closer than this to a whole number are rounded to it.
+const TOLERANCE: f64 = 1e-6;
+
+#[derive(Debug, Clone, PartialEq)]
+/// The crafting machines of a blueprint set to one recipe, and how many it should have.
+pub struct RecipeRatio {
+    /// The name of the recipe.
+    pub recipe: String,
+    /// The number of machines set to it.
+    pub machines: usize,
+    /// The machines it takes to keep up with the recipes using its products, or for recipes
+    /// making end products, the machines there are.
+    pub ideal: f64,
+    /// Whether it makes end products, which no other recipe of the blueprint uses.
+    pub end_product: bool,
+}
+
+impl RecipeRatio {
+    /// The whole number of machines it takes to keep up.
+    pub fn suggested(&self) -> usize {
+        (self.ideal - TOLERANCE).ceil().max(0.0) as usize
+    }
+
+    /// The machines to add, or if negative, to remove.
+    pub fn change(&self) -> i64 {
+        self.suggested() as i64 - self.machines as i64
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// Whether the recipes of a blueprint have machines in the right ratios.
+pub struct RatioReport {
+    /// Every recipe crafting machines are set to, by name.
+    pub recipes: Vec<RecipeRatio>,
+}
+
+/// A recipe's machines, and what an average one of them makes and uses each second.
+struct Group {
+    machines: usize,
+    made: Items,
+    used: Items,
+}
+
+impl RatioReport {
+    /// Work out how many machines each recipe in `blueprint` needs to keep up with the recipes
+    /// using its products, with crafting speeds, recipes, and module effects from `db`.
+    ///
+    /// The machines making end products are taken as they are, and the rest worked back from
+    /// them. Machines are compared by their average rate, so those with more modules or beacons
+    /// count for more.
+    pub fn new(blueprint: &Blueprint, db: &PrototypeDb) -> Self {
+        let throughput = ThroughputReport::new(blueprint, db);
+        let mut groups: BTreeMap<&str, Group> = BTreeMap::new();
+        for machine in &throughput.machines {
+            let group = groups.entry(&machine.recipe).or_insert_with(|| Group {
+                machines: 0,
+                made: Items::new(),
+                used: Items::new(),
+            });
+            group.machines += 1;
+            for (item, rate) in &machine.products {
+                *group.made.entry(item.clone()).or_default() += rate;
+            }
+            for (item, rate) in &machine.ingredients {
+                *group.used.entry(item.clone()).or_default() += rate;
+            }
+        }
+        // Everything made by all machines of each recipe, for sharing out demand between recipes
+        // making the same thing
+        let mut made_by_all = Items::new();
+        for group in groups.values_mut() {
+            for (item, rate) in &group.made {
+                *made_by_all.entry(item.clone()).or_default() += rate;
+            }
+            let machines = group.machines as f64;
+            group.made.values_mut().for_each(|rate| *rate /= machines);
+            group.used.values_mut().for_each(|rate| *rate /= machines);
+        }
+
+        let used_by_others = |recipe: &str, item: &str| {
+            groups
+                .iter()
+                .any(|(&other, group)| other != recipe && group.used.contains_key(item))
+        };
+        let end_products: BTreeSet<&str> = groups
+            .iter()
+            .filter(|(&recipe, group)| !group.made.keys().any(|item| used_by_others(recipe, item)))
+            .map(|(&recipe, _)| recipe)
+            .collect();
+
+        // Work back from the end products, a step further each round
+        let mut ideal: BTreeMap<&str, f64> = groups
+            .iter()
+            .map(|(&recipe, group)| {
+                let machines = if end_products.contains(recipe) {
+                    group.machines as f64
+                } else {
+                    0.0
+                };
+                (recipe, machines)
+            })
+            .collect();
+        for _ in 0..=groups.len() {
+            let mut next = ideal.clone();
+            for (&recipe, group) in &groups {
+                if end_products.contains(recipe) {
+                    continue;
+                }
+                let needed = group
+                    .made
+                    .iter()
+                    .map(|(item, &rate)| {
+                        let demand: f64 = groups
+                            .iter()
+                            .filter(|(&other, _)| other != recipe)
+                            .map(|(other, consumer)| {
+                                ideal[other] * consumer.used.get(item).copied().unwrap_or_default()
+                            })
+                            .sum();
+                        // Shared with other recipes making it, in proportion to what they make
+                        let share = rate * group.machines as f64 / made_by_all[item];
+                        demand * share / rate
+                    })
+                    .fold(0.0, f64::max);
+                next.insert(recipe, needed);
+            }
+            if next == ideal {
+                break;
+            }
+            ideal = next;
+        }
+
+        let recipes = groups
+            .iter()
+            .map(|(&recipe, group)| RecipeRatio {
+                recipe: recipe.to_owned(),
+                machines: group.machines,
+                ideal: ideal[recipe],
+                end_product: end_products.contains(recipe),
+            })
+            .collect();
+        Self { recipes }
+    }
+
+    /// The recipes that need machines added or removed.
+    pub fn suggestions(&self) -> impl Iterator<Item = &RecipeRatio> {
+        self.recipes.iter().filter(|recipe| recipe.change() != 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bp;
+
+    #[test]
+    fn intermediates_are_worked_back_from_end_products() {
+        let db = PrototypeDb::vanilla();
+        let blueprint = bp! {
+            "assembling-machine-2" @ (0, 0) recipe "electronic-circuit";
+            "assembling-machine-2" @ (3, 0) recipe "electronic-circuit";
+            "assembling-machine-2" @ (6, 0) recipe "copper-cable";
+        }
+        .build_with(db)
+        .unwrap();
+        let report = RatioReport::new(&blueprint, db);
+
+        let [cable, circuit] = &report.recipes[..] else {
+            panic!("expected two recipes, got {:?}", report.recipes);
+        };
+        assert_eq!(circuit.recipe, "electronic-circuit");
+        assert!(circuit.end_product);
+        assert_eq!((circuit.ideal, circuit.change()), (2.0, 0));
+        assert!(!cable.end_product);
+        assert_eq!(
+            (cable.machines, cable.suggested(), cable.change()),
+            (1, 3, 2)
+        );
+
+        let suggestions: Vec<_> = report.suggestions().map(|recipe| &recipe.recipe).collect();
+        assert_eq!(suggestions, ["copper-cable"]);
+    }
+}
@@ -0,0 +1,160 @@
+use crate::{blueprint::Blueprint, effects::Effects, prototypes::PrototypeDb};
+
+use super::throughput::ThroughputReport;
+
+#[derive(Debug, Clone, PartialEq)]
+/// The technology being researched, as far as the labs care.
+pub struct Research {
+    /// The seconds one unit takes in a lab with a researching speed of 1.
+    pub unit_time: f64,
+    /// The science packs each unit takes, one of each.
+    pub packs: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A lab, and how fast it researches.
+pub struct LabRate {
+    /// The entity_number of the lab.
+    pub entity_number: usize,
+    /// What its modules and the beacons around it do to it.
+    pub effects: Effects,
+    /// The units it works through each second, each using one of every pack.
+    pub units_per_second: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A science pack, and how fast the labs use it.
+pub struct PackRate {
+    /// The name of the science pack.
+    pub name: String,
+    /// The packs all labs together use each second when kept supplied.
+    pub needed: f64,
+    /// The packs the crafting machines of the blueprint make each second.
+    pub made: f64,
+}
+
+impl PackRate {
+    /// How much of what the labs need the blueprint makes, from 0 up.
+    pub fn fraction(&self) -> f64 {
+        if self.needed > 0.0 {
+            self.made / self.needed
+        } else {
+            1.0
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// How fast the labs of a blueprint research, and the science packs that takes.
+pub struct ScienceReport {
+    /// Every lab that takes all the packs, in entity order.
+    pub labs: Vec<LabRate>,
+    /// The entity_numbers of labs that can't take some of the packs. They aren't counted.
+    pub unusable: Vec<usize>,
+    /// Every pack of the research, in the order given.
+    pub packs: Vec<PackRate>,
+}
+
+impl ScienceReport {
+    /// Work out how fast the labs in `blueprint` research `research`, with researching speeds and
+    /// module effects from `db`, and compare the packs they use with those the blueprint makes.
+    pub fn new(blueprint: &Blueprint, db: &PrototypeDb, research: &Research) -> Self {
+        let mut effects = Effects::of_machines(blueprint, db);
+        let mut report = Self::default();
+        for entity in blueprint.entities.iter().flatten() {
+            let Some(lab) = entity.prototype(db).and_then(|p| p.lab.as_ref()) else {
+                continue;
+            };
+            let number = entity.entity_number.get();
+            let takes_all = research.packs.iter().all(|pack| lab.inputs.contains(pack));
+            let (true, Some(effects)) = (takes_all, effects.remove(&number)) else {
+                report.unusable.push(number);
+                continue;
+            };
+            report.labs.push(LabRate {
+                entity_number: number,
+                units_per_second: lab.researching_speed * effects.speed() / research.unit_time,
+                effects,
+            });
+        }
+
+        // Productivity makes more units, not fewer packs used
+        let needed = report
+            .labs
+            .iter()
+            .fold(0.0, |sum, lab| sum + lab.units_per_second);
+        let made = ThroughputReport::new(blueprint, db).produced();
+        report.packs = research
+            .packs
+            .iter()
+            .map(|name| PackRate {
+                name: name.clone(),
+                needed,
+                made: made.get(name).copied().unwrap_or(0.0),
+            })
+            .collect();
+        report
+    }
+
+    /// The research units all labs together finish each second, including productivity.
+    pub fn units_per_second(&self) -> f64 {
+        self.labs.iter().fold(0.0, |sum, lab| {
+            sum + lab.units_per_second * lab.effects.productivity()
+        })
+    }
+
+    /// How much of the time the labs can work with the packs the blueprint makes, from 0 to 1, or
+    /// `None` if it makes none of them and they are brought in from elsewhere.
+    pub fn saturation(&self) -> Option<f64> {
+        if self.packs.iter().all(|pack| pack.made == 0.0) {
+            return None;
+        }
+        Some(
+            self.packs
+                .iter()
+                .map(PackRate::fraction)
+                .fold(1.0, f64::min),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bp;
+
+    /// Research taking `packs`, one unit every 5 seconds.
+    fn research(packs: &[&str]) -> Research {
+        Research {
+            unit_time: 5.0,
+            packs: packs.iter().map(|&pack| pack.to_owned()).collect(),
+        }
+    }
+
+    #[test]
+    fn labs_are_compared_with_the_packs_made() {
+        let db = PrototypeDb::vanilla();
+        let blueprint = bp! {
+            "lab" @ (0, 0);
+            "biolab" @ (10, 0);
+            "assembling-machine-2" @ (20, 0) recipe "automation-science-pack";
+        }
+        .build_with(db)
+        .unwrap();
+
+        let report = ScienceReport::new(&blueprint, db, &research(&["automation-science-pack"]));
+        let rates: Vec<_> = report.labs.iter().map(|lab| lab.units_per_second).collect();
+        assert_eq!(rates, [0.2, 0.4]);
+        assert!((report.units_per_second() - 0.6).abs() < 1e-9);
+        assert!((report.packs[0].made - 0.15).abs() < 1e-9);
+        assert!((report.saturation().unwrap() - 0.25).abs() < 1e-9);
+
+        let report = ScienceReport::new(&blueprint, db, &research(&["logistic-science-pack"]));
+        assert_eq!(report.saturation(), None);
+
+        let modded = research(&["automation-science-pack", "modded-science-pack"]);
+        let report = ScienceReport::new(&blueprint, db, &modded);
+        assert!(report.labs.is_empty());
+        assert_eq!(report.unusable, [1, 2]);
+    }
+}
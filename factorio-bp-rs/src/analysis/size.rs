@@ -0,0 +1,132 @@
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use crate::blueprint::{BlueprintEnvelope, BpError, EncodeOptions, VERSION_BYTE};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// The size of one field of the blueprints, summed over every blueprint of a book.
+pub struct SectionSize {
+    /// The bytes of its JSON.
+    pub json_length: usize,
+    /// The characters it takes in the blueprint string, from compressing it on its own. Fields
+    /// compress better together, so the sections add up to a little more than the string.
+    pub string_length: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// How long the blueprint string of a blueprint, book, or planner is, and what it is made of.
+pub struct SizeReport {
+    /// The characters of the blueprint string.
+    pub string_length: usize,
+    /// The bytes of the JSON compressed into it.
+    pub json_length: usize,
+    /// The size of each field, e.g. "entities", by its name in the JSON. The fields of books and
+    /// planners count alongside those of blueprints.
+    pub sections: BTreeMap<String, SectionSize>,
+}
+
+/// Add the fields of the blueprint, book, or planner in `envelope` to `sections`, and those of
+/// the entries of books.
+fn collect<'a>(envelope: &'a Value, sections: &mut BTreeMap<String, Vec<&'a Value>>) {
+    let contents = envelope
+        .as_object()
+        .into_iter()
+        .flat_map(|object| object.values());
+    for fields in contents.filter_map(Value::as_object) {
+        for (name, value) in fields {
+            if name == "blueprints" {
+                for entry in value.as_array().into_iter().flatten() {
+                    collect(entry, sections);
+                }
+            } else {
+                sections.entry(name.clone()).or_default().push(value);
+            }
+        }
+    }
+}
+
+impl SizeReport {
+    /// Measure the blueprint string `envelope` encodes to with `options`.
+    pub fn new(envelope: &BlueprintEnvelope, options: &EncodeOptions) -> Result<Self, BpError> {
+        let string_length = options.encode(envelope)?.len();
+        let json = envelope.to_json_value()?;
+        let json_length = serde_json::to_string(&json)?.len();
+
+        let mut fields = BTreeMap::new();
+        collect(&json, &mut fields);
+        let mut sections = BTreeMap::new();
+        for (name, values) in fields {
+            let mut json_length = 0;
+            for value in &values {
+                json_length += serde_json::to_string(value)?.len();
+            }
+            let all = serde_json::to_string(&values)?;
+            let string_length = options.encode_json(&all)?.len() - VERSION_BYTE.len_utf8();
+            sections.insert(
+                name,
+                SectionSize {
+                    json_length,
+                    string_length,
+                },
+            );
+        }
+
+        Ok(Self {
+            string_length,
+            json_length,
+            sections,
+        })
+    }
+
+    /// The sections, the most of the string first.
+    pub fn largest(&self) -> Vec<(&str, SectionSize)> {
+        let mut sections: Vec<_> = self
+            .sections
+            .iter()
+            .map(|(name, size)| (name.as_str(), *size))
+            .collect();
+        sections.sort_by_key(|(_, size)| core::cmp::Reverse(size.string_length));
+        sections
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        blueprint::{decode_string, BlueprintBuilder, EntityBuilder},
+        prototypes::PrototypeDb,
+    };
+
+    #[test]
+    fn strings_are_measured_by_field() {
+        let mut builder = BlueprintBuilder::new().label("Belts");
+        for x in 0..50 {
+            builder.add_entity(EntityBuilder::new("transport-belt").at_tile(x, 0));
+        }
+        let blueprint = builder.build_with(PrototypeDb::vanilla()).unwrap();
+        let envelope = BlueprintEnvelope::Blueprint(blueprint);
+
+        let stored = EncodeOptions {
+            compression_level: 0,
+            ..EncodeOptions::default()
+        };
+        let best = EncodeOptions::default();
+        let (stored_string, best_string) = (
+            stored.encode(&envelope).unwrap(),
+            best.encode(&envelope).unwrap(),
+        );
+        assert!(best_string.len() < stored_string.len());
+        assert_eq!(decode_string(&stored_string).unwrap(), envelope);
+
+        let report = SizeReport::new(&envelope, &best).unwrap();
+        assert_eq!(report.string_length, best_string.len());
+        assert!(report.json_length > report.string_length);
+        assert_eq!(report.largest()[0].0, "entities");
+        assert!(report.sections.contains_key("label"));
+        let stored_report = SizeReport::new(&envelope, &stored).unwrap();
+        assert_eq!(stored_report.json_length, report.json_length);
+        assert!(stored_report.string_length > report.string_length);
+    }
+}
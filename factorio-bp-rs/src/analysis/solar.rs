@@ -0,0 +1,204 @@
+use crate::{
+    blueprint::Blueprint,
+    prototypes::{PowerSource, PrototypeDb},
+};
+
+use super::power::PowerReport;
+
+/// Accumulators per solar panel for vanilla panels to keep a constant load powered through a
+/// Nauvis night, the well known 21 accumulators to 25 panels.
+pub const IDEAL_RATIO: f64 = 21.0 / 25.0;
+
+/// Ticks per second at normal game speed.
+const TICKS_PER_SECOND: f64 = 60.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// When a surface is light and dark. Times are fractions of the day, starting at noon.
+pub struct DayCycle {
+    /// The length of a whole day, in ticks.
+    pub ticks: u32,
+    /// When light starts to fade.
+    pub dusk: f64,
+    /// When it's fully dark.
+    pub evening: f64,
+    /// When light starts to return.
+    pub morning: f64,
+    /// When it's fully light again.
+    pub dawn: f64,
+}
+
+impl DayCycle {
+    /// The day on Nauvis: seven minutes long, with half of it in full daylight.
+    pub const NAUVIS: DayCycle = DayCycle {
+        ticks: 25_000,
+        dusk: 0.25,
+        evening: 0.45,
+        morning: 0.55,
+        dawn: 0.75,
+    };
+
+    /// How much of their peak output solar panels make at `time`, a fraction of the day since
+    /// noon.
+    pub fn light(&self, time: f64) -> f64 {
+        let time = time.rem_euclid(1.0);
+        if time < self.dusk || time >= self.dawn {
+            1.0
+        } else if time < self.evening {
+            (self.evening - time) / (self.evening - self.dusk)
+        } else if time < self.morning {
+            0.0
+        } else {
+            (time - self.morning) / (self.dawn - self.morning)
+        }
+    }
+}
+
+impl Default for DayCycle {
+    fn default() -> Self {
+        Self::NAUVIS
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// How solar panels and accumulators cope with a constant load over a day.
+pub struct SolarReport {
+    /// The number of solar panels.
+    pub panels: usize,
+    /// The number of accumulators.
+    pub accumulators: usize,
+    /// The constant load simulated, in watts.
+    pub load: f64,
+    /// The least energy left in the accumulators over the day, in joules.
+    pub min_charge: f64,
+    /// The energy the load wanted but didn't get over the day, in joules.
+    pub shortfall: f64,
+    /// How many seconds of the day the load wasn't fully powered.
+    pub unpowered_seconds: f64,
+}
+
+impl SolarReport {
+    /// Simulate a Nauvis day of the blueprint powering `load` watts, see
+    /// [SolarReport::with_cycle].
+    pub fn new(blueprint: &Blueprint, db: &PrototypeDb, load: f64) -> Self {
+        Self::with_cycle(blueprint, db, load, &DayCycle::NAUVIS)
+    }
+
+    /// Simulate a day of `cycle`, tick by tick, with the blueprint powering `load` watts.
+    ///
+    /// Generators are counted as a constant supply alongside the solar panels. Accumulators
+    /// charge from any surplus and cover any deficit, as fast as their output allows either
+    /// way. The accumulators start empty at noon and a day is run to charge them, so the report
+    /// is of the second day.
+    pub fn with_cycle(
+        blueprint: &Blueprint,
+        db: &PrototypeDb,
+        load: f64,
+        cycle: &DayCycle,
+    ) -> Self {
+        let power = PowerReport::new(blueprint, db);
+        let mut report = Self {
+            load,
+            ..Self::default()
+        };
+        for entity in blueprint.entities.iter().flatten() {
+            match entity
+                .prototype(db)
+                .and_then(|prototype| prototype.power_source)
+            {
+                Some(PowerSource::Solar(_)) => report.panels += 1,
+                Some(PowerSource::Accumulator { .. }) => report.accumulators += 1,
+                _ => {}
+            }
+        }
+
+        let seconds = 1.0 / TICKS_PER_SECOND;
+        let rate = power.accumulator_output * seconds;
+        let mut charge: f64 = 0.0;
+        report.min_charge = f64::MAX;
+        for day in 0..2 {
+            for tick in 0..cycle.ticks {
+                let light = cycle.light(tick as f64 / cycle.ticks as f64);
+                let surplus = (power.generator_output + power.solar_peak * light - load) * seconds;
+                if surplus >= 0.0 {
+                    charge = (charge + surplus.min(rate)).min(power.accumulator_capacity);
+                } else {
+                    let drawn = (-surplus).min(rate).min(charge);
+                    charge -= drawn;
+                    let missing = -surplus - drawn;
+                    if day == 1 && missing > 0.0 {
+                        report.shortfall += missing;
+                        report.unpowered_seconds += seconds;
+                    }
+                }
+                if day == 1 {
+                    report.min_charge = report.min_charge.min(charge);
+                }
+            }
+        }
+        report
+    }
+
+    /// Whether the load was fully powered all day, give or take a joule of rounding.
+    pub fn is_covered(&self) -> bool {
+        self.shortfall < 1.0
+    }
+
+    /// Accumulators per solar panel, or `None` without any panels.
+    pub fn ratio(&self) -> Option<f64> {
+        (self.panels > 0).then(|| self.accumulators as f64 / self.panels as f64)
+    }
+
+    /// How far the ratio is from the [IDEAL_RATIO], e.g. -0.1 for 10% too few accumulators.
+    pub fn ratio_deviation(&self) -> Option<f64> {
+        self.ratio().map(|ratio| ratio / IDEAL_RATIO - 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blueprint::{BlueprintBuilder, EntityBuilder};
+
+    /// A row of solar panels with a row of accumulators under it.
+    fn solar_field(panels: i64, accumulators: i64) -> Blueprint {
+        let panels = (0..panels).map(|i| EntityBuilder::new("solar-panel").at_tile(i * 3, 0));
+        let accumulators =
+            (0..accumulators).map(|i| EntityBuilder::new("accumulator").at_tile(i * 2, 3));
+        panels
+            .chain(accumulators)
+            .fold(BlueprintBuilder::new(), BlueprintBuilder::entity)
+            .build_with(PrototypeDb::vanilla())
+            .unwrap()
+    }
+
+    #[test]
+    fn nauvis_nights_fade_in_and_out() {
+        let light = |time| (DayCycle::NAUVIS.light(time) * 1e9).round() / 1e9;
+        assert_eq!(light(0.0), 1.0);
+        assert_eq!(light(0.35), 0.5);
+        assert_eq!(light(0.5), 0.0);
+        assert_eq!(light(0.65), 0.5);
+        assert_eq!(light(1.2), 1.0);
+    }
+
+    #[test]
+    fn accumulators_at_the_ideal_ratio_last_the_night() {
+        let db = PrototypeDb::vanilla();
+        let load = 25.0 * 40e3;
+
+        let report = SolarReport::new(&solar_field(25, 21), db, load);
+        assert_eq!((report.panels, report.accumulators), (25, 21));
+        assert!(report.is_covered());
+        assert_eq!(report.unpowered_seconds, 0.0);
+        assert!(report.ratio_deviation().unwrap().abs() < 1e-9);
+
+        let report = SolarReport::new(&solar_field(25, 0), db, load);
+        assert!(!report.is_covered());
+        assert!(report.unpowered_seconds > 41.0);
+        assert_eq!(report.ratio(), Some(0.0));
+
+        let report = SolarReport::new(&solar_field(0, 0), db, 0.0);
+        assert!(report.is_covered());
+        assert_eq!(report.ratio(), None);
+    }
+}
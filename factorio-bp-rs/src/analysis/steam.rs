@@ -0,0 +1,318 @@
+use core::fmt;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    blueprint::Blueprint,
+    fluid::{FluidBoxId, FluidGraph},
+    prototypes::PrototypeDb,
+};
+
+/// Water an offshore pump pumps, per second.
+const OFFSHORE_PUMP_WATER: f64 = 1200.0;
+/// Water a boiler turns into steam at full power, per second.
+const BOILER_WATER: f64 = 60.0;
+/// The heat a boiler gives its steam at full power, in watts.
+const BOILER_POWER: f64 = 1.8e6;
+/// The electricity a steam engine makes at full power, in watts.
+const ENGINE_POWER: f64 = 900e3;
+
+/// Boilers one offshore pump can keep supplied.
+pub const BOILERS_PER_PUMP: f64 = OFFSHORE_PUMP_WATER / BOILER_WATER;
+/// Steam engines one boiler can keep supplied.
+pub const ENGINES_PER_BOILER: f64 = BOILER_POWER / ENGINE_POWER;
+
+/// The fluid box of a boiler that takes water.
+const BOILER_WATER_BOX: usize = 0;
+/// The fluid box of a boiler that gives steam.
+const BOILER_STEAM_BOX: usize = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+/// The parts of a steam power setup.
+pub enum SteamComponent {
+    /// Pumps water out of a lake.
+    OffshorePump,
+    /// Burns fuel to turn water into steam.
+    Boiler,
+    /// Turns steam into electricity.
+    SteamEngine,
+}
+
+impl fmt::Display for SteamComponent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SteamComponent::OffshorePump => "offshore pumps",
+            SteamComponent::Boiler => "boilers",
+            SteamComponent::SteamEngine => "steam engines",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+/// Offshore pumps, boilers, and steam engines joined by pipes.
+pub struct SteamSetup {
+    /// The entity_numbers of the offshore pumps, in order.
+    pub offshore_pumps: Vec<usize>,
+    /// The entity_numbers of the boilers, in order.
+    pub boilers: Vec<usize>,
+    /// The entity_numbers of the steam engines, in order.
+    pub engines: Vec<usize>,
+}
+
+impl SteamSetup {
+    /// The electricity the water of the offshore pumps could make, in watts.
+    pub fn pump_capacity(&self) -> f64 {
+        self.offshore_pumps.len() as f64 * OFFSHORE_PUMP_WATER / BOILER_WATER * BOILER_POWER
+    }
+
+    /// The electricity the steam of the boilers could make, in watts.
+    pub fn boiler_capacity(&self) -> f64 {
+        self.boilers.len() as f64 * BOILER_POWER
+    }
+
+    /// The electricity the steam engines could make, in watts.
+    pub fn engine_capacity(&self) -> f64 {
+        self.engines.len() as f64 * ENGINE_POWER
+    }
+
+    /// The electricity the setup can keep making, in watts, limited by its weakest part.
+    pub fn output(&self) -> f64 {
+        self.pump_capacity()
+            .min(self.boiler_capacity())
+            .min(self.engine_capacity())
+    }
+
+    /// The part that limits the output, or `None` if the boilers and steam engines match and
+    /// the offshore pumps keep up with them. Spare pump capacity is normal, as one pump supplies
+    /// many boilers.
+    pub fn limit(&self) -> Option<SteamComponent> {
+        let (pumps, boilers, engines) = (
+            self.pump_capacity(),
+            self.boiler_capacity(),
+            self.engine_capacity(),
+        );
+        if boilers == engines && pumps >= boilers {
+            return None;
+        }
+        let output = self.output();
+        [
+            (SteamComponent::OffshorePump, pumps),
+            (SteamComponent::Boiler, boilers),
+            (SteamComponent::SteamEngine, engines),
+        ]
+        .into_iter()
+        .find(|&(_, capacity)| capacity == output)
+        .map(|(component, _)| component)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A part of a steam power setup that isn't piped up.
+pub enum SteamIssue {
+    /// A boiler whose water input doesn't lead to any offshore pump.
+    NoWater(usize),
+    /// A boiler whose steam output doesn't lead to any steam engine.
+    NoEngine(usize),
+    /// A steam engine that doesn't lead to any boiler.
+    NoSteam(usize),
+    /// An offshore pump that doesn't lead to any boiler.
+    UnusedPump(usize),
+}
+
+impl fmt::Display for SteamIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SteamIssue::NoWater(boiler) => {
+                write!(f, "boiler {boiler} isn't connected to an offshore pump")
+            }
+            SteamIssue::NoEngine(boiler) => {
+                write!(f, "boiler {boiler} isn't connected to a steam engine")
+            }
+            SteamIssue::NoSteam(engine) => {
+                write!(f, "steam engine {engine} isn't connected to a boiler")
+            }
+            SteamIssue::UnusedPump(pump) => {
+                write!(f, "offshore pump {pump} isn't connected to a boiler")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+/// The steam power setups of a blueprint.
+pub struct SteamReport {
+    /// Each group of offshore pumps, boilers, and steam engines joined by pipes, ordered by their
+    /// lowest entity_number.
+    pub setups: Vec<SteamSetup>,
+    /// Parts that aren't piped up, in entity order.
+    pub issues: Vec<SteamIssue>,
+}
+
+impl SteamReport {
+    /// Find the steam power setups of `blueprint`, with pipe connections from `db`.
+    ///
+    /// Pipe throughput isn't taken into account, so very long pipes may give less than reported.
+    pub fn new(blueprint: &Blueprint, db: &PrototypeDb) -> Self {
+        let graph = FluidGraph::new(blueprint, db);
+        let components: BTreeMap<usize, SteamComponent> = blueprint
+            .entities
+            .iter()
+            .flatten()
+            .filter_map(|entity| {
+                let component = match entity.name.as_str() {
+                    "offshore-pump" => SteamComponent::OffshorePump,
+                    "boiler" => SteamComponent::Boiler,
+                    "steam-engine" => SteamComponent::SteamEngine,
+                    _ => return None,
+                };
+                Some((entity.entity_number.get(), component))
+            })
+            .collect();
+
+        // The steam entities each fluid box's network leads to, by their part
+        let reaches = |fluid_box: FluidBoxId| -> BTreeSet<(SteamComponent, usize, usize)> {
+            let Some(network) = graph.network(fluid_box) else {
+                return BTreeSet::new();
+            };
+            network
+                .fluid_boxes
+                .iter()
+                .filter(|other| other.entity_number != fluid_box.entity_number)
+                .filter_map(|other| {
+                    let component = *components.get(&other.entity_number)?;
+                    Some((component, other.entity_number, other.index))
+                })
+                .collect()
+        };
+        let leads_to = |fluid_box: FluidBoxId, wanted: SteamComponent, index: Option<usize>| {
+            reaches(fluid_box)
+                .iter()
+                .any(|&(component, _, other_index)| {
+                    component == wanted && index.is_none_or(|index| index == other_index)
+                })
+        };
+
+        let mut report = Self::default();
+        let mut adjacent: BTreeMap<usize, BTreeSet<usize>> = BTreeMap::new();
+        for (&number, &component) in &components {
+            let fluid_box = |index| FluidBoxId {
+                entity_number: number,
+                index,
+            };
+            let boxes = match component {
+                SteamComponent::Boiler => vec![BOILER_WATER_BOX, BOILER_STEAM_BOX],
+                _ => vec![0],
+            };
+            for index in boxes {
+                adjacent.entry(number).or_default().extend(
+                    reaches(fluid_box(index))
+                        .into_iter()
+                        .map(|(_, other, _)| other),
+                );
+            }
+
+            let issue = match component {
+                SteamComponent::OffshorePump => {
+                    let boiler = Some(BOILER_WATER_BOX);
+                    (!leads_to(fluid_box(0), SteamComponent::Boiler, boiler))
+                        .then_some(SteamIssue::UnusedPump(number))
+                }
+                SteamComponent::Boiler => {
+                    if !leads_to(
+                        fluid_box(BOILER_WATER_BOX),
+                        SteamComponent::OffshorePump,
+                        None,
+                    ) {
+                        report.issues.push(SteamIssue::NoWater(number));
+                    }
+                    (!leads_to(
+                        fluid_box(BOILER_STEAM_BOX),
+                        SteamComponent::SteamEngine,
+                        None,
+                    ))
+                    .then_some(SteamIssue::NoEngine(number))
+                }
+                SteamComponent::SteamEngine => {
+                    let boiler = Some(BOILER_STEAM_BOX);
+                    (!leads_to(fluid_box(0), SteamComponent::Boiler, boiler))
+                        .then_some(SteamIssue::NoSteam(number))
+                }
+            };
+            report.issues.extend(issue);
+        }
+
+        let mut seen = BTreeSet::new();
+        for &start in components.keys() {
+            if !seen.insert(start) {
+                continue;
+            }
+            let mut setup = SteamSetup::default();
+            let mut queue = vec![start];
+            while let Some(next) = queue.pop() {
+                match components[&next] {
+                    SteamComponent::OffshorePump => setup.offshore_pumps.push(next),
+                    SteamComponent::Boiler => setup.boilers.push(next),
+                    SteamComponent::SteamEngine => setup.engines.push(next),
+                }
+                for &neighbor in adjacent.get(&next).into_iter().flatten() {
+                    if seen.insert(neighbor) {
+                        queue.push(neighbor);
+                    }
+                }
+            }
+            setup.offshore_pumps.sort_unstable();
+            setup.boilers.sort_unstable();
+            setup.engines.sort_unstable();
+            report.setups.push(setup);
+        }
+        report
+    }
+
+    /// The electricity every setup together can keep making, in watts.
+    pub fn output(&self) -> f64 {
+        self.setups.iter().map(SteamSetup::output).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bp;
+
+    #[test]
+    fn setups_are_limited_by_their_weakest_part() {
+        let db = PrototypeDb::vanilla();
+        let blueprint = bp! {
+            "offshore-pump" @ (-2, 1) facing East;
+            "boiler" @ (0, 0);
+            "steam-engine" @ (0, -5);
+            "steam-engine" @ (0, -10);
+            "steam-engine" @ (10, 0);
+        }
+        .build_with(db)
+        .unwrap();
+        let report = SteamReport::new(&blueprint, db);
+
+        assert_eq!(report.setups.len(), 2);
+        let setup = &report.setups[0];
+        assert_eq!(setup.offshore_pumps, [1]);
+        assert_eq!(setup.boilers, [2]);
+        assert_eq!(setup.engines, [3, 4]);
+        assert_eq!(setup.limit(), None);
+        assert_eq!(setup.output(), 1.8e6);
+        assert_eq!(report.setups[1].engines, [5]);
+        assert_eq!(report.issues, [SteamIssue::NoSteam(5)]);
+        assert_eq!(report.output(), 1.8e6);
+
+        let short = SteamSetup {
+            engines: vec![3],
+            ..setup.clone()
+        };
+        assert_eq!(short.limit(), Some(SteamComponent::SteamEngine));
+        assert_eq!(short.output(), 900e3);
+        let dry = SteamSetup {
+            offshore_pumps: Vec::new(),
+            ..setup.clone()
+        };
+        assert_eq!(dry.limit(), Some(SteamComponent::OffshorePump));
+    }
+}
@@ -0,0 +1,496 @@
+use core::fmt;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use crate::{
+    belt::{BeltGraph, Items, NodeKind},
+    blueprint::{Blueprint, Entity, Position},
+    effects::Effects,
+    prototypes::PrototypeDb,
+};
+
+/// Rates closer than this, in items per second, count as equal.
+const TOLERANCE: f64 = 1e-6;
+
+#[derive(Debug, Clone, PartialEq)]
+/// A crafting machine with a recipe, and how fast it works through it.
+pub struct Machine {
+    /// The entity_number of the machine.
+    pub entity_number: usize,
+    /// The name of the recipe it is set to.
+    pub recipe: String,
+    /// What its modules and the beacons around it do to it.
+    pub effects: Effects,
+    /// The crafts it finishes each second.
+    pub crafts_per_second: f64,
+    /// The items and fluids it uses each second.
+    pub ingredients: Items,
+    /// The items and fluids it makes each second, including productivity.
+    pub products: Items,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// The crafting machines of a blueprint and the items they make and use, assuming every one is
+/// kept supplied and emptied.
+pub struct ThroughputReport {
+    /// Every crafting machine with a recipe, in entity order.
+    pub machines: Vec<Machine>,
+    /// The entity_numbers of crafting machines with no recipe set, like furnaces, or a recipe
+    /// `db` doesn't know. They aren't counted.
+    pub unknown: Vec<usize>,
+    /// What keeps machines from working at full speed, worst first.
+    pub bottlenecks: Vec<Bottleneck>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// The parts of a production line that can hold it back.
+pub enum Stage {
+    /// A machine has no inserters bringing its ingredients.
+    Starved,
+    /// The inserters bringing a machine's ingredients are too slow.
+    InputInserters,
+    /// The inserters taking a machine's products away are too slow, or there are none.
+    OutputInserters,
+    /// A belt can't carry what the inserters along it put on and take off.
+    Belt,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A stage of a production line that can't keep up with the machines it serves.
+pub struct Bottleneck {
+    /// What sort of stage it is.
+    pub stage: Stage,
+    /// The entity_number of the machine, or of the belt that carries the most.
+    pub entity_number: usize,
+    /// The items per second the stage would need to move for every machine to work at full
+    /// speed.
+    pub needed: f64,
+    /// The items per second it can move.
+    pub capacity: f64,
+}
+
+impl Bottleneck {
+    /// The fraction of what's needed the stage can move, from 0 to 1.
+    pub fn fraction(&self) -> f64 {
+        (self.capacity / self.needed).min(1.0)
+    }
+}
+
+impl fmt::Display for Bottleneck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            entity_number,
+            needed,
+            capacity,
+            ..
+        } = self;
+        match self.stage {
+            Stage::Starved => write!(
+                f,
+                "machine {entity_number} has no inserters bringing the {needed:.2}/s of \
+                 ingredients it uses"
+            ),
+            Stage::InputInserters => write!(
+                f,
+                "inserters into machine {entity_number} move {capacity:.2}/s, but it uses \
+                 {needed:.2}/s"
+            ),
+            Stage::OutputInserters if *capacity == 0.0 => write!(
+                f,
+                "machine {entity_number} has no inserters taking away the {needed:.2}/s of \
+                 products it makes"
+            ),
+            Stage::OutputInserters => write!(
+                f,
+                "inserters out of machine {entity_number} move {capacity:.2}/s, but it makes \
+                 {needed:.2}/s"
+            ),
+            Stage::Belt => write!(
+                f,
+                "belt {entity_number} carries {capacity:.2}/s, but needs to carry {needed:.2}/s"
+            ),
+        }
+    }
+}
+
+impl ThroughputReport {
+    /// Work out the rate of every crafting machine in `blueprint`, with crafting speeds, recipes,
+    /// and module effects from `db`.
+    pub fn new(blueprint: &Blueprint, db: &PrototypeDb) -> Self {
+        let mut effects = Effects::of_machines(blueprint, db);
+        let mut report = Self::default();
+        for entity in blueprint.entities.iter().flatten() {
+            let Some(machine) = entity
+                .prototype(db)
+                .and_then(|p| p.crafting_machine.as_ref())
+            else {
+                continue;
+            };
+            let number = entity.entity_number.get();
+            let recipe = entity.recipe().and_then(|name| db.recipe(name));
+            let (Some(recipe), Some(effects)) = (recipe, effects.remove(&number)) else {
+                report.unknown.push(number);
+                continue;
+            };
+
+            let crafts_per_second =
+                machine.crafting_speed * effects.speed() / recipe.energy_required;
+            let productivity = effects.productivity();
+            let rates = |amounts: &[(String, f64)], factor: f64| -> Items {
+                let mut items = Items::new();
+                for (name, amount) in amounts {
+                    *items.entry(name.clone()).or_default() += amount * factor;
+                }
+                items
+            };
+            report.machines.push(Machine {
+                entity_number: number,
+                recipe: recipe.name.clone(),
+                effects,
+                crafts_per_second,
+                ingredients: rates(&recipe.ingredients, crafts_per_second),
+                products: rates(&recipe.products, crafts_per_second * productivity),
+            });
+        }
+        report.unknown.sort_unstable();
+        report.bottlenecks = report.find_bottlenecks(blueprint, db);
+        report
+    }
+
+    /// Follow the items the machines need through inserters and belts, and find the stages that
+    /// can't keep up. Items aren't told apart, so a belt carrying several counts them all.
+    fn find_bottlenecks(&self, blueprint: &Blueprint, db: &PrototypeDb) -> Vec<Bottleneck> {
+        let tiles = occupied_tiles(blueprint, db);
+        let at = |position: &Position| {
+            tiles
+                .get(&(position.x.floor() as i64, position.y.floor() as i64))
+                .map(|entity| entity.entity_number.get())
+        };
+        let solid = |items: &Items| -> f64 {
+            items
+                .iter()
+                .filter(|(name, _)| !db.is_fluid(name))
+                // Not `sum`, which gives -0.0 for no items
+                .fold(0.0, |sum, (_, rate)| sum + rate)
+        };
+        let machines: BTreeMap<usize, &Machine> = self
+            .machines
+            .iter()
+            .map(|machine| (machine.entity_number, machine))
+            .collect();
+
+        // The inserters into and out of each machine, with how fast they move items
+        let mut inputs: BTreeMap<usize, Vec<(f64, Option<usize>)>> = BTreeMap::new();
+        let mut outputs: BTreeMap<usize, Vec<(f64, Option<usize>)>> = BTreeMap::new();
+        // Other inserters taking from and putting onto belts, at full speed
+        let mut picked = BTreeMap::new();
+        let mut dropped = BTreeMap::new();
+        for entity in blueprint.entities.iter().flatten() {
+            let Some(inserter) = entity.prototype(db).and_then(|p| p.inserter) else {
+                continue;
+            };
+            let Some((pickup, drop)) = entity.inserter_positions(db) else {
+                continue;
+            };
+            let speed = inserter.items_per_second;
+            let (from, to) = (at(&pickup), at(&drop));
+            match (from.filter(|from| machines.contains_key(from)), to) {
+                (Some(machine), to) => outputs.entry(machine).or_default().push((speed, to)),
+                (None, Some(to)) if !machines.contains_key(&to) => {
+                    if let Some(from) = from {
+                        *picked.entry(from).or_default() += speed;
+                        *dropped.entry(to).or_default() += speed;
+                    }
+                }
+                _ => {}
+            }
+            if let Some(machine) = to.filter(|to| machines.contains_key(to)) {
+                inputs.entry(machine).or_default().push((speed, from));
+            }
+        }
+
+        let mut bottlenecks = Vec::new();
+        // Each inserter's share of what its machine needs moved, taken from or put onto
+        let share =
+            |needed: f64, inserters: &[(f64, Option<usize>)], belts: &mut BTreeMap<usize, f64>| {
+                let capacity = inserters.iter().fold(0.0, |sum, &(speed, _)| sum + speed);
+                for &(speed, belt) in inserters {
+                    if let Some(belt) = belt.filter(|belt| !machines.contains_key(belt)) {
+                        *belts.entry(belt).or_default() += speed.min(needed * speed / capacity);
+                    }
+                }
+                capacity
+            };
+        for (&number, machine) in &machines {
+            let needed = solid(&machine.ingredients);
+            if needed > TOLERANCE {
+                let capacity = share(
+                    needed,
+                    inputs.get(&number).map_or(&[], Vec::as_slice),
+                    &mut picked,
+                );
+                let stage = if capacity > 0.0 {
+                    Stage::InputInserters
+                } else {
+                    Stage::Starved
+                };
+                if capacity < needed - TOLERANCE {
+                    bottlenecks.push(Bottleneck {
+                        stage,
+                        entity_number: number,
+                        needed,
+                        capacity,
+                    });
+                }
+            }
+            let needed = solid(&machine.products);
+            if needed > TOLERANCE {
+                let capacity = share(
+                    needed,
+                    outputs.get(&number).map_or(&[], Vec::as_slice),
+                    &mut dropped,
+                );
+                if capacity < needed - TOLERANCE {
+                    bottlenecks.push(Bottleneck {
+                        stage: Stage::OutputInserters,
+                        entity_number: number,
+                        needed,
+                        capacity,
+                    });
+                }
+            }
+        }
+
+        bottlenecks.extend(saturated_belts(
+            &BeltGraph::new(blueprint, db),
+            &picked,
+            &dropped,
+        ));
+        bottlenecks.sort_by(|a, b| {
+            a.fraction()
+                .total_cmp(&b.fraction())
+                .then(a.entity_number.cmp(&b.entity_number))
+        });
+        bottlenecks
+    }
+
+    /// The items and fluids every machine together makes each second.
+    pub fn produced(&self) -> Items {
+        sum(self.machines.iter().map(|machine| &machine.products))
+    }
+
+    /// The items and fluids every machine together uses each second.
+    pub fn consumed(&self) -> Items {
+        sum(self.machines.iter().map(|machine| &machine.ingredients))
+    }
+
+    /// The items and fluids made each second less those used, negative for those used more than
+    /// made. Ones made and used just as fast are left out.
+    pub fn net(&self) -> Items {
+        let mut net = self.produced();
+        for (name, amount) in self.consumed() {
+            *net.entry(name).or_default() -= amount;
+        }
+        net.retain(|_, amount| amount.abs() > 1e-9);
+        net
+    }
+}
+
+/// All of `items` added together.
+fn sum<'a>(items: impl Iterator<Item = &'a Items>) -> Items {
+    let mut total = Items::new();
+    for items in items {
+        for (name, amount) in items {
+            *total.entry(name.clone()).or_default() += amount;
+        }
+    }
+    total
+}
+
+/// The entity covering each tile, leaving out inserters so they don't hide what they reach.
+fn occupied_tiles<'a>(
+    blueprint: &'a Blueprint,
+    db: &PrototypeDb,
+) -> BTreeMap<(i64, i64), &'a Entity> {
+    let mut tiles = BTreeMap::new();
+    for entity in blueprint.entities.iter().flatten() {
+        let Some(prototype) = entity.prototype(db) else {
+            continue;
+        };
+        let Some(footprint) = entity
+            .footprint(db)
+            .filter(|_| prototype.inserter.is_none())
+        else {
+            continue;
+        };
+        for tile in footprint.tiles() {
+            tiles.insert(tile, entity);
+        }
+    }
+    tiles
+}
+
+/// The stretches of belt in `graph` that can't carry what inserters take off them at `picked`
+/// and put on at `dropped`, in items per second by belt. Each is reported at its busiest belt.
+///
+/// Inserters take what was put on further up the belt first, and anything more comes onto the
+/// belts from outside the blueprint, shared evenly where belts merge.
+fn saturated_belts(
+    graph: &BeltGraph,
+    picked: &BTreeMap<usize, f64>,
+    dropped: &BTreeMap<usize, f64>,
+) -> Vec<Bottleneck> {
+    let get = |rates: &BTreeMap<usize, f64>, node| rates.get(&node).copied().unwrap_or_default();
+
+    // Upstream first, with any loops after the rest
+    let mut order = Vec::new();
+    let mut waiting: BTreeMap<usize, usize> = graph
+        .nodes()
+        .map(|node| (node, graph.previous(node).len()))
+        .collect();
+    let mut queue: VecDeque<usize> = waiting
+        .iter()
+        .filter(|&(_, &count)| count == 0)
+        .map(|(&node, _)| node)
+        .collect();
+    while let Some(node) = queue.pop_front() {
+        order.push(node);
+        for &next in graph.next(node) {
+            if let Some(count) = waiting.get_mut(&next) {
+                *count -= 1;
+                if *count == 0 {
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+    let ordered: BTreeSet<usize> = order.iter().copied().collect();
+    order.extend(graph.nodes().filter(|node| !ordered.contains(node)));
+
+    // What arrives from further up, and what inserters want that nothing further up put on
+    let mut arriving: BTreeMap<usize, f64> = BTreeMap::new();
+    let mut unmet = BTreeMap::new();
+    for &node in &order {
+        let available = get(&arriving, node) + get(dropped, node);
+        unmet.insert(node, (get(picked, node) - available).max(0.0));
+        let leaving = (available - get(picked, node)).max(0.0);
+        let next = graph.next(node);
+        for &to in next {
+            *arriving.entry(to).or_default() += leaving / next.len() as f64;
+        }
+    }
+    // What has to come from outside the blueprint through each belt
+    let mut supplied: BTreeMap<usize, f64> = BTreeMap::new();
+    for &node in order.iter().rev() {
+        let downstream: f64 = graph
+            .next(node)
+            .iter()
+            .map(|&to| get(&supplied, to) / graph.previous(to).len() as f64)
+            .sum();
+        supplied.insert(node, get(&unmet, node) + downstream);
+    }
+
+    let load = |node| get(&arriving, node) + get(dropped, node) + get(&supplied, node);
+    let saturated = |node| {
+        !matches!(graph.kind(node), Some(NodeKind::Container))
+            && load(node) > graph.speed(node) + TOLERANCE
+    };
+    let mut bottlenecks = Vec::new();
+    let mut seen = BTreeSet::new();
+    for &node in &order {
+        if !saturated(node) || !seen.insert(node) {
+            continue;
+        }
+        // The whole saturated stretch, reported at its busiest belt
+        let mut busiest = node;
+        let mut stack = vec![node];
+        while let Some(node) = stack.pop() {
+            if load(node) > load(busiest) {
+                busiest = node;
+            }
+            for &neighbour in graph.next(node).iter().chain(graph.previous(node)) {
+                if saturated(neighbour) && seen.insert(neighbour) {
+                    stack.push(neighbour);
+                }
+            }
+        }
+        bottlenecks.push(Bottleneck {
+            stage: Stage::Belt,
+            entity_number: busiest,
+            needed: load(busiest),
+            capacity: graph.speed(busiest),
+        });
+    }
+    bottlenecks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bp;
+
+    #[test]
+    fn machines_work_at_their_crafting_speed() {
+        let db = PrototypeDb::vanilla();
+        let blueprint = bp! {
+            "assembling-machine-1" @ (0, 0) recipe "iron-gear-wheel";
+            "assembling-machine-2" @ (4, 0) recipe "iron-gear-wheel";
+            "assembling-machine-1" @ (8, 0);
+        }
+        .build_with(db)
+        .unwrap();
+        let report = ThroughputReport::new(&blueprint, db);
+
+        assert_eq!(report.machines.len(), 2);
+        let machine = &report.machines[0];
+        assert_eq!(machine.crafts_per_second, 1.0);
+        assert_eq!(
+            machine.ingredients,
+            Items::from([("iron-plate".into(), 2.0)])
+        );
+        assert_eq!(
+            machine.products,
+            Items::from([("iron-gear-wheel".into(), 1.0)])
+        );
+        assert_eq!(report.machines[1].crafts_per_second, 1.5);
+        assert_eq!(report.unknown, [3]);
+        assert_eq!(
+            report.net(),
+            Items::from([("iron-gear-wheel".into(), 2.5), ("iron-plate".into(), -5.0)])
+        );
+    }
+
+    #[test]
+    fn bottlenecks_are_ranked_worst_first() {
+        let db = PrototypeDb::vanilla();
+        let blueprint = bp! {
+            "assembling-machine-1" @ (0, 0) recipe "iron-gear-wheel";
+            "iron-chest" @ (1, -2);
+            "inserter" @ (1, -1);
+            "assembling-machine-1" @ (4, 0) recipe "iron-gear-wheel";
+        }
+        .build_with(db)
+        .unwrap();
+        let report = ThroughputReport::new(&blueprint, db);
+
+        let ranked: Vec<_> = report
+            .bottlenecks
+            .iter()
+            .map(|bottleneck| (bottleneck.stage, bottleneck.entity_number))
+            .collect();
+        assert_eq!(
+            ranked,
+            [
+                (Stage::OutputInserters, 1),
+                (Stage::Starved, 4),
+                (Stage::OutputInserters, 4),
+                (Stage::InputInserters, 1),
+            ]
+        );
+        let inserters = &report.bottlenecks[3];
+        assert_eq!((inserters.needed, inserters.capacity), (2.0, 0.83));
+        assert_eq!(
+            inserters.to_string(),
+            "inserters into machine 1 move 0.83/s, but it uses 2.00/s"
+        );
+    }
+}
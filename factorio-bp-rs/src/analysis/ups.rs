@@ -0,0 +1,238 @@
+use core::fmt;
+use std::collections::BTreeMap;
+
+use crate::{
+    blueprint::{Blueprint, Entity},
+    circuit::NetworkColor,
+    fluid::FluidGraph,
+    prototypes::PrototypeDb,
+};
+
+use super::pass::{run_passes, AnalysisPass, PassContext};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// The kinds of work that cost the game time each tick.
+pub enum Cost {
+    /// Inserters swinging.
+    Inserters,
+    /// Belts, underground belts, splitters, and loaders moving items.
+    Belts,
+    /// Roboports and logistic chests, standing in for the robots flying between them.
+    Robots,
+    /// Fluid boxes and the networks they make, sharing out fluid.
+    Fluids,
+    /// Crafting machines and labs working.
+    Machines,
+    /// Combinators working out their outputs.
+    Combinators,
+    /// Circuit networks adding up signals, and the entities reading and writing them.
+    CircuitNetworks,
+}
+
+impl Cost {
+    /// Every kind of cost, in order.
+    pub const ALL: [Self; 7] = [
+        Self::Inserters,
+        Self::Belts,
+        Self::Robots,
+        Self::Fluids,
+        Self::Machines,
+        Self::Combinators,
+        Self::CircuitNetworks,
+    ];
+}
+
+impl fmt::Display for Cost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Inserters => "inserters",
+            Self::Belts => "belts",
+            Self::Robots => "robots",
+            Self::Fluids => "fluids",
+            Self::Machines => "machines",
+            Self::Combinators => "combinators",
+            Self::CircuitNetworks => "circuit networks",
+        })
+    }
+}
+
+// Rough costs of one of each thing, relative to an inserter. Belts are cheap since the game moves
+// a whole line of them at once, and a roboport stands in for the robots it serves.
+const INSERTER: f64 = 1.0;
+const BELT: f64 = 0.1;
+const ROBOPORT: f64 = 5.0;
+const LOGISTIC_CHEST: f64 = 1.0;
+const FLUID_NETWORK: f64 = 1.0;
+const FLUID_BOX: f64 = 0.1;
+const MACHINE: f64 = 1.0;
+const IDLE_MACHINE: f64 = 0.1;
+const COMBINATOR: f64 = 0.5;
+const CIRCUIT_NETWORK: f64 = 0.5;
+const CIRCUIT_CONNECTOR: f64 = 0.05;
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// A rough, relative guess at how much a blueprint slows the game down, for comparing designs
+/// that do the same job. Lower is better.
+pub struct UpsReport {
+    /// The number of inserters.
+    pub inserters: usize,
+    /// The number of belts, underground belts, splitters, and loaders.
+    pub belts: usize,
+    /// The number of roboports.
+    pub roboports: usize,
+    /// The number of logistic chests.
+    pub logistic_chests: usize,
+    /// The number of separate fluid networks.
+    pub fluid_networks: usize,
+    /// The number of fluid boxes.
+    pub fluid_boxes: usize,
+    /// The number of crafting machines and labs that can work, those with a recipe or not
+    /// needing one.
+    pub active_machines: usize,
+    /// The number of crafting machines with no recipe set, which sit idle.
+    pub idle_machines: usize,
+    /// The number of arithmetic, decider, and other combinators that work out outputs.
+    pub combinators: usize,
+    /// The number of separate red and green circuit networks.
+    pub circuit_networks: usize,
+    /// The number of connectors on circuit networks, counted once per network.
+    pub circuit_connectors: usize,
+}
+
+impl UpsReport {
+    /// Count the entities of `blueprint` that cost the game time, with entity kinds from `db`.
+    pub fn new(blueprint: &Blueprint, db: &PrototypeDb) -> Self {
+        let mut report = Self::default();
+        run_passes(blueprint, db, &mut [&mut report]);
+        report
+    }
+
+    /// The score of each kind of cost.
+    pub fn breakdown(&self) -> BTreeMap<Cost, f64> {
+        Cost::ALL
+            .into_iter()
+            .map(|cost| (cost, self.cost(cost)))
+            .collect()
+    }
+
+    /// The score of one kind of cost.
+    pub fn cost(&self, cost: Cost) -> f64 {
+        match cost {
+            Cost::Inserters => self.inserters as f64 * INSERTER,
+            Cost::Belts => self.belts as f64 * BELT,
+            Cost::Robots => {
+                self.roboports as f64 * ROBOPORT + self.logistic_chests as f64 * LOGISTIC_CHEST
+            }
+            Cost::Fluids => {
+                self.fluid_networks as f64 * FLUID_NETWORK + self.fluid_boxes as f64 * FLUID_BOX
+            }
+            Cost::Machines => {
+                self.active_machines as f64 * MACHINE + self.idle_machines as f64 * IDLE_MACHINE
+            }
+            Cost::Combinators => self.combinators as f64 * COMBINATOR,
+            Cost::CircuitNetworks => {
+                self.circuit_networks as f64 * CIRCUIT_NETWORK
+                    + self.circuit_connectors as f64 * CIRCUIT_CONNECTOR
+            }
+        }
+    }
+
+    /// The score of the whole blueprint, in inserters' worth of work.
+    pub fn score(&self) -> f64 {
+        Cost::ALL
+            .into_iter()
+            .fold(0.0, |sum, cost| sum + self.cost(cost))
+    }
+}
+
+impl AnalysisPass for UpsReport {
+    fn entity(&mut self, entity: &Entity, context: &PassContext) {
+        let Some(prototype) = entity.prototype(context.db) else {
+            return;
+        };
+        if prototype.inserter.is_some() {
+            self.inserters += 1;
+        } else if prototype.transport_belt.is_some() {
+            self.belts += 1;
+        } else if entity.name == "roboport" {
+            self.roboports += 1;
+        } else if is_logistic_chest(&entity.name) {
+            self.logistic_chests += 1;
+        } else if entity.name.contains("combinator") && !entity.name.contains("constant") {
+            self.combinators += 1;
+        } else if prototype.lab.is_some() {
+            self.active_machines += 1;
+        } else if let Some(machine) = &prototype.crafting_machine {
+            // Furnaces pick their recipe from what they are given
+            let needs_recipe = !machine.crafting_categories.iter().any(|c| c == "smelting");
+            if needs_recipe && entity.recipe().is_none() {
+                self.idle_machines += 1;
+            } else {
+                self.active_machines += 1;
+            }
+        }
+    }
+
+    fn finish(&mut self, context: &PassContext) {
+        let fluids = FluidGraph::new(context.blueprint, context.db);
+        self.fluid_networks = fluids.networks().len();
+        self.fluid_boxes = fluids.fluid_boxes().count();
+
+        for color in [NetworkColor::Red, NetworkColor::Green] {
+            for network in context.circuits.networks(color) {
+                self.circuit_networks += 1;
+                self.circuit_connectors += network.connectors.len();
+            }
+        }
+    }
+}
+
+/// Whether `name` is a logistic chest, under its 1.x or 2.0 name.
+fn is_logistic_chest(name: &str) -> bool {
+    name.starts_with("logistic-chest-")
+        || matches!(
+            name,
+            "active-provider-chest"
+                | "passive-provider-chest"
+                | "storage-chest"
+                | "buffer-chest"
+                | "requester-chest"
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bp;
+
+    #[test]
+    fn entities_are_counted_by_what_they_cost() {
+        let db = PrototypeDb::vanilla();
+        let blueprint = bp! {
+            "inserter" @ (0, 0);
+            "transport-belt" @ (1, 0);
+            "transport-belt" @ (2, 0);
+            "roboport" @ (0, 2);
+            "assembling-machine-1" @ (5, 2);
+            "assembling-machine-1" @ (8, 2) recipe "iron-gear-wheel";
+            "stone-furnace" @ (11, 2);
+            "decider-combinator" @ (14, 2);
+            "constant-combinator" @ (15, 2);
+        }
+        .build_with(db)
+        .unwrap();
+        let report = UpsReport::new(&blueprint, db);
+
+        assert_eq!(report.inserters, 1);
+        assert_eq!(report.belts, 2);
+        assert_eq!(report.roboports, 1);
+        assert_eq!(report.active_machines, 2);
+        assert_eq!(report.idle_machines, 1);
+        assert_eq!(report.combinators, 1);
+        assert_eq!(report.circuit_networks, 0);
+        assert_eq!(report.cost(Cost::Robots), 5.0);
+        assert_eq!(report.cost(Cost::Machines), 2.1);
+        assert!((report.score() - 8.8).abs() < 1e-9);
+        assert_eq!(report.breakdown().len(), Cost::ALL.len());
+    }
+}
@@ -0,0 +1,163 @@
+use std::collections::BTreeMap;
+
+use crate::blueprint::{
+    Blueprint, EntityDetails, EntityItems, LogisticRequests, ScheduleData, SplitterFilter, Version,
+    MIGRATIONS,
+};
+
+/// The version assumed for blueprints using nothing newer. Older versions aren't told apart.
+pub const BASELINE: Version = Version::new(1, 0, 0, 0);
+
+/// The first version with the blueprint format of 2.0: wires, quality, parameters, and the rest.
+const FORMAT_2_0: Version = Version::new(2, 0, 0, 0);
+
+#[derive(Debug, Clone, PartialEq)]
+/// The oldest game version a blueprint can be pasted in, from what it uses.
+pub struct VersionReport {
+    /// The version the blueprint says it was made in.
+    pub stored: Version,
+    /// Each entity and blueprint feature used that is newer than [BASELINE], by entity name or
+    /// feature, e.g. "wires", and the version adding it.
+    pub features: BTreeMap<String, Version>,
+}
+
+impl VersionReport {
+    /// Find what `blueprint` uses that older versions don't have.
+    pub fn new(blueprint: &Blueprint) -> Self {
+        let mut features = BTreeMap::new();
+        let mut feature = |name: &str, used: bool| {
+            if used {
+                features.insert(name.to_owned(), FORMAT_2_0);
+            }
+        };
+
+        feature("wires", blueprint.wires.is_some());
+        feature("parameters", blueprint.parameters.is_some());
+        let icon_qualities = blueprint.icons.iter().any(|i| i.signal.quality.is_some());
+        feature("quality", icon_qualities);
+        let schedules = blueprint.schedules.iter().flatten();
+        feature(
+            "train schedule groups",
+            schedules
+                .map(|s| &s.schedule)
+                .any(|schedule| match schedule {
+                    ScheduleData::Legacy(_) => false,
+                    ScheduleData::Schedule(schedule) => {
+                        schedule.group.is_some() || !schedule.interrupts.is_empty()
+                    }
+                }),
+        );
+        for entity in blueprint.entities.iter().flatten() {
+            feature("mirror", entity.mirror.is_some());
+            let details_quality = match &entity.details {
+                EntityDetails::Splitter {
+                    filter: Some(SplitterFilter::Item(id)),
+                    ..
+                } => id.quality.is_some(),
+                EntityDetails::CraftingMachine { recipe_quality, .. } => recipe_quality.is_some(),
+                _ => false,
+            };
+            feature(
+                "quality",
+                entity.quality.is_some()
+                    || details_quality
+                    || entity.filters().iter().any(|f| f.quality.is_some()),
+            );
+            let use_filters = matches!(
+                entity.details,
+                EntityDetails::Inserter {
+                    use_filters: Some(_),
+                    ..
+                }
+            );
+            feature("use_filters", use_filters);
+            feature(
+                "insert plans",
+                matches!(entity.items, Some(EntityItems::InsertPlans(_))),
+            );
+            feature(
+                "logistic sections",
+                matches!(
+                    entity.request_filters(),
+                    Some(LogisticRequests::Sections(_))
+                ),
+            );
+            let direction = entity.direction.unwrap_or_default();
+            feature("16 directions", direction.to_legacy().is_none());
+        }
+
+        for migration in MIGRATIONS {
+            let names = blueprint.entities.iter().flatten().map(|e| e.name.as_str());
+            for name in names {
+                let renamed = migration.revert(name, false).is_some();
+                if renamed || migration.added.contains(&name) {
+                    features.insert(name.to_owned(), migration.version);
+                }
+            }
+        }
+
+        Self {
+            stored: blueprint.version,
+            features,
+        }
+    }
+
+    /// The oldest version that can paste the blueprint.
+    pub fn required(&self) -> &Version {
+        self.features.values().max().unwrap_or(&BASELINE)
+    }
+
+    /// Whether the stored version is at least the one required, as it is for any blueprint the
+    /// game exported.
+    pub fn is_consistent(&self) -> bool {
+        self.stored >= *self.required()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        blueprint::{BlueprintBuilder, EntityBuilder, Quality},
+        prototypes::PrototypeDb,
+    };
+
+    #[test]
+    fn the_newest_thing_used_sets_the_version() {
+        let db = PrototypeDb::vanilla();
+        let old = Version::new(1, 1, 0, 0);
+        let belt = BlueprintBuilder::new()
+            .version(old)
+            .entity(EntityBuilder::new("transport-belt").at_tile(0, 0))
+            .build_with(db)
+            .unwrap();
+        let report = VersionReport::new(&belt);
+        assert!(report.features.is_empty());
+        assert_eq!(report.required(), &BASELINE);
+        assert!(report.is_consistent());
+
+        let spidertron = BlueprintBuilder::new()
+            .version(old)
+            .entity(EntityBuilder::new("spidertron").at(0.0, 0.0))
+            .build_with(db)
+            .unwrap();
+        assert_eq!(VersionReport::new(&spidertron).required(), &old);
+
+        let quality = BlueprintBuilder::new()
+            .version(old)
+            .entity(
+                EntityBuilder::new("bulk-inserter")
+                    .at_tile(0, 0)
+                    .quality(Quality::Rare),
+            )
+            .build_with(db)
+            .unwrap();
+        let report = VersionReport::new(&quality);
+        assert_eq!(
+            report.features.keys().collect::<Vec<_>>(),
+            ["bulk-inserter", "quality"]
+        );
+        assert_eq!(report.required(), &FORMAT_2_0);
+        assert!(!report.is_consistent());
+    }
+}
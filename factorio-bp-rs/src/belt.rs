@@ -0,0 +1,495 @@
+use std::collections::{btree_map::Entry, BTreeMap, BTreeSet, HashMap, VecDeque};
+
+use crate::{
+    blueprint::{Blueprint, Direction, Entity, EntityDetails, IoPriority, IoType},
+    prototypes::{BeltKind, PrototypeDb},
+};
+
+mod flow;
+mod lanes;
+
+pub use flow::{Flow, Items};
+pub use lanes::{Lane, LaneGraph, LaneId};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+/// What a node of the belt graph is.
+pub enum NodeKind {
+    /// An ordinary belt.
+    Belt,
+    /// An underground belt entrance.
+    UndergroundInput,
+    /// An underground belt exit.
+    UndergroundOutput,
+    /// A splitter.
+    Splitter,
+    /// A loader moving items from a belt into an inventory.
+    LoaderInput,
+    /// A loader moving items from an inventory onto a belt.
+    LoaderOutput,
+    /// A machine or chest that loaders put items into or take them out of.
+    Container,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// How a splitter shares items between its two halves, or for a [LaneGraph], how one lane of it
+/// does.
+pub struct Splitter<N = usize> {
+    /// The nodes feeding the left and right halves, looking the way the splitter faces.
+    pub inputs: [Option<N>; 2],
+    /// The nodes the left and right halves feed.
+    pub outputs: [Option<N>; 2],
+    /// The side items are taken from first.
+    pub input_priority: Option<IoPriority>,
+    /// The side items are put on first, or with a filter, the side the filtered item goes to.
+    pub output_priority: Option<IoPriority>,
+    /// The item sent to the output priority side, with everything else going to the other.
+    pub filter: Option<String>,
+}
+
+/// The index into [Splitter::inputs] and [Splitter::outputs] of a side.
+pub(crate) fn side_index(side: &IoPriority) -> usize {
+    match side {
+        IoPriority::Left => 0,
+        IoPriority::Right => 1,
+    }
+}
+
+/// A belt entity placed in the blueprint.
+struct Placed<'a> {
+    entity: &'a Entity,
+    kind: NodeKind,
+    direction: Direction,
+    speed: f64,
+    tiles: Vec<(i64, i64)>,
+}
+
+impl Placed<'_> {
+    /// Which half of the entity `tile` is, as an index into [Splitter::inputs].
+    fn side(&self, tile: (i64, i64)) -> usize {
+        let (x, y) = self.direction.counter_clockwise().offset();
+        let dx = tile.0 as f64 + 0.5 - self.entity.position.x;
+        let dy = tile.1 as f64 + 0.5 - self.entity.position.y;
+        if dx * x + dy * y > 0.0 {
+            0
+        } else {
+            1
+        }
+    }
+}
+
+/// The tile `distance` steps from `tile` in `direction`, which must be cardinal.
+fn step(tile: (i64, i64), direction: Direction, distance: i64) -> (i64, i64) {
+    let (x, y) = direction.tile_offset().unwrap_or_default();
+    (tile.0 + x as i64 * distance, tile.1 + y as i64 * distance)
+}
+
+/// The tiles an entity covers, or just the one it's centred on if `db` doesn't know it.
+fn tiles(entity: &Entity, db: &PrototypeDb) -> Vec<(i64, i64)> {
+    match entity.footprint(db) {
+        Some(bounds) => {
+            let (min_x, min_y) = (bounds.min_x.round() as i64, bounds.min_y.round() as i64);
+            let (max_x, max_y) = (bounds.max_x.round() as i64, bounds.max_y.round() as i64);
+            (min_y..max_y)
+                .flat_map(|y| (min_x..max_x).map(move |x| (x, y)))
+                .collect()
+        }
+        None => vec![(
+            entity.position.x.floor() as i64,
+            entity.position.y.floor() as i64,
+        )],
+    }
+}
+
+/// Whether `to` takes items from a belt entity moving them in `direction` into it.
+fn accepts(to: &Placed, direction: Direction) -> bool {
+    match to.kind {
+        // Anything but head on, which includes sideloading
+        NodeKind::Belt | NodeKind::UndergroundInput => direction != to.direction.opposite(),
+        // The back of an exit is its hood, so only sideloading
+        NodeKind::UndergroundOutput => {
+            direction != to.direction && direction != to.direction.opposite()
+        }
+        NodeKind::Splitter | NodeKind::LoaderInput => direction == to.direction,
+        NodeKind::LoaderOutput | NodeKind::Container => false,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// The flow of items between the belts of a blueprint, as a directed graph.
+///
+/// Nodes are belts, underground belts, splitters, and loaders, along with the machines and chests
+/// loaders feed. Edges go the way items move, worked out from entity positions and directions:
+/// each belt entity leads to whatever takes items from the tiles in front of it, and underground
+/// entrances lead to their exit. Items flow the way belt entities face, which for loaders means
+/// from the belt to the inventory for input loaders and the other way for output ones.
+pub struct BeltGraph {
+    kinds: BTreeMap<usize, NodeKind>,
+    directions: BTreeMap<usize, Direction>,
+    speeds: BTreeMap<usize, f64>,
+    splitters: BTreeMap<usize, Splitter>,
+    next: BTreeMap<usize, Vec<usize>>,
+    previous: BTreeMap<usize, Vec<usize>>,
+}
+
+impl BeltGraph {
+    /// The belts of `blueprint`, with their sizes and kinds from `db`.
+    ///
+    /// Belt entities facing between the cardinal directions are left out, as belts can't.
+    pub fn new(blueprint: &Blueprint, db: &PrototypeDb) -> Self {
+        let entities: Vec<&Entity> = blueprint.entities.iter().flatten().collect();
+        let mut placed = Vec::new();
+        for entity in &entities {
+            let Some(belt) = entity
+                .prototype(db)
+                .and_then(|prototype| prototype.transport_belt)
+            else {
+                continue;
+            };
+            let direction = entity.direction.unwrap_or_default();
+            if !direction.is_cardinal() {
+                continue;
+            }
+            let output = matches!(entity.io_type(), Some(IoType::Output));
+            let kind = match (belt.kind, output) {
+                (BeltKind::Belt, _) => NodeKind::Belt,
+                (BeltKind::Underground { .. }, false) => NodeKind::UndergroundInput,
+                (BeltKind::Underground { .. }, true) => NodeKind::UndergroundOutput,
+                (BeltKind::Splitter, _) => NodeKind::Splitter,
+                (BeltKind::Loader, false) => NodeKind::LoaderInput,
+                (BeltKind::Loader, true) => NodeKind::LoaderOutput,
+            };
+            placed.push(Placed {
+                entity,
+                kind,
+                direction,
+                speed: belt.speed,
+                tiles: tiles(entity, db),
+            });
+        }
+
+        let mut belt_at: HashMap<(i64, i64), usize> = HashMap::new();
+        for (i, belt) in placed.iter().enumerate() {
+            for &tile in &belt.tiles {
+                belt_at.insert(tile, i);
+            }
+        }
+        // Anything else loaders might reach
+        let mut other_at: HashMap<(i64, i64), usize> = HashMap::new();
+        for entity in &entities {
+            if entity
+                .prototype(db)
+                .is_some_and(|prototype| prototype.transport_belt.is_none())
+            {
+                for tile in tiles(entity, db) {
+                    other_at.insert(tile, entity.entity_number.get());
+                }
+            }
+        }
+
+        let mut graph = Self::default();
+        for belt in &placed {
+            let number = belt.entity.entity_number.get();
+            graph.kinds.insert(number, belt.kind);
+            graph.directions.insert(number, belt.direction);
+            graph.speeds.insert(number, belt.speed);
+            if belt.kind == NodeKind::Splitter {
+                let mut splitter = Splitter::default();
+                if let EntityDetails::Splitter {
+                    input_priority,
+                    output_priority,
+                    filter,
+                } = &belt.entity.details
+                {
+                    splitter.input_priority = input_priority.clone();
+                    splitter.output_priority = output_priority.clone();
+                    splitter.filter = filter.as_ref().map(|filter| filter.name().to_string());
+                }
+                graph.splitters.insert(number, splitter);
+            }
+        }
+        for belt in &placed {
+            let number = belt.entity.entity_number.get();
+            // The tiles just outside the entity, in front and behind
+            let beyond = |distance: i64| -> Vec<(i64, i64)> {
+                belt.tiles
+                    .iter()
+                    .map(|&tile| step(tile, belt.direction, distance))
+                    .filter(|tile| !belt.tiles.contains(tile))
+                    .collect()
+            };
+
+            match belt.kind {
+                NodeKind::UndergroundInput => {
+                    if let Some(exit) = Self::exit(belt, &placed, &belt_at, db) {
+                        graph.add_edge(number, exit);
+                    }
+                }
+                NodeKind::LoaderInput => {
+                    let containers: BTreeSet<usize> = beyond(1)
+                        .iter()
+                        .filter_map(|tile| other_at.get(tile).copied())
+                        .collect();
+                    for container in containers {
+                        graph.kinds.insert(container, NodeKind::Container);
+                        graph.add_edge(number, container);
+                    }
+                }
+                _ => {
+                    for tile in beyond(1) {
+                        let Some(&i) = belt_at.get(&tile) else {
+                            continue;
+                        };
+                        let target = &placed[i];
+                        if !accepts(target, belt.direction) {
+                            continue;
+                        }
+                        let target_number = target.entity.entity_number.get();
+                        graph.add_edge(number, target_number);
+                        if let Some(splitter) = graph.splitters.get_mut(&target_number) {
+                            splitter.inputs[target.side(tile)] = Some(number);
+                        }
+                        if let Some(splitter) = graph.splitters.get_mut(&number) {
+                            let from = step(tile, belt.direction, -1);
+                            splitter.outputs[belt.side(from)] = Some(target_number);
+                        }
+                    }
+                }
+            }
+
+            if belt.kind == NodeKind::LoaderOutput {
+                let containers: BTreeSet<usize> = beyond(-1)
+                    .iter()
+                    .filter_map(|tile| other_at.get(tile).copied())
+                    .collect();
+                for container in containers {
+                    graph.kinds.insert(container, NodeKind::Container);
+                    graph.add_edge(container, number);
+                }
+            }
+        }
+
+        for nodes in graph.next.values_mut().chain(graph.previous.values_mut()) {
+            nodes.sort_unstable();
+            nodes.dedup();
+        }
+        graph
+    }
+
+    /// Record that items move from `from` into `to`.
+    fn add_edge(&mut self, from: usize, to: usize) {
+        self.next.entry(from).or_default().push(to);
+        self.previous.entry(to).or_default().push(from);
+    }
+
+    /// The exit an underground entrance leads to: the nearest underground belt of the same name
+    /// in line within reach, if that is an exit facing the same way.
+    fn exit(
+        entrance: &Placed,
+        placed: &[Placed],
+        belt_at: &HashMap<(i64, i64), usize>,
+        db: &PrototypeDb,
+    ) -> Option<usize> {
+        let max_distance = match entrance.entity.prototype(db)?.transport_belt?.kind {
+            BeltKind::Underground { max_distance } => max_distance,
+            _ => return None,
+        };
+        let start = *entrance.tiles.first()?;
+        for distance in 1..=max_distance as i64 {
+            let tile = step(start, entrance.direction, distance);
+            let Some(&i) = belt_at.get(&tile) else {
+                continue;
+            };
+            let other = &placed[i];
+            let in_line = other.direction == entrance.direction
+                || other.direction == entrance.direction.opposite();
+            if other.entity.name == entrance.entity.name && in_line {
+                let paired = other.kind == NodeKind::UndergroundOutput
+                    && other.direction == entrance.direction;
+                return paired.then(|| other.entity.entity_number.get());
+            }
+        }
+        None
+    }
+
+    /// Every node, in order.
+    pub fn nodes(&self) -> impl Iterator<Item = usize> + '_ {
+        self.kinds.keys().copied()
+    }
+
+    /// What the node is, or `None` if it isn't part of the graph.
+    pub fn kind(&self, node: usize) -> Option<NodeKind> {
+        self.kinds.get(&node).copied()
+    }
+
+    /// The way the node faces, or `None` for containers and nodes that aren't part of the graph.
+    pub fn direction(&self, node: usize) -> Option<Direction> {
+        self.directions.get(&node).copied()
+    }
+
+    /// The most items per second the node moves, over both lanes and for splitters, over each
+    /// half. Containers take any number.
+    pub fn speed(&self, node: usize) -> f64 {
+        match self.kinds.get(&node) {
+            Some(NodeKind::Container) => f64::INFINITY,
+            _ => self.speeds.get(&node).copied().unwrap_or_default(),
+        }
+    }
+
+    /// The halves and settings of a splitter, or `None` if the node isn't one.
+    pub fn splitter(&self, node: usize) -> Option<&Splitter> {
+        self.splitters.get(&node)
+    }
+
+    /// Whether `from` moves items onto the side of `to`, which puts them all on one lane, rather
+    /// than from behind or round a curve.
+    pub fn is_sideload(&self, from: usize, to: usize) -> bool {
+        let (Some(from_direction), Some(to_direction)) = (self.direction(from), self.direction(to))
+        else {
+            return false;
+        };
+        match self.kind(to) {
+            // A belt fed only from the side curves instead
+            Some(NodeKind::Belt) => from_direction != to_direction && self.previous(to).len() > 1,
+            Some(NodeKind::UndergroundInput | NodeKind::UndergroundOutput) => {
+                from_direction != to_direction
+            }
+            _ => false,
+        }
+    }
+
+    /// The nodes `node` moves items into.
+    pub fn next(&self, node: usize) -> &[usize] {
+        self.next.get(&node).map_or(&[], Vec::as_slice)
+    }
+
+    /// The nodes that move items into `node`.
+    pub fn previous(&self, node: usize) -> &[usize] {
+        self.previous.get(&node).map_or(&[], Vec::as_slice)
+    }
+
+    /// The nodes nothing moves items into, where items enter the belts.
+    pub fn sources(&self) -> Vec<usize> {
+        self.nodes()
+            .filter(|&node| self.previous(node).is_empty())
+            .collect()
+    }
+
+    /// The nodes that move items into nothing, where items leave the belts.
+    pub fn sinks(&self) -> Vec<usize> {
+        self.nodes()
+            .filter(|&node| self.next(node).is_empty())
+            .collect()
+    }
+
+    /// The belt entities that lead nowhere, where items stop unless inserters take them off.
+    /// This includes underground entrances without an exit.
+    pub fn dead_ends(&self) -> Vec<usize> {
+        self.nodes()
+            .filter(|&node| {
+                self.next(node).is_empty()
+                    && !matches!(
+                        self.kinds[&node],
+                        NodeKind::LoaderInput | NodeKind::Container
+                    )
+            })
+            .collect()
+    }
+
+    /// Every node items from `node` can reach, including `node` itself.
+    pub fn reachable(&self, node: usize) -> BTreeSet<usize> {
+        let mut seen = BTreeSet::from([node]);
+        let mut queue = vec![node];
+        while let Some(next) = queue.pop() {
+            for &neighbor in self.next(next) {
+                if seen.insert(neighbor) {
+                    queue.push(neighbor);
+                }
+            }
+        }
+        seen
+    }
+
+    /// The shortest path items can take from `from` to `to`, both included, or `None` if they
+    /// can't get there.
+    pub fn path(&self, from: usize, to: usize) -> Option<Vec<usize>> {
+        if !self.kinds.contains_key(&from) {
+            return None;
+        }
+
+        let mut came_from = BTreeMap::from([(from, from)]);
+        let mut queue = VecDeque::from([from]);
+        while let Some(next) = queue.pop_front() {
+            if next == to {
+                let mut path = vec![to];
+                let mut node = to;
+                while node != from {
+                    node = came_from[&node];
+                    path.push(node);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for &neighbor in self.next(next) {
+                if let Entry::Vacant(entry) = came_from.entry(neighbor) {
+                    entry.insert(next);
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blueprint::{BlueprintBuilder, EntityBuilder};
+
+    /// A belt facing `direction` on the tile `(x, y)`.
+    fn belt(x: i64, y: i64, direction: Direction) -> EntityBuilder {
+        EntityBuilder::new("transport-belt")
+            .at_tile(x, y)
+            .direction(direction)
+    }
+
+    /// An underground belt facing east on the tile `(x, y)`.
+    fn underground(x: i64, y: i64, io_type: IoType) -> EntityBuilder {
+        EntityBuilder::new("underground-belt")
+            .at_tile(x, y)
+            .direction(Direction::East)
+            .details(EntityDetails::UndergroundBelt {
+                io_type: Some(io_type),
+            })
+    }
+
+    #[test]
+    fn items_follow_belts_undergrounds_and_sideloads() {
+        let db = PrototypeDb::vanilla();
+        let blueprint = BlueprintBuilder::new()
+            .entity(belt(0, 0, Direction::East))
+            .entity(belt(1, 0, Direction::East))
+            .entity(underground(2, 0, IoType::Input))
+            .entity(underground(5, 0, IoType::Output))
+            .entity(belt(6, 0, Direction::East))
+            .entity(belt(1, 1, Direction::North))
+            .entity(belt(10, 0, Direction::West))
+            .build_with(db)
+            .unwrap();
+        let graph = BeltGraph::new(&blueprint, db);
+
+        assert_eq!(graph.kind(3), Some(NodeKind::UndergroundInput));
+        assert_eq!(graph.kind(4), Some(NodeKind::UndergroundOutput));
+        assert_eq!(graph.speed(1), 15.0);
+        assert_eq!(graph.next(3), [4]);
+        assert_eq!(graph.previous(2), [1, 6]);
+        assert!(graph.is_sideload(6, 2));
+        assert!(!graph.is_sideload(1, 2));
+
+        assert_eq!(graph.sources(), [1, 6, 7]);
+        assert_eq!(graph.sinks(), [5, 7]);
+        assert_eq!(graph.dead_ends(), [5, 7]);
+        assert_eq!(graph.path(6, 5), Some(vec![6, 2, 3, 4, 5]));
+        assert_eq!(graph.path(5, 1), None);
+        assert_eq!(graph.reachable(4), BTreeSet::from([4, 5]));
+    }
+}
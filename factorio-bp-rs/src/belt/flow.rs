@@ -0,0 +1,500 @@
+//! Working out how many items move along each belt once they settle into a steady flow.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::{side_index, BeltGraph, Splitter};
+
+/// Items per second, by item name.
+pub type Items = BTreeMap<String, f64>;
+
+/// Flows closer than this, in items per second, count as settled.
+const TOLERANCE: f64 = 1e-9;
+/// How many rounds of passing items along to try before giving up on them settling.
+const MAX_ITERATIONS: usize = 10_000;
+
+/// The total of `items`.
+fn total(items: &Items) -> f64 {
+    // Not `sum`, which gives -0.0 for no items
+    items.values().fold(0.0, |sum, rate| sum + rate)
+}
+
+/// `items` scaled by `factor`, leaving out items that scale to nothing.
+fn scaled(items: &Items, factor: f64) -> Items {
+    items
+        .iter()
+        .map(|(name, rate)| (name.clone(), rate * factor))
+        .filter(|(_, rate)| *rate > 0.0)
+        .collect()
+}
+
+/// `items` scaled down to `rate` in total, keeping their proportions.
+fn limited(items: &Items, rate: f64) -> Items {
+    let offered = total(items);
+    if offered > 0.0 {
+        scaled(items, rate.min(offered) / offered)
+    } else {
+        Items::new()
+    }
+}
+
+/// Add `items` to `into`.
+fn add(into: &mut Items, items: &Items) {
+    for (name, rate) in items {
+        *into.entry(name.clone()).or_default() += rate;
+    }
+}
+
+/// Share `total` between `offers`, in equal parts except that nothing gets more than it offers.
+fn fair_share(offers: &[f64], total: f64) -> Vec<f64> {
+    let mut order: Vec<usize> = (0..offers.len()).collect();
+    order.sort_by(|&a, &b| offers[a].total_cmp(&offers[b]));
+    let mut shares = vec![0.0; offers.len()];
+    let mut left = total;
+    for (taken, &i) in order.iter().enumerate() {
+        shares[i] = offers[i].min(left / (offers.len() - taken) as f64);
+        left -= shares[i];
+    }
+    shares
+}
+
+/// Share `total` between `offers`, filling each group of offers in turn.
+fn prioritized_share(offers: &[f64], groups: &[usize], total: f64) -> Vec<f64> {
+    let mut shares = vec![0.0; offers.len()];
+    let mut left = total;
+    let mut levels: Vec<usize> = groups.to_vec();
+    levels.sort_unstable();
+    levels.dedup();
+    for level in levels {
+        let members: Vec<usize> = (0..offers.len()).filter(|&i| groups[i] == level).collect();
+        let group: Vec<f64> = members.iter().map(|&i| offers[i]).collect();
+        for (&i, share) in members.iter().zip(fair_share(&group, left)) {
+            shares[i] = share;
+            left -= share;
+        }
+    }
+    shares
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// The steady flow of items through a [BeltGraph], in items per second, or with lane nodes,
+/// through a [LaneGraph](super::LaneGraph).
+pub struct Flow<N = usize> {
+    through: BTreeMap<N, Items>,
+    edges: BTreeMap<(N, N), Items>,
+    converged: bool,
+}
+
+impl<N: Copy + Ord> Flow<N> {
+    /// The items per second moving through `node`.
+    pub fn through(&self, node: N) -> f64 {
+        self.through.get(&node).map_or(0.0, total)
+    }
+
+    /// The items moving through `node`, or `None` if nothing does.
+    pub fn items(&self, node: N) -> Option<&Items> {
+        self.through.get(&node).filter(|items| !items.is_empty())
+    }
+
+    /// The items per second `from` moves into `to`.
+    pub fn between(&self, from: N, to: N) -> f64 {
+        self.edges.get(&(from, to)).map_or(0.0, total)
+    }
+
+    /// Whether the flow settled. Belts that loop back on themselves can keep shifting items
+    /// around, in which case the flow is where it had got to when the solver gave up.
+    pub fn converged(&self) -> bool {
+        self.converged
+    }
+}
+
+/// A graph items flow through, of whole belts or of their lanes.
+pub(crate) trait Network {
+    /// What identifies a node.
+    type Node: Copy + Ord;
+
+    /// Every node, in order.
+    fn nodes(&self) -> Vec<Self::Node>;
+
+    /// The nodes `node` moves items into.
+    fn next(&self, node: Self::Node) -> &[Self::Node];
+
+    /// The nodes that move items into `node`.
+    fn previous(&self, node: Self::Node) -> &[Self::Node];
+
+    /// The most items per second the node moves, for splitters over each half.
+    fn speed(&self, node: Self::Node) -> f64;
+
+    /// The halves and settings of a splitter, or `None` if the node isn't one.
+    fn splitter(&self, node: Self::Node) -> Option<&Splitter<Self::Node>>;
+
+    /// Whether `from` moves items onto the side of `to`.
+    fn is_sideload(&self, from: Self::Node, to: Self::Node) -> bool;
+}
+
+impl Network for BeltGraph {
+    type Node = usize;
+
+    fn nodes(&self) -> Vec<usize> {
+        BeltGraph::nodes(self).collect()
+    }
+
+    fn next(&self, node: usize) -> &[usize] {
+        BeltGraph::next(self, node)
+    }
+
+    fn previous(&self, node: usize) -> &[usize] {
+        BeltGraph::previous(self, node)
+    }
+
+    fn speed(&self, node: usize) -> f64 {
+        BeltGraph::speed(self, node)
+    }
+
+    fn splitter(&self, node: usize) -> Option<&Splitter> {
+        BeltGraph::splitter(self, node)
+    }
+
+    fn is_sideload(&self, from: usize, to: usize) -> bool {
+        BeltGraph::is_sideload(self, from, to)
+    }
+}
+
+/// What a node does with the items offered to it in one round.
+struct Step<N> {
+    /// The items moving through it.
+    through: Items,
+    /// The items it offers each node it feeds.
+    outputs: Vec<(N, Items)>,
+    /// How many more items per second it could take than it does.
+    headroom: f64,
+}
+
+impl BeltGraph {
+    /// The steady flow of items with `inputs` put onto the belts, by node, for example onto the
+    /// [sources](Self::sources).
+    ///
+    /// Belts move no more than their speed, splitters split items evenly unless they have an
+    /// output priority or filter, and take evenly from both sides unless they have an input
+    /// priority. Sideloaded items wait for gaps in the straight belt. Items reaching the end of
+    /// the belts, or a splitter half with nothing in front, are taken away as fast as they
+    /// arrive, and inputs that can't all fit are only taken as far as they do. Lanes aren't
+    /// modelled, see [LaneGraph::flow](super::LaneGraph::flow) for that.
+    pub fn flow(&self, inputs: &BTreeMap<usize, Items>) -> Flow {
+        solve(self, inputs, &BTreeSet::new())
+    }
+
+    /// Like [flow](Self::flow), except that items reaching the `blocked` nodes stop there rather
+    /// than being taken away.
+    pub fn blocked_flow(&self, inputs: &BTreeMap<usize, Items>, blocked: &BTreeSet<usize>) -> Flow {
+        solve(self, inputs, blocked)
+    }
+}
+
+/// The steady flow of items through `graph`, passing items along until they settle.
+pub(crate) fn solve<G: Network>(
+    graph: &G,
+    inputs: &BTreeMap<G::Node, Items>,
+    blocked: &BTreeSet<G::Node>,
+) -> Flow<G::Node> {
+    let mut offers: BTreeMap<(G::Node, G::Node), Items> = BTreeMap::new();
+    let mut limits: BTreeMap<(G::Node, G::Node), f64> = BTreeMap::new();
+    for node in graph.nodes() {
+        for &next in graph.next(node) {
+            let limit = match graph.splitter(next) {
+                Some(_) => graph.speed(next) * 2.0,
+                None => graph.speed(next),
+            };
+            limits.insert((node, next), limit);
+        }
+    }
+
+    let mut flow = Flow {
+        through: BTreeMap::new(),
+        edges: BTreeMap::new(),
+        converged: false,
+    };
+    for _ in 0..MAX_ITERATIONS {
+        let mut next_offers = BTreeMap::new();
+        let mut next_limits = BTreeMap::new();
+        let mut through = BTreeMap::new();
+        for node in graph.nodes() {
+            // Every feeder of the node, with `None` for the items put onto it
+            let mut feeders: Vec<(Option<G::Node>, Items)> = graph
+                .previous(node)
+                .iter()
+                .map(|&from| {
+                    (
+                        Some(from),
+                        offers.get(&(from, node)).cloned().unwrap_or_default(),
+                    )
+                })
+                .collect();
+            if let Some(items) = inputs.get(&node) {
+                feeders.push((None, items.clone()));
+            }
+            if let Some(splitter) = graph.splitter(node) {
+                // Each half moves no more than the splitter's speed
+                for (from, items) in &mut feeders {
+                    let halves = match from {
+                        Some(from) => splitter
+                            .inputs
+                            .iter()
+                            .filter(|&&input| input == Some(*from))
+                            .count(),
+                        None => 2,
+                    };
+                    let most = graph.speed(node) * halves.max(1) as f64;
+                    *items = limited(items, most);
+                }
+            }
+
+            let step = step(graph, node, &feeders, &limits, blocked.contains(&node));
+            let offered: Vec<f64> = feeders.iter().map(|(_, items)| total(items)).collect();
+            let groups: Vec<usize> = feeders
+                .iter()
+                .map(|(from, _)| feeder_priority(graph, node, *from))
+                .collect();
+            let accepted = prioritized_share(&offered, &groups, total(&step.through));
+            for ((from, _), accepted) in feeders.iter().zip(accepted) {
+                if let Some(from) = from {
+                    next_limits.insert((*from, node), accepted + step.headroom);
+                }
+            }
+            for (to, items) in step.outputs {
+                add(next_offers.entry((node, to)).or_default(), &items);
+            }
+            through.insert(node, step.through);
+        }
+
+        let settled = next_offers.iter().all(|(edge, items)| {
+            (total(items) - offers.get(edge).map_or(0.0, total)).abs() < TOLERANCE
+        }) && next_limits.iter().all(|(edge, limit)| {
+            let previous = limits.get(edge).copied().unwrap_or_default();
+            previous == *limit || (previous - limit).abs() < TOLERANCE
+        });
+        flow = Flow {
+            through,
+            edges: next_offers.clone(),
+            converged: settled,
+        };
+        offers = next_offers;
+        limits = next_limits;
+        if settled {
+            break;
+        }
+    }
+    flow
+}
+
+/// The order `node` takes items from `from` in, lowest first: anything before sideloads, and the
+/// input priority side first for splitters.
+fn feeder_priority<G: Network>(graph: &G, node: G::Node, from: Option<G::Node>) -> usize {
+    let Some(from) = from else {
+        return 0;
+    };
+    if let Some(splitter) = graph.splitter(node) {
+        let preferred = splitter
+            .input_priority
+            .as_ref()
+            .and_then(|side| splitter.inputs[side_index(side)]);
+        return usize::from(preferred.is_some_and(|preferred| preferred != from));
+    }
+    usize::from(graph.is_sideload(from, node))
+}
+
+/// What `node` does with the items `feeders` offer it, given how much the nodes it feeds took
+/// last round, and whether items stop at it if it leads nowhere.
+fn step<G: Network>(
+    graph: &G,
+    node: G::Node,
+    feeders: &[(Option<G::Node>, Items)],
+    limits: &BTreeMap<(G::Node, G::Node), f64>,
+    blocked: bool,
+) -> Step<G::Node> {
+    let mut offered = Items::new();
+    for (_, items) in feeders {
+        add(&mut offered, items);
+    }
+    let offered_total = total(&offered);
+    let speed = graph.speed(node);
+    let limit = |to: G::Node| limits.get(&(node, to)).copied().unwrap_or_default();
+
+    let Some(splitter) = graph.splitter(node) else {
+        let next = graph.next(node);
+        let room = match next {
+            [] if blocked => 0.0,
+            [] => f64::INFINITY,
+            _ => next.iter().map(|&to| limit(to)).sum(),
+        };
+        let moved = offered_total.min(speed).min(room);
+        let through = limited(&offered, moved);
+        let outputs = next
+            .iter()
+            .map(|&to| {
+                let share = if next.len() == 1 {
+                    1.0
+                } else {
+                    limit(to) / room
+                };
+                (to, scaled(&through, share))
+            })
+            .collect();
+        return Step {
+            through,
+            outputs,
+            headroom: (speed.min(room) - moved).max(0.0),
+        };
+    };
+
+    // How much each half can put out, with halves leading nowhere taking anything
+    let room = splitter.outputs.map(|to| match to {
+        Some(to) => limit(to).min(speed),
+        None if blocked => 0.0,
+        None => speed,
+    });
+    let halves: [Items; 2] = match &splitter.filter {
+        Some(filter) => {
+            let side = splitter.output_priority.as_ref().map_or(0, side_index);
+            let (filtered, rest): (Items, Items) = offered
+                .iter()
+                .map(|(name, rate)| (name.clone(), *rate))
+                .partition(|(name, _)| name == filter);
+            let mut halves = [Items::new(), Items::new()];
+            halves[side] = limited(&filtered, room[side]);
+            halves[1 - side] = limited(&rest, room[1 - side]);
+            halves
+        }
+        None => {
+            let moved = offered_total.min(room[0] + room[1]);
+            let split = match &splitter.output_priority {
+                Some(side) => {
+                    let side = side_index(side);
+                    let mut split = [0.0; 2];
+                    split[side] = moved.min(room[side]);
+                    split[1 - side] = moved - split[side];
+                    split
+                }
+                None => {
+                    let left = (moved / 2.0).min(room[0]);
+                    let right = (moved - left).min(room[1]);
+                    [moved - right, right]
+                }
+            };
+            split.map(|rate| limited(&offered, rate))
+        }
+    };
+
+    let mut through = Items::new();
+    let mut outputs = Vec::new();
+    for (to, items) in splitter.outputs.iter().zip(&halves) {
+        add(&mut through, items);
+        if let Some(to) = to {
+            outputs.push((*to, items.clone()));
+        }
+    }
+    let moved = total(&through);
+    Step {
+        through,
+        outputs,
+        headroom: (room[0] + room[1] - moved).max(0.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        blueprint::{Blueprint, Direction, EntityDetails, IoPriority, SplitterFilter},
+        bp,
+        prototypes::PrototypeDb,
+    };
+
+    /// Two belts into a splitter at entity 3, and two belts out of it, with the splitter's
+    /// settings changed by `details`.
+    fn splitter(details: EntityDetails) -> Blueprint {
+        let mut blueprint = bp! {
+            "transport-belt" @ (0, 2);
+            "transport-belt" @ (1, 2);
+            "splitter" @ (0, 1);
+            "transport-belt" @ (0, 0);
+            "transport-belt" @ (1, 0);
+        }
+        .build_with(PrototypeDb::vanilla())
+        .unwrap();
+        blueprint.entities.as_mut().unwrap()[2].details = details;
+        blueprint
+    }
+
+    /// Items per second by name, from pairs of names and rates.
+    fn items(rates: &[(&str, f64)]) -> Items {
+        rates
+            .iter()
+            .map(|&(name, rate)| (name.to_owned(), rate))
+            .collect()
+    }
+
+    #[test]
+    fn shares_are_fair_and_prioritized() {
+        assert_eq!(fair_share(&[2.0, 10.0, 10.0], 12.0), [2.0, 5.0, 5.0]);
+        assert_eq!(fair_share(&[1.0, 1.0], 5.0), [1.0, 1.0]);
+        assert_eq!(
+            prioritized_share(&[4.0, 10.0, 10.0], &[1, 0, 1], 14.0),
+            [2.0, 10.0, 2.0]
+        );
+    }
+
+    #[test]
+    fn output_priority_fills_its_side_first() {
+        let blueprint = splitter(EntityDetails::Splitter {
+            input_priority: None,
+            output_priority: Some(IoPriority::Left),
+            filter: None,
+        });
+        let graph = BeltGraph::new(&blueprint, PrototypeDb::vanilla());
+        let inputs = BTreeMap::from([
+            (1, items(&[("iron-plate", 10.0)])),
+            (2, items(&[("iron-plate", 10.0)])),
+        ]);
+        let flow = graph.flow(&inputs);
+
+        assert!(flow.converged());
+        assert_eq!((flow.through(4), flow.through(5)), (15.0, 5.0));
+    }
+
+    #[test]
+    fn filtered_items_go_to_the_priority_side() {
+        let blueprint = splitter(EntityDetails::Splitter {
+            input_priority: None,
+            output_priority: Some(IoPriority::Right),
+            filter: Some(SplitterFilter::Legacy("iron-plate".to_owned())),
+        });
+        let graph = BeltGraph::new(&blueprint, PrototypeDb::vanilla());
+        let inputs = BTreeMap::from([(1, items(&[("iron-plate", 6.0), ("copper-plate", 4.0)]))]);
+        let flow = graph.flow(&inputs);
+
+        assert!(flow.converged());
+        assert_eq!(flow.items(4), Some(&items(&[("copper-plate", 4.0)])));
+        assert_eq!(flow.items(5), Some(&items(&[("iron-plate", 6.0)])));
+    }
+
+    #[test]
+    fn input_priority_takes_from_its_side_first() {
+        let mut blueprint = splitter(EntityDetails::Splitter {
+            input_priority: Some(IoPriority::Right),
+            output_priority: None,
+            filter: None,
+        });
+        // The right output sideloads onto the left one, so only one belt's worth gets out
+        blueprint.entities.as_mut().unwrap()[4].direction = Some(Direction::West);
+        let graph = BeltGraph::new(&blueprint, PrototypeDb::vanilla());
+        let inputs = BTreeMap::from([
+            (1, items(&[("iron-plate", 15.0)])),
+            (2, items(&[("iron-plate", 15.0)])),
+        ]);
+        let flow = graph.flow(&inputs);
+
+        assert!(flow.converged());
+        assert!((flow.between(2, 3) - 15.0).abs() < 1e-6);
+        assert!(flow.between(1, 3) < 1e-6);
+    }
+}
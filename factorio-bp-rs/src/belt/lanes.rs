@@ -0,0 +1,293 @@
+//! Following items along each lane of the belts separately.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::blueprint::Direction;
+
+use super::{
+    flow::{solve, Network},
+    BeltGraph, Flow, Items, NodeKind, Splitter,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+/// One of the two lanes of a belt, looking the way items move.
+pub enum Lane {
+    /// The lane on the left.
+    Left,
+    /// The lane on the right.
+    Right,
+}
+
+impl Lane {
+    /// Both lanes, left first.
+    pub const BOTH: [Lane; 2] = [Lane::Left, Lane::Right];
+
+    /// The lane on the `side` side of a belt facing `facing`, or `None` if `side` is the front or
+    /// back.
+    pub fn on_side(facing: Direction, side: Direction) -> Option<Lane> {
+        if side == facing.counter_clockwise() {
+            Some(Lane::Left)
+        } else if side == facing.clockwise() {
+            Some(Lane::Right)
+        } else {
+            None
+        }
+    }
+}
+
+/// A lane of a node of a [BeltGraph].
+pub type LaneId = (usize, Lane);
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// The flow of items between the lanes of a blueprint's belts, as a directed graph.
+///
+/// Each node of a [BeltGraph] becomes a node for each of its lanes. Items stay on their lane along
+/// belts, round curves, through splitters, and under the ground. Sideloading puts every item on
+/// the lane nearest the belt doing it, and for underground belts only lets the lane by the open
+/// half of the tile through. Containers take items from both lanes of loaders, and give them out
+/// onto both.
+pub struct LaneGraph {
+    directions: BTreeMap<usize, Direction>,
+    speeds: BTreeMap<LaneId, f64>,
+    next: BTreeMap<LaneId, Vec<LaneId>>,
+    previous: BTreeMap<LaneId, Vec<LaneId>>,
+    splitters: BTreeMap<LaneId, Splitter<LaneId>>,
+    sideloads: BTreeSet<(LaneId, LaneId)>,
+    stuck: BTreeSet<LaneId>,
+}
+
+impl LaneGraph {
+    /// The lanes of the belts of `belts`.
+    pub fn new(belts: &BeltGraph) -> Self {
+        let mut graph = Self::default();
+        for node in belts.nodes() {
+            if let Some(direction) = belts.direction(node) {
+                graph.directions.insert(node, direction);
+            }
+            for lane in Lane::BOTH {
+                graph.speeds.insert((node, lane), belts.speed(node) / 2.0);
+            }
+        }
+
+        for from in belts.nodes() {
+            for &to in belts.next(from) {
+                match (belts.kind(from), belts.kind(to)) {
+                    (_, Some(NodeKind::Container)) => {
+                        for lane in Lane::BOTH {
+                            graph.add_edge((from, lane), (to, lane));
+                        }
+                    }
+                    (Some(NodeKind::Container), _) => {
+                        for from_lane in Lane::BOTH {
+                            for to_lane in Lane::BOTH {
+                                graph.add_edge((from, from_lane), (to, to_lane));
+                            }
+                        }
+                    }
+                    _ if belts.is_sideload(from, to) => {
+                        let (Some(from_direction), Some(to_direction)) =
+                            (belts.direction(from), belts.direction(to))
+                        else {
+                            continue;
+                        };
+                        let Some(to_lane) = Lane::on_side(to_direction, from_direction.opposite())
+                        else {
+                            continue;
+                        };
+                        // The hood of an underground belt covers half the tile
+                        let open = match belts.kind(to) {
+                            Some(NodeKind::UndergroundInput) => Some(to_direction.opposite()),
+                            Some(NodeKind::UndergroundOutput) => Some(to_direction),
+                            _ => None,
+                        };
+                        for from_lane in Lane::BOTH {
+                            let through = open.is_none_or(|open| {
+                                Lane::on_side(from_direction, open) == Some(from_lane)
+                            });
+                            if through {
+                                graph.add_edge((from, from_lane), (to, to_lane));
+                                graph.sideloads.insert(((from, from_lane), (to, to_lane)));
+                            }
+                        }
+                    }
+                    _ => {
+                        for lane in Lane::BOTH {
+                            graph.add_edge((from, lane), (to, lane));
+                        }
+                    }
+                }
+            }
+        }
+
+        for node in belts.nodes() {
+            for lane in Lane::BOTH {
+                let id = (node, lane);
+                if graph.next(id).is_empty() && !belts.next(node).is_empty() {
+                    graph.stuck.insert(id);
+                }
+                let Some(splitter) = belts.splitter(node) else {
+                    continue;
+                };
+                let lane_splitter = Splitter {
+                    inputs: splitter.inputs.map(|from| from.map(|from| (from, lane))),
+                    outputs: splitter.outputs.map(|to| {
+                        to.and_then(|to| graph.next(id).iter().copied().find(|next| next.0 == to))
+                    }),
+                    input_priority: splitter.input_priority.clone(),
+                    output_priority: splitter.output_priority.clone(),
+                    filter: splitter.filter.clone(),
+                };
+                graph.splitters.insert(id, lane_splitter);
+            }
+        }
+
+        for lanes in graph.next.values_mut().chain(graph.previous.values_mut()) {
+            lanes.sort_unstable();
+            lanes.dedup();
+        }
+        graph
+    }
+
+    /// Record that items move from `from` into `to`.
+    fn add_edge(&mut self, from: LaneId, to: LaneId) {
+        self.next.entry(from).or_default().push(to);
+        self.previous.entry(to).or_default().push(from);
+    }
+
+    /// Every lane, in order.
+    pub fn nodes(&self) -> impl Iterator<Item = LaneId> + '_ {
+        self.speeds.keys().copied()
+    }
+
+    /// The lanes `lane` moves items into.
+    pub fn next(&self, lane: LaneId) -> &[LaneId] {
+        self.next.get(&lane).map_or(&[], Vec::as_slice)
+    }
+
+    /// The lanes that move items into `lane`.
+    pub fn previous(&self, lane: LaneId) -> &[LaneId] {
+        self.previous.get(&lane).map_or(&[], Vec::as_slice)
+    }
+
+    /// The most items per second the lane moves, for splitters over each half.
+    pub fn speed(&self, lane: LaneId) -> f64 {
+        self.speeds.get(&lane).copied().unwrap_or_default()
+    }
+
+    /// How one lane of a splitter shares items between its halves, or `None` if the node isn't a
+    /// splitter.
+    pub fn splitter(&self, lane: LaneId) -> Option<&Splitter<LaneId>> {
+        self.splitters.get(&lane)
+    }
+
+    /// Whether `from` sideloads onto `to`.
+    pub fn is_sideload(&self, from: LaneId, to: LaneId) -> bool {
+        self.sideloads.contains(&(from, to))
+    }
+
+    /// The lane of `belt` an inserter moving items in `direction` drops them on: the far lane, or
+    /// the right lane when the inserter is in line with the belt. `None` if the node isn't a belt
+    /// entity.
+    pub fn drop_lane(&self, belt: usize, direction: Direction) -> Option<Lane> {
+        let facing = *self.directions.get(&belt)?;
+        Some(Lane::on_side(facing, direction).unwrap_or(Lane::Right))
+    }
+
+    /// The steady flow of items with `inputs` put onto the lanes, worked out like
+    /// [BeltGraph::flow] but with each lane moving half of the belt's items. Items on a lane that
+    /// can't go on, like the lane an underground belt's hood blocks, stop there.
+    pub fn flow(&self, inputs: &BTreeMap<LaneId, Items>) -> Flow<LaneId> {
+        self.blocked_flow(inputs, &BTreeSet::new())
+    }
+
+    /// Like [flow](Self::flow), except that items reaching the `blocked` lanes stop there rather
+    /// than being taken away.
+    pub fn blocked_flow(
+        &self,
+        inputs: &BTreeMap<LaneId, Items>,
+        blocked: &BTreeSet<LaneId>,
+    ) -> Flow<LaneId> {
+        let blocked: BTreeSet<LaneId> = blocked.union(&self.stuck).copied().collect();
+        solve(self, inputs, &blocked)
+    }
+
+    /// The lanes of belts leading nowhere that get no items in `flow` while the other lane of the
+    /// belt does.
+    pub fn starved_lanes(&self, flow: &Flow<LaneId>) -> Vec<LaneId> {
+        self.nodes()
+            .filter(|&(node, lane)| {
+                let other = match lane {
+                    Lane::Left => Lane::Right,
+                    Lane::Right => Lane::Left,
+                };
+                self.next((node, lane)).is_empty()
+                    && !self.stuck.contains(&(node, lane))
+                    && self.directions.contains_key(&node)
+                    && flow.through((node, lane)) == 0.0
+                    && flow.through((node, other)) > 0.0
+            })
+            .collect()
+    }
+}
+
+impl Network for LaneGraph {
+    type Node = LaneId;
+
+    fn nodes(&self) -> Vec<LaneId> {
+        LaneGraph::nodes(self).collect()
+    }
+
+    fn next(&self, node: LaneId) -> &[LaneId] {
+        LaneGraph::next(self, node)
+    }
+
+    fn previous(&self, node: LaneId) -> &[LaneId] {
+        LaneGraph::previous(self, node)
+    }
+
+    fn speed(&self, node: LaneId) -> f64 {
+        LaneGraph::speed(self, node)
+    }
+
+    fn splitter(&self, node: LaneId) -> Option<&Splitter<LaneId>> {
+        LaneGraph::splitter(self, node)
+    }
+
+    fn is_sideload(&self, from: LaneId, to: LaneId) -> bool {
+        LaneGraph::is_sideload(self, from, to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bp, prototypes::PrototypeDb};
+
+    #[test]
+    fn sideloading_fills_the_near_lane() {
+        let db = PrototypeDb::vanilla();
+        let blueprint = bp! {
+            "transport-belt" @ (0, 0) facing East;
+            "transport-belt" @ (1, 0) facing East;
+            "transport-belt" @ (1, 1) facing North;
+        }
+        .build_with(db)
+        .unwrap();
+        let graph = LaneGraph::new(&BeltGraph::new(&blueprint, db));
+
+        assert_eq!(graph.speed((2, Lane::Left)), 7.5);
+        assert_eq!(graph.next((1, Lane::Left)), [(2, Lane::Left)]);
+        for lane in Lane::BOTH {
+            assert_eq!(graph.next((3, lane)), [(2, Lane::Right)]);
+            assert!(graph.is_sideload((3, lane), (2, Lane::Right)));
+        }
+        assert_eq!(graph.drop_lane(2, Direction::North), Some(Lane::Left));
+        assert_eq!(graph.drop_lane(2, Direction::East), Some(Lane::Right));
+
+        let plates = || Items::from([("iron-plate".to_owned(), 7.5)]);
+        let inputs = BTreeMap::from([((3, Lane::Left), plates()), ((3, Lane::Right), plates())]);
+        let flow = graph.flow(&inputs);
+        assert_eq!(flow.through((2, Lane::Right)), 7.5);
+        assert_eq!(graph.starved_lanes(&flow), [(2, Lane::Left)]);
+    }
+}
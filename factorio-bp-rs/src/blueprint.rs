@@ -1,73 +1,142 @@
 use core::num::NonZeroUsize;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use serde::{Deserialize, Serialize};
 use serde_repr::*;
 
-/// Direction of an entity (gives no further explanation, todo, after decoding simple BPs infer directions)
-pub type Direction = usize;
+/// Circuit network settings of entities.
+pub mod control_behavior;
+
+mod book;
+mod builder;
+mod canonical;
+mod cost;
+mod decode;
+mod diff;
+mod direction;
+mod error;
+mod flat;
+mod float;
+mod geometry;
+mod index;
+mod migrate;
+mod minimize;
+mod mods;
+mod names;
+mod normalize;
+mod occupancy;
+mod parameters;
+mod planner;
+mod sanitize;
+mod signal;
+mod speaker;
+mod string;
+mod transform;
+mod validate;
+mod version;
+
+pub use book::BookIter;
+pub use builder::{BlueprintBuilder, BuildError, EntityBuilder, BUILD_VERSION};
+pub use control_behavior::ControlBehavior;
+pub use decode::{DecodeOptions, DecodeWarning, Decoded, EntityDecodeError, JsonPath, PathSegment};
+pub use diff::{diff, BlueprintDiff, EntityChange, EntityDiff};
+pub use direction::Direction;
+pub use error::BpError;
+use flat::FlatEntity;
+pub use geometry::BoundingBox;
+pub use index::EntityIndex;
+pub use migrate::{Migration, Stripped, MIGRATIONS};
+pub use mods::RequiredMods;
+pub use names::{
+    AssemblerTier, BeltTier, ElectricPoleKind, FurnaceKind, InserterKind, UnknownName,
+};
+pub use normalize::Anchor;
+pub use occupancy::OccupancyGrid;
+pub use parameters::{
+    BlueprintParameter, IdParameter, NumberParameter, ParameterError, ParameterValue,
+    QualityCondition,
+};
+pub use planner::{
+    DeconstructionFilter, DeconstructionPlanner, DeconstructionSettings, PlannerFilterMode,
+    TileSelectionMode, UpgradeMapper, UpgradePlanner, UpgradeSettings, UpgradeTarget,
+    UpgradeTargetType,
+};
+pub use sanitize::SanitizeOptions;
+pub use signal::{SignalColor, VirtualSignal, Wildcard};
+pub use speaker::{Instrument, Notes, SpeakerCircuitParameters, VANILLA_INSTRUMENTS};
+pub use string::{decode_json, decode_string, encode_json, EncodeOptions, VERSION_BYTE};
+pub use transform::Rotation;
+pub use validate::{Issue, Severity};
+pub use version::{ParseVersionError, Version};
+
 /// No further explanation given.
 pub type GraphicsVariation = u8;
 /// The number of items in a given stack.
 pub type ItemCountType = u32;
-/// 1 or more instances of key/value pairs. Key is the name of the item, string. Value is the amount of items to be requested
-pub type ItemRequest = HashMap<String, ItemCountType>;
+/// 1 or more instances of key/value pairs. Key is the name of the item, string. Value is the amount of items to be requested. Ordered by item name, so it always encodes the same way.
+pub type ItemRequest = BTreeMap<String, ItemCountType>;
+/// Free-form data attached to an entity by mods. Keys are strings, values any JSON.
+pub type Tags = serde_json::Map<String, serde_json::Value>;
+/// Fields of a JSON object that don't map to a known field.
+pub type UnknownFields = serde_json::Map<String, serde_json::Value>;
+/// A 2.0 wire as stored in blueprint JSON: source entity, source connector, target entity, target connector.
+pub type WireTuple = (NonZeroUsize, WireConnectorId, NonZeroUsize, WireConnectorId);
 /// The index of an item stack in a container.
 pub type ItemStackIndex = u16;
 
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+/// The top level object of a blueprint string, identifying what kind of item was exported.
+pub enum BlueprintEnvelope {
+    /// A single blueprint, stored under the "blueprint" key.
+    Blueprint(Blueprint),
+    /// A blueprint book, stored under the "blueprint_book" key.
+    BlueprintBook(BlueprintBook),
+    /// A deconstruction planner, stored under the "deconstruction_planner" key.
+    DeconstructionPlanner(DeconstructionPlanner),
+    /// An upgrade planner, stored under the "upgrade_planner" key.
+    UpgradePlanner(UpgradePlanner),
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 /// A book full of many blueprints.
 pub struct BlueprintBook {
     /// The name of the item that was saved ("blueprint-book" in vanilla).
     pub item: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     /// The name of the blueprint set by the user.
     pub label: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     /// The color of the label of this blueprint.
     pub label_color: Option<Color>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The description of the blueprint book set by the user.
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    /// The icons of the blueprint book set by the user.
+    pub icons: Vec<Icon>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     /// The actual content of the blueprint book.
     pub blueprints: Vec<BookBpWrapper>,
     /// Index of the currently selected blueprint
     pub active_index: usize,
     /// The map version of the map the blueprint [book] was created in.
     pub version: Version,
+    #[cfg_attr(feature = "preserve-unknown", serde(flatten, skip_deserializing))]
+    #[cfg_attr(not(feature = "preserve-unknown"), serde(skip))]
+    /// Fields this crate doesn't know about, kept so they survive a round trip.
+    /// Only filled with the `preserve-unknown` feature.
+    pub extra: UnknownFields,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(from = "u64")]
-/// A Factorio game version
-pub struct Version {
-    major: u16,
-    minor: u16,
-    patch: u16,
-    developer: u16,
-}
-
-impl From<u64> for Version {
-    fn from(value: u64) -> Self {
-        // The factorio version string "consists of four consecutive 2-byte unsigned little-endian values".
-        let bytes = value.to_be_bytes();
-
-        let mut version_numbers: [u16; 4] = [0; 4];
-        for i in 0..4 {
-            version_numbers[i] = u16::from_le_bytes([bytes[i * 2], bytes[i * 2 + 1]])
-        }
-
-        Self {
-            major: version_numbers[0],
-            minor: version_numbers[1],
-            patch: version_numbers[2],
-            developer: version_numbers[3],
-        }
-    }
-}
-
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-/// A wrapper around a blueprint for use in blueprint books so its index in the book can be easily referenced back to.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// A wrapper around a book entry so its index in the book can be easily referenced back to.
 pub struct BookBpWrapper {
-    /// The index of the blueprint in its book
+    /// The index of the entry in its book
     pub index: usize,
-    /// The actual blueprint
-    pub blueprint: Blueprint,
+    #[serde(flatten)]
+    /// The actual blueprint, blueprint book, or planner
+    pub content: BlueprintEnvelope,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -75,23 +144,119 @@ pub struct BookBpWrapper {
 pub struct Blueprint {
     /// The name of the item that was saved ("blueprint" in vanilla).
     pub item: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     /// The name of the blueprint set by the user.
     pub label: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     /// The color of the label of this blueprint.
     pub label_color: Option<Color>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The description of the blueprint set by the user.
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     /// The actual content of the blueprint
     pub entities: Option<Vec<Entity>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     /// The tiles included in the blueprint.
     pub tiles: Option<Vec<Tile>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     /// The icons of the blueprint set by the user.
     pub icons: Vec<Icon>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     /// The schedules for trains in this blueprint.
     pub schedules: Option<Vec<Schedule>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Circuit and copper wires between entities. Used since 2.0 instead of per-entity connections.
+    pub wires: Option<Vec<Wire>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The parameters of a parameterised blueprint (2.0).
+    pub parameters: Option<Vec<BlueprintParameter>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "snap-to-grid")]
+    /// The dimensions of the grid the blueprint snaps to, if grid snapping is enabled.
+    pub snap_to_grid: Option<Position>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "absolute-snapping")]
+    /// Whether the grid is aligned to the map grid rather than to the cursor.
+    pub absolute_snapping: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "position-relative-to-grid")]
+    /// The offset of the blueprint from the absolute grid, if absolute snapping is enabled.
+    pub position_relative_to_grid: Option<Position>,
     /// The map version of the map the blueprint was created in.
     pub version: Version,
+    #[cfg_attr(feature = "preserve-unknown", serde(flatten, skip_deserializing))]
+    #[cfg_attr(not(feature = "preserve-unknown"), serde(skip))]
+    /// Fields this crate doesn't know about, kept so they survive a round trip.
+    /// Only filled with the `preserve-unknown` feature.
+    pub extra: UnknownFields,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "WireTuple", into = "WireTuple")]
+/// A single circuit or copper wire in a 2.0 blueprint. Stored as `[source_entity, source_connector, target_entity, target_connector]`.
+pub struct Wire {
+    /// The entity_number of the entity the wire starts at.
+    pub source_entity: NonZeroUsize,
+    /// The connection point the wire starts at.
+    pub source_connector: WireConnectorId,
+    /// The entity_number of the entity the wire ends at.
+    pub target_entity: NonZeroUsize,
+    /// The connection point the wire ends at.
+    pub target_connector: WireConnectorId,
+}
+
+impl From<WireTuple> for Wire {
+    fn from(value: WireTuple) -> Self {
+        Self {
+            source_entity: value.0,
+            source_connector: value.1,
+            target_entity: value.2,
+            target_connector: value.3,
+        }
+    }
+}
+
+impl From<Wire> for WireTuple {
+    fn from(value: Wire) -> Self {
+        (
+            value.source_entity,
+            value.source_connector,
+            value.target_entity,
+            value.target_connector,
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+/// A connection point for 2.0 wires (`defines.wire_connector_id`).
+pub enum WireConnectorId {
+    /// Red wire on the first (or only) circuit connector, e.g. a combinator's input.
+    CircuitRed = 1,
+    /// Green wire on the first (or only) circuit connector, e.g. a combinator's input.
+    CircuitGreen = 2,
+    /// Red wire on a combinator's output.
+    CombinatorOutputRed = 3,
+    /// Green wire on a combinator's output.
+    CombinatorOutputGreen = 4,
+    /// Copper wire on an electric pole, or the left side of a power switch.
+    PoleCopper = 5,
+    /// Copper wire on the right side of a power switch.
+    PowerSwitchRightCopper = 6,
+}
+
+impl WireConnectorId {
+    /// Whether this connector is for copper (power) wire rather than circuit wire.
+    pub fn is_copper(&self) -> bool {
+        matches!(
+            self,
+            WireConnectorId::PoleCopper | WireConnectorId::PowerSwitchRightCopper
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 /// An icon displayed in an inventory
 pub struct Icon {
     /// Index of the icon.
@@ -100,18 +265,103 @@ pub struct Icon {
     pub signal: SignalId,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 /// A circuit signal ID.
 pub struct SignalId {
     /// The name of the signal prototype this signal is set to.
     pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "type")]
-    /// Type of the signal.
-    pub signal_type: SignalType,
+    /// Type of the signal. Omitted for items since 2.0.
+    pub signal_type: Option<SignalType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Quality of the signal (2.0). Normal if absent.
+    pub quality: Option<Quality>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
+impl SignalId {
+    /// The type of the signal, taking into account that 2.0 omits the type of item signals.
+    pub fn kind(&self) -> SignalType {
+        self.signal_type.clone().unwrap_or(SignalType::Item)
+    }
+
+    /// Whether two IDs refer to the same signal, treating omitted types and qualities as their defaults.
+    pub fn matches(&self, other: &SignalId) -> bool {
+        self.name == other.name
+            && self.kind() == other.kind()
+            && self.quality.clone().unwrap_or_default() == other.quality.clone().unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Default, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+/// The quality of an entity, item, or signal (2.0, Space Age).
+pub enum Quality {
+    #[default]
+    /// The default quality.
+    Normal,
+    /// The first quality above normal.
+    Uncommon,
+    /// The second quality above normal.
+    Rare,
+    /// The third quality above normal.
+    Epic,
+    /// The highest vanilla quality.
+    Legendary,
+    /// A quality added by a mod, by prototype name.
+    Other(String),
+}
+
+impl Quality {
+    /// The prototype name of the quality.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Quality::Normal => "normal",
+            Quality::Uncommon => "uncommon",
+            Quality::Rare => "rare",
+            Quality::Epic => "epic",
+            Quality::Legendary => "legendary",
+            Quality::Other(name) => name,
+        }
+    }
+
+    /// The level of a vanilla quality, which scales most of its bonuses. `None` for modded qualities.
+    pub fn level(&self) -> Option<u8> {
+        match self {
+            Quality::Normal => Some(0),
+            Quality::Uncommon => Some(1),
+            Quality::Rare => Some(2),
+            Quality::Epic => Some(3),
+            Quality::Legendary => Some(5),
+            Quality::Other(_) => None,
+        }
+    }
+}
+
+impl From<String> for Quality {
+    fn from(value: String) -> Self {
+        match value.as_str() {
+            "normal" => Quality::Normal,
+            "uncommon" => Quality::Uncommon,
+            "rare" => Quality::Rare,
+            "epic" => Quality::Epic,
+            "legendary" => Quality::Legendary,
+            _ => Quality::Other(value),
+        }
+    }
+}
+
+impl From<Quality> for String {
+    fn from(value: Quality) -> Self {
+        match value {
+            Quality::Other(name) => name,
+            quality => quality.as_str().to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 /// The type of a circuit signal
 pub enum SignalType {
     /// Represents an item (e.g. iron plate)
@@ -120,9 +370,20 @@ pub enum SignalType {
     Fluid,
     /// Represents a virtual value (e.g. "Signal 0")
     Virtual,
+    /// Represents an entity (2.0)
+    Entity,
+    /// Represents a recipe (2.0)
+    Recipe,
+    /// Represents a planet or other space location (2.0)
+    SpaceLocation,
+    /// Represents an asteroid chunk (2.0)
+    AsteroidChunk,
+    /// Represents a quality level (2.0)
+    Quality,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "FlatEntity", into = "FlatEntity")]
 /// A placed structure in the blueprint (e.g. Assembling Machine 3)
 pub struct Entity {
     /// Index of the entity.
@@ -133,61 +394,425 @@ pub struct Entity {
     pub position: Position,
     /// Direction of the entity.
     pub direction: Option<Direction>,
-    /// Orientation of cargo wagon or locomotive, value 0 to 1.
-    pub orientation: Option<f64>,
+    /// Whether the entity is mirrored (2.0).
+    pub mirror: Option<bool>,
+    /// Quality of the entity (2.0). Normal if absent.
+    pub quality: Option<Quality>,
     /// Circuit connection.
     pub connections: Option<Connection>,
-    #[serde(rename = "neighbours")]
+    /// Circuit network settings of the entity.
+    pub control_behavior: Option<ControlBehavior>,
     /// Copper wire connections
     pub neighbors: Option<Vec<NonZeroUsize>>,
     /// Item requests by this entity; this is what defines the item-request-proxy when the blueprint is placed.
-    pub items: Option<ItemRequest>,
-    /// Name of the recipe prototype this assembling machine is set to.
-    pub recipe: Option<String>,
-    /// Used by (Prototype/Container)[https://wiki.factorio.com/Prototype/Container]. The index of the first inaccessible item slot due to limiting with the red "bar".
-    pub bar: Option<ItemStackIndex>,
-    /// Cargo wagon inventory configuration.
-    pub inventory: Option<Inventory>,
-    /// Used by (Prototype/InfinityContainer)[https://wiki.factorio.com/Prototype/InfinityContainer].
-    pub infinity_settings: Option<InfinitySettings>,
-    #[serde(rename = "type")]
-    /// Type of the underground belt or loader.
-    pub io_type: Option<IoType>,
-    /// Input priority of the splitter.
-    pub input_priority: Option<IoPriority>,
-    /// Output priority of the splitter.
-    pub output_priority: Option<IoPriority>,
-    /// Filter of the splitter. Name of the item prototype the filter is set to.
-    pub filter: Option<String>,
-    /// Filters of the filter inserter or loader.
-    pub filters: Option<Vec<ItemFilter>>,
-    /// Filter mode of the filter inserter.
-    pub filter_mode: Option<FilterMode>,
-    /// The stack size the inserter is set to.
-    pub override_stack_size: Option<u8>,
-    /// The drop position the inserter is set to.
-    pub drop_position: Option<Position>,
-    /// The pickup potition the inserter is set to.
-    pub pickup_position: Option<Position>,
-    /// Used by (Prototype/LogisticContainer)[https://wiki.factorio.com/Prototype/LogisticContainer].
-    pub request_filters: Option<Vec<LogisticFilter>>,
-    /// Whether this requester chest can request from buffer chests
-    pub request_from_buffers: Option<bool>,
-    /// Used by (Programmable speaker)[https://wiki.factorio.com/Programmable_speaker],
-    pub parameters: Option<SpeakerParameter>,
-    /// Used by (Programmable speaker)[https://wiki.factorio.com/Programmable_speaker],
-    pub alert_parameters: Option<SpeakerAlertParameter>,
-    /// Used by the rocket silo. Whether auto launch is enabled.
-    pub auto_launch: Option<bool>,
-    /// Used by (Prototype/SimpleEntityWithForce)[https://wiki.factorio.com/Prototype/SimpleEntityWithForce] or (Prototype/SimpleEntityWithOwner)[https://wiki.factorio.com/Prototype/SimpleEntityWithOwner]
-    pub variation: Option<GraphicsVariation>,
-    /// Color of the (Prototype/SimpleEntityWithForce)[https://wiki.factorio.com/Prototype/SimpleEntityWithForce], (Prototype/SimpleEntityWithOwner)[https://wiki.factorio.com/Prototype/SimpleEntityWithOwner], or train station
+    pub items: Option<EntityItems>,
+    /// Color of the (Prototype/SimpleEntityWithForce)[https://wiki.factorio.com/Prototype/SimpleEntityWithForce], (Prototype/SimpleEntityWithOwner)[https://wiki.factorio.com/Prototype/SimpleEntityWithOwner], train station, or rolling stock
     pub color: Option<Color>,
-    /// The name of the train station,
-    pub station: Option<String>,
+    /// Data set by mods, kept as-is.
+    pub tags: Option<Tags>,
+    /// The settings only some kinds of entity have.
+    pub details: EntityDetails,
+    /// Fields this crate doesn't know about, kept so they survive a round trip. Settings of
+    /// kinds of entity other than [Entity::details] are always kept here; other fields only
+    /// with the `preserve-unknown` feature.
+    pub extra: UnknownFields,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Default)]
+/// The settings of an entity that only some kinds of entity have. Blueprints don't say what kind
+/// an entity is, so it is told by which of these settings are set.
+pub enum EntityDetails {
+    #[default]
+    /// An entity with none of the settings below.
+    Plain,
+    /// An assembling machine or other crafting machine with a recipe, or a rocket silo.
+    CraftingMachine {
+        /// Name of the recipe prototype this assembling machine is set to.
+        recipe: Option<String>,
+        /// Quality of the recipe this assembling machine is set to (2.0). Normal if absent.
+        recipe_quality: Option<Quality>,
+        /// Used by the rocket silo. Whether auto launch is enabled.
+        auto_launch: Option<bool>,
+    },
+    /// An inserter.
+    Inserter {
+        /// Filters of the filter inserter.
+        filters: Option<Vec<ItemFilter>>,
+        /// Filter mode of the filter inserter.
+        filter_mode: Option<FilterMode>,
+        /// Whether the inserter's filters are in use (2.0).
+        use_filters: Option<bool>,
+        /// The stack size the inserter is set to.
+        override_stack_size: Option<u8>,
+        /// The drop position the inserter is set to.
+        drop_position: Option<Position>,
+        /// The pickup potition the inserter is set to.
+        pickup_position: Option<Position>,
+    },
+    /// An underground belt entrance or exit.
+    UndergroundBelt {
+        /// Whether it is the entrance or exit.
+        io_type: Option<IoType>,
+    },
+    /// A loader, moving items between a belt and an inventory.
+    Loader {
+        /// Whether it loads or unloads the inventory.
+        io_type: Option<IoType>,
+        /// Filters of the loader.
+        filters: Option<Vec<ItemFilter>>,
+        /// Filter mode of the loader.
+        filter_mode: Option<FilterMode>,
+    },
+    /// A splitter.
+    Splitter {
+        /// Input priority of the splitter.
+        input_priority: Option<IoPriority>,
+        /// Output priority of the splitter.
+        output_priority: Option<IoPriority>,
+        /// Filter of the splitter.
+        filter: Option<SplitterFilter>,
+    },
+    /// A chest, including logistic and infinity chests.
+    Container {
+        /// Used by (Prototype/Container)[https://wiki.factorio.com/Prototype/Container]. The index of the first inaccessible item slot due to limiting with the red "bar".
+        bar: Option<ItemStackIndex>,
+        /// Used by (Prototype/LogisticContainer)[https://wiki.factorio.com/Prototype/LogisticContainer].
+        request_filters: Option<LogisticRequests>,
+        /// Whether this requester chest can request from buffer chests
+        request_from_buffers: Option<bool>,
+        /// Used by (Prototype/InfinityContainer)[https://wiki.factorio.com/Prototype/InfinityContainer].
+        infinity_settings: Option<InfinitySettings>,
+    },
+    /// An infinity pipe, which makes or removes a fluid.
+    InfinityPipe {
+        /// The fluid the pipe is set to, and what it does with it.
+        infinity_settings: Option<InfinityPipeSettings>,
+    },
+    /// A locomotive, wagon, car, tank, or spidertron.
+    Vehicle {
+        /// Orientation of cargo wagon or locomotive, value 0 to 1.
+        orientation: Option<f64>,
+        /// Cargo wagon inventory configuration.
+        inventory: Option<Inventory>,
+        /// Equipment installed in the grid of a vehicle or spidertron.
+        grid: Option<Vec<EquipmentPlacement>>,
+    },
+    /// A train stop.
+    TrainStop {
+        /// The name of the train station,
+        station: Option<String>,
+    },
+    /// A programmable speaker.
+    Speaker {
+        /// Used by (Programmable speaker)[https://wiki.factorio.com/Programmable_speaker],
+        parameters: Option<SpeakerParameter>,
+        /// Used by (Programmable speaker)[https://wiki.factorio.com/Programmable_speaker],
+        alert_parameters: Option<SpeakerAlertParameter>,
+    },
+    /// A simple entity with owner or force, as placed by scenarios and mods.
+    SimpleEntity {
+        /// Used by (Prototype/SimpleEntityWithForce)[https://wiki.factorio.com/Prototype/SimpleEntityWithForce] or (Prototype/SimpleEntityWithOwner)[https://wiki.factorio.com/Prototype/SimpleEntityWithOwner]
+        variation: Option<GraphicsVariation>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(untagged)]
+/// The items requested by an entity, which changed format in 2.0.
+pub enum EntityItems {
+    /// Item name to count, used before 2.0.
+    Legacy(ItemRequest),
+    /// Items and the inventory slots they are inserted into, used since 2.0.
+    InsertPlans(Vec<BlueprintInsertPlan>),
+}
+
+impl EntityItems {
+    /// The name and total count of every requested item.
+    pub fn counts(&self) -> Vec<(&str, ItemCountType)> {
+        match self {
+            EntityItems::Legacy(request) => request
+                .iter()
+                .map(|(name, count)| (name.as_str(), *count))
+                .collect(),
+            EntityItems::InsertPlans(plans) => plans
+                .iter()
+                .map(|plan| (plan.id.name.as_str(), plan.items.total_count()))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// An item to be inserted into an entity once it is built (2.0).
+pub struct BlueprintInsertPlan {
+    /// The item to insert.
+    pub id: ItemId,
+    /// Where the item is inserted.
+    pub items: ItemInventoryPositions,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// An item prototype with an optional quality.
+pub struct ItemId {
+    /// Name of the item prototype.
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Quality of the item. Normal if absent.
+    pub quality: Option<Quality>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+/// The inventory slots (and equipment grid) an insert plan places items into.
+pub struct ItemInventoryPositions {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    /// Inventory slots that receive the item.
+    pub in_inventory: Vec<InventoryPosition>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Number of the item placed into the entity's equipment grid.
+    pub grid_count: Option<ItemCountType>,
+}
+
+impl ItemInventoryPositions {
+    /// Total number of items inserted by this plan.
+    pub fn total_count(&self) -> ItemCountType {
+        self.in_inventory
+            .iter()
+            .map(|position| position.count.unwrap_or(1))
+            .sum::<ItemCountType>()
+            + self.grid_count.unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// A single inventory slot targeted by an insert plan.
+pub struct InventoryPosition {
+    /// The `defines.inventory` index of the inventory.
+    pub inventory: u32,
+    /// The slot within the inventory, starting at 0.
+    pub stack: ItemStackIndex,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// How many items are put in the slot. 1 if absent.
+    pub count: Option<ItemCountType>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(untagged)]
+/// The filter of a splitter, which changed format in 2.0.
+pub enum SplitterFilter {
+    /// Name of the item prototype, used before 2.0.
+    Legacy(String),
+    /// Item prototype and quality, used since 2.0.
+    Item(ItemId),
+}
+
+impl SplitterFilter {
+    /// Name of the item prototype the filter is set to.
+    pub fn name(&self) -> &str {
+        match self {
+            SplitterFilter::Legacy(name) => name,
+            SplitterFilter::Item(id) => &id.name,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// A piece of equipment in an equipment grid.
+pub struct EquipmentPlacement {
+    /// The equipment prototype.
+    pub equipment: EquipmentId,
+    /// The grid cell of the top-left corner of the equipment.
+    pub position: GridPosition,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(untagged)]
+/// The equipment prototype of a grid placement, with a quality since 2.0.
+pub enum EquipmentId {
+    /// Name of the equipment prototype.
+    Legacy(String),
+    /// Equipment prototype and quality.
+    Item(ItemId),
+}
+
+impl EquipmentId {
+    /// Name of the equipment prototype.
+    pub fn name(&self) -> &str {
+        match self {
+            EquipmentId::Legacy(name) => name,
+            EquipmentId::Item(id) => &id.name,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// A cell of an equipment grid, counted from the top left.
+pub struct GridPosition {
+    /// Column.
+    pub x: u32,
+    /// Row.
+    pub y: u32,
+}
+
+impl Entity {
+    /// The value of a mod tag, if set.
+    pub fn tag(&self, key: &str) -> Option<&serde_json::Value> {
+        self.tags.as_ref()?.get(key)
+    }
+
+    /// The recipe this entity is set to, if it is a crafting machine with one.
+    pub fn recipe(&self) -> Option<&str> {
+        match &self.details {
+            EntityDetails::CraftingMachine { recipe, .. } => recipe.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// The item filters of this inserter or loader, empty if it has none.
+    pub fn filters(&self) -> &[ItemFilter] {
+        match &self.details {
+            EntityDetails::Inserter { filters, .. } | EntityDetails::Loader { filters, .. } => {
+                filters.as_deref().unwrap_or_default()
+            }
+            _ => &[],
+        }
+    }
+
+    /// Whether this underground belt or loader is an input or output, if set.
+    pub fn io_type(&self) -> Option<&IoType> {
+        match &self.details {
+            EntityDetails::UndergroundBelt { io_type } | EntityDetails::Loader { io_type, .. } => {
+                io_type.as_ref()
+            }
+            _ => None,
+        }
+    }
+
+    /// The logistic requests of this container, if it has any.
+    pub fn request_filters(&self) -> Option<&LogisticRequests> {
+        match &self.details {
+            EntityDetails::Container {
+                request_filters, ..
+            } => request_filters.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// The settings of this infinity pipe, if it is one that is set.
+    pub fn infinity_pipe_settings(&self) -> Option<&InfinityPipeSettings> {
+        match &self.details {
+            EntityDetails::InfinityPipe { infinity_settings } => infinity_settings.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// The equipment in this entity's grid, empty if it has none.
+    pub fn equipment(&self) -> &[EquipmentPlacement] {
+        match &self.details {
+            EntityDetails::Vehicle { grid, .. } => grid.as_deref().unwrap_or_default(),
+            _ => &[],
+        }
+    }
+
+    /// Place a piece of equipment with its top-left corner at `position`. An entity with settings
+    /// of another kind becomes a [EntityDetails::Vehicle], since only vehicles have a grid.
+    pub fn add_equipment(&mut self, equipment: ItemId, position: GridPosition) {
+        if !matches!(self.details, EntityDetails::Vehicle { .. }) {
+            self.details = EntityDetails::Vehicle {
+                orientation: None,
+                inventory: None,
+                grid: None,
+            };
+        }
+        if let EntityDetails::Vehicle { grid, .. } = &mut self.details {
+            grid.get_or_insert_with(Vec::new).push(EquipmentPlacement {
+                equipment: EquipmentId::Item(equipment),
+                position,
+            });
+        }
+    }
+
+    /// Remove the equipment whose top-left corner is at `position`, returning it.
+    pub fn remove_equipment(&mut self, position: GridPosition) -> Option<EquipmentPlacement> {
+        let EntityDetails::Vehicle { grid, .. } = &mut self.details else {
+            return None;
+        };
+        let placed = grid.as_mut()?;
+        let index = placed
+            .iter()
+            .position(|placed| placed.position == position)?;
+        let removed = placed.remove(index);
+        if placed.is_empty() {
+            *grid = None;
+        }
+        Some(removed)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(untagged)]
+/// The requests of a logistic container, which changed format in 2.0.
+pub enum LogisticRequests {
+    /// A flat list of requests, used before 2.0.
+    Legacy(Vec<LogisticFilter>),
+    /// Requests grouped into logistic sections, used since 2.0.
+    Sections(LogisticSections),
+}
+
+impl LogisticRequests {
+    /// Every request, regardless of which section it belongs to.
+    pub fn filters(&self) -> Vec<&LogisticFilter> {
+        match self {
+            LogisticRequests::Legacy(filters) => filters.iter().collect(),
+            LogisticRequests::Sections(sections) => sections
+                .sections
+                .iter()
+                .flat_map(|section| section.filters.iter())
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+/// Logistic requests grouped into sections (2.0).
+pub struct LogisticSections {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    /// The sections of requests.
+    pub sections: Vec<LogisticSection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Whether items that are not requested are moved to the trash.
+    pub trash_not_requested: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Whether the requests can be fulfilled from buffer chests.
+    pub request_from_buffers: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A single section of logistic requests (2.0).
+pub struct LogisticSection {
+    /// Index of the section.
+    pub index: NonZeroUsize,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    /// The requests in the section.
+    pub filters: Vec<LogisticFilter>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The name of the logistic group this section belongs to.
+    pub group: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The factor every count in the section is multiplied by. 1 if absent.
+    pub multiplier: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Whether the section is in use. True if absent.
+    pub active: Option<bool>,
+}
+
+impl LogisticSection {
+    /// Whether the section is in use.
+    pub fn is_active(&self) -> bool {
+        self.active.unwrap_or(true)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 /// The type of an underground belt or loader
 pub enum IoType {
@@ -197,7 +822,7 @@ pub enum IoType {
     Output,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 /// The input/output priority for a splitter.
 pub enum IoPriority {
@@ -207,7 +832,7 @@ pub enum IoPriority {
     Left,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 /// The mode for a filter inserter.
 pub enum FilterMode {
@@ -217,34 +842,103 @@ pub enum FilterMode {
     Blacklist,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 /// An inventory of a non-logistics container.
 pub struct Inventory {
+    #[serde(skip_serializing_if = "Option::is_none")]
     /// Array of item filters
-    pub filters: Vec<ItemFilter>,
+    pub filters: Option<Vec<ItemFilter>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     /// The index of the first inaccessible item slot due to limiting with the red "bar".
-    pub bar: ItemStackIndex,
+    pub bar: Option<ItemStackIndex>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 /// A train's automation schedule.
 pub struct Schedule {
-    /// Array of schedule records.
-    pub schedule: Vec<ScheduleRecord>,
+    /// The records of the schedule.
+    pub schedule: ScheduleData,
     /// Array of entity numbers of locomotives using this schedule.
     pub locomotives: Vec<NonZeroUsize>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(untagged)]
+/// The contents of a train schedule, which changed format in 2.0.
+pub enum ScheduleData {
+    /// A list of schedule records, used before 2.0.
+    Legacy(Vec<ScheduleRecord>),
+    /// A schedule object holding its records, used since 2.0.
+    Schedule(TrainSchedule),
+}
+
+impl ScheduleData {
+    /// The records of the schedule, regardless of format.
+    pub fn records(&self) -> &[ScheduleRecord] {
+        match self {
+            ScheduleData::Legacy(records) => records,
+            ScheduleData::Schedule(schedule) => &schedule.records,
+        }
+    }
+
+    /// The interrupts of the schedule. Always empty before 2.0.
+    pub fn interrupts(&self) -> &[ScheduleInterrupt] {
+        match self {
+            ScheduleData::Legacy(_) => &[],
+            ScheduleData::Schedule(schedule) => &schedule.interrupts,
+        }
+    }
+
+    /// The name of the schedule group the train belongs to, if any.
+    pub fn group(&self) -> Option<&str> {
+        match self {
+            ScheduleData::Legacy(_) => None,
+            ScheduleData::Schedule(schedule) => schedule.group.as_deref(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+/// A train schedule (2.0).
+pub struct TrainSchedule {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    /// Array of schedule records.
+    pub records: Vec<ScheduleRecord>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    /// Interrupts that can send the train elsewhere when their conditions are met.
+    pub interrupts: Vec<ScheduleInterrupt>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Name of the schedule group, shared by every train in it.
+    pub group: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// An interrupt of a train schedule (2.0).
+pub struct ScheduleInterrupt {
+    /// The name of the interrupt, which identifies it across trains.
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    /// Conditions that trigger the interrupt.
+    pub conditions: Vec<WaitCondition>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    /// Records the train visits when the interrupt triggers.
+    pub targets: Vec<ScheduleRecord>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Whether the interrupt can trigger while another interrupt is being handled. False if absent.
+    pub inside_interrupt: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 /// A single item in a train's automation schedule.
 pub struct ScheduleRecord {
     /// The name of the stop for this schedule record.
     pub station: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     /// Array of wait conditions.
     pub wait_conditions: Vec<WaitCondition>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 /// A wait condition for train automation.
 pub struct WaitCondition {
     #[serde(rename = "type")]
@@ -252,24 +946,107 @@ pub struct WaitCondition {
     pub condition_type: ConditionType,
     /// Tells how this condition is to be compared with the preceeding conditions in the corresponding wait_conditions array.
     pub compare_type: CompareType,
+    #[serde(skip_serializing_if = "Option::is_none")]
     /// Number of ticks to wait or of inactivity. Only present when type is "time" or "inactivity".
     pub ticks: Option<usize>,
-    #[serde(skip)]
-    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
     /// CircuitCondition Object, only present when type is "item_count", "circuit" or "fluid_count".
     pub condition: Option<CircuitCondition>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The station checked by "specific_destination_full" and similar conditions (2.0).
+    pub station: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Amount of damage for "damage_taken" conditions (2.0).
+    pub damage: Option<u32>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-/// The circuit condition object is not expanded on the Factorio Wiki.
-/// It's likely to be
-/// - an item name
-/// - a count
-/// - <, >, >=, <=, =, =/=
-/// based on the train item count conditions, but it could be more.
-pub struct CircuitCondition {}
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+/// A comparison between a signal and either another signal or a constant, e.g. `iron-plate > 100`.
+pub struct CircuitCondition {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The signal on the left hand side of the comparison.
+    pub first_signal: Option<SignalId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The signal on the right hand side of the comparison. Takes precedence over `constant`.
+    pub second_signal: Option<SignalId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The constant on the right hand side of the comparison. 0 if absent.
+    pub constant: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// How the two sides are compared. `<` if absent.
+    pub comparator: Option<Comparator>,
+}
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+impl CircuitCondition {
+    /// Evaluate the condition, looking up signal values with `signal_value`.
+    ///
+    /// A condition without a first signal is never fulfilled.
+    pub fn evaluate(&self, signal_value: impl Fn(&SignalId) -> i32) -> bool {
+        let Some(first) = &self.first_signal else {
+            return false;
+        };
+
+        let lhs = signal_value(first);
+        let rhs = match &self.second_signal {
+            Some(second) => signal_value(second),
+            None => self.constant.unwrap_or(0),
+        };
+
+        self.comparator.unwrap_or_default().evaluate(lhs, rhs)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+/// A comparison operator used by circuit conditions, decider combinators, and filters.
+pub enum Comparator {
+    #[default]
+    #[serde(rename = "<")]
+    /// Less than.
+    LessThan,
+    #[serde(rename = ">")]
+    /// Greater than.
+    GreaterThan,
+    #[serde(rename = "=")]
+    /// Equal to.
+    Equal,
+    #[serde(rename = "≥", alias = ">=")]
+    /// Greater than or equal to.
+    GreaterThanOrEqual,
+    #[serde(rename = "≤", alias = "<=")]
+    /// Less than or equal to.
+    LessThanOrEqual,
+    #[serde(rename = "≠", alias = "!=")]
+    /// Not equal to.
+    NotEqual,
+}
+
+impl Comparator {
+    /// Compare two values with this operator.
+    pub fn evaluate<T: PartialOrd>(&self, lhs: T, rhs: T) -> bool {
+        match self {
+            Comparator::LessThan => lhs < rhs,
+            Comparator::GreaterThan => lhs > rhs,
+            Comparator::Equal => lhs == rhs,
+            Comparator::GreaterThanOrEqual => lhs >= rhs,
+            Comparator::LessThanOrEqual => lhs <= rhs,
+            Comparator::NotEqual => lhs != rhs,
+        }
+    }
+
+    /// The symbol the game uses for this operator.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Comparator::LessThan => "<",
+            Comparator::GreaterThan => ">",
+            Comparator::Equal => "=",
+            Comparator::GreaterThanOrEqual => "≥",
+            Comparator::LessThanOrEqual => "≤",
+            Comparator::NotEqual => "≠",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 /// The types of wait conditions that can be used in a train schedule
 pub enum ConditionType {
@@ -293,9 +1070,27 @@ pub enum ConditionType {
     PassengerPresent,
     /// Wait until a passenger is not present.
     PassengerNotPresent,
+    /// Wait until every fuel slot satisfies an item condition (2.0).
+    FuelItemCountAll,
+    /// Wait until any fuel slot satisfies an item condition (2.0).
+    FuelItemCountAny,
+    /// Wait until the fuel inventory is full (2.0).
+    FuelFull,
+    /// Wait until the destination is full or unreachable (2.0).
+    DestinationFullOrNoPath,
+    /// Wait until a specific stop is full (2.0).
+    SpecificDestinationFull,
+    /// Wait until a specific stop is not full (2.0).
+    SpecificDestinationNotFull,
+    /// Wait until the train is at a specific stop (2.0).
+    AtStation,
+    /// Wait until the train is not at a specific stop (2.0).
+    NotAtStation,
+    /// Wait until the train has taken an amount of damage (2.0).
+    DamageTaken,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 /// Determines how multiple comparisons in a wait condition combine
 pub enum CompareType {
@@ -305,7 +1100,7 @@ pub enum CompareType {
     Or,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 /// Floor tiles in a blueprint, i.e. landfill, concrete, stone bricks
 pub struct Tile {
     /// Prototype name of the tile (e.g. "concrete").
@@ -314,7 +1109,7 @@ pub struct Tile {
     pub position: Position,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// A position within a blueprint
 pub struct Position {
     /// X position within the blueprint, 0 is the center.
@@ -323,36 +1118,41 @@ pub struct Position {
     pub y: f64,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 /// Object containing information about the connections to other entities formed by red or green wires.
 pub struct Connection {
+    #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "1")]
     /// First connection point. The default for everything that doesn't have multiple connection points.
     pub first: Option<ConnectionPoint>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "2")]
     /// Second connection point. For example, the "output" part of an arithmetic combinator.
     pub second: Option<ConnectionPoint>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 /// The actual point where a wire is connected to. Contains information about where it is connected to.
 pub struct ConnectionPoint {
+    #[serde(skip_serializing_if = "Option::is_none")]
     /// An array containing all the connections from this point created by red wire.
     pub red: Option<Vec<ConnectionData>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     /// An array containing all the connections from this point created by green wire.
     pub green: Option<Vec<ConnectionData>>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 /// Information about a single connection between two connection points.
 pub struct ConnectionData {
     /// ID of the entity this connection is connected with.
     pub entity_id: NonZeroUsize,
+    #[serde(skip_serializing_if = "Option::is_none")]
     /// The circuit connector id of the entity this connection is connected to
     pub circuit_id: Option<CircuitConnectorId>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize_repr, Deserialize_repr)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize_repr, Deserialize_repr)]
 #[repr(u32)]
 /// The type of entity a circuit is connected to.
 pub enum CircuitConnectorId {
@@ -392,25 +1192,32 @@ pub enum CircuitConnectorId {
     Pump = 16,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 /// An item filter in a non-logistics container.
 pub struct ItemFilter {
     /// Name of the item prototype this filter is set to.
     pub name: String,
     /// Index of the filter.
     pub index: NonZeroUsize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Quality the filter is set to (2.0). Any quality if absent.
+    pub quality: Option<Quality>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// How the item's quality is compared against the filter quality (2.0).
+    pub comparator: Option<Comparator>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 /// The settings on an "infinite" container
 pub struct InfinitySettings {
     /// Whether the "remove unfiltered items" checkbox is checked.
     pub remove_unfiltered_items: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
     /// Filters of the infinity container.
     pub filters: Option<Vec<InfinityFilter>>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 /// A filter for items/fluids in an "infinite" container.
 pub struct InfinityFilter {
     /// Name of the item prototype the filter is set to.
@@ -423,7 +1230,7 @@ pub struct InfinityFilter {
     pub index: NonZeroUsize,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 /// The mode for filters in an "infinite" container.
 pub enum InfinityFilterMode {
@@ -435,18 +1242,81 @@ pub enum InfinityFilterMode {
     Exactly,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// The settings of an infinity pipe.
+pub struct InfinityPipeSettings {
+    /// Name of the fluid prototype the pipe is set to.
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// How full the pipe is kept, from 0 to 1.
+    pub percentage: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Temperature of the fluid the pipe makes.
+    pub temperature: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// What the pipe does to keep itself as full as it is set to. At least if absent.
+    pub mode: Option<InfinityPipeMode>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+/// How an infinity pipe keeps itself filled.
+pub enum InfinityPipeMode {
+    #[default]
+    /// Add fluid until it is at least as full as it is set to.
+    AtLeast,
+    /// Remove fluid until it is at most as full as it is set to.
+    AtMost,
+    /// Add or remove fluid to keep it exactly as full as it is set to.
+    Exactly,
+    /// Keep adding fluid.
+    Add,
+    /// Keep removing fluid.
+    Remove,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 /// A logistics filter/request in a logistics container.
 pub struct LogisticFilter {
     /// Name of the item prototype this filter is set to.
     pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "type")]
+    /// Type of the signal, when used by a constant combinator (2.0). Item if absent.
+    pub signal_type: Option<SignalType>,
     /// Index of the filter.
     pub index: NonZeroUsize,
-    /// Number the filter is set to. Is 0 for storage chests.
-    pub count: ItemCountType,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    /// Number the filter is set to. Is 0 for storage chests, and may be negative in constant combinators.
+    pub count: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Maximum number of the item to keep (2.0).
+    pub max_count: Option<ItemCountType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Quality the filter is set to (2.0). Normal if absent.
+    pub quality: Option<Quality>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// How the item's quality is compared against the filter quality (2.0).
+    pub comparator: Option<Comparator>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+impl LogisticFilter {
+    /// The signal this filter refers to.
+    pub fn signal(&self) -> SignalId {
+        SignalId {
+            name: self.name.clone(),
+            signal_type: self.signal_type.clone(),
+            quality: self.quality.clone(),
+        }
+    }
+}
+
+/// Whether `count` is 0, as a filter without a count is read.
+fn is_zero(count: &i32) -> bool {
+    *count == 0
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// Parameters for a speaker.
 pub struct SpeakerParameter {
     /// Volume of the speaker.
@@ -457,20 +1327,22 @@ pub struct SpeakerParameter {
     pub allow_polyphony: bool,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 /// Parameters for a speaker alert.
 pub struct SpeakerAlertParameter {
     /// Whether an alert is shown.
     pub show_alert: bool,
     /// Whether an alert icon is shown on the map.
     pub show_on_map: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
     /// The icon that is displayed with the alert.
-    pub icon_signal_id: SignalId,
+    pub icon_signal_id: Option<SignalId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     /// Message of the alert.
-    pub alert_message: String,
+    pub alert_message: Option<String>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// Determines the color of an object.
 pub struct Color {
     /// Red, 0 to 1.
@@ -482,3 +1354,254 @@ pub struct Color {
     /// Transparency, 0 to 1.
     pub a: f64,
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn circuit_conditions_compare_against_a_signal_or_constant() {
+        let value = |signal: &SignalId| match signal.name.as_str() {
+            "iron-plate" => 50,
+            "copper-plate" => 80,
+            _ => 0,
+        };
+        let condition: CircuitCondition = serde_json::from_value(json!({
+            "first_signal": {"type": "item", "name": "iron-plate"},
+            "constant": 50,
+            "comparator": ">=",
+        }))
+        .unwrap();
+        assert_eq!(condition.comparator, Some(Comparator::GreaterThanOrEqual));
+        assert!(condition.evaluate(value));
+
+        // The second signal takes precedence over the constant
+        let against_copper = CircuitCondition {
+            second_signal: Some(SignalId::item("copper-plate")),
+            ..condition.clone()
+        };
+        assert!(!against_copper.evaluate(value));
+
+        // `<` against 0 when neither are set, and never fulfilled without a first signal
+        let defaults = CircuitCondition {
+            first_signal: Some(SignalId::item("coal")),
+            ..Default::default()
+        };
+        assert!(!defaults.evaluate(|_| 0));
+        assert!(defaults.evaluate(|_| -1));
+        assert!(!CircuitCondition::default().evaluate(|_| -1));
+    }
+
+    #[test]
+    fn modded_qualities_are_kept_by_name() {
+        let qualities: Vec<Quality> =
+            serde_json::from_value(json!(["legendary", "mythic", "normal"])).unwrap();
+        assert_eq!(
+            qualities,
+            [
+                Quality::Legendary,
+                Quality::Other("mythic".to_owned()),
+                Quality::Normal
+            ]
+        );
+        assert_eq!(qualities[0].level(), Some(5));
+        assert_eq!(qualities[1].level(), None);
+        assert_eq!(
+            serde_json::to_value(&qualities).unwrap(),
+            json!(["legendary", "mythic", "normal"])
+        );
+    }
+
+    #[test]
+    fn filters_without_a_count_keep_it_left_out() {
+        let json = json!([
+            {"name": "iron-plate", "index": 1},
+            {"name": "copper-plate", "index": 2, "count": 50},
+        ]);
+        let filters: Vec<LogisticFilter> = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(filters[0].count, 0);
+        assert_eq!(serde_json::to_value(&filters).unwrap(), json);
+    }
+
+    #[test]
+    fn blueprints_of_2_0_keep_their_shape() {
+        let json = json!({
+            "item": "blueprint",
+            "entities": [
+                {
+                    "entity_number": 1,
+                    "name": "assembling-machine-2",
+                    "position": {"x": 1.5, "y": 1.5},
+                    "mirror": true,
+                    "quality": "uncommon",
+                    "items": [{
+                        "id": {"name": "speed-module", "quality": "rare"},
+                        "items": {"in_inventory": [
+                            {"inventory": 4, "stack": 0},
+                            {"inventory": 4, "stack": 1, "count": 1},
+                        ]},
+                    }],
+                },
+                {
+                    "entity_number": 2,
+                    "name": "small-lamp",
+                    "position": {"x": 3.5, "y": 0.5},
+                },
+            ],
+            "wires": [[1, 1, 2, 2]],
+            "version": 562949954076673u64,
+        });
+        let blueprint: Blueprint = serde_json::from_value(json.clone()).unwrap();
+        let entities = blueprint.entities.as_deref().unwrap();
+        assert_eq!(entities[0].mirror, Some(true));
+        assert_eq!(entities[0].quality, Some(Quality::Uncommon));
+        let items = entities[0].items.as_ref().unwrap();
+        assert_eq!(items.counts(), [("speed-module", 2)]);
+        let wire = &blueprint.wires.as_deref().unwrap()[0];
+        assert_eq!(wire.source_connector, WireConnectorId::CircuitRed);
+        assert_eq!(wire.target_connector, WireConnectorId::CircuitGreen);
+        assert_eq!(serde_json::to_value(&blueprint).unwrap(), json);
+    }
+
+    #[test]
+    fn items_before_2_0_keep_their_shape() {
+        let json = json!({"speed-module": 2, "productivity-module": 1});
+        let items: EntityItems = serde_json::from_value(json.clone()).unwrap();
+        assert!(matches!(items, EntityItems::Legacy(_)));
+        assert_eq!(serde_json::to_value(&items).unwrap(), json);
+    }
+
+    #[test]
+    fn equipment_grids_keep_both_forms_and_can_be_edited() {
+        let json = json!({
+            "entity_number": 1,
+            "name": "spidertron",
+            "position": {"x": 1.0, "y": 1.0},
+            "grid": [
+                {"equipment": "fusion-reactor-equipment", "position": {"x": 0, "y": 0}},
+                {
+                    "equipment": {"name": "exoskeleton-equipment", "quality": "rare"},
+                    "position": {"x": 4, "y": 0},
+                },
+            ],
+        });
+        let mut entity: Entity = serde_json::from_value(json.clone()).unwrap();
+        let names: Vec<_> = entity
+            .equipment()
+            .iter()
+            .map(|e| e.equipment.name())
+            .collect();
+        assert_eq!(names, ["fusion-reactor-equipment", "exoskeleton-equipment"]);
+        assert_eq!(serde_json::to_value(&entity).unwrap(), json);
+
+        let corner = GridPosition { x: 0, y: 0 };
+        let removed = entity.remove_equipment(corner).unwrap();
+        assert_eq!(removed.equipment.name(), "fusion-reactor-equipment");
+        assert_eq!(entity.remove_equipment(corner), None);
+        let shield = ItemId {
+            name: "energy-shield-equipment".to_owned(),
+            quality: None,
+        };
+        entity.add_equipment(shield, corner);
+        assert_eq!(
+            entity.equipment()[1].equipment,
+            EquipmentId::Item(ItemId {
+                name: "energy-shield-equipment".to_owned(),
+                quality: None,
+            })
+        );
+    }
+
+    #[test]
+    fn schedules_of_2_0_keep_their_interrupts_and_group() {
+        let json = json!({
+            "locomotives": [1],
+            "schedule": {
+                "records": [{"station": "Mine"}],
+                "interrupts": [{
+                    "name": "Repair",
+                    "conditions": [{"type": "damage_taken", "compare_type": "or", "damage": 500}],
+                    "targets": [{"station": "Depot"}],
+                    "inside_interrupt": true,
+                }],
+                "group": "Ore trains",
+            },
+        });
+        let schedule: Schedule = serde_json::from_value(json.clone()).unwrap();
+        let interrupt = &schedule.schedule.interrupts()[0];
+        assert_eq!(
+            interrupt.conditions[0].condition_type,
+            ConditionType::DamageTaken
+        );
+        assert_eq!(interrupt.conditions[0].damage, Some(500));
+        assert_eq!(interrupt.targets[0].station, "Depot");
+        assert_eq!(schedule.schedule.records()[0].station, "Mine");
+        assert_eq!(serde_json::to_value(&schedule).unwrap(), json);
+
+        let legacy: Schedule = serde_json::from_value(json!({
+            "locomotives": [1],
+            "schedule": [{"station": "Mine"}],
+        }))
+        .unwrap();
+        assert!(legacy.schedule.interrupts().is_empty());
+        assert_eq!(legacy.schedule.records()[0].station, "Mine");
+    }
+
+    #[test]
+    fn entity_tags_are_kept_as_they_are() {
+        let json = json!({
+            "entity_number": 1,
+            "name": "iron-chest",
+            "position": {"x": 0.5, "y": 0.5},
+            "tags": {"mod": {"id": 7, "owners": ["a", "b"]}, "enabled": true},
+        });
+        let entity: Entity = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(entity.tag("enabled"), Some(&json!(true)));
+        assert_eq!(entity.tag("mod").unwrap()["owners"][1], "b");
+        assert_eq!(entity.tag("missing"), None);
+        assert_eq!(serde_json::to_value(&entity).unwrap(), json);
+    }
+
+    #[test]
+    fn item_requests_are_written_in_name_order() {
+        let items: EntityItems = serde_json::from_str(
+            r#"{"speed-module": 2, "productivity-module": 1, "efficiency-module": 3}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            serde_json::to_string(&items).unwrap(),
+            r#"{"efficiency-module":3,"productivity-module":1,"speed-module":2}"#
+        );
+    }
+
+    #[test]
+    fn infinity_pipes_keep_their_settings() {
+        let json = json!({
+            "entity_number": 1,
+            "name": "infinity-pipe",
+            "position": {"x": 0.5, "y": 0.5},
+            "infinity_settings": {
+                "name": "steam",
+                "percentage": 0.5,
+                "temperature": 500,
+                "mode": "exactly",
+            },
+        });
+        let entity: Entity = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(
+            entity.infinity_pipe_settings(),
+            Some(&InfinityPipeSettings {
+                name: "steam".to_owned(),
+                percentage: Some(0.5),
+                temperature: Some(500.0),
+                mode: Some(InfinityPipeMode::Exactly),
+            })
+        );
+        assert_eq!(
+            serde_json::to_value(&entity).unwrap()["infinity_settings"]["mode"],
+            "exactly"
+        );
+    }
+}
@@ -0,0 +1,169 @@
+//! Traversal of (possibly nested) blueprint books.
+
+use core::{fmt, slice};
+
+use serde::{
+    de::{self, IgnoredAny, MapAccess, Visitor},
+    Deserialize, Deserializer,
+};
+
+use super::{Blueprint, BlueprintBook, BlueprintEnvelope, BookBpWrapper};
+
+/// A depth-first iterator over every entry of a blueprint book, including the contents of nested books.
+///
+/// Nested books are yielded before their contents.
+pub struct BookIter<'a> {
+    stack: Vec<slice::Iter<'a, BookBpWrapper>>,
+}
+
+impl<'a> Iterator for BookIter<'a> {
+    type Item = &'a BlueprintEnvelope;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let entries = self.stack.last_mut()?;
+
+            match entries.next() {
+                Some(entry) => {
+                    if let BlueprintEnvelope::BlueprintBook(book) = &entry.content {
+                        self.stack.push(book.blueprints.iter());
+                    }
+                    return Some(&entry.content);
+                }
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+impl BlueprintBook {
+    /// Iterate over every entry in this book and all books nested within it.
+    pub fn iter_recursive(&self) -> BookIter<'_> {
+        BookIter {
+            stack: vec![self.blueprints.iter()],
+        }
+    }
+
+    /// Iterate over every blueprint in this book and all books nested within it.
+    pub fn blueprints_recursive(&self) -> impl Iterator<Item = &Blueprint> {
+        self.iter_recursive().filter_map(|entry| match entry {
+            BlueprintEnvelope::Blueprint(blueprint) => Some(blueprint),
+            _ => None,
+        })
+    }
+}
+
+// Written by hand rather than derived with `#[serde(flatten)]`, which buffers the entry and loses
+// track of where in the JSON any errors or unknown fields are.
+impl<'de> Deserialize<'de> for BookBpWrapper {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct EntryVisitor;
+
+        impl<'de> Visitor<'de> for EntryVisitor {
+            type Value = BookBpWrapper;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a blueprint book entry")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut index = None;
+                let mut content = None;
+
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "index" => index = Some(map.next_value()?),
+                        "blueprint" => {
+                            content = Some(BlueprintEnvelope::Blueprint(map.next_value()?))
+                        }
+                        "blueprint_book" => {
+                            content = Some(BlueprintEnvelope::BlueprintBook(map.next_value()?))
+                        }
+                        "deconstruction_planner" => {
+                            content =
+                                Some(BlueprintEnvelope::DeconstructionPlanner(map.next_value()?))
+                        }
+                        "upgrade_planner" => {
+                            content = Some(BlueprintEnvelope::UpgradePlanner(map.next_value()?))
+                        }
+                        _ => {
+                            map.next_value::<IgnoredAny>()?;
+                        }
+                    }
+                }
+
+                Ok(BookBpWrapper {
+                    index: index.ok_or_else(|| de::Error::missing_field("index"))?,
+                    content: content.ok_or_else(|| {
+                        de::Error::custom("book entry has no blueprint, book, or planner")
+                    })?,
+                })
+            }
+        }
+
+        deserializer.deserialize_map(EntryVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    const VERSION: u64 = 562949954076673;
+
+    /// A book entry at `index` holding `content` under the key `kind`.
+    fn entry(index: usize, kind: &str, content: Value) -> Value {
+        json!({"index": index, kind: content})
+    }
+
+    /// An empty blueprint labelled `label`.
+    fn blueprint(label: &str) -> Value {
+        json!({"item": "blueprint", "label": label, "version": VERSION})
+    }
+
+    #[test]
+    fn nested_books_are_walked_depth_first() {
+        let inner = json!({
+            "item": "blueprint-book",
+            "label": "Inner",
+            "blueprints": [
+                entry(0, "blueprint", blueprint("B")),
+                entry(1, "upgrade_planner", json!({"item": "upgrade-planner", "version": VERSION})),
+            ],
+            "active_index": 0,
+            "version": VERSION,
+        });
+        let book: BlueprintBook = serde_json::from_value(json!({
+            "item": "blueprint-book",
+            "blueprints": [
+                entry(0, "blueprint", blueprint("A")),
+                entry(1, "blueprint_book", inner),
+                entry(2, "blueprint", blueprint("C")),
+            ],
+            "active_index": 0,
+            "version": VERSION,
+        }))
+        .unwrap();
+
+        let kinds: Vec<_> = book
+            .iter_recursive()
+            .map(|entry| match entry {
+                BlueprintEnvelope::Blueprint(blueprint) => blueprint.label.as_deref().unwrap(),
+                BlueprintEnvelope::BlueprintBook(book) => book.label.as_deref().unwrap(),
+                BlueprintEnvelope::DeconstructionPlanner(_) => "deconstruction planner",
+                BlueprintEnvelope::UpgradePlanner(_) => "upgrade planner",
+            })
+            .collect();
+        assert_eq!(kinds, ["A", "Inner", "B", "upgrade planner", "C"]);
+
+        let labels: Vec<_> = book
+            .blueprints_recursive()
+            .filter_map(|blueprint| blueprint.label.as_deref())
+            .collect();
+        assert_eq!(labels, ["A", "B", "C"]);
+    }
+}
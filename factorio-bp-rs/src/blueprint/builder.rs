@@ -0,0 +1,403 @@
+//! Putting blueprints together in code, with entity numbers and positions worked out.
+
+use core::num::NonZeroUsize;
+
+use thiserror::Error;
+
+use crate::prototypes::PrototypeDb;
+
+use super::{
+    Blueprint, Color, ControlBehavior, Direction, Entity, EntityDetails, EntityItems, Icon, Issue,
+    Position, Quality, Severity, SignalId, Tile, UnknownFields, Version, Wire,
+};
+
+/// The version of blueprints built, unless one is given.
+pub const BUILD_VERSION: Version = Version::new(2, 0, 28, 0);
+
+#[derive(Debug, Clone, PartialEq, Error)]
+/// Why a [BlueprintBuilder] couldn't build its blueprint.
+pub enum BuildError {
+    /// An entity was placed by tile, but the prototypes don't know its size.
+    #[error("the size of {0:?} isn't known, so it can't be placed by tile")]
+    UnknownSize(String),
+    /// The blueprint has problems the game would reject it for. Only the issues with
+    /// [Severity::Error] are listed.
+    #[error("the blueprint is invalid: {}", list(.0))]
+    Invalid(Vec<Issue>),
+}
+
+/// The issues, separated by semicolons.
+fn list(issues: &[Issue]) -> String {
+    let issues: Vec<_> = issues.iter().map(Issue::to_string).collect();
+    issues.join("; ")
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// An entity to add to a [BlueprintBuilder], which numbers it.
+pub struct EntityBuilder {
+    entity: Entity,
+    tile: Option<(i64, i64)>,
+}
+
+impl EntityBuilder {
+    /// An entity of the prototype `name`, facing north, centred on the blueprint's origin.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            entity: Entity {
+                entity_number: NonZeroUsize::MIN,
+                name: name.into(),
+                position: Position { x: 0.0, y: 0.0 },
+                direction: None,
+                mirror: None,
+                quality: None,
+                connections: None,
+                control_behavior: None,
+                neighbors: None,
+                items: None,
+                color: None,
+                tags: None,
+                details: EntityDetails::Plain,
+                extra: UnknownFields::new(),
+            },
+            tile: None,
+        }
+    }
+
+    /// Centre the entity on `(x, y)`.
+    pub fn at(mut self, x: f64, y: f64) -> Self {
+        self.entity.position = Position { x, y };
+        self.tile = None;
+        self
+    }
+
+    /// Put the top-left corner of the entity on the tile `(x, y)`, whatever its size. The size
+    /// comes from the prototypes the blueprint is built with, turned to the entity's direction.
+    pub fn at_tile(mut self, x: i64, y: i64) -> Self {
+        self.tile = Some((x, y));
+        self
+    }
+
+    /// Turn the entity to face `direction`.
+    pub fn direction(mut self, direction: Direction) -> Self {
+        // The game leaves out the direction of entities facing north
+        self.entity.direction = (direction != Direction::North).then_some(direction);
+        self
+    }
+
+    /// Set the quality of the entity.
+    pub fn quality(mut self, quality: Quality) -> Self {
+        self.entity.quality = (quality != Quality::Normal).then_some(quality);
+        self
+    }
+
+    /// Set the recipe of a crafting machine.
+    pub fn recipe(mut self, recipe: impl Into<String>) -> Self {
+        let recipe = Some(recipe.into());
+        match &mut self.entity.details {
+            EntityDetails::CraftingMachine { recipe: set, .. } => *set = recipe,
+            details => {
+                *details = EntityDetails::CraftingMachine {
+                    recipe,
+                    recipe_quality: None,
+                    auto_launch: None,
+                }
+            }
+        }
+        self
+    }
+
+    /// Set the settings of the kind of entity this is.
+    pub fn details(mut self, details: EntityDetails) -> Self {
+        self.entity.details = details;
+        self
+    }
+
+    /// Set the circuit network settings of the entity.
+    pub fn control_behavior(mut self, control_behavior: ControlBehavior) -> Self {
+        self.entity.control_behavior = Some(control_behavior);
+        self
+    }
+
+    /// Set the items to insert into the entity once it is built.
+    pub fn items(mut self, items: EntityItems) -> Self {
+        self.entity.items = Some(items);
+        self
+    }
+
+    /// Set the colour of the entity.
+    pub fn color(mut self, color: Color) -> Self {
+        self.entity.color = Some(color);
+        self
+    }
+
+    /// Set a mod tag.
+    pub fn tag(mut self, key: impl Into<String>, value: impl Into<serde_json::Value>) -> Self {
+        let tags = self.entity.tags.get_or_insert_with(Default::default);
+        tags.insert(key.into(), value.into());
+        self
+    }
+
+    /// The entity, numbered `entity_number`, with its position worked out from `db`.
+    fn build(
+        mut self,
+        entity_number: NonZeroUsize,
+        db: &PrototypeDb,
+    ) -> Result<Entity, BuildError> {
+        self.entity.entity_number = entity_number;
+        if let Some((x, y)) = self.tile {
+            let prototype = self
+                .entity
+                .prototype(db)
+                .ok_or_else(|| BuildError::UnknownSize(self.entity.name.clone()))?;
+            let (width, height) = prototype.tile_size(self.entity.direction.unwrap_or_default());
+            self.entity.position = Position {
+                x: x as f64 + width as f64 / 2.0,
+                y: y as f64 + height as f64 / 2.0,
+            };
+        }
+        Ok(self.entity)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A blueprint being put together. Entities are numbered in the order they are added, from 1.
+pub struct BlueprintBuilder {
+    blueprint: Blueprint,
+    entities: Vec<EntityBuilder>,
+}
+
+impl BlueprintBuilder {
+    /// An empty blueprint of version [BUILD_VERSION].
+    pub fn new() -> Self {
+        Self {
+            blueprint: Blueprint {
+                item: "blueprint".to_owned(),
+                label: None,
+                label_color: None,
+                description: None,
+                entities: None,
+                tiles: None,
+                icons: Vec::new(),
+                schedules: None,
+                wires: None,
+                parameters: None,
+                snap_to_grid: None,
+                absolute_snapping: None,
+                position_relative_to_grid: None,
+                version: BUILD_VERSION,
+                extra: UnknownFields::new(),
+            },
+            entities: Vec::new(),
+        }
+    }
+
+    /// Set the name of the blueprint.
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        self.blueprint.label = Some(label.into());
+        self
+    }
+
+    /// Set the description of the blueprint.
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.blueprint.description = Some(description.into());
+        self
+    }
+
+    /// Add an icon after those already added.
+    pub fn icon(mut self, signal: SignalId) -> Self {
+        let index = NonZeroUsize::MIN.saturating_add(self.blueprint.icons.len());
+        self.blueprint.icons.push(Icon { index, signal });
+        self
+    }
+
+    /// Set the version of the blueprint.
+    pub fn version(mut self, version: Version) -> Self {
+        self.blueprint.version = version;
+        self
+    }
+
+    /// Add an entity.
+    pub fn entity(mut self, entity: EntityBuilder) -> Self {
+        self.add_entity(entity);
+        self
+    }
+
+    /// Add an entity, returning the entity_number it gets, for wiring it up.
+    pub fn add_entity(&mut self, entity: EntityBuilder) -> NonZeroUsize {
+        self.entities.push(entity);
+        NonZeroUsize::MIN.saturating_add(self.entities.len() - 1)
+    }
+
+    /// Add a tile of the prototype `name` with its top-left corner at `(x, y)`.
+    pub fn tile(mut self, name: impl Into<String>, x: i64, y: i64) -> Self {
+        let tile = Tile {
+            name: name.into(),
+            position: Position {
+                x: x as f64,
+                y: y as f64,
+            },
+        };
+        self.blueprint.tiles.get_or_insert_with(Vec::new).push(tile);
+        self
+    }
+
+    /// Add a wire between two entities, by the entity_numbers [BlueprintBuilder::add_entity]
+    /// gave them.
+    pub fn wire(mut self, wire: Wire) -> Self {
+        self.blueprint.wires.get_or_insert_with(Vec::new).push(wire);
+        self
+    }
+
+    /// The blueprint, if it has none of the problems [Blueprint::validate] finds errors.
+    ///
+    /// Entity sizes come from the [global](PrototypeDb::global) prototypes, see
+    /// [BlueprintBuilder::build_with].
+    pub fn build(self) -> Result<Blueprint, BuildError> {
+        self.build_with(PrototypeDb::global())
+    }
+
+    /// The blueprint, with entity sizes from `db`, if it has none of the problems
+    /// [Blueprint::validate_with] finds errors.
+    pub fn build_with(self, db: &PrototypeDb) -> Result<Blueprint, BuildError> {
+        let mut blueprint = self.blueprint;
+        if !self.entities.is_empty() {
+            let entities = self
+                .entities
+                .into_iter()
+                .enumerate()
+                .map(|(i, entity)| entity.build(NonZeroUsize::MIN.saturating_add(i), db))
+                .collect::<Result<_, _>>()?;
+            blueprint.entities = Some(entities);
+        }
+
+        let errors: Vec<_> = blueprint
+            .validate_with(db)
+            .into_iter()
+            .filter(|issue| issue.severity() == Severity::Error)
+            .collect();
+        if errors.is_empty() {
+            Ok(blueprint)
+        } else {
+            Err(BuildError::Invalid(errors))
+        }
+    }
+}
+
+impl Default for BlueprintBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A [BlueprintBuilder] with the entities listed, each numbered in order and separated by
+/// semicolons.
+///
+/// Each entity is its prototype name, `@`, and the tile its top-left corner is on, as in
+/// `"assembling-machine-2" @ (0, 0)`. Settings may follow: `recipe "iron-gear-wheel"`,
+/// `facing East` with a [Direction], and `quality Rare` with a [Quality]. So
+/// `bp! { "assembling-machine-2" @ (0, 0) recipe "iron-gear-wheel"; "inserter" @ (1, 3) facing South }`
+/// is an assembling machine making gears with an inserter taking them out, ready to `build()`.
+#[macro_export]
+macro_rules! bp {
+    ($($name:literal @ ($x:expr, $y:expr) $($setting:ident $value:tt)*);* $(;)?) => {{
+        let builder = $crate::blueprint::BlueprintBuilder::new();
+        $(
+            let entity = $crate::blueprint::EntityBuilder::new($name).at_tile($x, $y);
+            $(let entity = $crate::__bp_setting!(entity, $setting $value);)*
+            let builder = builder.entity(entity);
+        )*
+        builder
+    }};
+}
+
+/// Apply one setting of an entity in [bp!].
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __bp_setting {
+    ($entity:ident, recipe $recipe:tt) => {
+        $entity.recipe($recipe)
+    };
+    ($entity:ident, facing $direction:ident) => {
+        $entity.direction($crate::blueprint::Direction::$direction)
+    };
+    ($entity:ident, quality $quality:ident) => {
+        $entity.quality($crate::blueprint::Quality::$quality)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entities_are_numbered_and_placed_by_tile() {
+        let db = PrototypeDb::vanilla();
+        let mut builder = BlueprintBuilder::new().label("Power");
+        let engine = builder.add_entity(
+            EntityBuilder::new("steam-engine")
+                .at_tile(0, 0)
+                .direction(Direction::East),
+        );
+        let pole = builder.add_entity(EntityBuilder::new("small-electric-pole").at_tile(0, 3));
+        assert_eq!((engine.get(), pole.get()), (1, 2));
+
+        let blueprint = builder.build_with(db).unwrap();
+        assert_eq!(blueprint.label.as_deref(), Some("Power"));
+        assert_eq!(blueprint.version, BUILD_VERSION);
+        let entities = blueprint.entities.unwrap();
+        assert_eq!(entities[0].position, Position { x: 2.5, y: 1.5 });
+        assert_eq!(entities[1].position, Position { x: 0.5, y: 3.5 });
+        assert_eq!(entities[1].entity_number, pole);
+    }
+
+    #[test]
+    fn blueprints_the_game_would_reject_are_not_built() {
+        let db = PrototypeDb::vanilla();
+        let unknown = BlueprintBuilder::new()
+            .entity(EntityBuilder::new("modded-chest").at_tile(0, 0))
+            .build_with(db);
+        assert_eq!(
+            unknown,
+            Err(BuildError::UnknownSize("modded-chest".to_owned()))
+        );
+
+        let overlapping = BlueprintBuilder::new()
+            .entity(EntityBuilder::new("iron-chest").at_tile(0, 0))
+            .entity(EntityBuilder::new("assembling-machine-1").at_tile(0, 0))
+            .build_with(db);
+        let Err(BuildError::Invalid(issues)) = overlapping else {
+            panic!("expected the overlap to be rejected");
+        };
+        assert!(issues.iter().all(|i| i.severity() == Severity::Error));
+        assert!(issues.iter().any(|i| matches!(i, Issue::Overlap(..))));
+    }
+
+    #[test]
+    fn bp_lists_entities_with_their_settings() {
+        let db = PrototypeDb::vanilla();
+        let blueprint = crate::bp! {
+            "assembling-machine-2" @ (0, 0) recipe "iron-gear-wheel" quality Rare;
+            "inserter" @ (1, 3) facing South;
+        }
+        .build_with(db)
+        .unwrap();
+        let expected = BlueprintBuilder::new()
+            .entity(
+                EntityBuilder::new("assembling-machine-2")
+                    .at_tile(0, 0)
+                    .recipe("iron-gear-wheel")
+                    .quality(Quality::Rare),
+            )
+            .entity(
+                EntityBuilder::new("inserter")
+                    .at_tile(1, 3)
+                    .direction(Direction::South),
+            )
+            .build_with(db)
+            .unwrap();
+        assert_eq!(blueprint, expected);
+        let entities = blueprint.entities.unwrap();
+        assert_eq!(entities[0].recipe(), Some("iron-gear-wheel"));
+        assert_eq!(entities[1].position, Position { x: 1.5, y: 3.5 });
+    }
+}
@@ -0,0 +1,254 @@
+//! A canonical form of blueprints, so that the same blueprint always encodes the same way.
+
+use core::{cmp::Ordering, num::NonZeroUsize};
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use super::{Blueprint, BlueprintBook, BlueprintEnvelope, BpError, Position, Version, Wire};
+
+/// The offset basis of the 64-bit FNV-1a hash.
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+/// The prime of the 64-bit FNV-1a hash.
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Order positions top to bottom, then left to right.
+fn compare_positions(a: &Position, b: &Position) -> Ordering {
+    a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x))
+}
+
+/// The ends of a wire, each an entity number and connector.
+fn wire_ends(wire: &Wire) -> [(NonZeroUsize, u8); 2] {
+    [
+        (wire.source_entity, wire.source_connector as u8),
+        (wire.target_entity, wire.target_connector as u8),
+    ]
+}
+
+/// Write whole floats as integers, and -0 as 0, so a number is always written the same way.
+fn normalize_numbers(value: &mut Value) {
+    match value {
+        Value::Number(number) if number.is_f64() => {
+            let float = number.as_f64().unwrap_or_default();
+            if float.fract() == 0.0 && float.abs() <= i64::MAX as f64 {
+                *value = Value::from(float as i64);
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(normalize_numbers),
+        Value::Object(fields) => fields.values_mut().for_each(normalize_numbers),
+        _ => {}
+    }
+}
+
+impl Blueprint {
+    /// Put the blueprint in a canonical form, in which blueprints that are the same in game are
+    /// equal: [minimized](Blueprint::minimize), with entities ordered by position top to bottom
+    /// then left to right and renumbered in that order, and tiles, wires, circuit connections,
+    /// icons, and locomotives sorted.
+    pub fn canonicalize(&mut self) {
+        self.minimize();
+
+        if let Some(entities) = &mut self.entities {
+            entities.sort_by(|a, b| {
+                compare_positions(&a.position, &b.position).then_with(|| a.name.cmp(&b.name))
+            });
+            let numbers: BTreeMap<_, _> = entities
+                .iter()
+                .zip(1..)
+                .filter_map(|(entity, number)| {
+                    Some((entity.entity_number, NonZeroUsize::new(number)?))
+                })
+                .collect();
+            self.map_entity_numbers(|number| numbers.get(&number).copied().unwrap_or(number));
+        }
+
+        for entity in self.entities.iter_mut().flatten() {
+            if let Some(neighbors) = &mut entity.neighbors {
+                neighbors.sort();
+                neighbors.dedup();
+            }
+            let points = entity
+                .connections
+                .iter_mut()
+                .flat_map(|connection| [&mut connection.first, &mut connection.second])
+                .flatten();
+            for point in points {
+                for data in point.red.iter_mut().chain(&mut point.green) {
+                    data.sort_by_key(|data| {
+                        (data.entity_id, data.circuit_id.clone().map(|id| id as u32))
+                    });
+                }
+            }
+        }
+        if let Some(tiles) = &mut self.tiles {
+            tiles.sort_by(|a, b| {
+                compare_positions(&a.position, &b.position).then_with(|| a.name.cmp(&b.name))
+            });
+        }
+        if let Some(wires) = &mut self.wires {
+            // Wires have no direction, so start each at its lower end
+            for wire in wires.iter_mut() {
+                let [source, target] = wire_ends(wire);
+                if target < source {
+                    core::mem::swap(&mut wire.source_entity, &mut wire.target_entity);
+                    core::mem::swap(&mut wire.source_connector, &mut wire.target_connector);
+                }
+            }
+            wires.sort_by_key(wire_ends);
+            wires.dedup_by_key(|wire| wire_ends(wire));
+        }
+        self.icons.sort_by_key(|icon| icon.index);
+        for schedule in self.schedules.iter_mut().flatten() {
+            schedule.locomotives.sort();
+        }
+    }
+
+    /// A hash of what the blueprint builds, the same for blueprints that differ only in their
+    /// label, description, icons, version, or the order of their entities and wires, for finding
+    /// duplicates. It is an FNV-1a hash of the [canonical JSON](BlueprintEnvelope::to_canonical_json),
+    /// so it doesn't change between runs or platforms.
+    pub fn fingerprint(&self) -> u64 {
+        let blueprint = Blueprint {
+            label: None,
+            label_color: None,
+            description: None,
+            icons: Vec::new(),
+            // Directions are written the way 2.0 numbers them whatever the version was
+            version: Version::new(2, 0, 0, 0),
+            ..self.clone()
+        };
+        // Blueprints always serialize
+        let json = BlueprintEnvelope::Blueprint(blueprint)
+            .to_canonical_json()
+            .unwrap_or_default();
+        json.bytes().fold(FNV_OFFSET_BASIS, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+        })
+    }
+}
+
+impl BlueprintBook {
+    /// [Canonicalize](Blueprint::canonicalize) every blueprint in the book, and sort its icons.
+    pub fn canonicalize(&mut self) {
+        self.icons.sort_by_key(|icon| icon.index);
+        for entry in &mut self.blueprints {
+            entry.content.canonicalize();
+        }
+    }
+}
+
+impl BlueprintEnvelope {
+    /// [Canonicalize](Blueprint::canonicalize) the blueprints. Planners are left as they are.
+    pub fn canonicalize(&mut self) {
+        match self {
+            BlueprintEnvelope::Blueprint(blueprint) => blueprint.canonicalize(),
+            BlueprintEnvelope::BlueprintBook(book) => book.canonicalize(),
+            BlueprintEnvelope::DeconstructionPlanner(_) | BlueprintEnvelope::UpgradePlanner(_) => {}
+        }
+    }
+
+    /// The JSON of the [canonical](Blueprint::canonicalize) form, with the fields of every object
+    /// in alphabetical order, and whole numbers written without a decimal point. The same in
+    /// game means the same bytes, so it can be hashed and diffed.
+    pub fn to_canonical_json(&self) -> Result<String, BpError> {
+        let mut envelope = self.clone();
+        envelope.canonicalize();
+        let mut json = envelope.to_json_value()?;
+        normalize_numbers(&mut json);
+        Ok(serde_json::to_string(&json)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{blueprint::SignalId, bp, prototypes::PrototypeDb};
+
+    /// A blueprint of the entities, each a name and position, and the wires between them.
+    fn blueprint(entities: &[(&str, f64, f64)], wires: Value) -> BlueprintEnvelope {
+        let entities: Vec<_> = entities
+            .iter()
+            .zip(1..)
+            .map(|(&(name, x, y), number)| {
+                json!({"entity_number": number, "name": name, "position": {"x": x, "y": y}})
+            })
+            .collect();
+        serde_json::from_value(json!({
+            "blueprint": {
+                "item": "blueprint",
+                "label": "Chests",
+                "entities": entities,
+                "wires": wires,
+                "version": 562949954076673u64,
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn the_same_blueprint_in_any_order_encodes_the_same() {
+        let first = blueprint(
+            &[
+                ("iron-chest", 2.5, 0.5),
+                ("small-electric-pole", 0.5, 0.5),
+                ("iron-chest", 0.5, 2.5),
+            ],
+            json!([[3, 1, 1, 1], [1, 1, 2, 1]]),
+        );
+        // Numbered in another order, with the wires the other way round and one of them twice
+        let second = blueprint(
+            &[
+                ("small-electric-pole", 0.5, 0.5),
+                ("iron-chest", 0.5, 2.5),
+                ("iron-chest", 2.5, 0.5),
+            ],
+            json!([[1, 1, 3, 1], [3, 1, 2, 1], [2, 1, 3, 1]]),
+        );
+
+        let canonical = first.to_canonical_json().unwrap();
+        assert_eq!(second.to_canonical_json().unwrap(), canonical);
+        let entity = |number, name, x, y| json!({"entity_number": number, "name": name, "position": {"x": x, "y": y}});
+        let expected = json!({
+            "blueprint": {
+                "entities": [
+                    entity(1, "small-electric-pole", 0.5, 0.5),
+                    entity(2, "iron-chest", 2.5, 0.5),
+                    entity(3, "iron-chest", 0.5, 2.5),
+                ],
+                "item": "blueprint",
+                "label": "Chests",
+                "version": 562949954076673u64,
+                "wires": [[1, 1, 2, 1], [2, 1, 3, 1]],
+            }
+        });
+        assert_eq!(canonical, expected.to_string());
+    }
+
+    #[test]
+    fn fingerprints_ignore_labels_icons_versions_and_order() {
+        let db = PrototypeDb::vanilla();
+        let first = bp! { "iron-chest" @ (0, 0); "inserter" @ (0, 1) facing East }
+            .label("Mine")
+            .build_with(db)
+            .unwrap();
+        // Directions are numbered differently before 2.0, but still compare the same
+        let second = bp! { "inserter" @ (0, 1) facing East; "iron-chest" @ (0, 0) }
+            .description("Yours")
+            .icon(SignalId::item("iron-chest"))
+            .version(Version::new(1, 1, 110, 0))
+            .build_with(db)
+            .unwrap();
+        let moved = bp! { "iron-chest" @ (1, 0); "inserter" @ (0, 1) facing East }
+            .build_with(db)
+            .unwrap();
+        let turned = bp! { "iron-chest" @ (0, 0); "inserter" @ (0, 1) facing West }
+            .build_with(db)
+            .unwrap();
+
+        assert_eq!(first.fingerprint(), second.fingerprint());
+        assert_ne!(first.fingerprint(), moved.fingerprint());
+        assert_ne!(first.fingerprint(), turned.fingerprint());
+    }
+}
@@ -0,0 +1,710 @@
+use core::num::NonZeroUsize;
+
+use serde::{Deserialize, Serialize};
+use serde_repr::*;
+
+use super::{
+    CircuitCondition, Comparator, CompareType, LogisticFilter, LogisticSection, LogisticSections,
+    QualityCondition, SignalId, SpeakerCircuitParameters, UnknownFields,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+/// The circuit network settings of an entity. Which fields are present depends on the entity.
+pub struct ControlBehavior {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The condition that enables the entity, e.g. for lamps, inserters, belts, and pumps.
+    pub circuit_condition: Option<CircuitCondition>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Whether the entity is enabled/disabled by `circuit_condition`. Used by inserters and belts.
+    pub circuit_enable_disable: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The condition on the logistic network that enables the entity.
+    pub logistic_condition: Option<CircuitCondition>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Whether `logistic_condition` is used.
+    pub connect_to_logistic_network: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Entity specific mode of operation, e.g. for inserters 0 = enable/disable, 1 = set filters,
+    /// 2 = read hand contents, 3 = none, 4 = set stack size.
+    pub circuit_mode_of_operation: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Whether an inserter or belt reads the items it is holding.
+    pub circuit_read_hand_contents: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// How an inserter reports the items in its hand.
+    pub circuit_hand_read_mode: Option<ReadMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// How a belt reports the items on it.
+    pub circuit_contents_read_mode: Option<ReadMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Whether an inserter's stack size is set by the circuit network.
+    pub circuit_set_stack_size: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The signal an inserter's stack size is read from.
+    pub stack_control_input_signal: Option<SignalId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Whether a mining drill reads the resources under it.
+    pub circuit_read_resources: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Which resources a mining drill reports.
+    pub circuit_resource_read_mode: Option<ResourceReadMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Whether a lamp takes its color from color signals.
+    pub use_colors: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Whether a constant combinator is switched on.
+    pub is_on: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The signals output by a constant combinator, before 2.0.
+    pub filters: Option<Vec<ConstantCombinatorSignal>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The signals output by a constant combinator, grouped into logistic sections (2.0).
+    pub sections: Option<LogisticSections>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The settings of an arithmetic combinator.
+    pub arithmetic_conditions: Option<ArithmeticCombinatorParameters>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The settings of a decider combinator.
+    pub decider_conditions: Option<DeciderCombinatorParameters>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// What a selector combinator does (2.0).
+    pub operation: Option<SelectorOperation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Whether a selector combinator in select mode sorts its inputs in descending order (2.0).
+    pub select_max: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The signal a selector combinator in select mode reads its index from (2.0).
+    pub index_signal: Option<SignalId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The index a selector combinator in select mode uses when there is no `index_signal` (2.0).
+    pub index_constant: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The signal a selector combinator in count mode outputs the number of inputs on (2.0).
+    pub count_signal: Option<SignalId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// How many ticks a selector combinator in random mode holds each output for (2.0).
+    pub random_update_interval: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The qualities a selector combinator in quality filter mode lets through (2.0).
+    pub quality_filter: Option<QualityCondition>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Whether a selector combinator in quality transfer mode reads the quality from a signal (2.0).
+    pub select_quality_from_signal: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The quality a selector combinator in quality transfer mode applies (2.0).
+    pub quality_source_static: Option<SignalId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The signal a selector combinator in quality transfer mode reads the quality from (2.0).
+    pub quality_source_signal: Option<SignalId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The signal a selector combinator in quality transfer mode outputs, or every signal if absent (2.0).
+    pub quality_destination_signal: Option<SignalId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Whether a roboport reads the contents of its logistic network.
+    pub read_logistics: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Whether a roboport reads its robot counts.
+    pub read_robot_stats: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The signal a roboport outputs its available logistic robots on.
+    pub available_logistic_output_signal: Option<SignalId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The signal a roboport outputs its total logistic robots on.
+    pub total_logistic_output_signal: Option<SignalId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The signal a roboport outputs its available construction robots on.
+    pub available_construction_output_signal: Option<SignalId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The signal a roboport outputs its total construction robots on.
+    pub total_construction_output_signal: Option<SignalId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Whether a train stop sends its circuit signals to the stopped train.
+    pub send_to_train: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Whether a train stop reads the contents of the stopped train.
+    pub read_from_train: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Whether a train stop outputs the ID of the stopped train.
+    pub read_stopped_train: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The signal a train stop outputs the stopped train's ID on.
+    pub train_stopped_signal: Option<SignalId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Whether a train stop's train limit is set by the circuit network.
+    pub set_trains_limit: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The signal a train stop's train limit is read from.
+    pub trains_limit_signal: Option<SignalId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Whether a train stop outputs the number of trains headed to it.
+    pub read_trains_count: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The signal a train stop outputs its train count on.
+    pub trains_count_signal: Option<SignalId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Whether a rail signal is closed by the circuit network.
+    pub circuit_close_signal: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Whether a rail signal outputs its state.
+    pub circuit_read_signal: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The signal a rail signal outputs when red.
+    pub red_output_signal: Option<SignalId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The signal a rail signal outputs when yellow.
+    pub orange_output_signal: Option<SignalId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The signal a rail signal outputs when green.
+    pub green_output_signal: Option<SignalId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The signal a rail chain signal outputs when blue.
+    pub blue_output_signal: Option<SignalId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Whether a gate is opened by the circuit network.
+    pub circuit_open_gate: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Whether a gate outputs whether something is near it.
+    pub circuit_read_sensor: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The signal a gate or accumulator outputs on.
+    pub output_signal: Option<SignalId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The instrument and note a programmable speaker plays.
+    pub circuit_parameters: Option<SpeakerCircuitParameters>,
+    #[cfg_attr(feature = "preserve-unknown", serde(flatten, skip_deserializing))]
+    #[cfg_attr(not(feature = "preserve-unknown"), serde(skip))]
+    /// Fields this crate doesn't know about, kept so they survive a round trip.
+    /// Only filled with the `preserve-unknown` feature.
+    pub extra: UnknownFields,
+}
+
+impl ControlBehavior {
+    /// The total value of every signal output by a constant combinator, in slot order.
+    ///
+    /// Signals set in several slots or sections are summed, inactive sections are ignored, and
+    /// section multipliers are applied (rounding towards zero). Whether the combinator is switched
+    /// on is not taken into account.
+    pub fn constant_signals(&self) -> Vec<(SignalId, i32)> {
+        let mut totals: Vec<(SignalId, i32)> = Vec::new();
+        let mut add = |signal: SignalId, count: i32| match totals
+            .iter_mut()
+            .find(|(existing, _)| existing.matches(&signal))
+        {
+            Some((_, total)) => *total = total.wrapping_add(count),
+            None => totals.push((signal, count)),
+        };
+
+        for filter in self.filters.iter().flatten() {
+            add(filter.signal.clone(), filter.count);
+        }
+
+        let sections = self.sections.iter().flat_map(|s| s.sections.iter());
+        for section in sections.filter(|section| section.is_active()) {
+            let multiplier = section.multiplier.unwrap_or(1.0);
+            for filter in &section.filters {
+                add(filter.signal(), (filter.count as f64 * multiplier) as i32);
+            }
+        }
+
+        totals
+    }
+
+    /// Set the value a constant combinator outputs for `signal`, replacing any existing slots for it.
+    ///
+    /// Combinators using the pre-2.0 format are edited in place; otherwise the signal is written to
+    /// the first section, which is created if necessary.
+    pub fn set_constant_signal(&mut self, signal: SignalId, count: i32) {
+        self.remove_constant_signal(&signal);
+
+        if let Some(filters) = &mut self.filters {
+            let index = next_index(filters.iter().map(|f| f.index));
+            filters.push(ConstantCombinatorSignal {
+                signal,
+                count,
+                index,
+            });
+            return;
+        }
+
+        let sections = &mut self.sections.get_or_insert_with(Default::default).sections;
+        if sections.is_empty() {
+            sections.push(LogisticSection {
+                index: NonZeroUsize::MIN,
+                filters: Vec::new(),
+                group: None,
+                multiplier: None,
+                active: None,
+            });
+        }
+
+        let filters = &mut sections[0].filters;
+        let index = next_index(filters.iter().map(|f| f.index));
+        filters.push(LogisticFilter {
+            name: signal.name,
+            signal_type: signal.signal_type,
+            index,
+            count,
+            max_count: None,
+            quality: signal.quality,
+            comparator: Some(Comparator::Equal),
+        });
+    }
+
+    /// Remove every slot outputting `signal` from a constant combinator.
+    pub fn remove_constant_signal(&mut self, signal: &SignalId) {
+        if let Some(filters) = &mut self.filters {
+            filters.retain(|filter| !filter.signal.matches(signal));
+        }
+        for section in self.sections.iter_mut().flat_map(|s| s.sections.iter_mut()) {
+            section
+                .filters
+                .retain(|filter| !filter.signal().matches(signal));
+        }
+    }
+}
+
+/// The first slot index after all the given ones.
+fn next_index(indices: impl Iterator<Item = NonZeroUsize>) -> NonZeroUsize {
+    indices
+        .max()
+        .and_then(|index| index.checked_add(1))
+        .unwrap_or(NonZeroUsize::MIN)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+/// How an entity reports the items passing through it.
+pub enum ReadMode {
+    /// Output a signal for a single tick when an item arrives.
+    Pulse = 0,
+    /// Output a signal for as long as the item is present.
+    Hold = 1,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+/// Which resources a mining drill reports.
+pub enum ResourceReadMode {
+    /// Only the resources under this drill.
+    ThisMiner = 0,
+    /// The whole resource patch.
+    EntirePatch = 1,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// A single signal output by a constant combinator.
+pub struct ConstantCombinatorSignal {
+    /// The signal being output.
+    pub signal: SignalId,
+    /// The value of the signal.
+    pub count: i32,
+    /// Index of the combinator slot holding the signal.
+    pub index: NonZeroUsize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+/// The mode of a selector combinator (2.0).
+pub enum SelectorOperation {
+    #[default]
+    /// Output the input signal at a given index, after sorting by value.
+    Select,
+    /// Output the number of distinct input signals.
+    Count,
+    /// Output a random input signal.
+    Random,
+    /// Output the stack size of each input item.
+    StackSize,
+    /// Output the rocket capacity of each input item.
+    RocketCapacity,
+    /// Only pass through signals of matching quality.
+    QualityFilter,
+    /// Change the quality of signals passing through.
+    QualityTransfer,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+/// Which wire colors a combinator reads a signal from (2.0). Both if absent.
+pub struct CircuitNetworkSelection {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Whether the red network is read.
+    pub red: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Whether the green network is read.
+    pub green: Option<bool>,
+}
+
+impl CircuitNetworkSelection {
+    /// Whether the red network is read.
+    pub fn reads_red(&self) -> bool {
+        self.red.unwrap_or(true)
+    }
+
+    /// Whether the green network is read.
+    pub fn reads_green(&self) -> bool {
+        self.green.unwrap_or(true)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+/// The settings of an arithmetic combinator: `output = first <operation> second`.
+pub struct ArithmeticCombinatorParameters {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The signal used as the left operand. Takes precedence over `first_constant`.
+    pub first_signal: Option<SignalId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The constant used as the left operand.
+    pub first_constant: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Which networks the left operand is read from (2.0).
+    pub first_signal_networks: Option<CircuitNetworkSelection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The signal used as the right operand. Takes precedence over `second_constant`.
+    pub second_signal: Option<SignalId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The constant used as the right operand.
+    pub second_constant: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Which networks the right operand is read from (2.0).
+    pub second_signal_networks: Option<CircuitNetworkSelection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The operation applied to the operands. `*` if absent.
+    pub operation: Option<ArithmeticOperation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The signal the result is output on.
+    pub output_signal: Option<SignalId>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+/// An operation performed by an arithmetic combinator.
+pub enum ArithmeticOperation {
+    #[default]
+    #[serde(rename = "*")]
+    /// Multiplication.
+    Multiply,
+    #[serde(rename = "/")]
+    /// Integer division, rounding towards zero. Division by zero results in zero.
+    Divide,
+    #[serde(rename = "+")]
+    /// Addition.
+    Add,
+    #[serde(rename = "-")]
+    /// Subtraction.
+    Subtract,
+    #[serde(rename = "%")]
+    /// Remainder. Modulo zero results in zero.
+    Modulo,
+    #[serde(rename = "^")]
+    /// Exponentiation. Negative exponents result in zero.
+    Power,
+    #[serde(rename = "<<")]
+    /// Bitwise left shift.
+    LeftShift,
+    #[serde(rename = ">>")]
+    /// Arithmetic right shift.
+    RightShift,
+    #[serde(rename = "AND")]
+    /// Bitwise and.
+    And,
+    #[serde(rename = "OR")]
+    /// Bitwise or.
+    Or,
+    #[serde(rename = "XOR")]
+    /// Bitwise exclusive or.
+    Xor,
+}
+
+impl ArithmeticOperation {
+    /// Apply the operation the way the game does, with 32-bit wrapping arithmetic.
+    pub fn apply(&self, lhs: i32, rhs: i32) -> i32 {
+        match self {
+            ArithmeticOperation::Multiply => lhs.wrapping_mul(rhs),
+            ArithmeticOperation::Divide if rhs == 0 => 0,
+            ArithmeticOperation::Divide => lhs.wrapping_div(rhs),
+            ArithmeticOperation::Add => lhs.wrapping_add(rhs),
+            ArithmeticOperation::Subtract => lhs.wrapping_sub(rhs),
+            ArithmeticOperation::Modulo if rhs == 0 => 0,
+            ArithmeticOperation::Modulo => lhs.wrapping_rem(rhs),
+            ArithmeticOperation::Power if rhs < 0 => 0,
+            ArithmeticOperation::Power => lhs.wrapping_pow(rhs as u32),
+            ArithmeticOperation::LeftShift => lhs.wrapping_shl(rhs as u32),
+            ArithmeticOperation::RightShift => lhs.wrapping_shr(rhs as u32),
+            ArithmeticOperation::And => lhs & rhs,
+            ArithmeticOperation::Or => lhs | rhs,
+            ArithmeticOperation::Xor => lhs ^ rhs,
+        }
+    }
+
+    /// The symbol the game uses for this operation.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ArithmeticOperation::Multiply => "*",
+            ArithmeticOperation::Divide => "/",
+            ArithmeticOperation::Add => "+",
+            ArithmeticOperation::Subtract => "-",
+            ArithmeticOperation::Modulo => "%",
+            ArithmeticOperation::Power => "^",
+            ArithmeticOperation::LeftShift => "<<",
+            ArithmeticOperation::RightShift => ">>",
+            ArithmeticOperation::And => "AND",
+            ArithmeticOperation::Or => "OR",
+            ArithmeticOperation::Xor => "XOR",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+/// The settings of a decider combinator.
+///
+/// Before 2.0 a decider combinator has a single condition and output, stored directly in this object.
+/// Since 2.0 it has lists of `conditions` and `outputs` instead.
+pub struct DeciderCombinatorParameters {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The signal on the left hand side of the condition.
+    pub first_signal: Option<SignalId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The signal on the right hand side of the condition. Takes precedence over `constant`.
+    pub second_signal: Option<SignalId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The constant on the right hand side of the condition.
+    pub constant: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// How the two sides of the condition are compared.
+    pub comparator: Option<Comparator>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The signal output when the condition is true.
+    pub output_signal: Option<SignalId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Whether the output is the input value of `output_signal` rather than 1.
+    pub copy_count_from_input: Option<bool>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    /// The conditions of a 2.0 decider combinator, combined in order.
+    pub conditions: Vec<DeciderCondition>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    /// The outputs of a 2.0 decider combinator, all output when the conditions are true.
+    pub outputs: Vec<DeciderOutput>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+/// A single condition of a 2.0 decider combinator.
+pub struct DeciderCondition {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The signal on the left hand side of the condition.
+    pub first_signal: Option<SignalId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Which networks the left hand side is read from.
+    pub first_signal_networks: Option<CircuitNetworkSelection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The signal on the right hand side of the condition. Takes precedence over `constant`.
+    pub second_signal: Option<SignalId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Which networks the right hand side is read from.
+    pub second_signal_networks: Option<CircuitNetworkSelection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The constant on the right hand side of the condition.
+    pub constant: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// How the two sides of the condition are compared.
+    pub comparator: Option<Comparator>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// How this condition combines with the previous ones. `or` if absent.
+    pub compare_type: Option<CompareType>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+/// A single output of a 2.0 decider combinator.
+pub struct DeciderOutput {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The signal being output.
+    pub signal: Option<SignalId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Whether the output is the input value of `signal` rather than `constant`. True if absent.
+    pub copy_count_from_input: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Which networks the input value is read from.
+    pub networks: Option<CircuitNetworkSelection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The value output when not copying from the input. 1 if absent.
+    pub constant: Option<i32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::blueprint::{Quality, VirtualSignal};
+
+    #[test]
+    fn settings_of_inserters_and_train_stops_decode() {
+        let json = json!({
+            "circuit_condition": {
+                "first_signal": {"type": "item", "name": "iron-plate"},
+                "constant": 100,
+                "comparator": ">",
+            },
+            "circuit_mode_of_operation": 0,
+            "circuit_read_hand_contents": true,
+            "circuit_hand_read_mode": 1,
+            "set_trains_limit": true,
+            "trains_limit_signal": {"type": "virtual", "name": "signal-L"},
+        });
+        let behavior: ControlBehavior = serde_json::from_value(json.clone()).unwrap();
+        let condition = behavior.circuit_condition.as_ref().unwrap();
+        assert_eq!(condition.comparator, Some(Comparator::GreaterThan));
+        assert_eq!(behavior.circuit_hand_read_mode, Some(ReadMode::Hold));
+        assert_eq!(
+            behavior
+                .trains_limit_signal
+                .as_ref()
+                .map(|signal| signal.name.as_str()),
+            Some("signal-L")
+        );
+        assert_eq!(serde_json::to_value(&behavior).unwrap(), json);
+    }
+
+    #[test]
+    fn arithmetic_wraps_like_the_game() {
+        use ArithmeticOperation::*;
+        assert_eq!(Multiply.apply(i32::MAX, 2), -2);
+        assert_eq!(Divide.apply(-7, 2), -3);
+        assert_eq!(Divide.apply(7, 0), 0);
+        assert_eq!(Modulo.apply(-7, 2), -1);
+        assert_eq!(Modulo.apply(7, 0), 0);
+        assert_eq!(Power.apply(2, -1), 0);
+        assert_eq!(Power.apply(3, 4), 81);
+        assert_eq!(LeftShift.apply(1, 33), 2);
+        assert_eq!(RightShift.apply(-8, 1), -4);
+        assert_eq!(Xor.apply(0b1100, 0b1010), 0b0110);
+    }
+
+    #[test]
+    fn decider_settings_of_both_formats_decode() {
+        let legacy: DeciderCombinatorParameters = serde_json::from_value(json!({
+            "first_signal": {"type": "virtual", "name": "signal-A"},
+            "constant": 5,
+            "comparator": "≠",
+            "output_signal": {"type": "virtual", "name": "signal-B"},
+            "copy_count_from_input": false,
+        }))
+        .unwrap();
+        assert_eq!(legacy.comparator, Some(Comparator::NotEqual));
+        assert!(legacy.conditions.is_empty());
+
+        let json = json!({
+            "conditions": [
+                {
+                    "first_signal": {"type": "virtual", "name": "signal-A"},
+                    "first_signal_networks": {"green": false},
+                    "constant": 5,
+                    "comparator": "<",
+                },
+                {
+                    "first_signal": {"type": "virtual", "name": "signal-B"},
+                    "constant": 1,
+                    "comparator": "=",
+                    "compare_type": "and",
+                },
+            ],
+            "outputs": [{"signal": {"type": "virtual", "name": "signal-C"}, "constant": 3}],
+        });
+        let parameters: DeciderCombinatorParameters = serde_json::from_value(json.clone()).unwrap();
+        let networks = parameters.conditions[0]
+            .first_signal_networks
+            .as_ref()
+            .unwrap();
+        assert!(networks.reads_red() && !networks.reads_green());
+        assert_eq!(
+            parameters.conditions[1].compare_type,
+            Some(CompareType::And)
+        );
+        assert_eq!(serde_json::to_value(&parameters).unwrap(), json);
+    }
+
+    #[test]
+    fn constant_signals_sum_active_sections() {
+        let mut behavior: ControlBehavior = serde_json::from_value(json!({
+            "sections": {"sections": [
+                {"index": 1, "filters": [
+                    {"index": 1, "name": "iron-plate", "count": 10, "comparator": "="},
+                    {"index": 2, "name": "copper-plate", "count": 5, "comparator": "="},
+                ]},
+                {"index": 2, "multiplier": 2.5, "filters": [
+                    {"index": 1, "name": "iron-plate", "count": 3, "comparator": "="},
+                ]},
+                {"index": 3, "active": false, "filters": [
+                    {"index": 1, "name": "coal", "count": 100, "comparator": "="},
+                ]},
+            ]},
+        }))
+        .unwrap();
+        let totals = |behavior: &ControlBehavior| -> Vec<(String, i32)> {
+            let signals = behavior.constant_signals().into_iter();
+            signals
+                .map(|(signal, count)| (signal.name, count))
+                .collect()
+        };
+        assert_eq!(
+            totals(&behavior),
+            [
+                ("iron-plate".to_owned(), 17),
+                ("copper-plate".to_owned(), 5)
+            ]
+        );
+
+        behavior.set_constant_signal(SignalId::item("copper-plate"), -9);
+        let first = &behavior.sections.as_ref().unwrap().sections[0];
+        assert_eq!(first.filters.len(), 2);
+        assert_eq!(first.filters[1].index.get(), 2);
+        assert_eq!(
+            totals(&behavior),
+            [
+                ("iron-plate".to_owned(), 17),
+                ("copper-plate".to_owned(), -9)
+            ]
+        );
+    }
+
+    #[test]
+    fn constant_signals_before_2_0_are_edited_in_place() {
+        let mut behavior: ControlBehavior = serde_json::from_value(json!({
+            "filters": [
+                {"signal": {"type": "virtual", "name": "signal-A"}, "count": 1, "index": 1},
+                {"signal": {"type": "virtual", "name": "signal-B"}, "count": 2, "index": 4},
+            ],
+        }))
+        .unwrap();
+        let signal_a = SignalId::virtual_signal(VirtualSignal::Letter('A'));
+        behavior.set_constant_signal(signal_a.clone(), 7);
+        assert!(behavior.sections.is_none());
+        let filters = behavior.filters.as_ref().unwrap();
+        assert_eq!(filters[1].signal, signal_a);
+        assert_eq!(filters[1].index.get(), 5);
+
+        behavior.remove_constant_signal(&signal_a);
+        assert_eq!(behavior.filters.as_ref().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn selector_combinator_modes_decode() {
+        let select = json!({
+            "operation": "select",
+            "select_max": false,
+            "index_signal": {"type": "virtual", "name": "signal-I"},
+        });
+        let behavior: ControlBehavior = serde_json::from_value(select.clone()).unwrap();
+        assert_eq!(behavior.operation, Some(SelectorOperation::Select));
+        assert_eq!(serde_json::to_value(&behavior).unwrap(), select);
+
+        let filter = json!({
+            "operation": "quality-filter",
+            "quality_filter": {"quality": "rare", "comparator": "≥"},
+        });
+        let behavior: ControlBehavior = serde_json::from_value(filter.clone()).unwrap();
+        assert_eq!(behavior.operation, Some(SelectorOperation::QualityFilter));
+        let condition = behavior.quality_filter.as_ref().unwrap();
+        assert_eq!(condition.quality, Quality::Rare);
+        assert_eq!(condition.comparator, Comparator::GreaterThanOrEqual);
+        assert_eq!(serde_json::to_value(&behavior).unwrap(), filter);
+    }
+}
@@ -0,0 +1,100 @@
+//! The items it takes to build a blueprint.
+
+use std::collections::BTreeMap;
+
+use crate::{
+    belt::Items,
+    plan::{Plan, PlanOptions},
+    prototypes::PrototypeDb,
+};
+
+use super::Blueprint;
+
+impl Blueprint {
+    /// The items it takes to build the blueprint: those placing its entities and tiles, and the
+    /// modules and other items its entities request, with the items placing them from `db`.
+    pub fn build_cost(&self, db: &PrototypeDb) -> BTreeMap<String, u32> {
+        let mut cost = BTreeMap::new();
+        let mut add = |item: &str, count: u32| {
+            *cost.entry(item.to_owned()).or_default() += count;
+        };
+        for entity in self.entities.iter().flatten() {
+            let (item, count) = db.item_to_place(&entity.name);
+            add(item, count);
+            for (item, count) in entity.items.iter().flat_map(|items| items.counts()) {
+                add(item, count);
+            }
+        }
+        for tile in self.tiles.iter().flatten() {
+            let (item, count) = db.item_to_place(&tile.name);
+            add(item, count);
+        }
+        cost
+    }
+
+    /// The [build cost](Self::build_cost) crafted all the way back from raw resources, with the
+    /// recipes and crafting machines of `db` chosen by `options`. Items without a recipe the
+    /// machines can craft are left as they are.
+    pub fn raw_build_cost(&self, db: &PrototypeDb, options: &PlanOptions) -> Items {
+        let mut raw = Items::new();
+        for (item, count) in self.build_cost(db) {
+            for (name, amount) in Plan::new(&item, count as f64, db, options).raw {
+                *raw.entry(name).or_default() += amount;
+            }
+        }
+        raw
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blueprint::{BlueprintBuilder, EntityBuilder, EntityItems, ItemRequest};
+
+    #[test]
+    fn entities_tiles_and_requests_are_all_counted() {
+        let db = PrototypeDb::vanilla();
+        let modules = ItemRequest::from([("speed-module".to_owned(), 2)]);
+        let blueprint = BlueprintBuilder::new()
+            .entity(EntityBuilder::new("transport-belt").at_tile(0, 0))
+            .entity(EntityBuilder::new("transport-belt").at_tile(1, 0))
+            .entity(EntityBuilder::new("straight-rail").at(4.0, 4.0))
+            .entity(
+                EntityBuilder::new("assembling-machine-2")
+                    .at_tile(0, 8)
+                    .items(EntityItems::Legacy(modules)),
+            )
+            .tile("stone-path", 0, 2)
+            .build_with(db)
+            .unwrap();
+
+        let cost = blueprint.build_cost(db);
+        let expected = [
+            ("assembling-machine-2", 1),
+            ("rail", 1),
+            ("speed-module", 2),
+            ("stone-brick", 1),
+            ("transport-belt", 2),
+        ];
+        assert_eq!(
+            cost,
+            expected
+                .map(|(item, count)| (item.to_owned(), count))
+                .into()
+        );
+
+        let belts = BlueprintBuilder::new()
+            .entity(EntityBuilder::new("transport-belt").at_tile(0, 0))
+            .entity(EntityBuilder::new("transport-belt").at_tile(1, 0))
+            .build_with(db)
+            .unwrap();
+        let options = PlanOptions {
+            machines: vec!["assembling-machine-2".to_owned()],
+            ..PlanOptions::default()
+        };
+        assert_eq!(
+            belts.raw_build_cost(db, &options),
+            Items::from([("iron-plate".to_owned(), 3.0)])
+        );
+    }
+}
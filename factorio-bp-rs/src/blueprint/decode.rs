@@ -0,0 +1,660 @@
+//! Decoding with a choice of how strictly the JSON is checked against the schema.
+
+use core::fmt;
+use std::collections::HashMap;
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use thiserror::Error;
+
+use super::{string::decode_json, Blueprint, BlueprintEnvelope, BpError, Entity};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// How strictly blueprint JSON is checked while decoding.
+///
+/// The default ignores unknown fields and fails on values that don't fit the schema, like
+/// [decode_string](super::decode_string).
+pub struct DecodeOptions {
+    /// Fail on fields this crate doesn't know about, instead of ignoring them.
+    pub deny_unknown_fields: bool,
+    /// Drop values that don't fit the schema (with a warning), instead of failing.
+    pub skip_invalid: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// The result of decoding with [DecodeOptions].
+pub struct Decoded {
+    /// The decoded blueprint, book, or planner.
+    pub envelope: BlueprintEnvelope,
+    /// Everything that was ignored or dropped to decode it.
+    pub warnings: Vec<DecodeWarning>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Something that was not decoded as written.
+pub enum DecodeWarning {
+    /// A field this crate doesn't know about. It is kept with the `preserve-unknown` feature if
+    /// its object is one that keeps unknown fields.
+    UnknownField(JsonPath),
+    /// A value that didn't fit the schema and was dropped.
+    Skipped {
+        /// Where the value was, in the JSON as written rather than as left after dropping
+        /// other values.
+        path: JsonPath,
+        /// Why it didn't fit.
+        reason: String,
+    },
+}
+
+impl fmt::Display for DecodeWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeWarning::UnknownField(path) => write!(f, "unknown field {path}"),
+            DecodeWarning::Skipped { path, reason } => write!(f, "skipped {path}: {reason}"),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("{error}")]
+/// An entity left out by [DecodeOptions::decode_blueprint_partial].
+pub struct EntityDecodeError {
+    /// The position of the entity in the blueprint's entity array.
+    pub index: usize,
+    /// The `entity_number` of the entity, if it had a valid one.
+    pub entity_number: Option<usize>,
+    /// Why it was left out.
+    pub error: BpError,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+/// The location of a value in blueprint JSON, e.g. `blueprint.entities[3].control_behavior`.
+pub struct JsonPath(Vec<PathSegment>);
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// One step of a [JsonPath].
+pub enum PathSegment {
+    /// A field of an object.
+    Key(String),
+    /// An element of an array.
+    Index(usize),
+}
+
+impl JsonPath {
+    /// The steps from the top level of the JSON to the value.
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.0
+    }
+
+    /// Whether this is the path of the top level value.
+    pub fn is_root(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The value at this path in `root`.
+    pub fn get<'v>(&self, root: &'v Value) -> Option<&'v Value> {
+        self.0
+            .iter()
+            .try_fold(root, |value, segment| match segment {
+                PathSegment::Key(key) => value.get(key),
+                PathSegment::Index(index) => value.get(index),
+            })
+    }
+
+    /// The `entity_number` of the innermost entity in `root` that this path leads into.
+    pub fn entity_number(&self, root: &Value) -> Option<usize> {
+        let end = self.0.windows(2).rposition(|pair| {
+            matches!(pair, [PathSegment::Key(key), PathSegment::Index(_)] if key == "entities")
+        })?;
+        let entity = JsonPath(self.0[..end + 2].to_vec()).get(root)?;
+        entity
+            .get("entity_number")?
+            .as_u64()
+            .map(|number| number as usize)
+    }
+
+    /// Remove the value at this path from its parent in `root`. Returns whether there was one.
+    fn remove(&self, root: &mut Value) -> bool {
+        let Some((last, parent)) = self.0.split_last() else {
+            return false;
+        };
+        let parent = parent
+            .iter()
+            .try_fold(root, |value, segment| match segment {
+                PathSegment::Key(key) => value.get_mut(key),
+                PathSegment::Index(index) => value.get_mut(index),
+            });
+
+        match (parent, last) {
+            (Some(Value::Object(object)), PathSegment::Key(key)) => object.remove(key).is_some(),
+            (Some(Value::Array(array)), PathSegment::Index(index)) if *index < array.len() => {
+                array.remove(*index);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn from_tracked(path: &serde_path_to_error::Path) -> Self {
+        use serde_path_to_error::Segment;
+
+        // Stop at the first step that serde couldn't tell us about
+        let segments = path
+            .iter()
+            .map_while(|segment| match segment {
+                Segment::Seq { index } => Some(PathSegment::Index(*index)),
+                Segment::Map { key } => Some(PathSegment::Key(key.clone())),
+                Segment::Enum { variant } => Some(PathSegment::Key(variant.clone())),
+                Segment::Unknown => None,
+            })
+            .collect();
+
+        JsonPath(segments)
+    }
+
+    /// Resolve a path reported by `serde_ignored` against the JSON it was reported for, to fill in
+    /// the names of enum variants it leaves out.
+    fn from_ignored(path: &serde_ignored::Path, root: &Value) -> Self {
+        fn resolve<'v>(
+            path: &serde_ignored::Path,
+            root: &'v Value,
+        ) -> (Vec<PathSegment>, Option<&'v Value>) {
+            use serde_ignored::Path;
+
+            match path {
+                Path::Root => (Vec::new(), Some(root)),
+                Path::Seq { parent, index } => {
+                    let (mut segments, value) = resolve(parent, root);
+                    segments.push(PathSegment::Index(*index));
+                    (segments, value.and_then(|value| value.get(index)))
+                }
+                Path::Map { parent, key } => {
+                    let (mut segments, value) = resolve(parent, root);
+                    segments.push(PathSegment::Key(key.clone()));
+                    (segments, value.and_then(|value| value.get(key)))
+                }
+                Path::NewtypeVariant { parent } => {
+                    let (mut segments, value) = resolve(parent, root);
+                    // Externally tagged: the variant is the only key of the object
+                    match value.and_then(Value::as_object) {
+                        Some(object) if object.len() == 1 => {
+                            let (variant, inner) = object.iter().next().unwrap();
+                            segments.push(PathSegment::Key(variant.clone()));
+                            (segments, Some(inner))
+                        }
+                        _ => (segments, value),
+                    }
+                }
+                Path::Some { parent } | Path::NewtypeStruct { parent } => resolve(parent, root),
+            }
+        }
+
+        JsonPath(resolve(path, root).0)
+    }
+}
+
+#[derive(Debug, Default)]
+/// The array elements dropped while decoding, by the path of their array, so that paths into the
+/// JSON left can be reported as they are in the original.
+struct Removed(HashMap<Vec<PathSegment>, Vec<usize>>);
+
+impl Removed {
+    /// `path`, into the JSON left after the removals, as it is in the original JSON.
+    fn original(&self, path: &JsonPath) -> JsonPath {
+        let mut original = Vec::new();
+        for segment in &path.0 {
+            let segment = match segment {
+                PathSegment::Index(index) => {
+                    // Count the elements dropped before it, in ascending order
+                    let removed = self.0.get(&original).map_or(&[][..], Vec::as_slice);
+                    let index = removed.iter().fold(*index, |index, &removed| {
+                        index + usize::from(removed <= index)
+                    });
+                    PathSegment::Index(index)
+                }
+                PathSegment::Key(key) => PathSegment::Key(key.clone()),
+            };
+            original.push(segment);
+        }
+        JsonPath(original)
+    }
+
+    /// Record that the value at `original`, a path into the original JSON, was dropped.
+    fn record(&mut self, original: &JsonPath) {
+        if let Some((PathSegment::Index(index), parent)) = original.0.split_last() {
+            let removed = self.0.entry(parent.to_vec()).or_default();
+            let at = removed.partition_point(|&other| other < *index);
+            removed.insert(at, *index);
+        }
+    }
+}
+
+impl fmt::Display for JsonPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            return f.write_str(".");
+        }
+
+        for (i, segment) in self.0.iter().enumerate() {
+            match segment {
+                PathSegment::Key(key) if i == 0 => write!(f, "{key}")?,
+                PathSegment::Key(key) => write!(f, ".{key}")?,
+                PathSegment::Index(index) => write!(f, "[{index}]")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl DecodeOptions {
+    /// Fail on anything that doesn't match the schema exactly.
+    pub fn strict() -> Self {
+        Self {
+            deny_unknown_fields: true,
+            skip_invalid: false,
+        }
+    }
+
+    /// Decode as much as possible, recording warnings for everything that was left out.
+    pub fn lenient() -> Self {
+        Self {
+            deny_unknown_fields: false,
+            skip_invalid: true,
+        }
+    }
+
+    /// Decode a blueprint string.
+    pub fn decode(&self, bp_string: &str) -> Result<Decoded, BpError> {
+        self.decode_from_json(&decode_json(bp_string)?)
+    }
+
+    /// Decode the JSON contained in a blueprint string.
+    pub fn decode_from_json(&self, json: &str) -> Result<Decoded, BpError> {
+        self.decode_value(serde_json::from_str(json)?)
+    }
+
+    /// Decode a blueprint string holding a single blueprint, decoding each entity separately.
+    ///
+    /// Entities that fail to decode (or have unknown fields, if those are denied) are left out
+    /// and reported, rather than failing the whole blueprint. Everything outside the entities is
+    /// decoded as usual, and warnings about it are discarded.
+    pub fn decode_blueprint_partial(
+        &self,
+        bp_string: &str,
+    ) -> Result<(Blueprint, Vec<EntityDecodeError>), BpError> {
+        let mut value: Value = serde_json::from_str(&decode_json(bp_string)?)?;
+
+        let Some(blueprint) = value.get_mut("blueprint") else {
+            // Either it doesn't decode at all, or it's something other than a blueprint
+            let found = match self.decode_value(value)?.envelope {
+                BlueprintEnvelope::Blueprint(_) => unreachable!("no blueprint key"),
+                BlueprintEnvelope::BlueprintBook(_) => "blueprint book",
+                BlueprintEnvelope::DeconstructionPlanner(_) => "deconstruction planner",
+                BlueprintEnvelope::UpgradePlanner(_) => "upgrade planner",
+            };
+            return Err(BpError::NotABlueprint(found));
+        };
+
+        let mut errors = Vec::new();
+        if let Some(Value::Array(entities)) = blueprint.get_mut("entities") {
+            let mut index = 0;
+            entities.retain(|entity| {
+                let path = |inner: JsonPath| {
+                    let mut segments = vec![
+                        PathSegment::Key("blueprint".to_string()),
+                        PathSegment::Key("entities".to_string()),
+                        PathSegment::Index(index),
+                    ];
+                    segments.extend(inner.0);
+                    JsonPath(segments)
+                };
+                let entity_number = entity
+                    .get("entity_number")
+                    .and_then(Value::as_u64)
+                    .map(|number| number as usize);
+
+                let error = match deserialize_tracked::<Entity>(entity) {
+                    Err((inner, source)) => Some(BpError::Schema {
+                        path: path(inner),
+                        entity_number,
+                        source,
+                    }),
+                    Ok((_, unknown)) if self.deny_unknown_fields && !unknown.is_empty() => {
+                        Some(BpError::UnknownField {
+                            path: path(unknown.into_iter().next().unwrap()),
+                            entity_number,
+                        })
+                    }
+                    Ok(_) => None,
+                };
+
+                index += 1;
+                match error {
+                    Some(error) => {
+                        errors.push(EntityDecodeError {
+                            index: index - 1,
+                            entity_number,
+                            error,
+                        });
+                        false
+                    }
+                    None => true,
+                }
+            });
+        }
+
+        match self.decode_value(value)?.envelope {
+            BlueprintEnvelope::Blueprint(blueprint) => Ok((blueprint, errors)),
+            _ => unreachable!("checked for a blueprint above"),
+        }
+    }
+
+    fn decode_value(&self, mut value: Value) -> Result<Decoded, BpError> {
+        let mut warnings = Vec::new();
+        // Paths are found in what is left of the JSON, but reported as they are in the original
+        let mut removed = Removed::default();
+
+        // Each failed attempt removes a value, so this ends
+        loop {
+            match deserialize_tracked(&value) {
+                Ok((envelope, unknown)) => {
+                    return self.finish(envelope, &value, unknown, &removed, warnings);
+                }
+                Err((path, error)) => {
+                    let original = removed.original(&path);
+                    // Never drop the top level blueprint itself
+                    if self.skip_invalid && path.segments().len() >= 2 && path.remove(&mut value) {
+                        removed.record(&original);
+                        warnings.push(DecodeWarning::Skipped {
+                            path: original,
+                            reason: error.to_string(),
+                        });
+                    } else {
+                        return Err(BpError::Schema {
+                            entity_number: path.entity_number(&value),
+                            path: original,
+                            source: error,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    fn finish(
+        &self,
+        mut envelope: BlueprintEnvelope,
+        value: &Value,
+        unknown: Vec<JsonPath>,
+        removed: &Removed,
+        mut warnings: Vec<DecodeWarning>,
+    ) -> Result<Decoded, BpError> {
+        if self.deny_unknown_fields {
+            if let Some(path) = unknown.into_iter().next() {
+                return Err(BpError::UnknownField {
+                    entity_number: path.entity_number(value),
+                    path: removed.original(&path),
+                });
+            }
+        } else {
+            #[cfg(feature = "preserve-unknown")]
+            for path in &unknown {
+                envelope.preserve_unknown(path, value);
+            }
+            warnings.extend(
+                unknown
+                    .iter()
+                    .map(|path| DecodeWarning::UnknownField(removed.original(path))),
+            );
+        }
+
+        envelope.directions_from_legacy();
+
+        Ok(Decoded { envelope, warnings })
+    }
+}
+
+/// Deserialize `value`, returning the paths of any fields that were ignored, or the path of the
+/// value that failed.
+fn deserialize_tracked<T: DeserializeOwned>(
+    value: &Value,
+) -> Result<(T, Vec<JsonPath>), (JsonPath, serde_json::Error)> {
+    let mut unknown = Vec::new();
+    let mut track = serde_path_to_error::Track::new();
+    let deserializer = serde_path_to_error::Deserializer::new(value, &mut track);
+
+    match serde_ignored::deserialize(deserializer, |path| {
+        unknown.push(JsonPath::from_ignored(&path, value))
+    }) {
+        Ok(deserialized) => Ok((deserialized, unknown)),
+        Err(error) => Err((JsonPath::from_tracked(&track.path()), error)),
+    }
+}
+
+#[cfg(feature = "preserve-unknown")]
+mod preserve {
+    use serde_json::Value;
+
+    use super::{JsonPath, PathSegment};
+    use crate::blueprint::{Blueprint, BlueprintEnvelope, UnknownFields};
+
+    impl BlueprintEnvelope {
+        /// Copy the unknown field at `path` in `root` into the `extra` map of the structure
+        /// holding it, if that structure has one.
+        pub(super) fn preserve_unknown(&mut self, path: &JsonPath, root: &Value) {
+            let Some((PathSegment::Key(key), parent)) = path.segments().split_last() else {
+                return;
+            };
+            let (Some(value), Some(extra)) = (path.get(root), self.extra_at(parent)) else {
+                return;
+            };
+            extra.insert(key.clone(), value.clone());
+        }
+
+        fn extra_at(&mut self, path: &[PathSegment]) -> Option<&mut UnknownFields> {
+            let (PathSegment::Key(variant), rest) = path.split_first()? else {
+                return None;
+            };
+
+            match (self, variant.as_str(), rest) {
+                (BlueprintEnvelope::Blueprint(blueprint), "blueprint", _) => {
+                    blueprint.extra_at(rest)
+                }
+                (BlueprintEnvelope::BlueprintBook(book), "blueprint_book", []) => {
+                    Some(&mut book.extra)
+                }
+                (
+                    BlueprintEnvelope::BlueprintBook(book),
+                    "blueprint_book",
+                    [PathSegment::Key(field), PathSegment::Index(index), rest @ ..],
+                ) if field == "blueprints" => {
+                    book.blueprints.get_mut(*index)?.content.extra_at(rest)
+                }
+                (
+                    BlueprintEnvelope::DeconstructionPlanner(planner),
+                    "deconstruction_planner",
+                    [],
+                ) => Some(&mut planner.extra),
+                (BlueprintEnvelope::UpgradePlanner(planner), "upgrade_planner", []) => {
+                    Some(&mut planner.extra)
+                }
+                _ => None,
+            }
+        }
+    }
+
+    impl Blueprint {
+        fn extra_at(&mut self, path: &[PathSegment]) -> Option<&mut UnknownFields> {
+            let [PathSegment::Key(field), PathSegment::Index(index), rest @ ..] = path else {
+                return path.is_empty().then_some(&mut self.extra);
+            };
+            if field != "entities" {
+                return None;
+            }
+            let entity = self.entities.as_mut()?.get_mut(*index)?;
+
+            match rest {
+                [] => Some(&mut entity.extra),
+                [PathSegment::Key(field)] if field == "control_behavior" => {
+                    Some(&mut entity.control_behavior.as_mut()?.extra)
+                }
+                _ => None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::blueprint::encode_json;
+
+    /// A path into the entities of a blueprint.
+    fn entity_path(index: usize, field: Option<&str>) -> JsonPath {
+        let mut segments = vec![
+            PathSegment::Key("blueprint".to_owned()),
+            PathSegment::Key("entities".to_owned()),
+            PathSegment::Index(index),
+        ];
+        segments.extend(field.map(|field| PathSegment::Key(field.to_owned())));
+        JsonPath(segments)
+    }
+
+    #[test]
+    fn paths_are_reported_as_they_were_before_skipping() {
+        let chest = |number: u32| {
+            json!({
+                "entity_number": number,
+                "name": "iron-chest",
+                "position": {"x": 0.5, "y": 0.5},
+            })
+        };
+        let mut unknown = chest(5);
+        unknown["colour"] = json!("red");
+        let json = json!({
+            "blueprint": {
+                "item": "blueprint",
+                "version": 562949954076673u64,
+                "entities": [chest(1), 2, chest(3), "four", unknown],
+            }
+        });
+        let decoded = DecodeOptions::lenient()
+            .decode_from_json(&json.to_string())
+            .unwrap();
+
+        let paths: Vec<_> = decoded
+            .warnings
+            .iter()
+            .map(|warning| match warning {
+                DecodeWarning::Skipped { path, .. } => path.clone(),
+                DecodeWarning::UnknownField(path) => path.clone(),
+            })
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                entity_path(1, None),
+                entity_path(3, None),
+                entity_path(4, Some("colour"))
+            ]
+        );
+
+        let strict = DecodeOptions {
+            deny_unknown_fields: true,
+            skip_invalid: true,
+        };
+        match strict.decode_from_json(&json.to_string()) {
+            Err(BpError::UnknownField {
+                path,
+                entity_number,
+            }) => {
+                assert_eq!(path, entity_path(4, Some("colour")));
+                assert_eq!(entity_number, Some(5));
+            }
+            other => panic!("expected an unknown field, got {other:?}"),
+        }
+    }
+
+    #[cfg(feature = "preserve-unknown")]
+    #[test]
+    fn unknown_fields_are_kept_where_they_were() {
+        let json = json!({
+            "blueprint": {
+                "item": "blueprint",
+                "version": 562949954076673u64,
+                "snap_to_grid_mode": "fancy",
+                "entities": [{
+                    "entity_number": 1,
+                    "name": "inserter",
+                    "position": {"x": 0.5, "y": 0.5},
+                    "colour": "red",
+                    "control_behavior": {"circuit_enable_disable": true, "glow": 3},
+                }],
+            }
+        });
+        let decoded = DecodeOptions::default()
+            .decode_from_json(&json.to_string())
+            .unwrap();
+        assert_eq!(decoded.warnings.len(), 3);
+
+        let encoded = serde_json::to_value(&decoded.envelope).unwrap();
+        let blueprint = &encoded["blueprint"];
+        assert_eq!(blueprint["snap_to_grid_mode"], "fancy");
+        assert_eq!(blueprint["entities"][0]["colour"], "red");
+        assert_eq!(blueprint["entities"][0]["control_behavior"]["glow"], 3);
+    }
+
+    #[test]
+    fn entities_that_fail_are_left_out_of_partial_decodes() {
+        let json = json!({
+            "blueprint": {
+                "item": "blueprint",
+                "label": "Smelting",
+                "version": 562949954076673u64,
+                "entities": [
+                    {"entity_number": 1, "name": "iron-chest", "position": {"x": 0.5, "y": 0.5}},
+                    {"entity_number": 2, "name": "iron-chest", "position": "nowhere"},
+                    {"entity_number": 3, "name": "iron-chest", "position": {"x": 1.5, "y": 0.5}},
+                    {"entity_number": 4, "name": "iron-chest", "position": {"x": 2.5, "y": 0.5},
+                        "colour": "red"},
+                ],
+            }
+        });
+        let bp_string = encode_json(&json.to_string()).unwrap();
+
+        let (blueprint, errors) = DecodeOptions::default()
+            .decode_blueprint_partial(&bp_string)
+            .unwrap();
+        assert_eq!(blueprint.label.as_deref(), Some("Smelting"));
+        assert_eq!(blueprint.entities.unwrap().len(), 3);
+        assert_eq!(errors.len(), 1);
+        assert_eq!((errors[0].index, errors[0].entity_number), (1, Some(2)));
+        assert_eq!(
+            errors[0].error.path().unwrap().to_string(),
+            "blueprint.entities[1].position"
+        );
+
+        let (blueprint, errors) = DecodeOptions::strict()
+            .decode_blueprint_partial(&bp_string)
+            .unwrap();
+        assert_eq!(blueprint.entities.unwrap().len(), 2);
+        let numbers: Vec<_> = errors.iter().map(|error| error.entity_number).collect();
+        assert_eq!(numbers, [Some(2), Some(4)]);
+
+        let book = json!({
+            "blueprint_book": {
+                "item": "blueprint-book",
+                "active_index": 0,
+                "version": 562949954076673u64,
+            }
+        });
+        let bp_string = encode_json(&book.to_string()).unwrap();
+        assert!(matches!(
+            DecodeOptions::default().decode_blueprint_partial(&bp_string),
+            Err(BpError::NotABlueprint("blueprint book"))
+        ));
+    }
+}
@@ -0,0 +1,290 @@
+//! Structural differences between two versions of a blueprint.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use serde_json::Value;
+
+use super::{Blueprint, Direction, Entity, ItemCountType};
+
+/// Fields compared on their own, or that identify an entity rather than describe it.
+const SPECIAL_FIELDS: [&str; 6] = [
+    "entity_number",
+    "name",
+    "position",
+    "direction",
+    "recipe",
+    "items",
+];
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// The differences between two blueprints, from [diff].
+pub struct BlueprintDiff<'a> {
+    /// Entities only in the new blueprint.
+    pub added: Vec<&'a Entity>,
+    /// Entities only in the old blueprint.
+    pub removed: Vec<&'a Entity>,
+    /// Entities in both blueprints that differ.
+    pub modified: Vec<EntityDiff<'a>>,
+}
+
+impl BlueprintDiff<'_> {
+    /// Whether the blueprints have the same entities.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// An entity that is in both blueprints but differs between them.
+pub struct EntityDiff<'a> {
+    /// The entity in the old blueprint.
+    pub before: &'a Entity,
+    /// The entity in the new blueprint.
+    pub after: &'a Entity,
+    /// What changed.
+    pub changes: Vec<EntityChange>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A change to an entity between two blueprints.
+pub enum EntityChange {
+    /// The entity was replaced by another at the same position, e.g. by upgrading it.
+    Name {
+        /// The old prototype name.
+        before: String,
+        /// The new prototype name.
+        after: String,
+    },
+    /// The entity was rotated.
+    Direction {
+        /// The old direction.
+        before: Direction,
+        /// The new direction.
+        after: Direction,
+    },
+    /// The recipe was changed.
+    Recipe {
+        /// The old recipe.
+        before: Option<String>,
+        /// The new recipe.
+        after: Option<String>,
+    },
+    /// The modules or other items requested for the entity were changed.
+    Items {
+        /// The old item names and counts.
+        before: BTreeMap<String, ItemCountType>,
+        /// The new item names and counts.
+        after: BTreeMap<String, ItemCountType>,
+    },
+    /// Another field was changed, e.g. `control_behavior`.
+    Other(String),
+}
+
+/// The total count of each item requested by the entity.
+fn item_counts(entity: &Entity) -> BTreeMap<String, ItemCountType> {
+    let mut counts = BTreeMap::new();
+    for (name, count) in entity.items.iter().flat_map(|items| items.counts()) {
+        *counts.entry(name.to_owned()).or_default() += count;
+    }
+    counts
+}
+
+/// The changes from `before` to `after`.
+fn entity_changes(before: &Entity, after: &Entity) -> Vec<EntityChange> {
+    let mut changes = Vec::new();
+
+    if before.name != after.name {
+        changes.push(EntityChange::Name {
+            before: before.name.clone(),
+            after: after.name.clone(),
+        });
+    }
+
+    let directions = (
+        before.direction.unwrap_or_default(),
+        after.direction.unwrap_or_default(),
+    );
+    if directions.0 != directions.1 {
+        changes.push(EntityChange::Direction {
+            before: directions.0,
+            after: directions.1,
+        });
+    }
+
+    if before.recipe() != after.recipe() {
+        changes.push(EntityChange::Recipe {
+            before: before.recipe().map(str::to_owned),
+            after: after.recipe().map(str::to_owned),
+        });
+    }
+
+    let items = (item_counts(before), item_counts(after));
+    if items.0 != items.1 {
+        changes.push(EntityChange::Items {
+            before: items.0,
+            after: items.1,
+        });
+    }
+
+    // Everything else is compared as JSON, so every field is covered without listing them all
+    let fields = |entity| match serde_json::to_value(entity) {
+        Ok(Value::Object(fields)) => fields,
+        _ => Default::default(),
+    };
+    let (before, after) = (fields(before), fields(after));
+    let names: BTreeSet<_> = before.keys().chain(after.keys()).collect();
+    for name in names {
+        if !SPECIAL_FIELDS.contains(&name.as_str()) && before.get(name) != after.get(name) {
+            changes.push(EntityChange::Other(name.clone()));
+        }
+    }
+
+    changes
+}
+
+/// The entity changes from blueprint `before` to blueprint `after`.
+///
+/// Entities are matched by name and position. An entity that was removed from a position another
+/// entity was added at is reported as modified with an [EntityChange::Name], as when upgrading
+/// it. Tiles, wires, and blueprint settings are not compared.
+pub fn diff<'a>(before: &'a Blueprint, after: &'a Blueprint) -> BlueprintDiff<'a> {
+    let position = |entity: &Entity| (entity.position.x.to_bits(), entity.position.y.to_bits());
+    let new_entities: Vec<&Entity> = after.entities.iter().flatten().collect();
+    let mut matched = vec![false; new_entities.len()];
+
+    // Indices of the new entities by name and position, reversed so they are matched in order
+    let mut by_name_and_position: HashMap<_, Vec<usize>> = HashMap::new();
+    for (i, entity) in new_entities.iter().enumerate().rev() {
+        by_name_and_position
+            .entry((entity.name.as_str(), position(entity)))
+            .or_default()
+            .push(i);
+    }
+
+    let mut result = BlueprintDiff::default();
+    let mut removed = Vec::new();
+    for old in before.entities.iter().flatten() {
+        let key = (old.name.as_str(), position(old));
+        match by_name_and_position.get_mut(&key).and_then(Vec::pop) {
+            Some(i) => {
+                matched[i] = true;
+                let changes = entity_changes(old, new_entities[i]);
+                if !changes.is_empty() {
+                    result.modified.push(EntityDiff {
+                        before: old,
+                        after: new_entities[i],
+                        changes,
+                    });
+                }
+            }
+            None => removed.push(old),
+        }
+    }
+
+    // Pair what is left by position alone, to catch entities replaced by others
+    let mut by_position: HashMap<_, Vec<usize>> = HashMap::new();
+    for (i, entity) in new_entities.iter().enumerate().rev() {
+        if !matched[i] {
+            by_position.entry(position(entity)).or_default().push(i);
+        }
+    }
+    for old in removed {
+        match by_position.get_mut(&position(old)).and_then(Vec::pop) {
+            Some(i) => {
+                matched[i] = true;
+                result.modified.push(EntityDiff {
+                    before: old,
+                    after: new_entities[i],
+                    changes: entity_changes(old, new_entities[i]),
+                });
+            }
+            None => result.removed.push(old),
+        }
+    }
+
+    result.added = new_entities
+        .into_iter()
+        .zip(matched)
+        .filter_map(|(entity, matched)| (!matched).then_some(entity))
+        .collect();
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{blueprint::EntityBuilder, bp, prototypes::PrototypeDb};
+
+    #[test]
+    fn entities_are_matched_by_name_and_position_then_by_position() {
+        let db = PrototypeDb::vanilla();
+        let before = bp! {
+            "transport-belt" @ (6, 0);
+            "iron-chest" @ (4, 0);
+            "assembling-machine-1" @ (0, 0) recipe "iron-gear-wheel";
+            "inserter" @ (3, 1);
+        }
+        .build_with(db)
+        .unwrap();
+        // Renumbered, which isn't a change
+        let after = bp! {
+            "assembling-machine-2" @ (0, 0) recipe "copper-cable";
+            "inserter" @ (3, 1) facing East;
+        }
+        .entity(
+            EntityBuilder::new("iron-chest")
+                .at_tile(4, 0)
+                .tag("owner", "me"),
+        )
+        .entity(EntityBuilder::new("iron-chest").at_tile(8, 0))
+        .build_with(db)
+        .unwrap();
+
+        let old = before.entities.as_deref().unwrap();
+        let new = after.entities.as_deref().unwrap();
+        let diff = diff(&before, &after);
+        assert_eq!(diff.added, [&new[3]]);
+        assert_eq!(diff.removed, [&old[0]]);
+        assert_eq!(
+            diff.modified,
+            [
+                EntityDiff {
+                    before: &old[1],
+                    after: &new[2],
+                    changes: vec![EntityChange::Other("tags".to_owned())],
+                },
+                EntityDiff {
+                    before: &old[3],
+                    after: &new[1],
+                    changes: vec![EntityChange::Direction {
+                        before: Direction::North,
+                        after: Direction::East,
+                    }],
+                },
+                EntityDiff {
+                    before: &old[2],
+                    after: &new[0],
+                    changes: vec![
+                        EntityChange::Name {
+                            before: "assembling-machine-1".to_owned(),
+                            after: "assembling-machine-2".to_owned(),
+                        },
+                        EntityChange::Recipe {
+                            before: Some("iron-gear-wheel".to_owned()),
+                            after: Some("copper-cable".to_owned()),
+                        },
+                    ],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn a_blueprint_has_no_differences_from_itself() {
+        let blueprint = bp! { "iron-chest" @ (0, 0); "inserter" @ (0, 1) }
+            .build_with(PrototypeDb::vanilla())
+            .unwrap();
+        assert!(diff(&blueprint, &blueprint).is_empty());
+    }
+}
@@ -0,0 +1,262 @@
+//! Entity directions, and the conversion between the 1.1 and 2.0 numbering of them.
+
+use serde_repr::*;
+
+use super::{Blueprint, BlueprintEnvelope, Version};
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Default,
+    Serialize_repr,
+    Deserialize_repr,
+)]
+#[repr(u8)]
+/// Direction of an entity, in sixteenths of a turn clockwise from north.
+///
+/// This is the 2.0 numbering. Blueprints from earlier versions number the 8 directions they
+/// support in eighths of a turn instead; [decode_string](super::decode_string) and `to_bp_string`
+/// convert between the two, so in memory every blueprint uses this numbering.
+pub enum Direction {
+    #[default]
+    /// Up.
+    North = 0,
+    /// Between north and north-east (2.0).
+    NorthNorthEast = 1,
+    /// Up and to the right.
+    NorthEast = 2,
+    /// Between east and north-east (2.0).
+    EastNorthEast = 3,
+    /// Right.
+    East = 4,
+    /// Between east and south-east (2.0).
+    EastSouthEast = 5,
+    /// Down and to the right.
+    SouthEast = 6,
+    /// Between south and south-east (2.0).
+    SouthSouthEast = 7,
+    /// Down.
+    South = 8,
+    /// Between south and south-west (2.0).
+    SouthSouthWest = 9,
+    /// Down and to the left.
+    SouthWest = 10,
+    /// Between west and south-west (2.0).
+    WestSouthWest = 11,
+    /// Left.
+    West = 12,
+    /// Between west and north-west (2.0).
+    WestNorthWest = 13,
+    /// Up and to the left.
+    NorthWest = 14,
+    /// Between north and north-west (2.0).
+    NorthNorthWest = 15,
+}
+
+impl Direction {
+    /// Every direction, clockwise from north.
+    pub const ALL: [Direction; 16] = [
+        Direction::North,
+        Direction::NorthNorthEast,
+        Direction::NorthEast,
+        Direction::EastNorthEast,
+        Direction::East,
+        Direction::EastSouthEast,
+        Direction::SouthEast,
+        Direction::SouthSouthEast,
+        Direction::South,
+        Direction::SouthSouthWest,
+        Direction::SouthWest,
+        Direction::WestSouthWest,
+        Direction::West,
+        Direction::WestNorthWest,
+        Direction::NorthWest,
+        Direction::NorthNorthWest,
+    ];
+
+    /// The direction a number of sixteenths of a turn clockwise from north. Wraps around.
+    pub fn from_sixteenths(sixteenths: i32) -> Self {
+        Self::ALL[sixteenths.rem_euclid(16) as usize]
+    }
+
+    /// The number of sixteenths of a turn clockwise from north.
+    pub fn sixteenths(&self) -> u8 {
+        *self as u8
+    }
+
+    /// Convert from the pre-2.0 numbering, in eighths of a turn.
+    pub fn from_legacy(eighths: u8) -> Option<Self> {
+        Self::ALL.get(eighths as usize * 2).copied()
+    }
+
+    /// Convert to the pre-2.0 numbering, in eighths of a turn. `None` for directions that only exist since 2.0.
+    pub fn to_legacy(&self) -> Option<u8> {
+        let sixteenths = self.sixteenths();
+        sixteenths.is_multiple_of(2).then_some(sixteenths / 2)
+    }
+
+    /// This direction rotated clockwise by a number of sixteenths of a turn (negative for counter-clockwise).
+    pub fn rotated(&self, sixteenths: i32) -> Self {
+        Self::from_sixteenths(self.sixteenths() as i32 + sixteenths)
+    }
+
+    /// This direction rotated a quarter turn clockwise.
+    pub fn clockwise(&self) -> Self {
+        self.rotated(4)
+    }
+
+    /// This direction rotated a quarter turn counter-clockwise.
+    pub fn counter_clockwise(&self) -> Self {
+        self.rotated(-4)
+    }
+
+    /// The direction pointing the other way.
+    pub fn opposite(&self) -> Self {
+        self.rotated(8)
+    }
+
+    /// Whether this is north, east, south, or west.
+    pub fn is_cardinal(&self) -> bool {
+        self.sixteenths().is_multiple_of(4)
+    }
+
+    /// The unit vector pointing in this direction. North is negative y, as in game.
+    pub fn offset(&self) -> (f64, f64) {
+        let angle = self.sixteenths() as f64 * std::f64::consts::PI / 8.0;
+        let (x, y) = (angle.sin(), -angle.cos());
+        // Snap the exact axes so cardinal directions give integer offsets
+        let snap = |v: f64| if v.abs() < 1e-9 { 0.0 } else { v };
+        (snap(x), snap(y))
+    }
+
+    /// The tile offset one step in this direction, for the 8 directions that exist before 2.0.
+    pub fn tile_offset(&self) -> Option<(i32, i32)> {
+        match self {
+            Direction::North => Some((0, -1)),
+            Direction::NorthEast => Some((1, -1)),
+            Direction::East => Some((1, 0)),
+            Direction::SouthEast => Some((1, 1)),
+            Direction::South => Some((0, 1)),
+            Direction::SouthWest => Some((-1, 1)),
+            Direction::West => Some((-1, 0)),
+            Direction::NorthWest => Some((-1, -1)),
+            _ => None,
+        }
+    }
+}
+
+/// Whether blueprints saved by this version number directions in eighths of a turn.
+fn uses_legacy_directions(version: &Version) -> bool {
+    !version.is_at_least(2, 0, 0)
+}
+
+impl Blueprint {
+    /// Apply `convert` to the direction of every entity.
+    fn map_directions(&mut self, convert: impl Fn(Direction) -> Direction) {
+        for entity in self.entities.iter_mut().flatten() {
+            entity.direction = entity.direction.map(&convert);
+        }
+    }
+}
+
+impl BlueprintEnvelope {
+    /// Apply `convert` to every blueprint whose version numbers directions in eighths of a turn.
+    fn map_legacy_directions(&mut self, convert: &impl Fn(Direction) -> Direction) {
+        match self {
+            BlueprintEnvelope::Blueprint(blueprint) => {
+                if uses_legacy_directions(&blueprint.version) {
+                    blueprint.map_directions(convert);
+                }
+            }
+            BlueprintEnvelope::BlueprintBook(book) => {
+                for entry in &mut book.blueprints {
+                    entry.content.map_legacy_directions(convert);
+                }
+            }
+            BlueprintEnvelope::DeconstructionPlanner(_) | BlueprintEnvelope::UpgradePlanner(_) => {}
+        }
+    }
+
+    /// Reinterpret directions that were decoded as raw pre-2.0 values.
+    pub(super) fn directions_from_legacy(&mut self) {
+        // Raw values 0-7 decode as the first 8 sixteenths, which are the eighths being converted
+        self.map_legacy_directions(&|raw| Direction::from_sixteenths(raw.sixteenths() as i32 * 2));
+    }
+
+    /// Convert directions to raw pre-2.0 values, ready to be encoded. Directions that don't exist
+    /// before 2.0 are rounded counter-clockwise.
+    pub(super) fn directions_to_legacy(&mut self) {
+        self.map_legacy_directions(&|direction| {
+            Direction::from_sixteenths(direction.sixteenths() as i32 / 2)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::blueprint::{decode_json, decode_string, encode_json};
+
+    #[test]
+    fn directions_turn_and_convert_to_eighths() {
+        assert_eq!(Direction::West.clockwise(), Direction::North);
+        assert_eq!(Direction::North.counter_clockwise(), Direction::West);
+        assert_eq!(
+            Direction::EastSouthEast.opposite(),
+            Direction::WestNorthWest
+        );
+        assert_eq!(Direction::from_legacy(2), Some(Direction::East));
+        assert_eq!(Direction::from_legacy(8), None);
+        assert_eq!(Direction::SouthWest.to_legacy(), Some(5));
+        assert_eq!(Direction::NorthNorthEast.to_legacy(), None);
+        assert_eq!(Direction::East.offset(), (1.0, 0.0));
+        assert_eq!(Direction::EastNorthEast.tile_offset(), None);
+    }
+
+    #[test]
+    fn each_blueprint_in_a_book_is_numbered_by_its_version() {
+        let blueprint = |direction: u8, version: u64| {
+            json!({
+                "item": "blueprint",
+                "entities": [{
+                    "entity_number": 1,
+                    "name": "inserter",
+                    "position": {"x": 0.5, "y": 0.5},
+                    "direction": direction,
+                }],
+                "version": version,
+            })
+        };
+        let json = json!({
+            "blueprint_book": {
+                "item": "blueprint-book",
+                "blueprints": [
+                    {"index": 0, "blueprint": blueprint(2, 281479276920832)},
+                    {"index": 1, "blueprint": blueprint(4, 562949954076673)},
+                ],
+                "active_index": 0,
+                "version": 562949954076673u64,
+            }
+        });
+        let envelope = decode_string(&encode_json(&json.to_string()).unwrap()).unwrap();
+        let BlueprintEnvelope::BlueprintBook(book) = &envelope else {
+            panic!("expected a book, found {envelope:?}");
+        };
+        for blueprint in book.blueprints_recursive() {
+            let entity = &blueprint.entities.as_deref().unwrap()[0];
+            assert_eq!(entity.direction, Some(Direction::East));
+        }
+
+        let encoded: serde_json::Value =
+            serde_json::from_str(&decode_json(&envelope.to_bp_string().unwrap()).unwrap()).unwrap();
+        assert_eq!(encoded, json);
+    }
+}
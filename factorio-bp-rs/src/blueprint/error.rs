@@ -0,0 +1,121 @@
+//! The error type for decoding and encoding blueprint strings.
+
+use std::io;
+
+use thiserror::Error;
+
+use super::JsonPath;
+
+#[derive(Debug, Error)]
+/// An error encountered while decoding or encoding a blueprint string.
+pub enum BpError {
+    /// The blueprint string contained no data.
+    #[error("blueprint string is empty")]
+    Empty,
+    /// The blueprint string starts with a version byte this library does not understand.
+    #[error("unsupported blueprint string version byte '{0}'")]
+    UnsupportedVersion(char),
+    /// The blueprint string is not valid base64.
+    #[error("blueprint string is not valid base64: {0}")]
+    Base64(#[from] base64::DecodeError),
+    /// The base64 payload could not be decompressed, or the JSON could not be compressed.
+    #[error("blueprint string could not be (de)compressed: {0}")]
+    Zlib(#[source] io::Error),
+    /// The decompressed data is not JSON, or the blueprint could not be written as JSON.
+    #[error("blueprint JSON is malformed: {0}")]
+    Json(#[from] serde_json::Error),
+    /// The JSON is well formed, but does not describe a blueprint, book, or planner.
+    #[error("invalid blueprint at {path}{}: {source}", EntityContext(*entity_number))]
+    Schema {
+        /// Where in the JSON the mismatch is.
+        path: JsonPath,
+        /// The `entity_number` of the entity containing the mismatch, if it is inside one.
+        entity_number: Option<usize>,
+        /// What didn't match.
+        #[source]
+        source: serde_json::Error,
+    },
+    /// A blueprint was expected, but the string holds something else.
+    #[error("expected a blueprint, found a {0}")]
+    NotABlueprint(&'static str),
+    /// The JSON has a field this library doesn't know about, and unknown fields were denied.
+    #[error("unknown field {path}{}", EntityContext(*entity_number))]
+    UnknownField {
+        /// Where in the JSON the field is.
+        path: JsonPath,
+        /// The `entity_number` of the entity containing the field, if it is inside one.
+        entity_number: Option<usize>,
+    },
+}
+
+impl BpError {
+    /// Where in the JSON the error is, for errors about its contents.
+    pub fn path(&self) -> Option<&JsonPath> {
+        match self {
+            BpError::Schema { path, .. } | BpError::UnknownField { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+
+    /// The `entity_number` of the entity the error is in, if it is inside one.
+    pub fn entity_number(&self) -> Option<usize> {
+        match self {
+            BpError::Schema { entity_number, .. } | BpError::UnknownField { entity_number, .. } => {
+                *entity_number
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Formats as " (entity N)" when there is an entity number, and as nothing otherwise.
+struct EntityContext(Option<usize>);
+
+impl core::fmt::Display for EntityContext {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.0 {
+            Some(entity_number) => write!(f, " (entity {entity_number})"),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::blueprint::DecodeOptions;
+
+    #[test]
+    fn errors_inside_entities_name_the_entity() {
+        let json = json!({
+            "blueprint": {
+                "item": "blueprint",
+                "version": 562949954076673u64,
+                "entities": [
+                    {"entity_number": 1, "name": "iron-chest", "position": {"x": 0.5, "y": 0.5}},
+                    {"entity_number": 7, "name": "iron-chest", "position": "nowhere"},
+                ],
+            }
+        });
+        let error = DecodeOptions::default()
+            .decode_from_json(&json.to_string())
+            .unwrap_err();
+
+        assert!(matches!(error, BpError::Schema { .. }));
+        assert_eq!(
+            error.path().unwrap().to_string(),
+            "blueprint.entities[1].position"
+        );
+        assert_eq!(error.entity_number(), Some(7));
+        assert!(error
+            .to_string()
+            .starts_with("invalid blueprint at blueprint.entities[1].position (entity 7): "));
+
+        let error = DecodeOptions::default().decode("").unwrap_err();
+        assert!(matches!(error, BpError::Empty));
+        assert_eq!(error.path(), None);
+        assert_eq!(error.entity_number(), None);
+    }
+}
@@ -0,0 +1,402 @@
+//! The flat JSON form of entities, and telling the kind of an entity from its settings.
+
+use core::num::NonZeroUsize;
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    Color, Connection, ControlBehavior, Direction, Entity, EntityDetails, EntityItems,
+    EquipmentPlacement, FilterMode, GraphicsVariation, InfinityPipeSettings, InfinitySettings,
+    Inventory, IoPriority, IoType, ItemFilter, ItemStackIndex, LogisticRequests, Position, Quality,
+    SpeakerAlertParameter, SpeakerParameter, SplitterFilter, Tags, UnknownFields,
+};
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+/// The `infinity_settings` of an infinity chest or pipe, told apart by their fields.
+enum FlatInfinitySettings {
+    Container(InfinitySettings),
+    Pipe(InfinityPipeSettings),
+}
+
+#[derive(Serialize, Deserialize)]
+/// An entity as blueprint JSON has it, with the settings of every kind of entity side by side.
+pub(super) struct FlatEntity {
+    entity_number: NonZeroUsize,
+    name: String,
+    position: Position,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    direction: Option<Direction>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mirror: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    quality: Option<Quality>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    orientation: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    connections: Option<Connection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    control_behavior: Option<ControlBehavior>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "neighbours")]
+    neighbors: Option<Vec<NonZeroUsize>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    items: Option<EntityItems>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    recipe: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    recipe_quality: Option<Quality>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bar: Option<ItemStackIndex>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    inventory: Option<Inventory>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    infinity_settings: Option<FlatInfinitySettings>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "type")]
+    io_type: Option<IoType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    input_priority: Option<IoPriority>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output_priority: Option<IoPriority>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    filter: Option<SplitterFilter>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    filters: Option<Vec<ItemFilter>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    filter_mode: Option<FilterMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    use_filters: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    override_stack_size: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    drop_position: Option<Position>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pickup_position: Option<Position>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_filters: Option<LogisticRequests>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_from_buffers: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parameters: Option<SpeakerParameter>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    alert_parameters: Option<SpeakerAlertParameter>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    auto_launch: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    variation: Option<GraphicsVariation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    color: Option<Color>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    station: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    grid: Option<Vec<EquipmentPlacement>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tags: Option<Tags>,
+    // Always written, as settings of other kinds of entity are kept here even without the
+    // `preserve-unknown` feature
+    #[serde(flatten, skip_deserializing)]
+    extra: UnknownFields,
+}
+
+impl FlatEntity {
+    /// Take out the settings of the kind of entity this is, judged by the first kind with any of
+    /// its settings set.
+    fn take_details(&mut self) -> EntityDetails {
+        if self.input_priority.is_some() || self.output_priority.is_some() || self.filter.is_some()
+        {
+            EntityDetails::Splitter {
+                input_priority: self.input_priority.take(),
+                output_priority: self.output_priority.take(),
+                filter: self.filter.take(),
+            }
+        } else if self.io_type.is_some() {
+            let filtered = self.filters.is_some() || self.filter_mode.is_some();
+            if filtered || self.name.contains("loader") {
+                EntityDetails::Loader {
+                    io_type: self.io_type.take(),
+                    filters: self.filters.take(),
+                    filter_mode: self.filter_mode.take(),
+                }
+            } else {
+                EntityDetails::UndergroundBelt {
+                    io_type: self.io_type.take(),
+                }
+            }
+        } else if self.filters.is_some()
+            || self.filter_mode.is_some()
+            || self.use_filters.is_some()
+            || self.override_stack_size.is_some()
+            || self.drop_position.is_some()
+            || self.pickup_position.is_some()
+        {
+            EntityDetails::Inserter {
+                filters: self.filters.take(),
+                filter_mode: self.filter_mode.take(),
+                use_filters: self.use_filters.take(),
+                override_stack_size: self.override_stack_size.take(),
+                drop_position: self.drop_position.take(),
+                pickup_position: self.pickup_position.take(),
+            }
+        } else if self.recipe.is_some()
+            || self.recipe_quality.is_some()
+            || self.auto_launch.is_some()
+        {
+            EntityDetails::CraftingMachine {
+                recipe: self.recipe.take(),
+                recipe_quality: self.recipe_quality.take(),
+                auto_launch: self.auto_launch.take(),
+            }
+        } else if let Some(FlatInfinitySettings::Pipe(settings)) = self
+            .infinity_settings
+            .take_if(|settings| matches!(settings, FlatInfinitySettings::Pipe(_)))
+        {
+            EntityDetails::InfinityPipe {
+                infinity_settings: Some(settings),
+            }
+        } else if self.bar.is_some()
+            || self.request_filters.is_some()
+            || self.request_from_buffers.is_some()
+            || self.infinity_settings.is_some()
+        {
+            // Pipe settings were taken above
+            let infinity_settings = match self.infinity_settings.take() {
+                Some(FlatInfinitySettings::Container(settings)) => Some(settings),
+                _ => None,
+            };
+            EntityDetails::Container {
+                bar: self.bar.take(),
+                request_filters: self.request_filters.take(),
+                request_from_buffers: self.request_from_buffers.take(),
+                infinity_settings,
+            }
+        } else if self.orientation.is_some() || self.inventory.is_some() || self.grid.is_some() {
+            EntityDetails::Vehicle {
+                orientation: self.orientation.take(),
+                inventory: self.inventory.take(),
+                grid: self.grid.take(),
+            }
+        } else if self.station.is_some() {
+            EntityDetails::TrainStop {
+                station: self.station.take(),
+            }
+        } else if self.parameters.is_some() || self.alert_parameters.is_some() {
+            EntityDetails::Speaker {
+                parameters: self.parameters.take(),
+                alert_parameters: self.alert_parameters.take(),
+            }
+        } else if self.variation.is_some() {
+            EntityDetails::SimpleEntity {
+                variation: self.variation.take(),
+            }
+        } else {
+            EntityDetails::Plain
+        }
+    }
+
+    /// Move the settings left over after [FlatEntity::take_details], those of another kind of
+    /// entity, into `extra`, so they still survive a round trip.
+    fn keep_leftovers(self, extra: &mut UnknownFields) {
+        keep(extra, "recipe", self.recipe);
+        keep(extra, "recipe_quality", self.recipe_quality);
+        keep(extra, "auto_launch", self.auto_launch);
+        keep(extra, "filters", self.filters);
+        keep(extra, "filter_mode", self.filter_mode);
+        keep(extra, "use_filters", self.use_filters);
+        keep(extra, "override_stack_size", self.override_stack_size);
+        keep(extra, "drop_position", self.drop_position);
+        keep(extra, "pickup_position", self.pickup_position);
+        keep(extra, "type", self.io_type);
+        keep(extra, "input_priority", self.input_priority);
+        keep(extra, "output_priority", self.output_priority);
+        keep(extra, "filter", self.filter);
+        keep(extra, "bar", self.bar);
+        keep(extra, "request_filters", self.request_filters);
+        keep(extra, "request_from_buffers", self.request_from_buffers);
+        keep(extra, "infinity_settings", self.infinity_settings);
+        keep(extra, "orientation", self.orientation);
+        keep(extra, "inventory", self.inventory);
+        keep(extra, "grid", self.grid);
+        keep(extra, "station", self.station);
+        keep(extra, "parameters", self.parameters);
+        keep(extra, "alert_parameters", self.alert_parameters);
+        keep(extra, "variation", self.variation);
+    }
+}
+
+/// Add `value` to `extra` under `key`, if there is one.
+fn keep(extra: &mut UnknownFields, key: &str, value: Option<impl Serialize>) {
+    if let Some(value) = value.and_then(|value| serde_json::to_value(value).ok()) {
+        extra.insert(key.to_owned(), value);
+    }
+}
+
+impl From<FlatEntity> for Entity {
+    fn from(mut flat: FlatEntity) -> Self {
+        let details = flat.take_details();
+        let mut entity = Entity {
+            entity_number: flat.entity_number,
+            name: core::mem::take(&mut flat.name),
+            position: flat.position.clone(),
+            direction: flat.direction.take(),
+            mirror: flat.mirror.take(),
+            quality: flat.quality.take(),
+            connections: flat.connections.take(),
+            control_behavior: flat.control_behavior.take(),
+            neighbors: flat.neighbors.take(),
+            items: flat.items.take(),
+            color: flat.color.take(),
+            tags: flat.tags.take(),
+            details,
+            extra: core::mem::take(&mut flat.extra),
+        };
+        flat.keep_leftovers(&mut entity.extra);
+        entity
+    }
+}
+
+impl From<Entity> for FlatEntity {
+    fn from(entity: Entity) -> Self {
+        let mut flat = FlatEntity {
+            entity_number: entity.entity_number,
+            name: entity.name,
+            position: entity.position,
+            direction: entity.direction,
+            mirror: entity.mirror,
+            quality: entity.quality,
+            orientation: None,
+            connections: entity.connections,
+            control_behavior: entity.control_behavior,
+            neighbors: entity.neighbors,
+            items: entity.items,
+            recipe: None,
+            recipe_quality: None,
+            bar: None,
+            inventory: None,
+            infinity_settings: None,
+            io_type: None,
+            input_priority: None,
+            output_priority: None,
+            filter: None,
+            filters: None,
+            filter_mode: None,
+            use_filters: None,
+            override_stack_size: None,
+            drop_position: None,
+            pickup_position: None,
+            request_filters: None,
+            request_from_buffers: None,
+            parameters: None,
+            alert_parameters: None,
+            auto_launch: None,
+            variation: None,
+            color: entity.color,
+            station: None,
+            grid: None,
+            tags: entity.tags,
+            extra: entity.extra,
+        };
+        match entity.details {
+            EntityDetails::Plain => {}
+            EntityDetails::CraftingMachine {
+                recipe,
+                recipe_quality,
+                auto_launch,
+            } => {
+                flat.recipe = recipe;
+                flat.recipe_quality = recipe_quality;
+                flat.auto_launch = auto_launch;
+            }
+            EntityDetails::Inserter {
+                filters,
+                filter_mode,
+                use_filters,
+                override_stack_size,
+                drop_position,
+                pickup_position,
+            } => {
+                flat.filters = filters;
+                flat.filter_mode = filter_mode;
+                flat.use_filters = use_filters;
+                flat.override_stack_size = override_stack_size;
+                flat.drop_position = drop_position;
+                flat.pickup_position = pickup_position;
+            }
+            EntityDetails::UndergroundBelt { io_type } => flat.io_type = io_type,
+            EntityDetails::Loader {
+                io_type,
+                filters,
+                filter_mode,
+            } => {
+                flat.io_type = io_type;
+                flat.filters = filters;
+                flat.filter_mode = filter_mode;
+            }
+            EntityDetails::Splitter {
+                input_priority,
+                output_priority,
+                filter,
+            } => {
+                flat.input_priority = input_priority;
+                flat.output_priority = output_priority;
+                flat.filter = filter;
+            }
+            EntityDetails::Container {
+                bar,
+                request_filters,
+                request_from_buffers,
+                infinity_settings,
+            } => {
+                flat.bar = bar;
+                flat.request_filters = request_filters;
+                flat.request_from_buffers = request_from_buffers;
+                flat.infinity_settings = infinity_settings.map(FlatInfinitySettings::Container);
+            }
+            EntityDetails::InfinityPipe { infinity_settings } => {
+                flat.infinity_settings = infinity_settings.map(FlatInfinitySettings::Pipe);
+            }
+            EntityDetails::Vehicle {
+                orientation,
+                inventory,
+                grid,
+            } => {
+                flat.orientation = orientation;
+                flat.inventory = inventory;
+                flat.grid = grid;
+            }
+            EntityDetails::TrainStop { station } => flat.station = station,
+            EntityDetails::Speaker {
+                parameters,
+                alert_parameters,
+            } => {
+                flat.parameters = parameters;
+                flat.alert_parameters = alert_parameters;
+            }
+            EntityDetails::SimpleEntity { variation } => flat.variation = variation,
+        }
+        flat
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn settings_of_other_kinds_survive_a_round_trip() {
+        let json = json!({
+            "entity_number": 1,
+            "name": "electric-mining-drill",
+            "position": {"x": 1.5, "y": 1.5},
+            "filters": [{"name": "iron-ore", "index": 1}],
+            "bar": 3,
+            "station": "Ore",
+        });
+        let entity: Entity = serde_json::from_value(json.clone()).unwrap();
+        assert!(matches!(entity.details, EntityDetails::Inserter { .. }));
+        assert_eq!(entity.extra.len(), 2);
+        assert_eq!(serde_json::to_value(&entity).unwrap(), json);
+    }
+}
@@ -0,0 +1,178 @@
+//! Equality and hashing for the blueprint types holding floats, so they can be kept in sets and
+//! used as map keys.
+//!
+//! Floats are equal as they are with `==`, except that NaN equals itself, and they hash by their
+//! bits, with 0 and -0 hashing the same.
+
+use core::hash::{Hash, Hasher};
+
+use super::{
+    BoundingBox, Color, InfinityPipeSettings, LogisticSection, Position, SpeakerParameter,
+};
+
+/// The bits of `value`, the same for all floats equal by [PartialEq] here.
+fn float_bits(value: f64) -> u64 {
+    if value == 0.0 {
+        0
+    } else if value.is_nan() {
+        f64::NAN.to_bits()
+    } else {
+        value.to_bits()
+    }
+}
+
+/// Whether `a` and `b` are equal, counting NaN as equal to itself.
+fn float_eq(a: f64, b: f64) -> bool {
+    float_bits(a) == float_bits(b)
+}
+
+impl PartialEq for Position {
+    fn eq(&self, other: &Self) -> bool {
+        float_eq(self.x, other.x) && float_eq(self.y, other.y)
+    }
+}
+
+impl Eq for Position {}
+
+impl Hash for Position {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        float_bits(self.x).hash(state);
+        float_bits(self.y).hash(state);
+    }
+}
+
+impl PartialEq for Color {
+    fn eq(&self, other: &Self) -> bool {
+        float_eq(self.r, other.r)
+            && float_eq(self.g, other.g)
+            && float_eq(self.b, other.b)
+            && float_eq(self.a, other.a)
+    }
+}
+
+impl Eq for Color {}
+
+impl Hash for Color {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for channel in [self.r, self.g, self.b, self.a] {
+            float_bits(channel).hash(state);
+        }
+    }
+}
+
+impl PartialEq for BoundingBox {
+    fn eq(&self, other: &Self) -> bool {
+        float_eq(self.min_x, other.min_x)
+            && float_eq(self.min_y, other.min_y)
+            && float_eq(self.max_x, other.max_x)
+            && float_eq(self.max_y, other.max_y)
+    }
+}
+
+impl Eq for BoundingBox {}
+
+impl Hash for BoundingBox {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for edge in [self.min_x, self.min_y, self.max_x, self.max_y] {
+            float_bits(edge).hash(state);
+        }
+    }
+}
+
+impl PartialEq for SpeakerParameter {
+    fn eq(&self, other: &Self) -> bool {
+        float_eq(self.playback_volume, other.playback_volume)
+            && self.playback_globally == other.playback_globally
+            && self.allow_polyphony == other.allow_polyphony
+    }
+}
+
+impl Eq for SpeakerParameter {}
+
+impl Hash for SpeakerParameter {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        float_bits(self.playback_volume).hash(state);
+        self.playback_globally.hash(state);
+        self.allow_polyphony.hash(state);
+    }
+}
+
+impl PartialEq for LogisticSection {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index
+            && self.filters == other.filters
+            && self.group == other.group
+            && self.multiplier.map(float_bits) == other.multiplier.map(float_bits)
+            && self.active == other.active
+    }
+}
+
+impl Eq for LogisticSection {}
+
+impl Hash for LogisticSection {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.index.hash(state);
+        self.filters.hash(state);
+        self.group.hash(state);
+        self.multiplier.map(float_bits).hash(state);
+        self.active.hash(state);
+    }
+}
+
+impl PartialEq for InfinityPipeSettings {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+            && self.percentage.map(float_bits) == other.percentage.map(float_bits)
+            && self.temperature.map(float_bits) == other.temperature.map(float_bits)
+            && self.mode == other.mode
+    }
+}
+
+impl Eq for InfinityPipeSettings {}
+
+impl Hash for InfinityPipeSettings {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+        self.percentage.map(float_bits).hash(state);
+        self.temperature.map(float_bits).hash(state);
+        self.mode.hash(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn zeros_and_nans_are_one_value_each() {
+        let position = |x, y| Position { x, y };
+        let positions = HashSet::from([
+            position(0.0, 1.5),
+            position(-0.0, 1.5),
+            position(f64::NAN, 1.5),
+            position(-f64::NAN, 1.5),
+            position(0.5, 1.5),
+        ]);
+        assert_eq!(positions.len(), 3);
+        assert!(positions.contains(&position(-0.0, 1.5)));
+        assert_ne!(position(0.5, 1.5), position(1.5, 0.5));
+
+        let colors = HashSet::from([
+            Color {
+                r: 1.0,
+                g: 0.0,
+                b: 0.0,
+                a: 1.0,
+            },
+            Color {
+                r: 1.0,
+                g: -0.0,
+                b: 0.0,
+                a: 1.0,
+            },
+        ]);
+        assert_eq!(colors.len(), 1);
+    }
+}
@@ -0,0 +1,229 @@
+//! Areas covered by entities, tiles, and whole blueprints.
+
+use crate::{prototypes::PrototypeDb, Size};
+
+use super::{Blueprint, Entity, Position, Tile};
+
+#[derive(Debug, Clone, Copy, Default)]
+/// An axis-aligned rectangle in blueprint coordinates. Positive y is down, as in game.
+pub struct BoundingBox {
+    /// The x coordinate of the left edge.
+    pub min_x: f64,
+    /// The y coordinate of the top edge.
+    pub min_y: f64,
+    /// The x coordinate of the right edge.
+    pub max_x: f64,
+    /// The y coordinate of the bottom edge.
+    pub max_y: f64,
+}
+
+impl BoundingBox {
+    /// The box with the given edges.
+    pub fn new(min_x: f64, min_y: f64, max_x: f64, max_y: f64) -> Self {
+        Self {
+            min_x,
+            min_y,
+            max_x,
+            max_y,
+        }
+    }
+
+    /// The box of the given width and height centred on `center`.
+    pub fn around(center: &Position, width: f64, height: f64) -> Self {
+        Self::new(
+            center.x - width / 2.0,
+            center.y - height / 2.0,
+            center.x + width / 2.0,
+            center.y + height / 2.0,
+        )
+    }
+
+    /// Width of the box.
+    pub fn width(&self) -> f64 {
+        self.max_x - self.min_x
+    }
+
+    /// Height of the box.
+    pub fn height(&self) -> f64 {
+        self.max_y - self.min_y
+    }
+
+    /// Area of the box.
+    pub fn area(&self) -> f64 {
+        self.width() * self.height()
+    }
+
+    /// The centre of the box.
+    pub fn center(&self) -> Position {
+        Position {
+            x: (self.min_x + self.max_x) / 2.0,
+            y: (self.min_y + self.max_y) / 2.0,
+        }
+    }
+
+    /// The number of whole tiles the box spans in each direction, counting partly covered tiles.
+    pub fn tile_size(&self) -> Size {
+        Size {
+            w: (self.max_x.ceil() - self.min_x.floor()).max(0.0) as usize,
+            h: (self.max_y.ceil() - self.min_y.floor()).max(0.0) as usize,
+        }
+    }
+
+    /// Whether `position` is inside the box or on its edge.
+    pub fn contains(&self, position: &Position) -> bool {
+        (self.min_x..=self.max_x).contains(&position.x)
+            && (self.min_y..=self.max_y).contains(&position.y)
+    }
+
+    /// Whether the boxes share some area. Boxes that only touch along an edge don't intersect.
+    pub fn intersects(&self, other: &BoundingBox) -> bool {
+        self.min_x < other.max_x
+            && other.min_x < self.max_x
+            && self.min_y < other.max_y
+            && other.min_y < self.max_y
+    }
+
+    /// The box grown by `distance` on every side, or shrunk if `distance` is negative.
+    pub fn expand(&self, distance: f64) -> BoundingBox {
+        BoundingBox::new(
+            self.min_x - distance,
+            self.min_y - distance,
+            self.max_x + distance,
+            self.max_y + distance,
+        )
+    }
+
+    /// The whole tiles the box covers any part of, by the coordinates of their top left corners,
+    /// row by row from the top left.
+    pub fn tiles(&self) -> impl Iterator<Item = (i64, i64)> {
+        let columns = self.min_x.floor() as i64..self.max_x.ceil() as i64;
+        let rows = self.min_y.floor() as i64..self.max_y.ceil() as i64;
+        rows.flat_map(move |y| columns.clone().map(move |x| (x, y)))
+    }
+
+    /// The smallest box containing both boxes.
+    pub fn union(&self, other: &BoundingBox) -> BoundingBox {
+        BoundingBox::new(
+            self.min_x.min(other.min_x),
+            self.min_y.min(other.min_y),
+            self.max_x.max(other.max_x),
+            self.max_y.max(other.max_y),
+        )
+    }
+}
+
+/// The smallest entity size along one axis that allows it to be centred on `coordinate`.
+///
+/// Entities with odd sizes are centred on tile centres, and those with even sizes on tile corners.
+fn min_size_at(coordinate: f64) -> f64 {
+    if coordinate.fract() == 0.0 {
+        2.0
+    } else {
+        1.0
+    }
+}
+
+impl Entity {
+    /// The smallest area the entity could cover, judging only by its position.
+    ///
+    /// An entity centred on a tile is assumed to cover that tile, and one centred on a tile corner
+    /// the 4 tiles around it.
+    pub fn min_bounding_box(&self) -> BoundingBox {
+        let position = &self.position;
+        BoundingBox::around(position, min_size_at(position.x), min_size_at(position.y))
+    }
+}
+
+impl Tile {
+    /// The area the tile covers. Tile positions are their top left corners.
+    pub fn bounding_box(&self) -> BoundingBox {
+        let Position { x, y } = self.position;
+        BoundingBox::new(x, y, x + 1.0, y + 1.0)
+    }
+}
+
+impl Blueprint {
+    /// The smallest box containing every entity and tile, or `None` if the blueprint is empty.
+    ///
+    /// Entity footprints come from the [global](PrototypeDb::global) prototypes, see
+    /// [Blueprint::bounding_box_with].
+    pub fn bounding_box(&self) -> Option<BoundingBox> {
+        self.bounding_box_with(PrototypeDb::global())
+    }
+
+    /// The smallest box containing every entity and tile, with entity footprints from `db`, or
+    /// `None` if the blueprint is empty.
+    ///
+    /// Entities `db` doesn't know are given the smallest footprint their position allows, see
+    /// [Entity::min_bounding_box].
+    pub fn bounding_box_with(&self, db: &PrototypeDb) -> Option<BoundingBox> {
+        let entities = self.entities.iter().flatten().map(|entity| {
+            entity
+                .footprint(db)
+                .unwrap_or_else(|| entity.min_bounding_box())
+        });
+        let tiles = self.tiles.iter().flatten().map(Tile::bounding_box);
+
+        entities.chain(tiles).reduce(|a, b| a.union(&b))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blueprint::{BlueprintBuilder, EntityBuilder};
+
+    #[test]
+    fn blueprints_are_bounded_by_their_entities_and_tiles() {
+        let blueprint = BlueprintBuilder::new()
+            .entity(EntityBuilder::new("modded-box").at(0.5, 0.5))
+            .entity(EntityBuilder::new("modded-box").at(3.0, 2.0))
+            .tile("concrete", -2, 5)
+            .build()
+            .unwrap();
+        let db = PrototypeDb::new();
+
+        let bounds = blueprint.bounding_box_with(&db).unwrap();
+        assert_eq!(
+            (bounds.min_x, bounds.min_y, bounds.max_x, bounds.max_y),
+            (-2.0, 0.0, 4.0, 6.0)
+        );
+        assert_eq!(bounds.area(), 36.0);
+        assert_eq!(bounds.center(), Position { x: 1.0, y: 3.0 });
+        assert_eq!(bounds.tile_size(), Size { w: 6, h: 6 });
+        assert!(bounds.contains(&Position { x: 4.0, y: 6.0 }));
+        assert!(!bounds.contains(&Position { x: 4.5, y: 0.5 }));
+
+        let empty = BlueprintBuilder::new().build().unwrap();
+        assert!(empty.bounding_box_with(&db).is_none());
+    }
+
+    #[test]
+    fn boxes_touching_along_an_edge_do_not_intersect() {
+        let a = BoundingBox::new(0.0, 0.0, 2.0, 2.0);
+        assert!(!a.intersects(&BoundingBox::new(2.0, 0.0, 3.0, 1.0)));
+        assert!(a.intersects(&BoundingBox::new(1.5, 1.5, 3.0, 3.0)));
+
+        let union = a.union(&BoundingBox::new(-1.0, 1.0, 1.0, 4.0));
+        assert_eq!(
+            (union.min_x, union.min_y, union.max_x, union.max_y),
+            (-1.0, 0.0, 2.0, 4.0)
+        );
+    }
+
+    #[test]
+    fn boxes_expand_and_cover_partly_covered_tiles() {
+        let pole = BoundingBox::around(&Position { x: 0.5, y: 0.5 }, 1.0, 1.0).expand(2.5);
+        assert_eq!(
+            (pole.min_x, pole.min_y, pole.max_x, pole.max_y),
+            (-2.5, -2.5, 3.5, 3.5)
+        );
+        assert_eq!(pole.expand(-2.5).area(), 1.0);
+
+        let tiles: Vec<_> = BoundingBox::new(-0.5, 0.0, 1.5, 1.0).tiles().collect();
+        assert_eq!(tiles, [(-1, 0), (0, 0), (1, 0)]);
+        let tiles: Vec<_> = BoundingBox::new(0.0, 0.0, 2.0, 2.0).tiles().collect();
+        assert_eq!(tiles, [(0, 0), (1, 0), (0, 1), (1, 1)]);
+        assert_eq!(pole.tiles().count(), 49);
+    }
+}
@@ -0,0 +1,184 @@
+//! Finding entities in a blueprint without searching through all of them.
+
+use core::{cell::OnceCell, num::NonZeroUsize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::prototypes::PrototypeDb;
+
+use super::{occupancy::covered_tiles, Blueprint, Entity, Position};
+
+#[derive(Debug)]
+/// Lookups of a blueprint's entities by number, name, and position. Each index is built the
+/// first time it is used, so building an `EntityIndex` costs nothing until then.
+///
+/// This is the way to look up many entities: keep one `EntityIndex` for as long as the
+/// blueprint doesn't change. The lookups of the same names on [Blueprint] search every entity
+/// each time.
+pub struct EntityIndex<'a> {
+    entities: &'a [Entity],
+    db: &'a PrototypeDb,
+    by_number: OnceCell<BTreeMap<NonZeroUsize, &'a Entity>>,
+    by_name: OnceCell<BTreeMap<&'a str, Vec<&'a Entity>>>,
+    by_tile: OnceCell<HashMap<(i64, i64), &'a Entity>>,
+}
+
+impl<'a> EntityIndex<'a> {
+    /// An index of the entities of `blueprint`, with their footprints from `db`.
+    pub fn new(blueprint: &'a Blueprint, db: &'a PrototypeDb) -> Self {
+        Self {
+            entities: blueprint.entities.as_deref().unwrap_or_default(),
+            db,
+            by_number: OnceCell::new(),
+            by_name: OnceCell::new(),
+            by_tile: OnceCell::new(),
+        }
+    }
+
+    /// The entity with this entity_number. If several have it, the first in the blueprint.
+    pub fn entity(&self, entity_number: NonZeroUsize) -> Option<&'a Entity> {
+        let by_number = self.by_number.get_or_init(|| {
+            let mut by_number = BTreeMap::new();
+            for entity in self.entities {
+                by_number.entry(entity.entity_number).or_insert(entity);
+            }
+            by_number
+        });
+        by_number.get(&entity_number).copied()
+    }
+
+    /// The entities with this prototype name, e.g. "fast-inserter", in blueprint order.
+    pub fn entities_named(&self, name: &str) -> &[&'a Entity] {
+        let by_name = self.by_name.get_or_init(|| {
+            let mut by_name: BTreeMap<_, Vec<_>> = BTreeMap::new();
+            for entity in self.entities {
+                by_name
+                    .entry(entity.name.as_str())
+                    .or_default()
+                    .push(entity);
+            }
+            by_name
+        });
+        by_name.get(name).map_or(&[], Vec::as_slice)
+    }
+
+    /// The entity covering the tile `position` is on. If several do, the first in the
+    /// blueprint. Entities the prototypes don't know only cover the tile they are centred on.
+    pub fn entity_at(&self, position: &Position) -> Option<&'a Entity> {
+        let by_tile = self.by_tile.get_or_init(|| {
+            let mut by_tile = HashMap::new();
+            for entity in self.entities {
+                for tile in covered_tiles(entity, self.db) {
+                    by_tile.entry(tile).or_insert(entity);
+                }
+            }
+            by_tile
+        });
+        by_tile.get(&tile_of(position)).copied()
+    }
+}
+
+/// The tile `position` is on, by its top left corner.
+fn tile_of(position: &Position) -> (i64, i64) {
+    (position.x.floor() as i64, position.y.floor() as i64)
+}
+
+impl Blueprint {
+    /// An [EntityIndex] for looking up the blueprint's entities, with their footprints from the
+    /// [global](PrototypeDb::global) prototypes.
+    pub fn index(&self) -> EntityIndex<'_> {
+        self.index_with(PrototypeDb::global())
+    }
+
+    /// An [EntityIndex] for looking up the blueprint's entities, with their footprints from
+    /// `db`.
+    pub fn index_with<'a>(&'a self, db: &'a PrototypeDb) -> EntityIndex<'a> {
+        EntityIndex::new(self, db)
+    }
+
+    /// The entity with this entity_number. If several have it, the first in the blueprint.
+    ///
+    /// This searches every entity; use an [index](Blueprint::index) for many lookups.
+    pub fn entity(&self, entity_number: NonZeroUsize) -> Option<&Entity> {
+        self.entities
+            .iter()
+            .flatten()
+            .find(|entity| entity.entity_number == entity_number)
+    }
+
+    /// The entities with this prototype name, e.g. "fast-inserter", in blueprint order.
+    ///
+    /// This searches every entity; use an [index](Blueprint::index) for many lookups.
+    pub fn entities_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Entity> {
+        self.entities
+            .iter()
+            .flatten()
+            .filter(move |entity| entity.name == name)
+    }
+
+    /// The entity covering the tile `position` is on, with footprints from the
+    /// [global](PrototypeDb::global) prototypes. If several do, the first in the blueprint.
+    ///
+    /// This searches every entity; use an [index](Blueprint::index) for many lookups.
+    pub fn entity_at(&self, position: &Position) -> Option<&Entity> {
+        let tile = tile_of(position);
+        let db = PrototypeDb::global();
+        self.entities
+            .iter()
+            .flatten()
+            .find(|entity| covered_tiles(entity, db).contains(&tile))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bp;
+
+    #[test]
+    fn entities_are_found_by_any_tile_they_cover() {
+        let db = PrototypeDb::vanilla();
+        let blueprint = bp! {
+            "assembling-machine-2" @ (0, 0);
+            "inserter" @ (1, 3);
+        }
+        .build_with(db)
+        .unwrap();
+        let index = blueprint.index_with(db);
+        let at = |x, y| {
+            index
+                .entity_at(&Position { x, y })
+                .map(|entity| entity.name.as_str())
+        };
+
+        assert_eq!(at(1.5, 1.5), Some("assembling-machine-2"));
+        assert_eq!(at(0.1, 2.9), Some("assembling-machine-2"));
+        assert_eq!(at(1.5, 3.5), Some("inserter"));
+        assert_eq!(at(0.5, 3.5), None);
+        assert_eq!(at(-0.5, 0.5), None);
+        assert_eq!(
+            blueprint.entity_at(&Position { x: 2.9, y: 0.1 }),
+            index.entity(NonZeroUsize::MIN)
+        );
+    }
+
+    #[test]
+    fn blueprint_lookups_match_the_index() {
+        let db = PrototypeDb::vanilla();
+        let blueprint = bp! {
+            "inserter" @ (0, 0);
+            "fast-inserter" @ (1, 0);
+            "inserter" @ (2, 0);
+        }
+        .build_with(db)
+        .unwrap();
+        let index = blueprint.index_with(db);
+        let third = NonZeroUsize::new(3).unwrap();
+
+        assert_eq!(blueprint.entity(third), index.entity(third));
+        assert_eq!(
+            blueprint.entities_named("inserter").collect::<Vec<_>>(),
+            index.entities_named("inserter")
+        );
+        assert_eq!(blueprint.entities_named("stone-furnace").count(), 0);
+    }
+}
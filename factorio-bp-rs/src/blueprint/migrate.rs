@@ -0,0 +1,503 @@
+//! Prototypes renamed and added between game versions, and moving blueprints between versions.
+
+use std::collections::BTreeSet;
+use std::num::NonZeroUsize;
+
+use super::{
+    Blueprint, EntityDetails, EntityItems, ItemRequest, LogisticRequests, ScheduleData, SignalType,
+    SplitterFilter, Version,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+/// The prototypes renamed and added by a game version.
+pub struct Migration {
+    /// The version renaming them.
+    pub version: Version,
+    /// Each old name and the name replacing it. Entities, items, recipes, and tiles share one
+    /// list, since a rename applies to every kind of prototype with that name.
+    pub renames: &'static [(&'static str, &'static str)],
+    /// The entities first added by this version, including those of the official mods released
+    /// with it, but not those it renamed.
+    pub added: &'static [&'static str],
+}
+
+impl Migration {
+    /// The name replacing `name`, if this migration renames it.
+    pub fn rename(&self, name: &str) -> Option<&'static str> {
+        self.renames
+            .iter()
+            .find(|(old, _)| *old == name)
+            .map(|&(_, new)| new)
+    }
+
+    /// The name `name` had before this migration, if it renamed something to it. Filter inserters
+    /// became inserters with their filters in use, so only those with `filtered` set get a filter
+    /// inserter's name back.
+    pub fn revert(&self, name: &str, filtered: bool) -> Option<&'static str> {
+        let mut olds = self
+            .renames
+            .iter()
+            .filter(|(_, new)| *new == name)
+            .map(|&(old, _)| old);
+        olds.clone()
+            .find(|old| filtered && FILTER_INSERTERS.contains(old))
+            .or_else(|| olds.find(|old| !FILTER_INSERTERS.contains(old)))
+    }
+}
+
+/// The renames and additions of the base game, oldest first.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: Version::new(1, 1, 0, 0),
+        renames: &[],
+        added: &["spidertron"],
+    },
+    Migration {
+        version: Version::new(2, 0, 0, 0),
+        renames: &[
+            ("filter-inserter", "fast-inserter"),
+            ("stack-inserter", "bulk-inserter"),
+            ("stack-filter-inserter", "bulk-inserter"),
+            ("logistic-chest-active-provider", "active-provider-chest"),
+            ("logistic-chest-passive-provider", "passive-provider-chest"),
+            ("logistic-chest-storage", "storage-chest"),
+            ("logistic-chest-buffer", "buffer-chest"),
+            ("logistic-chest-requester", "requester-chest"),
+            ("straight-rail", "legacy-straight-rail"),
+            ("curved-rail", "legacy-curved-rail"),
+            ("effectivity-module", "efficiency-module"),
+            ("effectivity-module-2", "efficiency-module-2"),
+            ("effectivity-module-3", "efficiency-module-3"),
+            ("empty-barrel", "barrel"),
+        ],
+        added: &[
+            "selector-combinator",
+            "display-panel",
+            "half-diagonal-rail",
+            "curved-rail-a",
+            "curved-rail-b",
+            "rail-ramp",
+            "rail-support",
+            "elevated-straight-rail",
+            "elevated-half-diagonal-rail",
+            "elevated-curved-rail-a",
+            "elevated-curved-rail-b",
+            "recycler",
+            "turbo-transport-belt",
+            "turbo-underground-belt",
+            "turbo-splitter",
+            "turbo-loader",
+            "lightning-rod",
+            "lightning-collector",
+            "fusion-reactor",
+            "fusion-generator",
+            "heating-tower",
+            "big-mining-drill",
+            "biolab",
+            "electromagnetic-plant",
+            "foundry",
+            "biochamber",
+            "cryogenic-plant",
+            "crusher",
+            "agricultural-tower",
+            "captive-biter-spawner",
+            "asteroid-collector",
+            "thruster",
+            "space-platform-hub",
+            "cargo-bay",
+            "cargo-landing-pad",
+            "rocket-turret",
+            "tesla-turret",
+            "railgun-turret",
+        ],
+    },
+];
+
+/// Inserters that lost their own prototype when filters became a setting of every inserter.
+const FILTER_INSERTERS: &[&str] = &["filter-inserter", "stack-filter-inserter"];
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// What [Blueprint::strip_unsupported] removed.
+pub struct Stripped {
+    /// The entity number and name of each entity removed.
+    pub entities: Vec<(NonZeroUsize, String)>,
+    /// The names of the fields removed from the blueprint or its entities, or converted losing
+    /// some of their settings, e.g. "quality".
+    pub fields: BTreeSet<&'static str>,
+}
+
+impl Stripped {
+    /// Whether nothing was removed.
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty() && self.fields.is_empty()
+    }
+}
+
+impl Blueprint {
+    /// Rename the entities, items, recipes, and tiles renamed by the game since the blueprint's
+    /// version, up to and including `target`. Filter inserters keep using their filters.
+    ///
+    /// Circuit conditions and train schedules keep their old names, and the blueprint's version
+    /// is left as is, since its wires and directions are still stored the old way.
+    pub fn migrate_to(&mut self, target: &Version) {
+        let from = self.version;
+        let migrations = MIGRATIONS
+            .iter()
+            .filter(|migration| from < migration.version && migration.version <= *target);
+        for migration in migrations {
+            for entity in self.entities.iter_mut().flatten() {
+                if !FILTER_INSERTERS.contains(&entity.name.as_str()) {
+                    continue;
+                }
+                if let EntityDetails::Inserter {
+                    filters: Some(_),
+                    use_filters,
+                    ..
+                } = &mut entity.details
+                {
+                    *use_filters = Some(true);
+                }
+            }
+            self.rename_prototypes(|name| migration.rename(name));
+        }
+    }
+
+    /// Make the blueprint importable by `target`, an older version than its own: give renamed
+    /// prototypes their old names back, remove the entities `target` doesn't have, and remove or
+    /// convert what the blueprint format gained since. The blueprint then has `target` as its
+    /// version.
+    ///
+    /// Before 2.0 there are no wires, so circuit and copper wires are removed rather than
+    /// converted, along with qualities, parameters, and mirroring. Items to insert and logistic
+    /// requests lose their slots and sections, keeping only the requests in use.
+    pub fn strip_unsupported(&mut self, target: &Version) -> Stripped {
+        let mut stripped = Stripped::default();
+        if *target >= self.version {
+            return stripped;
+        }
+        let from = self.version;
+        let migrations = MIGRATIONS
+            .iter()
+            .rev()
+            .filter(|migration| *target < migration.version && migration.version <= from);
+        for migration in migrations {
+            for entity in self.entities.iter_mut().flatten() {
+                let filtered = matches!(
+                    entity.details,
+                    EntityDetails::Inserter {
+                        use_filters: Some(true),
+                        ..
+                    }
+                );
+                if let Some(old) = migration.revert(&entity.name, filtered) {
+                    entity.name = old.to_owned();
+                }
+            }
+            self.rename_prototypes(|name| migration.revert(name, false));
+
+            let added = |name: &str| migration.added.contains(&name);
+            for entity in self.entities.iter().flatten().filter(|e| added(&e.name)) {
+                stripped
+                    .entities
+                    .push((entity.entity_number, entity.name.clone()));
+            }
+            if let Some(entities) = &mut self.entities {
+                entities.retain(|entity| !added(&entity.name));
+            }
+        }
+
+        if !target.is_at_least(2, 0, 0) {
+            self.strip_2_0_fields(&mut stripped);
+        }
+        self.version = *target;
+        stripped
+    }
+
+    /// Remove or convert the fields of the 2.0 blueprint format.
+    fn strip_2_0_fields(&mut self, stripped: &mut Stripped) {
+        let mut strip = |field: &'static str, removed: bool| {
+            if removed {
+                stripped.fields.insert(field);
+            }
+        };
+
+        strip("wires", self.wires.take().is_some());
+        strip("parameters", self.parameters.take().is_some());
+        for icon in &mut self.icons {
+            strip("quality", icon.signal.quality.take().is_some());
+            // Item signals have always had their type written out before 2.0
+            icon.signal.signal_type.get_or_insert(SignalType::Item);
+        }
+        for schedule in self.schedules.iter_mut().flatten() {
+            if let ScheduleData::Schedule(train_schedule) = &schedule.schedule {
+                strip("interrupts", !train_schedule.interrupts.is_empty());
+                strip("group", train_schedule.group.is_some());
+                schedule.schedule = ScheduleData::Legacy(train_schedule.records.clone());
+            }
+        }
+
+        for entity in self.entities.iter_mut().flatten() {
+            strip("mirror", entity.mirror.take().is_some());
+            strip("quality", entity.quality.take().is_some());
+            if let Some(EntityItems::InsertPlans(plans)) = &entity.items {
+                let mut request = ItemRequest::new();
+                for plan in plans {
+                    strip("quality", plan.id.quality.is_some());
+                    *request.entry(plan.id.name.clone()).or_default() += plan.items.total_count();
+                }
+                strip("items", true);
+                entity.items = Some(EntityItems::Legacy(request));
+            }
+            if let EntityDetails::Inserter { use_filters, .. } = &mut entity.details {
+                strip("use_filters", use_filters.take().is_some());
+            }
+            match &mut entity.details {
+                EntityDetails::CraftingMachine { recipe_quality, .. } => {
+                    strip("recipe_quality", recipe_quality.take().is_some());
+                }
+                EntityDetails::Inserter { filters, .. } | EntityDetails::Loader { filters, .. } => {
+                    for filter in filters.iter_mut().flatten() {
+                        strip("quality", filter.quality.take().is_some());
+                        strip("comparator", filter.comparator.take().is_some());
+                    }
+                }
+                EntityDetails::Splitter { filter, .. } => {
+                    if let Some(SplitterFilter::Item(id)) = filter {
+                        strip("quality", id.quality.is_some());
+                        *filter = Some(SplitterFilter::Legacy(id.name.clone()));
+                    }
+                }
+                EntityDetails::Container {
+                    request_filters, ..
+                } => {
+                    if let Some(LogisticRequests::Sections(sections)) = request_filters {
+                        strip("request_filters", true);
+                        let in_use = sections
+                            .sections
+                            .drain(..)
+                            .filter(|section| section.is_active());
+                        let filters = in_use.flat_map(|section| section.filters).collect();
+                        *request_filters = Some(LogisticRequests::Legacy(filters));
+                    }
+                    if let Some(LogisticRequests::Legacy(filters)) = request_filters {
+                        for filter in filters {
+                            strip("quality", filter.quality.take().is_some());
+                            strip("comparator", filter.comparator.take().is_some());
+                            strip("max_count", filter.max_count.take().is_some());
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Replace each entity, item, recipe, and tile name `rename` has a new name for.
+    fn rename_prototypes(&mut self, rename: impl Fn(&str) -> Option<&'static str>) {
+        let rename = |name: &mut String| {
+            if let Some(new) = rename(name) {
+                *name = new.to_owned();
+            }
+        };
+
+        for entity in self.entities.iter_mut().flatten() {
+            rename(&mut entity.name);
+            match &mut entity.items {
+                Some(EntityItems::Legacy(request)) => {
+                    let mut renamed = ItemRequest::new();
+                    for (mut name, count) in core::mem::take(request) {
+                        rename(&mut name);
+                        *renamed.entry(name).or_default() += count;
+                    }
+                    *request = renamed;
+                }
+                Some(EntityItems::InsertPlans(plans)) => {
+                    for plan in plans {
+                        rename(&mut plan.id.name);
+                    }
+                }
+                None => {}
+            }
+            match &mut entity.details {
+                EntityDetails::CraftingMachine {
+                    recipe: Some(recipe),
+                    ..
+                } => rename(recipe),
+                EntityDetails::Inserter { filters, .. } | EntityDetails::Loader { filters, .. } => {
+                    for filter in filters.iter_mut().flatten() {
+                        rename(&mut filter.name);
+                    }
+                }
+                EntityDetails::Splitter { filter, .. } => match filter {
+                    Some(SplitterFilter::Legacy(name)) => rename(name),
+                    Some(SplitterFilter::Item(id)) => rename(&mut id.name),
+                    None => {}
+                },
+                EntityDetails::Container {
+                    request_filters,
+                    infinity_settings,
+                    ..
+                } => {
+                    let requests = match request_filters {
+                        Some(LogisticRequests::Legacy(filters)) => filters.iter_mut().collect(),
+                        Some(LogisticRequests::Sections(sections)) => sections
+                            .sections
+                            .iter_mut()
+                            .flat_map(|section| section.filters.iter_mut())
+                            .collect(),
+                        None => Vec::new(),
+                    };
+                    for request in requests {
+                        if matches!(request.signal_type, None | Some(SignalType::Item)) {
+                            rename(&mut request.name);
+                        }
+                    }
+                    let infinity_filters = infinity_settings
+                        .iter_mut()
+                        .flat_map(|settings| settings.filters.iter_mut().flatten());
+                    for filter in infinity_filters {
+                        rename(&mut filter.name);
+                    }
+                }
+                EntityDetails::InfinityPipe {
+                    infinity_settings: Some(settings),
+                } => rename(&mut settings.name),
+                _ => {}
+            }
+        }
+        for tile in self.tiles.iter_mut().flatten() {
+            rename(&mut tile.name);
+        }
+        for icon in &mut self.icons {
+            if matches!(
+                icon.signal.kind(),
+                SignalType::Item | SignalType::Entity | SignalType::Recipe
+            ) {
+                rename(&mut icon.signal.name);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// A 1.1 blueprint of a filter inserter, a stack inserter, a requester chest asking for
+    /// barrels, and an assembler with effectivity modules.
+    fn old_blueprint() -> Blueprint {
+        serde_json::from_value(json!({
+            "item": "blueprint",
+            "version": 281479271677952u64,
+            "entities": [
+                {
+                    "entity_number": 1,
+                    "name": "filter-inserter",
+                    "position": {"x": 0.5, "y": 0.5},
+                    "filters": [{"index": 1, "name": "iron-plate"}]
+                },
+                {"entity_number": 2, "name": "stack-inserter", "position": {"x": 1.5, "y": 0.5}},
+                {
+                    "entity_number": 3,
+                    "name": "logistic-chest-requester",
+                    "position": {"x": 2.5, "y": 0.5},
+                    "request_filters": [{"index": 1, "name": "empty-barrel", "count": 10}]
+                },
+                {
+                    "entity_number": 4,
+                    "name": "assembling-machine-2",
+                    "position": {"x": 5.5, "y": 1.5},
+                    "items": {"effectivity-module": 2}
+                }
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn renamed_prototypes_get_their_new_names() {
+        let mut blueprint = old_blueprint();
+        blueprint.migrate_to(&Version::new(2, 0, 0, 0));
+
+        let entities = blueprint.entities.as_deref().unwrap();
+        let names: Vec<_> = entities.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "fast-inserter",
+                "bulk-inserter",
+                "requester-chest",
+                "assembling-machine-2"
+            ]
+        );
+        assert!(matches!(
+            entities[0].details,
+            EntityDetails::Inserter {
+                use_filters: Some(true),
+                ..
+            }
+        ));
+        let requests: Vec<_> = entities[2]
+            .request_filters()
+            .into_iter()
+            .flat_map(|r| r.filters())
+            .map(|r| r.name.as_str())
+            .collect();
+        assert_eq!(requests, ["barrel"]);
+        let items: Vec<_> = entities[3].items.iter().flat_map(|i| i.counts()).collect();
+        assert_eq!(items, [("efficiency-module", 2)]);
+        assert_eq!(blueprint.version, Version::new(1, 1, 0, 0));
+
+        let mut unchanged = old_blueprint();
+        unchanged.migrate_to(&Version::new(1, 1, 50, 0));
+        assert_eq!(unchanged, old_blueprint());
+    }
+
+    #[test]
+    fn older_versions_lose_what_they_do_not_support() {
+        let mut blueprint: Blueprint = serde_json::from_value(json!({
+            "item": "blueprint",
+            "version": 562949954076673u64,
+            "entities": [
+                {
+                    "entity_number": 1,
+                    "name": "fast-inserter",
+                    "position": {"x": 0.5, "y": 0.5},
+                    "use_filters": true,
+                    "filters": [{"index": 1, "name": "iron-plate", "quality": "rare"}]
+                },
+                {"entity_number": 2, "name": "foundry", "position": {"x": 4.5, "y": 2.5}},
+                {
+                    "entity_number": 3,
+                    "name": "bulk-inserter",
+                    "position": {"x": 1.5, "y": 0.5},
+                    "quality": "uncommon"
+                }
+            ],
+            "wires": [[1, 1, 3, 1]]
+        }))
+        .unwrap();
+        let target = Version::new(1, 1, 0, 0);
+        let stripped = blueprint.strip_unsupported(&target);
+
+        let names: Vec<_> = blueprint
+            .entities
+            .iter()
+            .flatten()
+            .map(|e| &e.name)
+            .collect();
+        assert_eq!(names, ["filter-inserter", "stack-inserter"]);
+        assert_eq!(
+            stripped.entities,
+            [(NonZeroUsize::new(2).unwrap(), "foundry".to_owned())]
+        );
+        assert_eq!(
+            stripped.fields,
+            BTreeSet::from(["quality", "use_filters", "wires"])
+        );
+        assert_eq!(blueprint.wires, None);
+        assert_eq!(blueprint.version, target);
+        assert!(blueprint.strip_unsupported(&target).is_empty());
+    }
+}
@@ -0,0 +1,198 @@
+//! Leaving out what the game would assume anyway, to make blueprint strings shorter.
+
+use super::{
+    Blueprint, BlueprintBook, BlueprintEnvelope, Direction, Entity, EntityDetails, EntityItems,
+    FilterMode, Position, Quality,
+};
+
+/// The steps positions are stored in by the game, a 256th of a tile.
+const POSITION_STEPS: f64 = 256.0;
+
+/// Clear `field` if it holds a value `is_default` says the game would assume without it.
+fn drop_if<T>(field: &mut Option<T>, is_default: impl FnOnce(&T) -> bool) {
+    if field.as_ref().is_some_and(is_default) {
+        *field = None;
+    }
+}
+
+/// Clear `field` if it holds an empty list.
+fn drop_empty<T>(field: &mut Option<Vec<T>>) {
+    drop_if(field, Vec::is_empty);
+}
+
+impl Position {
+    /// Round to the precision the game keeps, so that floating point noise like
+    /// `0.49999999999999994` is written as `0.5`.
+    fn round(&mut self) {
+        self.x = (self.x * POSITION_STEPS).round() / POSITION_STEPS;
+        self.y = (self.y * POSITION_STEPS).round() / POSITION_STEPS;
+    }
+}
+
+impl Entity {
+    fn minimize(&mut self) {
+        self.position.round();
+        drop_if(&mut self.direction, |&direction| {
+            direction == Direction::North
+        });
+        drop_if(&mut self.mirror, |&mirror| !mirror);
+        drop_if(&mut self.quality, |quality| *quality == Quality::Normal);
+        drop_if(&mut self.connections, |connections| {
+            connections.first.is_none() && connections.second.is_none()
+        });
+        drop_if(&mut self.control_behavior, |behavior| {
+            *behavior == Default::default()
+        });
+        drop_empty(&mut self.neighbors);
+        drop_if(&mut self.items, |items| match items {
+            EntityItems::Legacy(request) => request.is_empty(),
+            EntityItems::InsertPlans(plans) => plans.is_empty(),
+        });
+        drop_if(&mut self.tags, |tags| tags.is_empty());
+
+        match &mut self.details {
+            EntityDetails::CraftingMachine {
+                recipe_quality,
+                auto_launch,
+                ..
+            } => {
+                drop_if(recipe_quality, |quality| *quality == Quality::Normal);
+                drop_if(auto_launch, |&auto_launch| !auto_launch);
+            }
+            EntityDetails::Inserter {
+                filters,
+                filter_mode,
+                use_filters,
+                drop_position,
+                pickup_position,
+                ..
+            } => {
+                drop_empty(filters);
+                drop_if(filter_mode, |mode| *mode == FilterMode::Whitelist);
+                drop_if(use_filters, |&use_filters| !use_filters);
+                for position in drop_position.iter_mut().chain(pickup_position) {
+                    position.round();
+                }
+            }
+            EntityDetails::Loader {
+                filters,
+                filter_mode,
+                ..
+            } => {
+                drop_empty(filters);
+                drop_if(filter_mode, |mode| *mode == FilterMode::Whitelist);
+            }
+            EntityDetails::Container {
+                request_from_buffers,
+                ..
+            } => drop_if(request_from_buffers, |&from_buffers| !from_buffers),
+            _ => {}
+        }
+    }
+}
+
+impl Blueprint {
+    /// Remove the fields holding what the game assumes when they are absent, like north facing
+    /// entities' directions and empty lists, and round positions to the 256th of a tile the
+    /// game stores them in. The blueprint is the same in game, but its string is shorter.
+    pub fn minimize(&mut self) {
+        for entity in self.entities.iter_mut().flatten() {
+            entity.minimize();
+        }
+        for tile in self.tiles.iter_mut().flatten() {
+            tile.position.round();
+        }
+        drop_empty(&mut self.entities);
+        drop_empty(&mut self.tiles);
+        drop_empty(&mut self.schedules);
+        drop_empty(&mut self.wires);
+        drop_empty(&mut self.parameters);
+        drop_if(&mut self.absolute_snapping, |&absolute| !absolute);
+    }
+}
+
+impl BlueprintBook {
+    /// [Minimize](Blueprint::minimize) every blueprint in the book.
+    pub fn minimize(&mut self) {
+        for entry in &mut self.blueprints {
+            entry.content.minimize();
+        }
+    }
+}
+
+impl BlueprintEnvelope {
+    /// [Minimize](Blueprint::minimize) the blueprints. Planners are left as they are.
+    pub fn minimize(&mut self) {
+        match self {
+            BlueprintEnvelope::Blueprint(blueprint) => blueprint.minimize(),
+            BlueprintEnvelope::BlueprintBook(book) => book.minimize(),
+            BlueprintEnvelope::DeconstructionPlanner(_) | BlueprintEnvelope::UpgradePlanner(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn defaults_are_left_out_and_positions_rounded() {
+        let mut blueprint: Blueprint = serde_json::from_value(json!({
+            "item": "blueprint",
+            "entities": [
+                {
+                    "entity_number": 1,
+                    "name": "filter-inserter",
+                    "position": {"x": 0.49999999999999994, "y": 0.5},
+                    "direction": 0,
+                    "mirror": false,
+                    "quality": "normal",
+                    "tags": {},
+                    "filters": [],
+                    "filter_mode": "whitelist",
+                    "use_filters": false,
+                },
+                {
+                    "entity_number": 2,
+                    "name": "filter-inserter",
+                    "position": {"x": 1.5, "y": 0.5},
+                    "direction": 4,
+                    "mirror": true,
+                    "quality": "rare",
+                    "filter_mode": "blacklist",
+                },
+            ],
+            "tiles": [],
+            "wires": [],
+            "absolute_snapping": false,
+            "version": 562949954076673u64,
+        }))
+        .unwrap();
+        blueprint.minimize();
+        assert_eq!(
+            serde_json::to_value(&blueprint).unwrap(),
+            json!({
+                "item": "blueprint",
+                "entities": [
+                    {
+                        "entity_number": 1,
+                        "name": "filter-inserter",
+                        "position": {"x": 0.5, "y": 0.5},
+                    },
+                    {
+                        "entity_number": 2,
+                        "name": "filter-inserter",
+                        "position": {"x": 1.5, "y": 0.5},
+                        "direction": 4,
+                        "mirror": true,
+                        "quality": "rare",
+                        "filter_mode": "blacklist",
+                    },
+                ],
+                "version": 562949954076673u64,
+            })
+        );
+    }
+}
@@ -0,0 +1,125 @@
+//! The mods it takes to import a blueprint.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::prototypes::PrototypeDb;
+
+use super::{Blueprint, EntityDetails, SignalType};
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// The prototypes a blueprint uses, sorted by the mod adding them.
+pub struct RequiredMods {
+    /// The names of the entities, items, recipes, and tiles used, by the mod adding them, with
+    /// "base" for the base game.
+    pub by_mod: BTreeMap<String, BTreeSet<String>>,
+    /// The names `db` doesn't know, which come from some other mod.
+    pub unknown: BTreeSet<String>,
+}
+
+impl RequiredMods {
+    /// The mods needed besides the base game, in order, not counting those of unknown names.
+    pub fn mods(&self) -> impl Iterator<Item = &str> {
+        self.by_mod
+            .keys()
+            .map(String::as_str)
+            .filter(|&name| name != "base")
+    }
+
+    /// Whether the base game alone has everything used.
+    pub fn is_vanilla(&self) -> bool {
+        self.mods().next().is_none() && self.unknown.is_empty()
+    }
+}
+
+impl Blueprint {
+    /// The mods adding the entities, tiles, recipes, and items the blueprint uses, from the names
+    /// of the prototypes in `db` and the mods it says add them. Names `db` knows but has no mod
+    /// for count as the base game.
+    pub fn required_mods(&self, db: &PrototypeDb) -> RequiredMods {
+        let mut names = BTreeSet::new();
+        for entity in self.entities.iter().flatten() {
+            names.insert(entity.name.as_str());
+            names.extend(entity.recipe());
+            for (item, _) in entity.items.iter().flat_map(|items| items.counts()) {
+                names.insert(item);
+            }
+            for filter in entity.filters() {
+                names.insert(&filter.name);
+            }
+            if let EntityDetails::Splitter {
+                filter: Some(filter),
+                ..
+            } = &entity.details
+            {
+                names.insert(filter.name());
+            }
+            let requests = entity
+                .request_filters()
+                .into_iter()
+                .flat_map(|r| r.filters());
+            for request in requests {
+                if matches!(request.signal_type, None | Some(SignalType::Item)) {
+                    names.insert(&request.name);
+                }
+            }
+        }
+        for tile in self.tiles.iter().flatten() {
+            names.insert(&tile.name);
+        }
+
+        let mut mods = RequiredMods::default();
+        for name in names {
+            let mod_name = match db.mod_of(name) {
+                Some(mod_name) => mod_name,
+                None if db.knows(name) => "base",
+                None => {
+                    mods.unknown.insert(name.to_owned());
+                    continue;
+                }
+            };
+            mods.by_mod
+                .entry(mod_name.to_owned())
+                .or_default()
+                .insert(name.to_owned());
+        }
+        mods
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blueprint::{BlueprintBuilder, EntityBuilder, EntityItems, ItemRequest};
+
+    #[test]
+    fn names_are_sorted_by_the_mod_adding_them() {
+        let db = PrototypeDb::vanilla();
+        let modules = ItemRequest::from([("quality-module".to_owned(), 1)]);
+        let blueprint = BlueprintBuilder::new()
+            .entity(
+                EntityBuilder::new("foundry")
+                    .at_tile(0, 0)
+                    .items(EntityItems::Legacy(modules)),
+            )
+            .entity(EntityBuilder::new("transport-belt").at_tile(6, 0))
+            .entity(EntityBuilder::new("modded-belt").at(8.5, 0.5))
+            .tile("concrete", 0, 6)
+            .build_with(db)
+            .unwrap();
+        let mods = blueprint.required_mods(db);
+
+        let names = |names: &[&str]| names.iter().map(|&n| n.to_owned()).collect();
+        assert_eq!(mods.by_mod["base"], names(&["concrete", "transport-belt"]));
+        assert_eq!(mods.by_mod["space-age"], names(&["foundry"]));
+        assert_eq!(mods.by_mod["quality"], names(&["quality-module"]));
+        assert_eq!(mods.unknown, names(&["modded-belt"]));
+        assert!(mods.mods().eq(["quality", "space-age"]));
+        assert!(!mods.is_vanilla());
+
+        let belt = BlueprintBuilder::new()
+            .entity(EntityBuilder::new("transport-belt").at_tile(0, 0))
+            .build_with(db)
+            .unwrap();
+        assert!(belt.required_mods(db).is_vanilla());
+    }
+}
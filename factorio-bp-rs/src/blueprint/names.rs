@@ -0,0 +1,376 @@
+//! Typed names of common vanilla entities.
+
+use core::{fmt, str::FromStr};
+
+use thiserror::Error;
+
+use super::Entity;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("{0:?} is not the name of a vanilla {1}")]
+/// A name that isn't one of the vanilla prototypes a typed name stands for.
+pub struct UnknownName(String, &'static str);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// The speed tiers of belts, which undergrounds, splitters, and loaders share.
+pub enum BeltTier {
+    /// transport-belt, the yellow tier.
+    Transport,
+    /// fast-transport-belt, the red tier.
+    Fast,
+    /// express-transport-belt, the blue tier.
+    Express,
+    /// turbo-transport-belt, the green tier (Space Age).
+    Turbo,
+}
+
+impl BeltTier {
+    /// Every tier, slowest first.
+    pub const ALL: [BeltTier; 4] = [
+        BeltTier::Transport,
+        BeltTier::Fast,
+        BeltTier::Express,
+        BeltTier::Turbo,
+    ];
+
+    /// The prototype name of the tier's belt.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BeltTier::Transport => "transport-belt",
+            BeltTier::Fast => "fast-transport-belt",
+            BeltTier::Express => "express-transport-belt",
+            BeltTier::Turbo => "turbo-transport-belt",
+        }
+    }
+
+    /// The prototype name of the tier's underground belt.
+    pub fn underground_belt(&self) -> &'static str {
+        match self {
+            BeltTier::Transport => "underground-belt",
+            BeltTier::Fast => "fast-underground-belt",
+            BeltTier::Express => "express-underground-belt",
+            BeltTier::Turbo => "turbo-underground-belt",
+        }
+    }
+
+    /// The prototype name of the tier's splitter.
+    pub fn splitter(&self) -> &'static str {
+        match self {
+            BeltTier::Transport => "splitter",
+            BeltTier::Fast => "fast-splitter",
+            BeltTier::Express => "express-splitter",
+            BeltTier::Turbo => "turbo-splitter",
+        }
+    }
+
+    /// The prototype name of the tier's loader.
+    pub fn loader(&self) -> &'static str {
+        match self {
+            BeltTier::Transport => "loader",
+            BeltTier::Fast => "fast-loader",
+            BeltTier::Express => "express-loader",
+            BeltTier::Turbo => "turbo-loader",
+        }
+    }
+
+    /// The tier of a belt, underground belt, splitter, or loader, by prototype name.
+    pub fn of(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|tier| {
+            [
+                tier.as_str(),
+                tier.underground_belt(),
+                tier.splitter(),
+                tier.loader(),
+            ]
+            .contains(&name)
+        })
+    }
+
+    /// The items per second a full belt of this tier carries, over both lanes.
+    pub fn items_per_second(&self) -> f64 {
+        match self {
+            BeltTier::Transport => 15.0,
+            BeltTier::Fast => 30.0,
+            BeltTier::Express => 45.0,
+            BeltTier::Turbo => 60.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The vanilla inserters.
+pub enum InserterKind {
+    /// burner-inserter
+    Burner,
+    /// inserter
+    Basic,
+    /// long-handed-inserter
+    LongHanded,
+    /// fast-inserter
+    Fast,
+    /// filter-inserter (before 2.0).
+    Filter,
+    /// stack-inserter. Before 2.0 this moves whole stacks, and was renamed the bulk inserter.
+    /// Since 2.0 it is the Space Age inserter stacking items on belts.
+    Stack,
+    /// stack-filter-inserter (before 2.0).
+    StackFilter,
+    /// bulk-inserter (2.0).
+    Bulk,
+}
+
+impl InserterKind {
+    /// Every inserter.
+    pub const ALL: [InserterKind; 8] = [
+        InserterKind::Burner,
+        InserterKind::Basic,
+        InserterKind::LongHanded,
+        InserterKind::Fast,
+        InserterKind::Filter,
+        InserterKind::Stack,
+        InserterKind::StackFilter,
+        InserterKind::Bulk,
+    ];
+
+    /// The prototype name.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InserterKind::Burner => "burner-inserter",
+            InserterKind::Basic => "inserter",
+            InserterKind::LongHanded => "long-handed-inserter",
+            InserterKind::Fast => "fast-inserter",
+            InserterKind::Filter => "filter-inserter",
+            InserterKind::Stack => "stack-inserter",
+            InserterKind::StackFilter => "stack-filter-inserter",
+            InserterKind::Bulk => "bulk-inserter",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// The tiers of assembling machine.
+pub enum AssemblerTier {
+    /// assembling-machine-1
+    One,
+    /// assembling-machine-2
+    Two,
+    /// assembling-machine-3
+    Three,
+}
+
+impl AssemblerTier {
+    /// Every tier, slowest first.
+    pub const ALL: [AssemblerTier; 3] =
+        [AssemblerTier::One, AssemblerTier::Two, AssemblerTier::Three];
+
+    /// The prototype name.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AssemblerTier::One => "assembling-machine-1",
+            AssemblerTier::Two => "assembling-machine-2",
+            AssemblerTier::Three => "assembling-machine-3",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The vanilla furnaces.
+pub enum FurnaceKind {
+    /// stone-furnace
+    Stone,
+    /// steel-furnace
+    Steel,
+    /// electric-furnace
+    Electric,
+}
+
+impl FurnaceKind {
+    /// Every furnace.
+    pub const ALL: [FurnaceKind; 3] = [
+        FurnaceKind::Stone,
+        FurnaceKind::Steel,
+        FurnaceKind::Electric,
+    ];
+
+    /// The prototype name.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FurnaceKind::Stone => "stone-furnace",
+            FurnaceKind::Steel => "steel-furnace",
+            FurnaceKind::Electric => "electric-furnace",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The vanilla electric poles.
+pub enum ElectricPoleKind {
+    /// small-electric-pole
+    Small,
+    /// medium-electric-pole
+    Medium,
+    /// big-electric-pole
+    Big,
+    /// substation
+    Substation,
+}
+
+impl ElectricPoleKind {
+    /// Every electric pole.
+    pub const ALL: [ElectricPoleKind; 4] = [
+        ElectricPoleKind::Small,
+        ElectricPoleKind::Medium,
+        ElectricPoleKind::Big,
+        ElectricPoleKind::Substation,
+    ];
+
+    /// The prototype name.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ElectricPoleKind::Small => "small-electric-pole",
+            ElectricPoleKind::Medium => "medium-electric-pole",
+            ElectricPoleKind::Big => "big-electric-pole",
+            ElectricPoleKind::Substation => "substation",
+        }
+    }
+}
+
+impl FromStr for BeltTier {
+    type Err = UnknownName;
+
+    /// Parse the prototype name of a belt. See [BeltTier::of] for undergrounds, splitters, and
+    /// loaders too.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tier = Self::ALL.into_iter().find(|tier| tier.as_str() == s);
+        tier.ok_or_else(|| UnknownName(s.to_owned(), "belt"))
+    }
+}
+
+impl FromStr for InserterKind {
+    type Err = UnknownName;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let kind = Self::ALL.into_iter().find(|kind| kind.as_str() == s);
+        kind.ok_or_else(|| UnknownName(s.to_owned(), "inserter"))
+    }
+}
+
+impl FromStr for AssemblerTier {
+    type Err = UnknownName;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tier = Self::ALL.into_iter().find(|tier| tier.as_str() == s);
+        tier.ok_or_else(|| UnknownName(s.to_owned(), "assembling machine"))
+    }
+}
+
+impl FromStr for FurnaceKind {
+    type Err = UnknownName;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let kind = Self::ALL.into_iter().find(|kind| kind.as_str() == s);
+        kind.ok_or_else(|| UnknownName(s.to_owned(), "furnace"))
+    }
+}
+
+impl FromStr for ElectricPoleKind {
+    type Err = UnknownName;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let kind = Self::ALL.into_iter().find(|kind| kind.as_str() == s);
+        kind.ok_or_else(|| UnknownName(s.to_owned(), "electric pole"))
+    }
+}
+
+impl fmt::Display for BeltTier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl fmt::Display for InserterKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl fmt::Display for AssemblerTier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl fmt::Display for FurnaceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl fmt::Display for ElectricPoleKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Entity {
+    /// The belt tier of this entity, if it is a vanilla belt, underground belt, splitter, or
+    /// loader.
+    pub fn as_belt(&self) -> Option<BeltTier> {
+        BeltTier::of(&self.name)
+    }
+
+    /// Which inserter this entity is, if it is a vanilla one.
+    pub fn as_inserter(&self) -> Option<InserterKind> {
+        self.name.parse().ok()
+    }
+
+    /// The tier of this entity, if it is an assembling machine.
+    pub fn as_assembler(&self) -> Option<AssemblerTier> {
+        self.name.parse().ok()
+    }
+
+    /// Which furnace this entity is, if it is a vanilla one.
+    pub fn as_furnace(&self) -> Option<FurnaceKind> {
+        self.name.parse().ok()
+    }
+
+    /// Which electric pole this entity is, if it is a vanilla one.
+    pub fn as_electric_pole(&self) -> Option<ElectricPoleKind> {
+        self.name.parse().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        blueprint::{BlueprintBuilder, EntityBuilder},
+        prototypes::PrototypeDb,
+    };
+
+    #[test]
+    fn names_parse_back_to_their_types() {
+        for tier in BeltTier::ALL {
+            assert_eq!(tier.as_str().parse(), Ok(tier));
+            assert_eq!(BeltTier::of(tier.splitter()), Some(tier));
+        }
+        assert_eq!(BeltTier::of("fast-underground-belt"), Some(BeltTier::Fast));
+        assert_eq!(
+            "fast-splitter".parse::<BeltTier>(),
+            Err(UnknownName("fast-splitter".to_owned(), "belt"))
+        );
+        assert_eq!(InserterKind::Fast.to_string(), "fast-inserter");
+
+        let blueprint = BlueprintBuilder::new()
+            .entity(EntityBuilder::new("express-splitter").at(0.5, 0.0))
+            .entity(EntityBuilder::new("medium-electric-pole").at(3.5, 0.5))
+            .build_with(PrototypeDb::vanilla())
+            .unwrap();
+        let [splitter, pole] = blueprint.entities.as_deref().unwrap() else {
+            panic!("expected two entities");
+        };
+        assert_eq!(splitter.as_belt(), Some(BeltTier::Express));
+        assert_eq!(splitter.as_inserter(), None);
+        assert_eq!(pole.as_electric_pole(), Some(ElectricPoleKind::Medium));
+    }
+}
@@ -0,0 +1,156 @@
+//! Moving blueprints to the origin and entities onto the grid.
+
+use core::num::NonZeroUsize;
+
+use crate::{prototypes::PrototypeDb, rail::uses_rail_grid};
+
+use super::{Blueprint, EntityDetails, Position};
+
+/// How far off the grid an entity can be before it is reported, rather than put back quietly.
+/// Anything closer is taken to be floating point noise.
+const GRID_TOLERANCE: f64 = 1.0 / 256.0;
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+/// The point [Blueprint::normalize_positions_with] moves to the origin.
+pub enum Anchor {
+    #[default]
+    /// The average position of the entities.
+    CenterOfMass,
+    /// The given position.
+    Position(Position),
+}
+
+/// `coordinate` moved to the nearest tile centre for entities of odd `size`, or the nearest
+/// tile corner for even sizes.
+fn snap(coordinate: f64, size: u32) -> f64 {
+    if size.is_multiple_of(2) {
+        coordinate.round()
+    } else {
+        coordinate.floor() + 0.5
+    }
+}
+
+impl Blueprint {
+    /// [Normalize](Blueprint::normalize_positions_with) the positions about the entities' centre
+    /// of mass, with entity sizes from the [global](PrototypeDb::global) prototypes.
+    pub fn normalize_positions(&mut self) -> Vec<NonZeroUsize> {
+        self.normalize_positions_with(&Anchor::CenterOfMass, PrototypeDb::global())
+    }
+
+    /// Move entities onto the grid their size puts them on, centred on tiles if they are an odd
+    /// number of tiles across and on tile corners if even, then move the blueprint by whole tiles
+    /// so that `anchor` is as close to the origin as it can be. Blueprints with rails, rail
+    /// signals, or train stops are moved by an even number of tiles, to keep them on the rail
+    /// grid.
+    ///
+    /// Returns the entities that were off the grid by more than floating point noise, which the
+    /// game would not build where the blueprint puts them. Vehicles and entities `db` doesn't
+    /// know are left where they are. Blueprints snapping to the absolute grid aren't moved, as
+    /// that would change where in the world they are built.
+    pub fn normalize_positions_with(
+        &mut self,
+        anchor: &Anchor,
+        db: &PrototypeDb,
+    ) -> Vec<NonZeroUsize> {
+        let mut off_grid = Vec::new();
+        for entity in self.entities.iter_mut().flatten() {
+            if let EntityDetails::Vehicle { .. } = entity.details {
+                continue;
+            }
+            let Some(prototype) = entity.prototype(db) else {
+                continue;
+            };
+            let (width, height) = prototype.tile_size(entity.direction.unwrap_or_default());
+            let position = &mut entity.position;
+            let snapped = Position {
+                x: snap(position.x, width),
+                y: snap(position.y, height),
+            };
+            if (snapped.x - position.x).abs() > GRID_TOLERANCE
+                || (snapped.y - position.y).abs() > GRID_TOLERANCE
+            {
+                off_grid.push(entity.entity_number);
+            }
+            *position = snapped;
+        }
+        for tile in self.tiles.iter_mut().flatten() {
+            tile.position.x = tile.position.x.round();
+            tile.position.y = tile.position.y.round();
+        }
+
+        if self.absolute_snapping == Some(true) {
+            return off_grid;
+        }
+        let center = match anchor {
+            Anchor::CenterOfMass => {
+                let positions: Vec<_> = self
+                    .entities
+                    .iter()
+                    .flatten()
+                    .map(|entity| &entity.position)
+                    .collect();
+                if positions.is_empty() {
+                    return off_grid;
+                }
+                let count = positions.len() as f64;
+                Position {
+                    x: positions.iter().map(|position| position.x).sum::<f64>() / count,
+                    y: positions.iter().map(|position| position.y).sum::<f64>() / count,
+                }
+            }
+            Anchor::Position(position) => position.clone(),
+        };
+        // By whole tiles to keep everything on the grid, and by whole rail cells if there are
+        // rails
+        let step = if uses_rail_grid(self) { 2.0 } else { 1.0 };
+        let shift = |coordinate: f64| -(coordinate / step).round() * step;
+        self.translate(shift(center.x), shift(center.y));
+
+        off_grid
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bp;
+
+    #[test]
+    fn rails_stay_on_the_rail_grid() {
+        let db = PrototypeDb::vanilla();
+        let mut blueprint = bp! {
+            "straight-rail" @ (4, 2);
+            "rail-signal" @ (6, 2);
+        }
+        .build_with(db)
+        .unwrap();
+        blueprint.normalize_positions_with(&Anchor::Position(Position { x: 5.0, y: 3.0 }), db);
+
+        // A move of (-5, -3) would be closest, but is odd, so the rail's centre stays odd
+        let rail = &blueprint.entities.as_ref().unwrap()[0];
+        assert_eq!(rail.position, Position { x: -1.0, y: -1.0 });
+    }
+
+    #[test]
+    fn other_blueprints_move_by_single_tiles() {
+        let db = PrototypeDb::vanilla();
+        let mut blueprint = bp! { "iron-chest" @ (4, 2); "iron-chest" @ (7, 2) }
+            .build_with(db)
+            .unwrap();
+        assert_eq!(
+            blueprint.normalize_positions_with(&Anchor::CenterOfMass, db),
+            []
+        );
+
+        let positions: Vec<_> = blueprint
+            .entities
+            .iter()
+            .flatten()
+            .map(|entity| entity.position.clone())
+            .collect();
+        assert_eq!(
+            positions,
+            [Position { x: -1.5, y: -0.5 }, Position { x: 1.5, y: -0.5 }]
+        );
+    }
+}
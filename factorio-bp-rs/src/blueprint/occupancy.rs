@@ -0,0 +1,162 @@
+//! Which entity covers each tile of a blueprint.
+
+use core::num::NonZeroUsize;
+
+use crate::{prototypes::PrototypeDb, Size};
+
+use super::{Blueprint, Entity};
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+/// A grid of the tiles a blueprint's entities cover, with the entity covering each. Tiles are
+/// given by the coordinates of their top left corners.
+pub struct OccupancyGrid {
+    /// The top left tile of the grid.
+    origin: (i64, i64),
+    size: Size,
+    /// The entity numbers covering each tile, row by row.
+    cells: Vec<Option<NonZeroUsize>>,
+}
+
+/// The tiles `entity` covers, by its footprint in `db`, or the tile it is centred on if `db`
+/// doesn't know it. Footprints are rounded to whole tiles, so entities off the grid count as
+/// covering the tiles they mostly cover.
+pub(super) fn covered_tiles(entity: &Entity, db: &PrototypeDb) -> Vec<(i64, i64)> {
+    match entity.footprint(db) {
+        Some(bounds) => {
+            let columns = bounds.min_x.round() as i64..bounds.max_x.round() as i64;
+            let rows = bounds.min_y.round() as i64..bounds.max_y.round() as i64;
+            rows.flat_map(|y| columns.clone().map(move |x| (x, y)))
+                .collect()
+        }
+        None => vec![(
+            entity.position.x.floor() as i64,
+            entity.position.y.floor() as i64,
+        )],
+    }
+}
+
+impl OccupancyGrid {
+    /// The grid of `blueprint`'s entities, with their footprints from `db`. Where entities
+    /// overlap, the tile goes to the one first in the blueprint.
+    pub fn new(blueprint: &Blueprint, db: &PrototypeDb) -> Self {
+        let covered: Vec<_> = blueprint
+            .entities
+            .iter()
+            .flatten()
+            .map(|entity| (entity.entity_number, covered_tiles(entity, db)))
+            .collect();
+        let tiles = || covered.iter().flat_map(|(_, tiles)| tiles);
+        let (Some(min_x), Some(min_y), Some(max_x), Some(max_y)) = (
+            tiles().map(|&(x, _)| x).min(),
+            tiles().map(|&(_, y)| y).min(),
+            tiles().map(|&(x, _)| x).max(),
+            tiles().map(|&(_, y)| y).max(),
+        ) else {
+            return Self::default();
+        };
+
+        let mut grid = Self {
+            origin: (min_x, min_y),
+            size: Size {
+                w: (max_x - min_x + 1) as usize,
+                h: (max_y - min_y + 1) as usize,
+            },
+            cells: Vec::new(),
+        };
+        grid.cells = vec![None; grid.size.w * grid.size.h];
+        for (number, tiles) in &covered {
+            for &(x, y) in tiles {
+                if let Some(index) = grid.index(x, y) {
+                    grid.cells[index].get_or_insert(*number);
+                }
+            }
+        }
+        grid
+    }
+
+    /// The index into `cells` of the tile at `(x, y)`, if it is in the grid.
+    fn index(&self, x: i64, y: i64) -> Option<usize> {
+        let column = usize::try_from(x - self.origin.0).ok()?;
+        let row = usize::try_from(y - self.origin.1).ok()?;
+        (column < self.size.w && row < self.size.h).then_some(row * self.size.w + column)
+    }
+
+    /// The top left tile of the grid.
+    pub fn origin(&self) -> (i64, i64) {
+        self.origin
+    }
+
+    /// The number of tiles the grid spans in each direction. Empty for blueprints without entities.
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    /// The number of the entity covering the tile at `(x, y)`, or `None` if the tile is empty.
+    pub fn get(&self, x: i64, y: i64) -> Option<NonZeroUsize> {
+        self.cells[self.index(x, y)?]
+    }
+
+    /// Whether no entity covers the tile at `(x, y)`.
+    pub fn is_free(&self, x: i64, y: i64) -> bool {
+        self.get(x, y).is_none()
+    }
+
+    /// Every covered tile and the entity covering it, row by row from the top left.
+    pub fn occupied(&self) -> impl Iterator<Item = ((i64, i64), NonZeroUsize)> + '_ {
+        let (width, (left, top)) = (self.size.w, self.origin);
+        self.cells.iter().enumerate().filter_map(move |(i, cell)| {
+            let tile = (left + (i % width) as i64, top + (i / width) as i64);
+            Some((tile, (*cell)?))
+        })
+    }
+}
+
+impl Blueprint {
+    /// The [OccupancyGrid] of the blueprint's entities, with their footprints, turned the way
+    /// the entities face, from `db`.
+    pub fn occupancy_grid(&self, db: &PrototypeDb) -> OccupancyGrid {
+        OccupancyGrid::new(self, db)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        blueprint::{BlueprintBuilder, BoundingBox, Direction, EntityBuilder},
+        prototypes::EntityPrototype,
+    };
+
+    #[test]
+    fn tiles_go_to_the_first_entity_covering_them() {
+        let mut db = PrototypeDb::new();
+        db.insert_entity(EntityPrototype::new(
+            "boiler",
+            BoundingBox::new(-1.29, -0.79, 1.29, 0.79),
+        ));
+        let blueprint = BlueprintBuilder::new()
+            .entity(
+                EntityBuilder::new("boiler")
+                    .at(1.0, 1.5)
+                    .direction(Direction::East),
+            )
+            .entity(EntityBuilder::new("modded-box").at(0.5, 2.5))
+            .entity(EntityBuilder::new("modded-box").at(3.5, 0.5))
+            .build_with(&db)
+            .unwrap();
+        let grid = blueprint.occupancy_grid(&db);
+
+        assert_eq!(grid.origin(), (0, 0));
+        assert_eq!(grid.size(), Size { w: 4, h: 3 });
+        let number = |n| NonZeroUsize::new(n);
+        assert_eq!(grid.get(1, 2), number(1));
+        assert_eq!(grid.get(0, 2), number(1));
+        assert_eq!(grid.get(3, 0), number(3));
+        assert!(grid.is_free(2, 0));
+        assert!(grid.is_free(10, 10));
+        assert_eq!(grid.occupied().count(), 7);
+
+        let empty = BlueprintBuilder::new().build().unwrap();
+        assert_eq!(empty.occupancy_grid(&db), OccupancyGrid::default());
+    }
+}
@@ -0,0 +1,294 @@
+//! Parameterised blueprints (2.0), whose placeholder signals and numbers are filled in when the blueprint is placed.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+use super::{Blueprint, Comparator, Quality};
+
+/// Keys of the settings the game parametrises numbers in: combinator constants, signal counts,
+/// and filter and request counts. Numbers anywhere else, like entity numbers and wire ends, are
+/// never substituted.
+const PARAMETRISED_NUMBER_KEYS: [&str; 6] = [
+    "constant",
+    "first_constant",
+    "second_constant",
+    "index_constant",
+    "count",
+    "max_count",
+];
+
+/// Keys whose values belong to mods, so nothing in them is substituted.
+const UNPARAMETRISED_KEYS: [&str; 1] = ["tags"];
+
+/// Keys whose values hold item counts that aren't settings, like the insert plans of modules, so
+/// only IDs are substituted in them.
+const UNPARAMETRISED_NUMBER_KEYS: [&str; 1] = ["items"];
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+/// A single parameter of a parameterised blueprint.
+pub enum BlueprintParameter {
+    /// A placeholder signal, item, or recipe (e.g. "parameter-0").
+    Id(IdParameter),
+    /// A number used in entity settings (e.g. a combinator constant).
+    Number(NumberParameter),
+}
+
+impl BlueprintParameter {
+    /// The name of the parameter set by the user.
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            BlueprintParameter::Id(parameter) => parameter.name.as_deref(),
+            BlueprintParameter::Number(parameter) => parameter.name.as_deref(),
+        }
+    }
+
+    /// Whether the user has unticked this parameter, meaning it is left as-is when the blueprint is placed.
+    pub fn is_parametrised(&self) -> bool {
+        let not_parametrised = match self {
+            BlueprintParameter::Id(parameter) => parameter.not_parametrised,
+            BlueprintParameter::Number(parameter) => parameter.not_parametrised,
+        };
+        !not_parametrised.unwrap_or(false)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// A parameter that stands in for a prototype ID.
+pub struct IdParameter {
+    /// The placeholder ID used throughout the blueprint (e.g. "parameter-0").
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The name of the parameter set by the user.
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "not-parametrised")]
+    /// Whether the parameter is left as-is when the blueprint is placed.
+    pub not_parametrised: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "quality-condition")]
+    /// The quality the chosen value must have.
+    pub quality_condition: Option<QualityCondition>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "ingredient-of")]
+    /// The ID of another parameter whose recipe this parameter is an ingredient of.
+    pub ingredient_of: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// A parameter that stands in for a number.
+pub struct NumberParameter {
+    /// The number used throughout the blueprint, as a string (e.g. "100").
+    pub number: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The name of the parameter set by the user.
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "not-parametrised")]
+    /// Whether the parameter is left as-is when the blueprint is placed.
+    pub not_parametrised: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The variable name other parameters' formulas use to refer to this parameter.
+    pub variable: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// A formula computing this parameter from other parameters.
+    pub formula: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Whether the value is computed by `formula` rather than asked for.
+    pub dependent: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// A restriction on the quality of the value chosen for an ID parameter.
+pub struct QualityCondition {
+    /// The quality being compared against.
+    pub quality: Quality,
+    /// How the chosen quality is compared against `quality`.
+    pub comparator: Comparator,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// A concrete value for a blueprint parameter.
+pub enum ParameterValue {
+    /// The prototype name replacing an ID parameter.
+    Id(String),
+    /// The number replacing a number parameter.
+    Number(i64),
+}
+
+#[derive(Debug, Error)]
+/// An error encountered while filling in a parameterised blueprint.
+pub enum ParameterError {
+    /// The number of values does not match the number of parameters.
+    #[error("expected {expected} parameter values, got {given}")]
+    WrongCount {
+        /// How many parameters the blueprint has.
+        expected: usize,
+        /// How many values were given.
+        given: usize,
+    },
+    /// A value was given for a parameter of the other kind.
+    #[error("parameter {0} was given a value of the wrong kind")]
+    WrongKind(usize),
+    /// A number parameter does not hold a number.
+    #[error("parameter {0} has an invalid number")]
+    InvalidNumber(usize),
+    /// The blueprint could not be converted to or from JSON.
+    #[error("blueprint could not be rewritten: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// The replacements to make while instantiating a blueprint.
+struct Substitutions {
+    ids: Vec<(String, String)>,
+    numbers: Vec<(i64, i64)>,
+}
+
+impl Substitutions {
+    /// Substitute in `value`, found under `key`, and numbers only if `numbers` is set.
+    fn apply(&self, value: &mut Value, key: Option<&str>, numbers: bool) {
+        match value {
+            Value::String(s) => {
+                if let Some((_, replacement)) = self.ids.iter().find(|(id, _)| id == s) {
+                    *s = replacement.clone();
+                }
+            }
+            Value::Number(n) => {
+                if !numbers || !key.is_some_and(|key| PARAMETRISED_NUMBER_KEYS.contains(&key)) {
+                    return;
+                }
+                if let Some((_, replacement)) = self
+                    .numbers
+                    .iter()
+                    .find(|(number, _)| n.as_i64() == Some(*number))
+                {
+                    *n = (*replacement).into();
+                }
+            }
+            Value::Array(values) => values.iter_mut().for_each(|v| self.apply(v, key, numbers)),
+            Value::Object(map) => self.apply_object(map, numbers),
+            Value::Bool(_) | Value::Null => (),
+        }
+    }
+
+    fn apply_object(&self, map: &mut Map<String, Value>, numbers: bool) {
+        for (key, value) in map.iter_mut() {
+            if !UNPARAMETRISED_KEYS.contains(&key.as_str()) {
+                let numbers = numbers && !UNPARAMETRISED_NUMBER_KEYS.contains(&key.as_str());
+                self.apply(value, Some(key), numbers);
+            }
+        }
+    }
+}
+
+impl Blueprint {
+    /// Whether this blueprint has any parameters.
+    pub fn is_parameterised(&self) -> bool {
+        self.parameters
+            .as_ref()
+            .is_some_and(|parameters| !parameters.is_empty())
+    }
+
+    /// Produce a regular blueprint by substituting a concrete value for every parameter, in order.
+    ///
+    /// Values given for parameters that are not parametrised are ignored. Dependent parameters are
+    /// not evaluated from their formulas; their value must be given like any other.
+    ///
+    /// Numbers are only substituted where the game parametrises them: combinator constants and
+    /// signal, filter, and request counts. IDs are substituted everywhere but entity tags.
+    pub fn instantiate(&self, values: &[ParameterValue]) -> Result<Blueprint, ParameterError> {
+        let parameters = self.parameters.as_deref().unwrap_or_default();
+        if parameters.len() != values.len() {
+            return Err(ParameterError::WrongCount {
+                expected: parameters.len(),
+                given: values.len(),
+            });
+        }
+
+        let mut substitutions = Substitutions {
+            ids: Vec::new(),
+            numbers: Vec::new(),
+        };
+        for (i, (parameter, value)) in parameters.iter().zip(values).enumerate() {
+            match (parameter, value) {
+                (parameter, _) if !parameter.is_parametrised() => (),
+                (BlueprintParameter::Id(parameter), ParameterValue::Id(name)) => {
+                    substitutions.ids.push((parameter.id.clone(), name.clone()));
+                }
+                (BlueprintParameter::Number(parameter), ParameterValue::Number(number)) => {
+                    let original = parameter
+                        .number
+                        .parse()
+                        .map_err(|_| ParameterError::InvalidNumber(i))?;
+                    substitutions.numbers.push((original, *number));
+                }
+                _ => return Err(ParameterError::WrongKind(i)),
+            }
+        }
+
+        let mut instance = self.clone();
+        instance.parameters = None;
+
+        let mut json = serde_json::to_value(&instance)?;
+        substitutions.apply(&mut json, None, true);
+
+        Ok(serde_json::from_value(json)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn only_settings_take_number_parameters() {
+        // The parameter's number is also the entity number of the second combinator, which both
+        // kinds of wire and the tags refer to
+        let blueprint: Blueprint = serde_json::from_value(json!({
+            "item": "blueprint",
+            "version": 562949954076673u64,
+            "entities": [
+                {
+                    "entity_number": 1,
+                    "name": "constant-combinator",
+                    "position": {"x": 0.5, "y": 0.5},
+                    "control_behavior": {"filters": [
+                        {"signal": {"type": "virtual", "name": "signal-A"}, "count": 2, "index": 1}
+                    ]},
+                    "connections": {"1": {"red": [{"entity_id": 2, "circuit_id": 2}]}},
+                    "tags": {"copies": 2}
+                },
+                {
+                    "entity_number": 2,
+                    "name": "small-electric-pole",
+                    "position": {"x": 1.5, "y": 0.5},
+                    "neighbours": [1]
+                },
+                {
+                    "entity_number": 3,
+                    "name": "small-electric-pole",
+                    "position": {"x": 2.5, "y": 0.5},
+                    "neighbours": [2]
+                }
+            ],
+            "wires": [[1, 1, 2, 1]],
+            "parameters": [{"type": "number", "number": "2"}]
+        }))
+        .unwrap();
+        let instance = blueprint.instantiate(&[ParameterValue::Number(7)]).unwrap();
+
+        let json = serde_json::to_value(&instance).unwrap();
+        let combinator = &json["entities"][0];
+        assert_eq!(combinator["control_behavior"]["filters"][0]["count"], 7);
+        assert_eq!(combinator["connections"]["1"]["red"][0]["entity_id"], 2);
+        assert_eq!(combinator["connections"]["1"]["red"][0]["circuit_id"], 2);
+        assert_eq!(combinator["tags"]["copies"], 2);
+        assert_eq!(json["entities"][1]["entity_number"], 2);
+        assert_eq!(json["entities"][2]["neighbours"][0], 2);
+        assert_eq!(json["wires"][0][2], 2);
+        assert_eq!(json.get("parameters"), None);
+    }
+}
@@ -0,0 +1,229 @@
+//! Deconstruction and upgrade planners, which can be exported as blueprint strings alongside blueprints.
+
+use core::num::NonZeroUsize;
+
+use serde::{Deserialize, Serialize};
+use serde_repr::*;
+
+use super::{Icon, UnknownFields, Version};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// A deconstruction planner, used to mark entities and tiles for removal.
+pub struct DeconstructionPlanner {
+    /// The name of the item that was saved ("deconstruction-planner" in vanilla).
+    pub item: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The name of the deconstruction planner set by the user.
+    pub label: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The filters and other settings of the planner.
+    pub settings: Option<DeconstructionSettings>,
+    /// The map version of the map the planner was created in.
+    pub version: Version,
+    #[cfg_attr(feature = "preserve-unknown", serde(flatten, skip_deserializing))]
+    #[cfg_attr(not(feature = "preserve-unknown"), serde(skip))]
+    /// Fields this crate doesn't know about, kept so they survive a round trip.
+    /// Only filled with the `preserve-unknown` feature.
+    pub extra: UnknownFields,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+/// The settings of a deconstruction planner.
+pub struct DeconstructionSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The description of the planner set by the user.
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    /// The icons of the planner set by the user.
+    pub icons: Vec<Icon>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Whether the entity filters are a whitelist or a blacklist. Whitelist if absent.
+    pub entity_filter_mode: Option<PlannerFilterMode>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    /// The entities the planner is filtered to.
+    pub entity_filters: Vec<DeconstructionFilter>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Whether the planner only selects trees and rocks.
+    pub trees_and_rocks_only: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Whether the tile filters are a whitelist or a blacklist. Whitelist if absent.
+    pub tile_filter_mode: Option<PlannerFilterMode>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    /// The tiles the planner is filtered to.
+    pub tile_filters: Vec<DeconstructionFilter>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// When tiles are selected for deconstruction. Normal if absent.
+    pub tile_selection_mode: Option<TileSelectionMode>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// An entity or tile filter in a deconstruction planner.
+pub struct DeconstructionFilter {
+    /// Name of the entity or tile prototype this filter is set to.
+    pub name: String,
+    /// Index of the filter.
+    pub index: NonZeroUsize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+/// The mode of the entity or tile filters in a deconstruction planner.
+pub enum PlannerFilterMode {
+    /// Only select the filtered entities or tiles.
+    Whitelist = 0,
+    /// Select everything except the filtered entities or tiles.
+    Blacklist = 1,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+/// When a deconstruction planner selects tiles.
+pub enum TileSelectionMode {
+    /// Only select tiles if no entities are selected.
+    Normal = 0,
+    /// Always select tiles.
+    Always = 1,
+    /// Never select tiles.
+    Never = 2,
+    /// Only select tiles, never entities.
+    Only = 3,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// An upgrade planner, used to replace entities with other entities.
+pub struct UpgradePlanner {
+    /// The name of the item that was saved ("upgrade-planner" in vanilla).
+    pub item: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The name of the upgrade planner set by the user.
+    pub label: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The mappers and other settings of the planner.
+    pub settings: Option<UpgradeSettings>,
+    /// The map version of the map the planner was created in.
+    pub version: Version,
+    #[cfg_attr(feature = "preserve-unknown", serde(flatten, skip_deserializing))]
+    #[cfg_attr(not(feature = "preserve-unknown"), serde(skip))]
+    /// Fields this crate doesn't know about, kept so they survive a round trip.
+    /// Only filled with the `preserve-unknown` feature.
+    pub extra: UnknownFields,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+/// The settings of an upgrade planner.
+pub struct UpgradeSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The description of the planner set by the user.
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    /// The icons of the planner set by the user.
+    pub icons: Vec<Icon>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    /// The replacements the planner performs.
+    pub mappers: Vec<UpgradeMapper>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// A single replacement rule in an upgrade planner.
+pub struct UpgradeMapper {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The entity or item being replaced.
+    pub from: Option<UpgradeTarget>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The entity or item it is replaced with.
+    pub to: Option<UpgradeTarget>,
+    /// Index of the mapper, starting at 0.
+    pub index: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// One side of an upgrade planner mapping.
+pub struct UpgradeTarget {
+    #[serde(rename = "type")]
+    /// Whether the mapping refers to an entity or a module item.
+    pub target_type: UpgradeTargetType,
+    /// Name of the entity or item prototype.
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// The kind of prototype an upgrade planner mapping refers to.
+pub enum UpgradeTargetType {
+    /// An entity placed in the world (e.g. transport-belt).
+    Entity,
+    /// An item inserted into entities (e.g. a module).
+    Item,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::blueprint::{decode_string, encode_json, BlueprintEnvelope};
+
+    #[test]
+    fn deconstruction_planners_decode_and_encode_back() {
+        let json = json!({
+            "deconstruction_planner": {
+                "item": "deconstruction-planner",
+                "label": "Trees",
+                "settings": {
+                    "entity_filter_mode": 1,
+                    "entity_filters": [{"name": "stone-furnace", "index": 2}],
+                    "trees_and_rocks_only": true,
+                    "tile_selection_mode": 3,
+                },
+                "version": 281479276920832u64,
+            }
+        });
+        let envelope = decode_string(&encode_json(&json.to_string()).unwrap()).unwrap();
+        let BlueprintEnvelope::DeconstructionPlanner(planner) = &envelope else {
+            panic!("expected a deconstruction planner, found {envelope:?}");
+        };
+        let settings = planner.settings.as_ref().unwrap();
+        assert_eq!(
+            settings.entity_filter_mode,
+            Some(PlannerFilterMode::Blacklist)
+        );
+        assert_eq!(settings.entity_filters[0].name, "stone-furnace");
+        assert_eq!(settings.tile_selection_mode, Some(TileSelectionMode::Only));
+        assert_eq!(serde_json::to_value(&envelope).unwrap(), json);
+    }
+
+    #[test]
+    fn upgrade_planners_decode_and_encode_back() {
+        let target = |target_type: &str, name: &str| json!({"type": target_type, "name": name});
+        let json = json!({
+            "upgrade_planner": {
+                "item": "upgrade-planner",
+                "settings": {
+                    "mappers": [
+                        {
+                            "from": target("entity", "transport-belt"),
+                            "to": target("entity", "fast-transport-belt"),
+                            "index": 0,
+                        },
+                        {"from": target("item", "speed-module"), "index": 1},
+                    ],
+                },
+                "version": 281479276920832u64,
+            }
+        });
+        let envelope = decode_string(&encode_json(&json.to_string()).unwrap()).unwrap();
+        let BlueprintEnvelope::UpgradePlanner(planner) = &envelope else {
+            panic!("expected an upgrade planner, found {envelope:?}");
+        };
+        let mappers = &planner.settings.as_ref().unwrap().mappers;
+        assert_eq!(
+            mappers[0].to,
+            Some(UpgradeTarget {
+                target_type: UpgradeTargetType::Entity,
+                name: "fast-transport-belt".to_owned(),
+            })
+        );
+        assert_eq!(mappers[1].to, None);
+        assert_eq!(serde_json::to_value(&envelope).unwrap(), json);
+    }
+}
@@ -0,0 +1,226 @@
+//! Removing the text and colours players put in blueprints, before sharing them.
+
+use std::collections::BTreeMap;
+
+use super::{Blueprint, BlueprintBook, BlueprintEnvelope, EntityDetails, ScheduleData};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// How [Blueprint::sanitize_with] treats what it removes.
+pub struct SanitizeOptions {
+    /// Put placeholders in place of labels, station names, and alert messages rather than
+    /// removing them. Stations are numbered in the order of the blueprint, "Station 1" and so on.
+    pub placeholders: bool,
+}
+
+impl SanitizeOptions {
+    /// `text` replaced with `placeholder`, or removed.
+    fn replace(&self, text: &mut Option<String>, placeholder: impl FnOnce() -> String) {
+        if text.is_some() {
+            *text = self.placeholders.then(placeholder);
+        }
+    }
+}
+
+impl Blueprint {
+    /// Remove the label, description, station names, speaker alert messages, and custom colours,
+    /// which can identify who made the blueprint. Icons, entity settings, and mod tags are kept.
+    pub fn sanitize(&mut self) {
+        self.sanitize_with(&SanitizeOptions::default());
+    }
+
+    /// [Blueprint::sanitize], with placeholders if `options` asks for them.
+    ///
+    /// The stations of train schedules are renamed along with the train stops, so that trains
+    /// still go to the same stops if there are placeholders. Without them, they go nowhere.
+    pub fn sanitize_with(&mut self, options: &SanitizeOptions) {
+        options.replace(&mut self.label, || "Blueprint".to_owned());
+        self.label_color = None;
+        self.description = None;
+
+        let mut stations = BTreeMap::new();
+        let mut station_placeholder = |name: &str| -> String {
+            let count = stations.len();
+            let placeholder = stations
+                .entry(name.to_owned())
+                .or_insert_with(|| format!("Station {}", count + 1));
+            if options.placeholders {
+                placeholder.clone()
+            } else {
+                String::new()
+            }
+        };
+
+        for entity in self.entities.iter_mut().flatten() {
+            entity.color = None;
+            match &mut entity.details {
+                EntityDetails::TrainStop { station } => {
+                    if let Some(name) = station {
+                        let placeholder = station_placeholder(name);
+                        *station = options.placeholders.then_some(placeholder);
+                    }
+                }
+                EntityDetails::Speaker {
+                    alert_parameters: Some(alert),
+                    ..
+                } => options.replace(&mut alert.alert_message, || "Alert".to_owned()),
+                _ => {}
+            }
+        }
+
+        for schedule in self.schedules.iter_mut().flatten() {
+            let (records, interrupts) = match &mut schedule.schedule {
+                ScheduleData::Legacy(records) => (records, None),
+                ScheduleData::Schedule(schedule) => {
+                    (&mut schedule.records, Some(&mut schedule.interrupts))
+                }
+            };
+            let mut conditions = Vec::new();
+            let mut targets = Vec::new();
+            for interrupt in interrupts.into_iter().flatten() {
+                conditions.extend(&mut interrupt.conditions);
+                targets.extend(&mut interrupt.targets);
+            }
+            for record in records.iter_mut().chain(targets) {
+                record.station = station_placeholder(&record.station);
+                conditions.extend(&mut record.wait_conditions);
+            }
+            // Conditions on how full other stations are name them too
+            for condition in conditions {
+                if let Some(station) = &mut condition.station {
+                    let placeholder = station_placeholder(station);
+                    condition.station = options.placeholders.then_some(placeholder);
+                }
+            }
+        }
+    }
+}
+
+impl BlueprintBook {
+    /// Remove the label, description, and label colour of the book, and
+    /// [sanitize](Blueprint::sanitize) everything in it.
+    pub fn sanitize(&mut self) {
+        self.sanitize_with(&SanitizeOptions::default());
+    }
+
+    /// [BlueprintBook::sanitize], with placeholders if `options` asks for them.
+    pub fn sanitize_with(&mut self, options: &SanitizeOptions) {
+        options.replace(&mut self.label, || "Blueprint book".to_owned());
+        self.label_color = None;
+        self.description = None;
+        for entry in &mut self.blueprints {
+            entry.content.sanitize_with(options);
+        }
+    }
+}
+
+impl BlueprintEnvelope {
+    /// [Sanitize](Blueprint::sanitize) the contents. Planners lose their label and description.
+    pub fn sanitize(&mut self) {
+        self.sanitize_with(&SanitizeOptions::default());
+    }
+
+    /// [BlueprintEnvelope::sanitize], with placeholders if `options` asks for them.
+    pub fn sanitize_with(&mut self, options: &SanitizeOptions) {
+        match self {
+            BlueprintEnvelope::Blueprint(blueprint) => blueprint.sanitize_with(options),
+            BlueprintEnvelope::BlueprintBook(book) => book.sanitize_with(options),
+            BlueprintEnvelope::DeconstructionPlanner(planner) => {
+                options.replace(&mut planner.label, || "Deconstruction planner".to_owned());
+                if let Some(settings) = &mut planner.settings {
+                    settings.description = None;
+                }
+            }
+            BlueprintEnvelope::UpgradePlanner(planner) => {
+                options.replace(&mut planner.label, || "Upgrade planner".to_owned());
+                if let Some(settings) = &mut planner.settings {
+                    settings.description = None;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// A blueprint with two named train stops and a train going between them.
+    fn blueprint() -> Blueprint {
+        let stop = |number: u32, x: f64, station: &str| {
+            json!({
+                "entity_number": number,
+                "name": "train-stop",
+                "position": {"x": x, "y": 1},
+                "station": station,
+                "color": {"r": 1, "g": 0, "b": 0, "a": 0.5},
+            })
+        };
+        let full = json!({
+            "type": "specific_destination_full",
+            "compare_type": "or",
+            "station": "Iron",
+        });
+        serde_json::from_value(json!({
+            "item": "blueprint",
+            "label": "Ore trains",
+            "description": "By me",
+            "entities": [stop(1, 1.0, "Iron"), stop(2, 9.0, "Copper")],
+            "schedules": [{
+                "locomotives": [3],
+                "schedule": [
+                    {"station": "Copper", "wait_conditions": [full]},
+                    {"station": "Iron"},
+                ],
+            }],
+            "version": 562949954076673u64,
+        }))
+        .unwrap()
+    }
+
+    /// The station of each train stop, then of each schedule record, then of the first record's
+    /// wait condition.
+    fn stations(blueprint: &Blueprint) -> Vec<Option<String>> {
+        let mut stations = Vec::new();
+        for entity in blueprint.entities.iter().flatten() {
+            if let EntityDetails::TrainStop { station } = &entity.details {
+                stations.push(station.clone());
+            }
+        }
+        let records = blueprint.schedules.as_ref().unwrap()[0].schedule.records();
+        stations.extend(records.iter().map(|record| Some(record.station.clone())));
+        stations.push(records[0].wait_conditions[0].station.clone());
+        stations
+    }
+
+    #[test]
+    fn placeholders_keep_trains_going_to_the_same_stops() {
+        let mut blueprint = blueprint();
+        blueprint.sanitize_with(&SanitizeOptions { placeholders: true });
+        assert_eq!(blueprint.label.as_deref(), Some("Blueprint"));
+        assert_eq!(blueprint.description, None);
+        assert!(blueprint
+            .entities
+            .iter()
+            .flatten()
+            .all(|e| e.color.is_none()));
+        let [first, second] = ["Station 1", "Station 2"].map(|name| Some(name.to_owned()));
+        assert_eq!(
+            stations(&blueprint),
+            [first.clone(), second.clone(), second, first.clone(), first]
+        );
+    }
+
+    #[test]
+    fn without_placeholders_text_is_removed() {
+        let mut blueprint = blueprint();
+        blueprint.sanitize();
+        assert_eq!(blueprint.label, None);
+        let empty = Some(String::new());
+        assert_eq!(
+            stations(&blueprint),
+            [None, None, empty.clone(), empty, None]
+        );
+    }
+}
@@ -0,0 +1,249 @@
+//! Typed vanilla virtual signals.
+
+use core::{fmt, str::FromStr};
+
+use super::{SignalId, SignalType};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// A signal that stands for a set of other signals in combinators.
+pub enum Wildcard {
+    /// Applies the operation to every input signal individually.
+    Each,
+    /// True if any input signal fulfils the condition.
+    Anything,
+    /// True if every input signal fulfils the condition.
+    Everything,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// A color signal, which sets the color of lamps using color signals.
+pub enum SignalColor {
+    /// signal-red
+    Red,
+    /// signal-green
+    Green,
+    /// signal-blue
+    Blue,
+    /// signal-yellow
+    Yellow,
+    /// signal-pink
+    Pink,
+    /// signal-cyan
+    Cyan,
+    /// signal-white
+    White,
+    /// signal-grey
+    Grey,
+    /// signal-black
+    Black,
+}
+
+impl SignalColor {
+    /// Every color signal, in the order lamps prioritise them.
+    pub const ALL: [SignalColor; 9] = [
+        SignalColor::Red,
+        SignalColor::Green,
+        SignalColor::Blue,
+        SignalColor::Yellow,
+        SignalColor::Pink,
+        SignalColor::Cyan,
+        SignalColor::White,
+        SignalColor::Grey,
+        SignalColor::Black,
+    ];
+
+    /// The name of the color in its signal name, e.g. "red".
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SignalColor::Red => "red",
+            SignalColor::Green => "green",
+            SignalColor::Blue => "blue",
+            SignalColor::Yellow => "yellow",
+            SignalColor::Pink => "pink",
+            SignalColor::Cyan => "cyan",
+            SignalColor::White => "white",
+            SignalColor::Grey => "grey",
+            SignalColor::Black => "black",
+        }
+    }
+
+    /// The color a lamp shows for this signal, as 8-bit RGB.
+    pub fn rgb(&self) -> (u8, u8, u8) {
+        match self {
+            SignalColor::Red => (255, 0, 0),
+            SignalColor::Green => (0, 255, 0),
+            SignalColor::Blue => (0, 0, 255),
+            SignalColor::Yellow => (255, 255, 0),
+            SignalColor::Pink => (255, 0, 255),
+            SignalColor::Cyan => (0, 255, 255),
+            SignalColor::White => (255, 255, 255),
+            SignalColor::Grey => (128, 128, 128),
+            SignalColor::Black => (0, 0, 0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// A vanilla virtual signal.
+pub enum VirtualSignal {
+    /// signal-0 to signal-9.
+    Digit(u8),
+    /// signal-A to signal-Z.
+    Letter(char),
+    /// A color signal.
+    Color(SignalColor),
+    /// signal-check
+    Check,
+    /// signal-info
+    Info,
+    /// signal-dot
+    Dot,
+    /// signal-each, signal-anything, or signal-everything.
+    Wildcard(Wildcard),
+    /// Any other virtual signal, by prototype name (e.g. from 2.0 or mods).
+    Other(String),
+}
+
+impl VirtualSignal {
+    /// The prototype name of the signal.
+    pub fn name(&self) -> String {
+        match self {
+            VirtualSignal::Digit(digit) => format!("signal-{digit}"),
+            VirtualSignal::Letter(letter) => format!("signal-{letter}"),
+            VirtualSignal::Color(color) => format!("signal-{}", color.as_str()),
+            VirtualSignal::Check => "signal-check".to_string(),
+            VirtualSignal::Info => "signal-info".to_string(),
+            VirtualSignal::Dot => "signal-dot".to_string(),
+            VirtualSignal::Wildcard(Wildcard::Each) => "signal-each".to_string(),
+            VirtualSignal::Wildcard(Wildcard::Anything) => "signal-anything".to_string(),
+            VirtualSignal::Wildcard(Wildcard::Everything) => "signal-everything".to_string(),
+            VirtualSignal::Other(name) => name.clone(),
+        }
+    }
+}
+
+impl FromStr for VirtualSignal {
+    type Err = core::convert::Infallible;
+
+    /// Parse a prototype name. Unrecognised names become [VirtualSignal::Other].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let signal = match s.strip_prefix("signal-") {
+            Some("check") => VirtualSignal::Check,
+            Some("info") => VirtualSignal::Info,
+            Some("dot") => VirtualSignal::Dot,
+            Some("each") => VirtualSignal::Wildcard(Wildcard::Each),
+            Some("anything") => VirtualSignal::Wildcard(Wildcard::Anything),
+            Some("everything") => VirtualSignal::Wildcard(Wildcard::Everything),
+            Some(suffix) => {
+                let mut chars = suffix.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c @ '0'..='9'), None) => VirtualSignal::Digit(c as u8 - b'0'),
+                    (Some(c @ 'A'..='Z'), None) => VirtualSignal::Letter(c),
+                    _ => SignalColor::ALL
+                        .into_iter()
+                        .find(|color| color.as_str() == suffix)
+                        .map(VirtualSignal::Color)
+                        .unwrap_or_else(|| VirtualSignal::Other(s.to_string())),
+                }
+            }
+            None => VirtualSignal::Other(s.to_string()),
+        };
+
+        Ok(signal)
+    }
+}
+
+impl fmt::Display for VirtualSignal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name())
+    }
+}
+
+impl SignalId {
+    /// An item signal.
+    pub fn item(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            signal_type: Some(SignalType::Item),
+            quality: None,
+        }
+    }
+
+    /// A fluid signal.
+    pub fn fluid(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            signal_type: Some(SignalType::Fluid),
+            quality: None,
+        }
+    }
+
+    /// A virtual signal.
+    pub fn virtual_signal(signal: VirtualSignal) -> Self {
+        Self {
+            name: signal.name(),
+            signal_type: Some(SignalType::Virtual),
+            quality: None,
+        }
+    }
+
+    /// The typed virtual signal, if this is a virtual signal.
+    pub fn as_virtual(&self) -> Option<VirtualSignal> {
+        match self.kind() {
+            SignalType::Virtual => self.name.parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// The wildcard this signal stands for, if it is one.
+    pub fn wildcard(&self) -> Option<Wildcard> {
+        match self.as_virtual()? {
+            VirtualSignal::Wildcard(wildcard) => Some(wildcard),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn virtual_signals_parse_from_their_names() {
+        let parse = |name: &str| name.parse::<VirtualSignal>().unwrap();
+        assert_eq!(parse("signal-7"), VirtualSignal::Digit(7));
+        assert_eq!(parse("signal-Q"), VirtualSignal::Letter('Q'));
+        assert_eq!(
+            parse("signal-cyan"),
+            VirtualSignal::Color(SignalColor::Cyan)
+        );
+        assert_eq!(
+            parse("signal-anything"),
+            VirtualSignal::Wildcard(Wildcard::Anything)
+        );
+        assert_eq!(
+            parse("signal-AB"),
+            VirtualSignal::Other("signal-AB".to_owned())
+        );
+        for name in [
+            "signal-0",
+            "signal-Z",
+            "signal-grey",
+            "signal-each",
+            "up-arrow",
+        ] {
+            assert_eq!(parse(name).name(), name);
+        }
+    }
+
+    #[test]
+    fn only_virtual_signals_are_wildcards() {
+        let each = SignalId::virtual_signal(VirtualSignal::Wildcard(Wildcard::Each));
+        assert_eq!(each.wildcard(), Some(Wildcard::Each));
+        assert_eq!(SignalId::item("signal-each").wildcard(), None);
+        assert_eq!(
+            SignalId::virtual_signal(VirtualSignal::Dot).wildcard(),
+            None
+        );
+    }
+}
@@ -0,0 +1,244 @@
+//! Programmable speaker instruments and notes.
+
+use serde::{Deserialize, Serialize};
+
+const NOTE_NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+/// The sound a programmable speaker plays when its circuit condition is fulfilled.
+pub struct SpeakerCircuitParameters {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Whether the value of the condition's first signal selects the note, instead of `note_id`.
+    pub signal_value_is_pitch: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Index of the instrument in [VANILLA_INSTRUMENTS] (or the modded equivalent).
+    pub instrument_id: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Index of the note within the instrument.
+    pub note_id: Option<u32>,
+}
+
+impl SpeakerCircuitParameters {
+    /// The vanilla instrument this speaker is set to.
+    pub fn instrument(&self) -> Option<&'static Instrument> {
+        VANILLA_INSTRUMENTS.get(self.instrument_id.unwrap_or(0) as usize)
+    }
+
+    /// The name of the note this speaker is set to, e.g. "C#4" or "kick-1".
+    pub fn note_name(&self) -> Option<String> {
+        self.instrument()?.note_name(self.note_id.unwrap_or(0))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The notes an instrument can play.
+pub enum Notes {
+    /// Individually named sounds, e.g. drum kit samples.
+    Named(&'static [&'static str]),
+    /// A chromatic scale of `count` notes, starting at the MIDI note number `lowest`.
+    Chromatic {
+        /// MIDI note number of note 0.
+        lowest: u8,
+        /// Number of notes in the scale.
+        count: u8,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// An instrument of the programmable speaker.
+pub struct Instrument {
+    /// The name of the instrument as shown in game.
+    pub name: &'static str,
+    /// The notes the instrument can play.
+    pub notes: Notes,
+}
+
+impl Instrument {
+    /// The number of notes the instrument can play.
+    pub fn note_count(&self) -> u32 {
+        match self.notes {
+            Notes::Named(names) => names.len() as u32,
+            Notes::Chromatic { count, .. } => count as u32,
+        }
+    }
+
+    /// The name of a note, e.g. "C#4" or "kick-1".
+    pub fn note_name(&self, note_id: u32) -> Option<String> {
+        match self.notes {
+            Notes::Named(names) => names.get(note_id as usize).map(|name| name.to_string()),
+            Notes::Chromatic { .. } => {
+                let midi = self.midi_note(note_id)? as usize;
+                Some(format!("{}{}", NOTE_NAMES[midi % 12], midi / 12 - 1))
+            }
+        }
+    }
+
+    /// The MIDI note number of a note of a chromatic instrument.
+    pub fn midi_note(&self, note_id: u32) -> Option<u8> {
+        match self.notes {
+            Notes::Chromatic { lowest, count } if note_id < count as u32 => {
+                Some(lowest + note_id as u8)
+            }
+            _ => None,
+        }
+    }
+
+    /// The note that plays a MIDI note number on a chromatic instrument, if it is in range.
+    pub fn note_for_midi(&self, midi: u8) -> Option<u32> {
+        match self.notes {
+            Notes::Chromatic { lowest, count } if (lowest..lowest + count).contains(&midi) => {
+                Some((midi - lowest) as u32)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The instruments of the vanilla programmable speaker, indexed by `instrument_id`.
+pub const VANILLA_INSTRUMENTS: [Instrument; 12] = [
+    Instrument {
+        name: "Alarms",
+        notes: Notes::Named(&[
+            "alarm-1", "alarm-2", "buzzer-1", "buzzer-2", "buzzer-3", "ring", "siren",
+        ]),
+    },
+    Instrument {
+        name: "Miscellaneous",
+        notes: Notes::Named(&[
+            "achievement-unlocked",
+            "alert-destroyed",
+            "armor-insert",
+            "armor-remove",
+            "cannot-build",
+            "console-message",
+            "crafting-finished",
+            "game-lost",
+            "game-won",
+            "gui-click",
+            "inventory-move",
+            "new-objective",
+            "research-completed",
+            "scenario-message",
+        ]),
+    },
+    Instrument {
+        name: "Drum kit",
+        notes: Notes::Named(&[
+            "kick-1",
+            "kick-2",
+            "snare-1",
+            "snare-2",
+            "snare-3",
+            "hat-1",
+            "hat-2",
+            "fx",
+            "high-q",
+            "perc-1",
+            "perc-2",
+            "crash",
+            "reverse-cymbal",
+            "clap",
+            "shaker",
+            "cowbell",
+            "triangle",
+        ]),
+    },
+    Instrument {
+        name: "Piano",
+        notes: Notes::Chromatic {
+            lowest: 53,
+            count: 48,
+        },
+    },
+    Instrument {
+        name: "Bass",
+        notes: Notes::Chromatic {
+            lowest: 41,
+            count: 36,
+        },
+    },
+    Instrument {
+        name: "Lead",
+        notes: Notes::Chromatic {
+            lowest: 41,
+            count: 36,
+        },
+    },
+    Instrument {
+        name: "Sawtooth",
+        notes: Notes::Chromatic {
+            lowest: 41,
+            count: 36,
+        },
+    },
+    Instrument {
+        name: "Square",
+        notes: Notes::Chromatic {
+            lowest: 41,
+            count: 36,
+        },
+    },
+    Instrument {
+        name: "Celesta",
+        notes: Notes::Chromatic {
+            lowest: 77,
+            count: 36,
+        },
+    },
+    Instrument {
+        name: "Vibraphone",
+        notes: Notes::Chromatic {
+            lowest: 77,
+            count: 36,
+        },
+    },
+    Instrument {
+        name: "Plucked strings",
+        notes: Notes::Chromatic {
+            lowest: 65,
+            count: 36,
+        },
+    },
+    Instrument {
+        name: "Steel drum",
+        notes: Notes::Chromatic {
+            lowest: 53,
+            count: 36,
+        },
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notes_are_named_by_instrument() {
+        let speaker = |instrument_id, note_id| SpeakerCircuitParameters {
+            signal_value_is_pitch: None,
+            instrument_id: Some(instrument_id),
+            note_id: Some(note_id),
+        };
+        assert_eq!(speaker(2, 2).note_name().as_deref(), Some("snare-1"));
+        assert_eq!(speaker(3, 0).note_name().as_deref(), Some("F3"));
+        assert_eq!(speaker(3, 7).note_name().as_deref(), Some("C4"));
+        assert_eq!(speaker(3, 48).note_name(), None);
+        assert_eq!(speaker(12, 0).note_name(), None);
+        assert_eq!(
+            SpeakerCircuitParameters::default().note_name().as_deref(),
+            Some("alarm-1")
+        );
+    }
+
+    #[test]
+    fn midi_notes_map_onto_chromatic_instruments() {
+        let piano = &VANILLA_INSTRUMENTS[3];
+        assert_eq!(piano.note_for_midi(60), Some(7));
+        assert_eq!(piano.midi_note(7), Some(60));
+        assert_eq!(piano.note_for_midi(52), None);
+        assert_eq!(piano.note_for_midi(101), None);
+        assert_eq!(VANILLA_INSTRUMENTS[2].note_for_midi(60), None);
+    }
+}
@@ -0,0 +1,218 @@
+//! Conversion between blueprint structures and blueprint exchange strings.
+
+use std::io::{Read, Write};
+
+use base64::{engine::general_purpose, Engine};
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+
+use super::{
+    Blueprint, BlueprintBook, BlueprintEnvelope, BpError, DecodeOptions, DeconstructionPlanner,
+    UpgradePlanner,
+};
+
+/// The version byte prepended to every blueprint string exported by the game.
+pub const VERSION_BYTE: char = '0';
+
+/// Decode a blueprint string into the JSON it contains, without interpreting that JSON.
+///
+/// Whitespace (including the line breaks some sites insert) is ignored.
+pub fn decode_json(bp_string: &str) -> Result<String, BpError> {
+    let mut input = bp_string.chars().filter(|c| !c.is_whitespace());
+
+    match input.next() {
+        Some(VERSION_BYTE) => (),
+        Some(version) => return Err(BpError::UnsupportedVersion(version)),
+        None => return Err(BpError::Empty),
+    }
+
+    let input_bytes = general_purpose::STANDARD.decode(input.collect::<String>())?;
+
+    let mut z = ZlibDecoder::new(&input_bytes[..]);
+    let mut json = String::new();
+    z.read_to_string(&mut json).map_err(BpError::Zlib)?;
+
+    Ok(json)
+}
+
+/// Decode a blueprint string into the blueprint (or book, etc.) it contains.
+///
+/// Directions of pre-2.0 blueprints are converted to the 2.0 numbering, see [Direction](super::Direction).
+/// Unknown fields are ignored; use [DecodeOptions] to deny them or to skip invalid values.
+pub fn decode_string(bp_string: &str) -> Result<BlueprintEnvelope, BpError> {
+    DecodeOptions::default()
+        .decode(bp_string)
+        .map(|decoded| decoded.envelope)
+}
+
+/// Encode JSON into a blueprint string, without checking that it describes a blueprint.
+///
+/// The JSON is compressed with zlib and base64 encoded, and the version byte is prepended.
+pub fn encode_json(json: &str) -> Result<String, BpError> {
+    EncodeOptions::default().encode_json(json)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// How blueprint strings are compressed.
+///
+/// The default is the best compression, like the game.
+pub struct EncodeOptions {
+    /// The zlib compression level, from 0 for none to 9 for the best. Higher levels are treated
+    /// as 9.
+    pub compression_level: u32,
+    /// Encode the [canonical JSON](BlueprintEnvelope::to_canonical_json), so that blueprints
+    /// that are the same in game always have the same string.
+    pub canonical: bool,
+}
+
+impl Default for EncodeOptions {
+    fn default() -> Self {
+        Self {
+            compression_level: Compression::best().level(),
+            canonical: false,
+        }
+    }
+}
+
+impl EncodeOptions {
+    /// Encode a blueprint, book, or planner into a blueprint string.
+    pub fn encode(&self, envelope: &BlueprintEnvelope) -> Result<String, BpError> {
+        if self.canonical {
+            return self.encode_bytes(envelope.to_canonical_json()?.as_bytes());
+        }
+        encode(envelope.clone(), self)
+    }
+
+    /// Encode JSON into a blueprint string, without checking that it describes a blueprint.
+    pub fn encode_json(&self, json: &str) -> Result<String, BpError> {
+        self.encode_bytes(json.as_bytes())
+    }
+
+    fn encode_bytes(&self, json: &[u8]) -> Result<String, BpError> {
+        let level = Compression::new(self.compression_level.min(Compression::best().level()));
+        let mut z = ZlibEncoder::new(Vec::new(), level);
+        z.write_all(json).map_err(BpError::Zlib)?;
+        let compressed = z.finish().map_err(BpError::Zlib)?;
+
+        let mut output = String::from(VERSION_BYTE);
+        general_purpose::STANDARD.encode_string(compressed, &mut output);
+
+        Ok(output)
+    }
+}
+
+/// Serialize the given envelope to JSON and encode it.
+fn encode(mut envelope: BlueprintEnvelope, options: &EncodeOptions) -> Result<String, BpError> {
+    envelope.directions_to_legacy();
+    options.encode_bytes(&serde_json::to_vec(&envelope)?)
+}
+
+impl BlueprintEnvelope {
+    /// Decode a blueprint string. Equivalent to [decode_string].
+    pub fn from_bp_string(bp_string: &str) -> Result<Self, BpError> {
+        decode_string(bp_string)
+    }
+
+    /// Encode the contents of this envelope into a blueprint string that can be imported into the game.
+    pub fn to_bp_string(&self) -> Result<String, BpError> {
+        encode(self.clone(), &EncodeOptions::default())
+    }
+
+    /// The JSON a blueprint string of this holds, with directions numbered the way the version
+    /// of each blueprint does.
+    pub(crate) fn to_json_value(&self) -> Result<serde_json::Value, BpError> {
+        let mut envelope = self.clone();
+        envelope.directions_to_legacy();
+        Ok(serde_json::to_value(&envelope)?)
+    }
+}
+
+impl Blueprint {
+    /// Encode this blueprint into a blueprint string that can be imported into the game.
+    pub fn to_bp_string(&self) -> Result<String, BpError> {
+        encode(
+            BlueprintEnvelope::Blueprint(self.clone()),
+            &EncodeOptions::default(),
+        )
+    }
+}
+
+impl BlueprintBook {
+    /// Encode this blueprint book into a blueprint string that can be imported into the game.
+    pub fn to_bp_string(&self) -> Result<String, BpError> {
+        encode(
+            BlueprintEnvelope::BlueprintBook(self.clone()),
+            &EncodeOptions::default(),
+        )
+    }
+}
+
+impl DeconstructionPlanner {
+    /// Encode this deconstruction planner into a blueprint string that can be imported into the game.
+    pub fn to_bp_string(&self) -> Result<String, BpError> {
+        encode(
+            BlueprintEnvelope::DeconstructionPlanner(self.clone()),
+            &EncodeOptions::default(),
+        )
+    }
+}
+
+impl UpgradePlanner {
+    /// Encode this upgrade planner into a blueprint string that can be imported into the game.
+    pub fn to_bp_string(&self) -> Result<String, BpError> {
+        encode(
+            BlueprintEnvelope::UpgradePlanner(self.clone()),
+            &EncodeOptions::default(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{blueprint::Version, bp, prototypes::PrototypeDb};
+
+    #[test]
+    fn blueprints_survive_encoding_and_decoding() {
+        let blueprint = bp! {
+            "iron-chest" @ (0, 0);
+            "inserter" @ (0, 1) facing East;
+        }
+        .label("Chest")
+        .version(Version::new(1, 1, 80, 0))
+        .build_with(PrototypeDb::vanilla())
+        .unwrap();
+
+        let bp_string = blueprint.to_bp_string().unwrap();
+        assert!(bp_string.starts_with(VERSION_BYTE));
+        let json: serde_json::Value =
+            serde_json::from_str(&decode_json(&bp_string).unwrap()).unwrap();
+        assert_eq!(json["blueprint"]["version"], 281479276920832u64);
+        assert_eq!(json["blueprint"]["entities"][1]["direction"], 2);
+
+        assert_eq!(
+            decode_string(&bp_string).unwrap(),
+            BlueprintEnvelope::Blueprint(blueprint)
+        );
+    }
+
+    #[test]
+    fn each_stage_of_decoding_reports_its_own_error() {
+        assert!(matches!(decode_json(" \n"), Err(BpError::Empty)));
+        assert!(matches!(
+            decode_json("1eJyrVgrKz0kt"),
+            Err(BpError::UnsupportedVersion('1'))
+        ));
+        assert!(matches!(decode_json("0!!!"), Err(BpError::Base64(_))));
+        assert!(matches!(decode_json("0AAAA"), Err(BpError::Zlib(_))));
+        let not_json = encode_json("blueprint").unwrap();
+        assert!(matches!(decode_string(&not_json), Err(BpError::Json(_))));
+
+        // Line breaks inserted by sites sharing the string are ignored
+        let bp_string = encode_json(r#"{"blueprint": 1}"#).unwrap();
+        let (start, end) = bp_string.split_at(5);
+        assert_eq!(
+            decode_json(&format!("{start}\n  {end}\n")).unwrap(),
+            r#"{"blueprint": 1}"#
+        );
+    }
+}
@@ -0,0 +1,373 @@
+//! Rotating, mirroring, moving, and repeating the contents of blueprints.
+
+use core::num::NonZeroUsize;
+
+use crate::{prototypes::PrototypeDb, rail::uses_rail_grid};
+
+use super::{Blueprint, Direction, Entity, EntityDetails, IoPriority, Position};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// A rotation by a whole number of quarter turns.
+pub enum Rotation {
+    /// A quarter turn clockwise.
+    Clockwise,
+    /// A half turn.
+    Half,
+    /// A quarter turn counter-clockwise.
+    CounterClockwise,
+}
+
+impl Rotation {
+    /// The number of quarter turns clockwise.
+    pub fn quarter_turns(&self) -> i32 {
+        match self {
+            Rotation::Clockwise => 1,
+            Rotation::Half => 2,
+            Rotation::CounterClockwise => 3,
+        }
+    }
+}
+
+/// How to move points, directions, and orientations about the blueprint's origin.
+struct Transform {
+    point: fn(f64, f64) -> (f64, f64),
+    direction: fn(Direction) -> Direction,
+    orientation: fn(f64) -> f64,
+    /// Whether the transform turns things into their mirror image.
+    mirrors: bool,
+}
+
+impl Transform {
+    fn rotation(rotation: Rotation) -> Self {
+        match rotation {
+            Rotation::Clockwise => Self {
+                point: |x, y| (-y, x),
+                direction: |d| d.clockwise(),
+                orientation: |o| (o + 0.25).rem_euclid(1.0),
+                mirrors: false,
+            },
+            Rotation::Half => Self {
+                point: |x, y| (-x, -y),
+                direction: |d| d.opposite(),
+                orientation: |o| (o + 0.5).rem_euclid(1.0),
+                mirrors: false,
+            },
+            Rotation::CounterClockwise => Self {
+                point: |x, y| (y, -x),
+                direction: |d| d.counter_clockwise(),
+                orientation: |o| (o + 0.75).rem_euclid(1.0),
+                mirrors: false,
+            },
+        }
+    }
+
+    fn flip_horizontal() -> Self {
+        Self {
+            point: |x, y| (-x, y),
+            direction: |d| Direction::from_sixteenths(-(d.sixteenths() as i32)),
+            orientation: |o| (1.0 - o).rem_euclid(1.0),
+            mirrors: true,
+        }
+    }
+
+    fn flip_vertical() -> Self {
+        Self {
+            point: |x, y| (x, -y),
+            direction: |d| Direction::from_sixteenths(8 - d.sixteenths() as i32),
+            orientation: |o| (0.5 - o).rem_euclid(1.0),
+            mirrors: true,
+        }
+    }
+
+    fn position(&self, position: &Position) -> Position {
+        let (x, y) = (self.point)(position.x, position.y);
+        // Avoid writing -0 for positions on an axis
+        Position {
+            x: x + 0.0,
+            y: y + 0.0,
+        }
+    }
+}
+
+impl Entity {
+    fn transform(&mut self, transform: &Transform) {
+        self.position = transform.position(&self.position);
+        // The game leaves out the direction of entities facing north, and of those that can't
+        // face any other way, like chests
+        let rotatable = || {
+            PrototypeDb::global()
+                .entity(&self.name)
+                .is_some_and(|prototype| prototype.rotatable)
+        };
+        if self.direction.is_some() || rotatable() {
+            let direction = (transform.direction)(self.direction.unwrap_or_default());
+            self.direction = (direction != Direction::North).then_some(direction);
+        }
+        match &mut self.details {
+            EntityDetails::Vehicle { orientation, .. } => {
+                *orientation = orientation.map(transform.orientation);
+            }
+            // Inserter pickup and drop positions are relative to the inserter
+            EntityDetails::Inserter {
+                pickup_position,
+                drop_position,
+                ..
+            } => {
+                for offset in [pickup_position, drop_position].into_iter().flatten() {
+                    *offset = transform.position(offset);
+                }
+            }
+            EntityDetails::Splitter {
+                input_priority,
+                output_priority,
+                ..
+            } if transform.mirrors => {
+                for priority in [input_priority, output_priority].into_iter().flatten() {
+                    *priority = match priority {
+                        IoPriority::Left => IoPriority::Right,
+                        IoPriority::Right => IoPriority::Left,
+                    };
+                }
+            }
+            _ => {}
+        }
+
+        if transform.mirrors {
+            // Crafting machines have a mirrored variant rather than a mirrored position
+            if self.recipe().is_some() || self.mirror.is_some() {
+                self.mirror = (!self.mirror.unwrap_or(false)).then_some(true);
+            }
+        }
+    }
+}
+
+impl Blueprint {
+    fn transform(&mut self, transform: &Transform) {
+        for entity in self.entities.iter_mut().flatten() {
+            entity.transform(transform);
+        }
+        for tile in self.tiles.iter_mut().flatten() {
+            // Tile positions are their top left corners, so move the centre instead
+            let Position { x, y } = transform.position(&Position {
+                x: tile.position.x + 0.5,
+                y: tile.position.y + 0.5,
+            });
+            tile.position = Position {
+                x: x - 0.5,
+                y: y - 0.5,
+            };
+        }
+    }
+
+    /// Apply `renumber` to every entity_number and every reference to one.
+    pub(super) fn map_entity_numbers(&mut self, renumber: impl Fn(NonZeroUsize) -> NonZeroUsize) {
+        for entity in self.entities.iter_mut().flatten() {
+            entity.entity_number = renumber(entity.entity_number);
+            for neighbour in entity.neighbors.iter_mut().flatten() {
+                *neighbour = renumber(*neighbour);
+            }
+            let points = entity
+                .connections
+                .iter_mut()
+                .flat_map(|connection| [&mut connection.first, &mut connection.second])
+                .flatten();
+            for point in points {
+                for data in point.red.iter_mut().chain(&mut point.green).flatten() {
+                    data.entity_id = renumber(data.entity_id);
+                }
+            }
+        }
+        for wire in self.wires.iter_mut().flatten() {
+            wire.source_entity = renumber(wire.source_entity);
+            wire.target_entity = renumber(wire.target_entity);
+        }
+        for schedule in self.schedules.iter_mut().flatten() {
+            for locomotive in &mut schedule.locomotives {
+                *locomotive = renumber(*locomotive);
+            }
+        }
+    }
+
+    /// Rotate the blueprint about its origin, turning entities with it.
+    ///
+    /// Entities without a direction only get one if the [global](PrototypeDb::global)
+    /// prototypes say they can face other ways than north.
+    pub fn rotate(&mut self, rotation: Rotation) {
+        self.transform(&Transform::rotation(rotation));
+
+        if rotation != Rotation::Half {
+            if let Some(grid) = &mut self.snap_to_grid {
+                core::mem::swap(&mut grid.x, &mut grid.y);
+            }
+        }
+    }
+
+    /// Mirror the blueprint left to right, as the game does when flipping it horizontally.
+    ///
+    /// Splitter priorities swap sides, and crafting machines toggle their `mirror` flag.
+    pub fn flip_horizontal(&mut self) {
+        self.transform(&Transform::flip_horizontal());
+    }
+
+    /// Mirror the blueprint top to bottom, as the game does when flipping it vertically.
+    ///
+    /// Splitter priorities swap sides, and crafting machines toggle their `mirror` flag.
+    pub fn flip_vertical(&mut self) {
+        self.transform(&Transform::flip_vertical());
+    }
+
+    /// Move every entity and tile by `dx` tiles right and `dy` tiles down.
+    ///
+    /// Tiles and most entities stay on the grid only when moved by whole tiles.
+    pub fn translate(&mut self, dx: f64, dy: f64) {
+        for entity in self.entities.iter_mut().flatten() {
+            entity.position.x += dx;
+            entity.position.y += dy;
+        }
+        for tile in self.tiles.iter_mut().flatten() {
+            tile.position.x += dx;
+            tile.position.y += dy;
+        }
+    }
+
+    /// A blueprint of `nx` by `ny` copies of this one, with `spacing` empty tiles between them.
+    ///
+    /// Copies are placed one snapping grid apart if the blueprint has one, and one
+    /// [bounding box](Blueprint::bounding_box) apart otherwise, rounded up to an even number of
+    /// tiles if the blueprint has rails, rail signals, or train stops so that every copy stays on
+    /// the rail grid. Each copy's entities are renumbered, and its wires, circuit connections,
+    /// and schedules follow them.
+    ///
+    /// Copies are never merged: entities of different copies that end up in the same place are
+    /// all kept, whether or not they have the same name, and aren't reported.
+    /// [Validation](Blueprint::validate) finds them.
+    pub fn tile(&self, nx: usize, ny: usize, spacing: u32) -> Blueprint {
+        let (width, height) = match (&self.snap_to_grid, self.bounding_box()) {
+            (Some(grid), _) => (grid.x, grid.y),
+            (None, Some(bounds)) => {
+                let size = bounds.tile_size();
+                (size.w as f64, size.h as f64)
+            }
+            (None, None) => (0.0, 0.0),
+        };
+        let (mut step_x, mut step_y) = (width + spacing as f64, height + spacing as f64);
+        if self.snap_to_grid.is_none() && uses_rail_grid(self) {
+            step_x = (step_x / 2.0).ceil() * 2.0;
+            step_y = (step_y / 2.0).ceil() * 2.0;
+        }
+        let stride = self
+            .entities
+            .iter()
+            .flatten()
+            .map(|entity| entity.entity_number.get())
+            .max()
+            .unwrap_or(0);
+
+        // Keep each list present or absent as it is in the original
+        let mut tiled = Blueprint {
+            entities: self.entities.as_ref().map(|_| Vec::new()),
+            tiles: self.tiles.as_ref().map(|_| Vec::new()),
+            wires: self.wires.as_ref().map(|_| Vec::new()),
+            schedules: self.schedules.as_ref().map(|_| Vec::new()),
+            ..self.clone()
+        };
+
+        for copy_y in 0..ny {
+            for copy_x in 0..nx {
+                let index = copy_y * nx + copy_x;
+                let mut copy = self.clone();
+                copy.translate(copy_x as f64 * step_x, copy_y as f64 * step_y);
+                copy.map_entity_numbers(|number| number.saturating_add(index * stride));
+
+                extend(&mut tiled.entities, copy.entities);
+                extend(&mut tiled.tiles, copy.tiles);
+                extend(&mut tiled.wires, copy.wires);
+                extend(&mut tiled.schedules, copy.schedules);
+            }
+        }
+
+        tiled
+    }
+}
+
+/// Append the items of `more`, if any, to `list`.
+fn extend<T>(list: &mut Option<Vec<T>>, more: Option<Vec<T>>) {
+    if let (Some(list), Some(more)) = (list, more) {
+        list.extend(more);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bp;
+
+    #[test]
+    fn rail_copies_stay_on_the_rail_grid() {
+        let mut blueprint = bp! {
+            "straight-rail" @ (0, 0);
+            "rail-signal" @ (2, 0);
+        }
+        .build_with(PrototypeDb::vanilla())
+        .unwrap();
+        blueprint.snap_to_grid = None;
+        // Three tiles wide, so copies go four tiles apart
+        let tiled = blueprint.tile(2, 1, 0);
+
+        let positions: Vec<_> = tiled
+            .entities
+            .iter()
+            .flatten()
+            .map(|entity| (entity.entity_number.get(), entity.position.x))
+            .collect();
+        assert_eq!(positions, [(1, 1.0), (2, 2.5), (3, 5.0), (4, 6.5)]);
+    }
+
+    #[test]
+    fn only_entities_that_can_face_other_ways_turn() {
+        let mut blueprint = bp! {
+            "iron-chest" @ (0, 0);
+            "inserter" @ (1, 0);
+            "transport-belt" @ (2, 0) facing East;
+        }
+        .build_with(PrototypeDb::vanilla())
+        .unwrap();
+        blueprint.rotate(Rotation::Clockwise);
+
+        let directions: Vec<_> = blueprint
+            .entities
+            .iter()
+            .flatten()
+            .map(|entity| entity.direction)
+            .collect();
+        assert_eq!(
+            directions,
+            [None, Some(Direction::East), Some(Direction::South)]
+        );
+    }
+
+    #[test]
+    fn flipping_swaps_splitter_priorities() {
+        let mut blueprint = bp! { "splitter" @ (0, 0) }
+            .build_with(PrototypeDb::vanilla())
+            .unwrap();
+        let splitter = &mut blueprint.entities.as_mut().unwrap()[0];
+        splitter.details = EntityDetails::Splitter {
+            input_priority: Some(IoPriority::Left),
+            output_priority: Some(IoPriority::Right),
+            filter: None,
+        };
+        blueprint.flip_horizontal();
+
+        let splitter = &blueprint.entities.as_ref().unwrap()[0];
+        assert_eq!(splitter.direction, None);
+        assert!(matches!(
+            splitter.details,
+            EntityDetails::Splitter {
+                input_priority: Some(IoPriority::Right),
+                output_priority: Some(IoPriority::Left),
+                ..
+            }
+        ));
+    }
+}
@@ -0,0 +1,991 @@
+//! Semantic checks of blueprints, for problems the schema alone doesn't catch.
+
+use core::{fmt, num::NonZeroUsize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use crate::{
+    belt::{BeltGraph, NodeKind},
+    fluid::fixed_fluids,
+    power::PowerGraph,
+    prototypes::{BeltKind, PrototypeDb},
+};
+
+use super::{Blueprint, BlueprintBook, BlueprintEnvelope, Direction, Entity, Icon, Position};
+
+/// The most icons a blueprint, book, or planner can show.
+const MAX_ICONS: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+/// How serious a validation issue is.
+pub enum Severity {
+    /// Suspicious, but the game will import it.
+    Warning,
+    /// The game will reject the blueprint or silently drop part of it.
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A problem found by [Blueprint::validate].
+pub enum Issue {
+    /// More than one entity has this entity_number.
+    DuplicateEntityNumber(NonZeroUsize),
+    /// Two entities are centred on the same position.
+    DuplicatePosition(NonZeroUsize, NonZeroUsize),
+    /// The collision boxes of two entities overlap, so the game can't place both.
+    Overlap(NonZeroUsize, NonZeroUsize),
+    /// A wire or circuit connection of an entity leads to an entity_number that doesn't exist.
+    DanglingConnection {
+        /// The entity the connection starts at.
+        entity: NonZeroUsize,
+        /// The missing entity it leads to.
+        target: NonZeroUsize,
+    },
+    /// An entity that needs electricity is outside the supply area of every pole.
+    Unpowered {
+        /// The entity without power.
+        entity: NonZeroUsize,
+        /// Where the entity is.
+        position: Position,
+    },
+    /// An inserter picks up from a tile with nothing on it to take items from.
+    NothingToPickUp {
+        /// The inserter.
+        entity: NonZeroUsize,
+        /// Where it picks up from.
+        position: Position,
+    },
+    /// An inserter drops onto a tile with nothing on it to put items in.
+    NothingToDropOn {
+        /// The inserter.
+        entity: NonZeroUsize,
+        /// Where it drops items.
+        position: Position,
+    },
+    /// An underground belt entrance or exit has no partner to pair with.
+    UnpairedUnderground {
+        /// The underground belt.
+        entity: NonZeroUsize,
+        /// Where it is.
+        position: Position,
+    },
+    /// An underground belt entrance and exit line up, but are too far apart to pair.
+    UndergroundTooLong {
+        /// The entrance.
+        entrance: NonZeroUsize,
+        /// The exit.
+        exit: NonZeroUsize,
+        /// The tiles between their positions.
+        distance: u32,
+        /// The most tiles between their positions the belt tier allows.
+        max_distance: u32,
+    },
+    /// An underground belt of another tier runs between a paired entrance and exit, in line with
+    /// them. Usually deliberate belt weaving, but easy to get wrong.
+    UndergroundWeaving {
+        /// The entrance.
+        entrance: NonZeroUsize,
+        /// The exit.
+        exit: NonZeroUsize,
+        /// The underground belt between them.
+        crossing: NonZeroUsize,
+    },
+    /// Pipes lead up to a crafting machine, but don't line up with the fluid boxes its recipe
+    /// uses, as when a chemical plant is pasted facing the wrong way.
+    MisalignedFluidBoxes {
+        /// The crafting machine.
+        entity: NonZeroUsize,
+        /// The fluids of the boxes no pipe lines up with, in fluid box order.
+        fluids: Vec<String>,
+        /// The direction that would line up the most boxes, the first clockwise from north if
+        /// several would.
+        direction: Direction,
+        /// Whether facing `direction` would line up every box.
+        all: bool,
+    },
+    /// A train schedule is assigned to a locomotive that doesn't exist.
+    DanglingLocomotive(NonZeroUsize),
+    /// An icon has an index outside 1 to 4.
+    IconIndexOutOfRange(NonZeroUsize),
+    /// More than one icon has this index.
+    DuplicateIconIndex(NonZeroUsize),
+    /// More than one book entry has this index.
+    DuplicateBookIndex(usize),
+    /// The active index of a book doesn't refer to any of its entries.
+    ActiveIndexOutOfRange(usize),
+    /// An issue with an entry of a blueprint book.
+    InBookEntry {
+        /// The index of the entry in the book.
+        index: usize,
+        /// The issue with the entry.
+        issue: Box<Issue>,
+    },
+}
+
+impl Issue {
+    /// How serious the issue is.
+    pub fn severity(&self) -> Severity {
+        match self {
+            Issue::DuplicatePosition(..)
+            | Issue::Unpowered { .. }
+            | Issue::NothingToPickUp { .. }
+            | Issue::NothingToDropOn { .. }
+            | Issue::UnpairedUnderground { .. }
+            | Issue::UndergroundTooLong { .. }
+            | Issue::UndergroundWeaving { .. }
+            | Issue::MisalignedFluidBoxes { .. }
+            | Issue::DanglingLocomotive(_)
+            | Issue::ActiveIndexOutOfRange(_) => Severity::Warning,
+            Issue::InBookEntry { issue, .. } => issue.severity(),
+            _ => Severity::Error,
+        }
+    }
+
+    /// The entity_numbers of the entities involved, in the blueprint the issue is in.
+    pub fn entity_numbers(&self) -> Vec<NonZeroUsize> {
+        match self {
+            Issue::DuplicateEntityNumber(entity) | Issue::DanglingLocomotive(entity) => {
+                vec![*entity]
+            }
+            Issue::DuplicatePosition(first, second) | Issue::Overlap(first, second) => {
+                vec![*first, *second]
+            }
+            Issue::DanglingConnection { entity, .. }
+            | Issue::Unpowered { entity, .. }
+            | Issue::NothingToPickUp { entity, .. }
+            | Issue::NothingToDropOn { entity, .. }
+            | Issue::UnpairedUnderground { entity, .. }
+            | Issue::MisalignedFluidBoxes { entity, .. } => vec![*entity],
+            Issue::UndergroundTooLong { entrance, exit, .. } => vec![*entrance, *exit],
+            Issue::UndergroundWeaving {
+                entrance,
+                exit,
+                crossing,
+            } => vec![*entrance, *exit, *crossing],
+            Issue::InBookEntry { issue, .. } => issue.entity_numbers(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::DuplicateEntityNumber(entity) => {
+                write!(f, "entity_number {entity} is used more than once")
+            }
+            Issue::DuplicatePosition(first, second) => {
+                write!(f, "entities {first} and {second} are at the same position")
+            }
+            Issue::Overlap(first, second) => {
+                write!(f, "entities {first} and {second} overlap")
+            }
+            Issue::DanglingConnection { entity, target } => {
+                write!(f, "entity {entity} is connected to missing entity {target}")
+            }
+            Issue::Unpowered { entity, position } => write!(
+                f,
+                "entity {entity} at ({}, {}) is outside every pole's supply area",
+                position.x, position.y
+            ),
+            Issue::NothingToPickUp { entity, position } => write!(
+                f,
+                "inserter {entity} picks up from ({}, {}), where there is nothing",
+                position.x, position.y
+            ),
+            Issue::NothingToDropOn { entity, position } => write!(
+                f,
+                "inserter {entity} drops onto ({}, {}), where there is nothing",
+                position.x, position.y
+            ),
+            Issue::UnpairedUnderground { entity, position } => write!(
+                f,
+                "underground belt {entity} at ({}, {}) has nothing to pair with",
+                position.x, position.y
+            ),
+            Issue::UndergroundTooLong {
+                entrance,
+                exit,
+                distance,
+                max_distance,
+            } => write!(
+                f,
+                "underground belts {entrance} and {exit} are {distance} tiles apart, more than \
+                 {max_distance}"
+            ),
+            Issue::UndergroundWeaving {
+                entrance,
+                exit,
+                crossing,
+            } => write!(
+                f,
+                "underground belt {crossing} is between underground belts {entrance} and {exit}"
+            ),
+            Issue::MisalignedFluidBoxes {
+                entity,
+                fluids,
+                direction,
+                all,
+            } => {
+                let direction = format!("{direction:?}").to_lowercase();
+                let how_many = if *all { "all of them" } else { "more of them" };
+                write!(
+                    f,
+                    "the {} boxes of machine {entity} don't line up with the pipes next to it, \
+                     facing {direction} would line up {how_many}",
+                    fluids.join(", ")
+                )
+            }
+            Issue::DanglingLocomotive(entity) => {
+                write!(f, "schedule is assigned to missing locomotive {entity}")
+            }
+            Issue::IconIndexOutOfRange(index) => {
+                write!(f, "icon index {index} is not between 1 and {MAX_ICONS}")
+            }
+            Issue::DuplicateIconIndex(index) => {
+                write!(f, "icon index {index} is used more than once")
+            }
+            Issue::DuplicateBookIndex(index) => {
+                write!(f, "book index {index} is used more than once")
+            }
+            Issue::ActiveIndexOutOfRange(index) => {
+                write!(f, "active index {index} is not an entry of the book")
+            }
+            Issue::InBookEntry { index, issue } => write!(f, "book entry {index}: {issue}"),
+        }
+    }
+}
+
+/// Check icon indices are in range and unique.
+fn validate_icons(icons: &[Icon], issues: &mut Vec<Issue>) {
+    let mut seen = HashSet::new();
+    for icon in icons {
+        if icon.index.get() > MAX_ICONS {
+            issues.push(Issue::IconIndexOutOfRange(icon.index));
+        }
+        if !seen.insert(icon.index) {
+            issues.push(Issue::DuplicateIconIndex(icon.index));
+        }
+    }
+}
+
+/// Whether the game lets the two entities overlap, like crossing rails or a gate on a rail.
+fn may_overlap(first: &Entity, second: &Entity) -> bool {
+    let is_rail = |entity: &Entity| entity.name.contains("rail") && !entity.name.contains("signal");
+    let is_gate = |entity: &Entity| entity.name == "gate";
+
+    (is_rail(first) && (is_rail(second) || is_gate(second))) || (is_gate(first) && is_rail(second))
+}
+
+/// Check for entities whose collision boxes overlap, skipping pairs already at the same position.
+fn validate_overlaps(
+    entities: &[Entity],
+    db: &PrototypeDb,
+    same_position: &HashSet<(NonZeroUsize, NonZeroUsize)>,
+    issues: &mut Vec<Issue>,
+) {
+    let boxes: Vec<_> = entities
+        .iter()
+        .filter_map(|entity| Some((entity, entity.collision_box(db)?)))
+        .collect();
+
+    // Bucket by tile so only entities near each other are compared
+    let mut tiles: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+    for (i, (_, bounds)) in boxes.iter().enumerate() {
+        for tile in bounds.tiles() {
+            tiles.entry(tile).or_default().push(i);
+        }
+    }
+
+    // Sorted, so issues come out in entity order whatever order the buckets are visited in
+    let mut overlapping = BTreeSet::new();
+    for bucket in tiles.values() {
+        for (n, &i) in bucket.iter().enumerate() {
+            for &j in &bucket[n + 1..] {
+                let ((first, first_box), (second, second_box)) = (boxes[i], boxes[j]);
+                if first_box.intersects(&second_box)
+                    && !may_overlap(first, second)
+                    && !same_position.contains(&(first.entity_number, second.entity_number))
+                {
+                    overlapping.insert((i, j));
+                }
+            }
+        }
+    }
+
+    issues.extend(
+        overlapping
+            .into_iter()
+            .map(|(i, j)| Issue::Overlap(boxes[i].0.entity_number, boxes[j].0.entity_number)),
+    );
+}
+
+/// Check every electric entity is in the supply area of a pole.
+///
+/// Blueprints without poles are skipped, as they are usually meant to be placed next to poles
+/// that are already built.
+fn validate_power(blueprint: &Blueprint, db: &PrototypeDb, issues: &mut Vec<Issue>) {
+    let graph = PowerGraph::new(blueprint, db);
+    if graph.networks().is_empty() {
+        return;
+    }
+
+    for entity in blueprint.entities.iter().flatten() {
+        let Some(prototype) = entity.prototype(db) else {
+            continue;
+        };
+        if prototype.electric
+            && prototype.electric_pole.is_none()
+            && !graph.is_powered(entity.entity_number.get())
+        {
+            issues.push(Issue::Unpowered {
+                entity: entity.entity_number,
+                position: entity.position.clone(),
+            });
+        }
+    }
+}
+
+/// Check every inserter picks up from and drops onto something: a belt, chest, machine, or
+/// anything else that isn't another inserter or an electric pole.
+fn validate_inserters(entities: &[Entity], db: &PrototypeDb, issues: &mut Vec<Issue>) {
+    let mut occupied: HashMap<(i64, i64), Vec<NonZeroUsize>> = HashMap::new();
+    for entity in entities {
+        let holds_items = entity.prototype(db).is_none_or(|prototype| {
+            prototype.inserter.is_none() && prototype.electric_pole.is_none()
+        });
+        if !holds_items {
+            continue;
+        }
+        match entity.footprint(db) {
+            Some(bounds) => {
+                for x in bounds.min_x.round() as i64..bounds.max_x.round() as i64 {
+                    for y in bounds.min_y.round() as i64..bounds.max_y.round() as i64 {
+                        occupied
+                            .entry((x, y))
+                            .or_default()
+                            .push(entity.entity_number);
+                    }
+                }
+            }
+            None => {
+                let tile = (
+                    entity.position.x.floor() as i64,
+                    entity.position.y.floor() as i64,
+                );
+                occupied.entry(tile).or_default().push(entity.entity_number);
+            }
+        }
+    }
+
+    let reaches = |entity: &Entity, position: &Position| {
+        let tile = (position.x.floor() as i64, position.y.floor() as i64);
+        occupied
+            .get(&tile)
+            .is_some_and(|numbers| numbers.iter().any(|&n| n != entity.entity_number))
+    };
+    for entity in entities {
+        let Some((pickup, drop)) = entity.inserter_positions(db) else {
+            continue;
+        };
+        if !reaches(entity, &pickup) {
+            issues.push(Issue::NothingToPickUp {
+                entity: entity.entity_number,
+                position: pickup,
+            });
+        }
+        if !reaches(entity, &drop) {
+            issues.push(Issue::NothingToDropOn {
+                entity: entity.entity_number,
+                position: drop,
+            });
+        }
+    }
+}
+
+/// Check every underground belt pairs up, and point out pairs that other undergrounds weave
+/// through.
+fn validate_undergrounds(blueprint: &Blueprint, db: &PrototypeDb, issues: &mut Vec<Issue>) {
+    // Each underground belt, with its tile and the most tiles between it and its partner
+    let undergrounds: BTreeMap<usize, (&Entity, (i64, i64), u32)> = blueprint
+        .entities
+        .iter()
+        .flatten()
+        .filter_map(|entity| {
+            let belt = entity.prototype(db)?.transport_belt?;
+            let BeltKind::Underground { max_distance } = belt.kind else {
+                return None;
+            };
+            let tile = (
+                entity.position.x.floor() as i64,
+                entity.position.y.floor() as i64,
+            );
+            Some((entity.entity_number.get(), (entity, tile, max_distance)))
+        })
+        .collect();
+    let graph = BeltGraph::new(blueprint, db);
+
+    let mut pairs = Vec::new();
+    let mut unpaired = BTreeSet::new();
+    for (&number, &(entity, _, _)) in &undergrounds {
+        match graph.kind(number) {
+            Some(NodeKind::UndergroundInput) => match graph.next(number) {
+                [exit] => pairs.push((number, *exit)),
+                _ => {
+                    unpaired.insert(number);
+                }
+            },
+            Some(NodeKind::UndergroundOutput) => {
+                let paired = graph
+                    .previous(number)
+                    .iter()
+                    .any(|&from| graph.kind(from) == Some(NodeKind::UndergroundInput));
+                if !paired {
+                    unpaired.insert(number);
+                }
+            }
+            // Facing between the cardinal directions
+            _ => {
+                issues.push(Issue::UnpairedUnderground {
+                    entity: entity.entity_number,
+                    position: entity.position.clone(),
+                });
+            }
+        }
+    }
+
+    // Unpaired entrances and exits that line up, but too far apart
+    let mut too_long = Vec::new();
+    for &number in &unpaired {
+        let (entrance, tile, max_distance) = undergrounds[&number];
+        if graph.kind(number) != Some(NodeKind::UndergroundInput) {
+            continue;
+        }
+        let Some((dx, dy)) = graph.direction(number).and_then(|d| d.tile_offset()) else {
+            continue;
+        };
+        let exit = unpaired
+            .iter()
+            .filter_map(|&other| {
+                let (exit, other_tile, _) = undergrounds[&other];
+                let (x, y) = (other_tile.0 - tile.0, other_tile.1 - tile.1);
+                let distance = x * dx as i64 + y * dy as i64;
+                let in_line = x * dy as i64 - y * dx as i64 == 0;
+                let pairs_with = graph.kind(other) == Some(NodeKind::UndergroundOutput)
+                    && graph.direction(other) == graph.direction(number)
+                    && exit.name == entrance.name;
+                (pairs_with && in_line && distance > 0).then_some((distance, other))
+            })
+            .min();
+        if let Some((distance, exit)) = exit {
+            too_long.push((number, exit, distance as u32, max_distance));
+        }
+    }
+    for &(entrance, exit, _, _) in &too_long {
+        unpaired.remove(&entrance);
+        unpaired.remove(&exit);
+    }
+
+    for number in unpaired {
+        let (entity, _, _) = undergrounds[&number];
+        issues.push(Issue::UnpairedUnderground {
+            entity: entity.entity_number,
+            position: entity.position.clone(),
+        });
+    }
+    for (entrance, exit, distance, max_distance) in too_long {
+        issues.push(Issue::UndergroundTooLong {
+            entrance: undergrounds[&entrance].0.entity_number,
+            exit: undergrounds[&exit].0.entity_number,
+            distance,
+            max_distance,
+        });
+    }
+
+    for (entrance, exit) in pairs {
+        let ((start, from), (_, to)) = (
+            (undergrounds[&entrance].0, undergrounds[&entrance].1),
+            (undergrounds[&exit].0, undergrounds[&exit].1),
+        );
+        let between = |tile: (i64, i64)| {
+            let inside = |value: i64, a: i64, b: i64| a.min(b) <= value && value <= a.max(b);
+            tile != from
+                && tile != to
+                && inside(tile.0, from.0, to.0)
+                && inside(tile.1, from.1, to.1)
+        };
+        let axis = graph.direction(entrance);
+        for (&other, &(crossing, tile, _)) in &undergrounds {
+            let parallel = graph.direction(other).is_some_and(|direction| {
+                Some(direction) == axis || Some(direction.opposite()) == axis
+            });
+            if other != entrance && other != exit && parallel && between(tile) {
+                issues.push(Issue::UndergroundWeaving {
+                    entrance: start.entity_number,
+                    exit: undergrounds[&exit].0.entity_number,
+                    crossing: crossing.entity_number,
+                });
+            }
+        }
+    }
+}
+
+/// Check the fluid boxes the recipes of crafting machines use line up with the pipes around
+/// them, and point out machines that would connect more of them facing another way.
+///
+/// Machines that can't connect any better are left alone, as their pipes are usually meant to be
+/// laid after they are built.
+fn validate_fluid_boxes(entities: &[Entity], db: &PrototypeDb, issues: &mut Vec<Issue>) {
+    // The tiles pipe connections lead into, with the way they face and the entity they're from
+    let mut leading_into: HashMap<(i64, i64), Vec<(Direction, NonZeroUsize)>> = HashMap::new();
+    for entity in entities {
+        let Some(prototype) = entity.prototype(db) else {
+            continue;
+        };
+        let connections = prototype
+            .fluid_boxes
+            .iter()
+            .flat_map(|fluid_box| &fluid_box.connections)
+            .filter(|connection| connection.underground_distance.is_none());
+        for connection in connections {
+            let connection = connection.of_entity(entity);
+            let Some((dx, dy)) = connection.direction.tile_offset() else {
+                continue;
+            };
+            let tile = (
+                (entity.position.x + connection.x).floor() as i64 + dx as i64,
+                (entity.position.y + connection.y).floor() as i64 + dy as i64,
+            );
+            leading_into
+                .entry(tile)
+                .or_default()
+                .push((connection.direction, entity.entity_number));
+        }
+    }
+
+    for entity in entities {
+        let Some(prototype) = entity.prototype(db) else {
+            continue;
+        };
+        if prototype.crafting_machine.is_none() {
+            continue;
+        }
+        let used = fixed_fluids(entity, prototype, db);
+        if used.is_empty() {
+            continue;
+        }
+        let from_others = |tile| {
+            leading_into
+                .get(&tile)
+                .into_iter()
+                .flatten()
+                .filter(|&&(_, from)| from != entity.entity_number)
+        };
+        let mirror = entity.mirror.unwrap_or(false);
+        // The fluids of the boxes that nothing connects to, were the machine facing `direction`
+        let unconnected = |direction: Direction| -> Vec<String> {
+            used.iter()
+                .filter(|&(&index, _)| {
+                    !prototype.fluid_boxes[index]
+                        .connections
+                        .iter()
+                        .any(|connection| {
+                            let connection = connection.oriented(direction, mirror);
+                            let tile = (
+                                (entity.position.x + connection.x).floor() as i64,
+                                (entity.position.y + connection.y).floor() as i64,
+                            );
+                            from_others(tile)
+                                .any(|&(facing, _)| facing == connection.direction.opposite())
+                        })
+                })
+                .map(|(_, fluid)| fluid.clone())
+                .collect()
+        };
+        let facing = entity.direction.unwrap_or_default();
+        let fluids = unconnected(facing);
+        if fluids.is_empty() {
+            continue;
+        }
+        // Only to directions the machine fits in the same space facing
+        let best = Direction::ALL
+            .into_iter()
+            .filter(|direction| direction.is_cardinal() && *direction != facing)
+            .filter(|&direction| prototype.tile_size(direction) == prototype.tile_size(facing))
+            .map(|direction| (unconnected(direction).len(), direction))
+            .min_by_key(|&(count, _)| count);
+        if let Some((count, direction)) = best.filter(|&(count, _)| count < fluids.len()) {
+            issues.push(Issue::MisalignedFluidBoxes {
+                entity: entity.entity_number,
+                fluids,
+                direction,
+                all: count == 0,
+            });
+        }
+    }
+}
+
+impl Blueprint {
+    /// Check the blueprint for problems the schema doesn't catch, like wires to missing entities.
+    ///
+    /// Entity sizes come from the [global](PrototypeDb::global) prototypes, see
+    /// [Blueprint::validate_with].
+    pub fn validate(&self) -> Vec<Issue> {
+        self.validate_with(PrototypeDb::global())
+    }
+
+    /// Check the blueprint for problems the schema doesn't catch, with entity sizes from `db`.
+    ///
+    /// Entities `db` doesn't know are never reported as overlapping or unpowered, and count as
+    /// covering the tile they are centred on for inserters to reach.
+    pub fn validate_with(&self, db: &PrototypeDb) -> Vec<Issue> {
+        let mut issues = Vec::new();
+        let entities = self.entities.as_deref().unwrap_or_default();
+
+        let mut numbers = HashSet::new();
+        for entity in entities {
+            if !numbers.insert(entity.entity_number) {
+                issues.push(Issue::DuplicateEntityNumber(entity.entity_number));
+            }
+        }
+
+        // Positions are compared exactly, as they are always multiples of 0.5 in practice
+        let mut positions = HashMap::new();
+        let mut same_position = HashSet::new();
+        for entity in entities {
+            let key = (entity.position.x.to_bits(), entity.position.y.to_bits());
+            if let Some(first) = positions.insert(key, entity.entity_number) {
+                issues.push(Issue::DuplicatePosition(first, entity.entity_number));
+                same_position.insert((first, entity.entity_number));
+            }
+        }
+
+        validate_overlaps(entities, db, &same_position, &mut issues);
+        validate_power(self, db, &mut issues);
+        validate_inserters(entities, db, &mut issues);
+        validate_undergrounds(self, db, &mut issues);
+        validate_fluid_boxes(entities, db, &mut issues);
+
+        let mut dangling = |entity: NonZeroUsize, target: NonZeroUsize| {
+            if !numbers.contains(&target) {
+                issues.push(Issue::DanglingConnection { entity, target });
+            }
+        };
+        for wire in self.wires.iter().flatten() {
+            dangling(wire.source_entity, wire.target_entity);
+            dangling(wire.target_entity, wire.source_entity);
+        }
+        for entity in entities {
+            for neighbour in entity.neighbors.iter().flatten() {
+                dangling(entity.entity_number, *neighbour);
+            }
+            let points = entity
+                .connections
+                .iter()
+                .flat_map(|connection| [&connection.first, &connection.second])
+                .flatten();
+            for point in points {
+                for data in point.red.iter().chain(&point.green).flatten() {
+                    dangling(entity.entity_number, data.entity_id);
+                }
+            }
+        }
+
+        for schedule in self.schedules.iter().flatten() {
+            for locomotive in &schedule.locomotives {
+                if !numbers.contains(locomotive) {
+                    issues.push(Issue::DanglingLocomotive(*locomotive));
+                }
+            }
+        }
+
+        validate_icons(&self.icons, &mut issues);
+
+        issues
+    }
+}
+
+impl BlueprintBook {
+    /// Check the book and everything in it for problems the schema doesn't catch.
+    pub fn validate(&self) -> Vec<Issue> {
+        self.validate_with(PrototypeDb::global())
+    }
+
+    /// Check the book and everything in it, with entity sizes from `db`.
+    pub fn validate_with(&self, db: &PrototypeDb) -> Vec<Issue> {
+        let mut issues = Vec::new();
+
+        let mut indices = HashSet::new();
+        for entry in &self.blueprints {
+            if !indices.insert(entry.index) {
+                issues.push(Issue::DuplicateBookIndex(entry.index));
+            }
+        }
+        // An empty book has nothing to select
+        if !self.blueprints.is_empty() && !indices.contains(&self.active_index) {
+            issues.push(Issue::ActiveIndexOutOfRange(self.active_index));
+        }
+
+        validate_icons(&self.icons, &mut issues);
+
+        for entry in &self.blueprints {
+            issues.extend(entry.content.validate_with(db).into_iter().map(|issue| {
+                Issue::InBookEntry {
+                    index: entry.index,
+                    issue: Box::new(issue),
+                }
+            }));
+        }
+
+        issues
+    }
+}
+
+impl BlueprintEnvelope {
+    /// Check the contents for problems the schema doesn't catch.
+    pub fn validate(&self) -> Vec<Issue> {
+        self.validate_with(PrototypeDb::global())
+    }
+
+    /// Check the contents, with entity sizes from `db`.
+    pub fn validate_with(&self, db: &PrototypeDb) -> Vec<Issue> {
+        match self {
+            BlueprintEnvelope::Blueprint(blueprint) => blueprint.validate_with(db),
+            BlueprintEnvelope::BlueprintBook(book) => book.validate_with(db),
+            BlueprintEnvelope::DeconstructionPlanner(planner) => {
+                let mut issues = Vec::new();
+                if let Some(settings) = &planner.settings {
+                    validate_icons(&settings.icons, &mut issues);
+                }
+                issues
+            }
+            BlueprintEnvelope::UpgradePlanner(planner) => {
+                let mut issues = Vec::new();
+                if let Some(settings) = &planner.settings {
+                    validate_icons(&settings.icons, &mut issues);
+                }
+                issues
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        blueprint::{BlueprintBuilder, BuildError, EntityBuilder, EntityDetails, IoType},
+        bp,
+    };
+
+    /// An underground belt of the prototype `name` facing north, on the tile `(x, y)`.
+    fn underground(name: &str, x: i64, y: i64, io_type: IoType) -> EntityBuilder {
+        EntityBuilder::new(name)
+            .at_tile(x, y)
+            .details(EntityDetails::UndergroundBelt {
+                io_type: Some(io_type),
+            })
+    }
+
+    /// Whether any of `issues` says a machine's fluid boxes don't line up.
+    fn misaligned(issues: &[Issue]) -> bool {
+        issues
+            .iter()
+            .any(|issue| matches!(issue, Issue::MisalignedFluidBoxes { .. }))
+    }
+
+    #[test]
+    fn overlapping_entities_are_errors() {
+        let db = PrototypeDb::vanilla();
+        let error = bp! {
+            "assembling-machine-1" @ (0, 0);
+            "iron-chest" @ (2, 2);
+            "iron-chest" @ (3, 0);
+        }
+        .build_with(db)
+        .unwrap_err();
+        let overlap = Issue::Overlap(NonZeroUsize::MIN, NonZeroUsize::new(2).unwrap());
+        assert_eq!(error, BuildError::Invalid(vec![overlap]));
+    }
+
+    #[test]
+    fn gates_may_sit_on_rails_and_entities_on_one_spot_are_reported_once() {
+        let db = PrototypeDb::vanilla();
+        let blueprint = bp! {
+            "straight-rail" @ (0, 0);
+            "gate" @ (0, 0);
+            "gate" @ (1, 1);
+            "iron-chest" @ (4, 0);
+            "wooden-chest" @ (4, 0);
+        }
+        .build_with(db)
+        .unwrap();
+        let (first, second) = (NonZeroUsize::new(4).unwrap(), NonZeroUsize::new(5).unwrap());
+        assert_eq!(
+            blueprint.validate_with(db),
+            [Issue::DuplicatePosition(first, second)]
+        );
+    }
+
+    #[test]
+    fn machines_outside_every_supply_area_are_unpowered() {
+        let db = PrototypeDb::vanilla();
+        let blueprint = bp! {
+            "small-electric-pole" @ (0, 0);
+            "assembling-machine-1" @ (2, 0);
+            "assembling-machine-1" @ (6, 0);
+        }
+        .build_with(db)
+        .unwrap();
+        assert_eq!(
+            blueprint.validate_with(db),
+            [Issue::Unpowered {
+                entity: NonZeroUsize::new(3).unwrap(),
+                position: Position { x: 7.5, y: 1.5 },
+            }]
+        );
+    }
+
+    #[test]
+    fn blueprints_without_poles_are_not_checked_for_power() {
+        let db = PrototypeDb::vanilla();
+        let blueprint = bp! { "assembling-machine-1" @ (0, 0) }
+            .build_with(db)
+            .unwrap();
+        assert!(blueprint.validate_with(db).is_empty());
+    }
+
+    #[test]
+    fn inserters_reach_chests_and_machines_beyond_belts() {
+        let db = PrototypeDb::vanilla();
+        let blueprint = bp! {
+            "iron-chest" @ (0, 0);
+            "inserter" @ (0, 1);
+            "long-handed-inserter" @ (1, 1);
+            "transport-belt" @ (1, 0);
+            "assembling-machine-1" @ (0, 2);
+            "iron-chest" @ (1, -1);
+        }
+        .build_with(db)
+        .unwrap();
+        assert!(blueprint.validate_with(db).is_empty());
+    }
+
+    #[test]
+    fn inserters_with_nothing_to_reach_are_reported() {
+        let db = PrototypeDb::vanilla();
+        // Each picks up from or drops onto the other, which holds no items
+        let blueprint = bp! {
+            "inserter" @ (0, 0);
+            "inserter" @ (0, -1);
+        }
+        .build_with(db)
+        .unwrap();
+        let (first, second) = (NonZeroUsize::MIN, NonZeroUsize::new(2).unwrap());
+        assert_eq!(
+            blueprint.validate_with(db),
+            [
+                Issue::NothingToPickUp {
+                    entity: first,
+                    position: Position { x: 0.5, y: -0.5 },
+                },
+                Issue::NothingToDropOn {
+                    entity: first,
+                    position: Position { x: 0.5, y: 1.7 },
+                },
+                Issue::NothingToPickUp {
+                    entity: second,
+                    position: Position { x: 0.5, y: -1.5 },
+                },
+                Issue::NothingToDropOn {
+                    entity: second,
+                    position: Position { x: 0.5, y: 0.7 },
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn undergrounds_pair_within_reach_of_their_tier() {
+        let db = PrototypeDb::vanilla();
+        let blueprint = BlueprintBuilder::new()
+            .entity(underground("underground-belt", 0, 0, IoType::Input))
+            .entity(underground("underground-belt", 0, -5, IoType::Output))
+            .entity(underground("underground-belt", 2, 0, IoType::Input))
+            .entity(underground("underground-belt", 2, -6, IoType::Output))
+            .entity(underground("underground-belt", 4, 0, IoType::Output))
+            .build_with(db)
+            .unwrap();
+        assert_eq!(
+            blueprint.validate_with(db),
+            [
+                Issue::UnpairedUnderground {
+                    entity: NonZeroUsize::new(5).unwrap(),
+                    position: Position { x: 4.5, y: 0.5 },
+                },
+                Issue::UndergroundTooLong {
+                    entrance: NonZeroUsize::new(3).unwrap(),
+                    exit: NonZeroUsize::new(4).unwrap(),
+                    distance: 6,
+                    max_distance: 5,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn undergrounds_of_another_tier_weave_between_a_pair() {
+        let db = PrototypeDb::vanilla();
+        let blueprint = BlueprintBuilder::new()
+            .entity(underground("underground-belt", 0, 0, IoType::Input))
+            .entity(underground("underground-belt", 0, -5, IoType::Output))
+            .entity(underground("fast-underground-belt", 0, -1, IoType::Input))
+            .entity(underground("fast-underground-belt", 0, -4, IoType::Output))
+            .build_with(db)
+            .unwrap();
+        let number = |n| NonZeroUsize::new(n).unwrap();
+        let weaving = |crossing| Issue::UndergroundWeaving {
+            entrance: number(1),
+            exit: number(2),
+            crossing: number(crossing),
+        };
+        assert_eq!(blueprint.validate_with(db), [weaving(3), weaving(4)]);
+    }
+
+    #[test]
+    fn flipped_chemical_plants_line_up_with_their_pipes() {
+        let db = PrototypeDb::vanilla();
+        // Flipped, the pipe beside the plant would line up with the input were the plant facing
+        // east without being mirrored
+        let mut blueprint = bp! {
+            "chemical-plant" @ (0, 0) recipe "plastic-bar";
+            "pipe" @ (0, -1);
+            "pipe" @ (-1, 0);
+        }
+        .build_with(db)
+        .unwrap();
+        assert!(!misaligned(&blueprint.validate_with(db)));
+
+        blueprint.flip_horizontal();
+        assert!(!misaligned(&blueprint.validate_with(db)));
+        blueprint.flip_vertical();
+        assert!(!misaligned(&blueprint.validate_with(db)));
+    }
+
+    #[test]
+    fn chemical_plants_facing_away_from_their_pipes_are_misaligned() {
+        let db = PrototypeDb::vanilla();
+        let blueprint = bp! {
+            "chemical-plant" @ (0, 0) recipe "plastic-bar";
+            "pipe" @ (3, 0);
+        }
+        .build_with(db)
+        .unwrap();
+        let issues = blueprint.validate_with(db);
+        assert!(issues.contains(&Issue::MisalignedFluidBoxes {
+            entity: NonZeroUsize::MIN,
+            fluids: vec!["petroleum-gas".to_owned()],
+            direction: Direction::East,
+            all: true,
+        }));
+    }
+}
@@ -0,0 +1,151 @@
+//! Game versions, as stored in blueprints and written by players.
+
+use core::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(from = "u64", into = "u64")]
+/// A Factorio game version. Versions compare in release order.
+pub struct Version {
+    major: u16,
+    minor: u16,
+    patch: u16,
+    developer: u16,
+}
+
+impl Version {
+    /// The version with these numbers, e.g. `Version::new(2, 0, 28, 0)` for 2.0.28.
+    pub const fn new(major: u16, minor: u16, patch: u16, developer: u16) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+            developer,
+        }
+    }
+
+    /// The major version, e.g. 1 for 1.1.110.
+    pub fn major(&self) -> u16 {
+        self.major
+    }
+
+    /// The minor version, e.g. 1 for 1.1.110.
+    pub fn minor(&self) -> u16 {
+        self.minor
+    }
+
+    /// The patch version, e.g. 110 for 1.1.110.
+    pub fn patch(&self) -> u16 {
+        self.patch
+    }
+
+    /// The developer version, 0 for released versions.
+    pub fn developer(&self) -> u16 {
+        self.developer
+    }
+
+    /// Whether this is the version `major.minor.patch` or a later one.
+    pub fn is_at_least(&self, major: u16, minor: u16, patch: u16) -> bool {
+        *self >= Self::new(major, minor, patch, 0)
+    }
+}
+
+impl fmt::Display for Version {
+    /// Writes e.g. "1.1.110", with the developer version after the patch only if it isn't 0.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        if self.developer != 0 {
+            write!(f, ".{}", self.developer)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("invalid version {0:?}, expected one like \"1.1.110\"")]
+/// A string that isn't a version.
+pub struct ParseVersionError(String);
+
+impl FromStr for Version {
+    type Err = ParseVersionError;
+
+    /// Reads e.g. "2.0" or "1.1.110", with the numbers left out taken as 0. A developer version
+    /// may follow the patch.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseVersionError(s.to_owned());
+        let mut numbers = [0; 4];
+        let mut parts = s.trim().split('.');
+        for number in &mut numbers {
+            match parts.next() {
+                Some(part) => *number = part.parse().map_err(|_| error())?,
+                None => break,
+            }
+        }
+        if parts.next().is_some() || !s.contains('.') {
+            return Err(error());
+        }
+        let [major, minor, patch, developer] = numbers;
+        Ok(Self::new(major, minor, patch, developer))
+    }
+}
+
+impl From<u64> for Version {
+    fn from(value: u64) -> Self {
+        // The factorio version number consists of four consecutive 2-byte unsigned values, major version first.
+        let bytes = value.to_be_bytes();
+
+        let mut version_numbers: [u16; 4] = [0; 4];
+        for (i, number) in version_numbers.iter_mut().enumerate() {
+            *number = u16::from_be_bytes([bytes[i * 2], bytes[i * 2 + 1]])
+        }
+
+        Self {
+            major: version_numbers[0],
+            minor: version_numbers[1],
+            patch: version_numbers[2],
+            developer: version_numbers[3],
+        }
+    }
+}
+
+impl From<Version> for u64 {
+    fn from(value: Version) -> Self {
+        let mut bytes = [0; 8];
+        for (i, number) in [value.major, value.minor, value.patch, value.developer]
+            .iter()
+            .enumerate()
+        {
+            bytes[i * 2..i * 2 + 2].copy_from_slice(&number.to_be_bytes());
+        }
+
+        u64::from_be_bytes(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_round_trip_through_numbers_and_text() {
+        let version = Version::new(2, 0, 28, 1);
+        assert_eq!(u64::from(version), 562949955256321);
+        assert_eq!(Version::from(562949955256321), version);
+        assert_eq!(version.to_string(), "2.0.28.1");
+        assert_eq!("2.0.28.1".parse(), Ok(version));
+        assert_eq!("1.1".parse(), Ok(Version::new(1, 1, 0, 0)));
+        assert!("2".parse::<Version>().is_err());
+        assert!("1.1.x".parse::<Version>().is_err());
+        assert!("1.1.1.1.1".parse::<Version>().is_err());
+
+        assert!(Version::new(1, 1, 110, 0) < Version::new(2, 0, 0, 0));
+        assert!(version.is_at_least(2, 0, 28));
+        assert!(!version.is_at_least(2, 0, 29));
+        assert_eq!(
+            (version.major(), version.minor(), version.patch()),
+            (2, 0, 28)
+        );
+    }
+}
@@ -0,0 +1,337 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::blueprint::{Blueprint, WireConnectorId};
+
+mod simulate;
+
+pub use simulate::{Signal, Signals, Simulator};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+/// The colour of a circuit wire. Red and green wires form separate networks.
+pub enum NetworkColor {
+    /// Red wire.
+    Red,
+    /// Green wire.
+    Green,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+/// A circuit connection point of an entity.
+pub enum ConnectorKind {
+    /// A combinator's input, or the only connection point of any other entity.
+    Input,
+    /// A combinator's output.
+    Output,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+/// A connection point of a particular entity.
+pub struct Connector {
+    /// The entity_number of the entity.
+    pub entity_number: usize,
+    /// Which of the entity's connection points.
+    pub kind: ConnectorKind,
+}
+
+impl Connector {
+    /// The input of a combinator, or the only connection point of any other entity.
+    pub fn input(entity_number: usize) -> Self {
+        Self {
+            entity_number,
+            kind: ConnectorKind::Input,
+        }
+    }
+
+    /// The output of a combinator.
+    pub fn output(entity_number: usize) -> Self {
+        Self {
+            entity_number,
+            kind: ConnectorKind::Output,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+/// A single red or green wire between two connectors.
+pub struct CircuitWire {
+    /// One end of the wire, the lower of the two.
+    pub from: Connector,
+    /// The other end of the wire.
+    pub to: Connector,
+    /// The colour of the wire.
+    pub color: NetworkColor,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A circuit network: connectors joined by wires of one colour, which all see the same signals.
+pub struct Network {
+    /// The colour of the wires.
+    pub color: NetworkColor,
+    /// The connectors on the network, in order.
+    pub connectors: Vec<Connector>,
+}
+
+impl Network {
+    /// Whether `connector` is on the network.
+    pub fn contains(&self, connector: Connector) -> bool {
+        self.connectors.binary_search(&connector).is_ok()
+    }
+
+    /// The entities with a connector on the network.
+    pub fn entity_numbers(&self) -> BTreeSet<usize> {
+        self.connectors
+            .iter()
+            .map(|connector| connector.entity_number)
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// The red and green wires of a blueprint, as a graph of connectors.
+///
+/// Built from 2.0 `wires` or older per-entity `connections`, whichever the blueprint uses. Copper
+/// wires are left out.
+pub struct CircuitGraph {
+    wires: Vec<CircuitWire>,
+    adjacent: BTreeMap<(NetworkColor, Connector), Vec<Connector>>,
+}
+
+impl CircuitGraph {
+    /// The circuit wires of `blueprint`.
+    pub fn new(blueprint: &Blueprint) -> Self {
+        let mut wires = BTreeSet::new();
+        let mut add = |a: Connector, b: Connector, color: NetworkColor| {
+            wires.insert(CircuitWire {
+                from: a.min(b),
+                to: a.max(b),
+                color,
+            });
+        };
+
+        for wire in blueprint.wires.iter().flatten() {
+            let end = |entity: usize, connector: WireConnectorId| match connector {
+                WireConnectorId::CircuitRed => Some((Connector::input(entity), NetworkColor::Red)),
+                WireConnectorId::CircuitGreen => {
+                    Some((Connector::input(entity), NetworkColor::Green))
+                }
+                WireConnectorId::CombinatorOutputRed => {
+                    Some((Connector::output(entity), NetworkColor::Red))
+                }
+                WireConnectorId::CombinatorOutputGreen => {
+                    Some((Connector::output(entity), NetworkColor::Green))
+                }
+                WireConnectorId::PoleCopper | WireConnectorId::PowerSwitchRightCopper => None,
+            };
+            let source = end(wire.source_entity.get(), wire.source_connector);
+            let target = end(wire.target_entity.get(), wire.target_connector);
+            if let (Some((from, color)), Some((to, _))) = (source, target) {
+                add(from, to, color);
+            }
+        }
+
+        // Connections are listed at both ends, which the set takes care of
+        for entity in blueprint.entities.iter().flatten() {
+            let number = entity.entity_number.get();
+            for connection in entity.connections.iter() {
+                let points = [
+                    (Connector::input(number), &connection.first),
+                    (Connector::output(number), &connection.second),
+                ];
+                for (from, point) in points {
+                    let Some(point) = point else {
+                        continue;
+                    };
+                    let colored = [
+                        (&point.red, NetworkColor::Red),
+                        (&point.green, NetworkColor::Green),
+                    ];
+                    for (data, color) in colored {
+                        for data in data.iter().flatten() {
+                            // The game stores the connection point of the other end here, 1 if absent
+                            let to = match data.circuit_id.clone().map_or(1, |id| id as u32) {
+                                2 => Connector::output(data.entity_id.get()),
+                                _ => Connector::input(data.entity_id.get()),
+                            };
+                            add(from, to, color);
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut adjacent: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for wire in &wires {
+            adjacent
+                .entry((wire.color, wire.from))
+                .or_default()
+                .push(wire.to);
+            adjacent
+                .entry((wire.color, wire.to))
+                .or_default()
+                .push(wire.from);
+        }
+
+        Self {
+            wires: wires.into_iter().collect(),
+            adjacent,
+        }
+    }
+
+    /// Every wire, each listed once, in order.
+    pub fn wires(&self) -> &[CircuitWire] {
+        &self.wires
+    }
+
+    /// Whether the blueprint has no circuit wires.
+    pub fn is_empty(&self) -> bool {
+        self.wires.is_empty()
+    }
+
+    /// Every connector with a wire of `color`, in order.
+    pub fn connectors(&self, color: NetworkColor) -> impl Iterator<Item = Connector> + '_ {
+        self.adjacent
+            .keys()
+            .filter(move |(c, _)| *c == color)
+            .map(|&(_, connector)| connector)
+    }
+
+    /// The connectors wired directly to `connector` with wires of `color`.
+    pub fn neighbors(&self, connector: Connector, color: NetworkColor) -> &[Connector] {
+        self.adjacent
+            .get(&(color, connector))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// The entities with at least one circuit wire.
+    pub fn entity_numbers(&self) -> BTreeSet<usize> {
+        self.wires
+            .iter()
+            .flat_map(|wire| [wire.from.entity_number, wire.to.entity_number])
+            .collect()
+    }
+
+    /// The network of `color` that `connector` is on, or `None` if it has no wire of that colour.
+    pub fn network(&self, connector: Connector, color: NetworkColor) -> Option<Network> {
+        if !self.adjacent.contains_key(&(color, connector)) {
+            return None;
+        }
+
+        let mut seen = BTreeSet::from([connector]);
+        let mut queue = vec![connector];
+        while let Some(next) = queue.pop() {
+            for &neighbor in self.neighbors(next, color) {
+                if seen.insert(neighbor) {
+                    queue.push(neighbor);
+                }
+            }
+        }
+        Some(Network {
+            color,
+            connectors: seen.into_iter().collect(),
+        })
+    }
+
+    /// Every network of `color`, ordered by their lowest connector.
+    pub fn networks(&self, color: NetworkColor) -> Vec<Network> {
+        let mut seen = BTreeSet::new();
+        let mut networks = Vec::new();
+        for connector in self.connectors(color) {
+            if seen.contains(&connector) {
+                continue;
+            }
+            if let Some(network) = self.network(connector, color) {
+                seen.extend(network.connectors.iter().copied());
+                networks.push(network);
+            }
+        }
+        networks
+    }
+
+    /// Every red network followed by every green network.
+    pub fn all_networks(&self) -> Vec<Network> {
+        let mut networks = self.networks(NetworkColor::Red);
+        networks.extend(self.networks(NetworkColor::Green));
+        networks
+    }
+
+    /// Whether two connectors are on the same network of `color`.
+    pub fn connected(&self, a: Connector, b: Connector, color: NetworkColor) -> bool {
+        self.network(a, color)
+            .is_some_and(|network| network.contains(b))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// A chest wired red to a combinator's input and green to another chest, with the
+    /// combinator's output wired red to a lamp.
+    fn blueprint(wires: serde_json::Value, connections: [serde_json::Value; 4]) -> Blueprint {
+        let [chest, combinator, lamp, other_chest] = connections;
+        serde_json::from_value(json!({
+            "item": "blueprint",
+            "version": 562949954076673u64,
+            "entities": [
+                {"entity_number": 1, "name": "iron-chest", "position": {"x": 0.5, "y": 0.5},
+                    "connections": chest},
+                {"entity_number": 2, "name": "arithmetic-combinator",
+                    "position": {"x": 2.0, "y": 0.5}, "connections": combinator},
+                {"entity_number": 3, "name": "small-lamp", "position": {"x": 3.5, "y": 0.5},
+                    "connections": lamp},
+                {"entity_number": 4, "name": "iron-chest", "position": {"x": 0.5, "y": 1.5},
+                    "connections": other_chest},
+            ],
+            "wires": wires,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn networks_of_both_formats_are_the_same() {
+        let wires = blueprint(
+            json!([[1, 1, 2, 1], [2, 3, 3, 1], [1, 2, 4, 2]]),
+            [json!(null), json!(null), json!(null), json!(null)],
+        );
+        let connections = blueprint(
+            json!(null),
+            [
+                json!({"1": {"red": [{"entity_id": 2}], "green": [{"entity_id": 4}]}}),
+                json!({
+                    "1": {"red": [{"entity_id": 1}]},
+                    "2": {"red": [{"entity_id": 3}]},
+                }),
+                json!({"1": {"red": [{"entity_id": 2, "circuit_id": 2}]}}),
+                json!({"1": {"green": [{"entity_id": 1}]}}),
+            ],
+        );
+        let graph = CircuitGraph::new(&wires);
+        assert_eq!(graph, CircuitGraph::new(&connections));
+
+        assert_eq!(graph.wires().len(), 3);
+        assert_eq!(graph.entity_numbers(), BTreeSet::from([1, 2, 3, 4]));
+        let red = graph.networks(NetworkColor::Red);
+        assert_eq!(red.len(), 2);
+        assert_eq!(
+            red[0].connectors,
+            [Connector::input(1), Connector::input(2)]
+        );
+        assert_eq!(
+            red[1].connectors,
+            [Connector::output(2), Connector::input(3)]
+        );
+        assert!(!graph.connected(Connector::input(1), Connector::input(3), NetworkColor::Red));
+        assert!(graph.connected(
+            Connector::input(4),
+            Connector::input(1),
+            NetworkColor::Green
+        ));
+        assert_eq!(
+            graph.network(Connector::input(3), NetworkColor::Green),
+            None
+        );
+        assert_eq!(graph.all_networks().len(), 3);
+    }
+}
@@ -0,0 +1,623 @@
+//! Stepping combinators tick by tick, the way the game updates them.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::blueprint::{
+    control_behavior::{
+        ArithmeticCombinatorParameters, CircuitNetworkSelection, DeciderCombinatorParameters,
+    },
+    Blueprint, Comparator, CompareType, Quality, SignalId, SignalType, Wildcard,
+};
+
+use super::{CircuitGraph, Connector, NetworkColor};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+/// A signal with the defaults of [SignalId] filled in, so equal signals compare equal.
+pub struct Signal {
+    /// The type of the signal.
+    pub kind: SignalType,
+    /// The prototype name of the signal.
+    pub name: String,
+    /// The quality of the signal.
+    pub quality: Quality,
+}
+
+impl From<&SignalId> for Signal {
+    fn from(id: &SignalId) -> Self {
+        Self {
+            kind: id.kind(),
+            name: id.name.clone(),
+            quality: id.quality.clone().unwrap_or_default(),
+        }
+    }
+}
+
+impl From<&Signal> for SignalId {
+    fn from(signal: &Signal) -> Self {
+        Self {
+            name: signal.name.clone(),
+            signal_type: Some(signal.kind.clone()),
+            quality: Some(signal.quality.clone()),
+        }
+    }
+}
+
+/// The values of the signals on a network or output. Signals with a value of 0 are left out.
+pub type Signals = BTreeMap<Signal, i32>;
+
+/// Add `value` to `signal`, wrapping like the game and dropping signals that reach 0.
+fn add(signals: &mut Signals, signal: Signal, value: i32) {
+    let total = signals
+        .get(&signal)
+        .copied()
+        .unwrap_or(0)
+        .wrapping_add(value);
+    if total == 0 {
+        signals.remove(&signal);
+    } else {
+        signals.insert(signal, total);
+    }
+}
+
+/// What a combinator reads on its input, per wire colour.
+struct Inputs {
+    red: Signals,
+    green: Signals,
+}
+
+impl Inputs {
+    /// The value of `signal` on the selected networks. Both if `networks` is absent.
+    fn value(&self, signal: &Signal, networks: Option<&CircuitNetworkSelection>) -> i32 {
+        let (red, green) = networks.map_or((true, true), |n| (n.reads_red(), n.reads_green()));
+        let get = |signals: &Signals| signals.get(signal).copied().unwrap_or(0);
+        let red = if red { get(&self.red) } else { 0 };
+        let green = if green { get(&self.green) } else { 0 };
+        red.wrapping_add(green)
+    }
+
+    /// Every signal on the selected networks. Both if `networks` is absent.
+    fn all(&self, networks: Option<&CircuitNetworkSelection>) -> Signals {
+        let (red, green) = networks.map_or((true, true), |n| (n.reads_red(), n.reads_green()));
+        let mut all = Signals::new();
+        for (read, signals) in [(red, &self.red), (green, &self.green)] {
+            for (signal, &value) in signals.iter().filter(|_| read) {
+                add(&mut all, signal.clone(), value);
+            }
+        }
+        all
+    }
+}
+
+/// One side of a calculation or comparison.
+enum Operand {
+    /// The `each` wildcard, standing for every input signal in turn.
+    Each,
+    /// Any other signal, or a constant.
+    Value(i32),
+}
+
+impl Operand {
+    fn new(
+        inputs: &Inputs,
+        signal: Option<&SignalId>,
+        constant: Option<i32>,
+        networks: Option<&CircuitNetworkSelection>,
+    ) -> Self {
+        match signal {
+            Some(id) if id.wildcard() == Some(Wildcard::Each) => Operand::Each,
+            Some(id) => Operand::Value(inputs.value(&id.into(), networks)),
+            None => Operand::Value(constant.unwrap_or(0)),
+        }
+    }
+
+    /// The value, with `each` standing for `signal` read from `networks`.
+    fn resolve(
+        &self,
+        inputs: &Inputs,
+        signal: &Signal,
+        networks: Option<&CircuitNetworkSelection>,
+    ) -> i32 {
+        match self {
+            Operand::Each => inputs.value(signal, networks),
+            Operand::Value(value) => *value,
+        }
+    }
+}
+
+/// The output of an arithmetic combinator.
+fn arithmetic(params: &ArithmeticCombinatorParameters, inputs: &Inputs) -> Signals {
+    let mut output = Signals::new();
+    let Some(output_id) = &params.output_signal else {
+        return output;
+    };
+    let operation = params.operation.unwrap_or_default();
+    let (first_networks, second_networks) = (
+        params.first_signal_networks.as_ref(),
+        params.second_signal_networks.as_ref(),
+    );
+    let first = Operand::new(
+        inputs,
+        params.first_signal.as_ref(),
+        params.first_constant,
+        first_networks,
+    );
+    let second = Operand::new(
+        inputs,
+        params.second_signal.as_ref(),
+        params.second_constant,
+        second_networks,
+    );
+
+    let each_networks = match (&first, &second) {
+        (Operand::Each, _) => first_networks,
+        (_, Operand::Each) => second_networks,
+        (Operand::Value(a), Operand::Value(b)) => {
+            if output_id.wildcard().is_none() {
+                add(&mut output, output_id.into(), operation.apply(*a, *b));
+            }
+            return output;
+        }
+    };
+
+    for signal in inputs.all(each_networks).into_keys() {
+        let result = operation.apply(
+            first.resolve(inputs, &signal, first_networks),
+            second.resolve(inputs, &signal, second_networks),
+        );
+        match output_id.wildcard() {
+            Some(Wildcard::Each) => add(&mut output, signal, result),
+            Some(_) => {}
+            None => add(&mut output, output_id.into(), result),
+        }
+    }
+    output
+}
+
+/// A single comparison of a decider combinator, in the same shape before and since 2.0.
+struct Condition<'a> {
+    first: Option<&'a SignalId>,
+    first_networks: Option<&'a CircuitNetworkSelection>,
+    second: Option<&'a SignalId>,
+    second_networks: Option<&'a CircuitNetworkSelection>,
+    constant: Option<i32>,
+    comparator: Comparator,
+    /// Whether the condition is joined to the previous one with `and` rather than `or`.
+    and: bool,
+}
+
+impl Condition<'_> {
+    fn uses_each(&self) -> bool {
+        [self.first, self.second]
+            .into_iter()
+            .flatten()
+            .any(|id| id.wildcard() == Some(Wildcard::Each))
+    }
+
+    /// Whether the condition holds, with `each` standing for `each` if given.
+    fn holds(&self, inputs: &Inputs, each: Option<&Signal>) -> bool {
+        let Some(first) = self.first else {
+            return false;
+        };
+        let rhs = match (self.second, each) {
+            (Some(id), Some(each)) if id.wildcard() == Some(Wildcard::Each) => {
+                inputs.value(each, self.second_networks)
+            }
+            (Some(id), _) => inputs.value(&id.into(), self.second_networks),
+            (None, _) => self.constant.unwrap_or(0),
+        };
+        let compare = |lhs: i32| self.comparator.evaluate(lhs, rhs);
+
+        match (first.wildcard(), each) {
+            (Some(Wildcard::Each), Some(each)) => compare(inputs.value(each, self.first_networks)),
+            (Some(Wildcard::Each), None) => false,
+            (Some(Wildcard::Anything), _) => {
+                inputs.all(self.first_networks).into_values().any(compare)
+            }
+            (Some(Wildcard::Everything), _) => {
+                inputs.all(self.first_networks).into_values().all(compare)
+            }
+            (None, _) => compare(inputs.value(&first.into(), self.first_networks)),
+        }
+    }
+}
+
+/// Whether the conditions hold together. `and` binds tighter than `or`, as in the game.
+fn conditions_hold(conditions: &[Condition], inputs: &Inputs, each: Option<&Signal>) -> bool {
+    let mut any = false;
+    let mut all = true;
+    for (i, condition) in conditions.iter().enumerate() {
+        if i > 0 && !condition.and {
+            any |= all;
+            all = true;
+        }
+        all &= condition.holds(inputs, each);
+    }
+    !conditions.is_empty() && (any || all)
+}
+
+/// A single output of a decider combinator, in the same shape before and since 2.0.
+struct Output<'a> {
+    signal: Option<&'a SignalId>,
+    copy_count_from_input: bool,
+    constant: i32,
+    networks: Option<&'a CircuitNetworkSelection>,
+}
+
+/// The output of a decider combinator.
+fn decider(params: &DeciderCombinatorParameters, inputs: &Inputs) -> Signals {
+    let (conditions, outputs) = if params.conditions.is_empty() && params.outputs.is_empty() {
+        // Before 2.0: a single condition and output
+        let condition = Condition {
+            first: params.first_signal.as_ref(),
+            first_networks: None,
+            second: params.second_signal.as_ref(),
+            second_networks: None,
+            constant: params.constant,
+            comparator: params.comparator.unwrap_or_default(),
+            and: false,
+        };
+        let output = Output {
+            signal: params.output_signal.as_ref(),
+            copy_count_from_input: params.copy_count_from_input.unwrap_or(true),
+            constant: 1,
+            networks: None,
+        };
+        (vec![condition], vec![output])
+    } else {
+        let conditions = params
+            .conditions
+            .iter()
+            .map(|condition| Condition {
+                first: condition.first_signal.as_ref(),
+                first_networks: condition.first_signal_networks.as_ref(),
+                second: condition.second_signal.as_ref(),
+                second_networks: condition.second_signal_networks.as_ref(),
+                constant: condition.constant,
+                comparator: condition.comparator.unwrap_or_default(),
+                and: condition.compare_type == Some(CompareType::And),
+            })
+            .collect();
+        let outputs = params
+            .outputs
+            .iter()
+            .map(|output| Output {
+                signal: output.signal.as_ref(),
+                copy_count_from_input: output.copy_count_from_input.unwrap_or(true),
+                constant: output.constant.unwrap_or(1),
+                networks: output.networks.as_ref(),
+            })
+            .collect();
+        (conditions, outputs)
+    };
+
+    // With `each` in a condition, it is checked once per input signal
+    let passing: Option<BTreeSet<Signal>> = if conditions.iter().any(Condition::uses_each) {
+        let candidates = inputs.all(None).into_keys();
+        let passing: BTreeSet<Signal> = candidates
+            .filter(|signal| conditions_hold(&conditions, inputs, Some(signal)))
+            .collect();
+        if passing.is_empty() {
+            return Signals::new();
+        }
+        Some(passing)
+    } else if conditions_hold(&conditions, inputs, None) {
+        None
+    } else {
+        return Signals::new();
+    };
+
+    let mut result = Signals::new();
+    for output in outputs {
+        let Some(id) = output.signal else {
+            continue;
+        };
+        let count = |signal: &Signal| match output.copy_count_from_input {
+            true => inputs.value(signal, output.networks),
+            false => output.constant,
+        };
+        match (id.wildcard(), &passing) {
+            (Some(Wildcard::Each), Some(passing)) => {
+                for signal in passing {
+                    add(&mut result, signal.clone(), count(signal));
+                }
+            }
+            (Some(Wildcard::Each | Wildcard::Everything), _) => {
+                for signal in inputs.all(output.networks).into_keys() {
+                    let value = count(&signal);
+                    add(&mut result, signal, value);
+                }
+            }
+            (Some(Wildcard::Anything), _) => {}
+            // A specific output of an `each` condition sums over the passing signals
+            (None, Some(passing)) => {
+                for signal in passing {
+                    add(&mut result, id.into(), count(signal));
+                }
+            }
+            (None, None) => add(&mut result, id.into(), count(&id.into())),
+        }
+    }
+    result
+}
+
+#[derive(Debug, Clone)]
+/// An entity the simulator updates.
+enum Combinator<'a> {
+    /// Outputs the same signals every tick.
+    Constant(Signals),
+    /// Calculates its output from its input.
+    Arithmetic(&'a ArithmeticCombinatorParameters),
+    /// Outputs signals when its conditions hold.
+    Decider(&'a DeciderCombinatorParameters),
+}
+
+#[derive(Debug, Clone)]
+/// Steps the circuit networks of a blueprint tick by tick.
+///
+/// Constant, arithmetic, and decider combinators are simulated. Like in the game, arithmetic and
+/// decider combinators take a tick to update their output. Other entities on the networks, such as
+/// chests or selector combinators, output nothing.
+pub struct Simulator<'a> {
+    graph: CircuitGraph,
+    /// The index in `networks` of the network each connector is on, per colour.
+    network_of: BTreeMap<(NetworkColor, Connector), usize>,
+    networks: usize,
+    combinators: BTreeMap<usize, Combinator<'a>>,
+    outputs: BTreeMap<usize, Signals>,
+    tick: u64,
+}
+
+impl<'a> Simulator<'a> {
+    /// A simulator at tick 0, with only constant combinators outputting anything.
+    pub fn new(blueprint: &'a Blueprint) -> Self {
+        let mut combinators = BTreeMap::new();
+        for entity in blueprint.entities.iter().flatten() {
+            let Some(behavior) = &entity.control_behavior else {
+                continue;
+            };
+            let combinator = if let Some(params) = &behavior.arithmetic_conditions {
+                Combinator::Arithmetic(params)
+            } else if let Some(params) = &behavior.decider_conditions {
+                Combinator::Decider(params)
+            } else if entity.name.contains("constant-combinator") {
+                let mut signals = Signals::new();
+                if behavior.is_on != Some(false) {
+                    for (id, value) in behavior.constant_signals() {
+                        add(&mut signals, (&id).into(), value);
+                    }
+                }
+                Combinator::Constant(signals)
+            } else {
+                continue;
+            };
+            combinators.insert(entity.entity_number.get(), combinator);
+        }
+
+        let outputs = combinators
+            .iter()
+            .filter_map(|(&number, combinator)| match combinator {
+                Combinator::Constant(signals) => Some((number, signals.clone())),
+                _ => None,
+            })
+            .collect();
+
+        let graph = CircuitGraph::new(blueprint);
+        let networks = graph.all_networks();
+        let network_of = networks
+            .iter()
+            .enumerate()
+            .flat_map(|(i, network)| {
+                let color = network.color;
+                network
+                    .connectors
+                    .iter()
+                    .map(move |&connector| ((color, connector), i))
+            })
+            .collect();
+
+        Self {
+            graph,
+            network_of,
+            networks: networks.len(),
+            combinators,
+            outputs,
+            tick: 0,
+        }
+    }
+
+    /// The number of ticks simulated so far.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// The circuit wires being simulated.
+    pub fn graph(&self) -> &CircuitGraph {
+        &self.graph
+    }
+
+    /// The connector an entity outputs to: the output of arithmetic and decider combinators, and
+    /// the only connector of constant combinators.
+    fn output_connector(&self, entity_number: usize) -> Connector {
+        match self.combinators.get(&entity_number) {
+            Some(Combinator::Constant(_)) | None => Connector::input(entity_number),
+            Some(_) => Connector::output(entity_number),
+        }
+    }
+
+    /// The signals on every network this tick, by index.
+    fn network_values(&self) -> Vec<Signals> {
+        let mut values = vec![Signals::new(); self.networks];
+        for (&number, output) in &self.outputs {
+            let connector = self.output_connector(number);
+            for color in [NetworkColor::Red, NetworkColor::Green] {
+                if let Some(&i) = self.network_of.get(&(color, connector)) {
+                    for (signal, &value) in output {
+                        add(&mut values[i], signal.clone(), value);
+                    }
+                }
+            }
+        }
+        values
+    }
+
+    /// What `connector` reads from the networks with `values`.
+    fn inputs(&self, values: &[Signals], connector: Connector) -> Inputs {
+        let read = |color| {
+            self.network_of
+                .get(&(color, connector))
+                .map_or_else(Signals::new, |&i| values[i].clone())
+        };
+        Inputs {
+            red: read(NetworkColor::Red),
+            green: read(NetworkColor::Green),
+        }
+    }
+
+    /// The signals on the network of `color` that `connector` is on, as they are this tick.
+    pub fn network(&self, connector: Connector, color: NetworkColor) -> Signals {
+        self.network_of
+            .get(&(color, connector))
+            .map_or_else(Signals::new, |&i| self.network_values().swap_remove(i))
+    }
+
+    /// The signals an entity sees on its input, or only connector, summed over both colours.
+    pub fn input(&self, entity_number: usize) -> Signals {
+        self.inputs(&self.network_values(), Connector::input(entity_number))
+            .all(None)
+    }
+
+    /// What a combinator is outputting this tick.
+    pub fn output(&self, entity_number: usize) -> Option<&Signals> {
+        self.outputs.get(&entity_number)
+    }
+
+    /// Advance one tick, updating the output of every combinator from its current input.
+    pub fn step(&mut self) {
+        let values = self.network_values();
+        let mut outputs = BTreeMap::new();
+        for (&number, combinator) in &self.combinators {
+            let output = match combinator {
+                Combinator::Constant(signals) => signals.clone(),
+                Combinator::Arithmetic(params) => {
+                    arithmetic(params, &self.inputs(&values, Connector::input(number)))
+                }
+                Combinator::Decider(params) => {
+                    decider(params, &self.inputs(&values, Connector::input(number)))
+                }
+            };
+            outputs.insert(number, output);
+        }
+        self.outputs = outputs;
+        self.tick += 1;
+    }
+
+    /// Advance a number of ticks.
+    pub fn run(&mut self, ticks: u64) {
+        for _ in 0..ticks {
+            self.step();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::num::NonZeroUsize;
+
+    use super::*;
+    use crate::{
+        blueprint::{
+            control_behavior::{ArithmeticOperation, ControlBehavior},
+            BlueprintBuilder, EntityBuilder, VirtualSignal, Wire, WireConnectorId,
+        },
+        prototypes::PrototypeDb,
+    };
+
+    fn letter(letter: char) -> SignalId {
+        SignalId::virtual_signal(VirtualSignal::Letter(letter))
+    }
+
+    /// An arithmetic combinator calculating `input operation constant` into `output`.
+    fn arithmetic(
+        input: char,
+        operation: ArithmeticOperation,
+        constant: i32,
+        output: char,
+    ) -> ControlBehavior {
+        ControlBehavior {
+            arithmetic_conditions: Some(ArithmeticCombinatorParameters {
+                first_signal: Some(letter(input)),
+                second_constant: Some(constant),
+                operation: Some(operation),
+                output_signal: Some(letter(output)),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    /// A red wire from `source_connector` of `source` to the input of `target`.
+    fn wire(source: usize, source_connector: WireConnectorId, target: usize) -> Wire {
+        Wire {
+            source_entity: NonZeroUsize::new(source).unwrap(),
+            source_connector,
+            target_entity: NonZeroUsize::new(target).unwrap(),
+            target_connector: WireConnectorId::CircuitRed,
+        }
+    }
+
+    #[test]
+    fn combinators_take_a_tick_to_update() {
+        let mut constant = ControlBehavior::default();
+        constant.set_constant_signal(letter('A'), 5);
+        let blueprint = BlueprintBuilder::new()
+            .entity(
+                EntityBuilder::new("constant-combinator")
+                    .at_tile(0, 0)
+                    .control_behavior(constant),
+            )
+            .entity(
+                EntityBuilder::new("arithmetic-combinator")
+                    .at_tile(1, 0)
+                    .control_behavior(arithmetic('A', ArithmeticOperation::Multiply, 2, 'B')),
+            )
+            .wire(wire(1, WireConnectorId::CircuitRed, 2))
+            .build_with(PrototypeDb::vanilla())
+            .unwrap();
+        let mut simulator = Simulator::new(&blueprint);
+        let b = Signal::from(&letter('B'));
+
+        assert_eq!(simulator.output(2), None);
+        assert_eq!(
+            simulator.input(2).get(&Signal::from(&letter('A'))),
+            Some(&5)
+        );
+        simulator.step();
+        assert_eq!(simulator.output(2).unwrap().get(&b), Some(&10));
+        simulator.run(3);
+        assert_eq!(simulator.output(2).unwrap().get(&b), Some(&10));
+        assert_eq!(simulator.tick(), 4);
+    }
+
+    #[test]
+    fn a_combinator_feeding_itself_counts_ticks() {
+        let blueprint = BlueprintBuilder::new()
+            .entity(
+                EntityBuilder::new("arithmetic-combinator")
+                    .at_tile(0, 0)
+                    .control_behavior(arithmetic('A', ArithmeticOperation::Add, 1, 'A')),
+            )
+            .wire(wire(1, WireConnectorId::CombinatorOutputRed, 1))
+            .build_with(PrototypeDb::vanilla())
+            .unwrap();
+        let mut simulator = Simulator::new(&blueprint);
+        simulator.run(10);
+
+        assert_eq!(
+            simulator
+                .output(1)
+                .unwrap()
+                .get(&Signal::from(&letter('A'))),
+            Some(&10)
+        );
+    }
+}
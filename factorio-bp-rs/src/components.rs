@@ -0,0 +1,215 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    belt::{BeltGraph, NodeKind},
+    blueprint::Blueprint,
+    circuit::CircuitGraph,
+    fluid::FluidGraph,
+    heat::HeatGraph,
+    prototypes::PrototypeDb,
+    rail::RailGraph,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+/// What joins the entities of a [Component].
+pub enum ComponentKind {
+    /// Belts, underground belts, splitters, and loaders that move items into each other.
+    Belt,
+    /// Fluid boxes joined by pipe connections, as in a [FluidNetwork](crate::fluid::FluidNetwork).
+    Pipe,
+    /// Heat pipes, reactors, and heat exchangers, as in a [HeatNetwork](crate::heat::HeatNetwork).
+    Heat,
+    /// Rails joined end to end, as in a [RailNetwork](crate::rail::RailNetwork).
+    Rail,
+    /// Red or green wires, as in a circuit [Network](crate::circuit::Network).
+    Circuit,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Entities joined by one kind of connection, like a belt line or a circuit network.
+pub struct Component {
+    /// What joins the entities.
+    pub kind: ComponentKind,
+    /// The entity_numbers of the entities, in order.
+    pub entities: BTreeSet<usize>,
+}
+
+impl Component {
+    /// Whether the entity is part of the component.
+    pub fn contains(&self, entity_number: usize) -> bool {
+        self.entities.contains(&entity_number)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// The parts of a blueprint that can be looked at on their own: its belt lines, pipe networks,
+/// heat networks, rail networks, and circuit networks.
+///
+/// Only groups of two or more entities are components, so a belt or pipe joined to nothing is
+/// left out. An entity can be part of several components, one for each way it is connected.
+pub struct Components {
+    components: Vec<Component>,
+}
+
+/// The sets of nodes joined by `edges`, following them either way, ordered by their lowest node.
+fn connected_sets(
+    nodes: impl IntoIterator<Item = usize>,
+    edges: impl IntoIterator<Item = (usize, usize)>,
+) -> Vec<BTreeSet<usize>> {
+    let mut adjacent: BTreeMap<usize, Vec<usize>> =
+        nodes.into_iter().map(|node| (node, Vec::new())).collect();
+    for (a, b) in edges {
+        adjacent.entry(a).or_default().push(b);
+        adjacent.entry(b).or_default().push(a);
+    }
+
+    let mut seen = BTreeSet::new();
+    let mut sets = Vec::new();
+    for &start in adjacent.keys() {
+        if !seen.insert(start) {
+            continue;
+        }
+        let mut set = BTreeSet::from([start]);
+        let mut queue = vec![start];
+        while let Some(next) = queue.pop() {
+            for &neighbor in &adjacent[&next] {
+                if seen.insert(neighbor) {
+                    set.insert(neighbor);
+                    queue.push(neighbor);
+                }
+            }
+        }
+        sets.push(set);
+    }
+    sets
+}
+
+impl Components {
+    /// The components of `blueprint`, with entity sizes and connections from `db`.
+    pub fn new(blueprint: &Blueprint, db: &PrototypeDb) -> Self {
+        let mut components = Vec::new();
+        let mut add = |kind, entities: BTreeSet<usize>| {
+            if entities.len() > 1 {
+                components.push(Component { kind, entities });
+            }
+        };
+
+        // The machines and chests loaders feed aren't part of the belt line
+        let belts = BeltGraph::new(blueprint, db);
+        let is_belt = |node| belts.kind(node) != Some(NodeKind::Container);
+        let edges = belts
+            .nodes()
+            .filter(|&node| is_belt(node))
+            .flat_map(|node| {
+                belts
+                    .next(node)
+                    .iter()
+                    .filter(|&&next| is_belt(next))
+                    .map(move |&next| (node, next))
+            });
+        for entities in connected_sets(belts.nodes().filter(|&node| is_belt(node)), edges) {
+            add(ComponentKind::Belt, entities);
+        }
+
+        for network in FluidGraph::new(blueprint, db).networks() {
+            add(ComponentKind::Pipe, network.entity_numbers());
+        }
+        for network in HeatGraph::new(blueprint, db).networks() {
+            add(ComponentKind::Heat, network.entities.into_iter().collect());
+        }
+        for network in RailGraph::new(blueprint).networks() {
+            add(ComponentKind::Rail, network.rails.into_iter().collect());
+        }
+        for network in CircuitGraph::new(blueprint).all_networks() {
+            add(ComponentKind::Circuit, network.entity_numbers());
+        }
+
+        Self { components }
+    }
+
+    /// Every component: belt lines, then pipe networks, then heat networks, then rail networks,
+    /// then red and green circuit networks, each ordered by their lowest entity.
+    pub fn iter(&self) -> impl Iterator<Item = &Component> {
+        self.components.iter()
+    }
+
+    /// The components of one kind.
+    pub fn of_kind(&self, kind: ComponentKind) -> impl Iterator<Item = &Component> {
+        self.iter().filter(move |component| component.kind == kind)
+    }
+
+    /// The components the entity is part of.
+    pub fn containing(&self, entity_number: usize) -> impl Iterator<Item = &Component> {
+        self.iter()
+            .filter(move |component| component.contains(entity_number))
+    }
+
+    /// The entities joined by any kind of connection, directly or through other entities, as
+    /// sets of entity_numbers ordered by their lowest entity. Entities in no component are left
+    /// out.
+    pub fn clusters(&self) -> Vec<BTreeSet<usize>> {
+        let entities = self
+            .iter()
+            .flat_map(|component| component.entities.iter().copied());
+        // Chaining each component's entities together joins them all
+        let edges = self.iter().flat_map(|component| {
+            component
+                .entities
+                .iter()
+                .zip(component.entities.iter().skip(1))
+                .map(|(&a, &b)| (a, b))
+        });
+        connected_sets(entities, edges)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::num::NonZeroUsize;
+
+    use super::*;
+    use crate::{
+        blueprint::{Wire, WireConnectorId},
+        bp,
+    };
+
+    #[test]
+    fn entities_group_by_how_they_connect() {
+        let db = PrototypeDb::vanilla();
+        let number = |n| NonZeroUsize::new(n).unwrap();
+        let blueprint = bp! {
+            "transport-belt" @ (0, 0) facing East;
+            "transport-belt" @ (1, 0) facing East;
+            "transport-belt" @ (0, 4) facing East;
+            "transport-belt" @ (1, 4) facing East;
+            "pipe" @ (4, 0);
+            "pipe" @ (5, 0);
+            "transport-belt" @ (8, 8);
+        }
+        .wire(Wire {
+            source_entity: number(2),
+            source_connector: WireConnectorId::CircuitRed,
+            target_entity: number(5),
+            target_connector: WireConnectorId::CircuitRed,
+        })
+        .build_with(db)
+        .unwrap();
+        let components = Components::new(&blueprint, db);
+
+        let sets = |kind| -> Vec<Vec<usize>> {
+            components
+                .of_kind(kind)
+                .map(|c| c.entities.iter().copied().collect())
+                .collect()
+        };
+        assert_eq!(sets(ComponentKind::Belt), [[1, 2], [3, 4]]);
+        assert_eq!(sets(ComponentKind::Pipe), [[5, 6]]);
+        assert_eq!(sets(ComponentKind::Circuit), [[2, 5]]);
+        assert_eq!(components.containing(2).count(), 2);
+        assert_eq!(components.containing(7).count(), 0);
+        assert_eq!(
+            components.clusters(),
+            [BTreeSet::from([1, 2, 5, 6]), BTreeSet::from([3, 4])]
+        );
+    }
+}
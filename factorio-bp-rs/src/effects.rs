@@ -0,0 +1,178 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    blueprint::{Blueprint, Entity},
+    prototypes::{Effect, PrototypeDb},
+};
+
+/// The least module effects can bring a machine's speed, power use, or pollution to, as a
+/// fraction of its base value.
+pub const MIN_MULTIPLIER: f64 = 0.2;
+/// The most productivity a machine can have, on top of what it crafts.
+pub const MAX_PRODUCTIVITY: f64 = 3.0;
+
+/// The fraction of its effect each beacon passes on to a machine `count` beacons reach, as
+/// machines in reach of more beacons get less from each since 2.0.
+pub fn beacon_profile(count: usize) -> f64 {
+    1.0 / (count.max(1) as f64).sqrt()
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// Everything changing how a machine works: its own modules, its base productivity, and the
+/// beacons around it.
+pub struct Effects {
+    /// The combined effect of the machine's modules.
+    pub modules: Effect,
+    /// The productivity the machine has without modules.
+    pub base_productivity: f64,
+    /// The entity_numbers of the beacons reaching the machine, in order.
+    pub beacons: Vec<usize>,
+    /// The combined effect the beacons pass on, after their distribution effectivity and
+    /// [beacon_profile].
+    pub transmitted: Effect,
+}
+
+impl Effects {
+    /// The effects of `entity`'s own modules and base productivity, from `db`, with no beacons.
+    pub fn new(entity: &Entity, db: &PrototypeDb) -> Self {
+        let base_productivity = entity
+            .prototype(db)
+            .and_then(|prototype| prototype.crafting_machine.as_ref())
+            .map_or(0.0, |machine| machine.base_productivity);
+        Self {
+            modules: entity.module_effect(db),
+            base_productivity,
+            beacons: Vec::new(),
+            transmitted: Effect::default(),
+        }
+    }
+
+    /// The effects of every crafting machine and lab in `blueprint`, by entity_number, including the
+    /// beacons reaching them.
+    pub fn of_machines(blueprint: &Blueprint, db: &PrototypeDb) -> BTreeMap<usize, Self> {
+        let entities: Vec<&Entity> = blueprint.entities.iter().flatten().collect();
+        let beacons: Vec<_> = entities
+            .iter()
+            .filter_map(|entity| {
+                let beacon = entity.prototype(db)?.beacon?;
+                let effect = entity.module_effect(db) * beacon.distribution_effectivity;
+                Some((entity.entity_number.get(), entity.beacon_area(db)?, effect))
+            })
+            .collect();
+
+        let mut machines = BTreeMap::new();
+        for entity in entities {
+            let is_machine = entity.prototype(db).is_some_and(|prototype| {
+                prototype.crafting_machine.is_some() || prototype.lab.is_some()
+            });
+            let Some(footprint) = entity.footprint(db).filter(|_| is_machine) else {
+                continue;
+            };
+            let mut effects = Self::new(entity, db);
+            let reaching: Vec<_> = beacons
+                .iter()
+                .filter(|(_, area, _)| area.intersects(&footprint))
+                .collect();
+            let profile = beacon_profile(reaching.len());
+            for &&(number, _, effect) in &reaching {
+                effects.beacons.push(number);
+                effects.transmitted += effect * profile;
+            }
+            machines.insert(entity.entity_number.get(), effects);
+        }
+        machines
+    }
+
+    /// Everything added together, before the game's limits.
+    pub fn total(&self) -> Effect {
+        let base = Effect {
+            productivity: self.base_productivity,
+            ..Effect::default()
+        };
+        self.modules + self.transmitted + base
+    }
+
+    /// What the machine's crafting or mining speed is multiplied by.
+    pub fn speed(&self) -> f64 {
+        (1.0 + self.total().speed).max(MIN_MULTIPLIER)
+    }
+
+    /// What the machine's products are multiplied by, counting the extra ones productivity
+    /// makes.
+    pub fn productivity(&self) -> f64 {
+        1.0 + self.total().productivity.clamp(0.0, MAX_PRODUCTIVITY)
+    }
+
+    /// What the electricity the machine uses while working is multiplied by.
+    pub fn consumption(&self) -> f64 {
+        (1.0 + self.total().consumption).max(MIN_MULTIPLIER)
+    }
+
+    /// What the machine's pollution is multiplied by. Pollution goes up and down with the
+    /// electricity used as well as with its own effect.
+    pub fn pollution(&self) -> f64 {
+        (1.0 + self.total().pollution).max(MIN_MULTIPLIER) * self.consumption()
+    }
+
+    /// The chance of each craft making products of the next quality up.
+    pub fn quality(&self) -> f64 {
+        self.total().quality.max(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blueprint::{BlueprintBuilder, EntityBuilder, EntityItems, ItemRequest};
+
+    /// `name` on the tile `(x, y)` holding `modules`.
+    fn with_modules(name: &str, x: i64, y: i64, modules: &[(&str, u32)]) -> EntityBuilder {
+        let request: ItemRequest = modules
+            .iter()
+            .map(|&(module, count)| (module.to_owned(), count))
+            .collect();
+        EntityBuilder::new(name)
+            .at_tile(x, y)
+            .items(EntityItems::Legacy(request))
+    }
+
+    /// Whether `a` and `b` are equal but for rounding.
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn beacons_add_to_a_machines_own_modules() {
+        let db = PrototypeDb::vanilla();
+        let blueprint = BlueprintBuilder::new()
+            .entity(with_modules(
+                "assembling-machine-2",
+                0,
+                0,
+                &[("productivity-module", 2)],
+            ))
+            .entity(with_modules("beacon", 3, 0, &[("speed-module", 2)]))
+            .entity(with_modules(
+                "assembling-machine-2",
+                0,
+                20,
+                &[("efficiency-module-3", 5)],
+            ))
+            .build_with(db)
+            .unwrap();
+        let machines = Effects::of_machines(&blueprint, db);
+
+        let effects = &machines[&1];
+        assert_eq!(effects.beacons, [2]);
+        assert!(close(effects.speed(), 1.5));
+        assert!(close(effects.productivity(), 1.08));
+        assert!(close(effects.consumption(), 3.3));
+        assert!(close(effects.pollution(), 3.63));
+        assert_eq!(effects.quality(), 0.0);
+
+        let efficient = &machines[&3];
+        assert!(efficient.beacons.is_empty());
+        assert_eq!(efficient.consumption(), MIN_MULTIPLIER);
+        assert_eq!(beacon_profile(4), 0.5);
+    }
+}
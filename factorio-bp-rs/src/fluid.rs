@@ -0,0 +1,303 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::{
+    blueprint::{Blueprint, Direction, Entity},
+    prototypes::{EntityPrototype, PipeConnection, ProductionType, PrototypeDb},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+/// A fluid box of a particular entity.
+pub struct FluidBoxId {
+    /// The entity_number of the entity.
+    pub entity_number: usize,
+    /// Which of the entity's fluid boxes, the index into its prototype's
+    /// [fluid_boxes](crate::prototypes::EntityPrototype::fluid_boxes).
+    pub index: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Fluid boxes joined by pipe connections, which all hold the same fluid.
+pub struct FluidNetwork {
+    /// The fluid boxes in the network, in order.
+    pub fluid_boxes: Vec<FluidBoxId>,
+}
+
+impl FluidNetwork {
+    /// Whether `fluid_box` is part of the network.
+    pub fn contains(&self, fluid_box: FluidBoxId) -> bool {
+        self.fluid_boxes.binary_search(&fluid_box).is_ok()
+    }
+
+    /// The entities with a fluid box in the network.
+    pub fn entity_numbers(&self) -> BTreeSet<usize> {
+        self.fluid_boxes
+            .iter()
+            .map(|fluid_box| fluid_box.entity_number)
+            .collect()
+    }
+}
+
+/// A pipe or heat connection in blueprint coordinates, of some node of a graph.
+pub(crate) struct Placed<T> {
+    node: T,
+    tile: (i64, i64),
+    direction: Direction,
+    underground_distance: Option<u32>,
+}
+
+impl<T> Placed<T> {
+    /// `connection` of `entity`, which is part of `node`.
+    pub(crate) fn new(node: T, entity: &Entity, connection: &PipeConnection) -> Self {
+        let connection = connection.of_entity(entity);
+        Self {
+            node,
+            tile: (
+                (entity.position.x + connection.x).floor() as i64,
+                (entity.position.y + connection.y).floor() as i64,
+            ),
+            direction: connection.direction,
+            underground_distance: connection.underground_distance,
+        }
+    }
+}
+
+/// The tile `distance` steps from `tile` in `direction`, which must be cardinal.
+fn step(tile: (i64, i64), direction: Direction, distance: i64) -> (i64, i64) {
+    let (x, y) = direction.tile_offset().unwrap_or_default();
+    (tile.0 + x as i64 * distance, tile.1 + y as i64 * distance)
+}
+
+/// The pairs of nodes joined by connections that face each other, each pair listed once with
+/// the lower node first.
+pub(crate) fn joined<T: Copy + Ord>(placed: &[Placed<T>]) -> BTreeSet<(T, T)> {
+    let mut by_tile: HashMap<(i64, i64), Vec<&Placed<T>>> = HashMap::new();
+    for connection in placed {
+        by_tile.entry(connection.tile).or_default().push(connection);
+    }
+    // Whether `connection` has a partner of the same kind at `tile`, facing back at it
+    let partner = |connection: &Placed<T>, tile: (i64, i64)| {
+        by_tile.get(&tile).and_then(|others| {
+            others
+                .iter()
+                .find(|other| {
+                    other.direction == connection.direction.opposite()
+                        && other.underground_distance.is_some()
+                            == connection.underground_distance.is_some()
+                })
+                .map(|other| other.node)
+        })
+    };
+
+    let mut edges = BTreeSet::new();
+    for connection in placed {
+        let found = match connection.underground_distance {
+            None => partner(connection, step(connection.tile, connection.direction, 1)),
+            // The nearest one in reach, as the game pairs them
+            Some(distance) => (1..=distance as i64)
+                .find_map(|i| partner(connection, step(connection.tile, connection.direction, i))),
+        };
+        if let Some(other) = found.filter(|&other| other != connection.node) {
+            let (a, b) = (connection.node, other);
+            edges.insert((a.min(b), a.max(b)));
+        }
+    }
+    edges
+}
+
+/// The fluids the fluid boxes of `entity` are fixed to hold, by their index: those with a
+/// filter, those of crafting machines holding an ingredient or product of the recipe, and those
+/// of infinity pipes set to a fluid.
+pub(crate) fn fixed_fluids(
+    entity: &Entity,
+    prototype: &EntityPrototype,
+    db: &PrototypeDb,
+) -> BTreeMap<usize, String> {
+    let recipe = entity.recipe().and_then(|recipe| db.recipe(recipe));
+    let fluids = |amounts: &[(String, f64)]| -> Vec<String> {
+        amounts
+            .iter()
+            .filter(|(name, _)| db.is_fluid(name))
+            .map(|(name, _)| name.clone())
+            .collect()
+    };
+    let (ingredients, products) = recipe
+        .map(|recipe| (fluids(&recipe.ingredients), fluids(&recipe.products)))
+        .unwrap_or_default();
+    let (mut ingredients, mut products) = (ingredients.into_iter(), products.into_iter());
+
+    let mut fixed = BTreeMap::new();
+    for (index, fluid_box) in prototype.fluid_boxes.iter().enumerate() {
+        let fluid = match fluid_box.production_type {
+            ProductionType::Input => ingredients.next(),
+            ProductionType::Output => products.next(),
+            ProductionType::None => None,
+        };
+        let set = || {
+            entity
+                .infinity_pipe_settings()
+                .map(|settings| settings.name.clone())
+        };
+        if let Some(fluid) = fluid.or_else(|| fluid_box.filter.clone()).or_else(set) {
+            fixed.insert(index, fluid);
+        }
+    }
+    fixed
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// The fluid boxes of a blueprint, as a graph joined by pipe connections.
+///
+/// Two connections join when they are on adjacent tiles and face each other, or for underground
+/// pipes, when they face each other in a line within reach. Every fluid box of an entity `db`
+/// knows is part of the graph, even if it connects to nothing, except for the ingredient and
+/// product boxes of crafting machines whose recipe doesn't use them, which are shut.
+pub struct FluidGraph {
+    adjacent: BTreeMap<FluidBoxId, Vec<FluidBoxId>>,
+    fluids: BTreeMap<FluidBoxId, String>,
+}
+
+impl FluidGraph {
+    /// The fluid boxes of `blueprint`, with their connections from `db`.
+    pub fn new(blueprint: &Blueprint, db: &PrototypeDb) -> Self {
+        let mut adjacent: BTreeMap<FluidBoxId, Vec<FluidBoxId>> = BTreeMap::new();
+        let mut fluids = BTreeMap::new();
+        let mut placed = Vec::new();
+        for entity in blueprint.entities.iter().flatten() {
+            let Some(prototype) = entity.prototype(db) else {
+                continue;
+            };
+            let mut fixed = fixed_fluids(entity, prototype, db);
+            for (index, fluid_box) in prototype.fluid_boxes.iter().enumerate() {
+                let id = FluidBoxId {
+                    entity_number: entity.entity_number.get(),
+                    index,
+                };
+                match fixed.remove(&index) {
+                    Some(fluid) => {
+                        fluids.insert(id, fluid);
+                    }
+                    None if fluid_box.production_type != ProductionType::None => continue,
+                    None => {}
+                }
+                adjacent.insert(id, Vec::new());
+                for connection in &fluid_box.connections {
+                    placed.push(Placed::new(id, entity, connection));
+                }
+            }
+        }
+
+        for (a, b) in joined(&placed) {
+            adjacent.entry(a).or_default().push(b);
+            adjacent.entry(b).or_default().push(a);
+        }
+
+        Self { adjacent, fluids }
+    }
+
+    /// Every fluid box, in order.
+    pub fn fluid_boxes(&self) -> impl Iterator<Item = FluidBoxId> + '_ {
+        self.adjacent.keys().copied()
+    }
+
+    /// The fluid boxes connected directly to `fluid_box`.
+    pub fn neighbors(&self, fluid_box: FluidBoxId) -> &[FluidBoxId] {
+        self.adjacent.get(&fluid_box).map_or(&[], Vec::as_slice)
+    }
+
+    /// The network `fluid_box` is part of, or `None` if there is no such fluid box.
+    pub fn network(&self, fluid_box: FluidBoxId) -> Option<FluidNetwork> {
+        if !self.adjacent.contains_key(&fluid_box) {
+            return None;
+        }
+
+        let mut seen = BTreeSet::from([fluid_box]);
+        let mut queue = vec![fluid_box];
+        while let Some(next) = queue.pop() {
+            for &neighbor in self.neighbors(next) {
+                if seen.insert(neighbor) {
+                    queue.push(neighbor);
+                }
+            }
+        }
+        Some(FluidNetwork {
+            fluid_boxes: seen.into_iter().collect(),
+        })
+    }
+
+    /// Every network, ordered by their lowest fluid box.
+    pub fn networks(&self) -> Vec<FluidNetwork> {
+        let mut seen = BTreeSet::new();
+        let mut networks = Vec::new();
+        for fluid_box in self.fluid_boxes() {
+            if seen.contains(&fluid_box) {
+                continue;
+            }
+            if let Some(network) = self.network(fluid_box) {
+                seen.extend(network.fluid_boxes.iter().copied());
+                networks.push(network);
+            }
+        }
+        networks
+    }
+
+    /// The fluid `fluid_box` is fixed to hold, by a filter or the recipe of its crafting machine,
+    /// or `None` if it can hold any.
+    pub fn fluid(&self, fluid_box: FluidBoxId) -> Option<&str> {
+        self.fluids.get(&fluid_box).map(String::as_str)
+    }
+
+    /// The fluids the fluid boxes of `network` are fixed to hold. More than one means the
+    /// network mixes fluids, which the game doesn't allow.
+    pub fn fluids(&self, network: &FluidNetwork) -> BTreeSet<&str> {
+        network
+            .fluid_boxes
+            .iter()
+            .filter_map(|&fluid_box| self.fluid(fluid_box))
+            .collect()
+    }
+
+    /// Whether two fluid boxes are part of the same network.
+    pub fn connected(&self, a: FluidBoxId, b: FluidBoxId) -> bool {
+        self.network(a).is_some_and(|network| network.contains(b))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{analysis::fluids::FluidReport, bp};
+
+    /// A chemical plant making plastic, with a pipe on its petroleum gas input and another
+    /// beside it that the plant doesn't connect to.
+    fn plastic() -> Blueprint {
+        bp! {
+            "chemical-plant" @ (0, 0) recipe "plastic-bar";
+            "pipe" @ (0, -1);
+            "pipe" @ (-1, 0);
+        }
+        .build_with(PrototypeDb::vanilla())
+        .unwrap()
+    }
+
+    #[test]
+    fn mirrored_machines_connect_on_the_mirrored_side() {
+        let db = PrototypeDb::vanilla();
+        let input = FluidBoxId {
+            entity_number: 1,
+            index: 0,
+        };
+        let pipe = FluidBoxId {
+            entity_number: 2,
+            index: 0,
+        };
+
+        let mut blueprint = plastic();
+        for flip in [Blueprint::flip_horizontal, Blueprint::flip_vertical] {
+            flip(&mut blueprint);
+            let graph = FluidGraph::new(&blueprint, db);
+            assert!(graph.connected(input, pipe));
+            assert_eq!(FluidReport::new(&blueprint, db).issues, []);
+        }
+        assert_eq!(blueprint.entities.as_ref().unwrap()[0].mirror, None);
+    }
+}
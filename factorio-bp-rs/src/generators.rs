@@ -0,0 +1,21 @@
+/// Solar panels and accumulators around substations, in tileable cells.
+pub mod solar;
+
+/// Columns of furnaces between an ore belt and a plate belt.
+pub mod smelting;
+
+/// Belt balancers with a power of two inputs and outputs.
+pub mod balancer;
+
+/// Text spelled out in lamps.
+pub mod lamps;
+
+/// Images drawn in coloured tiles or lamps, with the `image` feature.
+#[cfg(feature = "image")]
+pub mod pixel_art;
+
+/// Programmable speakers playing melodies from MIDI files.
+pub mod music;
+
+/// Combinators computing signals from arithmetic expressions.
+pub mod expression;
@@ -0,0 +1,287 @@
+use thiserror::Error;
+
+use crate::blueprint::{
+    BeltTier, BlueprintBuilder, Direction, EntityBuilder, EntityDetails, IoType,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("can't generate a {inputs} to {outputs} balancer, both sides need a power of two belts")]
+/// A balancer [Balancer::generate] has no construction for.
+pub struct UnsupportedBalancer {
+    /// The inputs asked for.
+    pub inputs: usize,
+    /// The outputs asked for.
+    pub outputs: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A belt balancer, giving every output an equal share of every input. Belts run north.
+///
+/// Balancers with as many inputs as outputs are Beneš networks: rows of splitters, with the
+/// belts between them crossed over with underground belts so that each row of splitters mixes
+/// belts the earlier rows kept apart, then as many rows again undoing the crossings. The first
+/// half balances the belts, and the second makes them throughput unlimited. Balancers with
+/// fewer inputs feed every other belt, or every fourth and so on, and those with fewer outputs
+/// merge neighbouring outputs with splitters, which keeps both properties.
+///
+/// Only powers of two are supported on either side, as other counts need belts looping back
+/// from the outputs to the inputs.
+pub struct Balancer {
+    /// The number of belts coming in, a power of two.
+    pub inputs: usize,
+    /// The number of belts going out, a power of two.
+    pub outputs: usize,
+    /// The tier of the belts, underground belts, and splitters.
+    pub belt: BeltTier,
+}
+
+/// The rows of tiles it takes to swap two neighbouring belts.
+const SWAP_ROWS: i64 = 6;
+
+/// A balancer being laid out, from the inputs northwards. Belts are two tiles apart, so that
+/// there is room for them to jog sideways into splitters and cross over each other.
+struct Layout {
+    builder: BlueprintBuilder,
+    belt: BeltTier,
+    /// The row to lay out next.
+    y: i64,
+    /// The column of each belt, west to east.
+    columns: Vec<i64>,
+    /// Whether each belt has items coming in. Belts that don't are left out.
+    fed: Vec<bool>,
+}
+
+impl Layout {
+    fn add(&mut self, name: &str, x: i64, y: i64, direction: Direction) {
+        let entity = EntityBuilder::new(name).at_tile(x, y).direction(direction);
+        self.builder.add_entity(entity);
+    }
+
+    fn belt(&mut self, x: i64, y: i64, direction: Direction) {
+        self.add(self.belt.as_str(), x, y, direction);
+    }
+
+    fn underground(&mut self, x: i64, y: i64, io_type: IoType) {
+        let entity = EntityBuilder::new(self.belt.underground_belt())
+            .at_tile(x, y)
+            .direction(Direction::North)
+            .details(EntityDetails::UndergroundBelt {
+                io_type: Some(io_type),
+            });
+        self.builder.add_entity(entity);
+    }
+
+    /// Run the belts at `positions` straight north for `rows` rows.
+    fn straight(&mut self, positions: impl IntoIterator<Item = usize>, rows: i64) {
+        for position in positions {
+            if !self.fed[position] {
+                continue;
+            }
+            let x = self.columns[position];
+            for dy in 0..rows {
+                self.belt(x, self.y - dy, Direction::North);
+            }
+        }
+    }
+
+    /// Split each pair of neighbouring belts, the first belt of each pair jogging east to sit
+    /// beside the second and back again.
+    fn split(&mut self) {
+        for pair in 0..self.columns.len() / 2 {
+            let (left, right) = (pair * 2, pair * 2 + 1);
+            let x = self.columns[left];
+            if self.fed[left] {
+                self.belt(x, self.y, Direction::East);
+                self.belt(x + 1, self.y, Direction::North);
+            }
+            if self.fed[right] {
+                self.belt(x + 2, self.y, Direction::North);
+            } else if !self.fed[left] {
+                continue;
+            }
+            self.add(self.belt.splitter(), x + 1, self.y - 1, Direction::North);
+            self.belt(x + 1, self.y - 2, Direction::West);
+            self.belt(x, self.y - 2, Direction::North);
+            self.belt(x + 2, self.y - 2, Direction::North);
+            self.fed[left] = true;
+            self.fed[right] = true;
+        }
+        self.y -= 3;
+    }
+
+    /// Swap the belts at each position in `swaps` with the belt east of it. The western belt
+    /// jogs into the gap between them and passes under the eastern one as it crosses west.
+    fn swap(&mut self, swaps: &[usize]) {
+        let y = self.y;
+        let untouched = (0..self.columns.len()).filter(|position| {
+            !swaps
+                .iter()
+                .any(|&swap| (swap..=swap + 1).contains(position))
+        });
+        self.straight(untouched.collect::<Vec<_>>(), SWAP_ROWS);
+
+        for &position in swaps {
+            let x = self.columns[position];
+            // The western belt, crossing to the east
+            if self.fed[position] {
+                self.belt(x, y, Direction::East);
+                self.belt(x + 1, y, Direction::North);
+                self.belt(x + 1, y - 1, Direction::North);
+                self.underground(x + 1, y - 2, IoType::Input);
+                self.underground(x + 1, y - 4, IoType::Output);
+                self.belt(x + 1, y - 5, Direction::East);
+                self.belt(x + 2, y - 5, Direction::North);
+            }
+            // The eastern belt, crossing to the west over it
+            if self.fed[position + 1] {
+                for dy in 0..3 {
+                    self.belt(x + 2, y - dy, Direction::North);
+                }
+                self.belt(x + 2, y - 3, Direction::West);
+                self.belt(x + 1, y - 3, Direction::West);
+                for dy in 3..SWAP_ROWS {
+                    self.belt(x, y - dy, Direction::North);
+                }
+            }
+            self.fed.swap(position, position + 1);
+        }
+        self.y -= SWAP_ROWS;
+    }
+
+    /// Merge each pair of neighbouring belts into the western one, the eastern belt jogging west
+    /// to sit beside it.
+    fn merge(&mut self) {
+        let columns = core::mem::take(&mut self.columns);
+        self.fed = vec![true; columns.len() / 2];
+        for pair in columns.chunks(2) {
+            let &[left, right] = pair else {
+                continue;
+            };
+            self.belt(left, self.y, Direction::North);
+            for x in left + 2..=right {
+                self.belt(x, self.y, Direction::West);
+            }
+            self.belt(left + 1, self.y, Direction::North);
+            self.add(self.belt.splitter(), left, self.y - 1, Direction::North);
+            self.belt(left, self.y - 2, Direction::North);
+            self.columns.push(left);
+        }
+        self.y -= 3;
+    }
+}
+
+impl Balancer {
+    /// The balancer, with the first input on the tile `(0, 0)` and the rest every other tile
+    /// east of it, unless the inputs or outputs aren't a power of two.
+    ///
+    /// A balancer of `n` belts, the more of its inputs and outputs, has `2 log2(n) - 1` rows of
+    /// splitters.
+    pub fn generate(&self) -> Result<BlueprintBuilder, UnsupportedBalancer> {
+        if !self.inputs.is_power_of_two() || !self.outputs.is_power_of_two() {
+            return Err(UnsupportedBalancer {
+                inputs: self.inputs,
+                outputs: self.outputs,
+            });
+        }
+        let belts = self.inputs.max(self.outputs);
+        let label = format!("{} to {} balancer", self.inputs, self.outputs);
+        let mut layout = Layout {
+            builder: BlueprintBuilder::new().label(label),
+            belt: self.belt,
+            y: 0,
+            columns: (0..belts as i64).map(|belt| belt * 2).collect(),
+            // Spread the inputs out so each splitter of the first row gets at most one
+            fed: (0..belts)
+                .map(|belt| belt % (belts / self.inputs) == 0)
+                .collect(),
+        };
+        layout.straight(0..belts, 1);
+        layout.y -= 1;
+
+        // Between rows of splitters, a perfect shuffle moves each belt to the position with its
+        // bits rotated left, and past the middle row the inverse shuffle rotates them right,
+        // laid out as the rows of swaps of an odd-even transposition sort
+        let bits = belts.trailing_zeros();
+        let shuffled = |belt: usize| ((belt << 1) | (belt >> (bits - 1))) & (belts - 1);
+        let unshuffled = |belt: usize| (belt >> 1) | ((belt & 1) << (bits - 1));
+        for stage in 0..(bits * 2).saturating_sub(1) {
+            let mut targets: Vec<usize> = (0..belts)
+                .map(|belt| {
+                    if stage < bits {
+                        shuffled(belt)
+                    } else {
+                        unshuffled(belt)
+                    }
+                })
+                .collect();
+            for round in 0.. {
+                if stage == 0 || targets.is_sorted() {
+                    break;
+                }
+                let swaps: Vec<usize> = (round % 2..belts - 1)
+                    .step_by(2)
+                    .filter(|&position| targets[position] > targets[position + 1])
+                    .collect();
+                for &position in &swaps {
+                    targets.swap(position, position + 1);
+                }
+                if !swaps.is_empty() {
+                    layout.swap(&swaps);
+                }
+            }
+            layout.split();
+        }
+
+        while layout.columns.len() > self.outputs {
+            layout.merge();
+        }
+        layout.straight(0..layout.columns.len(), 1);
+        Ok(layout.builder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{analysis::balancer::BalancerReport, prototypes::PrototypeDb};
+
+    /// The balancer checked, built with the vanilla prototypes.
+    fn report(inputs: usize, outputs: usize) -> BalancerReport {
+        let db = PrototypeDb::vanilla();
+        let balancer = Balancer {
+            inputs,
+            outputs,
+            belt: BeltTier::Transport,
+        };
+        let blueprint = balancer.generate().unwrap().build_with(db).unwrap();
+        BalancerReport::new(&blueprint, db).unwrap()
+    }
+
+    #[test]
+    fn powers_of_two_are_balanced_and_throughput_unlimited() {
+        for (inputs, outputs) in [(2, 2), (4, 4), (4, 2), (2, 4), (1, 4), (4, 1)] {
+            let report = report(inputs, outputs);
+            assert_eq!(
+                (report.inputs.len(), report.outputs.len()),
+                (inputs, outputs)
+            );
+            assert!(report.exhaustive);
+            assert_eq!(report.issues, [], "{inputs} to {outputs}");
+        }
+    }
+
+    #[test]
+    fn other_counts_are_unsupported() {
+        for (inputs, outputs) in [(3, 3), (2, 3), (6, 6), (5, 8)] {
+            let balancer = Balancer {
+                inputs,
+                outputs,
+                belt: BeltTier::Transport,
+            };
+            assert_eq!(
+                balancer.generate().unwrap_err(),
+                UnsupportedBalancer { inputs, outputs }
+            );
+        }
+    }
+}
@@ -0,0 +1,862 @@
+use thiserror::Error;
+
+use crate::blueprint::{
+    control_behavior::{
+        ArithmeticCombinatorParameters, ArithmeticOperation, DeciderCombinatorParameters,
+        DeciderCondition, DeciderOutput,
+    },
+    BlueprintBuilder, Comparator, CompareType, ControlBehavior, EntityBuilder, SignalColor,
+    SignalId, VirtualSignal, WireConnectorId,
+};
+
+use super::{music::circuit_wire, solar::copper_wire};
+
+/// The columns between medium electric poles.
+const POLE_SPACING: i64 = 7;
+
+/// The symbols of the language, longest first so that `<<` isn't read as two `<`.
+const SYMBOLS: &[&str] = &[
+    "<<", ">>", "<=", ">=", "==", "!=", "+", "-", "*", "/", "%", "^", "&", "|", "(", ")", "<", ">",
+    "=",
+];
+
+/// Words that can't be signal names.
+const KEYWORDS: &[&str] = &["if", "and", "or", "xor"];
+
+/// The binary operators, from the loosest binding to the tightest. `^` binds tighter still, and
+/// is right associative.
+const OPERATORS: &[&[(&str, ArithmeticOperation)]] = &[
+    &[("|", ArithmeticOperation::Or)],
+    &[("xor", ArithmeticOperation::Xor)],
+    &[("&", ArithmeticOperation::And)],
+    &[
+        ("<<", ArithmeticOperation::LeftShift),
+        (">>", ArithmeticOperation::RightShift),
+    ],
+    &[
+        ("+", ArithmeticOperation::Add),
+        ("-", ArithmeticOperation::Subtract),
+    ],
+    &[
+        ("*", ArithmeticOperation::Multiply),
+        ("/", ArithmeticOperation::Divide),
+        ("%", ArithmeticOperation::Modulo),
+    ],
+];
+
+/// The comparison operators of conditions.
+const COMPARATORS: &[(&str, Comparator)] = &[
+    ("==", Comparator::Equal),
+    ("!=", Comparator::NotEqual),
+    ("<=", Comparator::LessThanOrEqual),
+    (">=", Comparator::GreaterThanOrEqual),
+    ("<", Comparator::LessThan),
+    (">", Comparator::GreaterThan),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+/// Why an [ExpressionCircuit] couldn't be compiled.
+pub enum ExpressionError {
+    /// The source doesn't follow the grammar.
+    #[error("line {line}: unexpected {found}")]
+    Unexpected {
+        /// The line, counting from 1.
+        line: usize,
+        /// What was found instead.
+        found: String,
+    },
+    /// A number is out of the range of a signal.
+    #[error("line {line}: {number} doesn't fit in a signal")]
+    TooLarge {
+        /// The line, counting from 1.
+        line: usize,
+        /// The number as written.
+        number: String,
+    },
+    /// Both sides of a comparison are constants.
+    #[error("line {line}: a comparison needs a signal on one side")]
+    ConstantComparison {
+        /// The line, counting from 1.
+        line: usize,
+    },
+    /// A signal is assigned by more than one statement.
+    #[error("{0} is assigned more than once")]
+    Reassigned(String),
+    /// A signal is read by its own statement or one before it, which would loop the outputs
+    /// back into the combinators.
+    #[error("{0} is read before it is assigned")]
+    ReadBeforeAssigned(String),
+    /// Every virtual signal is used, leaving none for intermediate values.
+    #[error("no virtual signals are left for intermediate values")]
+    OutOfSignals,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Name(String),
+    Number(i32),
+    Symbol(&'static str),
+    /// The end of a statement, a line break or `;`.
+    End,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Name(name) => format!("`{name}`"),
+            Token::Number(number) => format!("`{number}`"),
+            Token::Symbol(symbol) => format!("`{symbol}`"),
+            Token::End => "end of line".to_owned(),
+        }
+    }
+}
+
+/// Split the source into tokens, each with its line.
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, ExpressionError> {
+    let mut tokens = Vec::new();
+    for (index, text) in source.lines().enumerate() {
+        let line = index + 1;
+        let mut rest = text.split('#').next().unwrap_or_default().trim_start();
+        while let Some(c) = rest.chars().next() {
+            let bytes = rest.as_bytes();
+            let (token, len) = if c == ';' {
+                (Token::End, 1)
+            } else if c.is_ascii_digit() {
+                let len = bytes.iter().take_while(|b| b.is_ascii_digit()).count();
+                let number = rest[..len].parse().map_err(|_| ExpressionError::TooLarge {
+                    line,
+                    number: rest[..len].to_owned(),
+                })?;
+                (Token::Number(number), len)
+            } else if c.is_ascii_alphabetic() || c == '_' {
+                // Hyphens join the words of prototype names like iron-plate, but not onto a
+                // letter signal, so `A-1` is a subtraction
+                let letter = c.is_ascii_uppercase();
+                let mut len = 1;
+                while let Some(&b) = bytes.get(len) {
+                    let joins = b == b'-'
+                        && !(letter && len == 1)
+                        && bytes.get(len + 1).is_some_and(u8::is_ascii_alphanumeric);
+                    if !(b.is_ascii_alphanumeric() || b == b'_' || joins) {
+                        break;
+                    }
+                    len += 1;
+                }
+                (Token::Name(rest[..len].to_owned()), len)
+            } else if let Some(&symbol) = SYMBOLS.iter().find(|symbol| rest.starts_with(**symbol)) {
+                (Token::Symbol(symbol), symbol.len())
+            } else {
+                return Err(ExpressionError::Unexpected {
+                    line,
+                    found: format!("`{c}`"),
+                });
+            };
+            tokens.push((token, line));
+            rest = rest[len..].trim_start();
+        }
+        tokens.push((Token::End, line));
+    }
+    Ok(tokens)
+}
+
+/// The signal a name stands for: a single capital letter is that letter's virtual signal, names
+/// starting with `signal-` are virtual signals, and any other name is an item. Wildcards aren't
+/// signals of their own, so have none.
+fn resolve(name: &str) -> Option<SignalId> {
+    let mut chars = name.chars();
+    if let (Some(letter @ 'A'..='Z'), None) = (chars.next(), chars.next()) {
+        return Some(SignalId::virtual_signal(VirtualSignal::Letter(letter)));
+    }
+    if name.starts_with("signal-") {
+        let Ok(signal) = name.parse::<VirtualSignal>();
+        if matches!(signal, VirtualSignal::Wildcard(_)) {
+            return None;
+        }
+        return Some(SignalId::virtual_signal(signal));
+    }
+    Some(SignalId::item(name))
+}
+
+/// The comparator with its sides swapped, so that `a < b` becomes `b > a`.
+fn mirrored(comparator: Comparator) -> Comparator {
+    match comparator {
+        Comparator::LessThan => Comparator::GreaterThan,
+        Comparator::GreaterThan => Comparator::LessThan,
+        Comparator::LessThanOrEqual => Comparator::GreaterThanOrEqual,
+        Comparator::GreaterThanOrEqual => Comparator::LessThanOrEqual,
+        Comparator::Equal | Comparator::NotEqual => comparator,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Number(i32),
+    Signal(SignalId),
+    Binary(ArithmeticOperation, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    /// Add the signals the expression reads to `signals`.
+    fn signals(&self, signals: &mut Vec<SignalId>) {
+        match self {
+            Expr::Number(_) => {}
+            Expr::Signal(signal) => signals.push(signal.clone()),
+            Expr::Binary(_, lhs, rhs) => {
+                lhs.signals(signals);
+                rhs.signals(signals);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Comparison {
+    lhs: Expr,
+    comparator: Comparator,
+    rhs: Expr,
+    line: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Statement {
+    output: SignalId,
+    value: Expr,
+    /// Groups of comparisons that must all be true, any group being true enough. Empty if the
+    /// statement has no condition.
+    condition: Vec<Vec<Comparison>>,
+}
+
+/// A recursive descent parser over the tokens of a program.
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .or(self.tokens.last())
+            .map_or(1, |&(_, line)| line)
+    }
+
+    fn unexpected(&self) -> ExpressionError {
+        ExpressionError::Unexpected {
+            line: self.line(),
+            found: self
+                .peek()
+                .map_or_else(|| "end of input".to_owned(), Token::describe),
+        }
+    }
+
+    /// Move past the next token if it is the symbol or keyword `expected`.
+    fn eat(&mut self, expected: &str) -> bool {
+        let matches = match self.peek() {
+            Some(Token::Symbol(symbol)) => *symbol == expected,
+            Some(Token::Name(name)) => name == expected && KEYWORDS.contains(&expected),
+            _ => false,
+        };
+        if matches {
+            self.position += 1;
+        }
+        matches
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), ExpressionError> {
+        if self.eat(expected) {
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    fn eat_end(&mut self) -> bool {
+        let end = self.peek() == Some(&Token::End);
+        if end {
+            self.position += 1;
+        }
+        end
+    }
+
+    fn program(&mut self) -> Result<Vec<Statement>, ExpressionError> {
+        let mut statements = Vec::new();
+        while self.peek().is_some() {
+            if self.eat_end() {
+                continue;
+            }
+            statements.push(self.statement()?);
+            if !self.eat_end() {
+                return Err(self.unexpected());
+            }
+        }
+        Ok(statements)
+    }
+
+    /// `output = value`, optionally followed by `if condition`.
+    fn statement(&mut self) -> Result<Statement, ExpressionError> {
+        let output = self.signal()?;
+        self.expect("=")?;
+        let value = self.binary(0)?;
+        let condition = if self.eat("if") {
+            self.condition()?
+        } else {
+            Vec::new()
+        };
+        Ok(Statement {
+            output,
+            value,
+            condition,
+        })
+    }
+
+    /// Comparisons joined by `and` and `or`, `and` binding tighter.
+    fn condition(&mut self) -> Result<Vec<Vec<Comparison>>, ExpressionError> {
+        let mut groups = vec![vec![self.comparison()?]];
+        loop {
+            if self.eat("and") {
+                let comparison = self.comparison()?;
+                groups.last_mut().unwrap().push(comparison);
+            } else if self.eat("or") {
+                groups.push(vec![self.comparison()?]);
+            } else {
+                return Ok(groups);
+            }
+        }
+    }
+
+    fn comparison(&mut self) -> Result<Comparison, ExpressionError> {
+        let line = self.line();
+        let lhs = self.binary(0)?;
+        let Some(&(_, comparator)) = COMPARATORS.iter().find(|(symbol, _)| self.eat(symbol)) else {
+            return Err(self.unexpected());
+        };
+        let rhs = self.binary(0)?;
+        Ok(Comparison {
+            lhs,
+            comparator,
+            rhs,
+            line,
+        })
+    }
+
+    /// An expression of the binary operators at `level` of [OPERATORS] or tighter.
+    fn binary(&mut self, level: usize) -> Result<Expr, ExpressionError> {
+        let Some(operators) = OPERATORS.get(level) else {
+            return self.unary();
+        };
+        let mut lhs = self.binary(level + 1)?;
+        'operators: loop {
+            for &(symbol, operation) in *operators {
+                if self.eat(symbol) {
+                    let rhs = self.binary(level + 1)?;
+                    lhs = Expr::Binary(operation, Box::new(lhs), Box::new(rhs));
+                    continue 'operators;
+                }
+            }
+            return Ok(lhs);
+        }
+    }
+
+    /// A power, or a negated one, `-x` standing for `0 - x`.
+    fn unary(&mut self) -> Result<Expr, ExpressionError> {
+        if self.eat("-") {
+            let operand = self.unary()?;
+            return Ok(Expr::Binary(
+                ArithmeticOperation::Subtract,
+                Box::new(Expr::Number(0)),
+                Box::new(operand),
+            ));
+        }
+        let base = self.atom()?;
+        if self.eat("^") {
+            let exponent = self.unary()?;
+            return Ok(Expr::Binary(
+                ArithmeticOperation::Power,
+                Box::new(base),
+                Box::new(exponent),
+            ));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<Expr, ExpressionError> {
+        if self.eat("(") {
+            let expr = self.binary(0)?;
+            self.expect(")")?;
+            return Ok(expr);
+        }
+        if let Some(&Token::Number(number)) = self.peek() {
+            self.position += 1;
+            return Ok(Expr::Number(number));
+        }
+        self.signal().map(Expr::Signal)
+    }
+
+    fn signal(&mut self) -> Result<SignalId, ExpressionError> {
+        let signal = match self.peek() {
+            Some(Token::Name(name)) if !KEYWORDS.contains(&name.as_str()) => resolve(name),
+            _ => None,
+        };
+        let signal = signal.ok_or_else(|| self.unexpected())?;
+        self.position += 1;
+        Ok(signal)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Operand {
+    Constant(i32),
+    Signal(SignalId),
+}
+
+impl Operand {
+    /// The operand as a signal or a constant, the way combinators take them.
+    fn split(self) -> (Option<SignalId>, Option<i32>) {
+        match self {
+            Operand::Constant(constant) => (None, Some(constant)),
+            Operand::Signal(signal) => (Some(signal), None),
+        }
+    }
+}
+
+/// The combinators of a program as they are compiled, statement by statement.
+struct Compiler {
+    /// Virtual signals the program doesn't use, for intermediate values, the next one last.
+    spare: Vec<SignalId>,
+    /// The outputs of statements not yet compiled, which can't be read yet.
+    pending: Vec<SignalId>,
+    /// Outputs assigned constants, which later statements read as the constant.
+    constants: Vec<(SignalId, i32)>,
+    /// Combinators computing intermediate values, by prototype name.
+    inner: Vec<(&'static str, ControlBehavior)>,
+    /// Combinators computing outputs, by prototype name.
+    outputs: Vec<(&'static str, ControlBehavior)>,
+}
+
+impl Compiler {
+    fn spare(&mut self) -> Result<SignalId, ExpressionError> {
+        self.spare.pop().ok_or(ExpressionError::OutOfSignals)
+    }
+
+    /// Add the combinators computing `expr`, the last of them outputting on `output` if given.
+    /// Operations on constants are done here instead.
+    fn lower(
+        &mut self,
+        expr: &Expr,
+        output: Option<&SignalId>,
+    ) -> Result<Operand, ExpressionError> {
+        match expr {
+            Expr::Number(number) => Ok(Operand::Constant(*number)),
+            Expr::Signal(signal) => {
+                if self.pending.contains(signal) {
+                    return Err(ExpressionError::ReadBeforeAssigned(signal.name.clone()));
+                }
+                match self.constants.iter().find(|(output, _)| output == signal) {
+                    Some(&(_, value)) => Ok(Operand::Constant(value)),
+                    None => Ok(Operand::Signal(signal.clone())),
+                }
+            }
+            Expr::Binary(operation, lhs, rhs) => {
+                let (lhs, rhs) = (self.lower(lhs, None)?, self.lower(rhs, None)?);
+                if let (Operand::Constant(lhs), Operand::Constant(rhs)) = (&lhs, &rhs) {
+                    return Ok(Operand::Constant(operation.apply(*lhs, *rhs)));
+                }
+                let output_signal = match output {
+                    Some(output) => output.clone(),
+                    None => self.spare()?,
+                };
+                let combinator = arithmetic(lhs, *operation, rhs, output_signal.clone());
+                if output.is_some() {
+                    self.outputs.push(combinator);
+                } else {
+                    self.inner.push(combinator);
+                }
+                Ok(Operand::Signal(output_signal))
+            }
+        }
+    }
+
+    /// The conditions of a decider combinator true when `condition` is.
+    fn conditions(
+        &mut self,
+        condition: &[Vec<Comparison>],
+    ) -> Result<Vec<DeciderCondition>, ExpressionError> {
+        let mut conditions = Vec::new();
+        for (group, comparisons) in condition.iter().enumerate() {
+            for (i, comparison) in comparisons.iter().enumerate() {
+                let lhs = self.lower(&comparison.lhs, None)?;
+                let rhs = self.lower(&comparison.rhs, None)?;
+                // The signal has to be on the left
+                let (first, second, comparator) = match (lhs, rhs) {
+                    (Operand::Signal(first), second) => (first, second, comparison.comparator),
+                    (first, Operand::Signal(second)) => {
+                        (second, first, mirrored(comparison.comparator))
+                    }
+                    _ => {
+                        return Err(ExpressionError::ConstantComparison {
+                            line: comparison.line,
+                        })
+                    }
+                };
+                let compare_type = match (group, i) {
+                    (0, 0) => None,
+                    (_, 0) => Some(CompareType::Or),
+                    _ => Some(CompareType::And),
+                };
+                let (second_signal, constant) = second.split();
+                conditions.push(DeciderCondition {
+                    first_signal: Some(first),
+                    second_signal,
+                    constant,
+                    comparator: Some(comparator),
+                    compare_type,
+                    ..Default::default()
+                });
+            }
+        }
+        Ok(conditions)
+    }
+
+    fn statement(&mut self, statement: &Statement) -> Result<(), ExpressionError> {
+        let output = &statement.output;
+        if statement.condition.is_empty() {
+            match self.lower(&statement.value, Some(output))? {
+                Operand::Signal(signal) if signal == *output => {}
+                signal @ Operand::Signal(_) => {
+                    let combinator = arithmetic(
+                        signal,
+                        ArithmeticOperation::Add,
+                        Operand::Constant(0),
+                        output.clone(),
+                    );
+                    self.outputs.push(combinator);
+                }
+                Operand::Constant(value) => self.constants.push((output.clone(), value)),
+            }
+        } else {
+            // Values other than constants pass through an arithmetic combinator multiplying them
+            // by a signal that is 1 while the condition is true, and 0 otherwise
+            let value = self.lower(&statement.value, None)?;
+            let conditions = self.conditions(&statement.condition)?;
+            match value {
+                Operand::Constant(value) => {
+                    let combinator = decider(conditions, output.clone(), value);
+                    self.outputs.push(combinator);
+                }
+                value @ Operand::Signal(_) => {
+                    let gate = self.spare()?;
+                    self.inner.push(decider(conditions, gate.clone(), 1));
+                    let combinator = arithmetic(
+                        value,
+                        ArithmeticOperation::Multiply,
+                        Operand::Signal(gate),
+                        output.clone(),
+                    );
+                    self.outputs.push(combinator);
+                }
+            }
+        }
+        self.pending.retain(|pending| pending != output);
+        Ok(())
+    }
+}
+
+fn arithmetic(
+    lhs: Operand,
+    operation: ArithmeticOperation,
+    rhs: Operand,
+    output: SignalId,
+) -> (&'static str, ControlBehavior) {
+    let (first_signal, first_constant) = lhs.split();
+    let (second_signal, second_constant) = rhs.split();
+    let parameters = ArithmeticCombinatorParameters {
+        first_signal,
+        first_constant,
+        second_signal,
+        second_constant,
+        operation: Some(operation),
+        output_signal: Some(output),
+        ..Default::default()
+    };
+    let behavior = ControlBehavior {
+        arithmetic_conditions: Some(parameters),
+        ..Default::default()
+    };
+    ("arithmetic-combinator", behavior)
+}
+
+fn decider(
+    conditions: Vec<DeciderCondition>,
+    output: SignalId,
+    value: i32,
+) -> (&'static str, ControlBehavior) {
+    let parameters = DeciderCombinatorParameters {
+        conditions,
+        outputs: vec![DeciderOutput {
+            signal: Some(output),
+            copy_count_from_input: Some(false),
+            constant: Some(value),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    let behavior = ControlBehavior {
+        decider_conditions: Some(parameters),
+        ..Default::default()
+    };
+    ("decider-combinator", behavior)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Combinators computing output signals from input signals, compiled from a program of
+/// statements such as `out = (iron-plate + copper-plate) * 2 if enable > 0`.
+///
+/// Each statement is on a line of its own, or separated from the next by `;`, and `#` starts a
+/// comment. A statement assigns an output signal the value of an expression, optionally only
+/// while a condition is true, and is 0 otherwise. Expressions are made of signals, integers,
+/// parentheses and the operators of arithmetic combinators, from the tightest binding: `^`,
+/// `*` `/` `%`, `+` `-`, `<<` `>>`, `&`, `xor`, and `|`. Conditions compare expressions with
+/// `==`, `!=`, `<`, `<=`, `>` or `>=`, joined by `and` and `or`.
+///
+/// A single capital letter names the virtual signal of that letter, names starting with
+/// `signal-` name other virtual signals, and any other name is an item. Names can contain
+/// hyphens, so subtraction needs spaces around it, except after a single capital letter.
+/// Statements can read the outputs of the statements before them, but not their own or later
+/// ones.
+///
+/// The input signals are read on the red input of the first combinator, and the outputs are on
+/// the red output of the last. Intermediate values are on the other virtual signals, and each
+/// operator adds a tick before the outputs settle.
+pub struct ExpressionCircuit {
+    /// The program.
+    pub source: String,
+}
+
+impl ExpressionCircuit {
+    /// The combinators in a row from the tile `(0, 0)` east, with the medium electric poles
+    /// powering them below.
+    pub fn generate(&self) -> Result<BlueprintBuilder, ExpressionError> {
+        let mut parser = Parser {
+            tokens: tokenize(&self.source)?,
+            position: 0,
+        };
+        let statements = parser.program()?;
+
+        let mut used = Vec::new();
+        let mut pending: Vec<SignalId> = Vec::new();
+        for statement in &statements {
+            if pending.contains(&statement.output) {
+                return Err(ExpressionError::Reassigned(statement.output.name.clone()));
+            }
+            pending.push(statement.output.clone());
+            statement.value.signals(&mut used);
+            for comparison in statement.condition.iter().flatten() {
+                comparison.lhs.signals(&mut used);
+                comparison.rhs.signals(&mut used);
+            }
+        }
+        used.extend(pending.iter().cloned());
+        let spare = ('A'..='Z')
+            .map(VirtualSignal::Letter)
+            .chain((0..=9).map(VirtualSignal::Digit))
+            .chain(SignalColor::ALL.into_iter().map(VirtualSignal::Color))
+            .chain([
+                VirtualSignal::Check,
+                VirtualSignal::Info,
+                VirtualSignal::Dot,
+            ])
+            .map(SignalId::virtual_signal)
+            .filter(|signal| !used.contains(signal))
+            .rev()
+            .collect();
+
+        let mut compiler = Compiler {
+            spare,
+            pending,
+            constants: Vec::new(),
+            inner: Vec::new(),
+            outputs: Vec::new(),
+        };
+        for statement in &statements {
+            compiler.statement(statement)?;
+        }
+
+        let mut builder = BlueprintBuilder::new().label("Expression circuit");
+        let output_count = compiler.outputs.len();
+        let combinators: Vec<_> = compiler
+            .inner
+            .into_iter()
+            .chain(compiler.outputs)
+            .enumerate()
+            .map(|(x, (name, behavior))| {
+                builder.add_entity(
+                    EntityBuilder::new(name)
+                        .at_tile(x as i64, 0)
+                        .control_behavior(behavior),
+                )
+            })
+            .collect();
+
+        // Inputs are on one red network, and intermediate values on one green network joining
+        // every input and output, the outputs joined on red as well
+        for pair in combinators.windows(2) {
+            for connector in [
+                WireConnectorId::CircuitRed,
+                WireConnectorId::CircuitGreen,
+                WireConnectorId::CombinatorOutputGreen,
+            ] {
+                builder = builder.wire(circuit_wire(pair[0], connector, pair[1], connector));
+            }
+        }
+        if let Some(&first) = combinators.first() {
+            builder = builder.wire(circuit_wire(
+                first,
+                WireConnectorId::CircuitGreen,
+                first,
+                WireConnectorId::CombinatorOutputGreen,
+            ));
+        }
+        let outputs = &combinators[combinators.len() - output_count..];
+        for pair in outputs.windows(2) {
+            builder = builder.wire(circuit_wire(
+                pair[0],
+                WireConnectorId::CombinatorOutputRed,
+                pair[1],
+                WireConnectorId::CombinatorOutputRed,
+            ));
+        }
+
+        // Outputs assigned constants come from a constant combinator at the end of the row
+        let mut width = combinators.len() as i64;
+        if !compiler.constants.is_empty() {
+            let mut behavior = ControlBehavior::default();
+            for (signal, value) in compiler.constants {
+                behavior.set_constant_signal(signal, value);
+            }
+            let constant = builder.add_entity(
+                EntityBuilder::new("constant-combinator")
+                    .at_tile(width, 0)
+                    .control_behavior(behavior),
+            );
+            if let Some(&last) = outputs.last() {
+                builder = builder.wire(circuit_wire(
+                    last,
+                    WireConnectorId::CombinatorOutputRed,
+                    constant,
+                    WireConnectorId::CircuitRed,
+                ));
+            }
+            width += 1;
+        }
+
+        let mut poles = Vec::new();
+        for x in (POLE_SPACING / 2..width + POLE_SPACING / 2).step_by(POLE_SPACING as usize) {
+            poles
+                .push(builder.add_entity(EntityBuilder::new("medium-electric-pole").at_tile(x, 2)));
+        }
+        for pair in poles.windows(2) {
+            builder = builder.wire(copper_wire(pair[0], pair[1]));
+        }
+        Ok(builder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The statements of `source`.
+    fn parse(source: &str) -> Result<Vec<Statement>, ExpressionError> {
+        Parser {
+            tokens: tokenize(source)?,
+            position: 0,
+        }
+        .program()
+    }
+
+    fn letter(letter: char) -> Expr {
+        Expr::Signal(SignalId::virtual_signal(VirtualSignal::Letter(letter)))
+    }
+
+    fn binary(operation: ArithmeticOperation, lhs: Expr, rhs: Expr) -> Expr {
+        Expr::Binary(operation, Box::new(lhs), Box::new(rhs))
+    }
+
+    #[test]
+    fn operators_bind_by_precedence() {
+        use ArithmeticOperation::*;
+
+        let value = |source| parse(source).unwrap().remove(0).value;
+        // `^` binds tightest and to the right, and negation looser than it
+        assert_eq!(
+            value("X = 1 + 2 * -A ^ 2 ^ 3"),
+            binary(
+                Add,
+                Expr::Number(1),
+                binary(
+                    Multiply,
+                    Expr::Number(2),
+                    binary(
+                        Subtract,
+                        Expr::Number(0),
+                        binary(
+                            Power,
+                            letter('A'),
+                            binary(Power, Expr::Number(2), Expr::Number(3))
+                        )
+                    )
+                )
+            )
+        );
+        // The others bind to the left, shifts looser than sums
+        assert_eq!(
+            value("X = A - B - C << 1 | D & E"),
+            binary(
+                Or,
+                binary(
+                    LeftShift,
+                    binary(
+                        Subtract,
+                        binary(Subtract, letter('A'), letter('B')),
+                        letter('C')
+                    ),
+                    Expr::Number(1)
+                ),
+                binary(And, letter('D'), letter('E'))
+            )
+        );
+        assert_eq!(
+            value("X = (A + B) * C"),
+            binary(Multiply, binary(Add, letter('A'), letter('B')), letter('C'))
+        );
+    }
+
+    #[test]
+    fn hyphens_after_a_letter_subtract() {
+        let statements = parse("X = A-1\nY = iron-plate - B-C").unwrap();
+        assert_eq!(
+            statements[0].value,
+            binary(ArithmeticOperation::Subtract, letter('A'), Expr::Number(1))
+        );
+        assert_eq!(
+            statements[1].value,
+            binary(
+                ArithmeticOperation::Subtract,
+                binary(
+                    ArithmeticOperation::Subtract,
+                    Expr::Signal(SignalId::item("iron-plate")),
+                    letter('B')
+                ),
+                letter('C')
+            )
+        );
+        assert_eq!(
+            parse("X = speed-module-2").unwrap()[0].value,
+            Expr::Signal(SignalId::item("speed-module-2"))
+        );
+    }
+}
@@ -0,0 +1,425 @@
+use thiserror::Error;
+
+use crate::blueprint::{BlueprintBuilder, Color, EntityBuilder};
+
+use super::solar::copper_wire;
+
+/// The tiles across each glyph.
+const GLYPH_WIDTH: i64 = 5;
+
+/// The tiles down each glyph.
+const GLYPH_HEIGHT: usize = 7;
+
+/// The glyphs of the font, each row a bit per tile with the leftmost tile the highest bit.
+const GLYPHS: &[(char, [u8; GLYPH_HEIGHT])] = &[
+    (
+        'A',
+        [
+            0b01110, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001,
+        ],
+    ),
+    (
+        'B',
+        [
+            0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110,
+        ],
+    ),
+    (
+        'C',
+        [
+            0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110,
+        ],
+    ),
+    (
+        'D',
+        [
+            0b11110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b11110,
+        ],
+    ),
+    (
+        'E',
+        [
+            0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111,
+        ],
+    ),
+    (
+        'F',
+        [
+            0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000,
+        ],
+    ),
+    (
+        'G',
+        [
+            0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111,
+        ],
+    ),
+    (
+        'H',
+        [
+            0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001,
+        ],
+    ),
+    (
+        'I',
+        [
+            0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110,
+        ],
+    ),
+    (
+        'J',
+        [
+            0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100,
+        ],
+    ),
+    (
+        'K',
+        [
+            0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001,
+        ],
+    ),
+    (
+        'L',
+        [
+            0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111,
+        ],
+    ),
+    (
+        'M',
+        [
+            0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001,
+        ],
+    ),
+    (
+        'N',
+        [
+            0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001,
+        ],
+    ),
+    (
+        'O',
+        [
+            0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110,
+        ],
+    ),
+    (
+        'P',
+        [
+            0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000,
+        ],
+    ),
+    (
+        'Q',
+        [
+            0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101,
+        ],
+    ),
+    (
+        'R',
+        [
+            0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001,
+        ],
+    ),
+    (
+        'S',
+        [
+            0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110,
+        ],
+    ),
+    (
+        'T',
+        [
+            0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100,
+        ],
+    ),
+    (
+        'U',
+        [
+            0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110,
+        ],
+    ),
+    (
+        'V',
+        [
+            0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100,
+        ],
+    ),
+    (
+        'W',
+        [
+            0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010,
+        ],
+    ),
+    (
+        'X',
+        [
+            0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001,
+        ],
+    ),
+    (
+        'Y',
+        [
+            0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100, 0b00100,
+        ],
+    ),
+    (
+        'Z',
+        [
+            0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111,
+        ],
+    ),
+    (
+        '0',
+        [
+            0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110,
+        ],
+    ),
+    (
+        '1',
+        [
+            0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110,
+        ],
+    ),
+    (
+        '2',
+        [
+            0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111,
+        ],
+    ),
+    (
+        '3',
+        [
+            0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110,
+        ],
+    ),
+    (
+        '4',
+        [
+            0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010,
+        ],
+    ),
+    (
+        '5',
+        [
+            0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110,
+        ],
+    ),
+    (
+        '6',
+        [
+            0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110,
+        ],
+    ),
+    (
+        '7',
+        [
+            0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000,
+        ],
+    ),
+    (
+        '8',
+        [
+            0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110,
+        ],
+    ),
+    (
+        '9',
+        [
+            0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100,
+        ],
+    ),
+    (
+        ' ',
+        [
+            0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000,
+        ],
+    ),
+    (
+        '.',
+        [
+            0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b01100,
+        ],
+    ),
+    (
+        ',',
+        [
+            0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b00100, 0b01000,
+        ],
+    ),
+    (
+        ':',
+        [
+            0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b01100, 0b00000,
+        ],
+    ),
+    (
+        '-',
+        [
+            0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000,
+        ],
+    ),
+    (
+        '!',
+        [
+            0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00000, 0b00100,
+        ],
+    ),
+    (
+        '?',
+        [
+            0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b00000, 0b00100,
+        ],
+    ),
+    (
+        '/',
+        [
+            0b00000, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b00000,
+        ],
+    ),
+    (
+        '(',
+        [
+            0b00010, 0b00100, 0b01000, 0b01000, 0b01000, 0b00100, 0b00010,
+        ],
+    ),
+    (
+        ')',
+        [
+            0b01000, 0b00100, 0b00010, 0b00010, 0b00010, 0b00100, 0b01000,
+        ],
+    ),
+    (
+        '+',
+        [
+            0b00000, 0b00100, 0b00100, 0b11111, 0b00100, 0b00100, 0b00000,
+        ],
+    ),
+    (
+        '=',
+        [
+            0b00000, 0b00000, 0b11111, 0b00000, 0b11111, 0b00000, 0b00000,
+        ],
+    ),
+    (
+        '\'',
+        [
+            0b00100, 0b00100, 0b01000, 0b00000, 0b00000, 0b00000, 0b00000,
+        ],
+    ),
+    (
+        '#',
+        [
+            0b01010, 0b01010, 0b11111, 0b01010, 0b11111, 0b01010, 0b01010,
+        ],
+    ),
+];
+
+/// The glyph drawing `c`, with lowercase letters drawn as capitals.
+fn glyph(c: char) -> Option<[u8; GLYPH_HEIGHT]> {
+    let c = c.to_ascii_uppercase();
+    GLYPHS
+        .iter()
+        .find(|&&(glyph, _)| glyph == c)
+        .map(|&(_, rows)| rows)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("there's no glyph for {0:?} to spell it in lamps")]
+/// A character [LampText::generate] can't spell.
+pub struct UnsupportedCharacter(pub char);
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// Text spelled out in small lamps, for station signs and the like. Each character is 5 lamps
+/// across and 7 down, with a column free between characters for the medium electric poles
+/// powering them, and a row free between lines.
+///
+/// Letters, digits, spaces, and `.,:-!?/()+='#` are supported, letters drawn as capitals.
+pub struct LampText {
+    /// The text, with a line of lamps for each line.
+    pub text: String,
+    /// The color the lamps shine, or the game's default white if `None`.
+    pub color: Option<Color>,
+}
+
+impl LampText {
+    /// The lamps, with the top-left corner of the first character on the tile `(0, 0)`, unless
+    /// the text has a character there's no glyph for.
+    pub fn generate(&self) -> Result<BlueprintBuilder, UnsupportedCharacter> {
+        let mut builder = BlueprintBuilder::new().label(self.text.replace('\n', " "));
+        let mut first_poles = Vec::new();
+        for (line, text) in self.text.lines().enumerate() {
+            let top = line as i64 * (GLYPH_HEIGHT as i64 + 1);
+            // A pole in the middle row of the column before each character powers the three
+            // columns either side, so with poles both sides every lamp of a character is reached
+            let pole = |left: i64| {
+                EntityBuilder::new("medium-electric-pole")
+                    .at_tile(left - 1, top + GLYPH_HEIGHT as i64 / 2)
+            };
+            let mut poles = Vec::new();
+            let mut left = 0;
+            for c in text.chars() {
+                poles.push(builder.add_entity(pole(left)));
+                let rows = glyph(c).ok_or(UnsupportedCharacter(c))?;
+                for (y, row) in rows.into_iter().enumerate() {
+                    for x in 0..GLYPH_WIDTH {
+                        if row & (1 << (GLYPH_WIDTH - 1 - x)) == 0 {
+                            continue;
+                        }
+                        let mut lamp =
+                            EntityBuilder::new("small-lamp").at_tile(left + x, top + y as i64);
+                        if let Some(color) = &self.color {
+                            lamp = lamp.color(color.clone());
+                        }
+                        builder.add_entity(lamp);
+                    }
+                }
+                left += GLYPH_WIDTH + 1;
+            }
+            poles.push(builder.add_entity(pole(left)));
+
+            for pair in poles.windows(2) {
+                builder = builder.wire(copper_wire(pair[0], pair[1]));
+            }
+            first_poles.push(poles[0]);
+        }
+        // The first poles of the lines are in line, one line apart
+        for pair in first_poles.windows(2) {
+            builder = builder.wire(copper_wire(pair[0], pair[1]));
+        }
+        Ok(builder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{blueprint::Position, power::PowerGraph, prototypes::PrototypeDb};
+
+    #[test]
+    fn text_is_spelled_in_powered_lamps() {
+        let db = PrototypeDb::vanilla();
+        let text = LampText {
+            text: "Hi\nHI".to_owned(),
+            color: None,
+        };
+        let blueprint = text.generate().unwrap().build_with(db).unwrap();
+        assert_eq!(blueprint.label.as_deref(), Some("Hi HI"));
+        let lamps: Vec<_> = blueprint
+            .entities
+            .iter()
+            .flatten()
+            .filter(|e| e.name == "small-lamp")
+            .collect();
+        // 17 lamps for each H and 11 for each I
+        assert_eq!(lamps.len(), 2 * (17 + 11));
+        assert_eq!(lamps[0].position, Position { x: 0.5, y: 0.5 });
+        assert!(blueprint.validate_with(db).is_empty());
+        assert_eq!(PowerGraph::new(&blueprint, db).networks().len(), 1);
+
+        let euro = LampText {
+            text: "5€".to_owned(),
+            color: None,
+        };
+        assert_eq!(euro.generate().err(), Some(UnsupportedCharacter('€')));
+    }
+}
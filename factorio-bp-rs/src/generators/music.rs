@@ -0,0 +1,576 @@
+use core::num::NonZeroUsize;
+use std::collections::BTreeMap;
+
+use thiserror::Error;
+
+use crate::blueprint::{
+    control_behavior::{DeciderCombinatorParameters, DeciderCondition, DeciderOutput},
+    BlueprintBuilder, CircuitCondition, Comparator, ControlBehavior, EntityBuilder, EntityDetails,
+    Notes, SignalId, SpeakerCircuitParameters, SpeakerParameter, VirtualSignal, Wire,
+    WireConnectorId, VANILLA_INSTRUMENTS,
+};
+
+use super::solar::copper_wire;
+
+/// Game ticks per second.
+const TICKS_PER_SECOND: f64 = 60.0;
+
+/// The tempo of a MIDI file until it sets one, in microseconds per quarter note.
+const DEFAULT_TEMPO: u32 = 500_000;
+
+/// The MIDI channel of percussion, which doesn't play notes.
+const PERCUSSION_CHANNEL: u8 = 9;
+
+/// The most notes played at once, a speaker each.
+const MAX_VOICES: usize = 35;
+
+/// The decider combinators in each row.
+const ROW_LENGTH: usize = 40;
+
+/// The columns between medium electric poles.
+const POLE_SPACING: i64 = 7;
+
+/// The silent ticks at the end of the melody before it plays again.
+const LOOP_GAP: u32 = 60;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+/// Why a MIDI file couldn't be read.
+pub enum MidiError {
+    /// The file doesn't start with a MIDI header.
+    #[error("not a MIDI file")]
+    NotMidi,
+    /// The file ends partway through a chunk or event.
+    #[error("the MIDI file ends early")]
+    Truncated,
+    /// The file counts time in SMPTE frames rather than beats, or doesn't say how.
+    #[error("only MIDI files timed in beats are supported")]
+    SmpteTiming,
+    /// An event has a status byte no MIDI event starts with.
+    #[error("unknown MIDI event {0:#04x}")]
+    UnknownEvent(u8),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// A note starting to play.
+pub struct Note {
+    /// The game tick the note starts on, from the start of the melody.
+    pub tick: u32,
+    /// The MIDI note number, 60 being middle C.
+    pub key: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// The notes of a piece of music, in the order they start.
+pub struct Melody {
+    /// The notes.
+    pub notes: Vec<Note>,
+}
+
+/// Reads the parts of a MIDI file in order.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], MidiError> {
+        if self.bytes.len() < n {
+            return Err(MidiError::Truncated);
+        }
+        let (taken, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8, MidiError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, MidiError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, MidiError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// A variable length quantity, 7 bits a byte with the top bit set on all but the last.
+    fn variable(&mut self) -> Result<u32, MidiError> {
+        let mut value = 0u32;
+        loop {
+            let byte = self.u8()?;
+            value = (value << 7) | (byte & 0x7f) as u32;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+    }
+}
+
+impl Melody {
+    /// The notes of a standard MIDI file, from every track and channel but percussion.
+    pub fn from_midi(bytes: &[u8]) -> Result<Self, MidiError> {
+        let mut reader = Reader { bytes };
+        if reader.take(4).map_err(|_| MidiError::NotMidi)? != b"MThd" {
+            return Err(MidiError::NotMidi);
+        }
+        let header = reader.u32()? as usize;
+        let mut header = Reader {
+            bytes: reader.take(header)?,
+        };
+        let _format = header.u16()?;
+        let _tracks = header.u16()?;
+        let division = header.u16()?;
+        if division == 0 || division & 0x8000 != 0 {
+            return Err(MidiError::SmpteTiming);
+        }
+
+        // Tempo changes and notes, by the MIDI tick they happen on
+        let mut tempos = BTreeMap::from([(0, DEFAULT_TEMPO)]);
+        let mut keys = Vec::new();
+        while !reader.bytes.is_empty() {
+            let kind = reader.take(4)?;
+            let length = reader.u32()? as usize;
+            let mut track = Reader {
+                bytes: reader.take(length)?,
+            };
+            if kind != b"MTrk" {
+                continue;
+            }
+
+            let mut tick = 0u64;
+            let mut running_status = None;
+            while !track.bytes.is_empty() {
+                tick += track.variable()? as u64;
+                let mut status = track.u8()?;
+                let mut first = None;
+                if status < 0x80 {
+                    // Running status: the byte is the first data byte of the previous event kind
+                    first = Some(status);
+                    status = running_status.ok_or(MidiError::UnknownEvent(status))?;
+                }
+                match status {
+                    0xff => {
+                        let meta = track.u8()?;
+                        let length = track.variable()? as usize;
+                        let data = track.take(length)?;
+                        if let (0x51, &[a, b, c]) = (meta, data) {
+                            tempos.insert(tick, u32::from_be_bytes([0, a, b, c]));
+                        }
+                    }
+                    0xf0 | 0xf7 => {
+                        let length = track.variable()? as usize;
+                        track.take(length)?;
+                    }
+                    0x80..=0xef => {
+                        running_status = Some(status);
+                        let mut data = || match first.take() {
+                            Some(byte) => Ok(byte),
+                            None => track.u8(),
+                        };
+                        let channel = status & 0x0f;
+                        match status & 0xf0 {
+                            0x90 => {
+                                let (key, velocity) = (data()?, data()?);
+                                if velocity > 0 && channel != PERCUSSION_CHANNEL {
+                                    keys.push((tick, key));
+                                }
+                            }
+                            0xc0 | 0xd0 => {
+                                data()?;
+                            }
+                            _ => {
+                                data()?;
+                                data()?;
+                            }
+                        }
+                    }
+                    _ => return Err(MidiError::UnknownEvent(status)),
+                }
+            }
+        }
+
+        // Seconds from the start to each tempo change, to turn MIDI ticks into game ticks
+        let mut changes = Vec::new();
+        let mut seconds = 0.0;
+        let mut previous = (0, DEFAULT_TEMPO);
+        for (&tick, &tempo) in &tempos {
+            seconds += (tick - previous.0) as f64 * previous.1 as f64 / 1e6 / division as f64;
+            changes.push((tick, seconds, tempo));
+            previous = (tick, tempo);
+        }
+        let game_tick = |tick: u64| -> u32 {
+            let index = changes.partition_point(|&(change, _, _)| change <= tick) - 1;
+            let (change, seconds, tempo) = changes[index];
+            let seconds = seconds + (tick - change) as f64 * tempo as f64 / 1e6 / division as f64;
+            (seconds * TICKS_PER_SECOND).round() as u32
+        };
+
+        let mut notes: Vec<Note> = keys
+            .into_iter()
+            .map(|(tick, key)| Note {
+                tick: game_tick(tick),
+                key,
+            })
+            .collect();
+        notes.sort_by_key(|note| note.tick);
+        Ok(Self { notes })
+    }
+}
+
+/// The signal the speaker of a voice listens to: the digits, then the letters but T.
+fn voice_signal(voice: usize) -> SignalId {
+    let signal = match voice {
+        0..=9 => VirtualSignal::Digit(voice as u8),
+        _ => VirtualSignal::Letter(
+            ('A'..='Z')
+                .filter(|&letter| letter != 'T')
+                .nth(voice - 10)
+                .unwrap_or('Z'),
+        ),
+    };
+    SignalId::virtual_signal(signal)
+}
+
+/// The signal the clock counts ticks on.
+fn clock_signal() -> SignalId {
+    SignalId::virtual_signal(VirtualSignal::Letter('T'))
+}
+
+/// A circuit wire between two entities.
+pub(super) fn circuit_wire(
+    source: NonZeroUsize,
+    source_connector: WireConnectorId,
+    target: NonZeroUsize,
+    target_connector: WireConnectorId,
+) -> Wire {
+    Wire {
+        source_entity: source,
+        source_connector,
+        target_entity: target,
+        target_connector,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Programmable speakers playing a melody on a loop, driven by combinators.
+///
+/// A decider combinator counting on itself is the clock, counting ticks on signal T. Each tick a
+/// note starts on has a decider combinator of its own, outputting the pitch of each note to the
+/// speaker of its voice while the clock reads that tick. Speakers play the value of their signal
+/// as the pitch, so there is one for each note played at once, up to 35. Notes the instrument
+/// can't play are moved by octaves into its range.
+pub struct MusicBox {
+    /// The melody to play.
+    pub melody: Melody,
+    /// The index of the instrument in [VANILLA_INSTRUMENTS].
+    pub instrument_id: u32,
+    /// The volume of the speakers, from 0 to 1.
+    pub volume: f64,
+}
+
+impl Default for MusicBox {
+    /// No notes, on the piano, at full volume.
+    fn default() -> Self {
+        Self {
+            melody: Melody::default(),
+            instrument_id: 3,
+            volume: 1.0,
+        }
+    }
+}
+
+impl MusicBox {
+    /// The note of the instrument playing the MIDI note `key`, if the instrument is known.
+    fn note_id(&self, key: u8) -> Option<u32> {
+        let instrument = VANILLA_INSTRUMENTS.get(self.instrument_id as usize)?;
+        match instrument.notes {
+            Notes::Named(names) => Some(key as u32 % names.len() as u32),
+            Notes::Chromatic { lowest, count } => {
+                let mut key = key as i32;
+                while key < lowest as i32 {
+                    key += 12;
+                }
+                while key >= lowest as i32 + count as i32 {
+                    key -= 12;
+                }
+                instrument.note_for_midi(key as u8)
+            }
+        }
+    }
+
+    /// The speakers and combinators. The speakers are along the top, starting on the tile
+    /// `(2, -1)`, the clock below them, and the rows of note combinators under it.
+    pub fn generate(&self) -> BlueprintBuilder {
+        let mut builder = BlueprintBuilder::new().label("Music box");
+
+        // The notes starting on each tick, each voice playing one
+        let mut chords: BTreeMap<u32, Vec<u32>> = BTreeMap::new();
+        for note in &self.melody.notes {
+            let Some(note_id) = self.note_id(note.key) else {
+                continue;
+            };
+            let chord = chords.entry(note.tick).or_default();
+            if !chord.contains(&note_id) && chord.len() < MAX_VOICES {
+                chord.push(note_id);
+            }
+        }
+        let voices = chords.values().map(Vec::len).max().unwrap_or(0);
+        let length = chords.keys().last().map_or(0, |&tick| tick + 1) + LOOP_GAP;
+
+        // The clock counts from 1 to `length`, the constant combinator adding 1 each tick
+        let mut constant = ControlBehavior::default();
+        constant.set_constant_signal(clock_signal(), 1);
+        let constant = builder.add_entity(
+            EntityBuilder::new("constant-combinator")
+                .at_tile(0, 1)
+                .control_behavior(constant),
+        );
+        let clock = ControlBehavior {
+            decider_conditions: Some(DeciderCombinatorParameters {
+                conditions: vec![DeciderCondition {
+                    first_signal: Some(clock_signal()),
+                    constant: Some(length as i32),
+                    comparator: Some(Comparator::LessThan),
+                    ..Default::default()
+                }],
+                outputs: vec![DeciderOutput {
+                    signal: Some(clock_signal()),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let clock = builder.add_entity(
+            EntityBuilder::new("decider-combinator")
+                .at_tile(1, 0)
+                .control_behavior(clock),
+        );
+        builder = builder
+            .wire(circuit_wire(
+                constant,
+                WireConnectorId::CircuitRed,
+                clock,
+                WireConnectorId::CircuitRed,
+            ))
+            .wire(circuit_wire(
+                clock,
+                WireConnectorId::CombinatorOutputRed,
+                clock,
+                WireConnectorId::CircuitRed,
+            ));
+
+        // Speakers along the top, each wired to the next on green
+        let mut speakers = Vec::new();
+        for voice in 0..voices {
+            let speaker = ControlBehavior {
+                circuit_condition: Some(CircuitCondition {
+                    first_signal: Some(voice_signal(voice)),
+                    comparator: Some(Comparator::GreaterThan),
+                    ..Default::default()
+                }),
+                circuit_parameters: Some(SpeakerCircuitParameters {
+                    signal_value_is_pitch: Some(true),
+                    instrument_id: Some(self.instrument_id),
+                    note_id: None,
+                }),
+                ..Default::default()
+            };
+            let details = EntityDetails::Speaker {
+                parameters: Some(SpeakerParameter {
+                    playback_volume: self.volume,
+                    playback_globally: false,
+                    allow_polyphony: true,
+                }),
+                alert_parameters: None,
+            };
+            speakers.push(
+                builder.add_entity(
+                    EntityBuilder::new("programmable-speaker")
+                        .at_tile(2 + voice as i64, -1)
+                        .control_behavior(speaker)
+                        .details(details),
+                ),
+            );
+        }
+        for pair in speakers.windows(2) {
+            builder = builder.wire(circuit_wire(
+                pair[0],
+                WireConnectorId::CircuitGreen,
+                pair[1],
+                WireConnectorId::CircuitGreen,
+            ));
+        }
+
+        // A decider combinator for each tick a note starts on, reading the clock on red and
+        // outputting the pitches, one more than the note, to the speakers on green
+        let mut deciders = Vec::new();
+        for (i, (&tick, chord)) in chords.iter().enumerate() {
+            let outputs = chord
+                .iter()
+                .enumerate()
+                .map(|(voice, &note_id)| DeciderOutput {
+                    signal: Some(voice_signal(voice)),
+                    copy_count_from_input: Some(false),
+                    constant: Some(note_id as i32 + 1),
+                    ..Default::default()
+                })
+                .collect();
+            let decider = ControlBehavior {
+                decider_conditions: Some(DeciderCombinatorParameters {
+                    conditions: vec![DeciderCondition {
+                        first_signal: Some(clock_signal()),
+                        constant: Some(tick as i32 + 1),
+                        comparator: Some(Comparator::Equal),
+                        ..Default::default()
+                    }],
+                    outputs,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            let (column, row) = ((i % ROW_LENGTH) as i64, (i / ROW_LENGTH) as i64);
+            deciders.push(
+                builder.add_entity(
+                    EntityBuilder::new("decider-combinator")
+                        .at_tile(2 + column, row * 3)
+                        .control_behavior(decider),
+                ),
+            );
+        }
+        let rows: Vec<_> = deciders.chunks(ROW_LENGTH).collect();
+
+        // Each row is wired along, and its first combinator to the first of the row above, the
+        // first row to the clock and speakers
+        let firsts: Vec<_> = rows.iter().map(|row| row[0]).collect();
+        let wired = rows
+            .iter()
+            .flat_map(|row| row.windows(2).map(|pair| (pair[0], pair[1])))
+            .chain(firsts.windows(2).map(|pair| (pair[0], pair[1])));
+        for (a, b) in wired {
+            builder = builder
+                .wire(circuit_wire(
+                    a,
+                    WireConnectorId::CircuitRed,
+                    b,
+                    WireConnectorId::CircuitRed,
+                ))
+                .wire(circuit_wire(
+                    a,
+                    WireConnectorId::CombinatorOutputGreen,
+                    b,
+                    WireConnectorId::CombinatorOutputGreen,
+                ));
+        }
+        if let Some(&first) = firsts.first() {
+            builder = builder.wire(circuit_wire(
+                clock,
+                WireConnectorId::CombinatorOutputRed,
+                first,
+                WireConnectorId::CircuitRed,
+            ));
+            if let Some(&speaker) = speakers.first() {
+                builder = builder.wire(circuit_wire(
+                    first,
+                    WireConnectorId::CombinatorOutputGreen,
+                    speaker,
+                    WireConnectorId::CircuitGreen,
+                ));
+            }
+        }
+
+        // Medium electric poles in the free row under every other row of combinators, each
+        // powering the rows above and below it
+        let last_column = 1 + voices.max(chords.len().min(ROW_LENGTH)) as i64;
+        let mut pole_rows: Vec<Vec<NonZeroUsize>> = Vec::new();
+        for row in (0..rows.len().max(1) as i64).step_by(2) {
+            let poles = (0..=last_column / POLE_SPACING)
+                .map(|i| {
+                    let pole = EntityBuilder::new("medium-electric-pole")
+                        .at_tile(POLE_SPACING / 2 + i * POLE_SPACING, row * 3 + 2);
+                    builder.add_entity(pole)
+                })
+                .collect();
+            pole_rows.push(poles);
+        }
+        for row in &pole_rows {
+            for pair in row.windows(2) {
+                builder = builder.wire(copper_wire(pair[0], pair[1]));
+            }
+        }
+        for pair in pole_rows.windows(2) {
+            builder = builder.wire(copper_wire(pair[0][0], pair[1][0]));
+        }
+        builder
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A MIDI file with a header and one track of `events`, counting 96 ticks a beat.
+    fn midi(events: &[u8]) -> Vec<u8> {
+        let mut bytes = b"MThd\0\0\0\x06\0\0\0\x01\0\x60MTrk".to_vec();
+        bytes.extend((events.len() as u32).to_be_bytes());
+        bytes.extend(events);
+        bytes
+    }
+
+    /// Middle C, the E above it a beat later using running status, and a drum in between.
+    const EVENTS: &[u8] = &[
+        0x00, 0x90, 60, 100, // middle C
+        0x30, 0x99, 36, 100, // a drum, half a beat in
+        0x30, 0x90, 64, 100, // E
+        0x00, 0x80, 64, 0, // E stops
+        0x00, 0xff, 0x2f, 0x00, // end of track
+    ];
+
+    #[test]
+    fn notes_are_timed_in_game_ticks() {
+        let melody = Melody::from_midi(&midi(EVENTS)).unwrap();
+        // A beat is half a second at the default tempo
+        assert_eq!(
+            melody.notes,
+            [Note { tick: 0, key: 60 }, Note { tick: 30, key: 64 }]
+        );
+    }
+
+    #[test]
+    fn truncated_files_are_errors() {
+        let bytes = midi(EVENTS);
+        // Stopping right after the header is a file without tracks
+        let header = 14;
+        for length in (0..bytes.len()).filter(|&length| length != header) {
+            let expected = match length {
+                0..4 => MidiError::NotMidi,
+                _ => MidiError::Truncated,
+            };
+            assert_eq!(Melody::from_midi(&bytes[..length]), Err(expected));
+        }
+        assert_eq!(Melody::from_midi(&bytes[..header]), Ok(Melody::default()));
+    }
+
+    #[test]
+    fn malformed_files_are_errors() {
+        assert_eq!(Melody::from_midi(b"RIFF\0\0\0\0"), Err(MidiError::NotMidi));
+        let mut smpte = midi(EVENTS);
+        smpte[12] = 0xe7;
+        assert_eq!(Melody::from_midi(&smpte), Err(MidiError::SmpteTiming));
+        assert_eq!(
+            Melody::from_midi(&midi(&[0x00, 0xf4])),
+            Err(MidiError::UnknownEvent(0xf4))
+        );
+        // A data byte with no event before it to repeat
+        assert_eq!(
+            Melody::from_midi(&midi(&[0x00, 60, 100])),
+            Err(MidiError::UnknownEvent(60))
+        );
+        // A track claiming to be longer than the file
+        let mut long = midi(EVENTS);
+        long[21] += 1;
+        assert_eq!(Melody::from_midi(&long), Err(MidiError::Truncated));
+    }
+}
@@ -0,0 +1,254 @@
+use std::collections::HashSet;
+
+use image::{imageops::FilterType, DynamicImage};
+
+use crate::{
+    blueprint::{BlueprintBuilder, Color, EntityBuilder, SignalColor},
+    render::Rgb,
+};
+
+use super::solar::{copper_wire, CELL_SIZE};
+
+/// The alpha below which pixels count as transparent and are left empty.
+const OPAQUE: u8 = 128;
+
+/// The refined concrete tiles and the colours they show on the map.
+const CONCRETE: [(&str, Rgb); 12] = [
+    ("refined-concrete", [49, 48, 45]),
+    ("acid-refined-concrete", [142, 194, 40]),
+    ("black-refined-concrete", [25, 25, 25]),
+    ("blue-refined-concrete", [26, 118, 216]),
+    ("brown-refined-concrete", [76, 29, 0]),
+    ("cyan-refined-concrete", [70, 192, 181]),
+    ("green-refined-concrete", [23, 195, 43]),
+    ("orange-refined-concrete", [221, 127, 33]),
+    ("pink-refined-concrete", [236, 98, 131]),
+    ("purple-refined-concrete", [123, 28, 168]),
+    ("red-refined-concrete", [207, 6, 0]),
+    ("yellow-refined-concrete", [212, 169, 19]),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+/// What a pixel of the image becomes, and the colour it stands for.
+pub enum Swatch {
+    /// A tile of the prototype named.
+    Tile(String, Rgb),
+    /// A small lamp shining the colour.
+    Lamp(Rgb),
+}
+
+impl Swatch {
+    /// Refined concrete in every colour.
+    pub fn concrete() -> Vec<Swatch> {
+        CONCRETE
+            .iter()
+            .map(|&(name, rgb)| Swatch::Tile(name.to_owned(), rgb))
+            .collect()
+    }
+
+    /// Lamps in the colours of the [SignalColor]s, which lamps can also be set to by signals.
+    pub fn signal_lamps() -> Vec<Swatch> {
+        SignalColor::ALL
+            .iter()
+            .map(|color| {
+                let (r, g, b) = color.rgb();
+                Swatch::Lamp([r, g, b])
+            })
+            .collect()
+    }
+
+    /// The colour the swatch stands for.
+    pub fn rgb(&self) -> Rgb {
+        match self {
+            Swatch::Tile(_, rgb) | Swatch::Lamp(rgb) => *rgb,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// An image drawn in tiles or lamps, a tile per pixel after scaling it down. Each pixel becomes
+/// the swatch of the palette closest to its colour, and transparent pixels are left empty.
+///
+/// Lamps are powered by substations every [CELL_SIZE] tiles, which take the place of the lamps
+/// under them.
+pub struct PixelArt {
+    /// The swatches to draw with.
+    pub palette: Vec<Swatch>,
+    /// The pixels of the image along each side of a tile.
+    pub scale: u32,
+    /// Whether to spread the difference between each pixel and its swatch over the pixels next
+    /// to it, Floyd-Steinberg dithering, so that areas average out to the right colour.
+    pub dither: bool,
+}
+
+impl Default for PixelArt {
+    /// Refined concrete, a tile per pixel, dithered.
+    fn default() -> Self {
+        Self {
+            palette: Swatch::concrete(),
+            scale: 1,
+            dither: true,
+        }
+    }
+}
+
+impl PixelArt {
+    /// The swatch closest to `rgb`.
+    fn closest(&self, rgb: [f32; 3]) -> Option<&Swatch> {
+        let distance = |swatch: &Swatch| -> f32 {
+            swatch
+                .rgb()
+                .iter()
+                .zip(rgb)
+                .map(|(&a, b)| (a as f32 - b).powi(2))
+                .sum()
+        };
+        self.palette
+            .iter()
+            .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+    }
+
+    /// The image, with its top-left pixel on the tile `(0, 0)`.
+    pub fn generate(&self, image: &DynamicImage) -> BlueprintBuilder {
+        let mut builder = BlueprintBuilder::new().label("Pixel art");
+        let scale = self.scale.max(1);
+        let image = image.resize_exact(
+            (image.width() / scale).max(1),
+            (image.height() / scale).max(1),
+            FilterType::Triangle,
+        );
+        let image = image.to_rgba8();
+        let (width, height) = (image.width() as i64, image.height() as i64);
+
+        // Substations in the middle of each cell, wired to those right of and below them
+        let mut substations = HashSet::new();
+        if self
+            .palette
+            .iter()
+            .any(|swatch| matches!(swatch, Swatch::Lamp(_)))
+        {
+            let (across, down) = (
+                (width + CELL_SIZE - 1) / CELL_SIZE,
+                (height + CELL_SIZE - 1) / CELL_SIZE,
+            );
+            let mut numbers = Vec::new();
+            for cell in 0..across * down {
+                let x = cell % across * CELL_SIZE + CELL_SIZE / 2 - 1;
+                let y = cell / across * CELL_SIZE + CELL_SIZE / 2 - 1;
+                numbers.push(builder.add_entity(EntityBuilder::new("substation").at_tile(x, y)));
+                substations.extend([(x, y), (x + 1, y), (x, y + 1), (x + 1, y + 1)]);
+            }
+            for (cell, &substation) in numbers.iter().enumerate() {
+                let cell = cell as i64;
+                let right = (cell % across + 1 < across).then(|| numbers[cell as usize + 1]);
+                let below = numbers.get((cell + across) as usize).copied();
+                for neighbor in right.into_iter().chain(below) {
+                    builder = builder.wire(copper_wire(substation, neighbor));
+                }
+            }
+        }
+
+        let mut pixels: Vec<[f32; 3]> = image
+            .pixels()
+            .map(|pixel| {
+                let [r, g, b, _] = pixel.0;
+                [r as f32, g as f32, b as f32]
+            })
+            .collect();
+        for y in 0..height {
+            for x in 0..width {
+                let i = (y * width + x) as usize;
+                if image.get_pixel(x as u32, y as u32).0[3] < OPAQUE {
+                    continue;
+                }
+                let Some(swatch) = self.closest(pixels[i]) else {
+                    continue;
+                };
+
+                if self.dither {
+                    let rgb = swatch.rgb();
+                    let error: [f32; 3] = core::array::from_fn(|c| pixels[i][c] - rgb[c] as f32);
+                    for (dx, dy, weight) in [(1, 0, 7.0), (-1, 1, 3.0), (0, 1, 5.0), (1, 1, 1.0)] {
+                        let (nx, ny) = (x + dx, y + dy);
+                        if !(0..width).contains(&nx) || ny >= height {
+                            continue;
+                        }
+                        let neighbor = &mut pixels[(ny * width + nx) as usize];
+                        for c in 0..3 {
+                            neighbor[c] += error[c] * weight / 16.0;
+                        }
+                    }
+                }
+
+                match swatch {
+                    Swatch::Tile(name, _) => builder = builder.tile(name.clone(), x, y),
+                    Swatch::Lamp(_) if substations.contains(&(x, y)) => {}
+                    Swatch::Lamp([r, g, b]) => {
+                        let color = Color {
+                            r: *r as f64 / 255.0,
+                            g: *g as f64 / 255.0,
+                            b: *b as f64 / 255.0,
+                            a: 1.0,
+                        };
+                        builder.add_entity(
+                            EntityBuilder::new("small-lamp").at_tile(x, y).color(color),
+                        );
+                    }
+                }
+            }
+        }
+        builder
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgba, RgbaImage};
+
+    use super::*;
+    use crate::prototypes::PrototypeDb;
+
+    #[test]
+    fn pixels_become_the_closest_swatch() {
+        let db = PrototypeDb::vanilla();
+        // A red left half and a transparent right half
+        let image = RgbaImage::from_fn(4, 2, |x, _| match x {
+            0 | 1 => Rgba([200, 10, 0, 255]),
+            _ => Rgba([0, 0, 0, 0]),
+        });
+        let art = PixelArt {
+            dither: false,
+            ..PixelArt::default()
+        };
+        let blueprint = art
+            .generate(&DynamicImage::ImageRgba8(image))
+            .build_with(db)
+            .unwrap();
+        let tiles = blueprint.tiles.unwrap();
+        assert_eq!(tiles.len(), 4);
+        assert!(tiles.iter().all(|t| t.name == "red-refined-concrete"));
+        assert!(tiles.iter().all(|t| t.position.x < 2.0));
+        assert_eq!(blueprint.entities, None);
+
+        let white = RgbaImage::from_pixel(20, 20, Rgba([255, 255, 255, 255]));
+        let lamps = PixelArt {
+            palette: Swatch::signal_lamps(),
+            ..PixelArt::default()
+        };
+        let blueprint = lamps
+            .generate(&DynamicImage::ImageRgba8(white))
+            .build_with(db)
+            .unwrap();
+        let count = |name| {
+            blueprint
+                .entities
+                .iter()
+                .flatten()
+                .filter(|e| e.name == name)
+                .count()
+        };
+        assert_eq!(count("substation"), 4);
+        // One substation sits inside the image, in place of 4 lamps
+        assert_eq!(count("small-lamp"), 20 * 20 - 4);
+    }
+}
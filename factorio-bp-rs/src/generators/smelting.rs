@@ -0,0 +1,139 @@
+use crate::blueprint::{
+    BeltTier, BlueprintBuilder, Direction, EntityBuilder, FurnaceKind, InserterKind,
+};
+
+use super::solar::copper_wire;
+
+/// The tiles either side of a medium electric pole it powers.
+const POLE_SUPPLY: i64 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A column of furnaces running south, each fed from an ore belt on its west by one inserter
+/// and emptied onto a plate belt on its east by another. Medium electric poles between the
+/// inserters power them, and the furnaces if they are electric.
+pub struct SmeltingColumn {
+    /// The number of furnaces.
+    pub furnaces: usize,
+    /// Which furnace to use.
+    pub furnace: FurnaceKind,
+    /// The tier of both belts.
+    pub belt: BeltTier,
+    /// Which inserter to use, both in and out.
+    pub inserter: InserterKind,
+}
+
+impl Default for SmeltingColumn {
+    /// 24 stone furnaces, the most a yellow belt of iron ore keeps busy, with basic inserters.
+    fn default() -> Self {
+        Self {
+            furnaces: 24,
+            furnace: FurnaceKind::Stone,
+            belt: BeltTier::Transport,
+            inserter: InserterKind::Basic,
+        }
+    }
+}
+
+impl SmeltingColumn {
+    /// The tiles along each side of the furnaces.
+    fn furnace_size(&self) -> i64 {
+        match self.furnace {
+            FurnaceKind::Stone | FurnaceKind::Steel => 2,
+            FurnaceKind::Electric => 3,
+        }
+    }
+
+    /// The column, with its top-left corner on the tile `(0, 0)`. The ore belt comes in at the
+    /// top of the first column of tiles, and the plates leave at the bottom of the last.
+    pub fn generate(&self) -> BlueprintBuilder {
+        let mut builder = BlueprintBuilder::new().label("Smelting column");
+        let needs_power =
+            self.furnace == FurnaceKind::Electric || self.inserter != InserterKind::Burner;
+        let size = self.furnace_size();
+        let furnaces_per_pole = (POLE_SUPPLY * 2 / size) as usize;
+        // One line of poles reaches both columns of inserters around small furnaces
+        let pole_columns = if size < POLE_SUPPLY {
+            vec![1]
+        } else {
+            vec![1, size + 2]
+        };
+
+        let mut poles = vec![Vec::new(); pole_columns.len()];
+        for i in 0..self.furnaces {
+            let y = i as i64 * size;
+            for dy in 0..size {
+                for x in [0, size + 3] {
+                    builder.add_entity(
+                        EntityBuilder::new(self.belt.as_str())
+                            .at_tile(x, y + dy)
+                            .direction(Direction::South),
+                    );
+                }
+            }
+            // Inserters facing west take from the west and drop to the east
+            for x in [1, size + 2] {
+                builder.add_entity(
+                    EntityBuilder::new(self.inserter.as_str())
+                        .at_tile(x, y)
+                        .direction(Direction::West),
+                );
+            }
+            builder.add_entity(EntityBuilder::new(self.furnace.as_str()).at_tile(2, y));
+
+            if needs_power && i % furnaces_per_pole == 0 {
+                for (line, &x) in poles.iter_mut().zip(&pole_columns) {
+                    let pole = EntityBuilder::new("medium-electric-pole").at_tile(x, y + 1);
+                    line.push(builder.add_entity(pole));
+                }
+            }
+        }
+
+        for line in &poles {
+            for pair in line.windows(2) {
+                builder = builder.wire(copper_wire(pair[0], pair[1]));
+            }
+        }
+        if let [first, second] = poles.as_slice() {
+            if let (Some(&a), Some(&b)) = (first.first(), second.first()) {
+                builder = builder.wire(copper_wire(a, b));
+            }
+        }
+        builder
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{blueprint::Issue, power::PowerGraph, prototypes::PrototypeDb};
+
+    #[test]
+    fn columns_are_fed_and_powered() {
+        let db = PrototypeDb::vanilla();
+        for furnace in [FurnaceKind::Stone, FurnaceKind::Electric] {
+            let column = SmeltingColumn {
+                furnaces: 6,
+                furnace,
+                ..SmeltingColumn::default()
+            };
+            let blueprint = column.generate().build_with(db).unwrap();
+            let issues = blueprint.validate_with(db);
+            assert!(issues.is_empty(), "{furnace:?}: {issues:?}");
+            assert_eq!(PowerGraph::new(&blueprint, db).networks().len(), 1);
+        }
+
+        let burners = SmeltingColumn {
+            furnaces: 2,
+            inserter: InserterKind::Burner,
+            ..SmeltingColumn::default()
+        };
+        let blueprint = burners.generate().build_with(db).unwrap();
+        let names = blueprint.entities.iter().flatten().map(|e| e.name.as_str());
+        assert!(!names.clone().any(|name| name == "medium-electric-pole"));
+        assert_eq!(names.filter(|&name| name == "stone-furnace").count(), 2);
+        assert!(!blueprint
+            .validate_with(db)
+            .iter()
+            .any(|issue| matches!(issue, Issue::Unpowered { .. })));
+    }
+}
@@ -0,0 +1,170 @@
+use core::num::NonZeroUsize;
+
+use crate::{
+    analysis::solar::IDEAL_RATIO,
+    blueprint::{BlueprintBuilder, EntityBuilder, Wire, WireConnectorId},
+};
+
+/// The tiles along each side of a cell, the area a substation supplies.
+pub const CELL_SIZE: i64 = 18;
+
+/// The tiles along each side of a group, which fits 4 solar panels or 9 accumulators.
+const GROUP_SIZE: i64 = 6;
+
+/// The groups of a cell that hold panels or accumulators, by column and row, in the order they
+/// are given accumulators. The middle group holds the substation.
+const GROUPS: [(i64, i64); 8] = [
+    (1, 0),
+    (1, 2),
+    (0, 1),
+    (2, 1),
+    (0, 0),
+    (2, 0),
+    (0, 2),
+    (2, 2),
+];
+
+/// The accumulators fitting around the substation of a cell.
+const MIDDLE_ACCUMULATORS: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A field of square cells, each a substation in the middle of solar panels and accumulators.
+/// Cells are [CELL_SIZE] tiles across, so the substations are wired to their neighbours and
+/// supply the whole field, and fields tile with each other.
+pub struct SolarField {
+    /// The number of cells across.
+    pub width: usize,
+    /// The number of cells down.
+    pub height: usize,
+    /// The accumulators to aim for per solar panel. Each 6 by 6 group of a cell holds either 4
+    /// panels or 9 accumulators, so the field gets as close as the groups allow.
+    pub ratio: f64,
+}
+
+impl Default for SolarField {
+    /// A single cell with the [IDEAL_RATIO] of accumulators for Nauvis.
+    fn default() -> Self {
+        Self {
+            width: 1,
+            height: 1,
+            ratio: IDEAL_RATIO,
+        }
+    }
+}
+
+impl SolarField {
+    /// The number of groups of accumulators, out of those of every cell, giving the ratio
+    /// closest to [SolarField::ratio].
+    pub fn accumulator_groups(&self) -> usize {
+        let cells = self.width * self.height;
+        let groups = cells * GROUPS.len();
+        let error = |accumulator_groups: usize| {
+            let accumulators = cells * MIDDLE_ACCUMULATORS + accumulator_groups * 9;
+            let panels = (groups - accumulator_groups) * 4;
+            match panels {
+                0 => f64::INFINITY,
+                _ => (accumulators as f64 / panels as f64 - self.ratio).abs(),
+            }
+        };
+        (0..=groups)
+            .min_by(|&a, &b| error(a).total_cmp(&error(b)))
+            .unwrap_or_default()
+    }
+
+    /// The field, with its top-left corner on the tile `(0, 0)`.
+    pub fn generate(&self) -> BlueprintBuilder {
+        let mut builder = BlueprintBuilder::new().label("Solar field");
+        let cells = self.width * self.height;
+        let accumulator_groups = self.accumulator_groups();
+
+        let mut substations = Vec::new();
+        for cell in 0..cells {
+            let (left, top) = (
+                (cell % self.width) as i64 * CELL_SIZE,
+                (cell / self.width) as i64 * CELL_SIZE,
+            );
+            // Spread the accumulator groups evenly, the first cells getting any left over
+            let accumulators =
+                accumulator_groups / cells + usize::from(cell < accumulator_groups % cells);
+
+            let middle = left + CELL_SIZE / 2 - 1;
+            let substation = builder.add_entity(
+                EntityBuilder::new("substation").at_tile(middle, top + CELL_SIZE / 2 - 1),
+            );
+            substations.push(substation);
+            add_accumulators(&mut builder, left + GROUP_SIZE, top + GROUP_SIZE, true);
+
+            for (i, &(column, row)) in GROUPS.iter().enumerate() {
+                let (x, y) = (left + column * GROUP_SIZE, top + row * GROUP_SIZE);
+                if i < accumulators {
+                    add_accumulators(&mut builder, x, y, false);
+                } else {
+                    for (dx, dy) in [(0, 0), (3, 0), (0, 3), (3, 3)] {
+                        builder
+                            .add_entity(EntityBuilder::new("solar-panel").at_tile(x + dx, y + dy));
+                    }
+                }
+            }
+        }
+
+        for (cell, &substation) in substations.iter().enumerate() {
+            let right = (cell % self.width + 1 < self.width).then(|| substations[cell + 1]);
+            let below = substations.get(cell + self.width).copied();
+            for neighbor in right.into_iter().chain(below) {
+                builder = builder.wire(copper_wire(substation, neighbor));
+            }
+        }
+        builder
+    }
+}
+
+/// Fill the group with its top-left corner on `(x, y)` with accumulators, leaving the middle
+/// free for the substation if `around_middle` is set.
+fn add_accumulators(builder: &mut BlueprintBuilder, x: i64, y: i64, around_middle: bool) {
+    for dy in (0..GROUP_SIZE).step_by(2) {
+        for dx in (0..GROUP_SIZE).step_by(2) {
+            if around_middle && (dx, dy) == (2, 2) {
+                continue;
+            }
+            builder.add_entity(EntityBuilder::new("accumulator").at_tile(x + dx, y + dy));
+        }
+    }
+}
+
+/// A copper wire between two poles.
+pub(super) fn copper_wire(source: NonZeroUsize, target: NonZeroUsize) -> Wire {
+    Wire {
+        source_entity: source,
+        source_connector: WireConnectorId::PoleCopper,
+        target_entity: target,
+        target_connector: WireConnectorId::PoleCopper,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{analysis::solar::SolarReport, power::PowerGraph, prototypes::PrototypeDb};
+
+    #[test]
+    fn fields_come_close_to_the_ratio_on_one_network() {
+        let db = PrototypeDb::vanilla();
+        let cell = SolarField::default();
+        assert_eq!(cell.accumulator_groups(), 1);
+        let blueprint = cell.generate().build_with(db).unwrap();
+        let report = SolarReport::new(&blueprint, db, 0.0);
+        assert_eq!(report.ratio(), Some(17.0 / 28.0));
+
+        let field = SolarField {
+            width: 2,
+            height: 2,
+            ..SolarField::default()
+        };
+        assert_eq!(field.accumulator_groups(), 6);
+        let blueprint = field.generate().build_with(db).unwrap();
+        assert_eq!(blueprint.entities.as_ref().unwrap().len(), 4 + 86 + 104);
+        assert_eq!(PowerGraph::new(&blueprint, db).networks().len(), 1);
+        let report = SolarReport::new(&blueprint, db, 0.0);
+        assert_eq!(report.ratio(), Some(86.0 / 104.0));
+    }
+}
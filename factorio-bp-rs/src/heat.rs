@@ -0,0 +1,140 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    blueprint::Blueprint,
+    fluid::{joined, Placed},
+    prototypes::PrototypeDb,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Heat pipes, reactors, heat exchangers, and other entities with heat buffers, joined by their
+/// heat connections so heat flows between them.
+pub struct HeatNetwork {
+    /// The entity_numbers of the entities in the network, in order.
+    pub entities: Vec<usize>,
+}
+
+impl HeatNetwork {
+    /// Whether the entity is part of the network.
+    pub fn contains(&self, entity_number: usize) -> bool {
+        self.entities.binary_search(&entity_number).is_ok()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// The entities of a blueprint with heat buffers, as a graph joined by heat connections.
+///
+/// Two entities join when they have heat connections on adjacent tiles facing each other. Every
+/// entity `db` gives heat connections to is part of the graph, even if it connects to nothing.
+pub struct HeatGraph {
+    adjacent: BTreeMap<usize, Vec<usize>>,
+}
+
+impl HeatGraph {
+    /// The entities of `blueprint` with heat buffers, with their connections from `db`.
+    pub fn new(blueprint: &Blueprint, db: &PrototypeDb) -> Self {
+        let mut adjacent: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        let mut placed = Vec::new();
+        for entity in blueprint.entities.iter().flatten() {
+            let Some(prototype) = entity.prototype(db) else {
+                continue;
+            };
+            if prototype.heat_connections.is_empty() {
+                continue;
+            }
+            let number = entity.entity_number.get();
+            adjacent.insert(number, Vec::new());
+            for connection in &prototype.heat_connections {
+                placed.push(Placed::new(number, entity, connection));
+            }
+        }
+
+        for (a, b) in joined(&placed) {
+            adjacent.entry(a).or_default().push(b);
+            adjacent.entry(b).or_default().push(a);
+        }
+
+        Self { adjacent }
+    }
+
+    /// The entity_numbers of every entity with a heat buffer, in order.
+    pub fn entities(&self) -> impl Iterator<Item = usize> + '_ {
+        self.adjacent.keys().copied()
+    }
+
+    /// The entities joined directly to the entity.
+    pub fn neighbors(&self, entity_number: usize) -> &[usize] {
+        self.adjacent.get(&entity_number).map_or(&[], Vec::as_slice)
+    }
+
+    /// The network the entity is part of, or `None` if it has no heat buffer.
+    pub fn network(&self, entity_number: usize) -> Option<HeatNetwork> {
+        if !self.adjacent.contains_key(&entity_number) {
+            return None;
+        }
+
+        let mut seen = BTreeSet::from([entity_number]);
+        let mut queue = vec![entity_number];
+        while let Some(next) = queue.pop() {
+            for &neighbor in self.neighbors(next) {
+                if seen.insert(neighbor) {
+                    queue.push(neighbor);
+                }
+            }
+        }
+        Some(HeatNetwork {
+            entities: seen.into_iter().collect(),
+        })
+    }
+
+    /// Every network, ordered by their lowest entity.
+    pub fn networks(&self) -> Vec<HeatNetwork> {
+        let mut seen = BTreeSet::new();
+        let mut networks = Vec::new();
+        for entity_number in self.entities() {
+            if seen.contains(&entity_number) {
+                continue;
+            }
+            if let Some(network) = self.network(entity_number) {
+                seen.extend(network.entities.iter().copied());
+                networks.push(network);
+            }
+        }
+        networks
+    }
+
+    /// Whether two entities are part of the same network.
+    pub fn connected(&self, a: usize, b: usize) -> bool {
+        self.network(a).is_some_and(|network| network.contains(b))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bp;
+
+    #[test]
+    fn heat_flows_between_side_by_side_connections() {
+        let db = PrototypeDb::vanilla();
+        let blueprint = bp! {
+            "heat-pipe" @ (0, 0);
+            "heat-pipe" @ (1, 0);
+            "heat-pipe" @ (1, 1);
+            "heat-pipe" @ (2, 2);
+            "iron-chest" @ (3, 2);
+        }
+        .build_with(db)
+        .unwrap();
+        let graph = HeatGraph::new(&blueprint, db);
+
+        assert_eq!(graph.entities().collect::<Vec<_>>(), [1, 2, 3, 4]);
+        assert_eq!(graph.neighbors(2), [1, 3]);
+        assert!(graph.connected(1, 3));
+        // Corners touching aren't joined
+        assert!(!graph.connected(3, 4));
+        assert_eq!(graph.network(5), None);
+        let networks: Vec<_> = graph.networks().into_iter().map(|n| n.entities).collect();
+        assert_eq!(networks, [vec![1, 2, 3], vec![4]]);
+    }
+}
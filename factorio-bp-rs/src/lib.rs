@@ -4,39 +4,86 @@
 
 use blueprint::Position;
 
+/// Reports on what a blueprint does, worked out from its entities and the prototype data
+pub mod analysis;
+
+/// The flow of items along a blueprint's belts
+pub mod belt;
+
 /// Structures for decoding blueprint strings. See https://wiki.factorio.com/Blueprint_string_format for more
 pub mod blueprint;
 
-#[cfg(feature = "cli")]
-pub mod cli;
+/// The red and green circuit networks formed by a blueprint's wires
+pub mod circuit;
+
+/// Groups of entities joined by belts, pipes, heat pipes, rails, or wires, to look at each part of a blueprint on its own
+pub mod components;
+
+/// The effects of modules and beacons on the machines of a blueprint
+pub mod effects;
+
+/// The fluid networks formed by a blueprint's pipes and fluid boxes
+pub mod fluid;
+
+/// The heat networks formed by a blueprint's heat pipes, reactors, and heat exchangers
+pub mod heat;
+
+/// Blueprints of common layouts, generated to the size asked for
+pub mod generators;
+
+/// Working out the machines and raw inputs it takes to make an item at a rate
+pub mod plan;
+
+/// The electric networks formed by a blueprint's poles and copper wires
+pub mod power;
+
+/// Game data about entities, used to work out their size and behaviour
+pub mod prototypes;
+
+/// The rail network formed by a blueprint's rails, with its signals and train stops, split into blocks
+pub mod rail;
+
+/// Recipes, with the items and fluids they use and make, and how they depend on each other
+pub mod recipes;
 
+/// Drawing blueprints as images, for previews outside the game
+pub mod render;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+/// The dimensions of an area, measured in tiles
 pub struct Size {
+    /// Width of the area
     pub w: usize,
+    /// Height of the area
     pub h: usize,
 }
 
 #[derive(Debug, PartialEq, Clone)]
+/// An item that can be held in an inventory
 pub struct Item {
     name: String,
     stack_size: usize,
 }
 
 impl Item {
+    /// The prototype name of the item
     pub fn name(&self) -> &String {
         &self.name
     }
 
+    /// The maximum number of items in a single inventory slot
     pub fn stack_size(&self) -> usize {
         self.stack_size
     }
 }
 
+/// A fluid that can flow through pipes
 pub struct Fluid {
     name: String,
-    stack_size: usize,
 }
 
 impl Fluid {
+    /// The prototype name of the fluid
     pub fn name(&self) -> &String {
         &self.name
     }
@@ -49,9 +96,28 @@ pub struct Entity {
     size: Size,
 }
 
+impl Entity {
+    /// The prototype name of the entity
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    /// The position of the entity's center
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+
+    /// The area the entity takes up
+    pub fn size(&self) -> &Size {
+        &self.size
+    }
+}
+
 /// Used as a common trait between 'Item' and 'Fluid'
 pub trait RecipeIO {}
 
+/// A way of crafting items or fluids from other items or fluids
 pub trait Recipe {
+    /// Whether productivity modules can be used on this recipe
     fn can_use_productivity(&self) -> bool;
 }
@@ -0,0 +1,381 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    analysis::throughput::ThroughputReport,
+    belt::Items,
+    blueprint::Blueprint,
+    prototypes::{PrototypeDb, RecipePrototype},
+    recipes::Graph,
+};
+
+/// The crafting machines plans use unless told otherwise, best first.
+pub const DEFAULT_MACHINES: &[&str] = &[
+    "assembling-machine-3",
+    "electric-furnace",
+    "chemical-plant",
+    "oil-refinery",
+    "centrifuge",
+];
+/// Rates closer than this, in items per second, count as equal.
+const TOLERANCE: f64 = 1e-9;
+
+#[derive(Debug, Clone, PartialEq)]
+/// How fast one machine works through a recipe.
+pub struct MachineRate {
+    /// The prototype name of the machine.
+    pub machine: String,
+    /// The crafts it finishes each second.
+    pub crafts_per_second: f64,
+    /// What its products are multiplied by, counting the extra ones productivity makes.
+    pub productivity: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Choices to make when planning.
+pub struct PlanOptions {
+    /// The crafting machines to use, best first. Each recipe is crafted in the first that can.
+    pub machines: Vec<String>,
+    /// Rates for recipes, in place of those of the machines above without modules.
+    pub rates: BTreeMap<String, MachineRate>,
+    /// The recipe to make each item with, where more than one makes it. Others are made with
+    /// the recipe named after them, or else the first by name, leaving recipes in loops like
+    /// Kovarex enrichment until last.
+    pub recipes: BTreeMap<String, String>,
+    /// The belt raw inputs and the target item are carried on.
+    pub belt: String,
+}
+
+impl Default for PlanOptions {
+    fn default() -> Self {
+        Self {
+            machines: DEFAULT_MACHINES
+                .iter()
+                .map(|&name| name.to_owned())
+                .collect(),
+            rates: BTreeMap::new(),
+            recipes: BTreeMap::new(),
+            belt: "transport-belt".to_owned(),
+        }
+    }
+}
+
+impl PlanOptions {
+    /// Options limited to the crafting machines of `blueprint`, fastest first. Recipes its
+    /// machines are set to use their average rate, with their modules and beacons.
+    pub fn from_blueprint(blueprint: &Blueprint, db: &PrototypeDb) -> Self {
+        let names: BTreeMap<usize, &str> = blueprint
+            .entities
+            .iter()
+            .flatten()
+            .map(|entity| (entity.entity_number.get(), entity.name.as_str()))
+            .collect();
+        let speed = |name: &str| {
+            db.entity(name)
+                .and_then(|prototype| prototype.crafting_machine.as_ref())
+                .map_or(0.0, |machine| machine.crafting_speed)
+        };
+        let mut machines: Vec<String> = blueprint
+            .entities
+            .iter()
+            .flatten()
+            .filter(|entity| speed(&entity.name) > 0.0)
+            .map(|entity| entity.name.clone())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        machines.sort_by(|a, b| speed(b).total_cmp(&speed(a)));
+
+        let mut rates: BTreeMap<String, (MachineRate, usize)> = BTreeMap::new();
+        for machine in ThroughputReport::new(blueprint, db).machines {
+            let (rate, count) = rates.entry(machine.recipe).or_insert_with(|| {
+                let rate = MachineRate {
+                    machine: names[&machine.entity_number].to_owned(),
+                    crafts_per_second: 0.0,
+                    productivity: 0.0,
+                };
+                (rate, 0)
+            });
+            rate.crafts_per_second += machine.crafts_per_second;
+            rate.productivity += machine.effects.productivity();
+            *count += 1;
+        }
+        let rates = rates
+            .into_iter()
+            .map(|(recipe, (mut rate, count))| {
+                rate.crafts_per_second /= count as f64;
+                rate.productivity /= count as f64;
+                (recipe, rate)
+            })
+            .collect();
+
+        Self {
+            machines,
+            rates,
+            ..Self::default()
+        }
+    }
+
+    /// The rate of the machine crafting `recipe`, or `None` if no machine can.
+    fn rate(&self, recipe: &RecipePrototype, db: &PrototypeDb) -> Option<MachineRate> {
+        if let Some(rate) = self.rates.get(&recipe.name) {
+            return Some(rate.clone());
+        }
+        self.machines.iter().find_map(|name| {
+            let machine = db.entity(name)?.crafting_machine.as_ref()?;
+            machine
+                .crafting_categories
+                .contains(&recipe.category)
+                .then(|| MachineRate {
+                    machine: name.clone(),
+                    crafts_per_second: machine.crafting_speed / recipe.energy_required,
+                    productivity: 1.0 + machine.base_productivity,
+                })
+        })
+    }
+
+    /// The recipe to make `item` with, and the rate of the machine crafting it, or `None` if
+    /// nothing the machines can craft makes it.
+    fn recipe<'db>(
+        &self,
+        item: &str,
+        db: &'db PrototypeDb,
+        loops: &BTreeSet<&str>,
+    ) -> Option<(&'db RecipePrototype, MachineRate)> {
+        let mut recipes: Vec<&RecipePrototype> = match self.recipes.get(item) {
+            Some(name) => db.recipe(name).into_iter().collect(),
+            None => db
+                .recipes()
+                .filter(|recipe| recipe.products.iter().any(|(product, _)| product == item))
+                .collect(),
+        };
+        recipes.sort_by_key(|recipe| {
+            (
+                recipe.name != item,
+                loops.contains(recipe.name.as_str()),
+                &recipe.name,
+            )
+        });
+        recipes
+            .into_iter()
+            .find_map(|recipe| Some((recipe, self.rate(recipe, db)?)))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// One recipe of a plan, and the machines crafting it.
+pub struct Step {
+    /// The name of the recipe.
+    pub recipe: String,
+    /// How fast each machine crafts it.
+    pub rate: MachineRate,
+    /// The crafts needed each second.
+    pub crafts_per_second: f64,
+}
+
+impl Step {
+    /// The machines needed, as a fraction where the last needn't work all the time.
+    pub fn machines(&self) -> f64 {
+        self.crafts_per_second / self.rate.crafts_per_second
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// The machines and inputs it takes to make an item at a rate.
+pub struct Plan {
+    /// The item or fluid to make.
+    pub item: String,
+    /// How much of it to make each second.
+    pub rate: f64,
+    /// Every recipe crafted, from the target back to the raw inputs.
+    pub steps: Vec<Step>,
+    /// The items and fluids to bring in each second, those nothing the machines can craft
+    /// makes.
+    pub raw: Items,
+    /// The items and fluids made each second that aren't needed, from recipes with more than
+    /// one product.
+    pub byproducts: Items,
+    /// The belts needed to carry each raw item in and the target out, leaving out fluids.
+    pub belts: BTreeMap<String, f64>,
+}
+
+impl Plan {
+    /// Work out how to make `rate` of `item` each second, with the recipes and machines of `db`
+    /// chosen by `options`.
+    ///
+    /// Byproducts aren't used in place of making the items again. A recipe using some of what
+    /// it makes, like Kovarex enrichment, is counted by what it makes more of than it uses.
+    pub fn new(item: &str, rate: f64, db: &PrototypeDb, options: &PlanOptions) -> Self {
+        let loops = Graph::new(db).loops().into_iter().flatten().collect();
+        // Every item needed, those using each before it
+        let mut order = Vec::new();
+        let mut recipes = BTreeMap::new();
+        let mut seen = BTreeSet::new();
+        visit(
+            item,
+            db,
+            options,
+            &loops,
+            &mut seen,
+            &mut recipes,
+            &mut order,
+        );
+        order.reverse();
+
+        let mut plan = Self {
+            item: item.to_owned(),
+            rate,
+            ..Self::default()
+        };
+        let mut demand = Items::from([(item.to_owned(), rate)]);
+        // Each recipe crafted, with its step, and where it is in the list
+        let mut steps: Vec<(&RecipePrototype, Step)> = Vec::new();
+        let mut index: BTreeMap<&str, usize> = BTreeMap::new();
+        for item in order {
+            let needed = demand.get(&item).copied().unwrap_or_default();
+            let Some((recipe, rate)) = recipes.get(&item) else {
+                if needed > TOLERANCE {
+                    *plan.raw.entry(item).or_default() += needed;
+                }
+                continue;
+            };
+            let amount = |amounts: &[(String, f64)]| {
+                amounts
+                    .iter()
+                    .filter(|(name, _)| *name == item)
+                    .fold(0.0, |sum, (_, amount)| sum + amount)
+            };
+            let made = amount(&recipe.products) * rate.productivity - amount(&recipe.ingredients);
+            if made <= TOLERANCE {
+                // It uses up at least as much as it makes
+                *plan.raw.entry(item).or_default() += needed;
+                continue;
+            }
+            let crafts = needed / made;
+            let i = *index.entry(&recipe.name).or_insert_with(|| {
+                let step = Step {
+                    recipe: recipe.name.clone(),
+                    rate: rate.clone(),
+                    crafts_per_second: 0.0,
+                };
+                steps.push((recipe, step));
+                steps.len() - 1
+            });
+            let step = &mut steps[i].1;
+            // A recipe making several needed items crafts enough for the most needed
+            let extra = crafts - step.crafts_per_second;
+            if extra > 0.0 {
+                step.crafts_per_second = crafts;
+                for (ingredient, amount) in &recipe.ingredients {
+                    *demand.entry(ingredient.clone()).or_default() += amount * extra;
+                }
+            }
+        }
+
+        for (recipe, step) in steps {
+            for (product, amount) in &recipe.products {
+                let made = amount * step.rate.productivity * step.crafts_per_second;
+                let spare = made - demand.get(product).copied().unwrap_or_default();
+                if spare > TOLERANCE {
+                    *plan.byproducts.entry(product.clone()).or_default() += spare;
+                }
+            }
+            plan.steps.push(step);
+        }
+
+        let speed = db
+            .entity(&options.belt)
+            .and_then(|prototype| prototype.transport_belt)
+            .map(|belt| belt.speed);
+        if let Some(speed) = speed {
+            let carried = plan.raw.iter().chain([(&plan.item, &plan.rate)]);
+            for (name, rate) in carried.filter(|(name, _)| !db.is_fluid(name)) {
+                *plan.belts.entry(name.clone()).or_default() += rate / speed;
+            }
+        }
+        plan
+    }
+}
+
+/// Add `item` and everything needed to make it to `order`, each after what it is made from, and
+/// the recipes chosen to make them to `recipes`. Recipes in `loops` are chosen only when nothing
+/// else makes an item.
+fn visit<'db>(
+    item: &str,
+    db: &'db PrototypeDb,
+    options: &PlanOptions,
+    loops: &BTreeSet<&str>,
+    seen: &mut BTreeSet<String>,
+    recipes: &mut BTreeMap<String, (&'db RecipePrototype, MachineRate)>,
+    order: &mut Vec<String>,
+) {
+    if !seen.insert(item.to_owned()) {
+        return;
+    }
+    if let Some((recipe, rate)) = options.recipe(item, db, loops) {
+        for (ingredient, _) in &recipe.ingredients {
+            visit(ingredient, db, options, loops, seen, recipes, order);
+        }
+        recipes.insert(item.to_owned(), (recipe, rate));
+    }
+    order.push(item.to_owned());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bp;
+
+    #[test]
+    fn plans_work_back_to_raw_inputs() {
+        let db = PrototypeDb::vanilla();
+        let options = PlanOptions {
+            machines: vec!["assembling-machine-2".to_owned()],
+            ..PlanOptions::default()
+        };
+        let plan = Plan::new("electronic-circuit", 1.0, db, &options);
+
+        let steps: Vec<_> = plan
+            .steps
+            .iter()
+            .map(|step| {
+                (
+                    step.recipe.as_str(),
+                    step.crafts_per_second,
+                    step.machines(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            steps,
+            [
+                ("electronic-circuit", 1.0, 1.0 / 1.5),
+                ("copper-cable", 1.5, 1.0)
+            ]
+        );
+        assert_eq!(
+            plan.raw,
+            Items::from([("copper-plate".into(), 1.5), ("iron-plate".into(), 1.0)])
+        );
+        assert!(plan.byproducts.is_empty());
+        assert_eq!(plan.belts["copper-plate"], 0.1);
+    }
+
+    #[test]
+    fn blueprints_give_the_rates_of_their_machines() {
+        let db = PrototypeDb::vanilla();
+        let blueprint = bp! {
+            "assembling-machine-1" @ (0, 0) recipe "copper-cable";
+            "assembling-machine-2" @ (3, 0) recipe "copper-cable";
+        }
+        .build_with(db)
+        .unwrap();
+        let options = PlanOptions::from_blueprint(&blueprint, db);
+
+        assert_eq!(
+            options.machines,
+            ["assembling-machine-2", "assembling-machine-1"]
+        );
+        let rate = &options.rates["copper-cable"];
+        assert_eq!((rate.crafts_per_second, rate.productivity), (1.25, 1.0));
+    }
+}
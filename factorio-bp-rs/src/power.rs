@@ -0,0 +1,202 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    blueprint::{Blueprint, Entity, WireConnectorId},
+    prototypes::PrototypeDb,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+/// Electric poles joined by copper wire, and the entities they supply.
+pub struct PowerNetwork {
+    /// The entity_numbers of the poles, in order.
+    pub poles: Vec<usize>,
+    /// The entity_numbers of the electric entities inside the supply area of any of the poles,
+    /// in order. Poles aren't included.
+    pub entities: Vec<usize>,
+}
+
+impl PowerNetwork {
+    /// Whether the pole or entity is part of the network.
+    pub fn contains(&self, entity_number: usize) -> bool {
+        self.poles.binary_search(&entity_number).is_ok()
+            || self.entities.binary_search(&entity_number).is_ok()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// The electric networks of a blueprint.
+///
+/// Poles are joined by the copper wires in the blueprint, from 2.0 `wires` or older `neighbours`.
+/// Poles the blueprint doesn't wire up are separate networks, even if they are in reach of each
+/// other, as the game only connects them automatically when they are placed one by one. Power
+/// switches keep the networks on either side apart.
+pub struct PowerGraph {
+    adjacent: BTreeMap<usize, Vec<usize>>,
+    networks: Vec<PowerNetwork>,
+}
+
+impl PowerGraph {
+    /// The electric networks of `blueprint`, with pole supply areas and which entities use
+    /// electricity from `db`.
+    pub fn new(blueprint: &Blueprint, db: &PrototypeDb) -> Self {
+        let entities: Vec<&Entity> = blueprint.entities.iter().flatten().collect();
+        let is_pole = |entity: &Entity| {
+            entity
+                .prototype(db)
+                .is_some_and(|prototype| prototype.electric_pole.is_some())
+        };
+        let poles: BTreeSet<usize> = entities
+            .iter()
+            .filter(|entity| is_pole(entity))
+            .map(|entity| entity.entity_number.get())
+            .collect();
+
+        let mut wires = BTreeSet::new();
+        for wire in blueprint.wires.iter().flatten() {
+            let copper = wire.source_connector == WireConnectorId::PoleCopper
+                && wire.target_connector == WireConnectorId::PoleCopper;
+            if copper {
+                let (a, b) = (wire.source_entity.get(), wire.target_entity.get());
+                wires.insert((a.min(b), a.max(b)));
+            }
+        }
+        for entity in &entities {
+            let a = entity.entity_number.get();
+            for b in entity.neighbors.iter().flatten().map(|b| b.get()) {
+                wires.insert((a.min(b), a.max(b)));
+            }
+        }
+
+        let mut adjacent: BTreeMap<usize, Vec<usize>> =
+            poles.iter().map(|&pole| (pole, Vec::new())).collect();
+        for (a, b) in wires {
+            // Wires to anything but poles, such as power switches, don't join networks
+            if poles.contains(&a) && poles.contains(&b) {
+                adjacent.entry(a).or_default().push(b);
+                adjacent.entry(b).or_default().push(a);
+            }
+        }
+
+        let mut networks = Vec::new();
+        let mut seen = BTreeSet::new();
+        for &pole in &poles {
+            if !seen.insert(pole) {
+                continue;
+            }
+            let mut network = BTreeSet::from([pole]);
+            let mut queue = vec![pole];
+            while let Some(next) = queue.pop() {
+                for &neighbor in &adjacent[&next] {
+                    if seen.insert(neighbor) {
+                        network.insert(neighbor);
+                        queue.push(neighbor);
+                    }
+                }
+            }
+            networks.push(PowerNetwork {
+                poles: network.into_iter().collect(),
+                entities: Vec::new(),
+            });
+        }
+
+        let supply_areas: BTreeMap<usize, _> = entities
+            .iter()
+            .filter_map(|entity| Some((entity.entity_number.get(), entity.supply_area(db)?)))
+            .collect();
+        for entity in &entities {
+            let electric = entity
+                .prototype(db)
+                .is_some_and(|prototype| prototype.electric);
+            if !electric || is_pole(entity) {
+                continue;
+            }
+            let Some(collision_box) = entity.collision_box(db) else {
+                continue;
+            };
+            for network in &mut networks {
+                let supplied = network.poles.iter().any(|pole| {
+                    supply_areas
+                        .get(pole)
+                        .is_some_and(|area| area.intersects(&collision_box))
+                });
+                if supplied {
+                    network.entities.push(entity.entity_number.get());
+                }
+            }
+        }
+        for network in &mut networks {
+            network.entities.sort_unstable();
+        }
+
+        Self { adjacent, networks }
+    }
+
+    /// Every network, ordered by their lowest pole.
+    pub fn networks(&self) -> &[PowerNetwork] {
+        &self.networks
+    }
+
+    /// The networks a pole or entity is part of. Entities in reach of poles of several networks
+    /// are part of each of them.
+    pub fn networks_of(&self, entity_number: usize) -> Vec<&PowerNetwork> {
+        self.networks
+            .iter()
+            .filter(|network| network.contains(entity_number))
+            .collect()
+    }
+
+    /// Whether the entity is supplied by any pole. Always true for the poles themselves.
+    pub fn is_powered(&self, entity_number: usize) -> bool {
+        self.networks
+            .iter()
+            .any(|network| network.contains(entity_number))
+    }
+
+    /// The poles joined to `pole` by copper wire.
+    pub fn neighbors(&self, pole: usize) -> &[usize] {
+        self.adjacent.get(&pole).map_or(&[], Vec::as_slice)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::num::NonZeroUsize;
+
+    use super::*;
+    use crate::{blueprint::Wire, bp};
+
+    #[test]
+    fn only_wired_poles_share_a_network() {
+        let copper = |source: usize, target: usize| Wire {
+            source_entity: NonZeroUsize::new(source).unwrap(),
+            source_connector: WireConnectorId::PoleCopper,
+            target_entity: NonZeroUsize::new(target).unwrap(),
+            target_connector: WireConnectorId::PoleCopper,
+        };
+        let db = PrototypeDb::vanilla();
+        let blueprint = bp! {
+            "small-electric-pole" @ (0, 0);
+            "small-electric-pole" @ (5, 0);
+            "small-electric-pole" @ (8, 0);
+            "assembling-machine-1" @ (1, 1);
+            "assembling-machine-1" @ (7, 1);
+            "assembling-machine-1" @ (20, 20);
+        }
+        .wire(copper(1, 2))
+        .build_with(db)
+        .unwrap();
+        let graph = PowerGraph::new(&blueprint, db);
+
+        let networks = graph.networks();
+        assert_eq!(networks.len(), 2);
+        assert_eq!(networks[0].poles, [1, 2]);
+        assert_eq!(networks[0].entities, [4, 5]);
+        assert_eq!(networks[1].poles, [3]);
+        assert_eq!(networks[1].entities, [5]);
+        assert_eq!(graph.networks_of(5).len(), 2);
+        assert_eq!(graph.neighbors(1), [2]);
+        assert!(graph.neighbors(3).is_empty());
+        assert!(graph.is_powered(3));
+        assert!(!graph.is_powered(6));
+    }
+}
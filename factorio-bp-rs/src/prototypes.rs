@@ -0,0 +1,707 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::OnceLock,
+};
+
+use crate::blueprint::{BoundingBox, Direction, Entity, EntityDetails, Position};
+
+mod data_raw;
+#[cfg(feature = "vanilla-data")]
+mod vanilla;
+
+pub use data_raw::DataRawError;
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// The properties of an entity prototype that matter for analysing blueprints.
+pub struct EntityPrototype {
+    /// The prototype name, e.g. "assembling-machine-2".
+    pub name: String,
+    /// The collision box when facing north, relative to the entity's position.
+    pub collision_box: BoundingBox,
+    /// The number of tiles the entity covers horizontally when facing north.
+    pub tile_width: u32,
+    /// The number of tiles the entity covers vertically when facing north.
+    pub tile_height: u32,
+    /// Whether the entity can face other directions than north. Blueprints leave out the
+    /// direction of entities that can't, like chests.
+    pub rotatable: bool,
+    /// Whether the entity connects to an electric network, to use or produce power.
+    pub electric: bool,
+    /// The reach and supply area of the entity, if it is an electric pole.
+    pub electric_pole: Option<ElectricPole>,
+    /// The electricity used while working, in watts, before module effects and not counting
+    /// the drain.
+    pub energy_usage: f64,
+    /// The electricity used all the time, even when idle, in watts.
+    pub drain: f64,
+    /// How the entity produces or stores energy, if it does.
+    pub power_source: Option<PowerSource>,
+    /// The separate fluid boxes of the entity, like the water and steam of a boiler.
+    pub fluid_boxes: Vec<FluidBox>,
+    /// Where the entity's heat buffer connects to those of its neighbours, when facing north.
+    pub heat_connections: Vec<PipeConnection>,
+    /// How the entity moves items, if it is a belt, underground belt, splitter, or loader.
+    pub transport_belt: Option<TransportBelt>,
+    /// Where the entity moves items from and to, if it is an inserter.
+    pub inserter: Option<Inserter>,
+    /// How fast the entity crafts, if it is an assembling machine, furnace, or other crafting
+    /// machine.
+    pub crafting_machine: Option<CraftingMachine>,
+    /// What the entity passes on of its modules' effects, if it is a beacon.
+    pub beacon: Option<Beacon>,
+    /// How fast the entity researches, if it is a lab.
+    pub lab: Option<Lab>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// The properties of a crafting machine.
+pub struct CraftingMachine {
+    /// How fast it crafts, as a multiple of the recipe's time, before module effects.
+    pub crafting_speed: f64,
+    /// The productivity it has without modules, e.g. 0.5 for a foundry's +50%.
+    pub base_productivity: f64,
+    /// The categories of recipes it can craft, e.g. "smelting".
+    pub crafting_categories: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+/// The properties of a beacon.
+pub struct Beacon {
+    /// The fraction of its modules' effects the beacon passes on to each machine.
+    pub distribution_effectivity: f64,
+    /// How far the effect area reaches beyond the beacon's edges in each direction.
+    pub supply_area_distance: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// The properties of a lab.
+pub struct Lab {
+    /// How fast it researches, as a multiple of the technology's time per unit, before module
+    /// effects.
+    pub researching_speed: f64,
+    /// The science packs it takes.
+    pub inputs: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// The properties of an item prototype.
+pub struct ItemPrototype {
+    /// The prototype name, e.g. "iron-plate".
+    pub name: String,
+    /// The most of the item one inventory slot holds.
+    pub stack_size: u32,
+    /// The entity or tile the item builds, if it builds one.
+    pub place_result: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// The properties of a recipe prototype.
+pub struct RecipePrototype {
+    /// The prototype name, e.g. "electronic-circuit".
+    pub name: String,
+    /// Which crafting machines can craft it, those with this among their crafting categories.
+    pub category: String,
+    /// The seconds one craft takes at crafting speed 1.
+    pub energy_required: f64,
+    /// The items and fluids used by one craft, with their amounts.
+    pub ingredients: Vec<(String, f64)>,
+    /// The items and fluids made by one craft, with their expected amounts.
+    pub products: Vec<(String, f64)>,
+    /// The products made by only some crafts, with the chance of each craft making them.
+    pub probabilities: Vec<(String, f64)>,
+    /// Whether productivity modules and bonuses apply to it.
+    pub allow_productivity: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Where an inserter takes items from and puts them, when facing north.
+pub struct Inserter {
+    /// The offset of the pickup position from the inserter's position.
+    pub pickup: (f64, f64),
+    /// The offset of the drop position from the inserter's position.
+    pub drop: (f64, f64),
+    /// The items it moves each second from chest to chest, without stack size bonuses.
+    pub items_per_second: f64,
+}
+
+/// The offset `(x, y)` for an entity facing `direction`, rounded to the nearest quarter turn.
+fn rotated(x: f64, y: f64, direction: Direction) -> (f64, f64) {
+    let quarter_turns = (direction.sixteenths() + 2) / 4 % 4;
+    let (mut x, mut y) = (x, y);
+    for _ in 0..quarter_turns {
+        (x, y) = (-y, x);
+    }
+    (x, y)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// The properties of an entity that moves items along belts.
+pub struct TransportBelt {
+    /// What sort of belt entity it is.
+    pub kind: BeltKind,
+    /// The most items it moves per second, over both lanes.
+    pub speed: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The sorts of entities that move items along belts.
+pub enum BeltKind {
+    /// An ordinary belt.
+    Belt,
+    /// An underground belt entrance or exit.
+    Underground {
+        /// The furthest apart an entrance and exit can be, in tiles between their positions.
+        max_distance: u32,
+    },
+    /// A splitter.
+    Splitter,
+    /// A loader, which moves items between a belt and an inventory.
+    Loader,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+/// What a fluid box of a crafting machine holds.
+pub enum ProductionType {
+    #[default]
+    /// Fluid passing through or stored, as in pipes, tanks, and boilers.
+    None,
+    /// A fluid ingredient of the machine's recipe.
+    Input,
+    /// A fluid product of the machine's recipe.
+    Output,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// A store of one fluid inside an entity, and where it connects to its neighbours.
+pub struct FluidBox {
+    /// Where the fluid box connects, when the entity faces north.
+    pub connections: Vec<PipeConnection>,
+    /// Whether the fluid box holds an ingredient or product of the recipe. The first input holds
+    /// the first fluid ingredient, and so on.
+    pub production_type: ProductionType,
+    /// The only fluid the fluid box can hold, like the water of a boiler, if it is fixed.
+    pub filter: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A point where a fluid box or heat buffer connects to that of another entity.
+pub struct PipeConnection {
+    /// The horizontal offset of the centre of the tile the connection is on, from the entity's
+    /// position.
+    pub x: f64,
+    /// The vertical offset of the centre of the tile the connection is on, from the entity's
+    /// position.
+    pub y: f64,
+    /// The side of the tile the connection is on.
+    pub direction: Direction,
+    /// For underground pipes, the furthest the connection reaches to a matching one facing back.
+    /// `None` for connections to the adjacent tile.
+    pub underground_distance: Option<u32>,
+}
+
+impl PipeConnection {
+    /// The connection for an entity facing `direction`, rounded to the nearest quarter turn.
+    pub fn rotated(&self, direction: Direction) -> Self {
+        let quarter_turns = (direction.sixteenths() + 2) / 4 % 4;
+        let (x, y) = rotated(self.x, self.y, direction);
+        Self {
+            x,
+            y,
+            direction: self.direction.rotated(quarter_turns as i32 * 4),
+            underground_distance: self.underground_distance,
+        }
+    }
+
+    /// The connection for an entity facing `direction`, mirrored left to right first if
+    /// `mirror` is set, as the game places those of mirrored crafting machines.
+    pub fn oriented(&self, direction: Direction, mirror: bool) -> Self {
+        if !mirror {
+            return self.rotated(direction);
+        }
+        Self {
+            x: -self.x,
+            direction: Direction::from_sixteenths(-(self.direction.sixteenths() as i32)),
+            ..*self
+        }
+        .rotated(direction)
+    }
+
+    /// The connection as `entity` places it, turned to face its direction and mirrored if it is.
+    pub fn of_entity(&self, entity: &Entity) -> Self {
+        self.oriented(
+            entity.direction.unwrap_or_default(),
+            entity.mirror.unwrap_or(false),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// The ways an entity can produce or store energy.
+pub enum PowerSource {
+    /// Makes electricity from steam, fuel, or plasma, up to this many watts.
+    Generator(f64),
+    /// Makes electricity from sunlight, this many watts in full daylight.
+    Solar(f64),
+    /// Stores electricity to give back later.
+    Accumulator {
+        /// How much energy it holds when full, in joules.
+        capacity: f64,
+        /// How fast it can give the energy back, in watts.
+        output: f64,
+    },
+    /// Makes heat, this many watts when fuelled, for heat exchangers to turn into steam.
+    Reactor(f64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+/// The properties of an electric pole.
+pub struct ElectricPole {
+    /// The longest copper wire the pole can connect, in tiles between the pole positions.
+    pub wire_reach: f64,
+    /// How far the supply area reaches from the pole's position in each direction.
+    pub supply_area_distance: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+/// Bonuses to a machine, as fractions of its base values, e.g. 0.5 for +50%.
+pub struct Effect {
+    /// Change in electricity used while working.
+    pub consumption: f64,
+    /// Change in crafting or mining speed.
+    pub speed: f64,
+    /// Extra products, as a fraction of what is crafted.
+    pub productivity: f64,
+    /// Change in pollution.
+    pub pollution: f64,
+    /// Chance of making products of better quality.
+    pub quality: f64,
+}
+
+impl core::ops::Add for Effect {
+    type Output = Effect;
+
+    fn add(self, other: Effect) -> Effect {
+        Effect {
+            consumption: self.consumption + other.consumption,
+            speed: self.speed + other.speed,
+            productivity: self.productivity + other.productivity,
+            pollution: self.pollution + other.pollution,
+            quality: self.quality + other.quality,
+        }
+    }
+}
+
+impl core::ops::AddAssign for Effect {
+    fn add_assign(&mut self, other: Effect) {
+        *self = *self + other;
+    }
+}
+
+impl core::ops::Mul<f64> for Effect {
+    type Output = Effect;
+
+    fn mul(self, factor: f64) -> Effect {
+        Effect {
+            consumption: self.consumption * factor,
+            speed: self.speed * factor,
+            productivity: self.productivity * factor,
+            pollution: self.pollution * factor,
+            quality: self.quality * factor,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// The properties of a module prototype.
+pub struct ModulePrototype {
+    /// The prototype name, e.g. "speed-module-3".
+    pub name: String,
+    /// What the module does to the machine it's in.
+    pub effect: Effect,
+}
+
+impl EntityPrototype {
+    /// A prototype with the given collision box, covering the tiles that box touches.
+    pub fn new(name: impl Into<String>, collision_box: BoundingBox) -> Self {
+        let tiles = |min: f64, max: f64| (max - min).ceil().max(1.0) as u32;
+        Self {
+            name: name.into(),
+            tile_width: tiles(collision_box.min_x, collision_box.max_x),
+            tile_height: tiles(collision_box.min_y, collision_box.max_y),
+            collision_box,
+            rotatable: false,
+            electric: false,
+            electric_pole: None,
+            energy_usage: 0.0,
+            drain: 0.0,
+            power_source: None,
+            fluid_boxes: Vec::new(),
+            heat_connections: Vec::new(),
+            transport_belt: None,
+            inserter: None,
+            crafting_machine: None,
+            beacon: None,
+            lab: None,
+        }
+    }
+
+    /// The collision box when facing `direction`, relative to the entity's position.
+    ///
+    /// Directions between the cardinal ones give the box around the rotated collision box.
+    pub fn rotated_collision_box(&self, direction: Direction) -> BoundingBox {
+        let angle = direction.sixteenths() as f64 * std::f64::consts::PI / 8.0;
+        let (sin, cos) = angle.sin_cos();
+        // Snap so quarter turns give exact results
+        let snap = |v: f64| (v * 1e9).round() / 1e9;
+
+        let corners = [
+            (self.collision_box.min_x, self.collision_box.min_y),
+            (self.collision_box.max_x, self.collision_box.min_y),
+            (self.collision_box.max_x, self.collision_box.max_y),
+            (self.collision_box.min_x, self.collision_box.max_y),
+        ]
+        .map(|(x, y)| (snap(x * cos - y * sin), snap(x * sin + y * cos)));
+
+        corners.iter().fold(
+            BoundingBox::new(f64::MAX, f64::MAX, f64::MIN, f64::MIN),
+            |bounds, &(x, y)| {
+                BoundingBox::new(
+                    bounds.min_x.min(x),
+                    bounds.min_y.min(y),
+                    bounds.max_x.max(x),
+                    bounds.max_y.max(y),
+                )
+            },
+        )
+    }
+
+    /// The number of tiles covered horizontally and vertically when facing `direction`.
+    pub fn tile_size(&self, direction: Direction) -> (u32, u32) {
+        // East and west, and the directions closer to them than to north or south
+        let sideways = matches!(direction.sixteenths() % 8, 3..=5);
+        if sideways {
+            (self.tile_height, self.tile_width)
+        } else {
+            (self.tile_width, self.tile_height)
+        }
+    }
+}
+
+/// The prototypes [PrototypeDb::global] gives.
+static GLOBAL: OnceLock<PrototypeDb> = OnceLock::new();
+
+#[derive(Debug, Clone, Default)]
+/// A collection of prototypes, looked up by name.
+pub struct PrototypeDb {
+    entities: HashMap<String, EntityPrototype>,
+    modules: HashMap<String, ModulePrototype>,
+    items: HashMap<String, ItemPrototype>,
+    recipes: HashMap<String, RecipePrototype>,
+    fluids: HashSet<String>,
+    tiles: HashSet<String>,
+    items_to_place: HashMap<String, (String, u32)>,
+    mods: HashMap<String, String>,
+}
+
+impl PrototypeDb {
+    /// An empty database.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The prototypes of the base game and Space Age, built into the library with the
+    /// `vanilla-data` feature.
+    #[cfg(feature = "vanilla-data")]
+    pub fn vanilla() -> &'static PrototypeDb {
+        static VANILLA: OnceLock<PrototypeDb> = OnceLock::new();
+        VANILLA.get_or_init(vanilla::build)
+    }
+
+    /// The prototypes used where none are given, like by [Blueprint::validate].
+    ///
+    /// These are the ones passed to [set_global](Self::set_global), or if none were, the
+    /// [vanilla](Self::vanilla) ones, or an empty database without the `vanilla-data` feature.
+    ///
+    /// [Blueprint::validate]: crate::blueprint::Blueprint::validate
+    pub fn global() -> &'static PrototypeDb {
+        GLOBAL.get_or_init(|| {
+            #[cfg(feature = "vanilla-data")]
+            return Self::vanilla().clone();
+            #[cfg(not(feature = "vanilla-data"))]
+            return Self::new();
+        })
+    }
+
+    /// Use `db` where no prototypes are given, for example one loaded with
+    /// [from_data_raw](Self::from_data_raw) for modded games. This has to happen before the
+    /// global prototypes are first used, and returns false if they already have been.
+    pub fn set_global(db: PrototypeDb) -> bool {
+        GLOBAL.set(db).is_ok()
+    }
+
+    /// The entity prototype with this name.
+    pub fn entity(&self, name: &str) -> Option<&EntityPrototype> {
+        self.entities.get(name)
+    }
+
+    /// Add an entity prototype, replacing any with the same name.
+    pub fn insert_entity(&mut self, prototype: EntityPrototype) {
+        self.entities.insert(prototype.name.clone(), prototype);
+    }
+
+    /// Every entity prototype, in no particular order.
+    pub fn entities(&self) -> impl Iterator<Item = &EntityPrototype> {
+        self.entities.values()
+    }
+
+    /// The module prototype with this name.
+    pub fn module(&self, name: &str) -> Option<&ModulePrototype> {
+        self.modules.get(name)
+    }
+
+    /// Add a module prototype, replacing any with the same name.
+    pub fn insert_module(&mut self, prototype: ModulePrototype) {
+        self.modules.insert(prototype.name.clone(), prototype);
+    }
+
+    /// Every module prototype, in no particular order.
+    pub fn modules(&self) -> impl Iterator<Item = &ModulePrototype> {
+        self.modules.values()
+    }
+
+    /// The item prototype with this name.
+    pub fn item(&self, name: &str) -> Option<&ItemPrototype> {
+        self.items.get(name)
+    }
+
+    /// Add an item prototype, replacing any with the same name.
+    pub fn insert_item(&mut self, prototype: ItemPrototype) {
+        self.items.insert(prototype.name.clone(), prototype);
+    }
+
+    /// Every item prototype, in no particular order.
+    pub fn items(&self) -> impl Iterator<Item = &ItemPrototype> {
+        self.items.values()
+    }
+
+    /// The recipe prototype with this name.
+    pub fn recipe(&self, name: &str) -> Option<&RecipePrototype> {
+        self.recipes.get(name)
+    }
+
+    /// Add a recipe prototype, replacing any with the same name.
+    pub fn insert_recipe(&mut self, prototype: RecipePrototype) {
+        self.recipes.insert(prototype.name.clone(), prototype);
+    }
+
+    /// Every recipe prototype, in no particular order.
+    pub fn recipes(&self) -> impl Iterator<Item = &RecipePrototype> {
+        self.recipes.values()
+    }
+
+    /// Whether the recipe ingredient or product with this name is a fluid rather than an item.
+    pub fn is_fluid(&self, name: &str) -> bool {
+        self.fluids.contains(name)
+    }
+
+    /// Add the name of a fluid.
+    pub fn insert_fluid(&mut self, name: impl Into<String>) {
+        self.fluids.insert(name.into());
+    }
+
+    /// Whether there is a tile with this name.
+    pub fn is_tile(&self, name: &str) -> bool {
+        self.tiles.contains(name)
+    }
+
+    /// Add the name of a tile.
+    pub fn insert_tile(&mut self, name: impl Into<String>) {
+        self.tiles.insert(name.into());
+    }
+
+    /// Whether there is an entity, item, module, recipe, fluid, or tile with this name.
+    pub fn knows(&self, name: &str) -> bool {
+        self.entities.contains_key(name)
+            || self.items.contains_key(name)
+            || self.modules.contains_key(name)
+            || self.recipes.contains_key(name)
+            || self.fluids.contains(name)
+            || self.tiles.contains(name)
+            || self.items_to_place.values().any(|(item, _)| item == name)
+            || self
+                .recipes
+                .values()
+                .any(|recipe| recipe.products.iter().any(|(product, _)| product == name))
+    }
+
+    /// The mod adding the prototype with this name, or `None` if it isn't known to come from
+    /// one. The base game counts as the mod "base".
+    pub fn mod_of(&self, name: &str) -> Option<&str> {
+        self.mods.get(name).map(String::as_str)
+    }
+
+    /// Set the mod adding the prototype with this name.
+    pub fn insert_mod(&mut self, name: impl Into<String>, mod_name: impl Into<String>) {
+        self.mods.insert(name.into(), mod_name.into());
+    }
+
+    /// The item and how many of it it takes to build the entity or tile with this name. Unless
+    /// set otherwise, one of the item with the same name.
+    pub fn item_to_place<'a>(&'a self, name: &'a str) -> (&'a str, u32) {
+        match self.items_to_place.get(name) {
+            Some((item, count)) => (item, *count),
+            None => (name, 1),
+        }
+    }
+
+    /// Set the item and how many of it it takes to build the entity or tile with this name.
+    pub fn insert_item_to_place(
+        &mut self,
+        name: impl Into<String>,
+        item: impl Into<String>,
+        count: u32,
+    ) {
+        self.items_to_place
+            .insert(name.into(), (item.into(), count));
+    }
+}
+
+impl Entity {
+    /// The entity's prototype in `db`.
+    pub fn prototype<'db>(&self, db: &'db PrototypeDb) -> Option<&'db EntityPrototype> {
+        db.entity(&self.name)
+    }
+
+    /// The tiles the entity covers, or `None` if `db` doesn't know the entity.
+    pub fn footprint(&self, db: &PrototypeDb) -> Option<BoundingBox> {
+        let (width, height) = self
+            .prototype(db)?
+            .tile_size(self.direction.unwrap_or_default());
+        Some(BoundingBox::around(
+            &self.position,
+            width as f64,
+            height as f64,
+        ))
+    }
+
+    /// The entity's collision box in blueprint coordinates, or `None` if `db` doesn't know the
+    /// entity.
+    pub fn collision_box(&self, db: &PrototypeDb) -> Option<BoundingBox> {
+        let relative = self
+            .prototype(db)?
+            .rotated_collision_box(self.direction.unwrap_or_default());
+        let Position { x, y } = self.position;
+        Some(BoundingBox::new(
+            relative.min_x + x,
+            relative.min_y + y,
+            relative.max_x + x,
+            relative.max_y + y,
+        ))
+    }
+
+    /// The combined effect of the modules requested for the entity. Items `db` doesn't know as
+    /// modules are ignored.
+    pub fn module_effect(&self, db: &PrototypeDb) -> Effect {
+        let mut effect = Effect::default();
+        for (name, count) in self.items.iter().flat_map(|items| items.counts()) {
+            if let Some(module) = db.module(name) {
+                for _ in 0..count {
+                    effect += module.effect;
+                }
+            }
+        }
+        effect
+    }
+
+    /// Where the entity takes items from and puts them, in blueprint coordinates, or `None` if
+    /// `db` doesn't know it as an inserter. Pickup and drop positions set on the entity, as
+    /// offsets from its position, take the place of the prototype's.
+    pub fn inserter_positions(&self, db: &PrototypeDb) -> Option<(Position, Position)> {
+        let inserter = self.prototype(db)?.inserter?;
+        let direction = self.direction.unwrap_or_default();
+        let place = |set: &Option<Position>, (x, y): (f64, f64)| {
+            let (x, y) = match set {
+                Some(offset) => (offset.x, offset.y),
+                None => rotated(x, y, direction),
+            };
+            // Snap away the rounding of adding fractions like 1.2
+            let snap = |v: f64| (v * 1e9).round() / 1e9;
+            Position {
+                x: snap(self.position.x + x),
+                y: snap(self.position.y + y),
+            }
+        };
+        let (pickup, drop) = match &self.details {
+            EntityDetails::Inserter {
+                pickup_position,
+                drop_position,
+                ..
+            } => (pickup_position, drop_position),
+            _ => (&None, &None),
+        };
+        Some((place(pickup, inserter.pickup), place(drop, inserter.drop)))
+    }
+
+    /// The area the entity's modules affect machines in, or `None` if `db` doesn't know it as a
+    /// beacon.
+    pub fn beacon_area(&self, db: &PrototypeDb) -> Option<BoundingBox> {
+        let distance = self.prototype(db)?.beacon?.supply_area_distance;
+        Some(self.footprint(db)?.expand(distance))
+    }
+
+    /// The area the entity supplies with power, or `None` if `db` doesn't know it as an electric
+    /// pole.
+    pub fn supply_area(&self, db: &PrototypeDb) -> Option<BoundingBox> {
+        let distance = self.prototype(db)?.electric_pole?.supply_area_distance;
+        Some(BoundingBox::around(
+            &self.position,
+            distance * 2.0,
+            distance * 2.0,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blueprint::{BlueprintBuilder, EntityBuilder};
+
+    #[test]
+    fn footprints_turn_with_their_entities() {
+        let mut db = PrototypeDb::new();
+        db.insert_entity(EntityPrototype::new(
+            "boiler",
+            BoundingBox::new(-1.29, -0.79, 1.29, 0.79),
+        ));
+        let boiler = db.entity("boiler").unwrap();
+        assert_eq!(boiler.tile_size(Direction::North), (3, 2));
+        assert_eq!(boiler.tile_size(Direction::East), (2, 3));
+        let east = boiler.rotated_collision_box(Direction::East);
+        assert_eq!(
+            (east.min_x, east.min_y, east.max_x, east.max_y),
+            (-0.79, -1.29, 0.79, 1.29)
+        );
+
+        let blueprint = BlueprintBuilder::new()
+            .entity(
+                EntityBuilder::new("boiler")
+                    .at(1.5, 1.0)
+                    .direction(Direction::East),
+            )
+            .entity(EntityBuilder::new("modded-box").at(0.5, 0.5))
+            .build_with(&db)
+            .unwrap();
+        let entities = blueprint.entities.unwrap();
+        let footprint = entities[0].footprint(&db).unwrap();
+        assert_eq!(
+            (
+                footprint.min_x,
+                footprint.min_y,
+                footprint.max_x,
+                footprint.max_y
+            ),
+            (0.5, -0.5, 2.5, 2.5)
+        );
+        assert!(entities[1].footprint(&db).is_none());
+    }
+}
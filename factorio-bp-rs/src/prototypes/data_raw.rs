@@ -0,0 +1,777 @@
+//! Loading prototypes from the `data-raw-dump.json` the game writes with `--dump-data`, so
+//! modded prototypes and changed numbers are picked up.
+
+use std::{collections::BTreeMap, io::Read};
+
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::blueprint::{BoundingBox, Direction};
+
+use super::{
+    Beacon, BeltKind, CraftingMachine, Effect, ElectricPole, EntityPrototype, FluidBox, Inserter,
+    ItemPrototype, Lab, ModulePrototype, PipeConnection, PowerSource, ProductionType, PrototypeDb,
+    RecipePrototype, TransportBelt,
+};
+
+/// The energy it takes to heat one unit of water or steam by one degree, in joules.
+const STEAM_HEAT_CAPACITY: f64 = 200.0;
+/// The items a belt holds per tile, over both lanes.
+const ITEMS_PER_TILE: f64 = 8.0;
+/// Game ticks per second.
+const TICKS_PER_SECOND: f64 = 60.0;
+/// The entity types that can face other directions than north, unless flagged not to. Crafting
+/// machines can when they have fluid boxes or aren't square.
+const ROTATABLE_TYPES: &[&str] = &[
+    "transport-belt",
+    "underground-belt",
+    "splitter",
+    "loader",
+    "loader-1x1",
+    "linked-belt",
+    "inserter",
+    "storage-tank",
+    "pipe-to-ground",
+    "pump",
+    "offshore-pump",
+    "boiler",
+    "generator",
+    "fusion-reactor",
+    "fusion-generator",
+    "mining-drill",
+    "asteroid-collector",
+    "arithmetic-combinator",
+    "decider-combinator",
+    "selector-combinator",
+    "constant-combinator",
+    "display-panel",
+    "straight-rail",
+    "curved-rail",
+    "rail-signal",
+    "rail-chain-signal",
+    "train-stop",
+    "gate",
+    "fluid-turret",
+];
+
+#[derive(Debug, Error)]
+/// An error encountered while loading a `data.raw` dump.
+pub enum DataRawError {
+    /// The dump is not JSON, or not an object of prototype types each holding prototypes by name.
+    #[error("data.raw dump is malformed: {0}")]
+    Json(#[from] serde_json::Error),
+    /// A prototype is missing fields this library needs, or has them in an unexpected form.
+    #[error("invalid {kind} prototype {name:?}: {source}")]
+    Prototype {
+        /// The prototype type, e.g. "assembling-machine".
+        kind: String,
+        /// The prototype name.
+        name: String,
+        /// What didn't match.
+        #[source]
+        source: serde_json::Error,
+    },
+}
+
+impl PrototypeDb {
+    /// Load the prototypes of a `data.raw` dump, as written to `script-output/data-raw-dump.json`
+    /// by running the game with `--dump-data`.
+    ///
+    /// Entities, items, modules, recipes, fluids, and tiles are read. Anything else is ignored, as are
+    /// fields this library doesn't use. Recipes from before 2.0 are read too, but pipe
+    /// connections are taken to be on the entity's own tiles, as they are since 2.0.
+    pub fn from_data_raw(json: &str) -> Result<Self, DataRawError> {
+        let dump: BTreeMap<String, BTreeMap<String, Value>> = serde_json::from_str(json)?;
+        Self::from_dump(&dump)
+    }
+
+    /// Load the prototypes of a `data.raw` dump read from `reader`. See
+    /// [from_data_raw](Self::from_data_raw).
+    pub fn from_data_raw_reader(reader: impl Read) -> Result<Self, DataRawError> {
+        let dump: BTreeMap<String, BTreeMap<String, Value>> = serde_json::from_reader(reader)?;
+        Self::from_dump(&dump)
+    }
+
+    fn from_dump(dump: &BTreeMap<String, BTreeMap<String, Value>>) -> Result<Self, DataRawError> {
+        let mut db = PrototypeDb::new();
+        // The recipes productivity modules were limited to before 2.0
+        let mut productivity_recipes = Vec::new();
+        for (kind, prototypes) in dump {
+            for (name, value) in prototypes {
+                let error = |source| DataRawError::Prototype {
+                    kind: kind.clone(),
+                    name: name.clone(),
+                    source,
+                };
+                if value.get("collision_box").is_some() {
+                    db.insert_entity(entity(kind, name, value).map_err(error)?);
+                }
+                if value.get("stack_size").is_some() {
+                    let item = RawItem::deserialize(value).map_err(error)?;
+                    if let Some(tile) = item.place_as_tile {
+                        db.insert_item_to_place(tile.result, name.as_str(), 1);
+                    }
+                    if let Some(place_result) = item.place_result.as_ref().filter(|&p| p != name) {
+                        db.insert_item_to_place(place_result.as_str(), name.as_str(), 1);
+                    }
+                    db.insert_item(ItemPrototype {
+                        name: name.clone(),
+                        stack_size: item.stack_size,
+                        place_result: item.place_result,
+                    });
+                }
+                match kind.as_str() {
+                    "module" => {
+                        let module = RawModule::deserialize(value).map_err(error)?;
+                        if module.effect.productivity.is_some() {
+                            productivity_recipes.extend(module.limitation);
+                        }
+                        db.insert_module(ModulePrototype {
+                            name: name.clone(),
+                            effect: module.effect.into(),
+                        });
+                    }
+                    "recipe" => {
+                        let recipe = RawRecipe::deserialize(value).map_err(error)?;
+                        db.insert_recipe(recipe.into_prototype(name));
+                    }
+                    "fluid" => db.insert_fluid(name.as_str()),
+                    "tile" => db.insert_tile(name.as_str()),
+                    _ => {}
+                }
+            }
+        }
+        for name in productivity_recipes {
+            if let Some(recipe) = db.recipes.get_mut(&name) {
+                recipe.allow_productivity = true;
+            }
+        }
+        Ok(db)
+    }
+}
+
+/// The entity prototype of type `kind` described by `value`.
+fn entity(kind: &str, name: &str, value: &Value) -> Result<EntityPrototype, serde_json::Error> {
+    let raw = RawEntity::deserialize(value)?;
+    let mut prototype = EntityPrototype::new(name, raw.collision_box.into());
+    if let Some(width) = raw.tile_width {
+        prototype.tile_width = width;
+    }
+    if let Some(height) = raw.tile_height {
+        prototype.tile_height = height;
+    }
+
+    let source = raw.energy_source.unwrap_or_default();
+    prototype.electric = source.kind == "electric";
+    if prototype.electric {
+        prototype.energy_usage = raw.energy_usage.unwrap_or(0.0);
+        prototype.drain = source.drain.unwrap_or(0.0);
+    }
+    prototype.fluid_boxes = raw
+        .fluid_box
+        .into_iter()
+        .chain(raw.input_fluid_box)
+        .chain(raw.fluid_boxes)
+        .chain(raw.output_fluid_box)
+        .map(|fluid_box| FluidBox {
+            connections: connections(fluid_box.pipe_connections),
+            production_type: match fluid_box.production_type.as_deref() {
+                Some("input") => ProductionType::Input,
+                Some("output") => ProductionType::Output,
+                _ => ProductionType::None,
+            },
+            filter: fluid_box.filter,
+        })
+        .collect();
+    prototype.heat_connections = connections(match raw.heat_buffer {
+        Some(buffer) => buffer.connections,
+        None if source.kind == "heat" => source.connections,
+        None => Vec::new(),
+    });
+    let crafting_machine = matches!(kind, "assembling-machine" | "furnace");
+    prototype.rotatable = !raw.flags.iter().any(|flag| flag == "not-rotatable")
+        && (ROTATABLE_TYPES.contains(&kind)
+            || crafting_machine
+                && (!prototype.fluid_boxes.is_empty()
+                    || prototype.tile_width != prototype.tile_height));
+
+    match kind {
+        "transport-belt" | "underground-belt" | "splitter" | "loader" | "loader-1x1" => {
+            let belt = RawBelt::deserialize(value)?;
+            let kind = match kind {
+                "transport-belt" => BeltKind::Belt,
+                "underground-belt" => BeltKind::Underground {
+                    max_distance: belt.max_distance.unwrap_or(0),
+                },
+                "splitter" => BeltKind::Splitter,
+                _ => BeltKind::Loader,
+            };
+            prototype.transport_belt = Some(TransportBelt {
+                kind,
+                speed: belt.speed * TICKS_PER_SECOND * ITEMS_PER_TILE,
+            });
+        }
+        "inserter" => {
+            let inserter = RawInserter::deserialize(value)?;
+            prototype.inserter = Some(Inserter {
+                pickup: inserter.pickup_position.into(),
+                drop: inserter.insert_position.into(),
+                // One item for each turn there and back
+                items_per_second: inserter.rotation_speed * TICKS_PER_SECOND,
+            });
+        }
+        "assembling-machine" | "furnace" | "rocket-silo" => {
+            let machine = RawCraftingMachine::deserialize(value)?;
+            // The game's default drain for crafting machines
+            if prototype.electric && source.drain.is_none() {
+                prototype.drain = prototype.energy_usage / 30.0;
+            }
+            prototype.crafting_machine = Some(CraftingMachine {
+                crafting_speed: machine.crafting_speed,
+                base_productivity: machine
+                    .effect_receiver
+                    .and_then(|receiver| receiver.base_effect.productivity)
+                    .map_or(0.0, f64::from),
+                crafting_categories: machine.crafting_categories,
+            });
+        }
+        "beacon" => {
+            let beacon = RawBeacon::deserialize(value)?;
+            prototype.beacon = Some(Beacon {
+                distribution_effectivity: beacon.distribution_effectivity,
+                supply_area_distance: beacon.supply_area_distance,
+            });
+        }
+        "lab" => {
+            let lab = RawLab::deserialize(value)?;
+            prototype.lab = Some(Lab {
+                researching_speed: lab.researching_speed,
+                inputs: lab.inputs,
+            });
+        }
+        "electric-pole" => {
+            let pole = RawElectricPole::deserialize(value)?;
+            prototype.electric_pole = Some(ElectricPole {
+                wire_reach: pole.maximum_wire_distance,
+                supply_area_distance: pole.supply_area_distance,
+            });
+        }
+        "generator" => {
+            let generator = RawGenerator::deserialize(value)?;
+            let output = generator.max_power_output.unwrap_or(
+                generator.fluid_usage_per_tick
+                    * TICKS_PER_SECOND
+                    * (generator.maximum_temperature - 15.0)
+                    * STEAM_HEAT_CAPACITY
+                    * generator.effectivity,
+            );
+            prototype.power_source = Some(PowerSource::Generator(output));
+        }
+        "burner-generator" | "fusion-generator" => {
+            if let Some(output) = RawGenerator::deserialize(value)?.max_power_output {
+                prototype.power_source = Some(PowerSource::Generator(output));
+            }
+        }
+        "solar-panel" => {
+            let panel = RawSolarPanel::deserialize(value)?;
+            prototype.power_source = Some(PowerSource::Solar(panel.production));
+        }
+        "accumulator" => {
+            prototype.power_source = Some(PowerSource::Accumulator {
+                capacity: source.buffer_capacity.unwrap_or(0.0),
+                output: source.output_flow_limit.unwrap_or(0.0),
+            });
+        }
+        "reactor" => {
+            let reactor = RawReactor::deserialize(value)?;
+            prototype.power_source = Some(PowerSource::Reactor(reactor.consumption));
+        }
+        _ => {}
+    }
+    Ok(prototype)
+}
+
+/// The pipe or heat connections described by `raw`, leaving out those with no fixed position.
+fn connections(raw: Vec<RawConnection>) -> Vec<PipeConnection> {
+    raw.into_iter()
+        .filter(|connection| connection.connection_type.as_deref() != Some("linked"))
+        .filter_map(|connection| {
+            let (x, y) = connection.position?.into();
+            let underground = connection.connection_type.as_deref() == Some("underground");
+            Some(PipeConnection {
+                x,
+                y,
+                direction: Direction::from_sixteenths(connection.direction?),
+                underground_distance: if underground {
+                    connection.max_underground_distance
+                } else {
+                    None
+                },
+            })
+        })
+        .collect()
+}
+
+/// A list, which the dump writes as `{}` when it is empty.
+fn list<'de, D: Deserializer<'de>, T: Deserialize<'de>>(
+    deserializer: D,
+) -> Result<Vec<T>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum List<T> {
+        Array(Vec<T>),
+        Table(BTreeMap<String, T>),
+    }
+
+    Ok(match List::deserialize(deserializer)? {
+        List::Array(items) => items,
+        List::Table(items) => items.into_values().collect(),
+    })
+}
+
+/// An amount of energy or power, like "150kW" or "5MJ", in joules or watts.
+fn energy<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    let text = String::deserialize(deserializer)?;
+    let number = text.trim_end_matches(['W', 'J']);
+    let (number, factor) = match number.char_indices().last() {
+        Some((i, prefix)) if prefix.is_ascii_alphabetic() => {
+            let factor = match prefix {
+                'k' | 'K' => 1e3,
+                'M' => 1e6,
+                'G' => 1e9,
+                'T' => 1e12,
+                'P' => 1e15,
+                'E' => 1e18,
+                _ => return Err(serde::de::Error::custom(format!("unknown energy {text:?}"))),
+            };
+            (&number[..i], factor)
+        }
+        _ => (number, 1.0),
+    };
+    number
+        .parse::<f64>()
+        .map(|number| number * factor)
+        .map_err(|_| serde::de::Error::custom(format!("unknown energy {text:?}")))
+}
+
+/// Like [energy], for fields that can be left out.
+fn optional_energy<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<f64>, D::Error> {
+    energy(deserializer).map(Some)
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+/// A position, written either as `[x, y]` or `{"x": x, "y": y}`.
+enum RawPosition {
+    Array(f64, f64),
+    Table { x: f64, y: f64 },
+}
+
+impl From<RawPosition> for (f64, f64) {
+    fn from(position: RawPosition) -> Self {
+        match position {
+            RawPosition::Array(x, y) | RawPosition::Table { x, y } => (x, y),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+/// A bounding box, written either as a pair of corners or with named corners.
+enum RawBox {
+    Array(RawPosition, RawPosition),
+    Table {
+        left_top: RawPosition,
+        right_bottom: RawPosition,
+    },
+}
+
+impl From<RawBox> for BoundingBox {
+    fn from(bounds: RawBox) -> Self {
+        let (RawBox::Array(min, max)
+        | RawBox::Table {
+            left_top: min,
+            right_bottom: max,
+        }) = bounds;
+        let ((min_x, min_y), (max_x, max_y)) = (min.into(), max.into());
+        BoundingBox::new(min_x, min_y, max_x, max_y)
+    }
+}
+
+#[derive(Deserialize)]
+/// The fields every entity can have.
+struct RawEntity {
+    collision_box: RawBox,
+    tile_width: Option<u32>,
+    tile_height: Option<u32>,
+    #[serde(default, deserialize_with = "list")]
+    flags: Vec<String>,
+    energy_source: Option<RawEnergySource>,
+    #[serde(default, deserialize_with = "optional_energy")]
+    energy_usage: Option<f64>,
+    fluid_box: Option<RawFluidBox>,
+    input_fluid_box: Option<RawFluidBox>,
+    output_fluid_box: Option<RawFluidBox>,
+    #[serde(default, deserialize_with = "list")]
+    fluid_boxes: Vec<RawFluidBox>,
+    heat_buffer: Option<RawHeatBuffer>,
+}
+
+#[derive(Deserialize, Default)]
+struct RawEnergySource {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default, deserialize_with = "optional_energy")]
+    drain: Option<f64>,
+    #[serde(default, deserialize_with = "optional_energy")]
+    buffer_capacity: Option<f64>,
+    #[serde(default, deserialize_with = "optional_energy")]
+    output_flow_limit: Option<f64>,
+    #[serde(default, deserialize_with = "list")]
+    connections: Vec<RawConnection>,
+}
+
+#[derive(Deserialize)]
+struct RawFluidBox {
+    #[serde(default, deserialize_with = "list")]
+    pipe_connections: Vec<RawConnection>,
+    production_type: Option<String>,
+    filter: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RawHeatBuffer {
+    #[serde(default, deserialize_with = "list")]
+    connections: Vec<RawConnection>,
+}
+
+#[derive(Deserialize)]
+struct RawConnection {
+    direction: Option<i32>,
+    position: Option<RawPosition>,
+    connection_type: Option<String>,
+    max_underground_distance: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct RawBelt {
+    speed: f64,
+    max_distance: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct RawInserter {
+    pickup_position: RawPosition,
+    insert_position: RawPosition,
+    rotation_speed: f64,
+}
+
+#[derive(Deserialize)]
+struct RawCraftingMachine {
+    crafting_speed: f64,
+    #[serde(default, deserialize_with = "list")]
+    crafting_categories: Vec<String>,
+    effect_receiver: Option<RawEffectReceiver>,
+}
+
+#[derive(Deserialize)]
+struct RawEffectReceiver {
+    #[serde(default)]
+    base_effect: RawEffect,
+}
+
+#[derive(Deserialize)]
+struct RawBeacon {
+    distribution_effectivity: f64,
+    supply_area_distance: f64,
+}
+
+#[derive(Deserialize)]
+struct RawLab {
+    #[serde(default = "one")]
+    researching_speed: f64,
+    #[serde(default, deserialize_with = "list")]
+    inputs: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct RawElectricPole {
+    maximum_wire_distance: f64,
+    supply_area_distance: f64,
+}
+
+#[derive(Deserialize)]
+struct RawGenerator {
+    #[serde(default, deserialize_with = "optional_energy")]
+    max_power_output: Option<f64>,
+    #[serde(default)]
+    fluid_usage_per_tick: f64,
+    #[serde(default)]
+    maximum_temperature: f64,
+    #[serde(default = "one")]
+    effectivity: f64,
+}
+
+#[derive(Deserialize)]
+struct RawSolarPanel {
+    #[serde(deserialize_with = "energy")]
+    production: f64,
+}
+
+#[derive(Deserialize)]
+struct RawReactor {
+    #[serde(deserialize_with = "energy")]
+    consumption: f64,
+}
+
+#[derive(Deserialize)]
+struct RawItem {
+    stack_size: u32,
+    place_result: Option<String>,
+    place_as_tile: Option<RawPlaceAsTile>,
+}
+
+#[derive(Deserialize)]
+struct RawPlaceAsTile {
+    result: String,
+}
+
+#[derive(Deserialize)]
+struct RawModule {
+    #[serde(default)]
+    effect: RawEffect,
+    /// The only recipes the module can be used on, before 2.0.
+    #[serde(default, deserialize_with = "list")]
+    limitation: Vec<String>,
+}
+
+#[derive(Deserialize, Default)]
+struct RawEffect {
+    consumption: Option<RawBonus>,
+    speed: Option<RawBonus>,
+    productivity: Option<RawBonus>,
+    pollution: Option<RawBonus>,
+    quality: Option<RawBonus>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+/// A bonus, written as a plain number since 2.0, and as `{"bonus": n}` before.
+enum RawBonus {
+    Plain(f64),
+    Table { bonus: f64 },
+}
+
+impl From<RawBonus> for f64 {
+    fn from(bonus: RawBonus) -> Self {
+        match bonus {
+            RawBonus::Plain(bonus) | RawBonus::Table { bonus } => bonus,
+        }
+    }
+}
+
+impl From<RawEffect> for Effect {
+    fn from(effect: RawEffect) -> Self {
+        let bonus = |bonus: Option<RawBonus>| bonus.map_or(0.0, f64::from);
+        Effect {
+            consumption: bonus(effect.consumption),
+            speed: bonus(effect.speed),
+            productivity: bonus(effect.productivity),
+            pollution: bonus(effect.pollution),
+            quality: bonus(effect.quality),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RawRecipe {
+    category: Option<String>,
+    energy_required: Option<f64>,
+    #[serde(default, deserialize_with = "list")]
+    ingredients: Vec<RawAmount>,
+    #[serde(default, deserialize_with = "list")]
+    results: Vec<RawAmount>,
+    /// The single product, with `result_count`, of recipes from before 2.0.
+    result: Option<String>,
+    result_count: Option<f64>,
+    /// The normal difficulty version, for recipes from before 2.0 with expensive versions.
+    normal: Option<Box<RawRecipe>>,
+    /// Missing before 2.0, when productivity modules listed the recipes they could be used on.
+    allow_productivity: Option<bool>,
+}
+
+impl RawRecipe {
+    fn into_prototype(self, name: &str) -> RecipePrototype {
+        let category = self
+            .category
+            .clone()
+            .unwrap_or_else(|| "crafting".to_owned());
+        let recipe = match self.normal {
+            Some(normal) => *normal,
+            None => self,
+        };
+        let mut products = Vec::new();
+        let mut probabilities = Vec::new();
+        for result in recipe.results {
+            let probability = result.probability();
+            let (name, amount) = result.expected();
+            if probability < 1.0 {
+                probabilities.push((name.clone(), probability));
+            }
+            products.push((name, amount));
+        }
+        if let Some(result) = recipe.result {
+            products.push((result, recipe.result_count.unwrap_or(1.0)));
+        }
+        RecipePrototype {
+            name: name.to_owned(),
+            category,
+            energy_required: recipe.energy_required.unwrap_or(0.5),
+            ingredients: recipe
+                .ingredients
+                .into_iter()
+                .map(RawAmount::expected)
+                .collect(),
+            products,
+            probabilities,
+            allow_productivity: recipe.allow_productivity.unwrap_or(false),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+/// A recipe ingredient or product, written as `[name, amount]` before 2.0.
+enum RawAmount {
+    Short(String, f64),
+    Full {
+        name: String,
+        amount: Option<f64>,
+        amount_min: Option<f64>,
+        amount_max: Option<f64>,
+        probability: Option<f64>,
+        extra_count_fraction: Option<f64>,
+    },
+}
+
+impl RawAmount {
+    /// The chance of a craft making it, for products.
+    fn probability(&self) -> f64 {
+        match self {
+            RawAmount::Short(..) => 1.0,
+            RawAmount::Full { probability, .. } => probability.unwrap_or(1.0),
+        }
+    }
+
+    /// The name, and the amount used or made on average.
+    fn expected(self) -> (String, f64) {
+        match self {
+            RawAmount::Short(name, amount) => (name, amount),
+            RawAmount::Full {
+                name,
+                amount,
+                amount_min,
+                amount_max,
+                probability,
+                extra_count_fraction,
+            } => {
+                let amount = amount.unwrap_or_else(|| {
+                    (amount_min.unwrap_or(0.0) + amount_max.unwrap_or(0.0)) / 2.0
+                });
+                let expected =
+                    (amount + extra_count_fraction.unwrap_or(0.0)) * probability.unwrap_or(1.0);
+                (name, expected)
+            }
+        }
+    }
+}
+
+/// The default of fields that default to 1.
+fn one() -> f64 {
+    1.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dumps_from_before_and_after_2_0_are_read() {
+        let json = serde_json::json!({
+            "assembling-machine": {
+                "big-assembler": {
+                    "collision_box": [[-1.2, -1.2], [1.2, 1.2]],
+                    "energy_source": {"type": "electric"},
+                    "energy_usage": "150kW",
+                    "crafting_speed": 0.75,
+                    "crafting_categories": ["crafting"]
+                }
+            },
+            "item": {
+                "big-assembler": {"stack_size": 50, "place_result": "big-assembler"},
+                "brick": {"stack_size": 100, "place_as_tile": {"result": "path"}}
+            },
+            "module": {
+                "fast-module": {"stack_size": 50, "effect": {"speed": {"bonus": 0.5}}},
+                "prod-module": {
+                    "stack_size": 50,
+                    "effect": {"productivity": 0.1},
+                    "limitation": ["old-gear"]
+                }
+            },
+            "recipe": {
+                "old-gear": {
+                    "normal": {"ingredients": [["iron-plate", 2]], "result": "gear"},
+                    "expensive": {"ingredients": [["iron-plate", 4]], "result": "gear"}
+                },
+                "new-gear": {
+                    "category": "advanced",
+                    "energy_required": 2,
+                    "ingredients": {},
+                    "results": [{"name": "gear", "amount_min": 1, "amount_max": 3,
+                        "probability": 0.5}]
+                }
+            },
+            "tile": {"path": {}}
+        });
+        let db = PrototypeDb::from_data_raw(&json.to_string()).unwrap();
+
+        let assembler = db.entity("big-assembler").unwrap();
+        assert_eq!((assembler.tile_width, assembler.tile_height), (3, 3));
+        assert_eq!((assembler.energy_usage, assembler.drain), (150e3, 5e3));
+        assert!(!assembler.rotatable);
+        let machine = assembler.crafting_machine.as_ref().unwrap();
+        assert_eq!(machine.crafting_speed, 0.75);
+        assert_eq!(db.item("big-assembler").unwrap().stack_size, 50);
+        assert_eq!(db.item_to_place("path"), ("brick", 1));
+        assert!(db.is_tile("path"));
+        assert_eq!(db.module("fast-module").unwrap().effect.speed, 0.5);
+
+        let old = db.recipe("old-gear").unwrap();
+        assert_eq!(old.category, "crafting");
+        assert_eq!(old.energy_required, 0.5);
+        assert_eq!(old.ingredients, [("iron-plate".to_owned(), 2.0)]);
+        assert_eq!(old.products, [("gear".to_owned(), 1.0)]);
+        assert!(old.allow_productivity);
+        let new = db.recipe("new-gear").unwrap();
+        assert_eq!(new.category, "advanced");
+        assert_eq!(new.products, [("gear".to_owned(), 1.0)]);
+        assert_eq!(new.probabilities, [("gear".to_owned(), 0.5)]);
+        assert!(!new.allow_productivity);
+    }
+
+    #[test]
+    fn bad_prototypes_are_named_in_errors() {
+        let json = r#"{"inserter": {"arm": {"collision_box": [[0, 0], [1, 1]]}}}"#;
+        let Err(DataRawError::Prototype { kind, name, .. }) = PrototypeDb::from_data_raw(json)
+        else {
+            panic!("expected the inserter to be rejected");
+        };
+        assert_eq!((kind.as_str(), name.as_str()), ("inserter", "arm"));
+        assert!(matches!(
+            PrototypeDb::from_data_raw("[]"),
+            Err(DataRawError::Json(_))
+        ));
+    }
+}
@@ -0,0 +1,1894 @@
+//! Prototype data of the base game and Space Age.
+
+use crate::blueprint::{BoundingBox, Direction};
+
+use super::{
+    Beacon, BeltKind, CraftingMachine, Effect, ElectricPole, EntityPrototype, FluidBox, Inserter,
+    Lab, ModulePrototype, PipeConnection, PowerSource, ProductionType, PrototypeDb,
+    RecipePrototype, TransportBelt,
+};
+
+/// Entity names and their collision boxes when facing north, as `[min_x, min_y, max_x, max_y]`.
+const COLLISION_BOXES: &[(&str, [f64; 4])] = &[
+    // Belts
+    ("transport-belt", [-0.4, -0.4, 0.4, 0.4]),
+    ("fast-transport-belt", [-0.4, -0.4, 0.4, 0.4]),
+    ("express-transport-belt", [-0.4, -0.4, 0.4, 0.4]),
+    ("turbo-transport-belt", [-0.4, -0.4, 0.4, 0.4]),
+    ("underground-belt", [-0.4, -0.4, 0.4, 0.4]),
+    ("fast-underground-belt", [-0.4, -0.4, 0.4, 0.4]),
+    ("express-underground-belt", [-0.4, -0.4, 0.4, 0.4]),
+    ("turbo-underground-belt", [-0.4, -0.4, 0.4, 0.4]),
+    ("splitter", [-0.9, -0.4, 0.9, 0.4]),
+    ("fast-splitter", [-0.9, -0.4, 0.9, 0.4]),
+    ("express-splitter", [-0.9, -0.4, 0.9, 0.4]),
+    ("turbo-splitter", [-0.9, -0.4, 0.9, 0.4]),
+    ("loader", [-0.4, -0.9, 0.4, 0.9]),
+    ("fast-loader", [-0.4, -0.9, 0.4, 0.9]),
+    ("express-loader", [-0.4, -0.9, 0.4, 0.9]),
+    ("turbo-loader", [-0.4, -0.9, 0.4, 0.9]),
+    ("loader-1x1", [-0.4, -0.4, 0.4, 0.4]),
+    // Inserters
+    ("burner-inserter", [-0.15, -0.15, 0.15, 0.15]),
+    ("inserter", [-0.15, -0.15, 0.15, 0.15]),
+    ("long-handed-inserter", [-0.15, -0.15, 0.15, 0.15]),
+    ("fast-inserter", [-0.15, -0.15, 0.15, 0.15]),
+    ("filter-inserter", [-0.15, -0.15, 0.15, 0.15]),
+    ("stack-inserter", [-0.15, -0.15, 0.15, 0.15]),
+    ("stack-filter-inserter", [-0.15, -0.15, 0.15, 0.15]),
+    ("bulk-inserter", [-0.15, -0.15, 0.15, 0.15]),
+    // Storage
+    ("wooden-chest", [-0.35, -0.35, 0.35, 0.35]),
+    ("iron-chest", [-0.35, -0.35, 0.35, 0.35]),
+    ("steel-chest", [-0.35, -0.35, 0.35, 0.35]),
+    ("logistic-chest-active-provider", [-0.35, -0.35, 0.35, 0.35]),
+    (
+        "logistic-chest-passive-provider",
+        [-0.35, -0.35, 0.35, 0.35],
+    ),
+    ("logistic-chest-storage", [-0.35, -0.35, 0.35, 0.35]),
+    ("logistic-chest-buffer", [-0.35, -0.35, 0.35, 0.35]),
+    ("logistic-chest-requester", [-0.35, -0.35, 0.35, 0.35]),
+    ("active-provider-chest", [-0.35, -0.35, 0.35, 0.35]),
+    ("passive-provider-chest", [-0.35, -0.35, 0.35, 0.35]),
+    ("storage-chest", [-0.35, -0.35, 0.35, 0.35]),
+    ("buffer-chest", [-0.35, -0.35, 0.35, 0.35]),
+    ("requester-chest", [-0.35, -0.35, 0.35, 0.35]),
+    ("infinity-chest", [-0.35, -0.35, 0.35, 0.35]),
+    ("storage-tank", [-1.3, -1.3, 1.3, 1.3]),
+    // Fluid handling
+    ("pipe", [-0.29, -0.29, 0.29, 0.29]),
+    ("pipe-to-ground", [-0.29, -0.29, 0.29, 0.29]),
+    ("infinity-pipe", [-0.29, -0.29, 0.29, 0.29]),
+    ("pump", [-0.29, -0.9, 0.29, 0.9]),
+    ("offshore-pump", [-0.6, -1.05, 0.6, 0.3]),
+    // Power
+    ("small-electric-pole", [-0.15, -0.15, 0.15, 0.15]),
+    ("medium-electric-pole", [-0.15, -0.15, 0.15, 0.15]),
+    ("big-electric-pole", [-0.65, -0.65, 0.65, 0.65]),
+    ("substation", [-0.7, -0.7, 0.7, 0.7]),
+    ("boiler", [-1.29, -0.79, 1.29, 0.79]),
+    ("steam-engine", [-1.35, -2.35, 1.35, 2.35]),
+    ("steam-turbine", [-1.35, -2.35, 1.35, 2.35]),
+    ("solar-panel", [-1.4, -1.4, 1.4, 1.4]),
+    ("accumulator", [-0.9, -0.9, 0.9, 0.9]),
+    ("nuclear-reactor", [-2.2, -2.2, 2.2, 2.2]),
+    ("heat-exchanger", [-1.29, -0.79, 1.29, 0.79]),
+    ("heat-pipe", [-0.3, -0.3, 0.3, 0.3]),
+    ("heat-interface", [-0.4, -0.4, 0.4, 0.4]),
+    ("electric-energy-interface", [-0.9, -0.9, 0.9, 0.9]),
+    ("power-switch", [-0.9, -0.9, 0.9, 0.9]),
+    ("lightning-rod", [-0.2, -0.2, 0.2, 0.2]),
+    ("lightning-collector", [-0.7, -0.7, 0.7, 0.7]),
+    ("fusion-reactor", [-2.7, -2.7, 2.7, 2.7]),
+    ("fusion-generator", [-1.4, -2.4, 1.4, 2.4]),
+    ("heating-tower", [-1.2, -1.2, 1.2, 1.2]),
+    // Production
+    ("burner-mining-drill", [-0.7, -0.7, 0.7, 0.7]),
+    ("electric-mining-drill", [-1.4, -1.4, 1.4, 1.4]),
+    ("big-mining-drill", [-2.3, -2.3, 2.3, 2.3]),
+    ("pumpjack", [-1.2, -1.2, 1.2, 1.2]),
+    ("stone-furnace", [-0.7, -0.7, 0.7, 0.7]),
+    ("steel-furnace", [-0.875, -0.875, 0.875, 0.875]),
+    ("electric-furnace", [-1.2, -1.2, 1.2, 1.2]),
+    ("assembling-machine-1", [-1.2, -1.2, 1.2, 1.2]),
+    ("assembling-machine-2", [-1.2, -1.2, 1.2, 1.2]),
+    ("assembling-machine-3", [-1.2, -1.2, 1.2, 1.2]),
+    ("oil-refinery", [-2.4, -2.4, 2.4, 2.4]),
+    ("chemical-plant", [-1.2, -1.2, 1.2, 1.2]),
+    ("centrifuge", [-1.2, -1.2, 1.2, 1.2]),
+    ("lab", [-1.2, -1.2, 1.2, 1.2]),
+    ("biolab", [-2.2, -2.2, 2.2, 2.2]),
+    ("beacon", [-1.2, -1.2, 1.2, 1.2]),
+    ("rocket-silo", [-4.4, -4.4, 4.4, 4.4]),
+    ("electromagnetic-plant", [-1.7, -1.7, 1.7, 1.7]),
+    ("foundry", [-2.2, -2.2, 2.2, 2.2]),
+    ("biochamber", [-1.2, -1.2, 1.2, 1.2]),
+    ("cryogenic-plant", [-2.2, -2.2, 2.2, 2.2]),
+    ("recycler", [-0.7, -1.7, 0.7, 1.7]),
+    ("crusher", [-0.7, -1.2, 0.7, 1.2]),
+    ("agricultural-tower", [-1.2, -1.2, 1.2, 1.2]),
+    ("captive-biter-spawner", [-2.2, -2.2, 2.2, 2.2]),
+    ("asteroid-collector", [-1.2, -1.2, 1.2, 1.2]),
+    ("thruster", [-1.7, -2.2, 1.7, 2.2]),
+    ("cargo-landing-pad", [-3.9, -3.9, 3.9, 3.9]),
+    ("space-platform-hub", [-3.9, -3.9, 3.9, 3.9]),
+    ("cargo-bay", [-1.9, -1.9, 1.9, 1.9]),
+    // Logistics network
+    ("roboport", [-1.7, -1.7, 1.7, 1.7]),
+    ("radar", [-1.4, -1.4, 1.4, 1.4]),
+    // Circuit network
+    ("small-lamp", [-0.15, -0.15, 0.15, 0.15]),
+    ("arithmetic-combinator", [-0.35, -0.65, 0.35, 0.65]),
+    ("decider-combinator", [-0.35, -0.65, 0.35, 0.65]),
+    ("selector-combinator", [-0.35, -0.65, 0.35, 0.65]),
+    ("constant-combinator", [-0.35, -0.35, 0.35, 0.35]),
+    ("programmable-speaker", [-0.3, -0.3, 0.3, 0.3]),
+    ("display-panel", [-0.35, -0.35, 0.35, 0.35]),
+    // Trains
+    ("straight-rail", [-0.7, -0.99, 0.7, 0.99]),
+    ("rail-signal", [-0.2, -0.2, 0.2, 0.2]),
+    ("rail-chain-signal", [-0.2, -0.2, 0.2, 0.2]),
+    ("train-stop", [-0.5, -0.5, 0.5, 0.5]),
+    // Defence
+    ("stone-wall", [-0.49, -0.49, 0.49, 0.49]),
+    ("gate", [-0.29, -0.29, 0.29, 0.29]),
+    ("land-mine", [-0.4, -0.4, 0.4, 0.4]),
+    ("gun-turret", [-0.7, -0.7, 0.7, 0.7]),
+    ("laser-turret", [-0.7, -0.7, 0.7, 0.7]),
+    ("flamethrower-turret", [-0.7, -1.2, 0.7, 1.2]),
+    ("artillery-turret", [-1.45, -1.45, 1.45, 1.45]),
+    ("rocket-turret", [-1.2, -1.2, 1.2, 1.2]),
+    ("tesla-turret", [-0.7, -0.7, 0.7, 0.7]),
+    ("railgun-turret", [-3.2, -3.2, 3.2, 3.2]),
+];
+
+/// Entities that cover more or fewer tiles than their collision box touches.
+const TILE_SIZES: &[(&str, u32, u32)] = &[("offshore-pump", 1, 2), ("train-stop", 2, 2)];
+
+/// Entities that can face other directions than north.
+const ROTATABLE: &[&str] = &[
+    "transport-belt",
+    "fast-transport-belt",
+    "express-transport-belt",
+    "turbo-transport-belt",
+    "underground-belt",
+    "fast-underground-belt",
+    "express-underground-belt",
+    "turbo-underground-belt",
+    "splitter",
+    "fast-splitter",
+    "express-splitter",
+    "turbo-splitter",
+    "loader",
+    "fast-loader",
+    "express-loader",
+    "turbo-loader",
+    "loader-1x1",
+    "burner-inserter",
+    "inserter",
+    "long-handed-inserter",
+    "fast-inserter",
+    "filter-inserter",
+    "stack-inserter",
+    "stack-filter-inserter",
+    "bulk-inserter",
+    "storage-tank",
+    "pipe-to-ground",
+    "pump",
+    "offshore-pump",
+    "boiler",
+    "steam-engine",
+    "steam-turbine",
+    "heat-exchanger",
+    "fusion-reactor",
+    "fusion-generator",
+    "burner-mining-drill",
+    "electric-mining-drill",
+    "big-mining-drill",
+    "assembling-machine-2",
+    "assembling-machine-3",
+    "oil-refinery",
+    "chemical-plant",
+    "electromagnetic-plant",
+    "foundry",
+    "biochamber",
+    "cryogenic-plant",
+    "recycler",
+    "crusher",
+    "asteroid-collector",
+    "arithmetic-combinator",
+    "decider-combinator",
+    "selector-combinator",
+    "constant-combinator",
+    "display-panel",
+    "straight-rail",
+    "rail-signal",
+    "rail-chain-signal",
+    "train-stop",
+    "gate",
+    "flamethrower-turret",
+];
+
+/// Entities that use or produce electricity.
+const ELECTRIC: &[&str] = &[
+    "inserter",
+    "long-handed-inserter",
+    "fast-inserter",
+    "filter-inserter",
+    "stack-inserter",
+    "stack-filter-inserter",
+    "bulk-inserter",
+    "pump",
+    "small-electric-pole",
+    "medium-electric-pole",
+    "big-electric-pole",
+    "substation",
+    "steam-engine",
+    "steam-turbine",
+    "solar-panel",
+    "accumulator",
+    "electric-energy-interface",
+    "lightning-collector",
+    "fusion-reactor",
+    "fusion-generator",
+    "electric-mining-drill",
+    "big-mining-drill",
+    "pumpjack",
+    "electric-furnace",
+    "assembling-machine-1",
+    "assembling-machine-2",
+    "assembling-machine-3",
+    "oil-refinery",
+    "chemical-plant",
+    "centrifuge",
+    "lab",
+    "biolab",
+    "beacon",
+    "rocket-silo",
+    "electromagnetic-plant",
+    "foundry",
+    "cryogenic-plant",
+    "recycler",
+    "crusher",
+    "agricultural-tower",
+    "asteroid-collector",
+    "roboport",
+    "radar",
+    "small-lamp",
+    "arithmetic-combinator",
+    "decider-combinator",
+    "selector-combinator",
+    "programmable-speaker",
+    "laser-turret",
+    "tesla-turret",
+    "railgun-turret",
+];
+
+/// Electric poles, with their wire reach and supply area distance.
+const ELECTRIC_POLES: &[(&str, f64, f64)] = &[
+    ("small-electric-pole", 7.5, 2.5),
+    ("medium-electric-pole", 9.0, 3.5),
+    ("big-electric-pole", 30.0, 2.0),
+    ("substation", 18.0, 9.0),
+];
+
+/// Electric entities, with the power they use while working and their drain, in kW.
+const ENERGY_USAGE: &[(&str, f64, f64)] = &[
+    ("inserter", 13.2, 0.4),
+    ("long-handed-inserter", 20.0, 0.4),
+    ("fast-inserter", 46.7, 0.5),
+    ("filter-inserter", 46.7, 0.5),
+    ("stack-inserter", 132.0, 1.0),
+    ("stack-filter-inserter", 132.0, 1.0),
+    ("bulk-inserter", 132.0, 1.0),
+    ("pump", 30.0, 0.0),
+    ("electric-mining-drill", 90.0, 0.0),
+    ("big-mining-drill", 300.0, 0.0),
+    ("pumpjack", 90.0, 0.0),
+    ("electric-furnace", 180.0, 6.0),
+    ("assembling-machine-1", 75.0, 2.5),
+    ("assembling-machine-2", 150.0, 5.0),
+    ("assembling-machine-3", 375.0, 12.5),
+    ("oil-refinery", 420.0, 14.0),
+    ("chemical-plant", 210.0, 7.0),
+    ("centrifuge", 350.0, 11.6),
+    ("lab", 60.0, 2.0),
+    ("biolab", 300.0, 10.0),
+    ("beacon", 0.0, 480.0),
+    ("rocket-silo", 250.0, 0.0),
+    ("electromagnetic-plant", 2000.0, 66.7),
+    ("foundry", 2500.0, 83.3),
+    ("cryogenic-plant", 1500.0, 50.0),
+    ("recycler", 180.0, 6.0),
+    ("crusher", 540.0, 18.0),
+    ("agricultural-tower", 100.0, 0.0),
+    ("roboport", 4000.0, 50.0),
+    ("radar", 0.0, 300.0),
+    ("small-lamp", 0.0, 5.0),
+    ("arithmetic-combinator", 0.0, 1.0),
+    ("decider-combinator", 0.0, 1.0),
+    ("selector-combinator", 0.0, 1.0),
+    ("programmable-speaker", 0.0, 2.0),
+    ("laser-turret", 2400.0, 24.0),
+];
+
+/// Entities that produce or store energy.
+const POWER_SOURCES: &[(&str, PowerSource)] = &[
+    ("steam-engine", PowerSource::Generator(900e3)),
+    ("steam-turbine", PowerSource::Generator(5.82e6)),
+    ("fusion-generator", PowerSource::Generator(50e6)),
+    ("solar-panel", PowerSource::Solar(60e3)),
+    (
+        "accumulator",
+        PowerSource::Accumulator {
+            capacity: 5e6,
+            output: 300e3,
+        },
+    ),
+    ("nuclear-reactor", PowerSource::Reactor(40e6)),
+];
+
+/// Belts, underground belts, splitters, and loaders, with their speed in items per second.
+const BELTS: &[(&str, BeltKind, f64)] = &[
+    ("transport-belt", BeltKind::Belt, 15.0),
+    (
+        "underground-belt",
+        BeltKind::Underground { max_distance: 5 },
+        15.0,
+    ),
+    ("splitter", BeltKind::Splitter, 15.0),
+    ("loader", BeltKind::Loader, 15.0),
+    ("loader-1x1", BeltKind::Loader, 15.0),
+    ("fast-transport-belt", BeltKind::Belt, 30.0),
+    (
+        "fast-underground-belt",
+        BeltKind::Underground { max_distance: 7 },
+        30.0,
+    ),
+    ("fast-splitter", BeltKind::Splitter, 30.0),
+    ("fast-loader", BeltKind::Loader, 30.0),
+    ("express-transport-belt", BeltKind::Belt, 45.0),
+    (
+        "express-underground-belt",
+        BeltKind::Underground { max_distance: 9 },
+        45.0,
+    ),
+    ("express-splitter", BeltKind::Splitter, 45.0),
+    ("express-loader", BeltKind::Loader, 45.0),
+    ("turbo-transport-belt", BeltKind::Belt, 60.0),
+    (
+        "turbo-underground-belt",
+        BeltKind::Underground { max_distance: 11 },
+        60.0,
+    ),
+    ("turbo-splitter", BeltKind::Splitter, 60.0),
+    ("turbo-loader", BeltKind::Loader, 60.0),
+];
+
+/// Inserters, with how far from them they pick up and drop items, and the items they move each
+/// second from chest to chest. They pick up from the way they face and drop beyond the middle of
+/// the tile behind them.
+const INSERTERS: &[(&str, f64, f64, f64)] = &[
+    ("burner-inserter", 1.0, 1.2, 0.6),
+    ("inserter", 1.0, 1.2, 0.83),
+    ("long-handed-inserter", 2.0, 2.2, 1.2),
+    ("fast-inserter", 1.0, 1.2, 2.31),
+    ("filter-inserter", 1.0, 1.2, 2.31),
+    ("stack-inserter", 1.0, 1.2, 4.62),
+    ("stack-filter-inserter", 1.0, 1.2, 4.62),
+    ("bulk-inserter", 1.0, 1.2, 4.62),
+];
+
+/// Crafting machines, with their crafting speed, the productivity they have without modules,
+/// and the categories of recipes they craft.
+const CRAFTING_MACHINES: &[(&str, f64, f64, &[&str])] = &[
+    ("stone-furnace", 1.0, 0.0, &["smelting"]),
+    ("steel-furnace", 2.0, 0.0, &["smelting"]),
+    ("electric-furnace", 2.0, 0.0, &["smelting"]),
+    ("assembling-machine-1", 0.5, 0.0, ASSEMBLING),
+    ("assembling-machine-2", 0.75, 0.0, ASSEMBLING_WITH_FLUID),
+    ("assembling-machine-3", 1.25, 0.0, ASSEMBLING_WITH_FLUID),
+    ("oil-refinery", 1.0, 0.0, &["oil-processing"]),
+    ("chemical-plant", 1.0, 0.0, &["chemistry"]),
+    ("centrifuge", 1.0, 0.0, &["centrifuging"]),
+    ("rocket-silo", 1.0, 0.0, &["rocket-building"]),
+    ("electromagnetic-plant", 2.0, 0.5, &["electromagnetics"]),
+    ("foundry", 4.0, 0.5, &["metallurgy"]),
+    ("biochamber", 2.0, 0.5, &["organic"]),
+    ("cryogenic-plant", 2.0, 0.0, &["cryogenics"]),
+    ("recycler", 0.5, 0.0, &["recycling"]),
+    ("crusher", 1.0, 0.0, &["crushing"]),
+];
+
+/// The recipe categories of assembling machines.
+const ASSEMBLING: &[&str] = &["crafting", "basic-crafting", "advanced-crafting"];
+/// The recipe categories of assembling machines with fluid inputs.
+const ASSEMBLING_WITH_FLUID: &[&str] = &[
+    "crafting",
+    "basic-crafting",
+    "advanced-crafting",
+    "crafting-with-fluid",
+];
+
+/// Beacons, with the fraction of their modules' effects they pass on and how far beyond their
+/// edges they reach.
+const BEACONS: &[(&str, f64, f64)] = &[("beacon", 1.5, 3.0)];
+
+/// Labs, with their researching speed.
+const LABS: &[(&str, f64)] = &[("lab", 1.0), ("biolab", 2.0)];
+
+/// The science packs labs take.
+const SCIENCE_PACKS: &[&str] = &[
+    "automation-science-pack",
+    "logistic-science-pack",
+    "military-science-pack",
+    "chemical-science-pack",
+    "production-science-pack",
+    "utility-science-pack",
+    "space-science-pack",
+    "metallurgic-science-pack",
+    "electromagnetic-science-pack",
+    "agricultural-science-pack",
+    "cryogenic-science-pack",
+    "promethium-science-pack",
+];
+
+/// Item or fluid names with their amounts, for the table below.
+type Amounts = &'static [(&'static str, f64)];
+
+/// Entities and tiles built with something other than one of the item of the same name, with
+/// the item and how many it takes.
+const ITEMS_TO_PLACE: &[(&str, &str, u32)] = &[
+    ("straight-rail", "rail", 1),
+    ("curved-rail", "rail", 4),
+    ("half-diagonal-rail", "rail", 2),
+    ("curved-rail-a", "rail", 3),
+    ("curved-rail-b", "rail", 3),
+    ("legacy-straight-rail", "rail", 1),
+    ("legacy-curved-rail", "rail", 4),
+    ("stone-path", "stone-brick", 1),
+    ("hazard-concrete-left", "hazard-concrete", 1),
+    ("hazard-concrete-right", "hazard-concrete", 1),
+    ("refined-hazard-concrete-left", "refined-hazard-concrete", 1),
+    (
+        "refined-hazard-concrete-right",
+        "refined-hazard-concrete",
+        1,
+    ),
+];
+
+/// Tiles that can be placed in blueprints.
+const TILES: &[&str] = &[
+    "stone-path",
+    "concrete",
+    "hazard-concrete-left",
+    "hazard-concrete-right",
+    "refined-concrete",
+    "refined-hazard-concrete-left",
+    "refined-hazard-concrete-right",
+    "landfill",
+    "foundation",
+    "ice-platform",
+    "space-platform-foundation",
+    "artificial-yumako-soil",
+    "artificial-jellynut-soil",
+    "overgrowth-yumako-soil",
+    "overgrowth-jellynut-soil",
+];
+
+/// The official mods, and the prototypes they add that blueprints can hold. The base game lists
+/// only what isn't described elsewhere here, and is assumed for everything else.
+const MODS: &[(&str, &[&str])] = &[
+    (
+        "base",
+        &[
+            "locomotive",
+            "cargo-wagon",
+            "fluid-wagon",
+            "artillery-wagon",
+            "car",
+            "tank",
+            "spidertron",
+            "parameter-0",
+            "parameter-1",
+            "parameter-2",
+            "parameter-3",
+            "parameter-4",
+            "parameter-5",
+            "parameter-6",
+            "parameter-7",
+            "parameter-8",
+            "parameter-9",
+        ],
+    ),
+    (
+        "space-age",
+        &[
+            "turbo-transport-belt",
+            "turbo-underground-belt",
+            "turbo-splitter",
+            "turbo-loader",
+            "lightning-rod",
+            "lightning-collector",
+            "fusion-reactor",
+            "fusion-generator",
+            "heating-tower",
+            "big-mining-drill",
+            "biolab",
+            "electromagnetic-plant",
+            "foundry",
+            "biochamber",
+            "cryogenic-plant",
+            "crusher",
+            "agricultural-tower",
+            "captive-biter-spawner",
+            "asteroid-collector",
+            "thruster",
+            "space-platform-hub",
+            "cargo-bay",
+            "rocket-turret",
+            "tesla-turret",
+            "railgun-turret",
+            "metallurgic-science-pack",
+            "electromagnetic-science-pack",
+            "agricultural-science-pack",
+            "cryogenic-science-pack",
+            "promethium-science-pack",
+            "foundation",
+            "ice-platform",
+            "space-platform-foundation",
+            "artificial-yumako-soil",
+            "artificial-jellynut-soil",
+            "overgrowth-yumako-soil",
+            "overgrowth-jellynut-soil",
+        ],
+    ),
+    (
+        "quality",
+        &[
+            "recycler",
+            "quality-module",
+            "quality-module-2",
+            "quality-module-3",
+        ],
+    ),
+    (
+        "elevated-rails",
+        &[
+            "rail-ramp",
+            "rail-support",
+            "elevated-straight-rail",
+            "elevated-half-diagonal-rail",
+            "elevated-curved-rail-a",
+            "elevated-curved-rail-b",
+        ],
+    ),
+];
+
+/// Fluids used and made by the recipes below.
+const FLUIDS: &[&str] = &[
+    "water",
+    "steam",
+    "crude-oil",
+    "heavy-oil",
+    "light-oil",
+    "petroleum-gas",
+    "lubricant",
+    "sulfuric-acid",
+];
+
+/// Recipes, with their category, the seconds a craft takes at crafting speed 1, and the
+/// ingredients and products of a craft. Products made only some of the time have their expected amounts.
+/// Ingredients and products are in the order the game lists them, which decides the fluid box
+/// each fluid goes in.
+const RECIPES: &[(&str, &str, f64, Amounts, Amounts)] = &[
+    // Smelting
+    (
+        "iron-plate",
+        "smelting",
+        3.2,
+        &[("iron-ore", 1.0)],
+        &[("iron-plate", 1.0)],
+    ),
+    (
+        "copper-plate",
+        "smelting",
+        3.2,
+        &[("copper-ore", 1.0)],
+        &[("copper-plate", 1.0)],
+    ),
+    (
+        "stone-brick",
+        "smelting",
+        3.2,
+        &[("stone", 2.0)],
+        &[("stone-brick", 1.0)],
+    ),
+    (
+        "steel-plate",
+        "smelting",
+        16.0,
+        &[("iron-plate", 5.0)],
+        &[("steel-plate", 1.0)],
+    ),
+    // Intermediates
+    (
+        "iron-gear-wheel",
+        "crafting",
+        0.5,
+        &[("iron-plate", 2.0)],
+        &[("iron-gear-wheel", 1.0)],
+    ),
+    (
+        "iron-stick",
+        "crafting",
+        0.5,
+        &[("iron-plate", 1.0)],
+        &[("iron-stick", 2.0)],
+    ),
+    (
+        "copper-cable",
+        "crafting",
+        0.5,
+        &[("copper-plate", 1.0)],
+        &[("copper-cable", 2.0)],
+    ),
+    (
+        "pipe",
+        "crafting",
+        0.5,
+        &[("iron-plate", 1.0)],
+        &[("pipe", 1.0)],
+    ),
+    (
+        "electronic-circuit",
+        "crafting",
+        0.5,
+        &[("iron-plate", 1.0), ("copper-cable", 3.0)],
+        &[("electronic-circuit", 1.0)],
+    ),
+    (
+        "advanced-circuit",
+        "crafting",
+        6.0,
+        &[
+            ("plastic-bar", 2.0),
+            ("copper-cable", 4.0),
+            ("electronic-circuit", 2.0),
+        ],
+        &[("advanced-circuit", 1.0)],
+    ),
+    (
+        "processing-unit",
+        "crafting-with-fluid",
+        10.0,
+        &[
+            ("electronic-circuit", 20.0),
+            ("advanced-circuit", 2.0),
+            ("sulfuric-acid", 5.0),
+        ],
+        &[("processing-unit", 1.0)],
+    ),
+    (
+        "engine-unit",
+        "advanced-crafting",
+        10.0,
+        &[
+            ("steel-plate", 1.0),
+            ("iron-gear-wheel", 1.0),
+            ("pipe", 2.0),
+        ],
+        &[("engine-unit", 1.0)],
+    ),
+    (
+        "electric-engine-unit",
+        "crafting-with-fluid",
+        10.0,
+        &[
+            ("engine-unit", 1.0),
+            ("electronic-circuit", 2.0),
+            ("lubricant", 15.0),
+        ],
+        &[("electric-engine-unit", 1.0)],
+    ),
+    (
+        "flying-robot-frame",
+        "crafting",
+        20.0,
+        &[
+            ("electric-engine-unit", 1.0),
+            ("battery", 2.0),
+            ("steel-plate", 1.0),
+            ("electronic-circuit", 3.0),
+        ],
+        &[("flying-robot-frame", 1.0)],
+    ),
+    (
+        "low-density-structure",
+        "crafting",
+        15.0,
+        &[
+            ("steel-plate", 2.0),
+            ("copper-plate", 20.0),
+            ("plastic-bar", 5.0),
+        ],
+        &[("low-density-structure", 1.0)],
+    ),
+    (
+        "battery",
+        "chemistry",
+        4.0,
+        &[
+            ("iron-plate", 1.0),
+            ("copper-plate", 1.0),
+            ("sulfuric-acid", 20.0),
+        ],
+        &[("battery", 1.0)],
+    ),
+    (
+        "rocket-fuel",
+        "crafting-with-fluid",
+        15.0,
+        &[("solid-fuel", 10.0), ("light-oil", 10.0)],
+        &[("rocket-fuel", 1.0)],
+    ),
+    // Oil
+    (
+        "basic-oil-processing",
+        "oil-processing",
+        5.0,
+        &[("crude-oil", 100.0)],
+        &[("petroleum-gas", 45.0)],
+    ),
+    (
+        "advanced-oil-processing",
+        "oil-processing",
+        5.0,
+        &[("water", 50.0), ("crude-oil", 100.0)],
+        &[
+            ("heavy-oil", 25.0),
+            ("light-oil", 45.0),
+            ("petroleum-gas", 55.0),
+        ],
+    ),
+    (
+        "coal-liquefaction",
+        "oil-processing",
+        5.0,
+        &[("coal", 10.0), ("heavy-oil", 25.0), ("steam", 50.0)],
+        &[
+            ("heavy-oil", 90.0),
+            ("light-oil", 20.0),
+            ("petroleum-gas", 10.0),
+        ],
+    ),
+    (
+        "heavy-oil-cracking",
+        "chemistry",
+        2.0,
+        &[("water", 30.0), ("heavy-oil", 40.0)],
+        &[("light-oil", 30.0)],
+    ),
+    (
+        "light-oil-cracking",
+        "chemistry",
+        2.0,
+        &[("water", 30.0), ("light-oil", 30.0)],
+        &[("petroleum-gas", 20.0)],
+    ),
+    (
+        "lubricant",
+        "chemistry",
+        1.0,
+        &[("heavy-oil", 10.0)],
+        &[("lubricant", 10.0)],
+    ),
+    (
+        "solid-fuel-from-light-oil",
+        "chemistry",
+        1.0,
+        &[("light-oil", 10.0)],
+        &[("solid-fuel", 1.0)],
+    ),
+    (
+        "solid-fuel-from-petroleum-gas",
+        "chemistry",
+        1.0,
+        &[("petroleum-gas", 20.0)],
+        &[("solid-fuel", 1.0)],
+    ),
+    (
+        "plastic-bar",
+        "chemistry",
+        1.0,
+        &[("petroleum-gas", 20.0), ("coal", 1.0)],
+        &[("plastic-bar", 2.0)],
+    ),
+    (
+        "sulfur",
+        "chemistry",
+        1.0,
+        &[("water", 30.0), ("petroleum-gas", 30.0)],
+        &[("sulfur", 2.0)],
+    ),
+    (
+        "sulfuric-acid",
+        "chemistry",
+        1.0,
+        &[("sulfur", 5.0), ("iron-plate", 1.0), ("water", 100.0)],
+        &[("sulfuric-acid", 50.0)],
+    ),
+    // Uranium
+    (
+        "uranium-processing",
+        "centrifuging",
+        12.0,
+        &[("uranium-ore", 10.0)],
+        &[("uranium-235", 0.007), ("uranium-238", 0.993)],
+    ),
+    (
+        "kovarex-enrichment-process",
+        "centrifuging",
+        60.0,
+        &[("uranium-235", 40.0), ("uranium-238", 5.0)],
+        &[("uranium-235", 41.0), ("uranium-238", 2.0)],
+    ),
+    (
+        "uranium-fuel-cell",
+        "crafting",
+        10.0,
+        &[
+            ("iron-plate", 10.0),
+            ("uranium-235", 1.0),
+            ("uranium-238", 19.0),
+        ],
+        &[("uranium-fuel-cell", 10.0)],
+    ),
+    // Logistics and military
+    (
+        "transport-belt",
+        "crafting",
+        0.5,
+        &[("iron-plate", 1.0), ("iron-gear-wheel", 1.0)],
+        &[("transport-belt", 2.0)],
+    ),
+    (
+        "inserter",
+        "crafting",
+        0.5,
+        &[
+            ("electronic-circuit", 1.0),
+            ("iron-gear-wheel", 1.0),
+            ("iron-plate", 1.0),
+        ],
+        &[("inserter", 1.0)],
+    ),
+    (
+        "rail",
+        "crafting",
+        0.5,
+        &[("stone", 1.0), ("iron-stick", 1.0), ("steel-plate", 1.0)],
+        &[("rail", 2.0)],
+    ),
+    (
+        "stone-wall",
+        "crafting",
+        0.5,
+        &[("stone-brick", 5.0)],
+        &[("stone-wall", 1.0)],
+    ),
+    (
+        "firearm-magazine",
+        "crafting",
+        1.0,
+        &[("iron-plate", 4.0)],
+        &[("firearm-magazine", 1.0)],
+    ),
+    (
+        "piercing-rounds-magazine",
+        "crafting",
+        6.0,
+        &[
+            ("firearm-magazine", 1.0),
+            ("steel-plate", 1.0),
+            ("copper-plate", 5.0),
+        ],
+        &[("piercing-rounds-magazine", 1.0)],
+    ),
+    (
+        "grenade",
+        "crafting",
+        8.0,
+        &[("iron-plate", 5.0), ("coal", 10.0)],
+        &[("grenade", 1.0)],
+    ),
+    // Production
+    (
+        "electric-furnace",
+        "crafting",
+        5.0,
+        &[
+            ("steel-plate", 10.0),
+            ("advanced-circuit", 5.0),
+            ("stone-brick", 10.0),
+        ],
+        &[("electric-furnace", 1.0)],
+    ),
+    (
+        "solar-panel",
+        "crafting",
+        10.0,
+        &[
+            ("steel-plate", 5.0),
+            ("electronic-circuit", 15.0),
+            ("copper-plate", 5.0),
+        ],
+        &[("solar-panel", 1.0)],
+    ),
+    (
+        "accumulator",
+        "crafting",
+        10.0,
+        &[("iron-plate", 2.0), ("battery", 5.0)],
+        &[("accumulator", 1.0)],
+    ),
+    (
+        "speed-module",
+        "crafting",
+        15.0,
+        &[("advanced-circuit", 5.0), ("electronic-circuit", 5.0)],
+        &[("speed-module", 1.0)],
+    ),
+    (
+        "efficiency-module",
+        "crafting",
+        15.0,
+        &[("advanced-circuit", 5.0), ("electronic-circuit", 5.0)],
+        &[("efficiency-module", 1.0)],
+    ),
+    (
+        "productivity-module",
+        "crafting",
+        15.0,
+        &[("advanced-circuit", 5.0), ("electronic-circuit", 5.0)],
+        &[("productivity-module", 1.0)],
+    ),
+    // Buildings
+    (
+        "wooden-chest",
+        "crafting",
+        0.5,
+        &[("wood", 2.0)],
+        &[("wooden-chest", 1.0)],
+    ),
+    (
+        "iron-chest",
+        "crafting",
+        0.5,
+        &[("iron-plate", 8.0)],
+        &[("iron-chest", 1.0)],
+    ),
+    (
+        "steel-chest",
+        "crafting",
+        0.5,
+        &[("steel-plate", 8.0)],
+        &[("steel-chest", 1.0)],
+    ),
+    (
+        "storage-tank",
+        "crafting",
+        3.0,
+        &[("iron-plate", 20.0), ("steel-plate", 5.0)],
+        &[("storage-tank", 1.0)],
+    ),
+    (
+        "burner-inserter",
+        "crafting",
+        0.5,
+        &[("iron-plate", 1.0), ("iron-gear-wheel", 1.0)],
+        &[("burner-inserter", 1.0)],
+    ),
+    (
+        "long-handed-inserter",
+        "crafting",
+        0.5,
+        &[
+            ("iron-gear-wheel", 1.0),
+            ("iron-plate", 1.0),
+            ("inserter", 1.0),
+        ],
+        &[("long-handed-inserter", 1.0)],
+    ),
+    (
+        "fast-inserter",
+        "crafting",
+        0.5,
+        &[
+            ("electronic-circuit", 2.0),
+            ("iron-plate", 2.0),
+            ("inserter", 1.0),
+        ],
+        &[("fast-inserter", 1.0)],
+    ),
+    (
+        "fast-transport-belt",
+        "crafting",
+        0.5,
+        &[("iron-gear-wheel", 5.0), ("transport-belt", 1.0)],
+        &[("fast-transport-belt", 1.0)],
+    ),
+    (
+        "underground-belt",
+        "crafting",
+        1.0,
+        &[("iron-plate", 10.0), ("transport-belt", 5.0)],
+        &[("underground-belt", 2.0)],
+    ),
+    (
+        "splitter",
+        "crafting",
+        1.0,
+        &[
+            ("electronic-circuit", 5.0),
+            ("iron-plate", 5.0),
+            ("transport-belt", 4.0),
+        ],
+        &[("splitter", 1.0)],
+    ),
+    (
+        "pipe-to-ground",
+        "crafting",
+        0.5,
+        &[("pipe", 10.0), ("iron-plate", 5.0)],
+        &[("pipe-to-ground", 2.0)],
+    ),
+    (
+        "pump",
+        "crafting",
+        2.0,
+        &[("engine-unit", 1.0), ("steel-plate", 1.0), ("pipe", 1.0)],
+        &[("pump", 1.0)],
+    ),
+    (
+        "offshore-pump",
+        "crafting",
+        0.5,
+        &[("pipe", 3.0), ("iron-gear-wheel", 2.0)],
+        &[("offshore-pump", 1.0)],
+    ),
+    (
+        "small-electric-pole",
+        "crafting",
+        0.5,
+        &[("wood", 1.0), ("copper-cable", 2.0)],
+        &[("small-electric-pole", 2.0)],
+    ),
+    (
+        "medium-electric-pole",
+        "crafting",
+        0.5,
+        &[
+            ("iron-stick", 4.0),
+            ("steel-plate", 2.0),
+            ("copper-plate", 2.0),
+        ],
+        &[("medium-electric-pole", 1.0)],
+    ),
+    (
+        "big-electric-pole",
+        "crafting",
+        0.5,
+        &[
+            ("iron-stick", 8.0),
+            ("steel-plate", 5.0),
+            ("copper-plate", 5.0),
+        ],
+        &[("big-electric-pole", 1.0)],
+    ),
+    (
+        "substation",
+        "crafting",
+        0.5,
+        &[
+            ("steel-plate", 10.0),
+            ("advanced-circuit", 5.0),
+            ("copper-plate", 10.0),
+        ],
+        &[("substation", 1.0)],
+    ),
+    (
+        "boiler",
+        "crafting",
+        0.5,
+        &[("stone-furnace", 1.0), ("pipe", 4.0)],
+        &[("boiler", 1.0)],
+    ),
+    (
+        "steam-engine",
+        "crafting",
+        0.5,
+        &[
+            ("iron-gear-wheel", 8.0),
+            ("pipe", 5.0),
+            ("iron-plate", 10.0),
+        ],
+        &[("steam-engine", 1.0)],
+    ),
+    (
+        "stone-furnace",
+        "crafting",
+        0.5,
+        &[("stone", 5.0)],
+        &[("stone-furnace", 1.0)],
+    ),
+    (
+        "steel-furnace",
+        "crafting",
+        3.0,
+        &[("steel-plate", 6.0), ("stone-brick", 10.0)],
+        &[("steel-furnace", 1.0)],
+    ),
+    (
+        "electric-mining-drill",
+        "crafting",
+        2.0,
+        &[
+            ("electronic-circuit", 3.0),
+            ("iron-gear-wheel", 5.0),
+            ("iron-plate", 10.0),
+        ],
+        &[("electric-mining-drill", 1.0)],
+    ),
+    (
+        "pumpjack",
+        "crafting",
+        5.0,
+        &[
+            ("steel-plate", 5.0),
+            ("iron-gear-wheel", 10.0),
+            ("electronic-circuit", 5.0),
+            ("pipe", 10.0),
+        ],
+        &[("pumpjack", 1.0)],
+    ),
+    (
+        "assembling-machine-1",
+        "crafting",
+        0.5,
+        &[
+            ("electronic-circuit", 3.0),
+            ("iron-gear-wheel", 5.0),
+            ("iron-plate", 9.0),
+        ],
+        &[("assembling-machine-1", 1.0)],
+    ),
+    (
+        "assembling-machine-2",
+        "crafting",
+        0.5,
+        &[
+            ("steel-plate", 2.0),
+            ("electronic-circuit", 3.0),
+            ("iron-gear-wheel", 5.0),
+            ("assembling-machine-1", 1.0),
+        ],
+        &[("assembling-machine-2", 1.0)],
+    ),
+    (
+        "assembling-machine-3",
+        "crafting",
+        0.5,
+        &[("speed-module", 4.0), ("assembling-machine-2", 2.0)],
+        &[("assembling-machine-3", 1.0)],
+    ),
+    (
+        "chemical-plant",
+        "crafting",
+        5.0,
+        &[
+            ("steel-plate", 5.0),
+            ("iron-gear-wheel", 5.0),
+            ("electronic-circuit", 5.0),
+            ("pipe", 5.0),
+        ],
+        &[("chemical-plant", 1.0)],
+    ),
+    (
+        "oil-refinery",
+        "crafting",
+        8.0,
+        &[
+            ("steel-plate", 15.0),
+            ("iron-gear-wheel", 10.0),
+            ("stone-brick", 10.0),
+            ("electronic-circuit", 10.0),
+            ("pipe", 10.0),
+        ],
+        &[("oil-refinery", 1.0)],
+    ),
+    (
+        "lab",
+        "crafting",
+        2.0,
+        &[
+            ("electronic-circuit", 10.0),
+            ("iron-gear-wheel", 10.0),
+            ("transport-belt", 4.0),
+        ],
+        &[("lab", 1.0)],
+    ),
+    (
+        "beacon",
+        "crafting",
+        15.0,
+        &[
+            ("electronic-circuit", 20.0),
+            ("advanced-circuit", 20.0),
+            ("steel-plate", 10.0),
+            ("copper-cable", 10.0),
+        ],
+        &[("beacon", 1.0)],
+    ),
+    (
+        "radar",
+        "crafting",
+        0.5,
+        &[
+            ("electronic-circuit", 5.0),
+            ("iron-gear-wheel", 5.0),
+            ("iron-plate", 10.0),
+        ],
+        &[("radar", 1.0)],
+    ),
+    (
+        "roboport",
+        "crafting",
+        5.0,
+        &[
+            ("steel-plate", 45.0),
+            ("iron-gear-wheel", 45.0),
+            ("advanced-circuit", 45.0),
+        ],
+        &[("roboport", 1.0)],
+    ),
+    (
+        "small-lamp",
+        "crafting",
+        0.5,
+        &[
+            ("electronic-circuit", 1.0),
+            ("copper-cable", 3.0),
+            ("iron-plate", 1.0),
+        ],
+        &[("small-lamp", 1.0)],
+    ),
+    (
+        "arithmetic-combinator",
+        "crafting",
+        0.5,
+        &[("copper-cable", 5.0), ("electronic-circuit", 5.0)],
+        &[("arithmetic-combinator", 1.0)],
+    ),
+    (
+        "decider-combinator",
+        "crafting",
+        0.5,
+        &[("copper-cable", 5.0), ("electronic-circuit", 5.0)],
+        &[("decider-combinator", 1.0)],
+    ),
+    (
+        "constant-combinator",
+        "crafting",
+        0.5,
+        &[("copper-cable", 5.0), ("electronic-circuit", 2.0)],
+        &[("constant-combinator", 1.0)],
+    ),
+    (
+        "train-stop",
+        "crafting",
+        0.5,
+        &[
+            ("electronic-circuit", 5.0),
+            ("iron-plate", 6.0),
+            ("iron-stick", 6.0),
+            ("steel-plate", 3.0),
+        ],
+        &[("train-stop", 1.0)],
+    ),
+    (
+        "rail-signal",
+        "crafting",
+        0.5,
+        &[("electronic-circuit", 1.0), ("iron-plate", 5.0)],
+        &[("rail-signal", 1.0)],
+    ),
+    (
+        "rail-chain-signal",
+        "crafting",
+        0.5,
+        &[("electronic-circuit", 1.0), ("iron-plate", 5.0)],
+        &[("rail-chain-signal", 1.0)],
+    ),
+    (
+        "gate",
+        "crafting",
+        0.5,
+        &[
+            ("stone-wall", 1.0),
+            ("steel-plate", 2.0),
+            ("electronic-circuit", 2.0),
+        ],
+        &[("gate", 1.0)],
+    ),
+    (
+        "landfill",
+        "crafting",
+        0.5,
+        &[("stone", 50.0)],
+        &[("landfill", 1.0)],
+    ),
+    (
+        "concrete",
+        "crafting-with-fluid",
+        10.0,
+        &[("stone-brick", 5.0), ("iron-ore", 1.0), ("water", 100.0)],
+        &[("concrete", 10.0)],
+    ),
+    // Science
+    (
+        "automation-science-pack",
+        "crafting",
+        5.0,
+        &[("copper-plate", 1.0), ("iron-gear-wheel", 1.0)],
+        &[("automation-science-pack", 1.0)],
+    ),
+    (
+        "logistic-science-pack",
+        "crafting",
+        6.0,
+        &[("inserter", 1.0), ("transport-belt", 1.0)],
+        &[("logistic-science-pack", 1.0)],
+    ),
+    (
+        "military-science-pack",
+        "crafting",
+        10.0,
+        &[
+            ("piercing-rounds-magazine", 1.0),
+            ("grenade", 1.0),
+            ("stone-wall", 2.0),
+        ],
+        &[("military-science-pack", 2.0)],
+    ),
+    (
+        "chemical-science-pack",
+        "crafting",
+        24.0,
+        &[
+            ("engine-unit", 2.0),
+            ("advanced-circuit", 3.0),
+            ("sulfur", 1.0),
+        ],
+        &[("chemical-science-pack", 2.0)],
+    ),
+    (
+        "production-science-pack",
+        "crafting",
+        21.0,
+        &[
+            ("electric-furnace", 1.0),
+            ("productivity-module", 1.0),
+            ("rail", 30.0),
+        ],
+        &[("production-science-pack", 3.0)],
+    ),
+    (
+        "utility-science-pack",
+        "crafting",
+        21.0,
+        &[
+            ("processing-unit", 2.0),
+            ("flying-robot-frame", 1.0),
+            ("low-density-structure", 3.0),
+        ],
+        &[("utility-science-pack", 3.0)],
+    ),
+];
+
+/// Recipe products made by only some crafts, with the chance of each craft making them.
+const PROBABILITIES: &[(&str, &str, f64)] = &[
+    ("uranium-processing", "uranium-235", 0.007),
+    ("uranium-processing", "uranium-238", 0.993),
+];
+
+/// Recipes that productivity modules and bonuses apply to: intermediate products and science
+/// packs.
+const PRODUCTIVITY_RECIPES: &[&str] = &[
+    "iron-plate",
+    "copper-plate",
+    "stone-brick",
+    "steel-plate",
+    "iron-gear-wheel",
+    "iron-stick",
+    "copper-cable",
+    "electronic-circuit",
+    "advanced-circuit",
+    "processing-unit",
+    "engine-unit",
+    "electric-engine-unit",
+    "flying-robot-frame",
+    "low-density-structure",
+    "battery",
+    "rocket-fuel",
+    "basic-oil-processing",
+    "advanced-oil-processing",
+    "coal-liquefaction",
+    "heavy-oil-cracking",
+    "light-oil-cracking",
+    "lubricant",
+    "solid-fuel-from-light-oil",
+    "solid-fuel-from-petroleum-gas",
+    "plastic-bar",
+    "sulfur",
+    "sulfuric-acid",
+    "uranium-processing",
+    "automation-science-pack",
+    "logistic-science-pack",
+    "military-science-pack",
+    "chemical-science-pack",
+    "production-science-pack",
+    "utility-science-pack",
+];
+
+/// A fluid box, for the table below: what it holds, the fluid it is fixed to if any, and its
+/// connections when facing north.
+type FluidBoxSpec = (
+    ProductionType,
+    Option<&'static str>,
+    &'static [PipeConnection],
+);
+
+/// Entities with fluid boxes, and their fluid boxes.
+const FLUID_BOXES: &[(&str, &[FluidBoxSpec])] = &[
+    (
+        "pipe",
+        &[(
+            ProductionType::None,
+            None,
+            &[
+                pipe(0.0, 0.0, Direction::North),
+                pipe(0.0, 0.0, Direction::East),
+                pipe(0.0, 0.0, Direction::South),
+                pipe(0.0, 0.0, Direction::West),
+            ],
+        )],
+    ),
+    (
+        "infinity-pipe",
+        &[(
+            ProductionType::None,
+            None,
+            &[
+                pipe(0.0, 0.0, Direction::North),
+                pipe(0.0, 0.0, Direction::East),
+                pipe(0.0, 0.0, Direction::South),
+                pipe(0.0, 0.0, Direction::West),
+            ],
+        )],
+    ),
+    (
+        "pipe-to-ground",
+        &[(
+            ProductionType::None,
+            None,
+            &[
+                pipe(0.0, 0.0, Direction::North),
+                underground(0.0, 0.0, Direction::South, 10),
+            ],
+        )],
+    ),
+    (
+        "pump",
+        &[(
+            ProductionType::None,
+            None,
+            &[
+                pipe(0.0, -0.5, Direction::North),
+                pipe(0.0, 0.5, Direction::South),
+            ],
+        )],
+    ),
+    (
+        "offshore-pump",
+        &[(
+            ProductionType::None,
+            Some("water"),
+            &[pipe(0.0, -0.5, Direction::North)],
+        )],
+    ),
+    (
+        "storage-tank",
+        &[(
+            ProductionType::None,
+            None,
+            &[
+                pipe(-1.0, -1.0, Direction::North),
+                pipe(-1.0, -1.0, Direction::West),
+                pipe(1.0, 1.0, Direction::East),
+                pipe(1.0, 1.0, Direction::South),
+            ],
+        )],
+    ),
+    (
+        "boiler",
+        &[
+            (
+                ProductionType::None,
+                Some("water"),
+                &[
+                    pipe(-1.0, 0.5, Direction::West),
+                    pipe(1.0, 0.5, Direction::East),
+                ],
+            ),
+            (
+                ProductionType::None,
+                Some("steam"),
+                &[pipe(0.0, -0.5, Direction::North)],
+            ),
+        ],
+    ),
+    (
+        "heat-exchanger",
+        &[
+            (
+                ProductionType::None,
+                Some("water"),
+                &[
+                    pipe(-1.0, 0.5, Direction::West),
+                    pipe(1.0, 0.5, Direction::East),
+                ],
+            ),
+            (
+                ProductionType::None,
+                Some("steam"),
+                &[pipe(0.0, -0.5, Direction::North)],
+            ),
+        ],
+    ),
+    (
+        "steam-engine",
+        &[(
+            ProductionType::None,
+            Some("steam"),
+            &[
+                pipe(0.0, -2.0, Direction::North),
+                pipe(0.0, 2.0, Direction::South),
+            ],
+        )],
+    ),
+    (
+        "steam-turbine",
+        &[(
+            ProductionType::None,
+            Some("steam"),
+            &[
+                pipe(0.0, -2.0, Direction::North),
+                pipe(0.0, 2.0, Direction::South),
+            ],
+        )],
+    ),
+    ("assembling-machine-2", ASSEMBLING_FLUID_BOXES),
+    ("assembling-machine-3", ASSEMBLING_FLUID_BOXES),
+    (
+        "chemical-plant",
+        &[
+            (
+                ProductionType::Input,
+                None,
+                &[pipe(-1.0, -1.0, Direction::North)],
+            ),
+            (
+                ProductionType::Input,
+                None,
+                &[pipe(1.0, -1.0, Direction::North)],
+            ),
+            (
+                ProductionType::Output,
+                None,
+                &[pipe(-1.0, 1.0, Direction::South)],
+            ),
+            (
+                ProductionType::Output,
+                None,
+                &[pipe(1.0, 1.0, Direction::South)],
+            ),
+        ],
+    ),
+    (
+        "oil-refinery",
+        &[
+            (
+                ProductionType::Input,
+                None,
+                &[pipe(-1.0, 2.0, Direction::South)],
+            ),
+            (
+                ProductionType::Input,
+                None,
+                &[pipe(1.0, 2.0, Direction::South)],
+            ),
+            (
+                ProductionType::Output,
+                None,
+                &[pipe(-2.0, -2.0, Direction::North)],
+            ),
+            (
+                ProductionType::Output,
+                None,
+                &[pipe(0.0, -2.0, Direction::North)],
+            ),
+            (
+                ProductionType::Output,
+                None,
+                &[pipe(2.0, -2.0, Direction::North)],
+            ),
+        ],
+    ),
+];
+
+/// The fluid boxes of assembling machines that can craft with fluids.
+const ASSEMBLING_FLUID_BOXES: &[FluidBoxSpec] = &[
+    (
+        ProductionType::Input,
+        None,
+        &[pipe(0.0, -1.0, Direction::North)],
+    ),
+    (
+        ProductionType::Output,
+        None,
+        &[pipe(0.0, 1.0, Direction::South)],
+    ),
+];
+
+/// Entities with heat buffers, and where they connect when facing north.
+const HEAT_CONNECTIONS: &[(&str, &[PipeConnection])] = &[
+    (
+        "heat-pipe",
+        &[
+            pipe(0.0, 0.0, Direction::North),
+            pipe(0.0, 0.0, Direction::East),
+            pipe(0.0, 0.0, Direction::South),
+            pipe(0.0, 0.0, Direction::West),
+        ],
+    ),
+    (
+        "nuclear-reactor",
+        &[
+            pipe(-2.0, -2.0, Direction::North),
+            pipe(0.0, -2.0, Direction::North),
+            pipe(2.0, -2.0, Direction::North),
+            pipe(2.0, -2.0, Direction::East),
+            pipe(2.0, 0.0, Direction::East),
+            pipe(2.0, 2.0, Direction::East),
+            pipe(2.0, 2.0, Direction::South),
+            pipe(0.0, 2.0, Direction::South),
+            pipe(-2.0, 2.0, Direction::South),
+            pipe(-2.0, 2.0, Direction::West),
+            pipe(-2.0, 0.0, Direction::West),
+            pipe(-2.0, -2.0, Direction::West),
+        ],
+    ),
+    ("heat-exchanger", &[pipe(0.0, 0.5, Direction::South)]),
+    (
+        "heating-tower",
+        &[
+            pipe(0.0, -1.0, Direction::North),
+            pipe(1.0, 0.0, Direction::East),
+            pipe(0.0, 1.0, Direction::South),
+            pipe(-1.0, 0.0, Direction::West),
+        ],
+    ),
+];
+
+/// A connection to the adjacent tile, for the tables above.
+const fn pipe(x: f64, y: f64, direction: Direction) -> PipeConnection {
+    PipeConnection {
+        x,
+        y,
+        direction,
+        underground_distance: None,
+    }
+}
+
+/// An underground connection, for the tables above.
+const fn underground(x: f64, y: f64, direction: Direction, distance: u32) -> PipeConnection {
+    PipeConnection {
+        x,
+        y,
+        direction,
+        underground_distance: Some(distance),
+    }
+}
+
+/// Modules and their effects. The efficiency modules were called effectivity modules before 2.0.
+const MODULES: &[(&str, Effect)] = &[
+    ("speed-module", module(0.5, 0.2, 0.0, 0.0, -0.01)),
+    ("speed-module-2", module(0.6, 0.3, 0.0, 0.0, -0.015)),
+    ("speed-module-3", module(0.7, 0.5, 0.0, 0.0, -0.025)),
+    ("efficiency-module", module(-0.3, 0.0, 0.0, 0.0, 0.0)),
+    ("efficiency-module-2", module(-0.4, 0.0, 0.0, 0.0, 0.0)),
+    ("efficiency-module-3", module(-0.5, 0.0, 0.0, 0.0, 0.0)),
+    ("effectivity-module", module(-0.3, 0.0, 0.0, 0.0, 0.0)),
+    ("effectivity-module-2", module(-0.4, 0.0, 0.0, 0.0, 0.0)),
+    ("effectivity-module-3", module(-0.5, 0.0, 0.0, 0.0, 0.0)),
+    ("productivity-module", module(0.4, -0.05, 0.04, 0.05, 0.0)),
+    ("productivity-module-2", module(0.6, -0.1, 0.06, 0.07, 0.0)),
+    ("productivity-module-3", module(0.8, -0.15, 0.1, 0.1, 0.0)),
+    ("quality-module", module(0.0, -0.05, 0.0, 0.0, 0.01)),
+    ("quality-module-2", module(0.0, -0.05, 0.0, 0.0, 0.02)),
+    ("quality-module-3", module(0.0, -0.05, 0.0, 0.0, 0.025)),
+];
+
+/// A module effect, for the table above.
+const fn module(
+    consumption: f64,
+    speed: f64,
+    productivity: f64,
+    pollution: f64,
+    quality: f64,
+) -> Effect {
+    Effect {
+        consumption,
+        speed,
+        productivity,
+        pollution,
+        quality,
+    }
+}
+
+pub(super) fn build() -> PrototypeDb {
+    let mut db = PrototypeDb::new();
+
+    for &(name, [min_x, min_y, max_x, max_y]) in COLLISION_BOXES {
+        db.insert_entity(EntityPrototype::new(
+            name,
+            BoundingBox::new(min_x, min_y, max_x, max_y),
+        ));
+    }
+    for &(name, width, height) in TILE_SIZES {
+        if let Some(prototype) = db.entities.get_mut(name) {
+            prototype.tile_width = width;
+            prototype.tile_height = height;
+        }
+    }
+    for name in ROTATABLE {
+        if let Some(prototype) = db.entities.get_mut(*name) {
+            prototype.rotatable = true;
+        }
+    }
+    for name in ELECTRIC {
+        if let Some(prototype) = db.entities.get_mut(*name) {
+            prototype.electric = true;
+        }
+    }
+    for &(name, wire_reach, supply_area_distance) in ELECTRIC_POLES {
+        if let Some(prototype) = db.entities.get_mut(name) {
+            prototype.electric_pole = Some(ElectricPole {
+                wire_reach,
+                supply_area_distance,
+            });
+        }
+    }
+    for &(name, energy_usage, drain) in ENERGY_USAGE {
+        if let Some(prototype) = db.entities.get_mut(name) {
+            prototype.energy_usage = energy_usage * 1e3;
+            prototype.drain = drain * 1e3;
+        }
+    }
+    for &(name, source) in POWER_SOURCES {
+        if let Some(prototype) = db.entities.get_mut(name) {
+            prototype.power_source = Some(source);
+        }
+    }
+    for &(name, boxes) in FLUID_BOXES {
+        if let Some(prototype) = db.entities.get_mut(name) {
+            prototype.fluid_boxes = boxes
+                .iter()
+                .map(|&(production_type, filter, connections)| FluidBox {
+                    connections: connections.to_vec(),
+                    production_type,
+                    filter: filter.map(str::to_owned),
+                })
+                .collect();
+        }
+    }
+    for &(name, connections) in HEAT_CONNECTIONS {
+        if let Some(prototype) = db.entities.get_mut(name) {
+            prototype.heat_connections = connections.to_vec();
+        }
+    }
+    for &(name, kind, speed) in BELTS {
+        if let Some(prototype) = db.entities.get_mut(name) {
+            prototype.transport_belt = Some(TransportBelt { kind, speed });
+        }
+    }
+    for &(name, pickup, drop, items_per_second) in INSERTERS {
+        if let Some(prototype) = db.entities.get_mut(name) {
+            prototype.inserter = Some(Inserter {
+                pickup: (0.0, -pickup),
+                drop: (0.0, drop),
+                items_per_second,
+            });
+        }
+    }
+    for &(name, crafting_speed, base_productivity, categories) in CRAFTING_MACHINES {
+        if let Some(prototype) = db.entities.get_mut(name) {
+            prototype.crafting_machine = Some(CraftingMachine {
+                crafting_speed,
+                base_productivity,
+                crafting_categories: categories.iter().map(|&c| c.to_owned()).collect(),
+            });
+        }
+    }
+    for &(name, distribution_effectivity, supply_area_distance) in BEACONS {
+        if let Some(prototype) = db.entities.get_mut(name) {
+            prototype.beacon = Some(Beacon {
+                distribution_effectivity,
+                supply_area_distance,
+            });
+        }
+    }
+    for &(name, researching_speed) in LABS {
+        if let Some(prototype) = db.entities.get_mut(name) {
+            prototype.lab = Some(Lab {
+                researching_speed,
+                inputs: SCIENCE_PACKS.iter().map(|&pack| pack.to_owned()).collect(),
+            });
+        }
+    }
+    for &(name, effect) in MODULES {
+        db.insert_module(ModulePrototype {
+            name: name.to_owned(),
+            effect,
+        });
+    }
+    let amounts = |amounts: &[(&str, f64)]| -> Vec<(String, f64)> {
+        amounts
+            .iter()
+            .map(|&(name, amount)| (name.to_owned(), amount))
+            .collect()
+    };
+    for name in FLUIDS {
+        db.insert_fluid(*name);
+    }
+    for &(name, item, count) in ITEMS_TO_PLACE {
+        db.insert_item_to_place(name, item, count);
+    }
+    for name in TILES {
+        db.insert_tile(*name);
+    }
+    for &(mod_name, names) in MODS {
+        for name in names {
+            db.insert_mod(*name, mod_name);
+        }
+    }
+    for &(name, category, energy_required, ingredients, products) in RECIPES {
+        db.insert_recipe(RecipePrototype {
+            name: name.to_owned(),
+            category: category.to_owned(),
+            energy_required,
+            ingredients: amounts(ingredients),
+            products: amounts(products),
+            probabilities: PROBABILITIES
+                .iter()
+                .filter(|&&(recipe, _, _)| recipe == name)
+                .map(|&(_, product, probability)| (product.to_owned(), probability))
+                .collect(),
+            allow_productivity: PRODUCTIVITY_RECIPES.contains(&name),
+        });
+    }
+
+    db
+}
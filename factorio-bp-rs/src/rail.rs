@@ -0,0 +1,538 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::blueprint::{Blueprint, Direction, Entity, EntityDetails};
+
+/// The offsets of a rail's two ends from its position, and the ways the track leads out of it.
+type EndOffsets = [((i64, i64), Direction); 2];
+
+/// The ends of a straight rail facing each way before 2.0, by direction in eighths of a turn, as
+/// offsets from its position and the way the track leads out of it there. Straight rails facing
+/// north or east run across their 2x2 cell, and diagonal ones cut across a corner of it.
+const STRAIGHT_ENDS: [EndOffsets; 8] = [
+    [((0, -1), Direction::North), ((0, 1), Direction::South)],
+    [
+        ((0, -1), Direction::NorthWest),
+        ((1, 0), Direction::SouthEast),
+    ],
+    [((-1, 0), Direction::West), ((1, 0), Direction::East)],
+    [
+        ((1, 0), Direction::NorthEast),
+        ((0, 1), Direction::SouthWest),
+    ],
+    [((0, -1), Direction::North), ((0, 1), Direction::South)],
+    [
+        ((0, 1), Direction::SouthEast),
+        ((-1, 0), Direction::NorthWest),
+    ],
+    [((-1, 0), Direction::West), ((1, 0), Direction::East)],
+    [
+        ((-1, 0), Direction::SouthWest),
+        ((0, -1), Direction::NorthEast),
+    ],
+];
+
+/// The ends of a curved rail facing each way before 2.0, as for [STRAIGHT_ENDS]. Each curve
+/// turns an eighth of a turn, from a straight end to a diagonal one three tiles across and seven
+/// along, and odd directions mirror the even ones before them.
+const CURVED_ENDS: [EndOffsets; 8] = [
+    [((1, 4), Direction::South), ((-2, -3), Direction::NorthWest)],
+    [((-1, 4), Direction::South), ((2, -3), Direction::NorthEast)],
+    [((-4, 1), Direction::West), ((3, -2), Direction::NorthEast)],
+    [((-4, -1), Direction::West), ((3, 2), Direction::SouthEast)],
+    [((-1, -4), Direction::North), ((2, 3), Direction::SouthEast)],
+    [((1, -4), Direction::North), ((-2, 3), Direction::SouthWest)],
+    [((4, -1), Direction::East), ((-3, 2), Direction::SouthWest)],
+    [((4, 1), Direction::East), ((-3, -2), Direction::NorthWest)],
+];
+
+/// The ends of a straight rail of 2.0 facing each way, by direction in eighths of a turn, as
+/// for [STRAIGHT_ENDS]. These run through the middle of their 2x2 cell, and diagonal ones from
+/// corner to corner. Directions a half turn apart give the same rail.
+const STRAIGHT_ENDS_2_0: [EndOffsets; 4] = [
+    [((0, -1), Direction::North), ((0, 1), Direction::South)],
+    [
+        ((1, -1), Direction::NorthEast),
+        ((-1, 1), Direction::SouthWest),
+    ],
+    [((-1, 0), Direction::West), ((1, 0), Direction::East)],
+    [
+        ((1, 1), Direction::SouthEast),
+        ((-1, -1), Direction::NorthWest),
+    ],
+];
+
+/// How far a signal can be from the joint between rails it sits at, in tiles.
+const SIGNAL_REACH: f64 = 2.0;
+/// How far a train stop can be from the centre of the straight rail it stands beside, in tiles.
+const STOP_REACH: f64 = 2.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+/// The shape of a rail.
+pub enum RailKind {
+    /// A straight rail, along or diagonal to the grid.
+    Straight,
+    /// A curved rail, joining a straight rail along the grid to a diagonal one.
+    Curved,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+/// One end of a rail.
+pub struct RailEnd {
+    /// Where the end is. Ends are on the edges between the 2x2 cells rails are built on, so the
+    /// position is whole tiles.
+    pub point: (i64, i64),
+    /// The way the track leads out of the rail at this end.
+    pub direction: Direction,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A rail of a blueprint, and where its ends are.
+pub struct Rail {
+    /// The entity_number of the rail.
+    pub entity_number: usize,
+    /// Its shape.
+    pub kind: RailKind,
+    /// Its two ends, in no particular order.
+    pub ends: [RailEnd; 2],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+/// An end of a particular rail.
+pub struct RailEndId {
+    /// The entity_number of the rail.
+    pub entity_number: usize,
+    /// Which of its [ends](Rail::ends).
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A rail signal or chain signal, and the joint between rails it sits at.
+pub struct RailSignal {
+    /// The entity_number of the signal.
+    pub entity_number: usize,
+    /// Whether it is a chain signal.
+    pub chain: bool,
+    /// The point where the rails it splits meet.
+    pub joint: (i64, i64),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A train stop, and the rail it stands beside.
+pub struct TrainStop {
+    /// The entity_number of the train stop.
+    pub entity_number: usize,
+    /// The entity_number of the rail trains stop on.
+    pub rail: usize,
+    /// The name of the station, if set.
+    pub station: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Rails joined to each other, directly or through other rails, regardless of signals.
+pub struct RailNetwork {
+    /// The entity_numbers of the rails, in order.
+    pub rails: Vec<usize>,
+}
+
+impl RailNetwork {
+    /// Whether the rail is part of the network.
+    pub fn contains(&self, entity_number: usize) -> bool {
+        self.rails.binary_search(&entity_number).is_ok()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Rails joined to each other without a signal between them, which only one train can be on
+/// at a time.
+pub struct Block {
+    /// The entity_numbers of the rails, in order.
+    pub rails: Vec<usize>,
+    /// The entity_numbers of the signals at the block's edges, in order.
+    pub signals: Vec<usize>,
+    /// The entity_numbers of the train stops beside its rails, in order.
+    pub stops: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// The rails of a blueprint, as a graph joined where their ends meet, with the signals and
+/// train stops along them.
+///
+/// Two rails join when they have ends at the same point leading out opposite ways. The rails
+/// from before 2.0 are understood: `straight-rail` and `curved-rail` in blueprints from earlier
+/// versions, and `legacy-straight-rail` and `legacy-curved-rail` in later ones. Of the rails of
+/// 2.0 only `straight-rail` is, and the curved, half diagonal, and elevated ones are left
+/// [unsupported](RailGraph::unsupported).
+pub struct RailGraph {
+    rails: BTreeMap<usize, Rail>,
+    adjacent: BTreeMap<RailEndId, Vec<RailEndId>>,
+    signals: Vec<RailSignal>,
+    stops: Vec<TrainStop>,
+    unsupported: Vec<usize>,
+}
+
+/// The shape of `entity` and the offsets of its ends from its position, if it is a rail whose
+/// shape is understood.
+fn rail_ends(entity: &Entity, legacy_names: bool) -> Option<(RailKind, EndOffsets)> {
+    let direction = entity.direction.unwrap_or_default();
+    let legacy = |kind, table: &[_; 8]| Some((kind, table[direction.to_legacy()? as usize]));
+    match entity.name.as_str() {
+        "legacy-straight-rail" => legacy(RailKind::Straight, &STRAIGHT_ENDS),
+        "legacy-curved-rail" => legacy(RailKind::Curved, &CURVED_ENDS),
+        "straight-rail" if legacy_names => legacy(RailKind::Straight, &STRAIGHT_ENDS),
+        "curved-rail" if legacy_names => legacy(RailKind::Curved, &CURVED_ENDS),
+        // Straight rails of 2.0 only face the eight directions
+        "straight-rail" if direction.sixteenths().is_multiple_of(2) => Some((
+            RailKind::Straight,
+            STRAIGHT_ENDS_2_0[(direction.sixteenths() / 2 % 4) as usize],
+        )),
+        _ => None,
+    }
+}
+
+/// Whether `entity` is any kind of rail, including the 2.0 ones and elevated rails.
+fn is_rail(entity: &Entity) -> bool {
+    let name = entity.name.as_str();
+    ["-rail", "-rail-a", "-rail-b"]
+        .iter()
+        .any(|suffix| name.ends_with(suffix))
+        || name == "rail-ramp"
+}
+
+/// Whether `blueprint` has rails, rail signals, or train stops, which the game only builds on
+/// the grid of 2x2 cells rails are on. Moving such a blueprint by an odd number of tiles moves
+/// them off it.
+pub(crate) fn uses_rail_grid(blueprint: &Blueprint) -> bool {
+    blueprint.entities.iter().flatten().any(|entity| {
+        is_rail(entity)
+            || matches!(
+                entity.name.as_str(),
+                "rail-signal" | "rail-chain-signal" | "train-stop"
+            )
+    })
+}
+
+impl RailGraph {
+    /// The rails of `blueprint`, with its signals and train stops.
+    ///
+    /// A signal is taken to split every rail meeting at the joint nearest it, and a train stop
+    /// to stand beside the nearest straight rail.
+    pub fn new(blueprint: &Blueprint) -> Self {
+        let mut graph = Self::default();
+        let legacy_names = !blueprint.version.is_at_least(2, 0, 0);
+        let entities = blueprint.entities.iter().flatten();
+
+        let mut at_point: BTreeMap<(i64, i64), Vec<RailEndId>> = BTreeMap::new();
+        for entity in entities.clone() {
+            let number = entity.entity_number.get();
+            let Some((kind, offsets)) = rail_ends(entity, legacy_names) else {
+                if is_rail(entity) {
+                    graph.unsupported.push(number);
+                }
+                continue;
+            };
+            let (x, y) = (
+                entity.position.x.round() as i64,
+                entity.position.y.round() as i64,
+            );
+            let ends = offsets.map(|((dx, dy), direction)| RailEnd {
+                point: (x + dx, y + dy),
+                direction,
+            });
+            for (end, rail_end) in ends.iter().enumerate() {
+                at_point.entry(rail_end.point).or_default().push(RailEndId {
+                    entity_number: number,
+                    end,
+                });
+            }
+            graph.rails.insert(
+                number,
+                Rail {
+                    entity_number: number,
+                    kind,
+                    ends,
+                },
+            );
+        }
+        graph.unsupported.sort_unstable();
+
+        for ends in at_point.values() {
+            for &a in ends {
+                for &b in ends {
+                    let (end_a, end_b) = (graph.end(a), graph.end(b));
+                    if a.entity_number != b.entity_number
+                        && end_a.direction == end_b.direction.opposite()
+                    {
+                        graph.adjacent.entry(a).or_default().push(b);
+                    }
+                }
+            }
+        }
+
+        for entity in entities {
+            let number = entity.entity_number.get();
+            let (x, y) = (entity.position.x, entity.position.y);
+            let distance = |(px, py): (i64, i64)| (px as f64 - x).hypot(py as f64 - y);
+            match entity.name.as_str() {
+                "rail-signal" | "rail-chain-signal" => {
+                    let joint = at_point
+                        .keys()
+                        .map(|&point| (distance(point), point))
+                        .filter(|&(distance, _)| distance <= SIGNAL_REACH)
+                        .min_by(|a, b| a.0.total_cmp(&b.0));
+                    if let Some((_, joint)) = joint {
+                        graph.signals.push(RailSignal {
+                            entity_number: number,
+                            chain: entity.name == "rail-chain-signal",
+                            joint,
+                        });
+                    }
+                }
+                "train-stop" => {
+                    let centre = |rail: &Rail| {
+                        let [a, b] = rail.ends;
+                        (
+                            (a.point.0 + b.point.0) as f64 / 2.0,
+                            (a.point.1 + b.point.1) as f64 / 2.0,
+                        )
+                    };
+                    let rail = graph
+                        .rails
+                        .values()
+                        .filter(|rail| rail.kind == RailKind::Straight)
+                        .map(|rail| {
+                            let (cx, cy) = centre(rail);
+                            ((cx - x).hypot(cy - y), rail.entity_number)
+                        })
+                        .filter(|&(distance, _)| distance <= STOP_REACH)
+                        .min_by(|a, b| a.0.total_cmp(&b.0));
+                    if let Some((_, rail)) = rail {
+                        let station = match &entity.details {
+                            EntityDetails::TrainStop { station } => station.clone(),
+                            _ => None,
+                        };
+                        graph.stops.push(TrainStop {
+                            entity_number: number,
+                            rail,
+                            station,
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+        graph.signals.sort_by_key(|signal| signal.entity_number);
+        graph.stops.sort_by_key(|stop| stop.entity_number);
+
+        graph
+    }
+
+    /// The end `id` refers to, which must be of a rail in the graph.
+    fn end(&self, id: RailEndId) -> RailEnd {
+        self.rails[&id.entity_number].ends[id.end]
+    }
+
+    /// Every rail understood, in entity order.
+    pub fn rails(&self) -> impl Iterator<Item = &Rail> {
+        self.rails.values()
+    }
+
+    /// The rail with this entity_number, if it is one understood.
+    pub fn rail(&self, entity_number: usize) -> Option<&Rail> {
+        self.rails.get(&entity_number)
+    }
+
+    /// The rail ends joined to `end`. More than one means a switch.
+    pub fn connections(&self, end: RailEndId) -> &[RailEndId] {
+        self.adjacent.get(&end).map_or(&[], Vec::as_slice)
+    }
+
+    /// The rails joined to either end of the rail.
+    pub fn neighbors(&self, entity_number: usize) -> BTreeSet<usize> {
+        (0..2)
+            .flat_map(|end| self.connections(RailEndId { entity_number, end }))
+            .map(|other| other.entity_number)
+            .collect()
+    }
+
+    /// The rail signals and chain signals next to a joint between rails, in entity order.
+    pub fn signals(&self) -> &[RailSignal] {
+        &self.signals
+    }
+
+    /// The train stops beside a straight rail, in entity order.
+    pub fn stops(&self) -> &[TrainStop] {
+        &self.stops
+    }
+
+    /// The entity_numbers of rails whose shape isn't understood, like the curved rails of 2.0,
+    /// in order. They are part of no network or block.
+    pub fn unsupported(&self) -> &[usize] {
+        &self.unsupported
+    }
+
+    /// The network the rail is part of, or `None` if it isn't a rail understood.
+    pub fn network(&self, entity_number: usize) -> Option<RailNetwork> {
+        if !self.rails.contains_key(&entity_number) {
+            return None;
+        }
+
+        let mut seen = BTreeSet::from([entity_number]);
+        let mut queue = vec![entity_number];
+        while let Some(next) = queue.pop() {
+            for neighbor in self.neighbors(next) {
+                if seen.insert(neighbor) {
+                    queue.push(neighbor);
+                }
+            }
+        }
+        Some(RailNetwork {
+            rails: seen.into_iter().collect(),
+        })
+    }
+
+    /// Every network, ordered by their lowest rail.
+    pub fn networks(&self) -> Vec<RailNetwork> {
+        let mut seen = BTreeSet::new();
+        let mut networks = Vec::new();
+        for &entity_number in self.rails.keys() {
+            if seen.contains(&entity_number) {
+                continue;
+            }
+            if let Some(network) = self.network(entity_number) {
+                seen.extend(network.rails.iter().copied());
+                networks.push(network);
+            }
+        }
+        networks
+    }
+
+    /// Whether two rails are part of the same network.
+    pub fn connected(&self, a: usize, b: usize) -> bool {
+        self.network(a).is_some_and(|network| network.contains(b))
+    }
+
+    /// The rails split into blocks by the signals, ordered by their lowest rail.
+    pub fn blocks(&self) -> Vec<Block> {
+        let signalled: BTreeSet<(i64, i64)> =
+            self.signals.iter().map(|signal| signal.joint).collect();
+        self.unsignalled_sets(&signalled)
+            .into_iter()
+            .map(|rails| {
+                let points: BTreeSet<(i64, i64)> = rails
+                    .iter()
+                    .flat_map(|rail| self.rails[rail].ends.map(|end| end.point))
+                    .collect();
+                Block {
+                    signals: self
+                        .signals
+                        .iter()
+                        .filter(|signal| points.contains(&signal.joint))
+                        .map(|signal| signal.entity_number)
+                        .collect(),
+                    stops: self
+                        .stops
+                        .iter()
+                        .filter(|stop| rails.contains(&stop.rail))
+                        .map(|stop| stop.entity_number)
+                        .collect(),
+                    rails: rails.into_iter().collect(),
+                }
+            })
+            .collect()
+    }
+
+    /// The sets of rails joined at points without a signal, ordered by their lowest rail.
+    fn unsignalled_sets(&self, signalled: &BTreeSet<(i64, i64)>) -> Vec<BTreeSet<usize>> {
+        let mut seen = BTreeSet::new();
+        let mut sets = Vec::new();
+        for &start in self.rails.keys() {
+            if !seen.insert(start) {
+                continue;
+            }
+            let mut set = BTreeSet::from([start]);
+            let mut queue = vec![start];
+            while let Some(next) = queue.pop() {
+                for (end, rail_end) in self.rails[&next].ends.iter().enumerate() {
+                    if signalled.contains(&rail_end.point) {
+                        continue;
+                    }
+                    let id = RailEndId {
+                        entity_number: next,
+                        end,
+                    };
+                    for other in self.connections(id) {
+                        if seen.insert(other.entity_number) {
+                            set.insert(other.entity_number);
+                            queue.push(other.entity_number);
+                        }
+                    }
+                }
+            }
+            sets.push(set);
+        }
+        sets
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::num::NonZeroUsize;
+
+    use super::*;
+    use crate::{blueprint::Version, bp, prototypes::PrototypeDb};
+
+    #[test]
+    fn straight_rails_of_2_0_join_end_to_end() {
+        let mut blueprint = bp! {
+            "straight-rail" @ (0, 0);
+            "straight-rail" @ (0, 2);
+            "straight-rail" @ (4, 0) facing NorthEast;
+            "straight-rail" @ (6, -2) facing NorthEast;
+            "straight-rail" @ (10, 0) facing East;
+        }
+        .build_with(PrototypeDb::vanilla())
+        .unwrap();
+        // The prototypes don't know the curved rails of 2.0, so it can't be built
+        let entities = blueprint.entities.as_mut().unwrap();
+        let mut curve = entities[4].clone();
+        curve.entity_number = NonZeroUsize::new(6).unwrap();
+        curve.name = "curved-rail-a".to_owned();
+        curve.position.x += 4.0;
+        entities.push(curve);
+        let graph = RailGraph::new(&blueprint);
+
+        let networks: Vec<_> = graph
+            .networks()
+            .into_iter()
+            .map(|network| network.rails)
+            .collect();
+        assert_eq!(networks, [vec![1, 2], vec![3, 4], vec![5]]);
+        assert_eq!(graph.unsupported(), [6]);
+        assert_eq!(
+            graph.rail(5).unwrap().ends.map(|end| end.point),
+            [(10, 1), (12, 1)]
+        );
+    }
+
+    #[test]
+    fn straight_rails_before_2_0_use_the_legacy_shapes() {
+        let blueprint = bp! {
+            "straight-rail" @ (0, 0) facing NorthEast;
+            "straight-rail" @ (0, 0) facing SouthWest;
+        }
+        .version(Version::new(1, 1, 110, 0))
+        .build_with(PrototypeDb::vanilla())
+        .unwrap();
+        let graph = RailGraph::new(&blueprint);
+
+        assert!(graph.unsupported().is_empty());
+        assert_eq!(
+            graph.rail(1).unwrap().ends.map(|end| end.point),
+            [(1, 0), (2, 1)]
+        );
+        assert_eq!(
+            graph.rail(2).unwrap().ends.map(|end| end.point),
+            [(1, 2), (0, 1)]
+        );
+    }
+}
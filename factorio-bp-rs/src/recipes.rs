@@ -0,0 +1,135 @@
+use crate::prototypes::{PrototypeDb, RecipePrototype};
+
+mod graph;
+
+pub use graph::Graph;
+
+#[derive(Debug, Clone, PartialEq)]
+/// An item or fluid a recipe uses.
+pub struct Ingredient {
+    /// The prototype name of the item or fluid.
+    pub name: String,
+    /// How much of it one craft uses.
+    pub amount: f64,
+    /// Whether it is a fluid rather than an item.
+    pub fluid: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// An item or fluid a recipe makes.
+pub struct Product {
+    /// The prototype name of the item or fluid.
+    pub name: String,
+    /// How much of it a craft makes, when it makes any.
+    pub amount: f64,
+    /// The chance of a craft making it, from 0 to 1.
+    pub probability: f64,
+    /// Whether it is a fluid rather than an item.
+    pub fluid: bool,
+}
+
+impl Product {
+    /// How much of it one craft makes on average.
+    pub fn expected(&self) -> f64 {
+        self.amount * self.probability
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A way of crafting items or fluids, with everything it uses and makes.
+pub struct Recipe {
+    /// The prototype name, e.g. "electronic-circuit".
+    pub name: String,
+    /// Which crafting machines can craft it, those with this among their crafting categories.
+    pub category: String,
+    /// The seconds one craft takes at crafting speed 1.
+    pub energy_required: f64,
+    /// The items and fluids one craft uses.
+    pub ingredients: Vec<Ingredient>,
+    /// The items and fluids one craft makes, before productivity.
+    pub products: Vec<Product>,
+    /// Whether productivity modules and bonuses apply to it.
+    pub allow_productivity: bool,
+}
+
+impl Recipe {
+    /// The recipe with this name in `db`.
+    pub fn from_db(name: &str, db: &PrototypeDb) -> Option<Self> {
+        db.recipe(name)
+            .map(|prototype| Self::from_prototype(prototype, db))
+    }
+
+    /// The recipe `prototype` describes, with fluids told apart from items by `db`.
+    pub fn from_prototype(prototype: &RecipePrototype, db: &PrototypeDb) -> Self {
+        let probability = |name: &str| {
+            prototype
+                .probabilities
+                .iter()
+                .find(|(product, _)| product == name)
+                .map_or(1.0, |&(_, probability)| probability)
+        };
+        Self {
+            name: prototype.name.clone(),
+            category: prototype.category.clone(),
+            energy_required: prototype.energy_required,
+            ingredients: prototype
+                .ingredients
+                .iter()
+                .map(|(name, amount)| Ingredient {
+                    name: name.clone(),
+                    amount: *amount,
+                    fluid: db.is_fluid(name),
+                })
+                .collect(),
+            products: prototype
+                .products
+                .iter()
+                .map(|(name, expected)| {
+                    let probability = probability(name);
+                    Product {
+                        name: name.clone(),
+                        amount: if probability > 0.0 {
+                            expected / probability
+                        } else {
+                            0.0
+                        },
+                        probability,
+                        fluid: db.is_fluid(name),
+                    }
+                })
+                .collect(),
+            allow_productivity: prototype.allow_productivity,
+        }
+    }
+}
+
+impl crate::Recipe for Recipe {
+    fn can_use_productivity(&self) -> bool {
+        self.allow_productivity
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recipes_tell_fluids_and_chances_apart() {
+        let db = PrototypeDb::vanilla();
+        let plastic = Recipe::from_db("plastic-bar", db).unwrap();
+        let fluids: Vec<_> = plastic.ingredients.iter().map(|i| i.fluid).collect();
+        assert_eq!(fluids, [true, false]);
+        assert!(plastic.allow_productivity);
+        assert!(crate::Recipe::can_use_productivity(&plastic));
+
+        let uranium = Recipe::from_db("uranium-processing", db).unwrap();
+        assert_eq!(uranium.category, "centrifuging");
+        let u235 = &uranium.products[0];
+        assert_eq!((u235.name.as_str(), u235.amount), ("uranium-235", 1.0));
+        assert_eq!(u235.probability, 0.007);
+        assert_eq!(u235.expected(), 0.007);
+        assert!(!u235.fluid);
+
+        assert_eq!(Recipe::from_db("no-such-recipe", db), None);
+    }
+}
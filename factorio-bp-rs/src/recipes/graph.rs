@@ -0,0 +1,222 @@
+//! How recipes depend on each other through the items and fluids they use and make.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::prototypes::{PrototypeDb, RecipePrototype};
+
+#[derive(Debug, Clone, Default)]
+/// Recipes, linked by the items and fluids they use and make.
+pub struct Graph<'db> {
+    recipes: BTreeMap<&'db str, &'db RecipePrototype>,
+    /// The recipes making each item or fluid, by name.
+    makers: BTreeMap<&'db str, Vec<&'db str>>,
+    /// The recipes using each item or fluid, by name.
+    users: BTreeMap<&'db str, Vec<&'db str>>,
+}
+
+impl<'db> Graph<'db> {
+    /// The graph of every recipe in `db`.
+    pub fn new(db: &'db PrototypeDb) -> Self {
+        Self::from_recipes(db.recipes())
+    }
+
+    /// The graph of `recipes` alone, like those chosen for a plan.
+    pub fn from_recipes(recipes: impl IntoIterator<Item = &'db RecipePrototype>) -> Self {
+        let mut graph = Self::default();
+        for recipe in recipes {
+            graph.recipes.insert(&recipe.name, recipe);
+        }
+        for recipe in graph.recipes.values() {
+            for (product, _) in &recipe.products {
+                graph.makers.entry(product).or_default().push(&recipe.name);
+            }
+            for (ingredient, _) in &recipe.ingredients {
+                graph
+                    .users
+                    .entry(ingredient)
+                    .or_default()
+                    .push(&recipe.name);
+            }
+        }
+        graph
+    }
+
+    /// The recipe with this name, if it is in the graph.
+    pub fn recipe(&self, name: &str) -> Option<&'db RecipePrototype> {
+        self.recipes.get(name).copied()
+    }
+
+    /// The names of the recipes making `item`, in order.
+    pub fn makers(&self, item: &str) -> &[&'db str] {
+        self.makers.get(item).map_or(&[], Vec::as_slice)
+    }
+
+    /// The names of the recipes using `item`, in order.
+    pub fn users(&self, item: &str) -> &[&'db str] {
+        self.users.get(item).map_or(&[], Vec::as_slice)
+    }
+
+    /// Whether no recipe makes `item`, so it has to be mined, pumped, or brought in.
+    pub fn is_raw(&self, item: &str) -> bool {
+        self.makers(item).is_empty()
+    }
+
+    /// The items and fluids used by the recipes making `item`.
+    pub fn ingredients(&self, item: &str) -> BTreeSet<&'db str> {
+        self.makers(item)
+            .iter()
+            .flat_map(|recipe| &self.recipes[recipe].ingredients)
+            .map(|(ingredient, _)| ingredient.as_str())
+            .collect()
+    }
+
+    /// Every item and fluid that goes into making `item`, by any of the recipes making it and
+    /// what they use, down to the raw ones. This includes `item` itself if it is made in a loop.
+    pub fn needs(&self, item: &str) -> BTreeSet<&'db str> {
+        let mut needs = BTreeSet::new();
+        let mut queue: Vec<&str> = self.ingredients(item).into_iter().collect();
+        while let Some(next) = queue.pop() {
+            if needs.insert(next) {
+                queue.extend(self.ingredients(next));
+            }
+        }
+        needs
+    }
+
+    /// The names of every recipe, each after the recipes making its ingredients. Recipes in a
+    /// loop, which can't all come after each other, are kept together.
+    pub fn crafting_order(&self) -> Vec<&'db str> {
+        self.components().into_iter().flatten().collect()
+    }
+
+    /// The recipes that depend on each other in a loop, like Kovarex enrichment using the
+    /// uranium-235 it makes, each loop by name.
+    pub fn loops(&self) -> Vec<Vec<&'db str>> {
+        self.components()
+            .into_iter()
+            .filter(|component| match component.as_slice() {
+                [recipe] => self.next(recipe).any(|next| next == *recipe),
+                _ => true,
+            })
+            .collect()
+    }
+
+    /// The recipes using what `recipe` makes.
+    fn next<'a>(&'a self, recipe: &str) -> impl Iterator<Item = &'db str> + 'a {
+        self.recipes[recipe]
+            .products
+            .iter()
+            .flat_map(|(product, _)| self.users(product))
+            .copied()
+    }
+
+    /// The strongly connected components of the graph, each after those making what it uses,
+    /// found with Tarjan's algorithm.
+    fn components(&self) -> Vec<Vec<&'db str>> {
+        let mut search = Search {
+            graph: self,
+            index: BTreeMap::new(),
+            low: BTreeMap::new(),
+            stack: Vec::new(),
+            on_stack: BTreeSet::new(),
+            components: Vec::new(),
+        };
+        for &recipe in self.recipes.keys() {
+            if !search.index.contains_key(recipe) {
+                search.visit(recipe);
+            }
+        }
+        // Tarjan's algorithm finds each component after those reachable from it
+        search.components.reverse();
+        search.components
+    }
+}
+
+/// The state of Tarjan's algorithm partway through.
+struct Search<'a, 'db> {
+    graph: &'a Graph<'db>,
+    /// The order recipes were reached in.
+    index: BTreeMap<&'db str, usize>,
+    /// The lowest index reachable from each recipe through those not yet in a component.
+    low: BTreeMap<&'db str, usize>,
+    stack: Vec<&'db str>,
+    on_stack: BTreeSet<&'db str>,
+    components: Vec<Vec<&'db str>>,
+}
+
+impl<'db> Search<'_, 'db> {
+    fn visit(&mut self, recipe: &'db str) {
+        let index = self.index.len();
+        self.index.insert(recipe, index);
+        self.low.insert(recipe, index);
+        self.stack.push(recipe);
+        self.on_stack.insert(recipe);
+
+        for next in self.graph.next(recipe) {
+            if !self.index.contains_key(next) {
+                self.visit(next);
+                let low = self.low[recipe].min(self.low[next]);
+                self.low.insert(recipe, low);
+            } else if self.on_stack.contains(next) {
+                let low = self.low[recipe].min(self.index[next]);
+                self.low.insert(recipe, low);
+            }
+        }
+
+        if self.low[recipe] == index {
+            let mut component = Vec::new();
+            while let Some(member) = self.stack.pop() {
+                self.on_stack.remove(member);
+                component.push(member);
+                if member == recipe {
+                    break;
+                }
+            }
+            component.sort_unstable();
+            self.components.push(component);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A recipe making `products` from `ingredients`, one of each.
+    fn recipe(name: &str, ingredients: &[&str], products: &[&str]) -> RecipePrototype {
+        let amounts = |names: &[&str]| names.iter().map(|&n| (n.to_owned(), 1.0)).collect();
+        RecipePrototype {
+            name: name.to_owned(),
+            ingredients: amounts(ingredients),
+            products: amounts(products),
+            ..RecipePrototype::default()
+        }
+    }
+
+    #[test]
+    fn recipes_come_after_what_they_use() {
+        let recipes = [
+            recipe("gear", &["plate"], &["gear"]),
+            recipe("plate", &["ore"], &["plate"]),
+            recipe("enrich", &["rare", "common"], &["rare"]),
+            recipe("refine", &["ore"], &["common"]),
+        ];
+        let graph = Graph::from_recipes(&recipes);
+
+        assert_eq!(graph.makers("plate"), ["plate"]);
+        assert_eq!(graph.users("ore"), ["plate", "refine"]);
+        assert!(graph.is_raw("ore"));
+        assert!(!graph.is_raw("gear"));
+        assert_eq!(graph.needs("gear"), BTreeSet::from(["ore", "plate"]));
+        assert_eq!(
+            graph.needs("rare"),
+            BTreeSet::from(["common", "ore", "rare"])
+        );
+
+        let order = graph.crafting_order();
+        let position = |name| order.iter().position(|&r| r == name).unwrap();
+        assert!(position("plate") < position("gear"));
+        assert!(position("refine") < position("enrich"));
+        assert_eq!(graph.loops(), [["enrich"]]);
+    }
+}
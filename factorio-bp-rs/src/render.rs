@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+
+use crate::{
+    blueprint::{Blueprint, BoundingBox, Direction, Entity, Position, Tile, WireConnectorId},
+    prototypes::PrototypeDb,
+};
+
+mod ascii;
+mod dot;
+#[cfg(feature = "image")]
+mod png;
+mod svg;
+mod theme;
+
+pub use ascii::{to_ascii, AsciiOptions};
+pub use dot::to_dot;
+#[cfg(feature = "image")]
+pub use png::{to_image, to_png, PngOptions};
+pub use svg::to_svg;
+pub use theme::{EntityClass, Rgb, Theme};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The colour of a wire.
+pub enum WireColor {
+    /// A red circuit wire.
+    Red,
+    /// A green circuit wire.
+    Green,
+    /// A copper power wire.
+    Copper,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// An entity as it is drawn.
+pub struct EntityShape {
+    /// The entity_number of the entity.
+    pub entity_number: usize,
+    /// The prototype name of the entity.
+    pub name: String,
+    /// The kind of entity, which decides its colour.
+    pub class: EntityClass,
+    /// The tiles the entity covers.
+    pub bounds: BoundingBox,
+    /// The way the entity faces, if it has a direction.
+    pub direction: Option<Direction>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A wire as it is drawn, as a straight line between the centres of the entities it connects.
+pub struct WireLine {
+    /// Where the wire starts.
+    pub from: Position,
+    /// Where the wire ends.
+    pub to: Position,
+    /// The colour of the wire.
+    pub color: WireColor,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// The shapes that make up a drawing of a blueprint, shared by every output format.
+pub struct Scene {
+    /// The area to draw. Covers every entity and tile.
+    pub bounds: BoundingBox,
+    /// The tiles, drawn first.
+    pub tiles: Vec<(String, BoundingBox)>,
+    /// The entities, drawn over the tiles.
+    pub entities: Vec<EntityShape>,
+    /// The wires, drawn over everything else.
+    pub wires: Vec<WireLine>,
+}
+
+impl Scene {
+    /// Lay out the blueprint, with entity sizes from `db`.
+    ///
+    /// Entities `db` doesn't know are drawn as small as their position allows, see
+    /// [Entity::min_bounding_box].
+    pub fn new(blueprint: &Blueprint, db: &PrototypeDb) -> Self {
+        let entities: Vec<_> = blueprint.entities.iter().flatten().collect();
+        let shapes = entities
+            .iter()
+            .map(|entity| EntityShape {
+                entity_number: entity.entity_number.get(),
+                name: entity.name.clone(),
+                class: EntityClass::of(&entity.name),
+                bounds: entity
+                    .footprint(db)
+                    .unwrap_or_else(|| entity.min_bounding_box()),
+                direction: entity.direction,
+            })
+            .collect();
+        let tiles = blueprint
+            .tiles
+            .iter()
+            .flatten()
+            .map(|tile| (tile.name.clone(), Tile::bounding_box(tile)))
+            .collect();
+
+        Self {
+            bounds: blueprint
+                .bounding_box_with(db)
+                .unwrap_or(BoundingBox::new(0.0, 0.0, 1.0, 1.0)),
+            tiles,
+            entities: shapes,
+            wires: wire_lines(blueprint, &entities),
+        }
+    }
+}
+
+/// Every wire of the blueprint, from both 2.0 `wires` and older `connections` and `neighbours`.
+fn wire_lines(blueprint: &Blueprint, entities: &[&Entity]) -> Vec<WireLine> {
+    let positions: HashMap<_, _> = entities
+        .iter()
+        .map(|entity| (entity.entity_number.get(), &entity.position))
+        .collect();
+    let mut lines = Vec::new();
+    let mut line = |from: usize, to: usize, color: WireColor| {
+        // Wires to missing entities can't be drawn
+        if let (Some(&from), Some(&to)) = (positions.get(&from), positions.get(&to)) {
+            lines.push(WireLine {
+                from: from.clone(),
+                to: to.clone(),
+                color,
+            });
+        }
+    };
+
+    for wire in blueprint.wires.iter().flatten() {
+        let color = match wire.source_connector {
+            WireConnectorId::CircuitRed | WireConnectorId::CombinatorOutputRed => WireColor::Red,
+            WireConnectorId::CircuitGreen | WireConnectorId::CombinatorOutputGreen => {
+                WireColor::Green
+            }
+            WireConnectorId::PoleCopper | WireConnectorId::PowerSwitchRightCopper => {
+                WireColor::Copper
+            }
+        };
+        line(wire.source_entity.get(), wire.target_entity.get(), color);
+    }
+
+    for entity in entities {
+        let number = entity.entity_number.get();
+        // Connections are listed at both ends, so only draw them from the lower entity_number
+        for neighbour in entity.neighbors.iter().flatten() {
+            if neighbour.get() > number {
+                line(number, neighbour.get(), WireColor::Copper);
+            }
+        }
+        let points = entity
+            .connections
+            .iter()
+            .flat_map(|connection| [&connection.first, &connection.second])
+            .flatten();
+        for point in points {
+            let colored = [
+                (&point.red, WireColor::Red),
+                (&point.green, WireColor::Green),
+            ];
+            for (data, color) in colored {
+                for data in data.iter().flatten() {
+                    if data.entity_id.get() > number {
+                        line(number, data.entity_id.get(), color);
+                    }
+                }
+            }
+        }
+    }
+
+    lines
+}
@@ -0,0 +1,170 @@
+//! Text output, one character per tile, for looking at blueprints in a terminal.
+
+use super::{EntityClass, EntityShape, Rgb, Scene, Theme};
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// Settings for drawing a blueprint as text.
+pub struct AsciiOptions {
+    /// Use box drawing characters and arrows rather than plain ASCII.
+    pub unicode: bool,
+    /// Colour the characters with ANSI escape codes, in the colours of this theme.
+    pub color: Option<Theme>,
+}
+
+/// The character drawn for an entity of `class`, in ASCII and in Unicode.
+fn class_char(class: EntityClass) -> (char, char) {
+    match class {
+        EntityClass::Belt => ('=', '═'),
+        EntityClass::Inserter => ('i', 'ı'),
+        EntityClass::Power => ('+', '┼'),
+        EntityClass::Production => ('#', '█'),
+        EntityClass::Storage => ('C', '▣'),
+        EntityClass::Fluid => ('~', '≈'),
+        EntityClass::Circuit => ('%', '◆'),
+        EntityClass::Rail => ('H', '╫'),
+        EntityClass::Defense => ('W', '▓'),
+        EntityClass::Other => ('?', '?'),
+    }
+}
+
+/// The character drawn for `entity`. Belts and inserters show the way they face.
+fn entity_char(entity: &EntityShape, unicode: bool) -> char {
+    let (ascii, unicode_char) = class_char(entity.class);
+    if !matches!(entity.class, EntityClass::Belt | EntityClass::Inserter) {
+        return if unicode { unicode_char } else { ascii };
+    }
+
+    // Without a direction, entities face north
+    let sixteenths = entity.direction.unwrap_or_default().sixteenths() as usize;
+    if unicode {
+        ['↑', '↗', '→', '↘', '↓', '↙', '←', '↖'][sixteenths.div_ceil(2) % 8]
+    } else {
+        ['^', '>', 'v', '<'][(sixteenths + 2) / 4 % 4]
+    }
+}
+
+/// A grid of characters covering the scene, one cell per tile.
+struct Grid {
+    origin: (i64, i64),
+    width: usize,
+    cells: Vec<Option<(char, Rgb)>>,
+}
+
+impl Grid {
+    /// Set the cells whose centres are inside the box from `min` to `max`, or the cell at the
+    /// centre of the box for entities too small to cover a centre.
+    fn fill(&mut self, min: (f64, f64), max: (f64, f64), cell: (char, Rgb)) {
+        let height = self.cells.len() / self.width;
+        let column = |x: f64| x.floor() as i64 - self.origin.0;
+        let row = |y: f64| y.floor() as i64 - self.origin.1;
+        let (left, right) = (column(min.0 + 0.5), column(max.0 - 0.5));
+        let (top, bottom) = (row(min.1 + 0.5), row(max.1 - 0.5));
+
+        let covered: Vec<(i64, i64)> = if left <= right && top <= bottom {
+            (top..=bottom)
+                .flat_map(|y| (left..=right).map(move |x| (x, y)))
+                .collect()
+        } else {
+            vec![(column((min.0 + max.0) / 2.0), row((min.1 + max.1) / 2.0))]
+        };
+        for (x, y) in covered {
+            if (0..self.width as i64).contains(&x) && (0..height as i64).contains(&y) {
+                self.cells[y as usize * self.width + x as usize] = Some(cell);
+            }
+        }
+    }
+}
+
+/// Draw the scene as lines of text, one character per tile.
+///
+/// Wires aren't drawn, as there is no room for them between the characters.
+pub fn to_ascii(scene: &Scene, options: &AsciiOptions) -> String {
+    let bounds = &scene.bounds;
+    let origin = (bounds.min_x.floor() as i64, bounds.min_y.floor() as i64);
+    let width = (bounds.max_x.ceil() as i64 - origin.0).max(1) as usize;
+    let height = (bounds.max_y.ceil() as i64 - origin.1).max(1) as usize;
+    let theme = options.color.clone().unwrap_or_default();
+
+    let mut grid = Grid {
+        origin,
+        width,
+        cells: vec![None; width * height],
+    };
+    let tile_char = if options.unicode { '·' } else { '.' };
+    for (_, tile) in &scene.tiles {
+        grid.fill(
+            (tile.min_x, tile.min_y),
+            (tile.max_x, tile.max_y),
+            (tile_char, theme.tile),
+        );
+    }
+    for entity in &scene.entities {
+        let bounds = &entity.bounds;
+        grid.fill(
+            (bounds.min_x, bounds.min_y),
+            (bounds.max_x, bounds.max_y),
+            (
+                entity_char(entity, options.unicode),
+                theme.entity(entity.class),
+            ),
+        );
+    }
+
+    let mut text = String::new();
+    for row in grid.cells.chunks(width) {
+        // Stop at the last drawn cell rather than pad the line with spaces
+        let end = row.iter().rposition(Option::is_some).map_or(0, |i| i + 1);
+        let mut current = None;
+        for cell in &row[..end] {
+            let (c, color) = cell.unwrap_or((' ', theme.background));
+            // Only switch colour where it changes, to keep the escape codes down
+            if options.color.is_some() && cell.is_some() && current != Some(color) {
+                let [r, g, b] = color;
+                text += &format!("\x1b[38;2;{r};{g};{b}m");
+                current = Some(color);
+            }
+            text.push(c);
+        }
+        if current.is_some() {
+            text += "\x1b[0m";
+        }
+        text.push('\n');
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bp, prototypes::PrototypeDb};
+
+    #[test]
+    fn each_tile_is_one_character() {
+        let blueprint = bp! {
+            "assembling-machine-1" @ (0, 0);
+            "inserter" @ (3, 1) facing East;
+            "transport-belt" @ (4, 1) facing South;
+            "iron-chest" @ (5, 1);
+        }
+        .tile("concrete", 0, 3)
+        .build_with(PrototypeDb::vanilla())
+        .unwrap();
+        let scene = Scene::new(&blueprint, PrototypeDb::vanilla());
+
+        let ascii = to_ascii(&scene, &AsciiOptions::default());
+        assert_eq!(ascii, "###\n###>vC\n###\n.\n");
+        let unicode = AsciiOptions {
+            unicode: true,
+            color: None,
+        };
+        assert_eq!(to_ascii(&scene, &unicode), "███\n███→↓▣\n███\n·\n");
+
+        let colored = AsciiOptions {
+            unicode: false,
+            color: Some(Theme::dark()),
+        };
+        let colored = to_ascii(&scene, &colored);
+        assert!(colored.starts_with("\x1b[38;2;"));
+        assert_eq!(colored.matches("\x1b[0m\n").count(), 4);
+    }
+}
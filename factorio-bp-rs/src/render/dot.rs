@@ -0,0 +1,244 @@
+//! Graphviz DOT output of the circuit networks of a blueprint.
+
+use core::fmt::Write;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    blueprint::{
+        control_behavior::{DeciderCombinatorParameters, SelectorOperation},
+        Blueprint, CompareType, ControlBehavior, Entity, SignalId,
+    },
+    circuit::{CircuitGraph, Connector, ConnectorKind, NetworkColor},
+};
+
+/// The name of a signal, or "nothing" if unset.
+fn signal_name(signal: Option<&SignalId>) -> &str {
+    signal.map_or("nothing", |signal| signal.name.as_str())
+}
+
+/// One side of a condition or calculation: a signal, else a constant, else 0.
+fn operand(signal: Option<&SignalId>, constant: Option<i32>) -> String {
+    match signal {
+        Some(signal) => signal.name.clone(),
+        None => constant.unwrap_or(0).to_string(),
+    }
+}
+
+/// What a decider combinator outputs and when, e.g. `signal-A > 10 → signal-B = input`.
+fn decider_label(decider: &DeciderCombinatorParameters) -> String {
+    if decider.conditions.is_empty() && decider.outputs.is_empty() {
+        // Before 2.0: a single condition and output
+        let count = match decider.copy_count_from_input.unwrap_or(true) {
+            true => "input",
+            false => "1",
+        };
+        return format!(
+            "{} {} {} → {} = {count}",
+            signal_name(decider.first_signal.as_ref()),
+            decider.comparator.unwrap_or_default().as_str(),
+            operand(decider.second_signal.as_ref(), decider.constant),
+            signal_name(decider.output_signal.as_ref()),
+        );
+    }
+
+    let mut conditions = String::new();
+    for (i, condition) in decider.conditions.iter().enumerate() {
+        if i > 0 {
+            conditions += match condition.compare_type {
+                Some(CompareType::And) => " and ",
+                _ => " or ",
+            };
+        }
+        conditions += &format!(
+            "{} {} {}",
+            signal_name(condition.first_signal.as_ref()),
+            condition.comparator.unwrap_or_default().as_str(),
+            operand(condition.second_signal.as_ref(), condition.constant),
+        );
+    }
+    let outputs: Vec<String> = decider
+        .outputs
+        .iter()
+        .map(|output| {
+            let count = match output.copy_count_from_input.unwrap_or(true) {
+                true => "input".to_owned(),
+                false => output.constant.unwrap_or(1).to_string(),
+            };
+            format!("{} = {count}", signal_name(output.signal.as_ref()))
+        })
+        .collect();
+    format!("{conditions} → {}", outputs.join(", "))
+}
+
+/// What a combinator does, if `behavior` belongs to one.
+fn operation_label(entity: &Entity, behavior: &ControlBehavior) -> Option<String> {
+    if let Some(arithmetic) = &behavior.arithmetic_conditions {
+        return Some(format!(
+            "{} {} {} → {}",
+            operand(arithmetic.first_signal.as_ref(), arithmetic.first_constant),
+            arithmetic.operation.unwrap_or_default().as_str(),
+            operand(
+                arithmetic.second_signal.as_ref(),
+                arithmetic.second_constant
+            ),
+            signal_name(arithmetic.output_signal.as_ref()),
+        ));
+    }
+    if let Some(decider) = &behavior.decider_conditions {
+        return Some(decider_label(decider));
+    }
+    if let Some(operation) = behavior.operation {
+        let name = match operation {
+            SelectorOperation::Select => "select",
+            SelectorOperation::Count => "count",
+            SelectorOperation::Random => "random",
+            SelectorOperation::StackSize => "stack size",
+            SelectorOperation::RocketCapacity => "rocket capacity",
+            SelectorOperation::QualityFilter => "quality filter",
+            SelectorOperation::QualityTransfer => "quality transfer",
+        };
+        return Some(name.to_owned());
+    }
+    if entity.name.contains("constant-combinator") {
+        let signals: Vec<String> = behavior
+            .constant_signals()
+            .into_iter()
+            .map(|(signal, count)| format!("{} = {count}", signal.name))
+            .collect();
+        return Some(signals.join("\n"));
+    }
+    None
+}
+
+/// Escape text for use in a quoted DOT string.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Draw the circuit networks of a blueprint as a Graphviz DOT graph.
+///
+/// Every entity with a circuit wire is a node, labelled with its entity_number and name, and for
+/// combinators with what they do. Wires are red and green edges. Wires to a combinator's input
+/// meet it on the left and wires to its output on the right.
+pub fn to_dot(blueprint: &Blueprint) -> String {
+    let mut dot = String::new();
+    write_graph(&mut dot, blueprint).expect("writing to a String can't fail");
+    dot
+}
+
+fn write_graph(dot: &mut String, blueprint: &Blueprint) -> core::fmt::Result {
+    let graph = CircuitGraph::new(blueprint);
+    let entities: BTreeMap<usize, &Entity> = blueprint
+        .entities
+        .iter()
+        .flatten()
+        .map(|entity| (entity.entity_number.get(), entity))
+        .collect();
+
+    writeln!(dot, "graph circuits {{")?;
+    writeln!(dot, "  rankdir=LR;")?;
+    writeln!(dot, "  node [shape=box, fontname=\"sans-serif\"];")?;
+
+    for number in graph.entity_numbers() {
+        let mut label = match entities.get(&number) {
+            Some(entity) => format!("#{number} {}", entity.name),
+            None => format!("#{number} (missing)"),
+        };
+        let operation = entities.get(&number).and_then(|entity| {
+            let behavior = entity.control_behavior.as_ref()?;
+            operation_label(entity, behavior)
+        });
+        if let Some(operation) = operation.filter(|operation| !operation.is_empty()) {
+            label += "\n";
+            label += &operation;
+        }
+        writeln!(dot, "  {number} [label=\"{}\"];", escape(&label))?;
+    }
+
+    // Only combinators have separate inputs and outputs, so the others need no port
+    let combinators: BTreeSet<usize> = graph
+        .wires()
+        .iter()
+        .flat_map(|wire| [wire.from, wire.to])
+        .filter(|connector| {
+            connector.kind == ConnectorKind::Output
+                || entities
+                    .get(&connector.entity_number)
+                    .is_some_and(|entity| {
+                        entity.name.contains("combinator") && !entity.name.contains("constant")
+                    })
+        })
+        .map(|connector| connector.entity_number)
+        .collect();
+    let end = |connector: Connector| {
+        let number = connector.entity_number;
+        match (combinators.contains(&number), connector.kind) {
+            (false, _) => number.to_string(),
+            (true, ConnectorKind::Input) => format!("{number}:w"),
+            (true, ConnectorKind::Output) => format!("{number}:e"),
+        }
+    };
+    for wire in graph.wires() {
+        let color = match wire.color {
+            NetworkColor::Red => "red",
+            NetworkColor::Green => "green",
+        };
+        writeln!(
+            dot,
+            "  {} -- {} [color={color}];",
+            end(wire.from),
+            end(wire.to)
+        )?;
+    }
+
+    writeln!(dot, "}}")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn combinators_are_wired_by_their_input_and_output() {
+        let blueprint: Blueprint = serde_json::from_value(json!({
+            "item": "blueprint",
+            "version": 562949954076673u64,
+            "entities": [
+                {"entity_number": 1, "name": "iron-chest", "position": {"x": 0.5, "y": 0.5}},
+                {
+                    "entity_number": 2,
+                    "name": "arithmetic-combinator",
+                    "position": {"x": 2.0, "y": 0.5},
+                    "direction": 4,
+                    "control_behavior": {
+                        "arithmetic_conditions": {
+                            "first_signal": {"type": "virtual", "name": "signal-A"},
+                            "second_constant": 2,
+                            "operation": "*",
+                            "output_signal": {"type": "virtual", "name": "signal-B"},
+                        },
+                    },
+                },
+                {"entity_number": 3, "name": "small-lamp", "position": {"x": 3.5, "y": 0.5}},
+                {"entity_number": 4, "name": "iron-chest", "position": {"x": 9.5, "y": 0.5}},
+            ],
+            "wires": [[1, 1, 2, 1], [2, 4, 3, 2]],
+        }))
+        .unwrap();
+        let dot = to_dot(&blueprint);
+
+        assert!(dot.starts_with("graph circuits {\n"));
+        assert!(dot.contains(r##"  1 [label="#1 iron-chest"];"##));
+        assert!(
+            dot.contains(r##"  2 [label="#2 arithmetic-combinator\nsignal-A * 2 → signal-B"];"##)
+        );
+        assert!(!dot.contains("#4"));
+        assert!(dot.contains("  1 -- 2:w [color=red];"));
+        assert!(dot.contains("  2:e -- 3 [color=green];"));
+        assert!(dot.ends_with("}\n"));
+    }
+}
@@ -0,0 +1,267 @@
+//! Raster output, with the `image` feature.
+
+use std::io::Cursor;
+
+use image::{ImageError, ImageFormat, Rgb as Pixel, RgbImage};
+
+use crate::blueprint::BoundingBox;
+
+use super::{EntityShape, Rgb, Scene, Theme};
+
+/// The digits 0-9 as 3 by 5 pixel glyphs, one bit per pixel, top row first.
+const DIGITS: [u16; 10] = [
+    0b111_101_101_101_111,
+    0b010_110_010_010_111,
+    0b111_001_111_100_111,
+    0b111_001_111_001_111,
+    0b101_101_111_001_001,
+    0b111_100_111_001_111,
+    0b111_100_111_101_111,
+    0b111_001_001_001_001,
+    0b111_101_111_101_111,
+    0b111_101_111_001_111,
+];
+
+#[derive(Debug, Clone, PartialEq)]
+/// Settings for drawing a blueprint as a raster image.
+pub struct PngOptions {
+    /// Pixels per tile.
+    pub scale: u32,
+    /// The colours to draw with.
+    pub theme: Theme,
+    /// Whether to write each entity's entity_number over it.
+    pub entity_numbers: bool,
+}
+
+impl Default for PngOptions {
+    fn default() -> Self {
+        Self {
+            scale: 16,
+            theme: Theme::default(),
+            entity_numbers: false,
+        }
+    }
+}
+
+/// An image being drawn on in blueprint coordinates.
+struct Canvas {
+    image: RgbImage,
+    origin: (f64, f64),
+    scale: f64,
+}
+
+impl Canvas {
+    /// The pixel at blueprint coordinates `(x, y)`.
+    fn pixel(&self, x: f64, y: f64) -> (i64, i64) {
+        (
+            ((x - self.origin.0) * self.scale).floor() as i64,
+            ((y - self.origin.1) * self.scale).floor() as i64,
+        )
+    }
+
+    fn put(&mut self, x: i64, y: i64, color: Rgb) {
+        if (0..self.image.width() as i64).contains(&x)
+            && (0..self.image.height() as i64).contains(&y)
+        {
+            self.image.put_pixel(x as u32, y as u32, Pixel(color));
+        }
+    }
+
+    /// Fill the pixels from `(x0, y0)` up to but not including `(x1, y1)`.
+    fn fill_pixels(&mut self, (x0, y0): (i64, i64), (x1, y1): (i64, i64), color: Rgb) {
+        for y in y0..y1 {
+            for x in x0..x1 {
+                self.put(x, y, color);
+            }
+        }
+    }
+
+    /// Fill `bounds`, with a border of `outline` one pixel wide if given.
+    fn rect(&mut self, bounds: &BoundingBox, fill: Rgb, outline: Option<Rgb>) {
+        let (x0, y0) = self.pixel(bounds.min_x, bounds.min_y);
+        let (x1, y1) = self.pixel(bounds.max_x, bounds.max_y);
+        match outline {
+            Some(outline) => {
+                self.fill_pixels((x0, y0), (x1, y1), outline);
+                self.fill_pixels((x0 + 1, y0 + 1), (x1 - 1, y1 - 1), fill);
+            }
+            None => self.fill_pixels((x0, y0), (x1, y1), fill),
+        }
+    }
+
+    /// Fill the triangle with corners `points`, in blueprint coordinates.
+    fn triangle(&mut self, points: [(f64, f64); 3], color: Rgb) {
+        let [a, b, c] = points.map(|(x, y)| {
+            (
+                (x - self.origin.0) * self.scale,
+                (y - self.origin.1) * self.scale,
+            )
+        });
+        let edge = |p: (f64, f64), q: (f64, f64), r: (f64, f64)| {
+            (q.0 - p.0) * (r.1 - p.1) - (q.1 - p.1) * (r.0 - p.0)
+        };
+        let area = edge(a, b, c);
+        if area == 0.0 {
+            return;
+        }
+
+        let (min_x, max_x) = (a.0.min(b.0).min(c.0), a.0.max(b.0).max(c.0));
+        let (min_y, max_y) = (a.1.min(b.1).min(c.1), a.1.max(b.1).max(c.1));
+        for y in min_y.floor() as i64..=max_y.ceil() as i64 {
+            for x in min_x.floor() as i64..=max_x.ceil() as i64 {
+                let p = (x as f64 + 0.5, y as f64 + 0.5);
+                // Inside when on the same side of every edge as the whole triangle
+                let inside = [edge(a, b, p), edge(b, c, p), edge(c, a, p)]
+                    .iter()
+                    .all(|side| side * area >= 0.0);
+                if inside {
+                    self.put(x, y, color);
+                }
+            }
+        }
+    }
+
+    /// Draw a line one pixel wide between two points in blueprint coordinates.
+    fn line(&mut self, from: (f64, f64), to: (f64, f64), color: Rgb) {
+        let (x0, y0) = self.pixel(from.0, from.1);
+        let (x1, y1) = self.pixel(to.0, to.1);
+        let steps = (x1 - x0).abs().max((y1 - y0).abs()).max(1);
+        for step in 0..=steps {
+            let t = step as f64 / steps as f64;
+            let x = x0 as f64 + (x1 - x0) as f64 * t;
+            let y = y0 as f64 + (y1 - y0) as f64 * t;
+            self.put(x.round() as i64, y.round() as i64, color);
+        }
+    }
+
+    /// Write `number` centred on a point in blueprint coordinates, with each glyph pixel
+    /// `size` pixels across.
+    fn number(&mut self, number: usize, center: (f64, f64), size: i64, color: Rgb) {
+        let digits: Vec<usize> = number
+            .to_string()
+            .bytes()
+            .map(|digit| (digit - b'0') as usize)
+            .collect();
+        let width = (digits.len() as i64 * 4 - 1) * size;
+        let (cx, cy) = self.pixel(center.0, center.1);
+        let (left, top) = (cx - width / 2, cy - 5 * size / 2);
+
+        for (i, digit) in digits.into_iter().enumerate() {
+            for bit in 0..15 {
+                if DIGITS[digit] & (1 << (14 - bit)) != 0 {
+                    let x = left + (i as i64 * 4 + bit % 3) * size;
+                    let y = top + (bit / 3) * size;
+                    self.fill_pixels((x, y), (x + size, y + size), color);
+                }
+            }
+        }
+    }
+}
+
+/// Draw an entity's footprint, an arrow for its direction, and optionally its number.
+fn draw_entity(canvas: &mut Canvas, entity: &EntityShape, options: &PngOptions) {
+    let theme = &options.theme;
+    let bounds = &entity.bounds;
+    canvas.rect(bounds, theme.entity(entity.class), Some(theme.outline));
+
+    let center = bounds.center();
+    if let Some(direction) = entity.direction {
+        let radius = bounds.width().min(bounds.height()) * 0.3;
+        let (dx, dy) = direction.offset();
+        let point = |forward: f64, sideways: f64| {
+            (
+                center.x + dx * forward * radius - dy * sideways * radius,
+                center.y + dy * forward * radius + dx * sideways * radius,
+            )
+        };
+        canvas.triangle(
+            [point(1.0, 0.0), point(-0.5, -0.6), point(-0.5, 0.6)],
+            theme.arrow,
+        );
+    }
+
+    if options.entity_numbers {
+        let size = (options.scale as i64 / 10).max(1);
+        canvas.number(entity.entity_number, (center.x, center.y), size, theme.text);
+    }
+}
+
+/// Draw the scene as an image.
+pub fn to_image(scene: &Scene, options: &PngOptions) -> RgbImage {
+    let bounds = &scene.bounds;
+    let scale = options.scale.max(1) as f64;
+    let theme = &options.theme;
+
+    let mut canvas = Canvas {
+        image: RgbImage::from_pixel(
+            (bounds.width() * scale).ceil() as u32,
+            (bounds.height() * scale).ceil() as u32,
+            Pixel(theme.background),
+        ),
+        origin: (bounds.min_x, bounds.min_y),
+        scale,
+    };
+
+    for (_, tile) in &scene.tiles {
+        canvas.rect(tile, theme.tile, None);
+    }
+    for entity in &scene.entities {
+        draw_entity(&mut canvas, entity, options);
+    }
+    for wire in &scene.wires {
+        canvas.line(
+            (wire.from.x, wire.from.y),
+            (wire.to.x, wire.to.y),
+            theme.wire(wire.color),
+        );
+    }
+
+    canvas.image
+}
+
+/// Draw the scene as a PNG file.
+pub fn to_png(scene: &Scene, options: &PngOptions) -> Result<Vec<u8>, ImageError> {
+    let mut png = Cursor::new(Vec::new());
+    to_image(scene, options).write_to(&mut png, ImageFormat::Png)?;
+    Ok(png.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bp, prototypes::PrototypeDb, render::EntityClass};
+
+    #[test]
+    fn entities_are_filled_inside_their_outline() {
+        let blueprint = bp! { "assembling-machine-1" @ (0, 0) }
+            .tile("concrete", 0, 3)
+            .build_with(PrototypeDb::vanilla())
+            .unwrap();
+        let scene = Scene::new(&blueprint, PrototypeDb::vanilla());
+        let options = PngOptions {
+            scale: 4,
+            ..PngOptions::default()
+        };
+        let theme = &options.theme;
+        let image = to_image(&scene, &options);
+
+        assert_eq!(image.dimensions(), (12, 16));
+        let class = EntityClass::of("assembling-machine-1");
+        assert_eq!(image.get_pixel(0, 0).0, theme.outline);
+        assert_eq!(image.get_pixel(6, 6).0, theme.entity(class));
+        assert_eq!(image.get_pixel(2, 14).0, theme.tile);
+        assert_eq!(image.get_pixel(6, 14).0, theme.background);
+
+        let numbered = to_image(
+            &scene,
+            &PngOptions {
+                entity_numbers: true,
+                ..options.clone()
+            },
+        );
+        assert_ne!(numbered, image);
+
+        let png = to_png(&scene, &options).unwrap();
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+    }
+}
@@ -0,0 +1,196 @@
+//! SVG output, in tile units so it scales without losing detail.
+
+use core::fmt::Write;
+
+use super::{EntityClass, EntityShape, Rgb, Scene, Theme, WireColor};
+
+/// Pixels per tile at the SVG's natural size.
+const TILE_PIXELS: f64 = 32.0;
+
+/// Escape text for use in XML.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// A colour in CSS hex notation.
+fn hex([r, g, b]: Rgb) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+/// Draw an entity's footprint, an arrow for its direction, and its name.
+fn write_entity(svg: &mut String, entity: &EntityShape) -> core::fmt::Result {
+    let bounds = &entity.bounds;
+    // Inset slightly so neighbouring entities stay distinguishable
+    writeln!(
+        svg,
+        r##"<rect class="entity {}" x="{}" y="{}" width="{}" height="{}"><title>{} ({})</title></rect>"##,
+        entity.class.as_str(),
+        bounds.min_x + 0.05,
+        bounds.min_y + 0.05,
+        bounds.width() - 0.1,
+        bounds.height() - 0.1,
+        escape(&entity.name),
+        entity.entity_number,
+    )?;
+
+    let center = bounds.center();
+    let radius = bounds.width().min(bounds.height()) * 0.3;
+    if let Some(direction) = entity.direction {
+        let (dx, dy) = direction.offset();
+        // Rounded, as the trigonometry leaves long tails of digits
+        let round = |v: f64| (v * 1000.0).round() / 1000.0;
+        let point = |forward: f64, sideways: f64| {
+            (
+                round(center.x + dx * forward * radius - dy * sideways * radius),
+                round(center.y + dy * forward * radius + dx * sideways * radius),
+            )
+        };
+        let [tip, left, right] = [point(1.0, 0.0), point(-0.5, -0.6), point(-0.5, 0.6)];
+        writeln!(
+            svg,
+            r#"<polygon class="arrow" points="{},{} {},{} {},{}"/>"#,
+            tip.0, tip.1, left.0, left.1, right.0, right.1,
+        )?;
+    }
+
+    // Shrink long names to fit the footprint
+    let font_size = (bounds.width() * 1.6 / entity.name.len().max(1) as f64).min(0.3);
+    writeln!(
+        svg,
+        r#"<text x="{}" y="{}" font-size="{font_size:.3}">{}</text>"#,
+        center.x,
+        bounds.max_y - 0.1,
+        escape(&entity.name),
+    )
+}
+
+/// Draw the scene as an SVG document, in the colours of `theme`.
+pub fn to_svg(scene: &Scene, theme: &Theme) -> String {
+    let mut svg = String::new();
+    write_document(&mut svg, scene, theme).expect("writing to a String can't fail");
+    svg
+}
+
+/// The stylesheet giving each class of entity and wire its colour.
+fn style(theme: &Theme) -> String {
+    let mut style = format!(
+        ".tile{{fill:{}}}\
+        .entity{{stroke:{};stroke-width:0.05}}\
+        .arrow{{fill:{}}}\
+        text{{font-family:sans-serif;text-anchor:middle;fill:{}}}\
+        .wire{{stroke-width:0.06;stroke-opacity:0.8;fill:none}}",
+        hex(theme.tile),
+        hex(theme.outline),
+        hex(theme.arrow),
+        hex(theme.text),
+    );
+    for class in EntityClass::ALL {
+        style += &format!(".{}{{fill:{}}}", class.as_str(), hex(theme.entity(class)));
+    }
+    for wire in [WireColor::Red, WireColor::Green, WireColor::Copper] {
+        style += &format!(".{}{{stroke:{}}}", wire_class(wire), hex(theme.wire(wire)));
+    }
+    style
+}
+
+/// The CSS class of a wire colour.
+fn wire_class(wire: WireColor) -> &'static str {
+    match wire {
+        WireColor::Red => "red",
+        WireColor::Green => "green",
+        WireColor::Copper => "copper",
+    }
+}
+
+fn write_document(svg: &mut String, scene: &Scene, theme: &Theme) -> core::fmt::Result {
+    let bounds = &scene.bounds;
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}" width="{}" height="{}">"#,
+        bounds.min_x,
+        bounds.min_y,
+        bounds.width(),
+        bounds.height(),
+        bounds.width() * TILE_PIXELS,
+        bounds.height() * TILE_PIXELS,
+    )?;
+    writeln!(svg, "<style>{}</style>", style(theme))?;
+    writeln!(
+        svg,
+        r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+        bounds.min_x,
+        bounds.min_y,
+        bounds.width(),
+        bounds.height(),
+        hex(theme.background),
+    )?;
+
+    for (name, tile) in &scene.tiles {
+        writeln!(
+            svg,
+            r#"<rect class="tile" x="{}" y="{}" width="1" height="1"><title>{}</title></rect>"#,
+            tile.min_x,
+            tile.min_y,
+            escape(name),
+        )?;
+    }
+    for entity in &scene.entities {
+        write_entity(svg, entity)?;
+    }
+    for wire in &scene.wires {
+        let color = wire_class(wire.color);
+        writeln!(
+            svg,
+            r#"<line class="wire {color}" x1="{}" y1="{}" x2="{}" y2="{}"/>"#,
+            wire.from.x, wire.from.y, wire.to.x, wire.to.y,
+        )?;
+    }
+
+    writeln!(svg, "</svg>")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+
+    use super::*;
+    use crate::{
+        blueprint::{Wire, WireConnectorId},
+        bp,
+        prototypes::PrototypeDb,
+    };
+
+    #[test]
+    fn blueprints_are_drawn_in_tile_units() {
+        let wire = Wire {
+            source_entity: NonZeroUsize::MIN,
+            source_connector: WireConnectorId::CircuitRed,
+            target_entity: NonZeroUsize::new(2).unwrap(),
+            target_connector: WireConnectorId::CircuitRed,
+        };
+        let blueprint = bp! {
+            "assembling-machine-1" @ (0, 0);
+            "inserter" @ (3, 1) facing East;
+        }
+        .tile("concrete", 0, 3)
+        .wire(wire)
+        .build_with(PrototypeDb::vanilla())
+        .unwrap();
+        let scene = Scene::new(&blueprint, PrototypeDb::vanilla());
+        let svg = to_svg(&scene, &Theme::dark());
+
+        assert!(svg.starts_with(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 4 4" width="128" height="128">"#
+        ));
+        assert_eq!(svg.matches(r#"<rect class="entity "#).count(), 2);
+        assert_eq!(svg.matches(r#"<rect class="tile" x="0" y="3""#).count(), 1);
+        assert_eq!(svg.matches("<polygon").count(), 1);
+        assert!(svg.contains(r#"<line class="wire red" x1="1.5" y1="1.5" x2="3.5" y2="1.5"/>"#));
+        assert!(svg.trim_end().ends_with("</svg>"));
+
+        assert_eq!(escape(r#"<"&">"#), "&lt;&quot;&amp;&quot;&gt;");
+    }
+}
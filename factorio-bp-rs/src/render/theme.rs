@@ -0,0 +1,222 @@
+//! Colours for drawing blueprints, picked by the kind of entity.
+
+use super::WireColor;
+
+/// A colour as red, green, and blue bytes.
+pub type Rgb = [u8; 3];
+
+/// Name fragments that identify the class of an entity, checked in order.
+const CLASS_PATTERNS: &[(&str, EntityClass)] = &[
+    ("transport-belt", EntityClass::Belt),
+    ("underground-belt", EntityClass::Belt),
+    ("splitter", EntityClass::Belt),
+    ("loader", EntityClass::Belt),
+    ("inserter", EntityClass::Inserter),
+    ("heat-pipe", EntityClass::Power),
+    ("electric-pole", EntityClass::Power),
+    ("substation", EntityClass::Power),
+    ("boiler", EntityClass::Power),
+    ("steam-engine", EntityClass::Power),
+    ("steam-turbine", EntityClass::Power),
+    ("solar-panel", EntityClass::Power),
+    ("accumulator", EntityClass::Power),
+    ("reactor", EntityClass::Power),
+    ("heat-exchanger", EntityClass::Power),
+    ("heating-tower", EntityClass::Power),
+    ("generator", EntityClass::Power),
+    ("power-switch", EntityClass::Power),
+    ("lightning", EntityClass::Power),
+    ("energy-interface", EntityClass::Power),
+    ("assembling-machine", EntityClass::Production),
+    ("furnace", EntityClass::Production),
+    ("mining-drill", EntityClass::Production),
+    ("pumpjack", EntityClass::Production),
+    ("refinery", EntityClass::Production),
+    ("chemical-plant", EntityClass::Production),
+    ("centrifuge", EntityClass::Production),
+    ("lab", EntityClass::Production),
+    ("rocket-silo", EntityClass::Production),
+    ("foundry", EntityClass::Production),
+    ("electromagnetic-plant", EntityClass::Production),
+    ("biochamber", EntityClass::Production),
+    ("cryogenic-plant", EntityClass::Production),
+    ("recycler", EntityClass::Production),
+    ("crusher", EntityClass::Production),
+    ("beacon", EntityClass::Production),
+    ("agricultural-tower", EntityClass::Production),
+    ("asteroid-collector", EntityClass::Production),
+    // Before storage, so cargo wagons aren't taken for cargo bays
+    ("locomotive", EntityClass::Rail),
+    ("wagon", EntityClass::Rail),
+    ("chest", EntityClass::Storage),
+    ("storage-tank", EntityClass::Storage),
+    ("cargo", EntityClass::Storage),
+    ("roboport", EntityClass::Storage),
+    ("pipe", EntityClass::Fluid),
+    ("pump", EntityClass::Fluid),
+    ("combinator", EntityClass::Circuit),
+    ("lamp", EntityClass::Circuit),
+    ("speaker", EntityClass::Circuit),
+    ("display-panel", EntityClass::Circuit),
+    ("rail", EntityClass::Rail),
+    ("train-stop", EntityClass::Rail),
+    ("wall", EntityClass::Defense),
+    ("gate", EntityClass::Defense),
+    ("turret", EntityClass::Defense),
+    ("land-mine", EntityClass::Defense),
+    ("radar", EntityClass::Defense),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// A broad kind of entity, used to colour it.
+pub enum EntityClass {
+    /// Belts, undergrounds, splitters, and loaders.
+    Belt,
+    /// Inserters.
+    Inserter,
+    /// Electric poles, generators, and heat.
+    Power,
+    /// Crafting machines, drills, labs, and beacons.
+    Production,
+    /// Chests, tanks, and roboports.
+    Storage,
+    /// Pipes and pumps.
+    Fluid,
+    /// Combinators, lamps, and other circuit devices.
+    Circuit,
+    /// Rails, stops, and rolling stock.
+    Rail,
+    /// Walls, gates, and turrets.
+    Defense,
+    /// Anything else, including modded entities.
+    Other,
+}
+
+impl EntityClass {
+    /// The class of an entity, guessed from its prototype name.
+    pub fn of(name: &str) -> Self {
+        CLASS_PATTERNS
+            .iter()
+            .find(|(pattern, _)| name.contains(pattern))
+            .map_or(EntityClass::Other, |&(_, class)| class)
+    }
+
+    /// A lowercase name for the class, e.g. "belt".
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EntityClass::Belt => "belt",
+            EntityClass::Inserter => "inserter",
+            EntityClass::Power => "power",
+            EntityClass::Production => "production",
+            EntityClass::Storage => "storage",
+            EntityClass::Fluid => "fluid",
+            EntityClass::Circuit => "circuit",
+            EntityClass::Rail => "rail",
+            EntityClass::Defense => "defense",
+            EntityClass::Other => "other",
+        }
+    }
+
+    /// Every class.
+    pub const ALL: [EntityClass; 10] = [
+        EntityClass::Belt,
+        EntityClass::Inserter,
+        EntityClass::Power,
+        EntityClass::Production,
+        EntityClass::Storage,
+        EntityClass::Fluid,
+        EntityClass::Circuit,
+        EntityClass::Rail,
+        EntityClass::Defense,
+        EntityClass::Other,
+    ];
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The colours a blueprint is drawn with.
+pub struct Theme {
+    /// Empty space.
+    pub background: Rgb,
+    /// Floor tiles.
+    pub tile: Rgb,
+    /// Entity borders.
+    pub outline: Rgb,
+    /// Direction arrows.
+    pub arrow: Rgb,
+    /// Labels and entity numbers.
+    pub text: Rgb,
+    /// Entities of each class, in the order of [EntityClass::ALL].
+    pub entities: [Rgb; 10],
+    /// Red, green, and copper wires.
+    pub wires: [Rgb; 3],
+}
+
+impl Theme {
+    /// Light entities on a dark background, like the game's map view.
+    pub fn dark() -> Self {
+        Self {
+            background: [40, 40, 36],
+            tile: [90, 90, 82],
+            outline: [20, 20, 18],
+            arrow: [240, 192, 64],
+            text: [16, 16, 16],
+            entities: [
+                [222, 186, 60],
+                [110, 170, 210],
+                [190, 120, 200],
+                [140, 140, 132],
+                [170, 120, 70],
+                [80, 130, 200],
+                [120, 200, 140],
+                [160, 160, 175],
+                [200, 90, 80],
+                [180, 180, 172],
+            ],
+            wires: [[208, 32, 32], [32, 176, 32], [200, 120, 48]],
+        }
+    }
+
+    /// Dark outlines on a white background, for printing.
+    pub fn light() -> Self {
+        Self {
+            background: [255, 255, 255],
+            tile: [225, 225, 218],
+            outline: [60, 60, 60],
+            arrow: [30, 30, 30],
+            text: [0, 0, 0],
+            entities: [
+                [250, 220, 120],
+                [170, 210, 240],
+                [225, 180, 235],
+                [200, 200, 195],
+                [215, 175, 135],
+                [150, 185, 240],
+                [170, 230, 185],
+                [205, 205, 215],
+                [240, 160, 150],
+                [220, 220, 215],
+            ],
+            wires: [[200, 0, 0], [0, 150, 0], [190, 110, 30]],
+        }
+    }
+
+    /// The colour of entities of `class`.
+    pub fn entity(&self, class: EntityClass) -> Rgb {
+        self.entities[class as usize]
+    }
+
+    /// The colour of `wire`.
+    pub fn wire(&self, wire: WireColor) -> Rgb {
+        match wire {
+            WireColor::Red => self.wires[0],
+            WireColor::Green => self.wires[1],
+            WireColor::Copper => self.wires[2],
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}